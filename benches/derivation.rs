@@ -0,0 +1,125 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The measurements behind `wallet bench`: BIP39 seed derivation, bulk
+//! address derivation and the keystore KDFs.
+//!
+//! Run them with `cargo bench`, or a single group with
+//! `cargo bench -- keystore`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use wallet_rust::{
+    bips::{
+        bip32::ExtendedKey,
+        bip39::{Mnemonic, MnemonicType},
+        wordlists::Language,
+        DerivationPath,
+    },
+    keystore::{Kdf, Keystore},
+};
+
+const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+    abandon abandon about";
+
+fn seed(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bip39");
+    for words in [MnemonicType::Words12, MnemonicType::Words24] {
+        let mnemonic = Mnemonic::new(words, Language::English);
+        group.bench_with_input(
+            BenchmarkId::new("to_seed", format!("{:?}", words)),
+            &mnemonic,
+            |b, mnemonic| b.iter(|| mnemonic.to_seed("")),
+        );
+    }
+    group.finish();
+}
+
+fn addresses(c: &mut Criterion) {
+    let mnemonic = Mnemonic::from_phrase(PHRASE, Language::English).unwrap();
+    let master = ExtendedKey::new_master(&mnemonic.to_seed("")).unwrap();
+    let account = DerivationPath::parse("m/44'/60'/0'/0").unwrap();
+
+    let mut group = c.benchmark_group("bip32");
+    group.bench_function("new_master", |b| {
+        let seed = mnemonic.to_seed("");
+        b.iter(|| ExtendedKey::new_master(&seed).unwrap())
+    });
+    for count in [10, 100] {
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(
+            BenchmarkId::new("derive_range", count),
+            &count,
+            |b, &count| {
+                b.iter(|| {
+                    master
+                        .derive_range(&account, 0..count)
+                        .unwrap()
+                        .collect::<Result<Vec<_>, _>>()
+                        .unwrap()
+                })
+            },
+        );
+        let paths = account.range(0..count).collect::<Vec<_>>();
+        group.bench_with_input(
+            BenchmarkId::new("derive_batch", count),
+            &paths,
+            |b, paths| b.iter(|| master.derive_batch(paths).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+fn keystore(c: &mut Criterion) {
+    let mnemonic = Mnemonic::from_phrase(PHRASE, Language::English).unwrap();
+    let key = ExtendedKey::new_master(&mnemonic.to_seed("")).unwrap();
+    let salt = vec![0x5a; 32];
+
+    // a thousandth of the default costs, the times scale linearly from there
+    let kdfs = [
+        (
+            "scrypt",
+            Kdf::Scrypt {
+                n: 1 << 8,
+                r: 8,
+                p: 1,
+                dklen: 32,
+                salt: salt.clone(),
+            },
+        ),
+        (
+            "pbkdf2",
+            Kdf::Pbkdf2 {
+                c: 262,
+                dklen: 32,
+                salt,
+            },
+        ),
+    ];
+
+    let mut group = c.benchmark_group("keystore");
+    for (name, kdf) in kdfs {
+        let keystore = Keystore::encrypt(key.private_key(), "password", kdf.clone()).unwrap();
+        group.bench_with_input(BenchmarkId::new("encrypt", name), &kdf, |b, kdf| {
+            b.iter(|| Keystore::encrypt(key.private_key(), "password", kdf.clone()).unwrap())
+        });
+        group.bench_with_input(
+            BenchmarkId::new("decrypt", name),
+            &keystore,
+            |b, keystore| b.iter(|| keystore.decrypt("password").unwrap()),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, seed, addresses, keystore);
+criterion_main!(benches);
@@ -0,0 +1,77 @@
+/*
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+/*
+ * C API of wallet-rust, built with the `ffi` feature.
+ *
+ * Handles are owned by the caller and released with their *_free function.
+ * Buffers and strings written by the library are released with
+ * wallet_buffer_free and wallet_string_free, which zero them first.
+ */
+
+#ifndef WALLET_H
+#define WALLET_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum WalletStatus {
+    WALLET_OK = 0,
+    WALLET_INVALID_ARGUMENT = 1,
+    WALLET_ERROR = 2,
+    WALLET_PANIC = 3,
+} WalletStatus;
+
+typedef struct WalletMnemonic WalletMnemonic;
+typedef struct WalletKey WalletKey;
+
+typedef struct WalletBuffer {
+    uint8_t *data;
+    size_t len;
+} WalletBuffer;
+
+/* Message of the last error on the calling thread, owned by the library. */
+const char *wallet_last_error(void);
+
+/* Mnemonics use the English wordlist. */
+WalletStatus wallet_mnemonic_generate(uint32_t word_count, WalletMnemonic **out);
+WalletStatus wallet_mnemonic_from_phrase(const char *phrase, WalletMnemonic **out);
+WalletStatus wallet_mnemonic_phrase(const WalletMnemonic *mnemonic, char **out);
+void wallet_mnemonic_free(WalletMnemonic *mnemonic);
+
+WalletStatus wallet_key_from_mnemonic(const WalletMnemonic *mnemonic,
+                                      const char *passphrase, WalletKey **out);
+WalletStatus wallet_key_from_seed(const uint8_t *seed, size_t len, WalletKey **out);
+WalletStatus wallet_key_derive(const WalletKey *key, const char *path, WalletKey **out);
+WalletStatus wallet_key_address(const WalletKey *key, char **out);
+WalletStatus wallet_key_public_key(const WalletKey *key, WalletBuffer *out);
+WalletStatus wallet_key_sign_hash(const WalletKey *key, const uint8_t hash[32],
+                                  WalletBuffer *out);
+WalletStatus wallet_key_sign_message(const WalletKey *key, const uint8_t *message,
+                                     size_t len, WalletBuffer *out);
+void wallet_key_free(WalletKey *key);
+
+void wallet_buffer_free(WalletBuffer buffer);
+void wallet_string_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* WALLET_H */
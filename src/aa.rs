@@ -0,0 +1,438 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Account Abstraction
+//!
+//! User operations of [ERC-4337](https://eips.ethereum.org/EIPS/eip-4337),
+//! the transactions of smart accounts, sent to a bundler instead of the
+//! mempool. Both layouts in use are supported: [`UserOperationV06`] for
+//! entry point v0.6 and [`UserOperationV07`] for the packed operations of
+//! entry point v0.7.
+//!
+//! The operation hash binds the operation to an entry point and a chain.
+//! Owners of the common smart accounts sign it as an EIP-191 message, which
+//! is what [`UserOperation::sign`] does with any [`Signer`], such as an HD
+//! key of this crate.
+//!
+//! ## Example
+//! ```rust
+//! use laron_crypto::PrivateKey;
+//! use wallet_rust::{address::Address, signer::Signer};
+//! use wallet_rust::aa::{UserOperation, UserOperationV07, ENTRY_POINT_V07};
+//!
+//! let owner = PrivateKey::from_bytes(&[1; 32]).unwrap();
+//! let mut op = UserOperation::V07(UserOperationV07 {
+//!     sender: Address::new([0x11; 20]),
+//!     call_gas_limit: 100_000,
+//!     verification_gas_limit: 200_000,
+//!     pre_verification_gas: 50_000,
+//!     max_fee_per_gas: 30_000_000_000,
+//!     max_priority_fee_per_gas: 2_000_000_000,
+//!     ..Default::default()
+//! });
+//!
+//! let signature = op.sign(&owner, &ENTRY_POINT_V07, 1).unwrap();
+//! let hash = op.hash(&ENTRY_POINT_V07, 1);
+//! assert_eq!(signature.recover_personal(hash).unwrap(), owner.address().unwrap());
+//! println!("{}", op.to_json());
+//! ```
+
+use serde_json::{json, Value};
+
+use crate::{
+    address::Address,
+    chains::Chain,
+    error::Result,
+    signature::Signature,
+    signer::Signer,
+    types::{B256, U256},
+    utils::keccak256,
+};
+
+/// The canonical entry point v0.6.
+pub const ENTRY_POINT_V06: Address = Address::new([
+    0x5f, 0xf1, 0x37, 0xd4, 0xb0, 0xfd, 0xcd, 0x49, 0xdc, 0xa3, 0x0c, 0x7c, 0xf5, 0x7e, 0x57, 0x8a,
+    0x02, 0x6d, 0x27, 0x89,
+]);
+/// The canonical entry point v0.7.
+pub const ENTRY_POINT_V07: Address = Address::new([
+    0x00, 0x00, 0x00, 0x00, 0x71, 0x72, 0x7d, 0xe2, 0x2e, 0x5e, 0x9d, 0x8b, 0xaf, 0x0e, 0xda, 0xc6,
+    0xf3, 0x7d, 0xa0, 0x32,
+]);
+
+/// A user operation of entry point v0.6.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserOperationV06 {
+    /// The smart account sending the operation.
+    pub sender: Address,
+    /// Nonce of the account, the key in the upper 192 bits and the sequence
+    /// in the lower 64.
+    pub nonce: U256,
+    /// Factory address followed by its calldata, to deploy the account
+    /// with its first operation.
+    pub init_code: Vec<u8>,
+    /// Calldata of the account.
+    pub call_data: Vec<u8>,
+    /// Gas limit of the call to the account.
+    pub call_gas_limit: u128,
+    /// Gas limit of the deployment and validation.
+    pub verification_gas_limit: u128,
+    /// Gas paid to the bundler on top of the execution.
+    pub pre_verification_gas: u128,
+    /// Maximum fee per gas, in wei.
+    pub max_fee_per_gas: u128,
+    /// Maximum priority fee per gas, in wei.
+    pub max_priority_fee_per_gas: u128,
+    /// Paymaster address followed by its data, empty when the account pays.
+    pub paymaster_and_data: Vec<u8>,
+    /// Signature checked by the account.
+    pub signature: Vec<u8>,
+}
+
+impl UserOperationV06 {
+    /// Return the hash of the operation for `entry_point` on `chain`.
+    pub fn hash(&self, entry_point: &Address, chain: impl Into<Chain>) -> B256 {
+        let packed = [
+            address_word(&self.sender),
+            uint_word(self.nonce),
+            keccak256(&self.init_code),
+            keccak256(&self.call_data),
+            uint_word(self.call_gas_limit.into()),
+            uint_word(self.verification_gas_limit.into()),
+            uint_word(self.pre_verification_gas.into()),
+            uint_word(self.max_fee_per_gas.into()),
+            uint_word(self.max_priority_fee_per_gas.into()),
+            keccak256(&self.paymaster_and_data),
+        ];
+        operation_hash(&packed, entry_point, chain.into().id)
+    }
+
+    /// Return the operation as a parameter of `eth_sendUserOperation`.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "sender": self.sender.to_checksum(),
+            "nonce": quantity(self.nonce),
+            "initCode": bytes(&self.init_code),
+            "callData": bytes(&self.call_data),
+            "callGasLimit": quantity(self.call_gas_limit.into()),
+            "verificationGasLimit": quantity(self.verification_gas_limit.into()),
+            "preVerificationGas": quantity(self.pre_verification_gas.into()),
+            "maxFeePerGas": quantity(self.max_fee_per_gas.into()),
+            "maxPriorityFeePerGas": quantity(self.max_priority_fee_per_gas.into()),
+            "paymasterAndData": bytes(&self.paymaster_and_data),
+            "signature": bytes(&self.signature),
+        })
+    }
+}
+
+/// A user operation of entry point v0.7, with the fields the entry point
+/// packs kept apart as bundlers expect them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserOperationV07 {
+    /// The smart account sending the operation.
+    pub sender: Address,
+    /// Nonce of the account, the key in the upper 192 bits and the sequence
+    /// in the lower 64.
+    pub nonce: U256,
+    /// Factory deploying the account with its first operation.
+    pub factory: Option<Address>,
+    /// Calldata of the factory.
+    pub factory_data: Vec<u8>,
+    /// Calldata of the account.
+    pub call_data: Vec<u8>,
+    /// Gas limit of the call to the account.
+    pub call_gas_limit: u128,
+    /// Gas limit of the deployment and validation.
+    pub verification_gas_limit: u128,
+    /// Gas paid to the bundler on top of the execution.
+    pub pre_verification_gas: u128,
+    /// Maximum fee per gas, in wei.
+    pub max_fee_per_gas: u128,
+    /// Maximum priority fee per gas, in wei.
+    pub max_priority_fee_per_gas: u128,
+    /// Paymaster sponsoring the operation, `None` when the account pays.
+    pub paymaster: Option<Address>,
+    /// Gas limit of the validation by the paymaster.
+    pub paymaster_verification_gas_limit: u128,
+    /// Gas limit of the post-operation call of the paymaster.
+    pub paymaster_post_op_gas_limit: u128,
+    /// Data of the paymaster.
+    pub paymaster_data: Vec<u8>,
+    /// Signature checked by the account.
+    pub signature: Vec<u8>,
+}
+
+impl UserOperationV07 {
+    /// Return the `initCode` of the packed operation, the factory followed
+    /// by its calldata.
+    pub fn init_code(&self) -> Vec<u8> {
+        match &self.factory {
+            Some(factory) => [factory.as_bytes(), &self.factory_data[..]].concat(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Return the `paymasterAndData` of the packed operation, the paymaster
+    /// followed by its two gas limits and its data.
+    pub fn paymaster_and_data(&self) -> Vec<u8> {
+        match &self.paymaster {
+            Some(paymaster) => [
+                &paymaster.as_bytes()[..],
+                &self.paymaster_verification_gas_limit.to_be_bytes(),
+                &self.paymaster_post_op_gas_limit.to_be_bytes(),
+                &self.paymaster_data,
+            ]
+            .concat(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Return the hash of the operation for `entry_point` on `chain`.
+    pub fn hash(&self, entry_point: &Address, chain: impl Into<Chain>) -> B256 {
+        let packed = [
+            address_word(&self.sender),
+            uint_word(self.nonce),
+            keccak256(self.init_code()),
+            keccak256(&self.call_data),
+            pair_word(self.verification_gas_limit, self.call_gas_limit),
+            uint_word(self.pre_verification_gas.into()),
+            pair_word(self.max_priority_fee_per_gas, self.max_fee_per_gas),
+            keccak256(self.paymaster_and_data()),
+        ];
+        operation_hash(&packed, entry_point, chain.into().id)
+    }
+
+    /// Return the operation as a parameter of `eth_sendUserOperation`.
+    pub fn to_json(&self) -> Value {
+        let mut value = json!({
+            "sender": self.sender.to_checksum(),
+            "nonce": quantity(self.nonce),
+            "callData": bytes(&self.call_data),
+            "callGasLimit": quantity(self.call_gas_limit.into()),
+            "verificationGasLimit": quantity(self.verification_gas_limit.into()),
+            "preVerificationGas": quantity(self.pre_verification_gas.into()),
+            "maxFeePerGas": quantity(self.max_fee_per_gas.into()),
+            "maxPriorityFeePerGas": quantity(self.max_priority_fee_per_gas.into()),
+            "signature": bytes(&self.signature),
+        });
+        if let Some(factory) = &self.factory {
+            value["factory"] = factory.to_checksum().into();
+            value["factoryData"] = bytes(&self.factory_data).into();
+        }
+        if let Some(paymaster) = &self.paymaster {
+            value["paymaster"] = paymaster.to_checksum().into();
+            value["paymasterVerificationGasLimit"] =
+                quantity(self.paymaster_verification_gas_limit.into()).into();
+            value["paymasterPostOpGasLimit"] =
+                quantity(self.paymaster_post_op_gas_limit.into()).into();
+            value["paymasterData"] = bytes(&self.paymaster_data).into();
+        }
+        value
+    }
+}
+
+/// A user operation for either entry point version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserOperation {
+    /// An operation of entry point v0.6.
+    V06(UserOperationV06),
+    /// An operation of entry point v0.7.
+    V07(UserOperationV07),
+}
+
+impl UserOperation {
+    /// Return the canonical entry point of the version of the operation.
+    pub fn entry_point(&self) -> Address {
+        match self {
+            UserOperation::V06(_) => ENTRY_POINT_V06,
+            UserOperation::V07(_) => ENTRY_POINT_V07,
+        }
+    }
+
+    /// Return the hash of the operation for `entry_point` on `chain`.
+    pub fn hash(&self, entry_point: &Address, chain: impl Into<Chain>) -> B256 {
+        match self {
+            UserOperation::V06(op) => op.hash(entry_point, chain),
+            UserOperation::V07(op) => op.hash(entry_point, chain),
+        }
+    }
+
+    /// Sign the hash of the operation as an EIP-191 message, the scheme of
+    /// the accounts owned by an ECDSA key, and store the signature in the
+    /// operation.
+    pub fn sign<S: Signer>(
+        &mut self,
+        signer: &S,
+        entry_point: &Address,
+        chain: impl Into<Chain>,
+    ) -> Result<Signature> {
+        let signature = signer.sign_message(self.hash(entry_point, chain))?;
+        match self {
+            UserOperation::V06(op) => op.signature = signature.to_bytes().to_vec(),
+            UserOperation::V07(op) => op.signature = signature.to_bytes().to_vec(),
+        }
+        Ok(signature)
+    }
+
+    /// Return the signature of the operation.
+    pub fn signature(&self) -> &[u8] {
+        match self {
+            UserOperation::V06(op) => &op.signature,
+            UserOperation::V07(op) => &op.signature,
+        }
+    }
+
+    /// Return the operation as a parameter of `eth_sendUserOperation`.
+    pub fn to_json(&self) -> Value {
+        match self {
+            UserOperation::V06(op) => op.to_json(),
+            UserOperation::V07(op) => op.to_json(),
+        }
+    }
+}
+
+/// Hash the packed operation, then the result with the entry point and the
+/// chain id.
+fn operation_hash(packed: &[[u8; 32]], entry_point: &Address, chain_id: u64) -> B256 {
+    let inner = keccak256(packed.concat());
+    let outer = [inner, address_word(entry_point), uint_word(chain_id.into())];
+    B256::new(keccak256(outer.concat()))
+}
+
+fn address_word(address: &Address) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[12..].copy_from_slice(address.as_bytes());
+    word
+}
+
+fn uint_word(value: U256) -> [u8; 32] {
+    value.to_big_endian()
+}
+
+/// Two 128-bit integers packed in a word, `high` first.
+fn pair_word(high: u128, low: u128) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[..16].copy_from_slice(&high.to_be_bytes());
+    word[16..].copy_from_slice(&low.to_be_bytes());
+    word
+}
+
+fn quantity(value: U256) -> String {
+    format!("{:#x}", value)
+}
+
+fn bytes(data: &[u8]) -> String {
+    format!("0x{}", hex::encode(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use laron_crypto::PrivateKey;
+
+    const CHAIN_ID: u64 = 11155111;
+
+    fn nonce() -> U256 {
+        (U256::from(7) << 64) + 3
+    }
+
+    fn v06() -> UserOperationV06 {
+        UserOperationV06 {
+            sender: Address::new([0x11; 20]),
+            nonce: nonce(),
+            init_code: [&[0x22; 20][..], &[0x5f, 0xbf, 0xb9, 0xcf]].concat(),
+            call_data: vec![0xb6, 0x1d, 0x27, 0xf6],
+            call_gas_limit: 100_000,
+            verification_gas_limit: 200_000,
+            pre_verification_gas: 50_000,
+            max_fee_per_gas: 30_000_000_000,
+            max_priority_fee_per_gas: 2_000_000_000,
+            paymaster_and_data: [&[0x33; 20][..], &[0xab, 0xcd]].concat(),
+            signature: Vec::new(),
+        }
+    }
+
+    fn v07() -> UserOperationV07 {
+        UserOperationV07 {
+            sender: Address::new([0x11; 20]),
+            nonce: nonce(),
+            factory: Some(Address::new([0x22; 20])),
+            factory_data: vec![0x5f, 0xbf, 0xb9, 0xcf],
+            call_data: vec![0xb6, 0x1d, 0x27, 0xf6],
+            call_gas_limit: 100_000,
+            verification_gas_limit: 200_000,
+            pre_verification_gas: 50_000,
+            max_fee_per_gas: 30_000_000_000,
+            max_priority_fee_per_gas: 2_000_000_000,
+            paymaster: Some(Address::new([0x33; 20])),
+            paymaster_verification_gas_limit: 60_000,
+            paymaster_post_op_gas_limit: 40_000,
+            paymaster_data: vec![0xab, 0xcd],
+            signature: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_hash() {
+        assert_eq!(
+            v06().hash(&ENTRY_POINT_V06, CHAIN_ID).to_string(),
+            "0x99c76855764f28fb879534b740eb702090f164c3d56ab842f6252bd9f162f585"
+        );
+        assert_eq!(
+            v07().hash(&ENTRY_POINT_V07, CHAIN_ID).to_string(),
+            "0x325c4fee2ebf429567e180b158f2a92e74f0e940cd749b2eafe29ca1b190fc37"
+        );
+        assert_ne!(
+            v07().hash(&ENTRY_POINT_V07, 1),
+            v07().hash(&ENTRY_POINT_V07, CHAIN_ID)
+        );
+        assert_eq!(
+            ENTRY_POINT_V07.to_checksum(),
+            "0x0000000071727De22E5E9d8BAf0edAc6f37da032"
+        );
+    }
+
+    #[test]
+    fn test_sign() {
+        let owner = PrivateKey::from_bytes(&[1; 32]).unwrap();
+        let mut op = UserOperation::V06(v06());
+        let entry_point = op.entry_point();
+
+        let signature = op.sign(&owner, &entry_point, CHAIN_ID).unwrap();
+        assert_eq!(op.signature(), signature.to_bytes());
+        let hash = op.hash(&entry_point, CHAIN_ID);
+        assert_eq!(
+            signature.recover_personal(hash).unwrap(),
+            owner.address().unwrap()
+        );
+        // the signature is not part of the hash
+        assert_eq!(hash, v06().hash(&entry_point, CHAIN_ID));
+    }
+
+    #[test]
+    fn test_json() {
+        let json = v07().to_json();
+        assert_eq!(json["nonce"], "0x70000000000000003");
+        assert_eq!(json["factoryData"], "0x5fbfb9cf");
+        assert_eq!(json["paymasterPostOpGasLimit"], "0x9c40");
+
+        let json = UserOperationV07::default().to_json();
+        assert_eq!(json["nonce"], "0x0");
+        assert!(json.get("factory").is_none());
+        assert!(json.get("paymaster").is_none());
+        assert_eq!(
+            v06().to_json()["paymasterAndData"].as_str().unwrap().len(),
+            46
+        );
+    }
+}
@@ -0,0 +1,230 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::{fits_int, AbiError, ParamType, Token};
+use crate::{address::Address, error::Result, types::U256};
+
+/// Decode ABI encoded values of the given types, such as the arguments of
+/// a call after its selector.
+///
+/// Decoding is strict: padding must be zero and values must fit their
+/// type, so that data encoded for another function with the same selector
+/// is rejected.
+pub fn decode(types: &[ParamType], data: &[u8]) -> Result<Vec<Token>> {
+    decode_tuple(types, data)
+}
+
+/// Decode the head and tail encoding of a tuple starting at `data`.
+fn decode_tuple(types: &[ParamType], data: &[u8]) -> Result<Vec<Token>> {
+    let mut head = 0;
+    let mut tokens = Vec::with_capacity(types.len());
+    for ty in types {
+        let token = match ty.is_dynamic() {
+            true => {
+                let offset = decode_usize(data.get(head..).ok_or(AbiError::InvalidData)?)?;
+                head += 32;
+                decode_value(ty, data.get(offset..).ok_or(AbiError::InvalidData)?)?
+            }
+            false => {
+                let token = decode_value(ty, data.get(head..).ok_or(AbiError::InvalidData)?)?;
+                head += ty.head_size();
+                token
+            }
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn decode_value(ty: &ParamType, data: &[u8]) -> Result<Token> {
+    let word =
+        || -> Result<&[u8; 32]> { Ok(data.first_chunk::<32>().ok_or(AbiError::InvalidData)?) };
+    let token = match ty {
+        ParamType::Address => {
+            let word = word()?;
+            check(word[..12].iter().all(|b| *b == 0))?;
+            Token::Address(Address::from_slice(&word[12..])?)
+        }
+        ParamType::Bool => match U256::from_big_endian(word()?) {
+            value if value.is_zero() => Token::Bool(false),
+            value if value == U256::one() => Token::Bool(true),
+            _ => return Err(AbiError::InvalidData.into()),
+        },
+        ParamType::Uint(bits) => {
+            let value = U256::from_big_endian(word()?);
+            check(value.bits() <= *bits)?;
+            Token::Uint(value)
+        }
+        ParamType::Int(bits) => {
+            let value = U256::from_big_endian(word()?);
+            check(fits_int(value, *bits))?;
+            Token::Int(value)
+        }
+        ParamType::FixedBytes(size) => {
+            let word = word()?;
+            check(word[*size..].iter().all(|b| *b == 0))?;
+            Token::FixedBytes(word[..*size].to_vec())
+        }
+        ParamType::Bytes => Token::Bytes(decode_bytes(data)?.to_vec()),
+        ParamType::String => Token::String(
+            String::from_utf8(decode_bytes(data)?.to_vec()).map_err(|_| AbiError::InvalidData)?,
+        ),
+        ParamType::Array(inner) => {
+            let len = decode_usize(data)?;
+            // reject lengths the data cannot hold before allocating
+            let elements = &data[32..];
+            check(len <= elements.len() / inner.head_size().max(1))?;
+            Token::Array(decode_tuple(&vec![(**inner).clone(); len], elements)?)
+        }
+        ParamType::FixedArray(inner, len) => {
+            check(*len <= data.len() / inner.head_size().max(1))?;
+            Token::Array(decode_tuple(&vec![(**inner).clone(); *len], data)?)
+        }
+        ParamType::Tuple(types) => Token::Tuple(decode_tuple(types, data)?),
+    };
+    Ok(token)
+}
+
+/// Decode the length prefixed bytes of a `bytes` or `string`.
+fn decode_bytes(data: &[u8]) -> Result<&[u8]> {
+    let len = decode_usize(data)?;
+    let padded = len
+        .checked_next_multiple_of(32)
+        .ok_or(AbiError::InvalidData)?;
+    let content = data.get(32..32 + padded).ok_or(AbiError::InvalidData)?;
+    check(content[len..].iter().all(|b| *b == 0))?;
+    Ok(&content[..len])
+}
+
+/// Decode a word holding a length or an offset.
+fn decode_usize(data: &[u8]) -> Result<usize> {
+    let word = data.first_chunk::<32>().ok_or(AbiError::InvalidData)?;
+    check(word[..24].iter().all(|b| *b == 0))?;
+    Ok(
+        usize::try_from(u64::from_be_bytes(word[24..].try_into().unwrap()))
+            .map_err(|_| AbiError::InvalidData)?,
+    )
+}
+
+fn check(valid: bool) -> Result<()> {
+    match valid {
+        true => Ok(()),
+        false => Err(AbiError::InvalidData.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(value: u64) -> String {
+        format!("{:064x}", value)
+    }
+
+    fn types(types: &[&str]) -> Vec<ParamType> {
+        types
+            .iter()
+            .map(|ty| ParamType::parse(ty).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_decode_static() {
+        let data = hex::decode(
+            [
+                format!("{:0>64}", "35".repeat(20)),
+                word(1),
+                "ff".repeat(32),
+                format!("{:0<64}", "cafe"),
+                word(7),
+                word(8),
+            ]
+            .concat(),
+        )
+        .unwrap();
+        let tokens = decode(
+            &types(&["address", "bool", "int8", "bytes2", "uint64[2]"]),
+            &data,
+        )
+        .unwrap();
+        assert_eq!(tokens[0], Token::Address(Address::new([0x35; 20])));
+        assert_eq!(tokens[1], Token::Bool(true));
+        assert_eq!(tokens[2].to_string(), "-1");
+        assert_eq!(tokens[3], Token::FixedBytes(vec![0xca, 0xfe]));
+        assert_eq!(tokens[4].to_string(), "[7, 8]");
+    }
+
+    #[test]
+    fn test_decode_dynamic() {
+        // f(uint256,uint32[],bytes10,bytes) from the Solidity documentation
+        let data = hex::decode(
+            [
+                word(0x123),
+                word(0x80),
+                format!("{:0<64}", hex::encode("1234567890")),
+                word(0xe0),
+                word(2),
+                word(0x456),
+                word(0x789),
+                word(13),
+                format!("{:0<64}", hex::encode("Hello, world!")),
+            ]
+            .concat(),
+        )
+        .unwrap();
+        let tokens = decode(&types(&["uint256", "uint32[]", "bytes10", "string"]), &data).unwrap();
+        assert_eq!(tokens[0], Token::Uint(0x123.into()));
+        assert_eq!(tokens[1].to_string(), "[1110, 1929]");
+        assert_eq!(tokens[2], Token::FixedBytes(b"1234567890".to_vec()));
+        assert_eq!(tokens[3], Token::String("Hello, world!".to_string()));
+
+        // (address,bool,bytes)[] as taken by aggregate3
+        let data = hex::decode(
+            [
+                word(0x20),
+                word(1),
+                word(0x20),
+                format!("{:0>64}", "11".repeat(20)),
+                word(0),
+                word(0x60),
+                word(4),
+                format!("{:0<64}", "18160ddd"),
+            ]
+            .concat(),
+        )
+        .unwrap();
+        let tokens = decode(&types(&["(address,bool,bytes)[]"]), &data).unwrap();
+        assert_eq!(
+            tokens[0].to_string(),
+            "[(0x1111111111111111111111111111111111111111, false, 0x18160ddd)]"
+        );
+    }
+
+    #[test]
+    fn test_decode_strict() {
+        let decode_one =
+            |ty: &str, data: String| decode(&types(&[ty]), &hex::decode(data).unwrap());
+        assert!(decode_one("uint8", word(0x100)).is_err());
+        assert!(decode_one("bool", word(2)).is_err());
+        assert!(decode_one("int8", word(0x80)).is_err());
+        assert!(decode_one("address", "ff".repeat(32)).is_err());
+        assert!(decode_one("bytes2", format!("{:0<64}", "cafe01")).is_err());
+        assert!(decode_one("uint256", "00".repeat(31)).is_err());
+
+        // lengths and offsets beyond the data
+        assert!(decode_one("bytes", [word(0x20), word(33), word(0)].concat()).is_err());
+        assert!(decode_one("uint256[]", [word(0x20), word(u32::MAX as u64)].concat()).is_err());
+        assert!(decode_one("string", word(0x1000)).is_err());
+        assert!(decode_one("bytes", [word(0x20), "ff".repeat(32)].concat()).is_err());
+    }
+}
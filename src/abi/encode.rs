@@ -0,0 +1,178 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::{fits_int, AbiError, ParamType, Token};
+use crate::{error::Result, types::U256};
+
+/// ABI encode values of the given types, such as the arguments of a call
+/// without its selector.
+pub fn encode(types: &[ParamType], tokens: &[Token]) -> Result<Vec<u8>> {
+    encode_tuple(types, tokens)
+}
+
+/// Encode the heads of a tuple followed by the tails of its dynamic
+/// members.
+fn encode_tuple(types: &[ParamType], tokens: &[Token]) -> Result<Vec<u8>> {
+    if types.len() != tokens.len() {
+        let tuple = ParamType::Tuple(types.to_vec());
+        return Err(AbiError::TypeMismatch(tuple.to_string()).into());
+    }
+
+    let head_size: usize = types.iter().map(ParamType::head_size).sum();
+    let mut head = Vec::with_capacity(head_size);
+    let mut tail = Vec::new();
+    for (ty, token) in types.iter().zip(tokens) {
+        let encoded = encode_value(ty, token)?;
+        match ty.is_dynamic() {
+            true => {
+                head.extend_from_slice(&word(U256::from(head_size + tail.len())));
+                tail.extend(encoded);
+            }
+            false => head.extend(encoded),
+        }
+    }
+    head.extend(tail);
+    Ok(head)
+}
+
+fn encode_value(ty: &ParamType, token: &Token) -> Result<Vec<u8>> {
+    let encoded = match (ty, token) {
+        (ParamType::Address, Token::Address(address)) => {
+            let mut word = [0u8; 32];
+            word[12..].copy_from_slice(address.as_bytes());
+            word.to_vec()
+        }
+        (ParamType::Bool, Token::Bool(value)) => word(U256::from(*value as u8)).to_vec(),
+        (ParamType::Uint(bits), Token::Uint(value)) if value.bits() <= *bits => {
+            word(*value).to_vec()
+        }
+        (ParamType::Int(bits), Token::Int(value)) if fits_int(*value, *bits) => {
+            word(*value).to_vec()
+        }
+        (ParamType::FixedBytes(size), Token::FixedBytes(bytes)) if bytes.len() == *size => {
+            let mut word = [0u8; 32];
+            word[..*size].copy_from_slice(bytes);
+            word.to_vec()
+        }
+        (ParamType::Bytes, Token::Bytes(bytes)) => encode_bytes(bytes),
+        (ParamType::String, Token::String(value)) => encode_bytes(value.as_bytes()),
+        (ParamType::Array(inner), Token::Array(tokens)) => {
+            let types = vec![(**inner).clone(); tokens.len()];
+            [
+                &word(U256::from(tokens.len()))[..],
+                &encode_tuple(&types, tokens)?,
+            ]
+            .concat()
+        }
+        (ParamType::FixedArray(inner, len), Token::Array(tokens)) if tokens.len() == *len => {
+            encode_tuple(&vec![(**inner).clone(); *len], tokens)?
+        }
+        (ParamType::Tuple(types), Token::Tuple(tokens)) => encode_tuple(types, tokens)?,
+        _ => return Err(AbiError::TypeMismatch(ty.to_string()).into()),
+    };
+    Ok(encoded)
+}
+
+fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+    let mut encoded = word(U256::from(bytes.len())).to_vec();
+    encoded.extend_from_slice(bytes);
+    encoded.resize(32 + bytes.len().div_ceil(32) * 32, 0);
+    encoded
+}
+
+fn word(value: U256) -> [u8; 32] {
+    value.to_big_endian()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        abi::{decode, Function},
+        address::Address,
+    };
+
+    fn types(types: &[&str]) -> Vec<ParamType> {
+        types
+            .iter()
+            .map(|ty| ParamType::parse(ty).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_encode() {
+        // f(uint256,uint32[],bytes10,string) from the Solidity documentation
+        let types = types(&["uint256", "uint32[]", "bytes10", "string"]);
+        let tokens = [
+            Token::Uint(0x123.into()),
+            Token::Array(vec![Token::Uint(0x456.into()), Token::Uint(0x789.into())]),
+            Token::FixedBytes(b"1234567890".to_vec()),
+            Token::String("Hello, world!".to_string()),
+        ];
+        let encoded = encode(&types, &tokens).unwrap();
+        assert_eq!(
+            hex::encode(&encoded),
+            [
+                format!("{:064x}", 0x123),
+                format!("{:064x}", 0x80),
+                format!("{:0<64}", hex::encode("1234567890")),
+                format!("{:064x}", 0xe0),
+                format!("{:064x}", 2),
+                format!("{:064x}", 0x456),
+                format!("{:064x}", 0x789),
+                format!("{:064x}", 13),
+                format!("{:0<64}", hex::encode("Hello, world!")),
+            ]
+            .concat()
+        );
+        assert_eq!(decode(&types, &encoded).unwrap(), tokens);
+    }
+
+    #[test]
+    fn test_encode_input() {
+        let aggregate3 = Function::parse("aggregate3((address,bool,bytes)[] calls)").unwrap();
+        let calls = Token::Array(vec![
+            Token::Tuple(vec![
+                Token::Address(Address::new([0x11; 20])),
+                Token::Bool(true),
+                Token::Bytes(vec![0x18, 0x16, 0x0d, 0xdd]),
+            ]),
+            Token::Tuple(vec![
+                Token::Address(Address::new([0x22; 20])),
+                Token::Bool(false),
+                Token::Bytes(Vec::new()),
+            ]),
+        ]);
+        let calls = [calls];
+        let calldata = aggregate3.encode_input(&calls).unwrap();
+        assert_eq!(calldata[..4], [0x82, 0xad, 0x56, 0xcb]);
+        assert_eq!(aggregate3.decode_input(&calldata).unwrap(), calls);
+
+        let minus_two = [Token::Int(U256::MAX - 1)];
+        let int8 = types(&["int8"]);
+        let encoded = encode(&int8, &minus_two).unwrap();
+        assert_eq!(hex::encode(&encoded), format!("{}fe", "ff".repeat(31)));
+        assert_eq!(decode(&int8, &encoded).unwrap(), minus_two);
+    }
+
+    #[test]
+    fn test_type_mismatch() {
+        let error = encode(&types(&["uint8"]), &[Token::Uint(256.into())]).unwrap_err();
+        assert_eq!(error.to_string(), "Value does not match the type uint8");
+        assert!(encode(&types(&["int8"]), &[Token::Int(0x80.into())]).is_err());
+        assert!(encode(&types(&["bytes2"]), &[Token::FixedBytes(vec![1])]).is_err());
+        assert!(encode(&types(&["uint256[2]"]), &[Token::Array(Vec::new())]).is_err());
+        assert!(encode(&types(&["address"]), &[Token::Bool(true)]).is_err());
+        assert!(encode(&types(&["bool", "bool"]), &[Token::Bool(true)]).is_err());
+    }
+}
@@ -0,0 +1,504 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # ABI
+//!
+//! Decoding of contract calls encoded with the Solidity
+//! [ABI](https://docs.soliditylang.org/en/latest/abi-spec.html), to show
+//! what a transaction does before it is signed.
+//!
+//! A [`Function`] is parsed from its signature, with or without parameter
+//! names, or read from a JSON ABI. The [`SelectorRegistry`] maps the
+//! 4-byte selectors starting the calldata to the functions they may call,
+//! starting with the common token and wallet functions, and
+//! [`SelectorRegistry::decode`] turns calldata into a [`DecodedCall`]
+//! printing the function and its arguments. The other way around,
+//! [`Function::encode_input`] builds the calldata of a call.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::abi::SelectorRegistry;
+//!
+//! let calldata = hex::decode(concat!(
+//!     "a9059cbb",
+//!     "0000000000000000000000003535353535353535353535353535353535353535",
+//!     "00000000000000000000000000000000000000000000000000000000000f4240",
+//! ))
+//! .unwrap();
+//!
+//! let call = SelectorRegistry::new().decode(&calldata).unwrap();
+//! assert_eq!(call.function.name, "transfer");
+//! assert_eq!(
+//!     call.to_string(),
+//!     "transfer(address to, uint256 amount)\n  to: 0x3535353535353535353535353535353535353535\n  amount: 1000000"
+//! );
+//! ```
+
+mod decode;
+mod encode;
+mod registry;
+
+pub use decode::*;
+pub use encode::*;
+pub use registry::*;
+
+use crate::{address::Address, error::Result, types::U256, utils::keccak256};
+
+/// Error returned when parsing ABI types or decoding calldata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AbiError {
+    /// The type is not a Solidity ABI type.
+    InvalidType(String),
+    /// The function signature cannot be parsed.
+    InvalidSignature(String),
+    /// The JSON ABI is malformed.
+    InvalidAbi,
+    /// The data is not a valid encoding of the expected types.
+    InvalidData,
+    /// No known function has this selector.
+    UnknownSelector([u8; 4]),
+    /// A value to encode does not match its type, given.
+    TypeMismatch(String),
+}
+
+impl std::fmt::Display for AbiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AbiError::InvalidType(ty) => write!(f, "Invalid ABI type: {}", ty),
+            AbiError::InvalidSignature(signature) => {
+                write!(f, "Invalid function signature: {}", signature)
+            }
+            AbiError::InvalidAbi => write!(f, "Invalid JSON ABI"),
+            AbiError::InvalidData => write!(f, "Invalid ABI encoded data"),
+            AbiError::UnknownSelector(selector) => {
+                write!(f, "Unknown function selector 0x{}", hex::encode(selector))
+            }
+            AbiError::TypeMismatch(ty) => write!(f, "Value does not match the type {}", ty),
+        }
+    }
+}
+
+impl std::error::Error for AbiError {}
+
+/// A Solidity ABI type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamType {
+    /// `address`
+    Address,
+    /// `bool`
+    Bool,
+    /// `uintN`, with its size in bits.
+    Uint(usize),
+    /// `intN`, with its size in bits.
+    Int(usize),
+    /// `bytesN`, with its size in bytes.
+    FixedBytes(usize),
+    /// `bytes`
+    Bytes,
+    /// `string`
+    String,
+    /// `T[]`
+    Array(Box<ParamType>),
+    /// `T[N]`
+    FixedArray(Box<ParamType>, usize),
+    /// `(T1,T2,...)`
+    Tuple(Vec<ParamType>),
+}
+
+impl ParamType {
+    /// Parse a type such as `uint256`, `bytes32[]` or `(address,bool)`.
+    pub fn parse(ty: &str) -> Result<Self> {
+        let ty = ty.trim();
+        let invalid = || AbiError::InvalidType(ty.to_string());
+
+        let base_end = match ty.starts_with('(') {
+            true => closing_paren(ty).ok_or_else(invalid)? + 1,
+            false => ty.find('[').unwrap_or(ty.len()),
+        };
+        let base = match &ty[..base_end] {
+            "address" => ParamType::Address,
+            "bool" => ParamType::Bool,
+            "bytes" => ParamType::Bytes,
+            "string" => ParamType::String,
+            "uint" => ParamType::Uint(256),
+            "int" => ParamType::Int(256),
+            tuple if tuple.starts_with('(') => ParamType::Tuple(
+                split_params(&tuple[1..tuple.len() - 1])
+                    .into_iter()
+                    .map(ParamType::parse)
+                    .collect::<Result<_>>()?,
+            ),
+            other => {
+                let size = |digits: &str| digits.parse::<usize>().map_err(|_| invalid());
+                if let Some(bits) = other.strip_prefix("uint") {
+                    ParamType::Uint(size(bits)?)
+                } else if let Some(bits) = other.strip_prefix("int") {
+                    ParamType::Int(size(bits)?)
+                } else if let Some(bytes) = other.strip_prefix("bytes") {
+                    ParamType::FixedBytes(size(bytes)?)
+                } else {
+                    return Err(invalid().into());
+                }
+            }
+        };
+
+        match base {
+            ParamType::Uint(bits) | ParamType::Int(bits)
+                if bits == 0 || bits > 256 || bits % 8 != 0 =>
+            {
+                Err(invalid().into())
+            }
+            ParamType::FixedBytes(size) if size == 0 || size > 32 => Err(invalid().into()),
+            base => with_suffix(base, &ty[base_end..]).ok_or_else(|| invalid().into()),
+        }
+    }
+
+    /// Whether the encoding of the type is stored after the head, pointed
+    /// to by an offset.
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            ParamType::Bytes | ParamType::String | ParamType::Array(_) => true,
+            ParamType::FixedArray(inner, _) => inner.is_dynamic(),
+            ParamType::Tuple(types) => types.iter().any(ParamType::is_dynamic),
+            _ => false,
+        }
+    }
+
+    /// Return the size of the encoding of the type in the head of a tuple.
+    fn head_size(&self) -> usize {
+        match self {
+            ty if ty.is_dynamic() => 32,
+            ParamType::FixedArray(inner, len) => inner.head_size().saturating_mul(*len),
+            ParamType::Tuple(types) => types
+                .iter()
+                .map(ParamType::head_size)
+                .fold(0, usize::saturating_add),
+            _ => 32,
+        }
+    }
+}
+
+impl std::fmt::Display for ParamType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParamType::Address => write!(f, "address"),
+            ParamType::Bool => write!(f, "bool"),
+            ParamType::Uint(bits) => write!(f, "uint{}", bits),
+            ParamType::Int(bits) => write!(f, "int{}", bits),
+            ParamType::FixedBytes(size) => write!(f, "bytes{}", size),
+            ParamType::Bytes => write!(f, "bytes"),
+            ParamType::String => write!(f, "string"),
+            ParamType::Array(inner) => write!(f, "{}[]", inner),
+            ParamType::FixedArray(inner, size) => write!(f, "{}[{}]", inner, size),
+            ParamType::Tuple(types) => {
+                let types: Vec<String> = types.iter().map(ParamType::to_string).collect();
+                write!(f, "({})", types.join(","))
+            }
+        }
+    }
+}
+
+/// A named parameter of a function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    /// Name of the parameter, empty when unknown.
+    pub name: String,
+    /// Type of the parameter.
+    pub kind: ParamType,
+}
+
+/// A contract function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    /// Name of the function.
+    pub name: String,
+    /// Parameters of the function.
+    pub inputs: Vec<Param>,
+}
+
+impl Function {
+    /// Parse a signature such as `transfer(address,uint256)` or, with
+    /// names, `transfer(address to, uint256 amount)`.
+    pub fn parse(signature: &str) -> Result<Self> {
+        let signature = signature.trim();
+        let signature = signature.strip_prefix("function ").unwrap_or(signature);
+        let invalid = || AbiError::InvalidSignature(signature.to_string());
+
+        let open = signature.find('(').ok_or_else(invalid)?;
+        if closing_paren(&signature[open..]) != Some(signature.len() - open - 1) {
+            return Err(invalid().into());
+        }
+        let name = &signature[..open];
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+        {
+            return Err(invalid().into());
+        }
+
+        let inputs = split_params(&signature[open + 1..signature.len() - 1])
+            .into_iter()
+            .map(|param| {
+                // the name follows the type, after the last space outside
+                // of a tuple
+                let (kind, name) = match param.rfind(' ') {
+                    Some(space) if !param[space..].contains(')') => {
+                        (&param[..space], param[space + 1..].trim())
+                    }
+                    _ => (param, ""),
+                };
+                Ok(Param {
+                    name: name.to_string(),
+                    kind: ParamType::parse(kind)?,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            name: name.to_string(),
+            inputs,
+        })
+    }
+
+    /// Return the canonical signature, hashed into the selector.
+    pub fn signature(&self) -> String {
+        let types: Vec<String> = self
+            .inputs
+            .iter()
+            .map(|param| param.kind.to_string())
+            .collect();
+        format!("{}({})", self.name, types.join(","))
+    }
+
+    /// Return the selector, the first 4 bytes of the Keccak-256 of the
+    /// signature.
+    pub fn selector(&self) -> [u8; 4] {
+        keccak256(self.signature())[..4].try_into().unwrap()
+    }
+
+    /// Encode a call to the function with the given arguments, the
+    /// selector first.
+    pub fn encode_input(&self, arguments: &[Token]) -> Result<Vec<u8>> {
+        let types: Vec<ParamType> = self.inputs.iter().map(|param| param.kind.clone()).collect();
+        Ok([&self.selector()[..], &encode(&types, arguments)?].concat())
+    }
+
+    /// Decode the arguments of a call to the function, the selector
+    /// included.
+    pub fn decode_input(&self, calldata: &[u8]) -> Result<Vec<Token>> {
+        match calldata.split_first_chunk::<4>() {
+            Some((selector, data)) if *selector == self.selector() => {
+                let types: Vec<ParamType> =
+                    self.inputs.iter().map(|param| param.kind.clone()).collect();
+                decode(&types, data)
+            }
+            Some((selector, _)) => Err(AbiError::UnknownSelector(*selector).into()),
+            None => Err(AbiError::InvalidData.into()),
+        }
+    }
+}
+
+impl std::fmt::Display for Function {
+    /// Print the signature with the parameter names.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let inputs: Vec<String> = self
+            .inputs
+            .iter()
+            .map(|param| match param.name.is_empty() {
+                true => param.kind.to_string(),
+                false => format!("{} {}", param.kind, param.name),
+            })
+            .collect();
+        write!(f, "{}({})", self.name, inputs.join(", "))
+    }
+}
+
+/// A decoded value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    /// An `address`.
+    Address(Address),
+    /// A `bool`.
+    Bool(bool),
+    /// A `uintN`.
+    Uint(U256),
+    /// An `intN`, in two's complement over 256 bits.
+    Int(U256),
+    /// A `bytesN`.
+    FixedBytes(Vec<u8>),
+    /// A `bytes`.
+    Bytes(Vec<u8>),
+    /// A `string`.
+    String(String),
+    /// A `T[]` or `T[N]`.
+    Array(Vec<Token>),
+    /// A tuple.
+    Tuple(Vec<Token>),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let list = |tokens: &[Token]| {
+            let tokens: Vec<String> = tokens.iter().map(Token::to_string).collect();
+            tokens.join(", ")
+        };
+        match self {
+            Token::Address(address) => write!(f, "{}", address),
+            Token::Bool(value) => write!(f, "{}", value),
+            Token::Uint(value) => write!(f, "{}", value),
+            Token::Int(value) if value.bit(255) => {
+                write!(f, "-{}", (!*value).overflowing_add(U256::one()).0)
+            }
+            Token::Int(value) => write!(f, "{}", value),
+            Token::FixedBytes(bytes) | Token::Bytes(bytes) => write!(f, "0x{}", hex::encode(bytes)),
+            Token::String(value) => write!(f, "{:?}", value),
+            Token::Array(tokens) => write!(f, "[{}]", list(tokens)),
+            Token::Tuple(tokens) => write!(f, "({})", list(tokens)),
+        }
+    }
+}
+
+/// Whether a value in two's complement over 256 bits fits an `intN`, all
+/// its bits above the sign bit copying it.
+fn fits_int(value: U256, bits: usize) -> bool {
+    let high = value >> (bits - 1);
+    high.is_zero() || high == U256::MAX >> (bits - 1)
+}
+
+/// Return the index of the parenthesis closing the one `s` starts with.
+fn closing_paren(s: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (index, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split a parameter list on the commas outside of tuples.
+fn split_params(params: &str) -> Vec<&str> {
+    if params.trim().is_empty() {
+        return Vec::new();
+    }
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0usize, 0);
+    for (index, c) in params.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                parts.push(params[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(params[start..].trim());
+    parts
+}
+
+/// Wrap `base` in the array suffixes following it, such as `[2][]`.
+fn with_suffix(mut base: ParamType, mut suffix: &str) -> Option<ParamType> {
+    while !suffix.is_empty() {
+        let close = suffix.find(']')?;
+        base = match suffix.strip_prefix('[')?[..close - 1].trim() {
+            "" => ParamType::Array(Box::new(base)),
+            size => ParamType::FixedArray(Box::new(base), size.parse().ok()?),
+        };
+        suffix = &suffix[close + 1..];
+    }
+    Some(base)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_type() {
+        for ty in [
+            "address",
+            "uint8",
+            "int256",
+            "bytes32",
+            "bytes",
+            "string[]",
+            "uint256[2][]",
+            "(address,bool,bytes)[]",
+            "((uint8,string)[3],bytes4)",
+        ] {
+            assert_eq!(ParamType::parse(ty).unwrap().to_string(), ty);
+        }
+        assert_eq!(ParamType::parse("uint").unwrap(), ParamType::Uint(256));
+        for ty in [
+            "uint7",
+            "uint264",
+            "bytes0",
+            "bytes33",
+            "foo",
+            "uint256[",
+            "(uint256",
+            "(address)x",
+        ] {
+            assert!(ParamType::parse(ty).is_err(), "{}", ty);
+        }
+        assert!(ParamType::parse("(uint256,bytes)").unwrap().is_dynamic());
+        assert!(!ParamType::parse("(uint256,bytes32)[2]")
+            .unwrap()
+            .is_dynamic());
+    }
+
+    #[test]
+    fn test_parse_function() {
+        let function = Function::parse("transfer(address to, uint256 amount)").unwrap();
+        assert_eq!(function.signature(), "transfer(address,uint256)");
+        assert_eq!(function.selector(), [0xa9, 0x05, 0x9c, 0xbb]);
+        assert_eq!(function.to_string(), "transfer(address to, uint256 amount)");
+
+        let function = Function::parse("aggregate3((address,bool,bytes)[] calls)").unwrap();
+        assert_eq!(function.selector(), [0x82, 0xad, 0x56, 0xcb]);
+        assert_eq!(function.inputs[0].name, "calls");
+        assert_eq!(Function::parse("deposit()").unwrap().inputs, []);
+
+        for signature in [
+            "transfer",
+            "(address)",
+            "transfer(address",
+            "transfer(address))",
+        ] {
+            assert!(Function::parse(signature).is_err(), "{}", signature);
+        }
+    }
+
+    #[test]
+    fn test_display_token() {
+        let minus_one = Token::Int(U256::MAX);
+        assert_eq!(minus_one.to_string(), "-1");
+        let tuple = Token::Tuple(vec![
+            Token::Bool(true),
+            Token::Array(vec![Token::Uint(1.into()), Token::Uint(2.into())]),
+            Token::String("hi".to_string()),
+            Token::Bytes(vec![0xbe, 0xef]),
+        ]);
+        assert_eq!(tuple.to_string(), "(true, [1, 2], \"hi\", 0xbeef)");
+    }
+}
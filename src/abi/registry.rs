@@ -0,0 +1,312 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use super::{with_suffix, AbiError, Function, Param, ParamType, Token};
+
+use crate::error::Result;
+
+/// Functions of the common token and wallet contracts, known to every
+/// [`SelectorRegistry::new`].
+const COMMON_FUNCTIONS: &[&str] = &[
+    // ERC-20
+    "transfer(address to, uint256 amount)",
+    "transferFrom(address from, address to, uint256 amount)",
+    "approve(address spender, uint256 amount)",
+    "increaseAllowance(address spender, uint256 addedValue)",
+    "decreaseAllowance(address spender, uint256 subtractedValue)",
+    // EIP-2612
+    "permit(address owner, address spender, uint256 value, uint256 deadline, uint8 v, bytes32 r, bytes32 s)",
+    // ERC-721
+    "safeTransferFrom(address from, address to, uint256 tokenId)",
+    "safeTransferFrom(address from, address to, uint256 tokenId, bytes data)",
+    "setApprovalForAll(address operator, bool approved)",
+    // ERC-1155
+    "safeTransferFrom(address from, address to, uint256 id, uint256 amount, bytes data)",
+    "safeBatchTransferFrom(address from, address to, uint256[] ids, uint256[] amounts, bytes data)",
+    // WETH
+    "deposit()",
+    "withdraw(uint256 amount)",
+    // Multicall3
+    "aggregate3((address,bool,bytes)[] calls)",
+    "multicall(bytes[] data)",
+    // Safe
+    "execTransaction(address to, uint256 value, bytes data, uint8 operation, uint256 safeTxGas, uint256 baseGas, uint256 gasPrice, address gasToken, address refundReceiver, bytes signatures)",
+];
+
+/// A call decoded from its calldata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedCall {
+    /// The function called.
+    pub function: Function,
+    /// The arguments, in the order of the parameters.
+    pub arguments: Vec<Token>,
+}
+
+impl std::fmt::Display for DecodedCall {
+    /// Print the function, then every argument on its own line.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.function)?;
+        for (index, (param, argument)) in
+            self.function.inputs.iter().zip(&self.arguments).enumerate()
+        {
+            match param.name.is_empty() {
+                true => write!(f, "\n  [{}]: {}", index, argument)?,
+                false => write!(f, "\n  {}: {}", param.name, argument)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The functions a selector may call.
+///
+/// Selectors are only 4 bytes and unrelated functions share some of them,
+/// so a selector maps to every known function with it. Decoding tries the
+/// functions added last first, so those of a local ABI win over the common
+/// ones and those of a selector database.
+#[derive(Debug, Clone, Default)]
+pub struct SelectorRegistry {
+    functions: HashMap<[u8; 4], Vec<Function>>,
+}
+
+impl SelectorRegistry {
+    /// Create a registry knowing the functions of the common token and
+    /// wallet contracts: ERC-20, ERC-721, ERC-1155, WETH, Multicall3 and
+    /// Safe.
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        for signature in COMMON_FUNCTIONS {
+            registry.insert(Function::parse(signature).expect("common functions parse"));
+        }
+        registry
+    }
+
+    /// Create a registry knowing no function.
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// Add a function, ahead of those already known with the same
+    /// selector.
+    pub fn insert(&mut self, function: Function) {
+        let functions = self.functions.entry(function.selector()).or_default();
+        functions.retain(|known| known.signature() != function.signature());
+        functions.insert(0, function);
+    }
+
+    /// Add the functions of a selector database listing one signature per
+    /// line, such as an export of the 4byte directory. Empty lines and
+    /// those starting with `#` are skipped. Returns the number of
+    /// functions added.
+    pub fn add_signatures(&mut self, signatures: &str) -> Result<usize> {
+        let functions = signatures
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(Function::parse)
+            .collect::<Result<Vec<_>>>()?;
+        let count = functions.len();
+        functions
+            .into_iter()
+            .for_each(|function| self.insert(function));
+        Ok(count)
+    }
+
+    /// Add the functions of a JSON ABI, given as an array or as the `abi`
+    /// of a compiler artifact. Returns the number of functions added.
+    pub fn add_abi(&mut self, json: &str) -> Result<usize> {
+        let root: Value = serde_json::from_str(json).map_err(|_| AbiError::InvalidAbi)?;
+        let entries = match &root {
+            Value::Array(entries) => entries,
+            root => root["abi"].as_array().ok_or(AbiError::InvalidAbi)?,
+        };
+
+        let functions = entries
+            .iter()
+            // entries without a type are functions
+            .filter(|entry| matches!(entry["type"].as_str(), None | Some("function")))
+            .map(|entry| {
+                Ok(Function {
+                    name: entry["name"]
+                        .as_str()
+                        .ok_or(AbiError::InvalidAbi)?
+                        .to_string(),
+                    inputs: params_from_json(&entry["inputs"])?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let count = functions.len();
+        functions
+            .into_iter()
+            .for_each(|function| self.insert(function));
+        Ok(count)
+    }
+
+    /// Return the functions with the given selector, the preferred first.
+    pub fn lookup(&self, selector: &[u8; 4]) -> &[Function] {
+        self.functions.get(selector).map_or(&[], Vec::as_slice)
+    }
+
+    /// Decode calldata with the first function of its selector its
+    /// arguments decode with.
+    pub fn decode(&self, calldata: &[u8]) -> Result<DecodedCall> {
+        let selector = calldata.first_chunk::<4>().ok_or(AbiError::InvalidData)?;
+        let functions = self.lookup(selector);
+        if functions.is_empty() {
+            return Err(AbiError::UnknownSelector(*selector).into());
+        }
+        functions
+            .iter()
+            .find_map(|function| {
+                let arguments = function.decode_input(calldata).ok()?;
+                Some(DecodedCall {
+                    function: function.clone(),
+                    arguments,
+                })
+            })
+            .ok_or_else(|| AbiError::InvalidData.into())
+    }
+}
+
+fn params_from_json(inputs: &Value) -> Result<Vec<Param>> {
+    match inputs {
+        Value::Null => Ok(Vec::new()),
+        Value::Array(inputs) => inputs
+            .iter()
+            .map(|input| {
+                Ok(Param {
+                    name: input["name"].as_str().unwrap_or_default().to_string(),
+                    kind: type_from_json(input)?,
+                })
+            })
+            .collect(),
+        _ => Err(AbiError::InvalidAbi.into()),
+    }
+}
+
+/// Read the type of a JSON ABI parameter, whose tuples list their
+/// components apart.
+fn type_from_json(input: &Value) -> Result<ParamType> {
+    let ty = input["type"].as_str().ok_or(AbiError::InvalidAbi)?;
+    match ty.strip_prefix("tuple") {
+        Some(suffix) => {
+            let components = params_from_json(&input["components"])?;
+            let tuple = ParamType::Tuple(components.into_iter().map(|param| param.kind).collect());
+            Ok(with_suffix(tuple, suffix).ok_or_else(|| AbiError::InvalidType(ty.to_string()))?)
+        }
+        None => ParamType::parse(ty),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::Token;
+
+    fn calldata(selector: &str, words: &[&str]) -> Vec<u8> {
+        let words: Vec<String> = words.iter().map(|word| format!("{:0>64}", word)).collect();
+        hex::decode([selector.to_string(), words.concat()].concat()).unwrap()
+    }
+
+    #[test]
+    fn test_common_functions() {
+        let registry = SelectorRegistry::new();
+        let approve = calldata("095ea7b3", &[&"11".repeat(20), &"ff".repeat(32)]);
+        let call = registry.decode(&approve).unwrap();
+        assert_eq!(call.function.signature(), "approve(address,uint256)");
+        assert_eq!(
+            call.to_string(),
+            "approve(address spender, uint256 amount)\n  spender: 0x1111111111111111111111111111111111111111\n  amount: 115792089237316195423570985008687907853269984665640564039457584007913129639935"
+        );
+
+        // the three safeTransferFrom have their own selectors
+        for selector in ["42842e0e", "b88d4fde", "f242432a"] {
+            let selector: [u8; 4] = hex::decode(selector).unwrap().try_into().unwrap();
+            assert_eq!(registry.lookup(&selector).len(), 1);
+        }
+        assert_eq!(
+            registry
+                .decode(&hex::decode("d0e30db0").unwrap())
+                .unwrap()
+                .arguments,
+            []
+        );
+
+        let unknown = registry.decode(&[0xde, 0xad, 0xbe, 0xef]).unwrap_err();
+        assert_eq!(unknown.to_string(), "Unknown function selector 0xdeadbeef");
+        assert!(registry.decode(&[0xa9, 0x05, 0x9c]).is_err());
+        assert!(SelectorRegistry::empty().decode(&approve).is_err());
+    }
+
+    #[test]
+    fn test_colliding_selectors() {
+        // both have the selector 0x42966c68
+        let mut registry = SelectorRegistry::empty();
+        registry
+            .add_signatures("# collisions\nburn(uint256)\n\ncollate_propagate_storage(bytes16)\n")
+            .unwrap();
+        assert_eq!(registry.lookup(&[0x42, 0x96, 0x6c, 0x68]).len(), 2);
+
+        // the last added is preferred, the other is tried when it fails
+        let call = registry.decode(&calldata("42966c68", &["1"])).unwrap();
+        assert_eq!(call.function.name, "burn");
+        let call = registry
+            .decode(&calldata("42966c68", &[&format!("{:0<64}", "ab")]))
+            .unwrap();
+        assert_eq!(call.function.name, "collate_propagate_storage");
+        assert!(registry.add_signatures("burn(uint256").is_err());
+    }
+
+    #[test]
+    fn test_add_abi() {
+        let abi = r#"{"abi": [
+            {"type": "constructor", "inputs": []},
+            {"type": "event", "name": "Transfer", "inputs": []},
+            {"type": "function", "name": "submit", "stateMutability": "nonpayable", "inputs": [
+                {"name": "orders", "type": "tuple[]", "components": [
+                    {"name": "maker", "type": "address"},
+                    {"name": "amounts", "type": "uint128[2]"}
+                ]},
+                {"name": "", "type": "bytes"}
+            ]}
+        ]}"#;
+        let mut registry = SelectorRegistry::empty();
+        assert_eq!(registry.add_abi(abi).unwrap(), 1);
+
+        let function = Function::parse("submit((address,uint128[2])[],bytes)").unwrap();
+        let submit = &registry.lookup(&function.selector())[0];
+        assert_eq!(
+            submit.to_string(),
+            "submit((address,uint128[2])[] orders, bytes)"
+        );
+
+        let mut calldata = function.selector().to_vec();
+        for word in ["40", "c0", "1", &"22".repeat(20), "5", "6", "0"] {
+            calldata.extend(hex::decode(format!("{:0>64}", word)).unwrap());
+        }
+        let call = registry.decode(&calldata).unwrap();
+        assert_eq!(
+            call.arguments[0],
+            Token::Array(vec![Token::Tuple(vec![
+                Token::Address(crate::address::Address::new([0x22; 20])),
+                Token::Array(vec![Token::Uint(5.into()), Token::Uint(6.into())]),
+            ])])
+        );
+        assert!(call.to_string().ends_with("\n  [1]: 0x"));
+        assert!(registry.add_abi("{}").is_err());
+    }
+}
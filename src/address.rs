@@ -0,0 +1,248 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Ethereum Address
+//!
+//! A 20-byte account address, displayed with the mixed-case checksum defined
+//! in [EIP-55](https://eips.ethereum.org/EIPS/eip-55).
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::address::Address;
+//!
+//! let address: Address = "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed".parse().unwrap();
+//! assert_eq!(address.to_string(), "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed");
+//!
+//! // mixed-case input must carry a valid checksum
+//! assert!("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD".parse::<Address>().is_err());
+//! ```
+
+use k256::ecdsa::VerifyingKey;
+use laron_crypto::PublicKey;
+
+use crate::{
+    error::{Result, WalletError},
+    utils::keccak256,
+};
+
+/// Error returned when parsing an address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressError {
+    /// The address is not 20 bytes long.
+    InvalidLength(usize),
+    /// The mixed-case address does not match its EIP-55 checksum.
+    InvalidChecksum,
+}
+
+impl std::fmt::Display for AddressError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AddressError::InvalidLength(len) => write!(f, "Invalid address length: {}", len),
+            AddressError::InvalidChecksum => write!(f, "Invalid address checksum"),
+        }
+    }
+}
+
+impl std::error::Error for AddressError {}
+
+/// An Ethereum account address.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Address([u8; 20]);
+
+impl Address {
+    /// Create a new address from its bytes.
+    pub const fn new(bytes: [u8; 20]) -> Self {
+        Self(bytes)
+    }
+
+    /// Create a new address from a byte slice, which must be 20 bytes long.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self> {
+        let bytes = bytes
+            .try_into()
+            .map_err(|_| AddressError::InvalidLength(bytes.len()))?;
+        Ok(Self(bytes))
+    }
+
+    /// Derive the address of the given public key.
+    pub fn from_public_key(public_key: &PublicKey) -> Result<Self> {
+        let key = VerifyingKey::from_sec1_bytes(&public_key.to_bytes())?;
+        Ok(Self::from(&key))
+    }
+
+    /// Return the bytes of the address.
+    pub fn as_bytes(&self) -> &[u8; 20] {
+        &self.0
+    }
+
+    /// Return the address as a `0x` prefixed, EIP-55 checksummed string.
+    pub fn to_checksum(&self) -> String {
+        let lower = hex::encode(self.0);
+        let hash = keccak256(lower.as_bytes());
+
+        let checksummed = lower
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let nibble = hash[i / 2] >> (4 * (1 - i % 2)) & 0x0f;
+                if nibble >= 8 {
+                    c.to_ascii_uppercase()
+                } else {
+                    c
+                }
+            })
+            .collect::<String>();
+
+        format!("0x{}", checksummed)
+    }
+}
+
+impl From<[u8; 20]> for Address {
+    fn from(bytes: [u8; 20]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<Address> for [u8; 20] {
+    fn from(address: Address) -> Self {
+        address.0
+    }
+}
+
+impl From<&VerifyingKey> for Address {
+    fn from(key: &VerifyingKey) -> Self {
+        let point = key.to_encoded_point(false);
+        let hash = keccak256(&point.as_bytes()[1..]);
+        Self(hash[12..].try_into().unwrap())
+    }
+}
+
+impl TryFrom<&PublicKey> for Address {
+    type Error = WalletError;
+
+    fn try_from(public_key: &PublicKey) -> Result<Self> {
+        Self::from_public_key(public_key)
+    }
+}
+
+impl AsRef<[u8]> for Address {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.to_checksum())
+    }
+}
+
+impl std::fmt::Debug for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Address({})", self.to_checksum())
+    }
+}
+
+impl std::str::FromStr for Address {
+    type Err = WalletError;
+
+    /// Parse an address with or without the `0x` prefix. All-lowercase and
+    /// all-uppercase addresses carry no checksum, mixed-case ones must be
+    /// valid EIP-55.
+    fn from_str(s: &str) -> Result<Self> {
+        let hex_str = s.strip_prefix("0x").unwrap_or(s);
+        let address = Self::from_slice(&hex::decode(hex_str)?)?;
+
+        let has_lower = hex_str.chars().any(|c| c.is_ascii_lowercase());
+        let has_upper = hex_str.chars().any(|c| c.is_ascii_uppercase());
+        if has_lower && has_upper && address.to_checksum()[2..] != *hex_str {
+            return Err(AddressError::InvalidChecksum.into());
+        }
+
+        Ok(address)
+    }
+}
+
+#[cfg(feature = "serde")]
+crate::types::serde_string!(Address);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bips::{bip32::ExtendedKey, bip39::Mnemonic, wordlists::Language, DerivationPath};
+
+    #[test]
+    fn test_checksum() {
+        let addresses = [
+            "0x52908400098527886E0F7030069857D2E4169EE7",
+            "0x8617E340B3D01FA5F11F306F4090FD50E238070D",
+            "0xde709f2102306220921060314715629080e2fb77",
+            "0x27b1fdb04752bbc536007a920d24acb045561c26",
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ];
+
+        for s in addresses {
+            let address = s.parse::<Address>().unwrap();
+            assert_eq!(address.to_string().to_lowercase(), s.to_lowercase());
+            assert_eq!(address.to_string().parse::<Address>().unwrap(), address);
+        }
+
+        assert_eq!(
+            addresses[4]
+                .to_lowercase()
+                .parse::<Address>()
+                .unwrap()
+                .to_string(),
+            addresses[4]
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD"
+            .parse::<Address>()
+            .is_err());
+        assert!("0x5aaeb6053f3e94c9b9a09f33669435e7ef1bea"
+            .parse::<Address>()
+            .is_err());
+        assert!("0xzaaeb6053f3e94c9b9a09f33669435e7ef1beaed"
+            .parse::<Address>()
+            .is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let json = "\"0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed\"";
+        let address = serde_json::from_str::<Address>(json).unwrap();
+        assert_eq!(serde_json::to_string(&address).unwrap(), json);
+        assert!(serde_json::from_str::<Address>("\"0x5aaeb6053f\"").is_err());
+    }
+
+    #[test]
+    fn test_from_public_key() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+        let key = ExtendedKey::new_master(&mnemonic.to_seed("")).unwrap();
+        let path = DerivationPath::parse("m/44'/60'/0'/0/0").unwrap();
+        let child = key.derive_path(&path).unwrap();
+
+        let address = Address::try_from(child.public_key()).unwrap();
+        assert_eq!(
+            address.to_string(),
+            "0x9858EfFD232B4033E47d90003D41EC34EcaEda94"
+        );
+    }
+}
@@ -0,0 +1,203 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Argon2id
+//!
+//! The memory-hard password hash of
+//! [RFC 9106](https://www.rfc-editor.org/rfc/rfc9106), version 1.3, in its
+//! Argon2id variant, computed by the
+//! [RustCrypto implementation](https://github.com/RustCrypto/password-hashes).
+//! Its costs are capped so that parameters read from wallet files cannot
+//! exhaust the machine.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::argon2::{self, Params};
+//!
+//! let params = Params { memory: 64, iterations: 2, lanes: 1 };
+//! let mut key = [0u8; 32];
+//! argon2::argon2id(b"password", b"somesalt", &params, &mut key).unwrap();
+//! ```
+
+use ::argon2::{Algorithm, Argon2, ParamsBuilder, Version};
+
+use crate::error::Result;
+
+/// Largest memory cost accepted, 2 GiB, the first recommendation of RFC 9106.
+pub const MAX_MEMORY: u32 = 1 << 21;
+/// Largest number of passes accepted.
+pub const MAX_ITERATIONS: u32 = 64;
+
+/// Error returned when hashing with invalid inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Argon2Error {
+    /// The costs are out of bounds, see [`Params::is_valid`].
+    InvalidParams,
+    /// The salt is shorter than 8 bytes.
+    SaltTooShort,
+    /// The output is shorter than 4 bytes.
+    OutputTooShort,
+}
+
+impl std::fmt::Display for Argon2Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Argon2Error::InvalidParams => write!(f, "Invalid Argon2 parameters"),
+            Argon2Error::SaltTooShort => write!(f, "Argon2 salt too short"),
+            Argon2Error::OutputTooShort => write!(f, "Argon2 output too short"),
+        }
+    }
+}
+
+impl std::error::Error for Argon2Error {}
+
+/// Cost parameters of Argon2id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Params {
+    /// Memory in KiB, at least 8 per lane.
+    pub memory: u32,
+    /// Number of passes over the memory, at least 1.
+    pub iterations: u32,
+    /// Degree of parallelism, between 1 and 2^24 - 1.
+    pub lanes: u32,
+}
+
+impl Params {
+    /// Return whether the parameters are within the bounds of RFC 9106,
+    /// and the costs at most [`MAX_MEMORY`] and [`MAX_ITERATIONS`] so that
+    /// parameters read from untrusted data cannot exhaust the machine.
+    pub fn is_valid(&self) -> bool {
+        (1..1 << 24).contains(&self.lanes)
+            && (1..=MAX_ITERATIONS).contains(&self.iterations)
+            && (8 * self.lanes..=MAX_MEMORY).contains(&self.memory)
+    }
+
+    fn builder(&self) -> ParamsBuilder {
+        let mut builder = ParamsBuilder::new();
+        builder
+            .m_cost(self.memory)
+            .t_cost(self.iterations)
+            .p_cost(self.lanes);
+        builder
+    }
+}
+
+/// Hash `password` with `salt` into `out`.
+pub fn argon2id(password: &[u8], salt: &[u8], params: &Params, out: &mut [u8]) -> Result<()> {
+    if !params.is_valid() {
+        return Err(Argon2Error::InvalidParams.into());
+    }
+    let params = params
+        .builder()
+        .build()
+        .map_err(|_| Argon2Error::InvalidParams)?;
+    let context = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+    hash(&context, password, salt, out)
+}
+
+fn hash(context: &Argon2, password: &[u8], salt: &[u8], out: &mut [u8]) -> Result<()> {
+    if salt.len() < ::argon2::MIN_SALT_LEN {
+        return Err(Argon2Error::SaltTooShort.into());
+    }
+    if out.len() < ::argon2::Params::MIN_OUTPUT_LEN {
+        return Err(Argon2Error::OutputTooShort.into());
+    }
+    context
+        .hash_password_into(password, salt, out)
+        .map_err(|_| Argon2Error::InvalidParams.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc9106() {
+        let params = Params {
+            memory: 32,
+            iterations: 3,
+            lanes: 4,
+        };
+        let data = ::argon2::AssociatedData::new(&[4; 12]).unwrap();
+        let mut builder = params.builder();
+        builder.data(data);
+        let context = Argon2::new_with_secret(
+            &[3; 8],
+            Algorithm::Argon2id,
+            Version::V0x13,
+            builder.build().unwrap(),
+        )
+        .unwrap();
+        let mut tag = [0u8; 32];
+        hash(&context, &[1; 32], &[2; 16], &mut tag).unwrap();
+        assert_eq!(
+            hex::encode(tag),
+            "0d640df58d78766c08c037a34a8b53c9d01ef0452d75b65eb52520e96b01e659"
+        );
+    }
+
+    #[test]
+    fn test_params() {
+        let params = Params {
+            memory: 8,
+            iterations: 1,
+            lanes: 1,
+        };
+        assert!(params.is_valid());
+        assert!(!Params { lanes: 2, ..params }.is_valid());
+        assert!(!Params {
+            iterations: 0,
+            ..params
+        }
+        .is_valid());
+        assert!(!Params {
+            iterations: MAX_ITERATIONS + 1,
+            ..params
+        }
+        .is_valid());
+        assert!(Params {
+            memory: MAX_MEMORY,
+            ..params
+        }
+        .is_valid());
+        assert!(!Params {
+            memory: MAX_MEMORY + 1,
+            ..params
+        }
+        .is_valid());
+
+        let mut short = [0u8; 4];
+        let mut long = [0u8; 100];
+        argon2id(b"password", b"somesalt", &params, &mut short).unwrap();
+        argon2id(b"password", b"somesalt", &params, &mut long).unwrap();
+        assert_ne!(short[..], long[..4]);
+
+        assert_eq!(
+            argon2id(
+                b"password",
+                b"somesalt",
+                &Params { lanes: 0, ..params },
+                &mut short
+            ),
+            Err(Argon2Error::InvalidParams.into())
+        );
+        assert_eq!(
+            argon2id(b"password", b"salt", &params, &mut short),
+            Err(Argon2Error::SaltTooShort.into())
+        );
+        assert_eq!(
+            argon2id(b"password", b"somesalt", &params, &mut short[..3]),
+            Err(Argon2Error::OutputTooShort.into())
+        );
+    }
+}
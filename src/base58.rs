@@ -0,0 +1,213 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Base58
+//!
+//! The Base58 encoding of Bitcoin, and Base58Check which appends the first
+//! four bytes of a double SHA-256 to detect typing errors. The XRP Ledger
+//! uses the same encodings over its own [`Alphabet`].
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::base58;
+//!
+//! assert_eq!(base58::encode(b"hello world"), "StV1DL6CwTryKyV");
+//! assert_eq!(base58::decode("StV1DL6CwTryKyV").unwrap(), b"hello world");
+//!
+//! let encoded = base58::encode_check(&[0x00; 21]);
+//! assert_eq!(base58::decode_check(&encoded).unwrap(), [0x00; 21]);
+//!
+//! let encoded = base58::encode_check_with(base58::Alphabet::Ripple, &[0x00; 21]);
+//! assert_eq!(encoded, "rrrrrrrrrrrrrrrrrrrrrhoLvTp");
+//! ```
+
+use sha2::{Digest, Sha256};
+
+use crate::error::Result;
+
+/// The alphabet of an encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Alphabet {
+    /// The alphabet of Bitcoin, used by most chains.
+    Bitcoin,
+    /// The alphabet of the XRP Ledger.
+    Ripple,
+}
+
+impl Alphabet {
+    fn chars(&self) -> &'static [u8; 58] {
+        match self {
+            Alphabet::Bitcoin => b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz",
+            Alphabet::Ripple => b"rpshnaf39wBUDNEGHJKLM4PQRST7VWXYZ2bcdeCg65jkm8oFqi1tuvAxyz",
+        }
+    }
+}
+
+/// Error returned when decoding Base58.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Base58Error {
+    /// A character outside of the alphabet.
+    InvalidCharacter(char),
+    /// The checksum does not match the data.
+    InvalidChecksum,
+}
+
+impl std::fmt::Display for Base58Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Base58Error::InvalidCharacter(c) => write!(f, "Invalid base58 character: {:?}", c),
+            Base58Error::InvalidChecksum => write!(f, "Invalid base58 checksum"),
+        }
+    }
+}
+
+impl std::error::Error for Base58Error {}
+
+/// Encode bytes as Base58.
+pub fn encode(data: impl AsRef<[u8]>) -> String {
+    encode_with(Alphabet::Bitcoin, data)
+}
+
+/// Encode bytes as Base58 over `alphabet`.
+pub fn encode_with(alphabet: Alphabet, data: impl AsRef<[u8]>) -> String {
+    let chars = alphabet.chars();
+    let data = data.as_ref();
+    let zeros = data.iter().take_while(|b| **b == 0).count();
+
+    // little-endian digits in base 58
+    let mut digits: Vec<u8> = Vec::with_capacity(data.len() * 138 / 100 + 1);
+    for byte in &data[zeros..] {
+        let mut carry = *byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    std::iter::repeat_n(chars[0] as char, zeros)
+        .chain(digits.iter().rev().map(|d| chars[*d as usize] as char))
+        .collect()
+}
+
+/// Decode Base58.
+pub fn decode(encoded: &str) -> Result<Vec<u8>> {
+    decode_with(Alphabet::Bitcoin, encoded)
+}
+
+/// Decode Base58 over `alphabet`.
+pub fn decode_with(alphabet: Alphabet, encoded: &str) -> Result<Vec<u8>> {
+    let chars = alphabet.chars();
+    let zeros = encoded
+        .chars()
+        .take_while(|c| *c == chars[0] as char)
+        .count();
+
+    // little-endian bytes
+    let mut bytes: Vec<u8> = Vec::with_capacity(encoded.len() * 733 / 1000 + 1);
+    for c in encoded.chars().skip(zeros) {
+        let mut carry = chars
+            .iter()
+            .position(|a| *a as char == c)
+            .ok_or(Base58Error::InvalidCharacter(c))? as u32;
+        for byte in bytes.iter_mut() {
+            carry += *byte as u32 * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+
+    bytes.extend(std::iter::repeat_n(0, zeros));
+    bytes.reverse();
+    Ok(bytes)
+}
+
+/// Encode bytes as Base58Check.
+pub fn encode_check(data: impl AsRef<[u8]>) -> String {
+    encode_check_with(Alphabet::Bitcoin, data)
+}
+
+/// Encode bytes as Base58Check over `alphabet`.
+pub fn encode_check_with(alphabet: Alphabet, data: impl AsRef<[u8]>) -> String {
+    let data = data.as_ref();
+    let mut bytes = data.to_vec();
+    bytes.extend_from_slice(&checksum(data));
+    encode_with(alphabet, bytes)
+}
+
+/// Decode Base58Check, returning the data without its checksum.
+pub fn decode_check(encoded: &str) -> Result<Vec<u8>> {
+    decode_check_with(Alphabet::Bitcoin, encoded)
+}
+
+/// Decode Base58Check over `alphabet`, returning the data without its
+/// checksum.
+pub fn decode_check_with(alphabet: Alphabet, encoded: &str) -> Result<Vec<u8>> {
+    let mut bytes = decode_with(alphabet, encoded)?;
+    if bytes.len() < 4 {
+        return Err(Base58Error::InvalidChecksum.into());
+    }
+    let check = bytes.split_off(bytes.len() - 4);
+    if check != checksum(&bytes) {
+        return Err(Base58Error::InvalidChecksum.into());
+    }
+    Ok(bytes)
+}
+
+fn checksum(data: &[u8]) -> [u8; 4] {
+    let hash = Sha256::digest(Sha256::digest(data));
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode(b""), "");
+        assert_eq!(encode([0, 0, 1]), "112");
+        assert_eq!(
+            encode(hex::decode("00010966776006953d5567439e5e39f86a0d273beed61967f6").unwrap()),
+            "16UwLL9Risc3QfPqBUvKofHmBQ7wMtjvM"
+        );
+        assert_eq!(decode("112").unwrap(), [0, 0, 1]);
+        assert_eq!(
+            decode("0").unwrap_err(),
+            Base58Error::InvalidCharacter('0').into()
+        );
+    }
+
+    #[test]
+    fn test_check() {
+        let address = "16UwLL9Risc3QfPqBUvKofHmBQ7wMtjvM";
+        let payload = decode_check(address).unwrap();
+        assert_eq!(
+            hex::encode(&payload),
+            "00010966776006953d5567439e5e39f86a0d273bee"
+        );
+        assert_eq!(encode_check(&payload), address);
+        assert_eq!(
+            decode_check("16UwLL9Risc3QfPqBUvKofHmBQ7wMtjvN").unwrap_err(),
+            Base58Error::InvalidChecksum.into()
+        );
+    }
+}
@@ -0,0 +1,230 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Bech32
+//!
+//! The Bech32 encoding of
+//! [BIP173](https://github.com/bitcoin/bips/blob/master/bip-0173.mediawiki):
+//! a human readable part naming the network or chain, the separator `1`,
+//! then the data in a 32 character alphabet followed by a 6 character
+//! checksum. Cosmos chains use it for their addresses and public keys.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::bech32;
+//!
+//! let encoded = bech32::encode("cosmos", [0x11; 20]).unwrap();
+//! assert_eq!(encoded, "cosmos1zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3pahzj0");
+//!
+//! let (hrp, data) = bech32::decode(&encoded).unwrap();
+//! assert_eq!((hrp.as_str(), data), ("cosmos", vec![0x11; 20]));
+//! ```
+
+use crate::error::Result;
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+/// Longest encoded string allowed by BIP173.
+const MAX_LENGTH: usize = 90;
+
+/// Error returned when encoding or decoding Bech32.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Bech32Error {
+    /// The human readable part is empty or has characters outside of
+    /// US-ASCII 33 to 126.
+    InvalidHrp,
+    /// A character outside of the alphabet.
+    InvalidCharacter(char),
+    /// The string mixes upper and lower case.
+    MixedCase,
+    /// The separator is missing, or the string is too long or too short.
+    InvalidLength,
+    /// The checksum does not match.
+    InvalidChecksum,
+    /// The data does not convert back to whole bytes.
+    InvalidPadding,
+}
+
+impl std::fmt::Display for Bech32Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Bech32Error::InvalidHrp => write!(f, "Invalid bech32 human readable part"),
+            Bech32Error::InvalidCharacter(c) => write!(f, "Invalid bech32 character: {:?}", c),
+            Bech32Error::MixedCase => write!(f, "Mixed case bech32 string"),
+            Bech32Error::InvalidLength => write!(f, "Invalid bech32 length"),
+            Bech32Error::InvalidChecksum => write!(f, "Invalid bech32 checksum"),
+            Bech32Error::InvalidPadding => write!(f, "Invalid bech32 padding"),
+        }
+    }
+}
+
+impl std::error::Error for Bech32Error {}
+
+/// Encode bytes under the human readable part `hrp`.
+pub fn encode(hrp: &str, data: impl AsRef<[u8]>) -> Result<String> {
+    let data = convert_bits(data.as_ref(), 8, 5, true)?;
+    encode_u5(hrp, &data)
+}
+
+/// Decode a string, returning its lowercase human readable part and the
+/// bytes of its data.
+pub fn decode(encoded: &str) -> Result<(String, Vec<u8>)> {
+    let (hrp, data) = decode_u5(encoded)?;
+    Ok((hrp, convert_bits(&data, 5, 8, false)?))
+}
+
+/// Encode 5-bit values under the human readable part `hrp`.
+pub(crate) fn encode_u5(hrp: &str, data: &[u8]) -> Result<String> {
+    if hrp.is_empty() || !hrp.bytes().all(|b| (33..=126).contains(&b)) {
+        return Err(Bech32Error::InvalidHrp.into());
+    }
+    let hrp = hrp.to_lowercase();
+    if hrp.len() + data.len() + 7 > MAX_LENGTH {
+        return Err(Bech32Error::InvalidLength.into());
+    }
+
+    let mut values = expand_hrp(&hrp);
+    values.extend_from_slice(data);
+    values.extend_from_slice(&[0; 6]);
+    let checksum = polymod(&values) ^ 1;
+
+    let mut encoded = hrp;
+    encoded.push('1');
+    encoded.extend(data.iter().map(|v| CHARSET[*v as usize] as char));
+    encoded.extend((0..6).map(|i| CHARSET[(checksum >> (5 * (5 - i))) as usize & 31] as char));
+    Ok(encoded)
+}
+
+/// Decode a string into its lowercase human readable part and 5-bit values.
+pub(crate) fn decode_u5(encoded: &str) -> Result<(String, Vec<u8>)> {
+    if encoded.len() > MAX_LENGTH {
+        return Err(Bech32Error::InvalidLength.into());
+    }
+    let lower = encoded.to_lowercase();
+    if lower != encoded && encoded.to_uppercase() != encoded {
+        return Err(Bech32Error::MixedCase.into());
+    }
+
+    let (hrp, data) = lower.rsplit_once('1').ok_or(Bech32Error::InvalidLength)?;
+    if hrp.is_empty() || !hrp.bytes().all(|b| (33..=126).contains(&b)) {
+        return Err(Bech32Error::InvalidHrp.into());
+    }
+    if data.len() < 6 {
+        return Err(Bech32Error::InvalidLength.into());
+    }
+    let data = data
+        .chars()
+        .map(|c| {
+            CHARSET
+                .iter()
+                .position(|v| *v as char == c)
+                .map(|v| v as u8)
+                .ok_or(Bech32Error::InvalidCharacter(c))
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut values = expand_hrp(hrp);
+    values.extend_from_slice(&data);
+    if polymod(&values) != 1 {
+        return Err(Bech32Error::InvalidChecksum.into());
+    }
+    Ok((hrp.to_string(), data[..data.len() - 6].to_vec()))
+}
+
+/// Regroup `from`-bit values into `to`-bit values.
+pub(crate) fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Result<Vec<u8>> {
+    let mut acc = 0u32;
+    let mut bits = 0;
+    let mut converted = Vec::with_capacity(data.len() * from as usize / to as usize + 1);
+    let max = (1 << to) - 1;
+    for value in data {
+        acc = (acc << from) | *value as u32;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            converted.push(((acc >> bits) & max) as u8);
+        }
+    }
+    if pad {
+        if bits > 0 {
+            converted.push(((acc << (to - bits)) & max) as u8);
+        }
+    } else if bits >= from || (acc << (to - bits)) & max != 0 {
+        return Err(Bech32Error::InvalidPadding.into());
+    }
+    Ok(converted)
+}
+
+fn expand_hrp(hrp: &str) -> Vec<u8> {
+    let mut values: Vec<u8> = hrp.bytes().map(|b| b >> 5).collect();
+    values.push(0);
+    values.extend(hrp.bytes().map(|b| b & 31));
+    values
+}
+
+fn polymod(values: &[u8]) -> u32 {
+    let mut checksum = 1u32;
+    for value in values {
+        let top = checksum >> 25;
+        checksum = (checksum & 0x1ffffff) << 5 ^ *value as u32;
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid() {
+        // BIP173 test vectors
+        for valid in [
+            "A12UEL5L",
+            "a12uel5l",
+            "an83characterlonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1tt5tgs",
+            "abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw",
+            "split1checkupstagehandshakeupstreamerranterredcaperred2y9e3w",
+        ] {
+            let (hrp, data) = decode_u5(valid).unwrap();
+            assert_eq!(encode_u5(&hrp, &data).unwrap(), valid.to_lowercase());
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        for invalid in [
+            "pzry9x0s0muk",
+            "1pzry9x0s0muk",
+            "x1b4n0q5v",
+            "li1dgmt3",
+            "A1G7SGD8",
+            "10a06t8",
+            "1qzzfhee",
+            "a12UEL5L",
+        ] {
+            assert!(decode_u5(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_bytes() {
+        let encoded = encode("bc", [0xff; 3]).unwrap();
+        assert_eq!(decode(&encoded).unwrap(), ("bc".to_string(), vec![0xff; 3]));
+        assert!(encode("", [0; 3]).is_err());
+    }
+}
@@ -18,17 +18,37 @@
 //! The main difference is that this implementation specializes in the use for
 //! Ethereum wallets generation and derivation.
 
-use super::{bip39::Seed, ChildNumber, DerivationPath};
+pub mod slip10;
+pub mod xpub;
+
+use std::ops::Range;
+
+use self::xpub::ExtendedPublicKey;
+use super::{bip39::Seed, locked::Locked, ChildNumber, DerivationPath};
+use crate::{
+    address::Address,
+    error::{Result, WalletError},
+    types::{FixedBytes, B256},
+};
 use hmac::{Hmac, Mac};
-use horror::Result;
+use k256::ecdsa::VerifyingKey;
 use laron_crypto::{PrivateKey, PublicKey};
-use ripemd::{Digest, Ripemd160};
 use sha2::Sha512;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
-#[derive(Debug, Clone)]
-pub(crate) enum ExtendedKeyError {
+/// Error returned when deriving an extended key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtendedKeyError {
+    /// The key is already at the maximum depth of 255.
     DepthTooLarge,
+    /// The seed is not 16, 32 or 64 bytes long.
     SeedLength,
+    /// A non-hardened child of an ed25519 key was requested.
+    NonHardened,
+    /// A hardened child of an extended public key was requested.
+    Hardened,
+    /// A serialized extended key is malformed.
+    InvalidFormat,
 }
 
 impl std::fmt::Display for ExtendedKeyError {
@@ -36,22 +56,53 @@ impl std::fmt::Display for ExtendedKeyError {
         match self {
             ExtendedKeyError::DepthTooLarge => write!(f, "Depth too large"),
             ExtendedKeyError::SeedLength => write!(f, "Seed length must be 16, 32, or 64"),
+            ExtendedKeyError::NonHardened => {
+                write!(f, "Ed25519 keys only have hardened children")
+            }
+            ExtendedKeyError::Hardened => {
+                write!(f, "Hardened children need the private key")
+            }
+            ExtendedKeyError::InvalidFormat => write!(f, "Invalid extended key"),
         }
     }
 }
 
 impl std::error::Error for ExtendedKeyError {}
 
+/// The public metadata of an [`ExtendedKey`], without its private key, for
+/// storing alongside an account or handing to watch-only software. It
+/// implements serde with the `serde` feature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyMetadata {
+    /// The depth of the key, 0 for the master key.
+    pub depth: u8,
+    /// The first four bytes of the parent's key identifier.
+    pub parent_fingerprint: FixedBytes<4>,
+    /// The child number the key was derived with.
+    pub child_number: ChildNumber,
+    /// The chain code.
+    pub chain_code: B256,
+    /// The compressed public key.
+    pub public_key: FixedBytes<33>,
+    /// The Ethereum address of the key.
+    pub address: Address,
+}
+
 /// BIP32 implementation for deriving private and public keys
 /// from a seed.
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// The chain code is zeroized when the key is dropped, and kept out of swap
+/// with the `mlock` feature. The private key is managed by laron-crypto,
+/// which wipes it itself. Both are left out of the `Debug` output.
+#[derive(Clone, PartialEq, Eq)]
 pub struct ExtendedKey {
     key: PrivateKey,
     public_key: PublicKey,
     parent_fingerprint: [u8; 4],
     child_number: ChildNumber,
     depth: u8,
-    chain_code: [u8; 32],
+    chain_code: Locked<B256>,
 }
 
 impl ExtendedKey {
@@ -62,15 +113,18 @@ impl ExtendedKey {
         parent_fingerprint: [u8; 4],
         child_number: ChildNumber,
         depth: u8,
-        chain_code: [u8; 32],
+        mut chain_code: B256,
     ) -> Self {
+        let locked = Locked::new(Box::new(chain_code));
+        chain_code.zeroize();
+
         Self {
             key,
             public_key,
             parent_fingerprint,
             child_number,
             depth,
-            chain_code,
+            chain_code: locked,
         }
     }
 
@@ -80,12 +134,17 @@ impl ExtendedKey {
             return Err(ExtendedKeyError::SeedLength.into());
         }
 
-        let mut hmac: Hmac<Sha512> = Hmac::new_from_slice(b"Bitcoin seed")?;
+        let mut hmac: Hmac<Sha512> =
+            Hmac::new_from_slice(b"Bitcoin seed").expect("HMAC takes keys of any length");
         hmac.update(seed.to_bytes());
-        let bytes = hmac.finalize().into_bytes();
+        let mut bytes = hmac.finalize().into_bytes();
 
         let (key, chain_code) = bytes.split_at(32);
-        let private_key = PrivateKey::from_bytes(key)?;
+        let private_key = PrivateKey::from_bytes(key).map_err(WalletError::crypto);
+        let chain_code = B256::from_slice(chain_code);
+        bytes.as_mut_slice().zeroize();
+
+        let private_key = private_key?;
         let public_key = private_key.public_key();
 
         Ok(Self::new(
@@ -94,7 +153,7 @@ impl ExtendedKey {
             [0; 4],
             ChildNumber::from(0),
             0,
-            chain_code.try_into()?,
+            chain_code?,
         ))
     }
 
@@ -105,24 +164,30 @@ impl ExtendedKey {
             .checked_add(1)
             .ok_or(ExtendedKeyError::DepthTooLarge)?;
 
-        let mut hmac: Hmac<Sha512> = Hmac::new_from_slice(&self.chain_code)?;
+        let mut hmac: Hmac<Sha512> =
+            Hmac::new_from_slice(self.chain_code.as_ref()).expect("HMAC takes keys of any length");
 
         if child_number.is_hardened() {
             hmac.update(&[0]);
-            hmac.update(&self.key.to_bytes());
+            hmac.update(Zeroizing::new(self.key.to_bytes()).as_slice());
         } else {
             hmac.update(&self.key.public_key().to_bytes());
         }
 
         hmac.update(&child_number.to_bytes());
 
-        let result = hmac.finalize().into_bytes();
+        let mut result = hmac.finalize().into_bytes();
         let (child_key, chain_code) = result.split_at(32);
+        let private_key = self
+            .key
+            .derive_child(child_key.try_into().expect("32 bytes"))
+            .map_err(WalletError::crypto);
+        let chain_code = B256::from_slice(chain_code);
+        result.as_mut_slice().zeroize();
 
-        let private_key = self.key.derive_child(child_key.try_into()?)?;
+        let private_key = private_key?;
         let public_key = private_key.public_key();
-        let fp = Ripemd160::digest(&self.key.public_key().to_bytes());
-        let parent_fingerprint: [u8; 4] = fp[0..4].try_into()?;
+        let parent_fingerprint = xpub::fingerprint(&self.verifying_key()?);
 
         Ok(Self::new(
             private_key,
@@ -130,7 +195,7 @@ impl ExtendedKey {
             parent_fingerprint,
             child_number,
             depth,
-            chain_code.try_into()?,
+            chain_code?,
         ))
     }
 
@@ -145,6 +210,96 @@ impl ExtendedKey {
         Ok(key)
     }
 
+    /// Derive the nodes of many paths at once, returned in the order of
+    /// `paths`. Paths are visited in sorted order so the nodes of a prefix
+    /// they share, such as an account, are derived only once.
+    pub fn derive_multi(&self, paths: &[DerivationPath]) -> Result<Vec<Self>> {
+        let mut order = (0..paths.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| {
+            let a = paths[a].iter().map(|c| u32::from(*c));
+            let b = paths[b].iter().map(|c| u32::from(*c));
+            a.cmp(b)
+        });
+
+        let mut keys = vec![None; paths.len()];
+        // the nodes derived along the previous path
+        let mut stack: Vec<(ChildNumber, Self)> = Vec::new();
+
+        for i in order {
+            let common = stack
+                .iter()
+                .zip(paths[i].iter())
+                .take_while(|((a, _), b)| a == *b)
+                .count();
+            stack.truncate(common);
+
+            for child_number in paths[i].iter().skip(common) {
+                let parent = stack.last().map_or(self, |(_, key)| key);
+                let child = parent.derive_child(*child_number)?;
+                stack.push((*child_number, child));
+            }
+
+            keys[i] = Some(stack.last().map_or(self, |(_, key)| key).clone());
+        }
+
+        Ok(keys.into_iter().flatten().collect())
+    }
+
+    /// Derive the nodes of many paths like [`ExtendedKey::derive_multi`],
+    /// spread over the rayon thread pool with the `parallel` feature. Each
+    /// thread takes a contiguous share of `paths`, so nearby paths should be
+    /// kept next to each other for their common nodes to be derived once
+    /// per thread.
+    pub fn derive_batch(&self, paths: &[DerivationPath]) -> Result<Vec<Self>> {
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+
+            let share = paths.len().div_ceil(rayon::current_num_threads()).max(1);
+            if paths.len() > share {
+                let shares = paths
+                    .par_chunks(share)
+                    .map(|paths| self.derive_multi(paths))
+                    .collect::<Result<Vec<_>>>()?;
+                return Ok(shares.into_iter().flatten().collect());
+            }
+        }
+
+        self.derive_multi(paths)
+    }
+
+    /// Return the paths and addresses of the children of `base` with the
+    /// given indices, such as the first receive addresses of an account.
+    /// The node of `base` is derived once, then a single child per address.
+    pub fn derive_range(
+        &self,
+        base: &DerivationPath,
+        indices: Range<u32>,
+    ) -> Result<impl Iterator<Item = Result<(DerivationPath, Address)>>> {
+        let node = self.derive_path(base)?;
+        Ok(base.range(indices).map(move |path| {
+            let child = node.derive_child(path[path.len() - 1])?;
+            let address = Address::from_public_key(child.public_key())?;
+            Ok((path, address))
+        }))
+    }
+
+    /// Return the extended public key of the node, to export an account
+    /// for watch-only address generation.
+    pub fn extended_public_key(&self) -> Result<ExtendedPublicKey> {
+        Ok(ExtendedPublicKey::new(
+            self.verifying_key()?,
+            *self.chain_code,
+            self.parent_fingerprint,
+            self.child_number,
+            self.depth,
+        ))
+    }
+
+    fn verifying_key(&self) -> Result<VerifyingKey> {
+        Ok(VerifyingKey::from_sec1_bytes(&self.public_key.to_bytes())?)
+    }
+
     /// Get the private key.
     pub fn private_key(&self) -> &PrivateKey {
         &self.key
@@ -171,11 +326,38 @@ impl ExtendedKey {
     }
 
     /// Get the chain code.
-    pub fn chain_code(&self) -> &[u8; 32] {
+    pub fn chain_code(&self) -> &B256 {
         &self.chain_code
     }
+
+    /// Get the public metadata of the key.
+    pub fn metadata(&self) -> Result<KeyMetadata> {
+        Ok(KeyMetadata {
+            depth: self.depth,
+            parent_fingerprint: FixedBytes::new(self.parent_fingerprint),
+            child_number: self.child_number,
+            chain_code: *self.chain_code,
+            public_key: FixedBytes::from_slice(&self.public_key.to_bytes())?,
+            address: Address::from_public_key(&self.public_key)?,
+        })
+    }
 }
 
+impl std::fmt::Debug for ExtendedKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ExtendedKey")
+            .field("key", &"<redacted>")
+            .field("public_key", &hex::encode(self.public_key.to_bytes()))
+            .field("parent_fingerprint", &hex::encode(self.parent_fingerprint))
+            .field("child_number", &self.child_number)
+            .field("depth", &self.depth)
+            .field("chain_code", &"<redacted>")
+            .finish()
+    }
+}
+
+impl ZeroizeOnDrop for ExtendedKey {}
+
 #[cfg(test)]
 mod tests {
     use crate::bips::{bip39::Mnemonic, wordlists::Language};
@@ -216,4 +398,122 @@ mod tests {
             "0237b0bb7a8288d38ed49a524b5dc98cff3eb5ca824c9f9dc0dfdb3d9cd600f299"
         );
     }
+
+    #[test]
+    pub fn test_redacted_debug() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+        let seed = mnemonic.to_seed("");
+        let key = ExtendedKey::new_master(&seed).unwrap();
+
+        let debug = format!("{:?} {:?} {:?}", mnemonic, seed, key);
+        assert!(!debug.contains("abandon"));
+        assert!(!debug.contains(&seed.expose_hex()));
+        assert!(!debug.contains(&hex::encode(key.chain_code())));
+        assert!(!debug.contains(&key.private_key().to_string()));
+        assert!(debug.contains("<redacted 64 bytes>"));
+
+        assert_eq!(mnemonic.phrase(), phrase);
+        assert_eq!(mnemonic.to_string(), "<redacted 12 words>");
+        assert!(!seed.to_string().contains(&seed.expose_hex()));
+    }
+
+    #[test]
+    pub fn test_zeroize_on_drop() {
+        fn zeroize_on_drop<T: ZeroizeOnDrop>() {}
+        zeroize_on_drop::<Mnemonic>();
+        zeroize_on_drop::<Seed>();
+        zeroize_on_drop::<ExtendedKey>();
+    }
+
+    #[test]
+    pub fn test_metadata() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+        let key = ExtendedKey::new_master(&mnemonic.to_seed("")).unwrap();
+        let child = key
+            .derive_path(&DerivationPath::parse("m/44'/60'/0'/0/0").unwrap())
+            .unwrap();
+
+        let metadata = child.metadata().unwrap();
+        assert_eq!(metadata.depth, 5);
+        assert_eq!(metadata.child_number, ChildNumber::normal(0));
+        assert_eq!(metadata.chain_code, *child.chain_code());
+        assert_eq!(
+            metadata.public_key.to_string(),
+            "0x0237b0bb7a8288d38ed49a524b5dc98cff3eb5ca824c9f9dc0dfdb3d9cd600f299"
+        );
+        assert_eq!(
+            metadata.address.to_string(),
+            "0x9858EfFD232B4033E47d90003D41EC34EcaEda94"
+        );
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&metadata).unwrap();
+            assert!(!json.contains(&child.private_key().to_string()));
+            assert_eq!(
+                serde_json::from_str::<KeyMetadata>(&json).unwrap(),
+                metadata
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_derive_multi() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+        let key = ExtendedKey::new_master(&mnemonic.to_seed("")).unwrap();
+
+        let paths = [
+            "m/44'/60'/0'/0/1",
+            "m/44'/0'/0'/0/0",
+            "m/44'/60'/0'/0/0",
+            "m",
+            "m/44'/60'/1'/0/0",
+            "m/44'/60'/0'/0/1",
+            "m/44'/60'",
+        ]
+        .iter()
+        .map(|p| DerivationPath::parse(p).unwrap())
+        .collect::<Vec<_>>();
+
+        let keys = key.derive_multi(&paths).unwrap();
+        assert_eq!(keys.len(), paths.len());
+        for (path, derived) in paths.iter().zip(keys) {
+            assert_eq!(derived, key.derive_path(path).unwrap());
+        }
+        assert!(key.derive_multi(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    pub fn test_derive_batch() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+        let key = ExtendedKey::new_master(&mnemonic.to_seed("")).unwrap();
+
+        let base = DerivationPath::parse("m/44'/60'/0'/0").unwrap();
+        let paths = base.range(0..40).collect::<Vec<_>>();
+        let keys = key.derive_batch(&paths).unwrap();
+        assert_eq!(keys, key.derive_multi(&paths).unwrap());
+
+        let addresses = key
+            .derive_range(&base, 0..40)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(addresses.len(), 40);
+        assert_eq!(addresses[0].0, paths[0]);
+        assert_eq!(
+            addresses[0].1.to_string(),
+            "0x9858EfFD232B4033E47d90003D41EC34EcaEda94"
+        );
+        for ((path, address), key) in addresses.iter().zip(&keys) {
+            assert_eq!(
+                *address,
+                Address::from_public_key(key.public_key()).unwrap()
+            );
+            assert_eq!(path.len(), 5);
+        }
+    }
 }
@@ -0,0 +1,236 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! SLIP-10 derivation of ed25519 keys
+//!
+//! [SLIP-10](https://github.com/satoshilabs/slips/blob/master/slip-0010.md)
+//! adapts BIP32 to other curves, so that ed25519 keys of chains such as
+//! Solana and Stellar are derived from the same [`Seed`] as the Ethereum
+//! keys. An ed25519 public key cannot be tweaked, so every child is derived
+//! from the private key of its parent and only hardened child numbers are
+//! allowed.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::bips::{bip32::slip10::Ed25519ExtendedKey, bip39::Seed, DerivationPath};
+//!
+//! let seed: Seed = "000102030405060708090a0b0c0d0e0f".parse().unwrap();
+//! let master = Ed25519ExtendedKey::new_master(&seed).unwrap();
+//! let key = master.derive_path(&DerivationPath::parse("m/0'").unwrap()).unwrap();
+//! assert_eq!(
+//!     hex::encode(key.public_key().as_bytes()),
+//!     "8c8a13df77a28f3445213a0f432fde644acaa215fc72dcdf300d5efaa85d350c"
+//! );
+//! assert!(key.derive_path(&DerivationPath::parse("m/0").unwrap()).is_err());
+//! ```
+
+use hmac::{Hmac, Mac};
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256, Sha512};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use super::ExtendedKeyError;
+use crate::{
+    bips::{bip39::Seed, locked::Locked, ChildNumber, DerivationPath},
+    ed25519::{SigningKey, VerifyingKey},
+    error::Result,
+    types::B256,
+};
+
+/// An ed25519 key with its chain code, derived following SLIP-10.
+///
+/// As with [`ExtendedKey`](super::ExtendedKey), the private key and chain
+/// code are zeroized on drop, kept out of swap with the `mlock` feature
+/// and left out of the `Debug` output.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Ed25519ExtendedKey {
+    key: Locked<[u8; 32]>,
+    public_key: VerifyingKey,
+    parent_fingerprint: [u8; 4],
+    child_number: ChildNumber,
+    depth: u8,
+    chain_code: Locked<B256>,
+}
+
+impl Ed25519ExtendedKey {
+    /// Create the master key of a seed.
+    pub fn new_master(seed: &Seed) -> Result<Self> {
+        if ![16, 32, 64].contains(&seed.len()) {
+            return Err(ExtendedKeyError::SeedLength.into());
+        }
+
+        let mut hmac: Hmac<Sha512> =
+            Hmac::new_from_slice(b"ed25519 seed").expect("HMAC takes keys of any length");
+        hmac.update(seed.to_bytes());
+        Ok(Self::from_hmac(hmac, [0; 4], ChildNumber::from(0), 0))
+    }
+
+    /// Derive the child with a hardened child number.
+    pub fn derive_child(&self, child_number: ChildNumber) -> Result<Self> {
+        if !child_number.is_hardened() {
+            return Err(ExtendedKeyError::NonHardened.into());
+        }
+        let depth = self
+            .depth
+            .checked_add(1)
+            .ok_or(ExtendedKeyError::DepthTooLarge)?;
+
+        let mut hmac: Hmac<Sha512> =
+            Hmac::new_from_slice(self.chain_code.as_ref()).expect("HMAC takes keys of any length");
+        hmac.update(&[0]);
+        hmac.update(&*self.key);
+        hmac.update(&child_number.to_bytes());
+        Ok(Self::from_hmac(
+            hmac,
+            self.fingerprint(),
+            child_number,
+            depth,
+        ))
+    }
+
+    /// Derive the key of a path whose child numbers are all hardened.
+    pub fn derive_path(&self, path: &DerivationPath) -> Result<Self> {
+        let mut key = self.clone();
+        for child_number in path.iter() {
+            key = key.derive_child(*child_number)?;
+        }
+        Ok(key)
+    }
+
+    /// Return the signing key.
+    pub fn signing_key(&self) -> SigningKey {
+        SigningKey::from_bytes(&self.key)
+    }
+
+    /// Get the public key.
+    pub fn public_key(&self) -> &VerifyingKey {
+        &self.public_key
+    }
+
+    /// Get the parent fingerprint.
+    pub fn parent_fingerprint(&self) -> &[u8] {
+        &self.parent_fingerprint
+    }
+
+    /// Get the child number.
+    pub fn child_number(&self) -> &ChildNumber {
+        &self.child_number
+    }
+
+    /// Get the depth.
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    /// Get the chain code.
+    pub fn chain_code(&self) -> &B256 {
+        &self.chain_code
+    }
+
+    fn from_hmac(
+        hmac: Hmac<Sha512>,
+        parent_fingerprint: [u8; 4],
+        child_number: ChildNumber,
+        depth: u8,
+    ) -> Self {
+        let mut bytes = hmac.finalize().into_bytes();
+        let mut key = Box::new([0u8; 32]);
+        key.copy_from_slice(&bytes[..32]);
+        let mut chain_code = Box::new(B256::ZERO);
+        chain_code.0.copy_from_slice(&bytes[32..]);
+        bytes.as_mut_slice().zeroize();
+
+        let public_key = *SigningKey::from_bytes(&key).verifying_key();
+        Self {
+            key: Locked::new(key),
+            public_key,
+            parent_fingerprint,
+            child_number,
+            depth,
+            chain_code: Locked::new(chain_code),
+        }
+    }
+
+    /// The first four bytes of the HASH160 of the public key, serialized
+    /// with a zero byte prefix.
+    fn fingerprint(&self) -> [u8; 4] {
+        let sha = Sha256::new()
+            .chain_update([0])
+            .chain_update(self.public_key.as_bytes())
+            .finalize();
+        let hash = Ripemd160::digest(sha);
+        hash[..4].try_into().expect("4 bytes")
+    }
+}
+
+impl std::fmt::Debug for Ed25519ExtendedKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Ed25519ExtendedKey")
+            .field("key", &"<redacted>")
+            .field("public_key", &hex::encode(self.public_key.as_bytes()))
+            .field("parent_fingerprint", &hex::encode(self.parent_fingerprint))
+            .field("child_number", &self.child_number)
+            .field("depth", &self.depth)
+            .field("chain_code", &"<redacted>")
+            .finish()
+    }
+}
+
+impl ZeroizeOnDrop for Ed25519ExtendedKey {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::WalletError;
+
+    #[test]
+    fn test_vector_1() {
+        let seed: Seed = "000102030405060708090a0b0c0d0e0f".parse().unwrap();
+        let master = Ed25519ExtendedKey::new_master(&seed).unwrap();
+        assert_eq!(
+            hex::encode(*master.signing_key().to_bytes()),
+            "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"
+        );
+        assert_eq!(
+            master.chain_code().to_string(),
+            "0x90046a93de5380a72b5e45010748567d5ea02bbf6522f979e05c0d8d8ca9fffb"
+        );
+        assert_eq!(
+            hex::encode(master.public_key().as_bytes()),
+            "a4b2856bfec510abab89753fac1ac0e1112364e7d250545963f135f2a33188ed"
+        );
+
+        let child = master.derive_child(ChildNumber::hardened(0)).unwrap();
+        assert_eq!(child.parent_fingerprint(), [0xdd, 0xeb, 0xc6, 0x75]);
+        assert_eq!(child.depth(), 1);
+        assert_eq!(
+            hex::encode(*child.signing_key().to_bytes()),
+            "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3"
+        );
+        assert_eq!(
+            child.chain_code().to_string(),
+            "0x8b59aa11380b624e81507a27fedda59fea6d0b779a778918a2fd3590e16e9c69"
+        );
+    }
+
+    #[test]
+    fn test_non_hardened() {
+        let seed: Seed = "000102030405060708090a0b0c0d0e0f".parse().unwrap();
+        let master = Ed25519ExtendedKey::new_master(&seed).unwrap();
+        assert_eq!(
+            master.derive_child(ChildNumber::normal(1)),
+            Err(WalletError::ExtendedKey(ExtendedKeyError::NonHardened))
+        );
+        assert!(!format!("{:?}", master).contains("2b4be7f1"));
+    }
+}
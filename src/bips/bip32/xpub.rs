@@ -0,0 +1,311 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Watch-only extended public keys
+//!
+//! An account exported as an `xpub` derives the public keys and addresses
+//! of all of its non-hardened children, but none of their private keys. A
+//! payment processor holding only the xpub of an account can hand out a
+//! fresh deposit address per order, while the funds stay under the control
+//! of the keys kept offline.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::bips::{bip32::ExtendedKey, bip39::Mnemonic, wordlists::Language, DerivationPath};
+//!
+//! let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+//! let seed = Mnemonic::from_phrase(phrase, Language::English).unwrap().to_seed("");
+//! let account = ExtendedKey::new_master(&seed)
+//!     .unwrap()
+//!     .derive_path(&DerivationPath::parse("m/44'/60'/0'").unwrap())
+//!     .unwrap();
+//!
+//! // exported once from the wallet, parsed by the backend
+//! let xpub = account.extended_public_key().unwrap().to_string();
+//! let xpub: wallet_rust::bips::bip32::xpub::ExtendedPublicKey = xpub.parse().unwrap();
+//!
+//! let address = xpub.addresses(0, 0..10).unwrap().next().unwrap().unwrap();
+//! assert_eq!(address.to_string(), "0x9858EfFD232B4033E47d90003D41EC34EcaEda94");
+//! ```
+
+use std::ops::Range;
+
+use hmac::{Hmac, Mac};
+use k256::{ecdsa::VerifyingKey, elliptic_curve::PrimeField, FieldBytes, ProjectivePoint, Scalar};
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256, Sha512};
+
+use super::ExtendedKeyError;
+use crate::{
+    address::Address,
+    base58,
+    bips::{ChildNumber, DerivationPath},
+    error::{Result, WalletError},
+    types::B256,
+};
+
+/// Version bytes of a mainnet `xpub`.
+const VERSION: [u8; 4] = [0x04, 0x88, 0xb2, 0x1e];
+
+/// A secp256k1 public key with its chain code, which derives the public
+/// keys of its non-hardened children.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ExtendedPublicKey {
+    key: VerifyingKey,
+    chain_code: B256,
+    parent_fingerprint: [u8; 4],
+    child_number: ChildNumber,
+    depth: u8,
+}
+
+impl ExtendedPublicKey {
+    /// Create new instance of ExtendedPublicKey.
+    pub fn new(
+        key: VerifyingKey,
+        chain_code: B256,
+        parent_fingerprint: [u8; 4],
+        child_number: ChildNumber,
+        depth: u8,
+    ) -> Self {
+        Self {
+            key,
+            chain_code,
+            parent_fingerprint,
+            child_number,
+            depth,
+        }
+    }
+
+    /// Derive the public key of a non-hardened child.
+    pub fn derive_child(&self, child_number: ChildNumber) -> Result<Self> {
+        if child_number.is_hardened() {
+            return Err(ExtendedKeyError::Hardened.into());
+        }
+        let depth = self
+            .depth
+            .checked_add(1)
+            .ok_or(ExtendedKeyError::DepthTooLarge)?;
+
+        let mut hmac: Hmac<Sha512> =
+            Hmac::new_from_slice(self.chain_code.as_ref()).expect("HMAC takes keys of any length");
+        hmac.update(self.key.to_encoded_point(true).as_bytes());
+        hmac.update(&child_number.to_bytes());
+        let result = hmac.finalize().into_bytes();
+        let (tweak, chain_code) = result.split_at(32);
+
+        // BIP32 skips to the next index in the unlikely case the tweak is
+        // not a scalar or the child is the point at infinity
+        let tweak = Option::<Scalar>::from(Scalar::from_repr(*FieldBytes::from_slice(tweak)))
+            .ok_or_else(|| WalletError::crypto("invalid child key"))?;
+        let point =
+            ProjectivePoint::GENERATOR * tweak + ProjectivePoint::from(*self.key.as_affine());
+        let key = VerifyingKey::from_affine(point.into()).map_err(WalletError::crypto)?;
+
+        Ok(Self::new(
+            key,
+            B256::from_slice(chain_code)?,
+            fingerprint(&self.key),
+            child_number,
+            depth,
+        ))
+    }
+
+    /// Derive the public key at the given path, which must not contain any
+    /// hardened child.
+    pub fn derive_path(&self, path: &DerivationPath) -> Result<Self> {
+        let mut key = self.clone();
+        for child_number in path.iter() {
+            key = key.derive_child(*child_number)?;
+        }
+        Ok(key)
+    }
+
+    /// Return the addresses of the account this key was exported from, at
+    /// the given indices of the external chain, `change = 0`, or of the
+    /// internal chain, `change = 1`.
+    pub fn addresses(
+        &self,
+        change: u32,
+        indices: Range<u32>,
+    ) -> Result<impl Iterator<Item = Result<Address>>> {
+        let chain = self.derive_child(ChildNumber::try_normal(change)?)?;
+        Ok(indices.map(move |index| {
+            let child = chain.derive_child(ChildNumber::try_normal(index)?)?;
+            Ok(child.address())
+        }))
+    }
+
+    /// Get the public key.
+    pub fn public_key(&self) -> &VerifyingKey {
+        &self.key
+    }
+
+    /// Get the address of the public key.
+    pub fn address(&self) -> Address {
+        Address::from(&self.key)
+    }
+
+    /// Get the parent fingerprint.
+    pub fn parent_fingerprint(&self) -> &[u8] {
+        &self.parent_fingerprint
+    }
+
+    /// Get the child number.
+    pub fn child_number(&self) -> &ChildNumber {
+        &self.child_number
+    }
+
+    /// Get the depth.
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    /// Get the chain code.
+    pub fn chain_code(&self) -> &B256 {
+        &self.chain_code
+    }
+}
+
+/// The first four bytes of the HASH160 of a compressed public key.
+pub(crate) fn fingerprint(key: &VerifyingKey) -> [u8; 4] {
+    let sha = Sha256::digest(key.to_encoded_point(true).as_bytes());
+    Ripemd160::digest(sha)[..4].try_into().expect("4 bytes")
+}
+
+impl std::fmt::Debug for ExtendedPublicKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "ExtendedPublicKey({})", self)
+    }
+}
+
+impl std::fmt::Display for ExtendedPublicKey {
+    /// Serialize as an `xpub`, in Base58Check.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut data = Vec::with_capacity(78);
+        data.extend_from_slice(&VERSION);
+        data.push(self.depth);
+        data.extend_from_slice(&self.parent_fingerprint);
+        data.extend_from_slice(&self.child_number.to_bytes());
+        data.extend_from_slice(self.chain_code.as_ref());
+        data.extend_from_slice(self.key.to_encoded_point(true).as_bytes());
+        write!(f, "{}", base58::encode_check(data))
+    }
+}
+
+impl std::str::FromStr for ExtendedPublicKey {
+    type Err = WalletError;
+
+    /// Parse an `xpub`.
+    fn from_str(s: &str) -> Result<Self> {
+        let data = base58::decode_check(s)?;
+        if data.len() != 78 || data[..4] != VERSION {
+            return Err(ExtendedKeyError::InvalidFormat.into());
+        }
+
+        let depth = data[4];
+        let parent_fingerprint: [u8; 4] = data[5..9].try_into().expect("4 bytes");
+        let child_number =
+            ChildNumber::from(u32::from_be_bytes(data[9..13].try_into().expect("4 bytes")));
+        // a master key has no parent
+        if depth == 0 && (parent_fingerprint != [0; 4] || u32::from(child_number) != 0) {
+            return Err(ExtendedKeyError::InvalidFormat.into());
+        }
+        if !matches!(data[45], 2 | 3) {
+            return Err(ExtendedKeyError::InvalidFormat.into());
+        }
+        let key = VerifyingKey::from_sec1_bytes(&data[45..])
+            .map_err(|_| ExtendedKeyError::InvalidFormat)?;
+
+        Ok(Self::new(
+            key,
+            B256::from_slice(&data[13..45])?,
+            parent_fingerprint,
+            child_number,
+            depth,
+        ))
+    }
+}
+
+#[cfg(feature = "serde")]
+crate::types::serde_string!(ExtendedPublicKey);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bips::{bip32::ExtendedKey, bip39::Seed};
+
+    fn master() -> ExtendedKey {
+        let seed: Seed = "000102030405060708090a0b0c0d0e0f".parse().unwrap();
+        ExtendedKey::new_master(&seed).unwrap()
+    }
+
+    #[test]
+    fn test_bip32_vector() {
+        let master = master();
+        let xpub = master.extended_public_key().unwrap();
+        assert_eq!(xpub.to_string(), "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8");
+
+        let account = master.derive_child(ChildNumber::hardened(0)).unwrap();
+        let xpub = account.extended_public_key().unwrap();
+        assert_eq!(xpub.to_string(), "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw");
+        assert_eq!(xpub.to_string().parse::<ExtendedPublicKey>().unwrap(), xpub);
+
+        // public derivation matches the private one
+        let child = xpub.derive_child(ChildNumber::normal(1)).unwrap();
+        assert_eq!(child.to_string(), "xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ");
+        assert_eq!(
+            child,
+            account
+                .derive_child(ChildNumber::normal(1))
+                .unwrap()
+                .extended_public_key()
+                .unwrap()
+        );
+
+        assert_eq!(
+            xpub.derive_child(ChildNumber::hardened(1)).unwrap_err(),
+            ExtendedKeyError::Hardened.into()
+        );
+    }
+
+    #[test]
+    fn test_addresses() {
+        let account = master()
+            .derive_path(&DerivationPath::parse("m/44'/60'/0'").unwrap())
+            .unwrap();
+        let xpub = account.extended_public_key().unwrap();
+
+        let addresses = xpub
+            .addresses(1, 5..8)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        for (index, address) in (5..8).zip(addresses) {
+            let path = DerivationPath::parse(&format!("m/1/{}", index)).unwrap();
+            let key = account.derive_path(&path).unwrap();
+            assert_eq!(address, Address::from_public_key(key.public_key()).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        // BIP32 test vector 5: a private key version and a master key with
+        // a parent fingerprint
+        for xpub in [
+            "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi",
+            "xpub661no6RGEX3uJkY4bNnPcw4URcQTrSibUZ4NqJEw5eBkv7ovTwgiT91XX27VbEXGENhYRCf7hyEbWrR3FewATdCEebj6znwMfQkhRYHRLpJ",
+        ] {
+            assert!(xpub.parse::<ExtendedPublicKey>().is_err());
+        }
+    }
+}
@@ -0,0 +1,218 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # BIP38 Encrypted Private Keys
+//!
+//! Export a single private key encrypted with a passphrase, as defined in
+//! [BIP38](https://github.com/bitcoin/bips/blob/master/bip-0038.mediawiki),
+//! for paper backups and cold storage where a whole keystore file is
+//! overkill. The key is stretched with scrypt and encrypted with AES-256,
+//! and the result is a 58 character Base58Check string starting with `6P`.
+//!
+//! The passphrase check hashes the Bitcoin address of the key as specified,
+//! so the strings are also accepted by other BIP38 tools. Keys encrypted
+//! with an EC multiplied intermediate code are not supported.
+//!
+//! ## Example
+//! ```rust
+//! use laron_crypto::PrivateKey;
+//! use wallet_rust::bips::bip38;
+//!
+//! let key = PrivateKey::from_bytes(&[0x46; 32]).unwrap();
+//! let encrypted = bip38::encrypt(&key, "correct horse", true).unwrap();
+//! assert!(encrypted.starts_with("6P"));
+//!
+//! let decrypted = bip38::decrypt(&encrypted, "correct horse").unwrap();
+//! assert_eq!(decrypted.to_bytes(), key.to_bytes());
+//! assert!(bip38::decrypt(&encrypted, "wrong horse").is_err());
+//! ```
+
+use aes::{
+    cipher::{generic_array::GenericArray, BlockDecrypt, BlockEncrypt, KeyInit},
+    Aes256,
+};
+use k256::ecdsa::SigningKey;
+use laron_crypto::PrivateKey;
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+use unicode_normalization::UnicodeNormalization;
+use zeroize::Zeroizing;
+
+use crate::{
+    base58,
+    error::{Result, WalletError},
+};
+
+const PREFIX: [u8; 2] = [0x01, 0x42];
+const PREFIX_EC_MULTIPLY: [u8; 2] = [0x01, 0x43];
+const FLAG_NO_EC_MULTIPLY: u8 = 0xc0;
+const FLAG_COMPRESSED: u8 = 0x20;
+const PAYLOAD_LENGTH: usize = 39;
+
+/// scrypt parameters of BIP38: `n = 2^14`, `r = 8` and `p = 8`.
+const SCRYPT_LOG_N: u8 = 14;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 8;
+
+/// Error returned when decrypting a BIP38 key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bip38Error {
+    /// The string is not a BIP38 encrypted key.
+    InvalidFormat,
+    /// The key was encrypted with an EC multiplied intermediate code.
+    EcMultiplyUnsupported,
+    /// The passphrase is wrong.
+    InvalidPassphrase,
+}
+
+impl std::fmt::Display for Bip38Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Bip38Error::InvalidFormat => write!(f, "Invalid BIP38 encrypted key"),
+            Bip38Error::EcMultiplyUnsupported => {
+                write!(f, "EC multiplied BIP38 keys are not supported")
+            }
+            Bip38Error::InvalidPassphrase => write!(f, "Invalid BIP38 passphrase"),
+        }
+    }
+}
+
+impl std::error::Error for Bip38Error {}
+
+/// Encrypt a private key with a passphrase. `compressed` selects the
+/// public key encoding of the Bitcoin address used in the passphrase check,
+/// wallets importing the key into Bitcoin use it as well.
+pub fn encrypt(key: &PrivateKey, passphrase: &str, compressed: bool) -> Result<String> {
+    let secret = Zeroizing::new(key.to_bytes());
+    let address_hash = address_hash(secret.as_slice(), compressed)?;
+    let derived = derive(passphrase, &address_hash);
+
+    let cipher = Aes256::new(GenericArray::from_slice(&derived[32..]));
+    let mut payload = Vec::with_capacity(PAYLOAD_LENGTH);
+    payload.extend_from_slice(&PREFIX);
+    payload.push(FLAG_NO_EC_MULTIPLY | if compressed { FLAG_COMPRESSED } else { 0 });
+    payload.extend_from_slice(&address_hash);
+    for half in 0..2 {
+        let range = half * 16..half * 16 + 16;
+        let mut block = GenericArray::clone_from_slice(&secret[range.clone()]);
+        for (b, d) in block.iter_mut().zip(&derived[range]) {
+            *b ^= d;
+        }
+        cipher.encrypt_block(&mut block);
+        payload.extend_from_slice(&block);
+    }
+    Ok(base58::encode_check(payload))
+}
+
+/// Decrypt a private key encrypted with [`encrypt`] or another BIP38
+/// implementation.
+pub fn decrypt(encrypted: &str, passphrase: &str) -> Result<PrivateKey> {
+    let payload = base58::decode_check(encrypted.trim())?;
+    if payload.len() != PAYLOAD_LENGTH {
+        return Err(Bip38Error::InvalidFormat.into());
+    }
+    if payload[..2] == PREFIX_EC_MULTIPLY {
+        return Err(Bip38Error::EcMultiplyUnsupported.into());
+    }
+    let flag = payload[2];
+    if payload[..2] != PREFIX || flag & !FLAG_COMPRESSED != FLAG_NO_EC_MULTIPLY {
+        return Err(Bip38Error::InvalidFormat.into());
+    }
+    let compressed = flag & FLAG_COMPRESSED != 0;
+    let address_hash = &payload[3..7];
+    let derived = derive(passphrase, address_hash);
+
+    let cipher = Aes256::new(GenericArray::from_slice(&derived[32..]));
+    let mut secret = Zeroizing::new([0u8; 32]);
+    for half in 0..2 {
+        let range = half * 16..half * 16 + 16;
+        let mut block = GenericArray::clone_from_slice(&payload[7 + range.start..7 + range.end]);
+        cipher.decrypt_block(&mut block);
+        for ((s, b), d) in secret[range.clone()]
+            .iter_mut()
+            .zip(block)
+            .zip(&derived[range])
+        {
+            *s = b ^ d;
+        }
+    }
+
+    // a wrong passphrase decrypts to a different key, or to no key at all
+    match self::address_hash(&*secret, compressed) {
+        Ok(hash) if hash == address_hash => {}
+        _ => return Err(Bip38Error::InvalidPassphrase.into()),
+    }
+    PrivateKey::from_bytes(&*secret).map_err(WalletError::crypto)
+}
+
+/// Stretch the NFC normalized passphrase, salted with the address hash.
+fn derive(passphrase: &str, address_hash: &[u8]) -> Zeroizing<[u8; 64]> {
+    let passphrase = Zeroizing::new(passphrase.nfc().collect::<String>());
+    let params = scrypt::Params::new(SCRYPT_LOG_N, SCRYPT_R, SCRYPT_P, 64)
+        .expect("BIP38 scrypt parameters are valid");
+    let mut derived = Zeroizing::new([0u8; 64]);
+    scrypt::scrypt(passphrase.as_bytes(), address_hash, &params, &mut *derived)
+        .expect("the output length is valid");
+    derived
+}
+
+/// Return the first four bytes of the double SHA-256 of the Bitcoin
+/// address of the key.
+fn address_hash(secret: &[u8], compressed: bool) -> Result<[u8; 4]> {
+    let key = SigningKey::from_slice(secret)?;
+    let point = key.verifying_key().to_encoded_point(compressed);
+    let mut payload = vec![0x00];
+    payload.extend(Ripemd160::digest(Sha256::digest(point.as_bytes())));
+    let address = base58::encode_check(payload);
+
+    let hash = Sha256::digest(Sha256::digest(address));
+    Ok([hash[0], hash[1], hash[2], hash[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "cbf4b9f70470856bb4f40f80b87edb90865997ffee6df315ab166d713af433a5";
+
+    #[test]
+    fn test_vectors() {
+        // test vectors of BIP38 without EC multiplication
+        let key = PrivateKey::from_bytes(&hex::decode(SECRET).unwrap()).unwrap();
+        let uncompressed = "6PRVWUbkzzsbcVac2qwfssoUJAN1Xhrg6bNk8J7Nzm5H7kxEbn2Nh2ZoGg";
+        let compressed = "6PYNKZ1EAgYgmQfmNVamxyXVWHzK5s6DGhwP4J5o44cvXdoY7sRzhtpUeo";
+
+        assert_eq!(
+            encrypt(&key, "TestingOneTwoThree", false).unwrap(),
+            uncompressed
+        );
+        assert_eq!(
+            encrypt(&key, "TestingOneTwoThree", true).unwrap(),
+            compressed
+        );
+        for encrypted in [uncompressed, compressed] {
+            let decrypted = decrypt(encrypted, "TestingOneTwoThree").unwrap();
+            assert_eq!(hex::encode(decrypted.to_bytes()), SECRET);
+        }
+
+        assert_eq!(
+            decrypt(compressed, "TestingOneTwoFour").unwrap_err(),
+            Bip38Error::InvalidPassphrase.into()
+        );
+        assert!(decrypt(
+            "6PYNKZ1EAgYgmQfmNVamxyXVWHzK5s6DGhwP4J5o44cvXdoY7sRzhtpUep",
+            ""
+        )
+        .is_err());
+    }
+}
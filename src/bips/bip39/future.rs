@@ -0,0 +1,194 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc,
+};
+
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+use tokio::task::JoinHandle;
+use zeroize::{Zeroize, Zeroizing};
+
+use super::{seed::kdf_inputs, Mnemonic, Seed, SeedKdf};
+use crate::error::Result;
+
+/// Iterations of PBKDF2 of BIP39.
+const ROUNDS: u32 = 2048;
+
+/// Progress of a seed derivation running in the background, shared with
+/// the task deriving it.
+#[derive(Debug, Clone, Default)]
+pub struct Progress(Arc<AtomicU32>);
+
+impl Progress {
+    /// Return the fraction of the derivation done, from 0 to 1.
+    pub fn fraction(&self) -> f32 {
+        self.0.load(Ordering::Relaxed) as f32 / ROUNDS as f32
+    }
+
+    /// Return whether the seed is derived.
+    pub fn is_done(&self) -> bool {
+        self.0.load(Ordering::Relaxed) == ROUNDS
+    }
+}
+
+impl Mnemonic {
+    /// Return the seed of the mnemonic, derived on the blocking pool of
+    /// tokio so async services do not block their executor for the 2048
+    /// rounds of PBKDF2, with a handle to follow the derivation, e.g. to
+    /// draw a spinner while the task is awaited. See
+    /// [`Seed::new_with_params_async`] for other derivation functions.
+    ///
+    /// It has to be called from within a tokio runtime.
+    pub fn to_seed_async(&self, passphrase: &str) -> (Progress, JoinHandle<Seed>) {
+        let progress = Progress::default();
+        let task = spawn_seed(self, passphrase, progress.clone());
+        (progress, task)
+    }
+}
+
+impl Seed {
+    /// Create a new Seed as [`Seed::new`] does, on the blocking pool of
+    /// tokio so the executor is not blocked while PBKDF2 runs.
+    pub async fn new_async(mnemonic: &Mnemonic, passphrase: &str) -> Self {
+        let (mnemonic, passphrase) = (mnemonic.clone(), Zeroizing::new(passphrase.to_string()));
+        blocking(move || Seed::new(&mnemonic, &passphrase)).await
+    }
+
+    /// Create a new Seed as [`Seed::new_with_params`] does, on the blocking
+    /// pool of tokio. Hardened parameters can take seconds to derive.
+    pub async fn new_with_params_async(
+        mnemonic: &Mnemonic,
+        passphrase: &str,
+        kdf: SeedKdf,
+    ) -> Result<Self> {
+        let (mnemonic, passphrase) = (mnemonic.clone(), Zeroizing::new(passphrase.to_string()));
+        blocking(move || Seed::new_with_params(&mnemonic, &passphrase, kdf)).await
+    }
+}
+
+/// Derive the seed of a mnemonic on the blocking pool, counting the rounds
+/// done in `progress`.
+fn spawn_seed(mnemonic: &Mnemonic, passphrase: &str, progress: Progress) -> JoinHandle<Seed> {
+    let (phrase, salt) = kdf_inputs(mnemonic, passphrase);
+    tokio::task::spawn_blocking(move || pbkdf2(phrase.as_bytes(), salt.as_bytes(), &progress.0))
+}
+
+/// PBKDF2-HMAC-SHA512 of a single block, the whole BIP39 seed, counting the
+/// rounds in `rounds`.
+fn pbkdf2(password: &[u8], salt: &[u8], rounds: &AtomicU32) -> Seed {
+    let mac = Hmac::<Sha512>::new_from_slice(password).expect("HMAC takes keys of any size");
+    let mut u = mac
+        .clone()
+        .chain_update(salt)
+        .chain_update(1u32.to_be_bytes())
+        .finalize()
+        .into_bytes();
+    let mut t = u;
+
+    for round in 2..=ROUNDS {
+        u = mac.clone().chain_update(u).finalize().into_bytes();
+        for (t, u) in t.iter_mut().zip(u) {
+            *t ^= u;
+        }
+        if round % 64 == 0 {
+            rounds.store(round, Ordering::Relaxed);
+        }
+    }
+
+    let seed = Seed::from(t.to_vec());
+    t.zeroize();
+    u.zeroize();
+    seed
+}
+
+/// Run `f` with [`tokio::task::spawn_blocking`], resuming its panic if it
+/// panicked.
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+    match tokio::task::spawn_blocking(f).await {
+        Ok(value) => value,
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bips::wordlists::Language;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn test_to_seed_async() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+
+        let (progress, seed) = block_on(async {
+            let (progress, task) = mnemonic.to_seed_async("TREZOR");
+            (progress, task.await.unwrap())
+        });
+        assert!(progress.is_done());
+        assert_eq!(seed, mnemonic.to_seed("TREZOR"));
+
+        let seed = block_on(Seed::new_async(&mnemonic, "TREZOR"));
+        assert_eq!(seed, mnemonic.to_seed("TREZOR"));
+
+        let kdf = SeedKdf::Pbkdf2 { rounds: 4096 };
+        let seed = block_on(Seed::new_with_params_async(&mnemonic, "TREZOR", kdf)).unwrap();
+        assert_eq!(
+            seed,
+            Seed::new_with_params(&mnemonic, "TREZOR", kdf).unwrap()
+        );
+        assert!(block_on(Seed::new_with_params_async(
+            &mnemonic,
+            "",
+            SeedKdf::Pbkdf2 { rounds: 0 }
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_progress() {
+        let phrase = "legal winner thank year wave sausage worth useful legal winner thank yellow";
+        let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+
+        let progress = Progress::default();
+        assert_eq!(progress.fraction(), 0.0);
+        assert!(!progress.is_done());
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let task = {
+            let _runtime = runtime.enter();
+            spawn_seed(&mnemonic, "", progress.clone())
+        };
+        let mut fractions = vec![progress.fraction()];
+        while !task.is_finished() {
+            fractions.push(progress.fraction());
+        }
+        let seed = runtime.block_on(task).unwrap();
+
+        assert!(fractions.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(progress.fraction(), 1.0);
+        assert!(progress.is_done());
+        assert_eq!(seed, mnemonic.to_seed(""));
+    }
+}
@@ -11,19 +11,36 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use horror::{Error, Result};
-use rand::Rng;
+use rand::{CryptoRng, RngCore};
 use sha2::Digest;
 use unicode_normalization::UnicodeNormalization;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
-use crate::bips::wordlists::Language;
+use crate::{
+    bips::wordlists::{Language, WordListError},
+    error::{Result, WalletError},
+};
 
 use super::Seed;
 
+/// Error returned when creating a mnemonic.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub(crate) enum MnemonicError {
+pub enum MnemonicError {
+    /// The number of words, or entropy size, is not supported by BIP39.
     InvalidMnemonicLength(usize),
+    /// The checksum of the mnemonic does not match its entropy.
     InvalidChecksum,
+    /// A dice roll is not between 1 and 6.
+    InvalidDiceRoll(u8),
+    /// A coin flip is not `H`, `T`, `1` or `0`.
+    InvalidCoinFlip(char),
+    /// The rolls or flips carry fewer bits of entropy than the mnemonic.
+    NotEnoughEntropy {
+        /// The bits of entropy of the mnemonic.
+        needed: usize,
+        /// The bits of entropy provided.
+        provided: usize,
+    },
 }
 
 impl std::fmt::Display for MnemonicError {
@@ -33,12 +50,26 @@ impl std::fmt::Display for MnemonicError {
                 write!(f, "Invalid mnemonic length: {}", len)
             }
             MnemonicError::InvalidChecksum => write!(f, "Invalid checksum"),
+            MnemonicError::InvalidDiceRoll(roll) => write!(f, "Invalid dice roll: {}", roll),
+            MnemonicError::InvalidCoinFlip(flip) => write!(f, "Invalid coin flip: {:?}", flip),
+            MnemonicError::NotEnoughEntropy { needed, provided } => write!(
+                f,
+                "Not enough entropy: {} bits needed, {} provided",
+                needed, provided
+            ),
         }
     }
 }
 
 impl std::error::Error for MnemonicError {}
 
+/// Code words of the NATO phonetic alphabet, from `a` to `z`.
+const NATO_ALPHABET: [&str; 26] = [
+    "Alfa", "Bravo", "Charlie", "Delta", "Echo", "Foxtrot", "Golf", "Hotel", "India", "Juliett",
+    "Kilo", "Lima", "Mike", "November", "Oscar", "Papa", "Quebec", "Romeo", "Sierra", "Tango",
+    "Uniform", "Victor", "Whiskey", "X-ray", "Yankee", "Zulu",
+];
+
 /// The type of mnemonic to generate.
 /// The number of words in the mnemonic is determined by the type.
 /// The number of bits of entropy is also determined by the type.
@@ -107,7 +138,20 @@ impl MnemonicType {
 /// A BIP39 mnemonic.
 /// A mnemonic is a sequence of words that can be used to generate a seed.
 /// It is defined in [BIP39](https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki).
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// With the `serde-secrets` feature it serializes as its phrase and language,
+/// and the phrase is validated when deserializing. The plain `serde` feature
+/// leaves it out so a mnemonic cannot end up in serialized data by accident.
+///
+/// The entropy and phrase are zeroized when the mnemonic is dropped. Neither
+/// is part of its `Debug` or `Display` output, [`Mnemonic::phrase`] has to
+/// be called to show the phrase.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde-secrets",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "MnemonicRepr", try_from = "MnemonicRepr")
+)]
 pub struct Mnemonic {
     language: Language,
     entropy: Vec<u8>,
@@ -117,8 +161,18 @@ pub struct Mnemonic {
 impl Mnemonic {
     /// Create a new `Mnemonic` by the given type and by the given language.
     pub fn new(ty: MnemonicType, language: Language) -> Self {
-        let mut bytes = vec![0u8; ty.entropy_bits() / 8];
-        rand::thread_rng().fill(&mut bytes[..]);
+        Self::new_with_rng(ty, language, &mut rand::thread_rng())
+    }
+
+    /// Create a new `Mnemonic` whose entropy is drawn from `rng`, such as
+    /// a seeded RNG in tests or one backed by a hardware module.
+    pub fn new_with_rng(
+        ty: MnemonicType,
+        language: Language,
+        rng: &mut (impl CryptoRng + RngCore),
+    ) -> Self {
+        let mut bytes = Zeroizing::new(vec![0u8; ty.entropy_bits() / 8]);
+        rng.fill_bytes(&mut bytes[..]);
         Self::from_entropy_unchecked(&bytes, language)
     }
 
@@ -130,27 +184,87 @@ impl Mnemonic {
         Ok(Self::from_entropy_unchecked(entropy, language))
     }
 
+    /// Create a new `Mnemonic` from rolls of a six-sided die, for entropy
+    /// generated away from any computer.
+    ///
+    /// The rolls are whitened by hashing their digits with SHA-256, as
+    /// Coldcard does, so a biased die only lowers the entropy per roll. At
+    /// least as many rolls as carry the entropy of the type at 2.58 bits
+    /// per roll are required, e.g. 50 for 12 words and 100 for 24 words.
+    pub fn from_dice_rolls(rolls: &[u8], ty: MnemonicType, language: Language) -> Result<Self> {
+        if let Some(roll) = rolls.iter().find(|roll| !(1..=6).contains(*roll)) {
+            return Err(MnemonicError::InvalidDiceRoll(*roll).into());
+        }
+
+        let provided = (rolls.len() as f64 * 6f64.log2()) as usize;
+        if provided < ty.entropy_bits() {
+            return Err(MnemonicError::NotEnoughEntropy {
+                needed: ty.entropy_bits(),
+                provided,
+            }
+            .into());
+        }
+
+        let digits = Zeroizing::new(rolls.iter().map(|roll| b'0' + roll).collect::<Vec<_>>());
+        let hash = Zeroizing::new(sha2::Sha256::digest(&*digits));
+        Ok(Self::from_entropy_unchecked(
+            &hash[..ty.entropy_bits() / 8],
+            language,
+        ))
+    }
+
+    /// Create a new `Mnemonic` from coin flips, `H` or `1` for heads and
+    /// `T` or `0` for tails. Whitespace is ignored.
+    ///
+    /// A biased coin is corrected with the von Neumann extractor: flips are
+    /// read in pairs, heads-tails gives a 1 bit, tails-heads a 0 bit, and
+    /// equal pairs are discarded. Expect to need about four flips per bit.
+    pub fn from_coin_flips(flips: &str, ty: MnemonicType, language: Language) -> Result<Self> {
+        let flips = Zeroizing::new(
+            flips
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .map(|c| match c.to_ascii_uppercase() {
+                    'H' | '1' => Ok(true),
+                    'T' | '0' => Ok(false),
+                    _ => Err(MnemonicError::InvalidCoinFlip(c)),
+                })
+                .collect::<std::result::Result<Vec<_>, _>>()?,
+        );
+
+        let bits = Zeroizing::new(
+            flips
+                .chunks_exact(2)
+                .filter(|pair| pair[0] != pair[1])
+                .map(|pair| pair[0])
+                .collect::<Vec<_>>(),
+        );
+        if bits.len() < ty.entropy_bits() {
+            return Err(MnemonicError::NotEnoughEntropy {
+                needed: ty.entropy_bits(),
+                provided: bits.len(),
+            }
+            .into());
+        }
+
+        let entropy = Zeroizing::new(
+            bits[..ty.entropy_bits()]
+                .chunks(8)
+                .map(|byte| byte.iter().fold(0u8, |acc, bit| (acc << 1) | *bit as u8))
+                .collect::<Vec<_>>(),
+        );
+        Ok(Self::from_entropy_unchecked(&entropy, language))
+    }
+
     fn from_entropy_unchecked(ent: &[u8], language: Language) -> Self {
         let ent = ent.to_vec();
         let wordlist = language.wordlist();
 
-        let checksum = sha2::Sha256::digest(&ent)[0];
-
-        let phrase = ent
-            .iter()
-            .chain(Some(&checksum))
-            .flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1))
-            .collect::<Vec<_>>()
-            .chunks(11)
-            .take_while(|chunk| chunk.len() == 11)
-            .map(|chunk| {
-                chunk
-                    .iter()
-                    .fold(0u16, |acc, bit| (acc << 1) | (*bit as u16))
-            })
+        let phrase = entropy_to_indexes(&ent)
+            .into_iter()
             .map(|idx| wordlist.get(idx.into()).unwrap())
             .collect::<Vec<_>>()
-            .join(" ");
+            .join(language.delimiter());
 
         Self {
             language,
@@ -159,32 +273,101 @@ impl Mnemonic {
         }
     }
 
+    /// Create a new `Mnemonic` from the indexes of its words in the wordlist
+    /// of the given language.
+    pub fn from_indexes(indexes: &[u16], language: Language) -> Result<Self> {
+        if indexes.iter().any(|idx| *idx >= 2048) {
+            return Err(WordListError::InvalidWord.into());
+        }
+
+        let indexes = indexes.iter().map(|idx| *idx as usize).collect::<Vec<_>>();
+        let ent = Self::indexes_to_entropy(&indexes)?;
+        Ok(Self::from_entropy_unchecked(&ent, language))
+    }
+
     /// Create a new `Mnemonic` from the given phrase and by the given language.
+    ///
+    /// French and Spanish words may be typed without their accents, and
+    /// Japanese words separated by either ASCII or ideographic spaces. The
+    /// mnemonic holds the words as spelled in the wordlist, joined by
+    /// [`Language::delimiter`].
     pub fn from_phrase(phrase: &str, language: Language) -> Result<Self> {
-        let phrase = phrase.nfkd().collect::<String>();
-        let ent = Self::phrase_to_entropy(&phrase, language)?;
-
-        Ok(Self {
-            language,
-            entropy: ent,
-            phrase,
-        })
+        let phrase = Zeroizing::new(phrase.nfkd().collect::<String>());
+        let ent = Zeroizing::new(Self::phrase_to_entropy(&phrase, language, false)?);
+        Ok(Self::from_entropy_unchecked(&ent, language))
     }
 
     /// Validate the given phrase.
     pub fn validate_phrase(phrase: &str, language: Language) -> Result<()> {
         let phrase = phrase.nfkd().collect::<String>();
-        Self::phrase_to_entropy(&phrase, language)?;
+        Self::phrase_to_entropy(&phrase, language, false)?;
         Ok(())
     }
 
-    fn phrase_to_entropy(phrase: &str, language: Language) -> Result<Vec<u8>> {
+    /// Return every word that completes the first words of a phrase into a
+    /// valid mnemonic, for users who picked all but the last word by hand.
+    ///
+    /// The last word holds both entropy and the checksum, so only a few of
+    /// the 2048 words are valid: 128 after 11 words, 8 after 23 words.
+    pub fn valid_final_words(words: &[&str], language: Language) -> Result<Vec<&'static str>> {
+        MnemonicType::from_word_count(words.len() + 1)?;
+
+        let wordmap = language.wordmap();
+        let mut indexes = words
+            .iter()
+            .map(|word| wordmap.get_index(&word.nfkd().collect::<String>()))
+            .collect::<Result<Vec<_>>>()?;
+
+        let wordlist = language.wordlist();
+        let mut valid = Vec::new();
+        indexes.push(0);
+        for index in 0..2048 {
+            *indexes.last_mut().unwrap() = index;
+            if let Ok(mut entropy) = Self::indexes_to_entropy(&indexes) {
+                entropy.zeroize();
+                valid.push(wordlist.get(index)?);
+            }
+        }
+        indexes.zeroize();
+        Ok(valid)
+    }
+
+    /// Create a new `Mnemonic` from a phrase whose words may be cut to
+    /// their first four letters, the way many metal backups and hardware
+    /// wallet cards are written. The mnemonic holds the full words.
+    pub fn from_truncated_phrase(phrase: &str, language: Language) -> Result<Self> {
+        let phrase = Zeroizing::new(phrase.nfkd().collect::<String>());
+        let ent = Zeroizing::new(Self::phrase_to_entropy(&phrase, language, true)?);
+        Ok(Self::from_entropy_unchecked(&ent, language))
+    }
+
+    fn phrase_to_entropy(phrase: &str, language: Language, truncated: bool) -> Result<Vec<u8>> {
         let wordmap = language.wordmap();
 
-        let bits = phrase
+        let indexes = phrase
             .split_whitespace()
-            .map(|word| wordmap.get_index(word))
-            .collect::<Result<Vec<_>>>()?
+            .enumerate()
+            .map(|(position, word)| {
+                let index = if truncated {
+                    wordmap.get_index_by_prefix(word)
+                } else {
+                    wordmap.get_index(word)
+                };
+                index.map_err(|_| {
+                    WordListError::UnknownWord {
+                        position,
+                        suggestions: language.wordlist().suggest(word, 2),
+                    }
+                    .into()
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Self::indexes_to_entropy(&indexes)
+    }
+
+    fn indexes_to_entropy(indexes: &[usize]) -> Result<Vec<u8>> {
+        let bits = indexes
             .iter()
             .flat_map(|idx| (0..11).rev().map(move |i| (idx >> i) & 1))
             .collect::<Vec<_>>();
@@ -218,6 +401,61 @@ impl Mnemonic {
         &self.phrase
     }
 
+    /// Return the indexes of the words of the mnemonic in the wordlist.
+    pub fn indexes(&self) -> Vec<u16> {
+        entropy_to_indexes(&self.entropy)
+    }
+
+    /// Return every word of the phrase spelled out in the NATO phonetic
+    /// alphabet, e.g. `"zoo"` becomes `"Zulu Oscar Oscar"`, for dictating a
+    /// backup over a voice channel. Characters outside of `a` ~ `z` are kept
+    /// as they are.
+    pub fn phonetic(&self) -> Vec<String> {
+        self.phrase
+            .split_whitespace()
+            .map(|word| {
+                word.chars()
+                    .map(|c| match c.to_ascii_lowercase() {
+                        c @ 'a'..='z' => NATO_ALPHABET[(c as u8 - b'a') as usize].to_string(),
+                        c => c.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect()
+    }
+
+    /// Return the words of the phrase split into groups of the given size.
+    pub fn chunks(&self, size: usize) -> Vec<Vec<&str>> {
+        self.phrase
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .chunks(size.max(1))
+            .map(|chunk| chunk.to_vec())
+            .collect()
+    }
+
+    /// Return the phrase with one group of the given size per line and every
+    /// word prefixed by its position, e.g. `1. abandon 2. abandon 3. abandon`.
+    pub fn to_chunked_string(&self, size: usize) -> String {
+        let mut position = 0;
+
+        self.chunks(size)
+            .into_iter()
+            .map(|chunk| {
+                chunk
+                    .into_iter()
+                    .map(|word| {
+                        position += 1;
+                        format!("{}. {}", position, word)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
     /// Return the language of the mnemonic.
     pub fn language(&self) -> Language {
         self.language
@@ -239,14 +477,77 @@ impl Mnemonic {
     }
 }
 
+impl Drop for Mnemonic {
+    fn drop(&mut self) {
+        self.entropy.zeroize();
+        self.phrase.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for Mnemonic {}
+
+fn entropy_to_indexes(ent: &[u8]) -> Vec<u16> {
+    let checksum = sha2::Sha256::digest(ent)[0];
+
+    ent.iter()
+        .chain(Some(&checksum))
+        .flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1))
+        .collect::<Vec<_>>()
+        .chunks(11)
+        .take_while(|chunk| chunk.len() == 11)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .fold(0u16, |acc, bit| (acc << 1) | (*bit as u16))
+        })
+        .collect()
+}
+
+/// The serialized form of a [`Mnemonic`].
+#[cfg(feature = "serde-secrets")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct MnemonicRepr {
+    phrase: String,
+    language: Language,
+}
+
+#[cfg(feature = "serde-secrets")]
+impl From<Mnemonic> for MnemonicRepr {
+    fn from(mnemonic: Mnemonic) -> Self {
+        Self {
+            phrase: mnemonic.phrase.clone(),
+            language: mnemonic.language,
+        }
+    }
+}
+
+#[cfg(feature = "serde-secrets")]
+impl TryFrom<MnemonicRepr> for Mnemonic {
+    type Error = WalletError;
+
+    fn try_from(repr: MnemonicRepr) -> Result<Self> {
+        Self::from_phrase(&repr.phrase, repr.language)
+    }
+}
+
+impl std::fmt::Debug for Mnemonic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Mnemonic")
+            .field("language", &self.language)
+            .field("words", &self.phrase.split_whitespace().count())
+            .field("phrase", &"<redacted>")
+            .finish()
+    }
+}
+
 impl std::fmt::Display for Mnemonic {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.phrase)
+        write!(f, "<redacted {} words>", self.mnemonic_type().word_count())
     }
 }
 
 impl std::str::FromStr for Mnemonic {
-    type Err = Error;
+    type Err = WalletError;
 
     fn from_str(s: &str) -> Result<Self> {
         Self::from_phrase(s, Language::English)
@@ -256,6 +557,30 @@ impl std::str::FromStr for Mnemonic {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        argon2,
+        bips::bip39::{SeedError, SeedKdf},
+    };
+
+    #[cfg(feature = "serde-secrets")]
+    #[test]
+    fn test_serde() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+        let json = serde_json::to_string(&mnemonic).unwrap();
+        assert_eq!(
+            json,
+            format!("{{\"phrase\":\"{}\",\"language\":\"english\"}}", phrase)
+        );
+        assert_eq!(serde_json::from_str::<Mnemonic>(&json).unwrap(), mnemonic);
+
+        let invalid = json.replace("about", "abandon");
+        assert!(serde_json::from_str::<Mnemonic>(&invalid).is_err());
+
+        let seed = mnemonic.to_seed("");
+        let json = serde_json::to_string(&seed).unwrap();
+        assert_eq!(serde_json::from_str::<Seed>(&json).unwrap(), seed);
+    }
 
     #[test]
     fn test_mnemonic() {
@@ -285,6 +610,181 @@ mod tests {
         assert_eq!(mnemonic.language(), Language::English);
     }
 
+    #[test]
+    fn test_new_with_rng() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let mnemonic = Mnemonic::new_with_rng(MnemonicType::Words24, Language::English, &mut rng);
+        assert_eq!(mnemonic.phrase().split_whitespace().count(), 24);
+
+        let mut entropy = [0u8; 32];
+        StdRng::seed_from_u64(7).fill_bytes(&mut entropy);
+        assert_eq!(mnemonic.entropy(), entropy);
+        let again = Mnemonic::new_with_rng(
+            MnemonicType::Words24,
+            Language::English,
+            &mut StdRng::seed_from_u64(7),
+        );
+        assert_eq!(again, mnemonic);
+    }
+
+    #[test]
+    fn test_dice_rolls() {
+        let rolls = [1, 2, 3, 4, 5, 6].repeat(9);
+        let mnemonic =
+            Mnemonic::from_dice_rolls(&rolls, MnemonicType::Words12, Language::English).unwrap();
+        let digits = "123456".repeat(9);
+        assert_eq!(
+            mnemonic.entropy(),
+            &sha2::Sha256::digest(digits.as_bytes())[..16]
+        );
+        assert_eq!(
+            mnemonic.phrase(),
+            "universe intact render tank net oval paddle thought trick movie chimney bullet"
+        );
+
+        assert_eq!(
+            Mnemonic::from_dice_rolls(&rolls, MnemonicType::Words24, Language::English)
+                .unwrap_err(),
+            MnemonicError::NotEnoughEntropy {
+                needed: 256,
+                provided: 139
+            }
+            .into()
+        );
+        assert!(
+            Mnemonic::from_dice_rolls(&[6; 99], MnemonicType::Words24, Language::English).is_err()
+        );
+        assert!(
+            Mnemonic::from_dice_rolls(&[6; 100], MnemonicType::Words24, Language::English).is_ok()
+        );
+        assert_eq!(
+            Mnemonic::from_dice_rolls(&[0; 60], MnemonicType::Words12, Language::English)
+                .unwrap_err(),
+            MnemonicError::InvalidDiceRoll(0).into()
+        );
+    }
+
+    #[test]
+    fn test_coin_flips() {
+        // HT gives 1 and TH gives 0, the HH and TT pairs are discarded
+        let flips = "HT TH HH TT ".repeat(64) + &"1001".repeat(64);
+        let mnemonic =
+            Mnemonic::from_coin_flips(&flips, MnemonicType::Words12, Language::English).unwrap();
+        assert_eq!(mnemonic.entropy(), [0xaa; 16]);
+
+        assert_eq!(
+            Mnemonic::from_coin_flips(&"HT".repeat(127), MnemonicType::Words12, Language::English)
+                .unwrap_err(),
+            MnemonicError::NotEnoughEntropy {
+                needed: 128,
+                provided: 127
+            }
+            .into()
+        );
+        assert_eq!(
+            Mnemonic::from_coin_flips("HTX", MnemonicType::Words12, Language::English).unwrap_err(),
+            MnemonicError::InvalidCoinFlip('X').into()
+        );
+    }
+
+    #[test]
+    fn test_unknown_word() {
+        let phrase = "abandon abandon abandon aboandon abandon abandon abandon abandon abandon abandon abandon about";
+        let err = Mnemonic::from_phrase(phrase, Language::English).unwrap_err();
+        assert_eq!(
+            err,
+            WordListError::UnknownWord {
+                position: 3,
+                suggestions: vec!["abandon"]
+            }
+            .into()
+        );
+        assert_eq!(err.to_string(), "Unknown word 4, did you mean abandon?");
+    }
+
+    #[test]
+    fn test_truncated_phrase() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::from_truncated_phrase(
+            "aban aban aban aban aban aban abandon aban aban aban aban abou",
+            Language::English,
+        )
+        .unwrap();
+        assert_eq!(mnemonic.phrase(), phrase);
+        assert_eq!(
+            mnemonic,
+            Mnemonic::from_phrase(phrase, Language::English).unwrap()
+        );
+
+        // truncated words are only accepted when asked for
+        assert!(Mnemonic::from_phrase(
+            "aban aban aban aban aban aban aban aban aban aban aban abou",
+            Language::English
+        )
+        .is_err());
+        assert!(Mnemonic::from_truncated_phrase(
+            "aba aban aban aban aban aban aban aban aban aban aban abou",
+            Language::English
+        )
+        .is_err());
+    }
+
+    #[cfg(feature = "french")]
+    #[test]
+    fn test_phrase_without_accents() {
+        let phrase = "implorer visage sonnette voyage véloce pourpre volaille tribunal implorer visage sonnette voyelle";
+        let mnemonic = Mnemonic::from_phrase(&phrase.replace('é', "e"), Language::French).unwrap();
+        assert_eq!(mnemonic.entropy(), [0x7f; 16]);
+        assert_eq!(mnemonic.phrase(), phrase.nfkd().collect::<String>());
+        assert_eq!(
+            mnemonic.to_seed("").to_bytes(),
+            Mnemonic::from_phrase(phrase, Language::French)
+                .unwrap()
+                .to_seed("")
+                .to_bytes()
+        );
+    }
+
+    #[cfg(feature = "japanese")]
+    #[test]
+    fn test_japanese_phrase() {
+        let mnemonic = Mnemonic::from_entropy(&[0; 16], Language::Japanese).unwrap();
+        let phrase = format!(
+            "{}\u{3000}{}",
+            ["あいこくしん"; 11].join("\u{3000}"),
+            "あおぞら".nfkd().collect::<String>()
+        );
+        assert_eq!(mnemonic.phrase(), phrase);
+
+        let seed = mnemonic.to_seed("㍍ガバヴァぱばぐゞちぢ十人十色");
+        assert_eq!(seed.expose_hex(), "a262d6fb6122ecf45be09c50492b31f92e9beb7d9a845987a02cefda57a15f9c467a17872029a9e92299b5cbdf306e3a0ee620245cbd508959b6cb7ca637bd55");
+
+        // ASCII spaces are accepted on input
+        let parsed = Mnemonic::from_phrase(&phrase.replace('\u{3000}', " "), Language::Japanese);
+        assert_eq!(parsed.unwrap(), mnemonic);
+    }
+
+    #[test]
+    fn test_valid_final_words() {
+        let words = ["abandon"; 11];
+        let valid = Mnemonic::valid_final_words(&words, Language::English).unwrap();
+        assert_eq!(valid.len(), 128);
+        assert_eq!(valid[0], "about");
+        for word in valid {
+            let phrase = format!("{} {}", words.join(" "), word);
+            assert!(Mnemonic::validate_phrase(&phrase, Language::English).is_ok());
+        }
+
+        let valid = Mnemonic::valid_final_words(&["abandon"; 23], Language::English).unwrap();
+        assert_eq!(valid.len(), 8);
+        assert!(valid.contains(&"art"));
+
+        assert!(Mnemonic::valid_final_words(&["abandon"; 12], Language::English).is_err());
+        assert!(Mnemonic::valid_final_words(&["abandonn"; 11], Language::English).is_err());
+    }
+
     #[test]
     fn test_entropy() {
         let entropy = vec![0u8; 16];
@@ -351,6 +851,45 @@ mod tests {
         assert_eq!(mnemonic.language(), Language::English);
     }
 
+    #[test]
+    fn test_indexes() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+        let indexes = mnemonic.indexes();
+        assert_eq!(indexes, vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3]);
+        assert_eq!(
+            Mnemonic::from_indexes(&indexes, Language::English).unwrap(),
+            mnemonic
+        );
+        assert!(Mnemonic::from_indexes(&[0; 12], Language::English).is_err());
+        assert!(Mnemonic::from_indexes(&[2048; 12], Language::English).is_err());
+    }
+
+    #[test]
+    fn test_phonetic() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+        let phonetic = mnemonic.phonetic();
+        assert_eq!(phonetic.len(), 12);
+        assert_eq!(phonetic[0], "Alfa Bravo Alfa November Delta Oscar November");
+        assert_eq!(phonetic[11], "Alfa Bravo Oscar Uniform Tango");
+    }
+
+    #[test]
+    fn test_chunks() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+        let chunks = mnemonic.chunks(5);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[2], vec!["abandon", "about"]);
+        assert_eq!(
+            mnemonic.to_chunked_string(4),
+            "1. abandon 2. abandon 3. abandon 4. abandon\n\
+             5. abandon 6. abandon 7. abandon 8. abandon\n\
+             9. abandon 10. abandon 11. abandon 12. about"
+        );
+    }
+
     #[test]
     fn test_seed() {
         let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
@@ -358,4 +897,53 @@ mod tests {
         let seed = mnemonic.to_seed("");
         assert_eq!(seed.to_bytes().len(), 64);
     }
+
+    #[test]
+    fn test_seed_params() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+        let seed = |kdf| Seed::new_with_params(&mnemonic, "TREZOR", kdf);
+
+        let bip39 = seed(SeedKdf::default()).unwrap();
+        assert_eq!(bip39, mnemonic.to_seed("TREZOR"));
+        assert_eq!(bip39.expose_hex(), "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04");
+
+        let pbkdf2 = seed(SeedKdf::Pbkdf2 { rounds: 4096 }).unwrap();
+        assert_eq!(
+            &pbkdf2.expose_hex()[..32],
+            "3d39670caaa237f5fb2999474413733b"
+        );
+        let scrypt = seed(SeedKdf::Scrypt {
+            log_n: 10,
+            r: 8,
+            p: 1,
+        })
+        .unwrap();
+        assert_eq!(
+            &scrypt.expose_hex()[..32],
+            "bafa9e1857b26ca8455f685df3759f2b"
+        );
+
+        let params = argon2::Params {
+            memory: 64,
+            iterations: 2,
+            lanes: 1,
+        };
+        let argon2 = seed(SeedKdf::Argon2id(params)).unwrap();
+        assert_eq!(argon2.len(), 64);
+        assert_eq!(argon2, seed(SeedKdf::Argon2id(params)).unwrap());
+        assert_ne!(argon2, bip39);
+
+        for kdf in [
+            SeedKdf::Pbkdf2 { rounds: 0 },
+            SeedKdf::Scrypt {
+                log_n: 64,
+                r: 8,
+                p: 1,
+            },
+            SeedKdf::Argon2id(argon2::Params { lanes: 0, ..params }),
+        ] {
+            assert_eq!(seed(kdf).unwrap_err(), SeedError::InvalidKdfParams.into());
+        }
+    }
 }
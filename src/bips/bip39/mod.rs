@@ -27,8 +27,16 @@
 //! let seed = mnemonic.to_seed("password");
 //! ```
 
+#[cfg(feature = "tokio")]
+mod future;
 mod mnemonic;
+mod plate;
+mod secret;
 mod seed;
 
+#[cfg(feature = "tokio")]
+pub use future::*;
 pub use mnemonic::*;
+pub use plate::*;
+pub use secret::*;
 pub use seed::*;
@@ -0,0 +1,256 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::Mnemonic;
+use crate::{
+    bips::wordlists::Language,
+    error::{Result, WalletError},
+};
+
+/// Number of words per row used by most metal backup plates.
+pub const DEFAULT_WORDS_PER_ROW: usize = 4;
+
+/// The row checksum is taken modulo the largest prime that fits in the four
+/// digits stamped next to each row.
+const CHECKSUM_MODULUS: u32 = 9973;
+
+/// Error returned when a plate backup is malformed or fails validation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlateError {
+    /// The row at the given position could not be parsed.
+    InvalidRow(usize),
+    /// The checksum of the row at the given position does not match.
+    InvalidChecksum(usize),
+    /// The word index is out of the 1 ~ 2048 range.
+    InvalidIndex(u16),
+    /// The backup does not contain any word.
+    Empty,
+}
+
+impl std::fmt::Display for PlateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PlateError::InvalidRow(row) => write!(f, "Invalid row: {}", row),
+            PlateError::InvalidChecksum(row) => write!(f, "Invalid checksum on row: {}", row),
+            PlateError::InvalidIndex(idx) => write!(f, "Invalid word index: {}", idx),
+            PlateError::Empty => write!(f, "Empty backup"),
+        }
+    }
+}
+
+impl std::error::Error for PlateError {}
+
+/// A row of a plate backup, a few word indexes followed by their checksum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlateRow {
+    indexes: Vec<u16>,
+    checksum: u16,
+}
+
+impl PlateRow {
+    /// Return the 1-based word indexes of the row, as stamped on the plate.
+    pub fn indexes(&self) -> &[u16] {
+        &self.indexes
+    }
+
+    /// Return the checksum of the row.
+    pub fn checksum(&self) -> u16 {
+        self.checksum
+    }
+}
+
+/// A mnemonic rendered as a compact grid of word indexes for metal backups.
+///
+/// Each word is written as its 1-based index in the wordlist (`0001` ~ `2048`)
+/// so it fits on four digit punches regardless of the language. Every row
+/// ends with a checksum over the position and index of its words, which
+/// catches mistyped digits as well as swapped words or rows before the
+/// BIP39 checksum is even checked.
+///
+/// ```text
+/// 01: 0001 0001 0001 0001 / 0010
+/// 02: 0001 0001 0001 0001 / 0026
+/// 03: 0001 0001 0001 0004 / 0078
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlateBackup {
+    rows: Vec<PlateRow>,
+}
+
+impl PlateBackup {
+    /// Create a new backup of the mnemonic with the given number of words per row.
+    pub fn new(mnemonic: &Mnemonic, words_per_row: usize) -> Self {
+        let indexes = mnemonic
+            .indexes()
+            .into_iter()
+            .map(|idx| idx + 1)
+            .collect::<Vec<_>>();
+
+        let rows = indexes
+            .chunks(words_per_row.max(1))
+            .enumerate()
+            .map(|(row, chunk)| PlateRow {
+                indexes: chunk.to_vec(),
+                checksum: row_checksum(row * words_per_row.max(1), chunk),
+            })
+            .collect();
+
+        Self { rows }
+    }
+
+    /// Return the rows of the backup.
+    pub fn rows(&self) -> &[PlateRow] {
+        &self.rows
+    }
+
+    /// Check the checksum of every row, returning the first invalid one.
+    pub fn validate(&self) -> Result<()> {
+        if self.rows.iter().all(|row| row.indexes.is_empty()) {
+            return Err(PlateError::Empty.into());
+        }
+
+        let mut position = 0;
+        for (row, plate_row) in self.rows.iter().enumerate() {
+            if let Some(idx) = plate_row
+                .indexes
+                .iter()
+                .find(|idx| !(1..=2048).contains(*idx))
+            {
+                return Err(PlateError::InvalidIndex(*idx).into());
+            }
+            if row_checksum(position, &plate_row.indexes) != plate_row.checksum {
+                return Err(PlateError::InvalidChecksum(row + 1).into());
+            }
+            position += plate_row.indexes.len();
+        }
+
+        Ok(())
+    }
+
+    /// Validate the backup and restore the mnemonic in the given language.
+    pub fn to_mnemonic(&self, language: Language) -> Result<Mnemonic> {
+        self.validate()?;
+
+        let indexes = self
+            .rows
+            .iter()
+            .flat_map(|row| row.indexes.iter().map(|idx| idx - 1))
+            .collect::<Vec<_>>();
+
+        Mnemonic::from_indexes(&indexes, language)
+    }
+}
+
+fn row_checksum(position: usize, indexes: &[u16]) -> u16 {
+    let sum = indexes.iter().enumerate().fold(0u32, |acc, (i, idx)| {
+        (acc + (position + i + 1) as u32 * *idx as u32) % CHECKSUM_MODULUS
+    });
+
+    sum as u16
+}
+
+impl std::fmt::Display for PlateBackup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, row) in self.rows.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{:02}:", i + 1)?;
+            for idx in &row.indexes {
+                write!(f, " {:04}", idx)?;
+            }
+            write!(f, " / {:04}", row.checksum)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for PlateBackup {
+    type Err = WalletError;
+
+    /// Parse a backup as rendered by `Display`. The row numbers are optional,
+    /// and blank lines are ignored.
+    fn from_str(s: &str) -> Result<Self> {
+        let mut rows = Vec::new();
+
+        for line in s.lines().filter(|line| !line.trim().is_empty()) {
+            let row = rows.len() + 1;
+            let line = line.split_once(':').map_or(line, |(_, rest)| rest);
+            let (words, checksum) = line.split_once('/').ok_or(PlateError::InvalidRow(row))?;
+
+            let indexes = words
+                .split_whitespace()
+                .map(|idx| idx.parse::<u16>())
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(|_| PlateError::InvalidRow(row))?;
+            let checksum = checksum
+                .trim()
+                .parse::<u16>()
+                .map_err(|_| PlateError::InvalidRow(row))?;
+
+            rows.push(PlateRow { indexes, checksum });
+        }
+
+        let backup = Self { rows };
+        backup.validate()?;
+        Ok(backup)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bips::bip39::MnemonicType;
+
+    #[test]
+    fn test_plate() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+        let backup = PlateBackup::new(&mnemonic, DEFAULT_WORDS_PER_ROW);
+        assert_eq!(
+            backup.to_string(),
+            "01: 0001 0001 0001 0001 / 0010\n\
+             02: 0001 0001 0001 0001 / 0026\n\
+             03: 0001 0001 0001 0004 / 0078"
+        );
+
+        let parsed = backup.to_string().parse::<PlateBackup>().unwrap();
+        assert_eq!(parsed, backup);
+        assert_eq!(parsed.to_mnemonic(Language::English).unwrap(), mnemonic);
+    }
+
+    #[test]
+    fn test_plate_roundtrip() {
+        let mnemonic = Mnemonic::new(MnemonicType::Words24, Language::English);
+        let backup = PlateBackup::new(&mnemonic, 3);
+        assert_eq!(backup.rows().len(), 8);
+
+        let parsed = backup.to_string().parse::<PlateBackup>().unwrap();
+        assert_eq!(parsed.to_mnemonic(Language::English).unwrap(), mnemonic);
+    }
+
+    #[test]
+    fn test_plate_invalid() {
+        // mistyped digit
+        let backup = "01: 0001 0001 0001 0001 / 0010\n02: 0001 0001 0001 0002 / 0026";
+        assert!(backup.parse::<PlateBackup>().is_err());
+
+        // swapped rows
+        let backup = "01: 0001 0001 0001 0004 / 0078\n02: 0001 0001 0001 0001 / 0010";
+        assert!(backup.parse::<PlateBackup>().is_err());
+
+        assert!("01: 0001 0001".parse::<PlateBackup>().is_err());
+        assert!("01: 2049 / 2049".parse::<PlateBackup>().is_err());
+        assert!("".parse::<PlateBackup>().is_err());
+    }
+}
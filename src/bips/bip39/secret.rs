@@ -0,0 +1,136 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Wrappers around [`Mnemonic`] and [`Seed`] that only hand out the secret
+//! through an explicit `expose_secret` call, so every place reading it is
+//! easy to find in review. With the `secrecy` feature they implement
+//! [`secrecy::ExposeSecret`] as well.
+
+use super::{Mnemonic, MnemonicType, Seed};
+use crate::bips::wordlists::Language;
+
+/// A [`Mnemonic`] whose phrase and entropy are only reachable through
+/// [`SecretMnemonic::expose_secret`].
+pub struct SecretMnemonic(Mnemonic);
+
+impl SecretMnemonic {
+    /// Wrap a mnemonic.
+    pub fn new(mnemonic: Mnemonic) -> Self {
+        Self(mnemonic)
+    }
+
+    /// Return the wrapped mnemonic.
+    pub fn expose_secret(&self) -> &Mnemonic {
+        &self.0
+    }
+
+    /// Return the language of the mnemonic.
+    pub fn language(&self) -> Language {
+        self.0.language()
+    }
+
+    /// Return the type of the mnemonic.
+    pub fn mnemonic_type(&self) -> MnemonicType {
+        self.0.mnemonic_type()
+    }
+
+    /// Return the seed of the mnemonic, wrapped as well.
+    pub fn to_seed(&self, passphrase: &str) -> SecretSeed {
+        SecretSeed(self.0.to_seed(passphrase))
+    }
+}
+
+impl From<Mnemonic> for SecretMnemonic {
+    fn from(mnemonic: Mnemonic) -> Self {
+        Self(mnemonic)
+    }
+}
+
+impl std::fmt::Debug for SecretMnemonic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "SecretMnemonic(<redacted>)")
+    }
+}
+
+/// A [`Seed`] whose bytes are only reachable through
+/// [`SecretSeed::expose_secret`].
+pub struct SecretSeed(Seed);
+
+impl SecretSeed {
+    /// Wrap a seed.
+    pub fn new(seed: Seed) -> Self {
+        Self(seed)
+    }
+
+    /// Return the wrapped seed.
+    pub fn expose_secret(&self) -> &Seed {
+        &self.0
+    }
+
+    /// Return the length of the seed.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Return true if the seed is empty.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<Seed> for SecretSeed {
+    fn from(seed: Seed) -> Self {
+        Self(seed)
+    }
+}
+
+impl std::fmt::Debug for SecretSeed {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "SecretSeed(<redacted>)")
+    }
+}
+
+#[cfg(feature = "secrecy")]
+impl secrecy::ExposeSecret<Mnemonic> for SecretMnemonic {
+    fn expose_secret(&self) -> &Mnemonic {
+        &self.0
+    }
+}
+
+#[cfg(feature = "secrecy")]
+impl secrecy::ExposeSecret<Seed> for SecretSeed {
+    fn expose_secret(&self) -> &Seed {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bips::bip32::ExtendedKey;
+
+    #[test]
+    fn test_expose() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic =
+            SecretMnemonic::new(Mnemonic::from_phrase(phrase, Language::English).unwrap());
+        assert_eq!(mnemonic.expose_secret().phrase(), phrase);
+        assert_eq!(mnemonic.mnemonic_type(), MnemonicType::Words12);
+        assert_eq!(format!("{:?}", mnemonic), "SecretMnemonic(<redacted>)");
+
+        let seed = mnemonic.to_seed("");
+        assert_eq!(seed.len(), 64);
+        assert_eq!(seed.expose_secret(), &mnemonic.expose_secret().to_seed(""));
+        assert!(ExtendedKey::new_master(seed.expose_secret()).is_ok());
+    }
+}
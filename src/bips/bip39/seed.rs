@@ -12,12 +12,67 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use super::Mnemonic;
-use horror::{Error, Result};
+use crate::{
+    argon2,
+    bips::locked::Locked,
+    error::{Result, WalletError},
+};
 use unicode_normalization::UnicodeNormalization;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+/// Error returned when deriving a seed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeedError {
+    /// The parameters of the key derivation function are out of range.
+    InvalidKdfParams,
+}
+
+impl std::fmt::Display for SeedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SeedError::InvalidKdfParams => write!(f, "Invalid seed derivation parameters"),
+        }
+    }
+}
+
+impl std::error::Error for SeedError {}
+
+/// The key derivation function stretching a mnemonic and its passphrase
+/// into a seed.
+///
+/// Only [`SeedKdf::BIP39`] derives the seed other wallets find for the same
+/// mnemonic. The others are for hardened vaults, where a stolen mnemonic
+/// should make brute forcing the passphrase as expensive as possible, and
+/// must be recorded alongside the backup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeedKdf {
+    /// PBKDF2-HMAC-SHA512 with `rounds` iterations.
+    Pbkdf2 { rounds: u32 },
+    /// scrypt with a cost of `2^log_n`.
+    Scrypt { log_n: u8, r: u32, p: u32 },
+    /// Argon2id.
+    Argon2id(argon2::Params),
+}
+
+impl SeedKdf {
+    /// PBKDF2 with 2048 iterations, as specified by BIP39.
+    pub const BIP39: SeedKdf = SeedKdf::Pbkdf2 { rounds: 2048 };
+}
+
+impl Default for SeedKdf {
+    fn default() -> Self {
+        Self::BIP39
+    }
+}
 
 /// A seed is a secret value that is used to generate private keys.
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub struct Seed(Vec<u8>);
+///
+/// It only implements serde with the `serde-secrets` feature, as hex, so
+/// seeds cannot end up in serialized data by accident. The bytes are
+/// zeroized when the seed is dropped, and its `Debug` and `Display` output
+/// is redacted. With the `mlock` feature they are kept out of swap.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Seed(Locked<[u8]>);
 
 impl Seed {
     /// Return the underlying byte array.
@@ -27,18 +82,57 @@ impl Seed {
 
     /// Create a new Seed from a mnemonic and a passphrase.
     pub fn new(mnemonic: &Mnemonic, passphrase: &str) -> Self {
-        let salt = format!("mnemonic{}", passphrase);
-        let normalized = salt.nfkd().collect::<String>();
-
-        let mut data = [0u8; 64];
-        pbkdf2::pbkdf2::<hmac::Hmac<sha2::Sha512>>(
-            mnemonic.to_bytes(),
-            normalized.as_bytes(),
-            2048,
-            &mut data,
-        );
-
-        Self(data.to_vec())
+        Self::new_with_params(mnemonic, passphrase, SeedKdf::BIP39)
+            .expect("BIP39 parameters are valid")
+    }
+
+    /// Create a new Seed from a mnemonic and a passphrase, stretched by
+    /// `kdf` instead of the PBKDF2 of BIP39. The salt is `"mnemonic"`
+    /// followed by the passphrase, as in BIP39.
+    pub fn new_with_params(mnemonic: &Mnemonic, passphrase: &str, kdf: SeedKdf) -> Result<Self> {
+        let (phrase, normalized) = kdf_inputs(mnemonic, passphrase);
+
+        let mut data = Zeroizing::new([0u8; 64]);
+        match kdf {
+            SeedKdf::Pbkdf2 { rounds } => {
+                if rounds == 0 {
+                    return Err(SeedError::InvalidKdfParams.into());
+                }
+                pbkdf2::pbkdf2::<hmac::Hmac<sha2::Sha512>>(
+                    phrase.as_bytes(),
+                    normalized.as_bytes(),
+                    rounds,
+                    &mut *data,
+                );
+            }
+            SeedKdf::Scrypt { log_n, r, p } => {
+                let params = scrypt::Params::new(log_n, r, p, data.len())
+                    .map_err(|_| SeedError::InvalidKdfParams)?;
+                scrypt::scrypt(
+                    phrase.as_bytes(),
+                    normalized.as_bytes(),
+                    &params,
+                    &mut *data,
+                )
+                .map_err(|_| SeedError::InvalidKdfParams)?;
+            }
+            SeedKdf::Argon2id(params) => {
+                argon2::argon2id(
+                    phrase.as_bytes(),
+                    normalized.as_bytes(),
+                    &params,
+                    &mut *data,
+                )
+                .map_err(|_| SeedError::InvalidKdfParams)?;
+            }
+        }
+
+        Ok(Self(Locked::from_slice(&*data)))
+    }
+
+    /// Return the seed as hex, for when it is meant to be shown.
+    pub fn expose_hex(&self) -> String {
+        hex::encode(&*self.0)
     }
 
     /// Return the length of the seed.
@@ -52,24 +146,66 @@ impl Seed {
     }
 }
 
+/// Return the password and salt the seed of a mnemonic is derived from.
+pub(super) fn kdf_inputs(
+    mnemonic: &Mnemonic,
+    passphrase: &str,
+) -> (Zeroizing<String>, Zeroizing<String>) {
+    // the seed is derived from the NFKD form of the phrase, where the
+    // ideographic spaces of Japanese phrases become ASCII spaces
+    let delimiter = mnemonic.language().delimiter();
+    let phrase = Zeroizing::new(mnemonic.phrase().replace(delimiter, " "));
+    let salt = format!("mnemonic{}", passphrase);
+    (phrase, Zeroizing::new(salt.nfkd().collect()))
+}
+
+impl ZeroizeOnDrop for Seed {}
+
+impl std::fmt::Debug for Seed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Seed(<redacted {} bytes>)", self.0.len())
+    }
+}
+
 impl std::fmt::Display for Seed {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", hex::encode(&self.0))
+        std::fmt::Debug::fmt(self, f)
     }
 }
 
 impl std::str::FromStr for Seed {
-    type Err = Error;
+    type Err = WalletError;
 
     fn from_str(s: &str) -> Result<Self> {
-        let bytes = hex::decode(s)?;
-        Ok(Self(bytes))
+        Ok(Self::from(hex::decode(s)?))
+    }
+}
+
+#[cfg(feature = "serde-secrets")]
+impl serde::Serialize for Seed {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.expose_hex())
+    }
+}
+
+#[cfg(feature = "serde-secrets")]
+impl<'de> serde::Deserialize<'de> for Seed {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let s = <std::borrow::Cow<str>>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
     }
 }
 
 impl From<Vec<u8>> for Seed {
-    fn from(bytes: Vec<u8>) -> Self {
-        Self(bytes)
+    fn from(mut bytes: Vec<u8>) -> Self {
+        let seed = Self(Locked::from_slice(&bytes));
+        bytes.zeroize();
+        seed
     }
 }
 
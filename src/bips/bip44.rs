@@ -0,0 +1,295 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! BIP44 implementation
+//!
+//! A typed [BIP44](https://github.com/bitcoin/bips/blob/master/bip-0044.mediawiki)
+//! path, `m / purpose' / coin_type' / account' / change / address_index`,
+//! for code that walks the receive and change addresses of an account.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::bips::bip44::Bip44Path;
+//!
+//! let path = Bip44Path::ethereum(0, 0);
+//! assert_eq!(path.to_string(), "m/44'/60'/0'/0/0");
+//! assert_eq!(path.next_index().unwrap().to_string(), "m/44'/60'/0'/0/1");
+//! assert_eq!(path.change().to_string(), "m/44'/60'/0'/1/0");
+//! ```
+
+use crate::error::{Result, WalletError};
+
+use super::{ChildNumber, DerivationPath};
+
+/// The purpose of BIP44 paths.
+pub const PURPOSE: u32 = 44;
+
+/// The SLIP-44 coin type of Bitcoin.
+pub const BITCOIN: u32 = 0;
+
+/// The SLIP-44 coin type shared by the test networks of all coins.
+pub const TESTNET: u32 = 1;
+
+/// The SLIP-44 coin type of Ethereum.
+pub const ETHEREUM: u32 = 60;
+
+/// The SLIP-44 coin type of the Cosmos Hub, shared by most Cosmos SDK
+/// chains.
+pub const COSMOS: u32 = 118;
+
+/// The SLIP-44 coin type of the XRP Ledger.
+pub const XRP: u32 = 144;
+
+/// The SLIP-44 coin type of Filecoin.
+pub const FILECOIN: u32 = 461;
+
+/// The SLIP-44 coin type of Solana.
+pub const SOLANA: u32 = 501;
+
+/// Error returned when a path does not follow BIP44.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bip44Error {
+    /// The path does not have 5 components.
+    InvalidDepth(usize),
+    /// The component at the given depth has the wrong hardening.
+    InvalidHardening(usize),
+    /// The change component is neither 0 nor 1.
+    InvalidChange(u32),
+    /// The index does not fit in 31 bits.
+    IndexOverflow,
+}
+
+impl std::fmt::Display for Bip44Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Bip44Error::InvalidDepth(depth) => write!(f, "invalid BIP44 path depth: {}", depth),
+            Bip44Error::InvalidHardening(depth) => {
+                write!(f, "invalid hardening of BIP44 path component {}", depth)
+            }
+            Bip44Error::InvalidChange(change) => write!(f, "invalid BIP44 change: {}", change),
+            Bip44Error::IndexOverflow => write!(f, "BIP44 index overflow"),
+        }
+    }
+}
+
+impl std::error::Error for Bip44Error {}
+
+/// A BIP44 derivation path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Bip44Path {
+    purpose: u32,
+    coin_type: u32,
+    account: u32,
+    change: bool,
+    index: u32,
+}
+
+impl Bip44Path {
+    /// Create a new path. The purpose, coin type, account and index must fit
+    /// in 31 bits, the first three being hardened.
+    pub fn new(
+        purpose: u32,
+        coin_type: u32,
+        account: u32,
+        change: bool,
+        index: u32,
+    ) -> Result<Self> {
+        if [purpose, coin_type, account, index]
+            .iter()
+            .any(|n| n & 0x80000000 != 0)
+        {
+            return Err(Bip44Error::IndexOverflow.into());
+        }
+
+        Ok(Self {
+            purpose,
+            coin_type,
+            account,
+            change,
+            index,
+        })
+    }
+
+    /// Return the path of an Ethereum receive address, `m/44'/60'/account'/0/index`.
+    pub fn ethereum(account: u32, index: u32) -> Self {
+        Self {
+            purpose: PURPOSE,
+            coin_type: ETHEREUM,
+            account: account & 0x7FFFFFFF,
+            change: false,
+            index: index & 0x7FFFFFFF,
+        }
+    }
+
+    /// Return the purpose.
+    pub fn purpose(&self) -> u32 {
+        self.purpose
+    }
+
+    /// Return the coin type.
+    pub fn coin_type(&self) -> u32 {
+        self.coin_type
+    }
+
+    /// Return the account.
+    pub fn account(&self) -> u32 {
+        self.account
+    }
+
+    /// Return true if the path is on the change chain.
+    pub fn is_change(&self) -> bool {
+        self.change
+    }
+
+    /// Return the address index.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Return the path of the next address on the same chain.
+    pub fn next_index(&self) -> Result<Self> {
+        self.with_index(self.index + 1)
+    }
+
+    /// Return the path with the given address index.
+    pub fn with_index(&self, index: u32) -> Result<Self> {
+        if index & 0x80000000 != 0 {
+            return Err(Bip44Error::IndexOverflow.into());
+        }
+        Ok(Self { index, ..*self })
+    }
+
+    /// Return the same address index on the change chain.
+    pub fn change(&self) -> Self {
+        Self {
+            change: true,
+            ..*self
+        }
+    }
+
+    /// Return the same address index on the receive chain.
+    pub fn receive(&self) -> Self {
+        Self {
+            change: false,
+            ..*self
+        }
+    }
+}
+
+impl From<Bip44Path> for DerivationPath {
+    fn from(path: Bip44Path) -> Self {
+        [
+            ChildNumber::hardened(path.purpose),
+            ChildNumber::hardened(path.coin_type),
+            ChildNumber::hardened(path.account),
+            ChildNumber::normal(path.change as u32),
+            ChildNumber::normal(path.index),
+        ]
+        .into_iter()
+        .collect()
+    }
+}
+
+impl TryFrom<&DerivationPath> for Bip44Path {
+    type Error = WalletError;
+
+    fn try_from(path: &DerivationPath) -> Result<Self> {
+        let components = path.iter().copied().collect::<Vec<_>>();
+        if components.len() != 5 {
+            return Err(Bip44Error::InvalidDepth(components.len()).into());
+        }
+
+        for (depth, component) in components.iter().enumerate() {
+            if component.is_hardened() != (depth < 3) {
+                return Err(Bip44Error::InvalidHardening(depth + 1).into());
+            }
+        }
+
+        let change = match components[3].index() {
+            0 => false,
+            1 => true,
+            n => return Err(Bip44Error::InvalidChange(n).into()),
+        };
+
+        Ok(Self {
+            purpose: components[0].index(),
+            coin_type: components[1].index(),
+            account: components[2].index(),
+            change,
+            index: components[4].index(),
+        })
+    }
+}
+
+impl std::fmt::Display for Bip44Path {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", DerivationPath::from(*self))
+    }
+}
+
+impl std::str::FromStr for Bip44Path {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::try_from(&DerivationPath::parse(s)?)
+    }
+}
+
+#[cfg(feature = "serde")]
+crate::types::serde_string!(Bip44Path);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversion() {
+        let path: Bip44Path = "m/44'/60'/3'/1/7".parse().unwrap();
+        assert_eq!(path.account(), 3);
+        assert!(path.is_change());
+        assert_eq!(path.index(), 7);
+
+        let derivation = DerivationPath::from(path);
+        assert_eq!(derivation.string(), "m/44'/60'/3'/1/7");
+        assert_eq!(Bip44Path::try_from(&derivation).unwrap(), path);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!("m/44'/60'/0'/0".parse::<Bip44Path>().is_err());
+        assert!("m/44'/60'/0'/2/0".parse::<Bip44Path>().is_err());
+        assert!("m/44'/60'/0/0/0".parse::<Bip44Path>().is_err());
+        assert!("m/44'/60'/0'/0/0'".parse::<Bip44Path>().is_err());
+        assert!(Bip44Path::new(44, 60, 0x80000000, false, 0).is_err());
+    }
+
+    #[test]
+    fn test_iteration() {
+        let path = Bip44Path::ethereum(1, 0);
+        let receive = std::iter::successors(Some(path), |p| p.next_index().ok())
+            .take(3)
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            receive,
+            ["m/44'/60'/1'/0/0", "m/44'/60'/1'/0/1", "m/44'/60'/1'/0/2"]
+        );
+
+        let change = path.change();
+        assert_eq!(change.to_string(), "m/44'/60'/1'/1/0");
+        assert_eq!(change.receive(), path);
+
+        let last = path.with_index(0x7FFFFFFF).unwrap();
+        assert!(last.next_index().is_err());
+    }
+}
@@ -0,0 +1,125 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Heap storage for secrets that is zeroized on drop and, with the `mlock`
+//! feature, locked into RAM so it is never written to swap or core dumps.
+//!
+//! Locking works on whole pages and is not reference counted, so dropping one
+//! value unlocks a page that other secrets may still share. It is also best
+//! effort: when the process runs out of lockable memory (`RLIMIT_MEMLOCK` on
+//! Unix) the value is kept unlocked rather than failing.
+
+use zeroize::Zeroize;
+
+/// A boxed value whose allocation is locked with the `mlock` feature.
+pub(crate) struct Locked<T: ?Sized + Zeroize> {
+    inner: Box<T>,
+    locked: bool,
+}
+
+impl<T: ?Sized + Zeroize> Locked<T> {
+    /// Take ownership of the boxed value and lock its allocation.
+    pub(crate) fn new(inner: Box<T>) -> Self {
+        let mut value = Self {
+            inner,
+            locked: false,
+        };
+        value.locked = value.lock();
+        value
+    }
+
+    #[cfg(feature = "mlock")]
+    fn lock(&mut self) -> bool {
+        let len = std::mem::size_of_val(&*self.inner);
+        let ptr = &mut *self.inner as *mut T as *mut u8;
+        // SAFETY: ptr points to the len bytes of the box allocation.
+        len > 0 && unsafe { memsec::mlock(ptr, len) }
+    }
+
+    #[cfg(not(feature = "mlock"))]
+    fn lock(&mut self) -> bool {
+        false
+    }
+
+    /// Return whether the allocation is locked into RAM.
+    #[allow(dead_code)]
+    pub(crate) fn is_locked(&self) -> bool {
+        self.locked
+    }
+}
+
+impl Locked<[u8]> {
+    /// Copy the bytes into a new locked allocation.
+    pub(crate) fn from_slice(bytes: &[u8]) -> Self {
+        Self::new(bytes.into())
+    }
+}
+
+impl<T: ?Sized + Zeroize> Drop for Locked<T> {
+    fn drop(&mut self) {
+        self.inner.zeroize();
+
+        #[cfg(feature = "mlock")]
+        if self.locked {
+            let len = std::mem::size_of_val(&*self.inner);
+            let ptr = &mut *self.inner as *mut T as *mut u8;
+            // SAFETY: the allocation was locked with the same pointer and
+            // length in `lock` and is still alive.
+            unsafe { memsec::munlock(ptr, len) };
+        }
+    }
+}
+
+impl<T: ?Sized + Zeroize> std::ops::Deref for Locked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: ?Sized + Zeroize> Clone for Locked<T>
+where
+    Box<T>: Clone,
+{
+    fn clone(&self) -> Self {
+        Self::new(self.inner.clone())
+    }
+}
+
+impl<T: ?Sized + Zeroize + PartialEq> PartialEq for Locked<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<T: ?Sized + Zeroize + Eq> Eq for Locked<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locked() {
+        let bytes = Locked::from_slice(&[1, 2, 3]);
+        assert_eq!(&*bytes, &[1, 2, 3]);
+        assert!(bytes.clone() == bytes);
+        // locking may fail under a low RLIMIT_MEMLOCK, but never happens
+        // without the feature
+        assert!(cfg!(feature = "mlock") || !bytes.is_locked());
+
+        let empty = Locked::from_slice(&[]);
+        assert!(empty.is_empty());
+        assert!(!empty.is_locked());
+    }
+}
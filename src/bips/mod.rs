@@ -1,6 +1,9 @@
+pub mod bip32;
+pub mod bip38;
 pub mod bip39;
+pub mod bip44;
 pub mod wordlists;
-pub mod bip32;
 
+mod locked;
 mod path;
 pub use path::*;
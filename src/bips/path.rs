@@ -11,24 +11,52 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use horror::Result;
+use std::borrow::Cow;
+
+use crate::error::{Result, WalletError};
 
 /// ChildNumber represents a child number in a BIP32 derivation path.
 /// Child numbers are hardened if the most significant bit is set.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct ChildNumber(u32);
 
 impl ChildNumber {
-    /// Creates a new hardened ChildNumber.
-    pub fn hardened(n: u32) -> Self {
+    /// Creates a new hardened ChildNumber. The index must be below 2^31, the
+    /// hardened bit of larger ones is lost; see [`ChildNumber::try_hardened`].
+    pub const fn hardened(n: u32) -> Self {
         Self(n | 0x80000000)
     }
 
-    /// Creates a new normal ChildNumber.
-    pub fn normal(n: u32) -> Self {
+    /// Creates a new normal ChildNumber. The index must be below 2^31, larger
+    /// ones result in a hardened child; see [`ChildNumber::try_normal`].
+    pub const fn normal(n: u32) -> Self {
         Self(n)
     }
 
+    /// Creates a new hardened ChildNumber, failing if the index does not fit
+    /// in 31 bits.
+    pub fn try_hardened(n: u32) -> Result<Self> {
+        Self::check(n).map(Self::hardened)
+    }
+
+    /// Creates a new normal ChildNumber, failing if the index does not fit
+    /// in 31 bits.
+    pub fn try_normal(n: u32) -> Result<Self> {
+        Self::check(n).map(Self::normal)
+    }
+
+    fn check(n: u32) -> Result<u32> {
+        if n & 0x80000000 != 0 {
+            return Err(Error::IndexOutOfRange(n).into());
+        }
+        Ok(n)
+    }
+
     /// Returns true if the ChildNumber is hardened.
     pub fn is_hardened(&self) -> bool {
         self.0 & 0x80000000 != 0
@@ -57,23 +85,67 @@ impl From<ChildNumber> for u32 {
     }
 }
 
-/// Error returned when parsing a BIP32 derivation path.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Error returned when parsing a BIP32 derivation path. Positions count the
+/// components after `m` from 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Error {
     /// The path is empty.
     Empty,
+    /// The path does not start with `m`.
+    MissingMaster,
+    /// The component at the given position is empty, as in `m//0` or `m/0/`.
+    EmptyComponent {
+        /// The position of the component.
+        position: usize,
+    },
+    /// The component is not a 31-bit index with an optional hardened suffix.
+    InvalidComponent {
+        /// The position of the component.
+        position: usize,
+        /// The component as written.
+        component: String,
+    },
+    /// The last component of a path range is not of the form `start..end`.
+    InvalidRange,
+    /// The index does not fit in 31 bits.
+    IndexOutOfRange(u32),
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             Error::Empty => write!(f, "empty path"),
+            Error::MissingMaster => write!(f, "path must start with m"),
+            Error::EmptyComponent { position } => {
+                write!(f, "empty path component at position {}", position)
+            }
+            Error::InvalidComponent {
+                position,
+                component,
+            } => write!(
+                f,
+                "invalid path component {:?} at position {}",
+                component, position
+            ),
+            Error::InvalidRange => write!(f, "invalid path range"),
+            Error::IndexOutOfRange(index) => write!(f, "index out of range: {}", index),
         }
     }
 }
 
 impl std::error::Error for Error {}
 
+/// The suffix marking hardened components in path strings. Parsing accepts
+/// both, `'` is the canonical form used by [`DerivationPath::string`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HardenedNotation {
+    /// `m/44'/60'/0'/0/0`, as in BIP32 and most wallets.
+    #[default]
+    Apostrophe,
+    /// `m/44h/60h/0h/0/0`, as in output descriptors and shell friendly tools.
+    H,
+}
+
 /// DerivationPath represents the computer friendly version of a hierarchical
 /// deterministic wallet account derivation path.
 ///
@@ -92,37 +164,109 @@ impl std::error::Error for Error {}
 /// yet whether accounts should increment the last component or the children of
 /// that. We will go with the simpler approach of incrementing the last component.
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct DerivationPath(Vec<ChildNumber>);
+pub struct DerivationPath(Cow<'static, [ChildNumber]>);
 
 impl DerivationPath {
-    /// Parses a derivation path from a string.
-    pub fn parse(path: &str) -> Result<Self> {
-        let path = path.split('/');
-        let mut result = Vec::new();
+    /// Creates a derivation path borrowing static components, usable in
+    /// constants. Paths written as string literals are better created with
+    /// [`derivation_path!`](crate::derivation_path).
+    pub const fn from_static(components: &'static [ChildNumber]) -> Self {
+        Self(Cow::Borrowed(components))
+    }
 
-        if path.clone().count() == 0 {
+    /// Parses a derivation path from a string such as `m/44'/60'/0'/0/0`.
+    /// The path must start with `m`, followed by `/` separated indices below
+    /// 2^31 which are hardened by a `'`, `h` or `H` suffix.
+    pub fn parse(path: &str) -> Result<Self> {
+        if path.is_empty() {
             return Err(Error::Empty.into());
         }
 
-        for component in path {
-            if component == "m" {
-                continue;
-            }
-            let hardened = component.ends_with('\'');
-            let index = component.trim_end_matches('\'').parse::<u32>()?;
+        let mut components = path.split('/');
+        if components.next() != Some("m") {
+            return Err(Error::MissingMaster.into());
+        }
 
-            if hardened {
-                result.push(ChildNumber::hardened(index));
-            } else {
-                result.push(ChildNumber::normal(index));
-            }
+        let components = components
+            .enumerate()
+            .map(|(position, component)| Self::parse_component(position, component))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self(components.into()))
+    }
+
+    fn parse_component(position: usize, component: &str) -> Result<ChildNumber> {
+        if component.is_empty() {
+            return Err(Error::EmptyComponent { position }.into());
+        }
+
+        let (digits, hardened) = match component.strip_suffix(['\'', 'h', 'H']) {
+            Some(digits) => (digits, true),
+            None => (component, false),
+        };
+        let index = parse_index(digits)
+            .filter(|index| index & 0x80000000 == 0)
+            .ok_or_else(|| Error::InvalidComponent {
+                position,
+                component: component.to_string(),
+            })?;
+
+        match hardened {
+            true => Ok(ChildNumber::hardened(index)),
+            false => Ok(ChildNumber::normal(index)),
+        }
+    }
+
+    /// Parses a path whose last component is a range of indices, such as
+    /// `m/44'/60'/0'/0/0..100`, into an iterator over the concrete paths. The
+    /// end is exclusive, a hardened suffix after it hardens every index.
+    pub fn parse_range(path: &str) -> Result<PathRange> {
+        let (base, last) = path.rsplit_once('/').ok_or(Error::InvalidRange)?;
+        let hardened = last.ends_with(['\'', 'h', 'H']);
+        let (start, end) = last
+            .strip_suffix(['\'', 'h', 'H'])
+            .unwrap_or(last)
+            .split_once("..")
+            .ok_or(Error::InvalidRange)?;
+
+        let start = parse_index(start).ok_or(Error::InvalidRange)?;
+        let end = parse_index(end).ok_or(Error::InvalidRange)?;
+        if start > end {
+            return Err(Error::InvalidRange.into());
+        }
+        if end > 0x80000000 {
+            return Err(Error::IndexOutOfRange(end - 1).into());
         }
 
-        Ok(Self(result))
+        Ok(PathRange {
+            base: Self::parse(base)?,
+            indices: start..end,
+            hardened,
+        })
+    }
+
+    /// Returns an iterator over the normal children of this path with the
+    /// given indices. Indices that do not fit in 31 bits are skipped.
+    pub fn range(&self, indices: std::ops::Range<u32>) -> PathRange {
+        PathRange {
+            base: self.clone(),
+            indices: indices.start.min(0x80000000)..indices.end.min(0x80000000),
+            hardened: false,
+        }
     }
 
     /// Returns the derivation path as a string.
     pub fn string(&self) -> String {
+        self.string_with(HardenedNotation::Apostrophe)
+    }
+
+    /// Returns the derivation path as a string, marking hardened components
+    /// with the given notation.
+    pub fn string_with(&self, notation: HardenedNotation) -> String {
+        if self.0.is_empty() {
+            return "m".to_string();
+        }
+
         let mut result = String::new();
 
         for (i, component) in self.0.iter().enumerate() {
@@ -135,7 +279,10 @@ impl DerivationPath {
             result.push_str(&component.index().to_string());
 
             if component.is_hardened() {
-                result.push('\'');
+                result.push(match notation {
+                    HardenedNotation::Apostrophe => '\'',
+                    HardenedNotation::H => 'h',
+                });
             }
         }
 
@@ -154,7 +301,220 @@ impl DerivationPath {
     }
 
     /// Returns the iterator over the components of the derivation path.
-    pub fn iter(&self) -> std::slice::Iter<ChildNumber> {
+    pub fn iter(&self) -> std::slice::Iter<'_, ChildNumber> {
+        self.0.iter()
+    }
+
+    /// Returns the number of components of the derivation path.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if the derivation path is the master node, `m`.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the path of the given child of this path.
+    pub fn child(&self, child_number: ChildNumber) -> Self {
+        let mut path = self.clone();
+        path.push(child_number);
+        path
+    }
+
+    /// Returns the path of the parent, or None for the master node.
+    pub fn parent(&self) -> Option<Self> {
+        let mut path = self.clone();
+        path.pop().map(|_| path)
+    }
+
+    /// Appends a component to the derivation path.
+    pub fn push(&mut self, child_number: ChildNumber) {
+        self.0.to_mut().push(child_number);
+    }
+
+    /// Removes the last component of the derivation path and returns it.
+    pub fn pop(&mut self) -> Option<ChildNumber> {
+        self.0.to_mut().pop()
+    }
+}
+
+/// Parses a decimal index, rejecting signs and whitespace that
+/// `u32::from_str` would accept or report vaguely.
+fn parse_index(digits: &str) -> Option<u32> {
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+/// Creates a [`DerivationPath`] from a string literal, which is validated at
+/// compile time. The path must start with `m` and components may be hardened
+/// with `'`, `h` or `H`.
+///
+/// ```rust
+/// use wallet_rust::{bips::DerivationPath, derivation_path};
+///
+/// const PATH: DerivationPath = derivation_path!("m/44'/60'/0'/0/0");
+/// assert_eq!(PATH, DerivationPath::parse("m/44'/60'/0'/0/0").unwrap());
+/// ```
+///
+/// A malformed path fails the build:
+///
+/// ```compile_fail
+/// let path = wallet_rust::derivation_path!("m/44'/60'/0'/O/0");
+/// ```
+#[macro_export]
+macro_rules! derivation_path {
+    ($path:expr) => {{
+        const COMPONENTS: &[$crate::bips::ChildNumber] =
+            &$crate::bips::const_parse::<{ $crate::bips::const_len($path) }>($path);
+        $crate::bips::DerivationPath::from_static(COMPONENTS)
+    }};
+}
+
+/// The first Ethereum account as derived by most wallets, `m/44'/60'/0'/0/0`.
+/// Further accounts increment the last component.
+pub const ETHEREUM_DEFAULT: DerivationPath = crate::derivation_path!("m/44'/60'/0'/0/0");
+
+/// The first account of Ledger Live, `m/44'/60'/0'/0/0`. Further accounts
+/// increment the account component instead of the last one.
+pub const LEDGER_LIVE: DerivationPath = crate::derivation_path!("m/44'/60'/0'/0/0");
+
+/// The first account of the legacy Ledger Chrome app and MyEtherWallet,
+/// `m/44'/60'/0'/0`. Further accounts increment the last component.
+pub const LEDGER_LEGACY: DerivationPath = crate::derivation_path!("m/44'/60'/0'/0");
+
+/// The first legacy (P2PKH) Bitcoin address, `m/44'/0'/0'/0/0`.
+pub const BITCOIN_BIP44: DerivationPath = crate::derivation_path!("m/44'/0'/0'/0/0");
+
+/// The first nested SegWit (P2SH-P2WPKH) Bitcoin address, `m/49'/0'/0'/0/0`.
+pub const BITCOIN_BIP49: DerivationPath = crate::derivation_path!("m/49'/0'/0'/0/0");
+
+/// The first native SegWit (P2WPKH) Bitcoin address, `m/84'/0'/0'/0/0`.
+pub const BITCOIN_BIP84: DerivationPath = crate::derivation_path!("m/84'/0'/0'/0/0");
+
+/// The number of components of a path, used by [`derivation_path!`].
+#[doc(hidden)]
+pub const fn const_len(path: &str) -> usize {
+    let bytes = path.as_bytes();
+    let mut count = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'/' {
+            count += 1;
+        }
+        i += 1;
+    }
+    count
+}
+
+/// Parses a path of `N` components at compile time, used by
+/// [`derivation_path!`]. Panics, failing the build, on a malformed path.
+#[doc(hidden)]
+pub const fn const_parse<const N: usize>(path: &str) -> [ChildNumber; N] {
+    let bytes = path.as_bytes();
+    if bytes.is_empty() || bytes[0] != b'm' {
+        panic!("derivation path must start with `m`");
+    }
+
+    let mut result = [ChildNumber(0); N];
+    let mut i = 1;
+    let mut n = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'/' {
+            panic!("derivation path components must be separated by `/`");
+        }
+        i += 1;
+
+        let start = i;
+        let mut index: u32 = 0;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            index = index * 10 + (bytes[i] - b'0') as u32;
+            if index >= 0x80000000 {
+                panic!("derivation path index out of range");
+            }
+            i += 1;
+        }
+        if i == start {
+            panic!("derivation path component must be a number");
+        }
+
+        if i < bytes.len() && matches!(bytes[i], b'\'' | b'h' | b'H') {
+            result[n] = ChildNumber::hardened(index);
+            i += 1;
+        } else {
+            result[n] = ChildNumber::normal(index);
+        }
+        n += 1;
+    }
+
+    result
+}
+
+/// Iterator over the children of a derivation path within a range of
+/// indices, created by [`DerivationPath::range`] and
+/// [`DerivationPath::parse_range`].
+#[derive(Clone, Debug)]
+pub struct PathRange {
+    base: DerivationPath,
+    indices: std::ops::Range<u32>,
+    hardened: bool,
+}
+
+impl PathRange {
+    /// Returns the path the children are derived from.
+    pub fn base(&self) -> &DerivationPath {
+        &self.base
+    }
+
+    fn path(&self, index: u32) -> DerivationPath {
+        self.base.child(match self.hardened {
+            true => ChildNumber::hardened(index),
+            false => ChildNumber::normal(index),
+        })
+    }
+}
+
+impl Iterator for PathRange {
+    type Item = DerivationPath;
+
+    fn next(&mut self) -> Option<DerivationPath> {
+        self.indices.next().map(|index| self.path(index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.indices.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for PathRange {
+    fn next_back(&mut self) -> Option<DerivationPath> {
+        self.indices.next_back().map(|index| self.path(index))
+    }
+}
+
+impl ExactSizeIterator for PathRange {}
+
+impl std::ops::Index<usize> for DerivationPath {
+    type Output = ChildNumber;
+
+    fn index(&self, index: usize) -> &ChildNumber {
+        &self.0[index]
+    }
+}
+
+impl Extend<ChildNumber> for DerivationPath {
+    fn extend<I: IntoIterator<Item = ChildNumber>>(&mut self, iter: I) {
+        self.0.to_mut().extend(iter)
+    }
+}
+
+impl<'a> IntoIterator for &'a DerivationPath {
+    type Item = &'a ChildNumber;
+    type IntoIter = std::slice::Iter<'a, ChildNumber>;
+
+    fn into_iter(self) -> Self::IntoIter {
         self.0.iter()
     }
 }
@@ -165,12 +525,29 @@ impl Default for DerivationPath {
     }
 }
 
+impl FromIterator<ChildNumber> for DerivationPath {
+    fn from_iter<I: IntoIterator<Item = ChildNumber>>(iter: I) -> Self {
+        Self(iter.into_iter().collect::<Vec<_>>().into())
+    }
+}
+
 impl std::fmt::Display for DerivationPath {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "{}", self.string())
     }
 }
 
+impl std::str::FromStr for DerivationPath {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+#[cfg(feature = "serde")]
+crate::types::serde_string!(DerivationPath);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -179,5 +556,186 @@ mod tests {
     fn test_parse() {
         let path = DerivationPath::parse("m/44'/60'/0'/0").unwrap();
         assert_eq!(path.string(), "m/44'/60'/0'/0");
+        assert!(DerivationPath::parse("m").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |path: &str| DerivationPath::parse(path).unwrap_err().to_string();
+        let invalid = |position: usize, component: &str| {
+            Error::InvalidComponent {
+                position,
+                component: component.to_string(),
+            }
+            .to_string()
+        };
+
+        assert_eq!(error(""), Error::Empty.to_string());
+        assert_eq!(error("44'/60'"), Error::MissingMaster.to_string());
+        assert_eq!(error("/44'/60'"), Error::MissingMaster.to_string());
+        assert_eq!(error("M/44'"), Error::MissingMaster.to_string());
+        assert_eq!(
+            error("m/"),
+            Error::EmptyComponent { position: 0 }.to_string()
+        );
+        assert_eq!(
+            error("m/44'//0"),
+            Error::EmptyComponent { position: 1 }.to_string()
+        );
+        assert_eq!(
+            error("m/44'/60'/0'/0/"),
+            Error::EmptyComponent { position: 4 }.to_string()
+        );
+        assert_eq!(error("m/44'/6O'/0'"), invalid(1, "6O'"));
+        assert_eq!(error("m/44''"), invalid(0, "44''"));
+        assert_eq!(error("m/44'/+60"), invalid(1, "+60"));
+        assert_eq!(error("m/44'/ 60"), invalid(1, " 60"));
+        assert_eq!(error("m/h"), invalid(0, "h"));
+        assert_eq!(error("m/2147483648"), invalid(0, "2147483648"));
+
+        assert_eq!(error("m/0/x"), "invalid path component \"x\" at position 1");
+    }
+
+    #[test]
+    fn test_index_range() {
+        assert_eq!(
+            ChildNumber::try_normal(0x7fffffff).unwrap(),
+            ChildNumber::normal(0x7fffffff)
+        );
+        assert_eq!(
+            ChildNumber::try_hardened(44).unwrap(),
+            ChildNumber::hardened(44)
+        );
+        assert!(ChildNumber::try_normal(0x80000000).is_err());
+        assert!(ChildNumber::try_hardened(0x80000000).is_err());
+
+        assert!(DerivationPath::parse("m/2147483647'/2147483647").is_ok());
+        for path in ["m/2147483648", "m/2147483648'", "m/4294967295h"] {
+            assert!(DerivationPath::parse(path).is_err());
+        }
+
+        assert!(DerivationPath::parse_range("m/0..2147483649").is_err());
+        assert_eq!(
+            DerivationPath::parse_range("m/2147483646..2147483648'")
+                .unwrap()
+                .count(),
+            2
+        );
+        assert_eq!(
+            DerivationPath::default()
+                .range(0x7ffffffe..u32::MAX)
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_hardened_notation() {
+        let canonical = DerivationPath::parse("m/44'/60'/0'/0/0").unwrap();
+        for path in ["m/44h/60h/0h/0/0", "m/44H/60H/0H/0/0", "m/44'/60h/0H/0/0"] {
+            assert_eq!(DerivationPath::parse(path).unwrap(), canonical);
+        }
+
+        assert_eq!(
+            canonical.string_with(HardenedNotation::H),
+            "m/44h/60h/0h/0/0"
+        );
+        assert_eq!(
+            canonical.string_with(HardenedNotation::Apostrophe),
+            canonical.string()
+        );
+        assert!(DerivationPath::parse("m/44''/60'").is_err());
+    }
+
+    #[test]
+    fn test_range() {
+        let paths = DerivationPath::parse_range("m/44'/60'/0'/0/0..3")
+            .unwrap()
+            .map(|path| path.string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            ["m/44'/60'/0'/0/0", "m/44'/60'/0'/0/1", "m/44'/60'/0'/0/2"]
+        );
+
+        let mut accounts = DerivationPath::parse_range("m/44'/60'/5..7'").unwrap();
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts.base().string(), "m/44'/60'");
+        assert_eq!(accounts.next_back().unwrap().string(), "m/44'/60'/6'");
+
+        let base = DerivationPath::default();
+        let mut range = base.range(10..12);
+        assert_eq!(range.next(), Some(base.child(ChildNumber::normal(10))));
+        assert_eq!(range.next_back(), Some(base.child(ChildNumber::normal(11))));
+        assert_eq!(range.next(), None);
+        assert_eq!(DerivationPath::parse_range("m/0..0").unwrap().count(), 0);
+
+        for path in ["m/44'/60'/0'/0/0", "m/0/5..2", "m/0/..5", "0..5"] {
+            assert!(DerivationPath::parse_range(path).is_err());
+        }
+    }
+
+    #[test]
+    fn test_macro() {
+        let paths = [
+            (derivation_path!("m"), "m"),
+            (derivation_path!("m/0"), "m/0"),
+            (
+                derivation_path!("m/44h/60H/0'/0/2147483647"),
+                "m/44'/60'/0'/0/2147483647",
+            ),
+            (ETHEREUM_DEFAULT, "m/44'/60'/0'/0/0"),
+            (LEDGER_LEGACY, "m/44'/60'/0'/0"),
+            (BITCOIN_BIP84, "m/84'/0'/0'/0/0"),
+        ];
+        for (path, expected) in paths {
+            assert_eq!(path, DerivationPath::parse(expected).unwrap());
+        }
+
+        let mut path = ETHEREUM_DEFAULT;
+        path.push(ChildNumber::normal(1));
+        assert_eq!(path.string(), "m/44'/60'/0'/0/0/1");
+        assert_eq!(ETHEREUM_DEFAULT.len(), 5);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let path = ETHEREUM_DEFAULT;
+        let json = serde_json::to_string(&path).unwrap();
+        assert_eq!(json, "\"m/44'/60'/0'/0/0\"");
+        assert_eq!(serde_json::from_str::<DerivationPath>(&json).unwrap(), path);
+        assert_eq!(
+            serde_json::from_str::<DerivationPath>("\"m/44h/60h/0h/0/0\"").unwrap(),
+            path
+        );
+        assert!(serde_json::from_str::<DerivationPath>("\"m/44'/x\"").is_err());
+
+        assert_eq!(serde_json::to_string(&path[0]).unwrap(), "2147483692");
+        assert_eq!(
+            serde_json::from_str::<ChildNumber>("2147483692").unwrap(),
+            path[0]
+        );
+    }
+
+    #[test]
+    fn test_manipulation() {
+        let mut path = DerivationPath::parse("m").unwrap();
+        assert!(path.is_empty());
+        assert_eq!(path.string(), "m");
+        assert_eq!(path.parent(), None);
+
+        path.push(ChildNumber::hardened(44));
+        path.extend([ChildNumber::hardened(60), ChildNumber::hardened(0)]);
+        assert_eq!(path.len(), 3);
+        assert_eq!(path[1], ChildNumber::hardened(60));
+
+        let child = path.child(ChildNumber::normal(0));
+        assert_eq!(child.string(), "m/44'/60'/0'/0");
+        assert_eq!(child.parent(), Some(path.clone()));
+
+        assert_eq!(path.pop(), Some(ChildNumber::hardened(0)));
+        assert_eq!(path.string(), "m/44'/60'");
+        assert_eq!((&path).into_iter().count(), 2);
     }
 }
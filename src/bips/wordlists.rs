@@ -42,20 +42,46 @@
 //! laron-wallet = { version = "0.1", features = ["chinese_simplified"] }
 //! ```
 
-use horror::Result;
-use std::collections::HashMap;
+use crate::error::Result;
+use sha2::Digest;
+use std::sync::OnceLock;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
 
 /// Error returned when a word is not found in a wordlist or error
 /// occurs while reading the wordlist.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum WordListError {
+    /// A word or index that is not in the wordlist.
     InvalidWord,
+    /// The embedded wordlist does not match the hash of the official one.
+    IntegrityCheckFailed(Language),
+    /// A word of a phrase is not in the wordlist. The suggestions are
+    /// close to the word the user typed, so the error should not be logged.
+    UnknownWord {
+        /// The position of the word in the phrase, from 0.
+        position: usize,
+        /// The closest words of the wordlist, see [`WordList::suggest`].
+        suggestions: Vec<&'static str>,
+    },
 }
 
 impl std::fmt::Display for WordListError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             WordListError::InvalidWord => write!(f, "Invalid word"),
+            WordListError::IntegrityCheckFailed(language) => {
+                write!(f, "Wordlist integrity check failed: {:?}", language)
+            }
+            WordListError::UnknownWord {
+                position,
+                suggestions,
+            } => {
+                write!(f, "Unknown word {}", position + 1)?;
+                if !suggestions.is_empty() {
+                    write!(f, ", did you mean {}?", suggestions.join(" or "))?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -86,24 +112,119 @@ impl WordList {
 
         &self.0[start..start + count]
     }
+
+    /// Return the words within `max_distance` edits of `word`, closest
+    /// first, to recover from typos.
+    pub fn suggest(&self, word: &str, max_distance: usize) -> Vec<&'static str> {
+        let word = word.chars().collect::<Vec<_>>();
+        let mut suggestions = self
+            .0
+            .iter()
+            .filter_map(|candidate| {
+                let distance = edit_distance(&word, candidate);
+                (distance <= max_distance).then_some((distance, *candidate))
+            })
+            .collect::<Vec<_>>();
+        // stable, words at the same distance keep the order of the list
+        suggestions.sort_by_key(|(distance, _)| *distance);
+        suggestions.into_iter().map(|(_, word)| word).collect()
+    }
+}
+
+/// Return the Levenshtein distance between two words.
+fn edit_distance(a: &[char], b: &str) -> usize {
+    let mut row = (0..=a.len()).collect::<Vec<_>>();
+    for (i, cb) in b.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, ca) in a.iter().enumerate() {
+            let substitution = diagonal + (*ca != cb) as usize;
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[a.len()]
 }
 
 /// A wordmap is a map of words to their index in a wordlist.
+///
+/// It holds the indexes of the words sorted by their NFKD form, so a lookup
+/// is a binary search over the wordlist and allocates nothing. French and
+/// Spanish words are compared without their accents, so "medaille" finds
+/// "médaille".
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct WordMap(HashMap<&'static str, usize>);
+pub struct WordMap {
+    language: Language,
+    words: &'static WordList,
+    sorted: Vec<u16>,
+}
 
 impl WordMap {
+    fn new(language: Language) -> Self {
+        let words = language.wordlist();
+        let mut sorted = (0..words.0.len() as u16).collect::<Vec<_>>();
+        sorted.sort_by(|a, b| {
+            language
+                .lookup_key(words.0[*a as usize])
+                .cmp(language.lookup_key(words.0[*b as usize]))
+        });
+        Self {
+            language,
+            words,
+            sorted,
+        }
+    }
+
+    /// Return the key of the word at `index` in the wordlist.
+    fn key(&self, index: u16) -> impl Iterator<Item = char> + 'static {
+        self.language.lookup_key(self.words.0[index as usize])
+    }
+
     /// get the index of the given word.
     pub fn get_index(&self, word: &str) -> Result<usize> {
-        self.0
-            .get(word)
-            .cloned()
-            .ok_or_else(|| WordListError::InvalidWord.into())
+        let position = self
+            .sorted
+            .binary_search_by(|index| self.key(*index).cmp(self.language.lookup_key(word)))
+            .map_err(|_| WordListError::InvalidWord)?;
+        Ok(self.sorted[position] as usize)
+    }
+
+    /// Get the index of the word equal to the given prefix, or the only word
+    /// starting with it if it has at least four characters. BIP39 makes the
+    /// first four letters of every English word unique.
+    pub fn get_index_by_prefix(&self, prefix: &str) -> Result<usize> {
+        if let Ok(index) = self.get_index(prefix) {
+            return Ok(index);
+        }
+        if self.language.lookup_key(prefix).count() < 4 {
+            return Err(WordListError::InvalidWord.into());
+        }
+
+        // the words starting with the prefix follow it in the sorted order
+        let start = self
+            .sorted
+            .partition_point(|index| self.key(*index).lt(self.language.lookup_key(prefix)));
+        let mut matches = self.sorted[start..].iter().take_while(|index| {
+            let mut key = self.key(**index);
+            self.language
+                .lookup_key(prefix)
+                .all(|c| key.next() == Some(c))
+        });
+        match (matches.next(), matches.next()) {
+            (Some(index), None) => Ok(*index as usize),
+            _ => Err(WordListError::InvalidWord.into()),
+        }
     }
 }
 
 /// Language of the wordlist.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
 pub enum Language {
     English,
     #[cfg(feature = "chinese_simplified")]
@@ -127,79 +248,150 @@ pub enum Language {
 }
 
 impl Language {
-    /// Get the wordlist for the given language.
-    pub fn wordlist(self) -> WordList {
+    /// Get the wordlist for the given language, parsed on first use.
+    pub fn wordlist(self) -> &'static WordList {
+        WORDLISTS[self as usize].get_or_init(|| WordList(self.raw().split_whitespace().collect()))
+    }
+
+    /// Return the separator between the words of a phrase. Japanese
+    /// phrases are written with ideographic spaces (U+3000), the others
+    /// with ASCII spaces.
+    pub fn delimiter(self) -> &'static str {
+        #[cfg(feature = "japanese")]
+        if self == Language::Japanese {
+            return "\u{3000}";
+        }
+        " "
+    }
+
+    /// Check the embedded wordlist against the SHA-256 of the official list
+    /// published with BIP39, failing if the vendored file was altered.
+    pub fn verify(self) -> Result<()> {
+        let hash = hex::encode(sha2::Sha256::digest(self.raw()));
+        if hash != self.official_hash() {
+            return Err(WordListError::IntegrityCheckFailed(self).into());
+        }
+        Ok(())
+    }
+
+    fn raw(self) -> &'static str {
         match self {
-            Language::English => WordList(
-                include_str!("./wordlists/english.txt")
-                    .split_whitespace()
-                    .collect(),
-            ),
+            Language::English => include_str!("./wordlists/english.txt"),
             #[cfg(feature = "chinese_simplified")]
-            Language::ChineseSimplified => WordList(
-                include_str!("./wordlists/chinese_simplified.txt")
-                    .split_whitespace()
-                    .collect(),
-            ),
+            Language::ChineseSimplified => include_str!("./wordlists/chinese_simplified.txt"),
             #[cfg(feature = "chinese_traditional")]
-            Language::ChineseTraditional => WordList(
-                include_str!("./wordlists/chinese_traditional.txt")
-                    .split_whitespace()
-                    .collect(),
-            ),
+            Language::ChineseTraditional => include_str!("./wordlists/chinese_traditional.txt"),
             #[cfg(feature = "czech")]
-            Language::Czech => WordList(
-                include_str!("./wordlists/czech.txt")
-                    .split_whitespace()
-                    .collect(),
-            ),
+            Language::Czech => include_str!("./wordlists/czech.txt"),
             #[cfg(feature = "french")]
-            Language::French => WordList(
-                include_str!("./wordlists/french.txt")
-                    .split_whitespace()
-                    .collect(),
-            ),
+            Language::French => include_str!("./wordlists/french.txt"),
             #[cfg(feature = "italian")]
-            Language::Italian => WordList(
-                include_str!("./wordlists/italian.txt")
-                    .split_whitespace()
-                    .collect(),
-            ),
+            Language::Italian => include_str!("./wordlists/italian.txt"),
             #[cfg(feature = "japanese")]
-            Language::Japanese => WordList(
-                include_str!("./wordlists/japanese.txt")
-                    .split_whitespace()
-                    .collect(),
-            ),
+            Language::Japanese => include_str!("./wordlists/japanese.txt"),
             #[cfg(feature = "korean")]
-            Language::Korean => WordList(
-                include_str!("./wordlists/korean.txt")
-                    .split_whitespace()
-                    .collect(),
-            ),
+            Language::Korean => include_str!("./wordlists/korean.txt"),
             #[cfg(feature = "portuguese")]
-            Language::Portuguese => WordList(
-                include_str!("./wordlists/portuguese.txt")
-                    .split_whitespace()
-                    .collect(),
-            ),
+            Language::Portuguese => include_str!("./wordlists/portuguese.txt"),
             #[cfg(feature = "spanish")]
-            Language::Spanish => WordList(
-                include_str!("./wordlists/spanish.txt")
-                    .split_whitespace()
-                    .collect(),
-            ),
+            Language::Spanish => include_str!("./wordlists/spanish.txt"),
         }
     }
 
-    /// Get the wordmap for the given language.
-    pub fn wordmap(self) -> WordMap {
-        let mut map = HashMap::new();
-        for (i, word) in self.wordlist().0.iter().enumerate() {
-            map.insert(*word, i);
+    fn official_hash(self) -> &'static str {
+        match self {
+            Language::English => "2f5eed53a4727b4bf8880d8f3f199efc90e58503646d9ff8eff3a2ed3b24dbda",
+            #[cfg(feature = "chinese_simplified")]
+            Language::ChineseSimplified => {
+                "5c5942792bd8340cb8b27cd592f1015edf56a8c5b26276ee18a482428e7c5726"
+            }
+            #[cfg(feature = "chinese_traditional")]
+            Language::ChineseTraditional => {
+                "417b26b3d8500a4ae3d59717d7011952db6fc2fb84b807f3f94ac734e89c1b5f"
+            }
+            #[cfg(feature = "czech")]
+            Language::Czech => "7e80e161c3e93d9554c2efb78d4e3cebf8fc727e9c52e03b83b94406bdcc95fc",
+            #[cfg(feature = "french")]
+            Language::French => "ebc3959ab7801a1df6bac4fa7d970652f1df76b683cd2f4003c941c63d517e59",
+            #[cfg(feature = "italian")]
+            Language::Italian => "d392c49fdb700a24cd1fceb237c1f65dcc128f6b34a8aacb58b59384b5c648c2",
+            #[cfg(feature = "japanese")]
+            Language::Japanese => {
+                "2eed0aef492291e061633d7ad8117f1a2b03eb80a29d0e4e3117ac2528d05ffd"
+            }
+            #[cfg(feature = "korean")]
+            Language::Korean => "9e95f86c167de88f450f0aaf89e87f6624a57f973c67b516e338e8e8b8897f60",
+            #[cfg(feature = "portuguese")]
+            Language::Portuguese => {
+                "2685e9c194c82ae67e10ba59d9ea5345a23dc093e92276fc5361f6667d79cd3f"
+            }
+            #[cfg(feature = "spanish")]
+            Language::Spanish => "46846a5a0139d1e3cb77293e521c2865f7bcdb82c44e8d0a06a2cd0ecba48c0b",
         }
-        WordMap(map)
     }
+
+    /// Get the wordmap for the given language, built on first use.
+    pub fn wordmap(self) -> &'static WordMap {
+        WORDMAPS[self as usize].get_or_init(|| WordMap::new(self))
+    }
+
+    /// Return the characters a word is compared by in the wordmap: its NFKD
+    /// form, stripped of combining marks for the languages in [`ACCENTED`].
+    /// Their words are unique without accents, so BIP39 recommends
+    /// accepting them typed without.
+    fn lookup_key(self, word: &str) -> impl Iterator<Item = char> + '_ {
+        let strip = ACCENTED.contains(&self);
+        word.nfkd()
+            .filter(move |c| !(strip && is_combining_mark(*c)))
+    }
+}
+
+/// The enabled languages whose words are matched without their accents.
+const ACCENTED: &[Language] = &[
+    #[cfg(feature = "french")]
+    Language::French,
+    #[cfg(feature = "spanish")]
+    Language::Spanish,
+];
+
+/// All of the languages enabled by the feature flags.
+pub const LANGUAGES: &[Language] = &[
+    Language::English,
+    #[cfg(feature = "chinese_simplified")]
+    Language::ChineseSimplified,
+    #[cfg(feature = "chinese_traditional")]
+    Language::ChineseTraditional,
+    #[cfg(feature = "czech")]
+    Language::Czech,
+    #[cfg(feature = "french")]
+    Language::French,
+    #[cfg(feature = "italian")]
+    Language::Italian,
+    #[cfg(feature = "japanese")]
+    Language::Japanese,
+    #[cfg(feature = "korean")]
+    Language::Korean,
+    #[cfg(feature = "portuguese")]
+    Language::Portuguese,
+    #[cfg(feature = "spanish")]
+    Language::Spanish,
+];
+
+// The variants of `Language` are numbered in the order of `LANGUAGES`, so a
+// language indexes its own cache.
+static WORDLISTS: [OnceLock<WordList>; LANGUAGES.len()] =
+    [const { OnceLock::new() }; LANGUAGES.len()];
+static WORDMAPS: [OnceLock<WordMap>; LANGUAGES.len()] =
+    [const { OnceLock::new() }; LANGUAGES.len()];
+
+/// Check every enabled wordlist against the SHA-256 of the official list.
+/// Institutional users can call this once at startup to make sure the
+/// vendored wordlists were not tampered with.
+pub fn verify_wordlists() -> Result<()> {
+    for language in LANGUAGES {
+        language.verify()?;
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -214,11 +406,73 @@ mod tests {
         assert!(wordlist.get(2048).is_err());
     }
 
+    #[test]
+    fn test_cache() {
+        for language in LANGUAGES {
+            assert!(std::ptr::eq(language.wordlist(), language.wordlist()));
+            assert!(std::ptr::eq(language.wordmap(), language.wordmap()));
+            assert_eq!(language.wordlist().0.len(), 2048);
+        }
+    }
+
+    #[test]
+    fn test_verify_wordlists() {
+        verify_wordlists().unwrap();
+        assert_eq!(LANGUAGES[0], Language::English);
+    }
+
+    #[test]
+    fn test_suggest() {
+        let wordlist = Language::English.wordlist();
+        assert_eq!(wordlist.suggest("aboandon", 2), ["abandon"]);
+        assert_eq!(wordlist.suggest("abandon", 0), ["abandon"]);
+        assert_eq!(wordlist.suggest("lettr", 1), ["letter"]);
+        assert_eq!(&wordlist.suggest("cat", 1)[..3], ["cat", "can", "car"]);
+        assert!(wordlist.suggest("xylophone", 2).is_empty());
+    }
+
     #[test]
     fn test_wordmap() {
         let wordmap = Language::English.wordmap();
         assert_eq!(wordmap.get_index("abandon").unwrap(), 0);
         assert_eq!(wordmap.get_index("zoo").unwrap(), 2047);
         assert!(wordmap.get_index("zonee").is_err());
+
+        assert_eq!(wordmap.get_index_by_prefix("aban").unwrap(), 0);
+        assert_eq!(wordmap.get_index_by_prefix("abando").unwrap(), 0);
+        assert_eq!(wordmap.get_index_by_prefix("cat").unwrap(), 286);
+        assert_eq!(wordmap.get_index_by_prefix("catc").unwrap(), 288);
+        assert!(wordmap.get_index_by_prefix("aba").is_err());
+        assert!(wordmap.get_index_by_prefix("abandonx").is_err());
+        for (index, word) in Language::English.wordlist().0.iter().enumerate() {
+            let prefix = word.chars().take(4).collect::<String>();
+            assert_eq!(wordmap.get_index_by_prefix(&prefix).unwrap(), index);
+        }
+    }
+
+    #[test]
+    fn test_wordmap_languages() {
+        // the Chinese and Japanese wordlists are not in byte order
+        for language in LANGUAGES {
+            let wordmap = language.wordmap();
+            for (index, word) in language.wordlist().0.iter().enumerate() {
+                assert_eq!(wordmap.get_index(word).unwrap(), index);
+            }
+        }
+    }
+
+    #[cfg(all(feature = "french", feature = "spanish"))]
+    #[test]
+    fn test_wordmap_accents() {
+        let wordmap = Language::French.wordmap();
+        let index = wordmap.get_index("médaille").unwrap();
+        assert_eq!(index, 1218);
+        assert_eq!(wordmap.get_index("medaille").unwrap(), index);
+        assert_eq!(wordmap.get_index_by_prefix("meda").unwrap(), index);
+
+        let wordmap = Language::Spanish.wordmap();
+        assert_eq!(wordmap.get_index("abaco").unwrap(), 0);
+        assert_eq!(wordmap.get_index("accion").unwrap(), 14);
+        assert_eq!(wordmap.get_index("acción").unwrap(), 14);
     }
 }
@@ -0,0 +1,158 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # BLS12-381
+//!
+//! Keys of the BLS signature scheme of the Ethereum consensus layer, which
+//! puts public keys in the `G1` group of the BLS12-381 curve and
+//! signatures in `G2`.
+//!
+//! Only the keys are implemented, to write validator keystores and derive
+//! validator keys with [`crate::eips::eip2333`]. Signing, which needs `G2`
+//! and the pairing, is left to the validator client.
+//!
+//! The public key is computed with the constant time group arithmetic of
+//! [`bls12_381`], so the secret does not leak through timing.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::bls::SecretKey;
+//!
+//! let secret = hex::decode("000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f").unwrap();
+//! let key = SecretKey::from_bytes(&secret.try_into().unwrap()).unwrap();
+//! assert_eq!(
+//!     hex::encode(key.public_key().as_bytes()),
+//!     "9612d7a727c9d0a22e185a1c768478dfe919cada9266988cb32359c11f2b7b27f4ae4040902382ae2910c15e2b420d07"
+//! );
+//! ```
+
+use bls12_381::{G1Affine, Scalar};
+use crypto_bigint::U256;
+use zeroize::Zeroizing;
+
+use crate::error::Result;
+
+/// Order of the `G1` and `G2` groups.
+pub(crate) const R: U256 =
+    U256::from_be_hex("73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001");
+
+/// Error returned when reading BLS keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlsError {
+    /// The secret key is zero or not below the group order.
+    InvalidSecretKey,
+    /// The seed is shorter than the 32 bytes EIP-2333 requires.
+    SeedLength,
+    /// The derivation path is not `m` followed by decimal indices.
+    InvalidPath,
+}
+
+impl std::fmt::Display for BlsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            BlsError::InvalidSecretKey => write!(f, "Invalid BLS secret key"),
+            BlsError::SeedLength => write!(f, "BLS key derivation needs a 32 byte seed"),
+            BlsError::InvalidPath => write!(f, "Invalid BLS derivation path"),
+        }
+    }
+}
+
+impl std::error::Error for BlsError {}
+
+/// A BLS secret key, a scalar below the group order.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SecretKey {
+    secret: Zeroizing<[u8; 32]>,
+    public_key: PublicKey,
+}
+
+impl SecretKey {
+    /// Create a key from its 32 byte big-endian encoding.
+    pub fn from_bytes(secret: &[u8; 32]) -> Result<Self> {
+        let scalar = Zeroizing::new(U256::from_be_slice(secret));
+        if *scalar == U256::ZERO || *scalar >= R {
+            return Err(BlsError::InvalidSecretKey.into());
+        }
+
+        let mut le = Zeroizing::new(*secret);
+        le.reverse();
+        let scalar = Zeroizing::new(Scalar::from_bytes(&le).expect("below the group order"));
+        let point = G1Affine::from(G1Affine::generator() * *scalar);
+
+        Ok(Self {
+            secret: Zeroizing::new(*secret),
+            public_key: PublicKey(point.to_compressed()),
+        })
+    }
+
+    /// Return the 32 byte big-endian encoding of the key.
+    pub fn to_bytes(&self) -> Zeroizing<[u8; 32]> {
+        self.secret.clone()
+    }
+
+    /// Return the public key.
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+}
+
+impl std::fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("SecretKey")
+            .field("public_key", &self.public_key)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A BLS public key, the compressed `G1` point.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PublicKey([u8; 48]);
+
+impl PublicKey {
+    /// Return the 48 byte encoding of the key.
+    pub fn as_bytes(&self) -> &[u8; 48] {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for PublicKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "PublicKey({})", hex::encode(self.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto_bigint::Encoding;
+
+    #[test]
+    fn test_public_key() {
+        let mut one = [0u8; 32];
+        one[31] = 1;
+        assert_eq!(
+            hex::encode(SecretKey::from_bytes(&one).unwrap().public_key().as_bytes()),
+            "97f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb"
+        );
+
+        // r - 1 is -1, the negated generator
+        let minus_one = (R.wrapping_sub(&U256::ONE)).to_be_bytes();
+        assert_eq!(
+            hex::encode(SecretKey::from_bytes(&minus_one).unwrap().public_key().as_bytes()),
+            "b7f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb"
+        );
+
+        assert!(SecretKey::from_bytes(&[0; 32]).is_err());
+        assert!(SecretKey::from_bytes(&R.to_be_bytes()).is_err());
+    }
+}
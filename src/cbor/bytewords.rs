@@ -0,0 +1,110 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::CborError;
+use crate::error::Result;
+
+/// The 256 words of
+/// [Bytewords](https://github.com/BlockchainCommons/Research/blob/master/papers/bcr-2020-012-bytewords.md),
+/// four letters each, in the order of the bytes. UR strings shorten them
+/// to their first and last letters.
+const WORDS: &str = concat!(
+    "ableacidalsoapexaquaarchatomauntawayaxisbackbaldbarnbeltbetabias",
+    "bluebodybragbrewbulbbuzzcalmcashcatschefcityclawcodecolacookcost",
+    "cruxcurlcuspcyandarkdatadaysdelidicedietdoordowndrawdropdrumdull",
+    "dutyeacheasyechoedgeepicevenexamexiteyesfactfairfernfigsfilmfish",
+    "fizzflapflewfluxfoxyfreefrogfuelfundgalagamegeargemsgiftgirlglow",
+    "goodgraygrimgurugushgyrohalfhanghardhawkheathelphighhillholyhope",
+    "hornhutsicedideaidleinchinkyintoirisironitemjadejazzjoinjoltjowl",
+    "judojugsjumpjunkjurykeepkenokeptkeyskickkilnkingkitekiwiknoblamb",
+    "lavalazyleaflegsliarlimplionlistlogoloudloveluaulucklungmainmany",
+    "mathmazememomenumeowmildmintmissmonknailnavyneednewsnextnoonnote",
+    "numbobeyoboeomitonyxopenovalowlspaidpartpeckplaypluspoempoolpose",
+    "puffpumapurrquadquizraceramprealredorichroadrockroofrubyruinruns",
+    "rustsafesagascarsetssilkskewslotsoapsolosongstubsurfswantacotask",
+    "taxitenttiedtimetinytoiltombtoystriptunatwinuglyundouniturgeuser",
+    "vastveryvetovialvibeviewvisavoidvowswallwandwarmwaspwavewaxywebs",
+    "whatwhenwhizwolfworkyankyawnyellyogayurtzapszerozestzinczonezoom",
+);
+
+/// Return the minimal bytewords of `data` followed by its checksum.
+pub(crate) fn encode(data: &[u8]) -> String {
+    let checksum = crc32(data).to_be_bytes();
+    let mut text = String::with_capacity((data.len() + 4) * 2);
+    for byte in data.iter().chain(&checksum) {
+        let word = &WORDS.as_bytes()[*byte as usize * 4..][..4];
+        text.push(word[0] as char);
+        text.push(word[3] as char);
+    }
+    text
+}
+
+/// Decode minimal bytewords, checking the checksum.
+pub(crate) fn decode(text: &str) -> Result<Vec<u8>> {
+    let text = text.as_bytes();
+    if !text.len().is_multiple_of(2) || text.len() < 8 {
+        return Err(CborError::InvalidUr.into());
+    }
+
+    let mut data = text
+        .chunks_exact(2)
+        .map(|pair| {
+            WORDS
+                .as_bytes()
+                .chunks_exact(4)
+                .position(|word| word[0] == pair[0] && word[3] == pair[1])
+                .map(|byte| byte as u8)
+                .ok_or(CborError::InvalidUr)
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let checksum = data.split_off(data.len() - 4);
+    if crc32(&data).to_be_bytes()[..] != checksum[..] {
+        return Err(CborError::InvalidChecksum.into());
+    }
+    Ok(data)
+}
+
+/// The CRC-32 of ISO 3309, as used by zlib.
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bytewords() {
+        // example of BCR-2020-012
+        let data = [0, 1, 2, 128, 255];
+        assert_eq!(encode(&data), "aeadaolazmjendeoti");
+        assert_eq!(decode("aeadaolazmjendeoti").unwrap(), data);
+
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+        assert_eq!(
+            decode("aeadaolazmjendeota").unwrap_err(),
+            CborError::InvalidChecksum.into()
+        );
+        assert!(decode("aeadaolazmjendeot").is_err());
+        assert!(decode("qqadaolazmjendeoti").is_err());
+        assert!(decode("aeaeaeae").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,368 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::error::Result;
+
+const MAJOR_UNSIGNED: u8 = 0;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+const MAJOR_TAG: u8 = 6;
+const MAJOR_SIMPLE: u8 = 7;
+
+const SIMPLE_FALSE: u64 = 20;
+const SIMPLE_TRUE: u64 = 21;
+
+/// Deepest nesting of arrays, maps and tags skipped before giving up.
+const MAX_DEPTH: usize = 32;
+
+/// Error returned when decoding malformed or unexpected CBOR data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CborError {
+    /// The input ended before the value was complete.
+    UnexpectedEnd,
+    /// The major type of the item is not the expected one.
+    UnexpectedType(u8),
+    /// The tag of the item is not the expected one.
+    UnexpectedTag(u64),
+    /// The item uses an encoding that is not supported (e.g. indefinite length).
+    Unsupported,
+    /// There are bytes left after the decoded value.
+    TrailingBytes,
+    /// A required map key is missing.
+    MissingKey(u64),
+    /// A value is out of range for its type.
+    InvalidValue,
+    /// The items are nested deeper than the decoder follows.
+    TooDeep,
+    /// The UR string or one of its parts is malformed.
+    InvalidUr,
+    /// The checksum of a UR does not match its content.
+    InvalidChecksum,
+}
+
+impl std::fmt::Display for CborError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CborError::UnexpectedEnd => write!(f, "unexpected end of CBOR data"),
+            CborError::UnexpectedType(ty) => write!(f, "unexpected CBOR major type: {}", ty),
+            CborError::UnexpectedTag(tag) => write!(f, "unexpected CBOR tag: {}", tag),
+            CborError::Unsupported => write!(f, "unsupported CBOR encoding"),
+            CborError::TrailingBytes => write!(f, "trailing bytes after CBOR value"),
+            CborError::MissingKey(key) => write!(f, "missing CBOR map key: {}", key),
+            CborError::InvalidValue => write!(f, "invalid CBOR value"),
+            CborError::TooDeep => write!(f, "CBOR items nested too deep"),
+            CborError::InvalidUr => write!(f, "invalid UR"),
+            CborError::InvalidChecksum => write!(f, "invalid UR checksum"),
+        }
+    }
+}
+
+impl std::error::Error for CborError {}
+
+/// A minimal CBOR encoder producing canonical (shortest form) items.
+#[derive(Debug, Clone, Default)]
+pub struct Encoder {
+    buf: Vec<u8>,
+}
+
+impl Encoder {
+    /// Create a new empty encoder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn head(&mut self, major: u8, value: u64) {
+        let major = major << 5;
+        if value < 24 {
+            self.buf.push(major | value as u8);
+        } else if value <= u8::MAX as u64 {
+            self.buf.push(major | 24);
+            self.buf.push(value as u8);
+        } else if value <= u16::MAX as u64 {
+            self.buf.push(major | 25);
+            self.buf.extend_from_slice(&(value as u16).to_be_bytes());
+        } else if value <= u32::MAX as u64 {
+            self.buf.push(major | 26);
+            self.buf.extend_from_slice(&(value as u32).to_be_bytes());
+        } else {
+            self.buf.push(major | 27);
+            self.buf.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+
+    /// Write an unsigned integer.
+    pub fn unsigned(&mut self, value: u64) -> &mut Self {
+        self.head(MAJOR_UNSIGNED, value);
+        self
+    }
+
+    /// Write a byte string.
+    pub fn bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        self.head(MAJOR_BYTES, bytes.len() as u64);
+        self.buf.extend_from_slice(bytes);
+        self
+    }
+
+    /// Write a text string.
+    pub fn text(&mut self, text: &str) -> &mut Self {
+        self.head(MAJOR_TEXT, text.len() as u64);
+        self.buf.extend_from_slice(text.as_bytes());
+        self
+    }
+
+    /// Write the header of an array with the given number of items.
+    pub fn array(&mut self, len: usize) -> &mut Self {
+        self.head(MAJOR_ARRAY, len as u64);
+        self
+    }
+
+    /// Write the header of a map with the given number of entries.
+    pub fn map(&mut self, len: usize) -> &mut Self {
+        self.head(MAJOR_MAP, len as u64);
+        self
+    }
+
+    /// Write a semantic tag for the next item.
+    pub fn tag(&mut self, tag: u64) -> &mut Self {
+        self.head(MAJOR_TAG, tag);
+        self
+    }
+
+    /// Write a boolean.
+    pub fn bool(&mut self, value: bool) -> &mut Self {
+        self.head(MAJOR_SIMPLE, if value { SIMPLE_TRUE } else { SIMPLE_FALSE });
+        self
+    }
+
+    /// Return the encoded bytes.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+/// A minimal CBOR decoder over a byte slice.
+#[derive(Debug, Clone)]
+pub struct Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    /// Create a new decoder over the given bytes.
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or(CborError::UnexpectedEnd)?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn peek_head(&self) -> Result<(u8, u8)> {
+        let byte = *self.data.get(self.pos).ok_or(CborError::UnexpectedEnd)?;
+        Ok((byte >> 5, byte & 0x1f))
+    }
+
+    fn head(&mut self, expected: u8) -> Result<u64> {
+        let (major, info) = self.peek_head()?;
+        if major != expected {
+            return Err(CborError::UnexpectedType(major).into());
+        }
+        self.pos += 1;
+
+        let value = match info {
+            0..=23 => info as u64,
+            24 => self.take(1)?[0] as u64,
+            25 => u16::from_be_bytes(self.take(2)?.try_into().expect("2 bytes")) as u64,
+            26 => u32::from_be_bytes(self.take(4)?.try_into().expect("4 bytes")) as u64,
+            27 => u64::from_be_bytes(self.take(8)?.try_into().expect("8 bytes")),
+            _ => return Err(CborError::Unsupported.into()),
+        };
+
+        Ok(value)
+    }
+
+    /// Return the major type of the next item without consuming it.
+    pub fn peek_type(&self) -> Result<u8> {
+        Ok(self.peek_head()?.0)
+    }
+
+    /// Read an unsigned integer.
+    pub fn unsigned(&mut self) -> Result<u64> {
+        self.head(MAJOR_UNSIGNED)
+    }
+
+    /// Read an unsigned integer that must fit in 32 bits.
+    pub fn u32(&mut self) -> Result<u32> {
+        Ok(u32::try_from(self.unsigned()?).map_err(|_| CborError::InvalidValue)?)
+    }
+
+    /// Read a byte string.
+    pub fn bytes(&mut self) -> Result<&'a [u8]> {
+        let len = self.head(MAJOR_BYTES)?;
+        self.take(length(len)?)
+    }
+
+    /// Read a text string.
+    pub fn text(&mut self) -> Result<&'a str> {
+        let len = self.head(MAJOR_TEXT)?;
+        let text = self.take(length(len)?)?;
+        Ok(std::str::from_utf8(text).map_err(|_| CborError::InvalidValue)?)
+    }
+
+    /// Read the header of an array, returning the number of items.
+    pub fn array(&mut self) -> Result<usize> {
+        length(self.head(MAJOR_ARRAY)?)
+    }
+
+    /// Read the header of a map, returning the number of entries.
+    pub fn map(&mut self) -> Result<usize> {
+        length(self.head(MAJOR_MAP)?)
+    }
+
+    /// Read a semantic tag.
+    pub fn tag(&mut self) -> Result<u64> {
+        self.head(MAJOR_TAG)
+    }
+
+    /// Consume the given tag if the next item is tagged, failing when the
+    /// item carries a different tag.
+    pub fn optional_tag(&mut self, expected: u64) -> Result<()> {
+        if self.peek_type()? == MAJOR_TAG {
+            let tag = self.tag()?;
+            if tag != expected {
+                return Err(CborError::UnexpectedTag(tag).into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Read a boolean.
+    pub fn bool(&mut self) -> Result<bool> {
+        match self.head(MAJOR_SIMPLE)? {
+            SIMPLE_FALSE => Ok(false),
+            SIMPLE_TRUE => Ok(true),
+            _ => Err(CborError::InvalidValue.into()),
+        }
+    }
+
+    /// Skip the next item, including any nested items.
+    pub fn skip(&mut self) -> Result<()> {
+        self.skip_nested(0)
+    }
+
+    fn skip_nested(&mut self, depth: usize) -> Result<()> {
+        if depth > MAX_DEPTH {
+            return Err(CborError::TooDeep.into());
+        }
+        match self.peek_type()? {
+            MAJOR_BYTES => {
+                self.bytes()?;
+            }
+            MAJOR_TEXT => {
+                self.text()?;
+            }
+            MAJOR_ARRAY => {
+                for _ in 0..self.array()? {
+                    self.skip_nested(depth + 1)?;
+                }
+            }
+            MAJOR_MAP => {
+                for _ in 0..self.map()? {
+                    self.skip_nested(depth + 1)?;
+                    self.skip_nested(depth + 1)?;
+                }
+            }
+            MAJOR_TAG => {
+                self.tag()?;
+                self.skip_nested(depth + 1)?;
+            }
+            major => {
+                self.head(major)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Ensure all of the input has been consumed.
+    pub fn finish(&self) -> Result<()> {
+        if self.pos != self.data.len() {
+            return Err(CborError::TrailingBytes.into());
+        }
+        Ok(())
+    }
+}
+
+fn length(len: u64) -> Result<usize> {
+    Ok(usize::try_from(len).map_err(|_| CborError::InvalidValue)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integers() {
+        for value in [
+            0u64,
+            23,
+            24,
+            255,
+            256,
+            65535,
+            65536,
+            u32::MAX as u64,
+            u64::MAX,
+        ] {
+            let mut encoder = Encoder::new();
+            encoder.unsigned(value);
+            let bytes = encoder.into_bytes();
+            let mut decoder = Decoder::new(&bytes);
+            assert_eq!(decoder.unsigned().unwrap(), value);
+            decoder.finish().unwrap();
+        }
+
+        let mut encoder = Encoder::new();
+        encoder.unsigned(500);
+        assert_eq!(encoder.into_bytes(), vec![0x19, 0x01, 0xf4]);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let mut decoder = Decoder::new(&[0x58, 0x20, 0x00]);
+        assert!(decoder.bytes().is_err());
+    }
+
+    #[test]
+    fn test_skip_depth() {
+        let mut nested = vec![0x81; MAX_DEPTH];
+        nested.push(0x00);
+        let mut decoder = Decoder::new(&nested);
+        decoder.skip().unwrap();
+        decoder.finish().unwrap();
+
+        let deep = vec![0x81; 100_000];
+        assert_eq!(
+            Decoder::new(&deep).skip().unwrap_err(),
+            CborError::TooDeep.into()
+        );
+    }
+}
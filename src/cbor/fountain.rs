@@ -0,0 +1,434 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use sha2::{Digest, Sha256};
+
+use super::{bytewords::crc32, CborError, Decoder, Encoder, FromCbor, ToCbor};
+use crate::error::Result;
+
+/// Largest number of fragments accepted from a part, far above what an
+/// animated QR code carries.
+const MAX_FRAGMENTS: usize = 1024;
+
+/// A part of a message, encoded in CBOR in the body of a multipart UR.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Part {
+    /// Number of the part, from 1.
+    pub(crate) sequence: u32,
+    /// Number of fragments of the message.
+    pub(crate) fragments: usize,
+    /// Length of the message.
+    pub(crate) message_len: usize,
+    /// CRC-32 of the message.
+    pub(crate) checksum: u32,
+    /// The XOR of the fragments of the part.
+    pub(crate) data: Vec<u8>,
+}
+
+impl Part {
+    /// Return the indices of the fragments mixed into the part.
+    fn indices(&self) -> Vec<usize> {
+        let sequence = self.sequence as usize;
+        if sequence <= self.fragments {
+            return vec![sequence - 1];
+        }
+
+        let seed = [self.sequence.to_be_bytes(), self.checksum.to_be_bytes()].concat();
+        let mut rng = Xoshiro256::new(&seed);
+        let degree = rng.degree(self.fragments);
+        let mut indices = rng.shuffle((0..self.fragments).collect());
+        indices.truncate(degree);
+        indices
+    }
+}
+
+impl ToCbor for Part {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder
+            .array(5)
+            .unsigned(self.sequence as u64)
+            .unsigned(self.fragments as u64)
+            .unsigned(self.message_len as u64)
+            .unsigned(self.checksum as u64)
+            .bytes(&self.data);
+    }
+}
+
+impl FromCbor for Part {
+    fn decode(decoder: &mut Decoder) -> Result<Self> {
+        if decoder.array()? != 5 {
+            return Err(CborError::InvalidUr.into());
+        }
+        let part = Self {
+            sequence: decoder.u32()?,
+            fragments: decoder.u32()? as usize,
+            message_len: decoder.u32()? as usize,
+            checksum: decoder.u32()?,
+            data: decoder.bytes()?.to_vec(),
+        };
+
+        // the fragments must be just enough to hold the message
+        let len = part.data.len();
+        let valid = part.sequence > 0
+            && (1..=MAX_FRAGMENTS).contains(&part.fragments)
+            && part.message_len > (part.fragments - 1) * len
+            && part.message_len <= part.fragments * len;
+        if !valid {
+            return Err(CborError::InvalidUr.into());
+        }
+        Ok(part)
+    }
+}
+
+/// Split a message into fragments and emit an endless sequence of parts,
+/// the fountain code of
+/// [BCR-2020-005](https://github.com/BlockchainCommons/Research/blob/master/papers/bcr-2020-005-ur.md).
+///
+/// The first parts carry the fragments in order, the next ones the XOR of
+/// fragments picked by a generator seeded with the number of the part, so
+/// that a receiver joining at any point recovers the message from enough
+/// parts.
+#[derive(Debug, Clone)]
+pub(crate) struct FountainEncoder {
+    fragments: Vec<Vec<u8>>,
+    message_len: usize,
+    checksum: u32,
+    sequence: u32,
+}
+
+impl FountainEncoder {
+    /// Split `message`, which must not be empty, into fragments of at most
+    /// `max_fragment_len` bytes, all of the same length.
+    pub(crate) fn new(message: &[u8], max_fragment_len: usize) -> Self {
+        let count = message.len().div_ceil(max_fragment_len.max(1));
+        let fragment_len = message.len().div_ceil(count);
+
+        let mut padded = message.to_vec();
+        padded.resize(message.len().next_multiple_of(fragment_len), 0);
+        Self {
+            fragments: padded.chunks(fragment_len).map(<[u8]>::to_vec).collect(),
+            message_len: message.len(),
+            checksum: crc32(message),
+            sequence: 0,
+        }
+    }
+
+    /// Return the number of fragments, the number of parts needed without
+    /// any loss.
+    pub(crate) fn fragments(&self) -> usize {
+        self.fragments.len()
+    }
+
+    /// Return the next part.
+    pub(crate) fn next_part(&mut self) -> Part {
+        self.sequence = self.sequence.wrapping_add(1).max(1);
+        let mut part = Part {
+            sequence: self.sequence,
+            fragments: self.fragments.len(),
+            message_len: self.message_len,
+            checksum: self.checksum,
+            data: Vec::new(),
+        };
+
+        let mut data = vec![0u8; self.fragments[0].len()];
+        for index in part.indices() {
+            xor(&mut data, &self.fragments[index]);
+        }
+        part.data = data;
+        part
+    }
+}
+
+/// Collect parts until the message can be recovered.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FountainDecoder {
+    /// The first part received, which the next ones must agree with.
+    first: Option<Part>,
+    /// The fragments recovered so far.
+    fragments: Vec<Option<Vec<u8>>>,
+    /// Parts still mixing several unknown fragments.
+    mixed: Vec<(Vec<usize>, Vec<u8>)>,
+    message: Option<Vec<u8>>,
+}
+
+impl FountainDecoder {
+    /// Add a part, returning the message once all of its fragments are
+    /// known.
+    pub(crate) fn receive(&mut self, part: Part) -> Result<Option<&[u8]>> {
+        if self.message.is_some() {
+            return Ok(self.message.as_deref());
+        }
+        match &self.first {
+            None => self.fragments = vec![None; part.fragments],
+            Some(first) => {
+                let consistent = part.fragments == first.fragments
+                    && part.message_len == first.message_len
+                    && part.checksum == first.checksum
+                    && part.data.len() == first.data.len();
+                if !consistent {
+                    return Err(CborError::InvalidUr.into());
+                }
+            }
+        }
+
+        let mut indices = part.indices();
+        indices.sort_unstable();
+        let mut queue = vec![(indices, part.data.clone())];
+        if self.first.is_none() {
+            self.first = Some(part);
+        }
+        while let Some((mut indices, mut data)) = queue.pop() {
+            // remove the fragments already known
+            indices.retain(|index| match &self.fragments[*index] {
+                Some(fragment) => {
+                    xor(&mut data, fragment);
+                    false
+                }
+                None => true,
+            });
+
+            match indices[..] {
+                [] => {}
+                [index] => {
+                    // the new fragment may reduce the parts kept so far
+                    let (reduced, mixed) = std::mem::take(&mut self.mixed)
+                        .into_iter()
+                        .partition(|(indices, _)| indices.contains(&index));
+                    self.mixed = mixed;
+                    queue.extend(reduced);
+                    self.fragments[index] = Some(data);
+                }
+                _ => {
+                    if !self.mixed.iter().any(|(known, _)| *known == indices) {
+                        self.mixed.push((indices, data));
+                    }
+                }
+            }
+        }
+
+        if self.fragments.iter().any(Option::is_none) {
+            return Ok(None);
+        }
+        let first = self.first.as_ref().expect("a part was received");
+        let mut message = self
+            .fragments
+            .iter()
+            .flatten()
+            .flatten()
+            .copied()
+            .collect::<Vec<_>>();
+        message.truncate(first.message_len);
+        if crc32(&message) != first.checksum {
+            return Err(CborError::InvalidChecksum.into());
+        }
+        self.message = Some(message);
+        Ok(self.message.as_deref())
+    }
+}
+
+fn xor(data: &mut [u8], other: &[u8]) {
+    for (a, b) in data.iter_mut().zip(other) {
+        *a ^= b;
+    }
+}
+
+/// The xoshiro256** generator, seeded with the SHA-256 of some data so
+/// that both ends pick the same fragments.
+struct Xoshiro256([u64; 4]);
+
+impl Xoshiro256 {
+    fn new(seed: &[u8]) -> Self {
+        let hash = Sha256::digest(seed);
+        Self(std::array::from_fn(|i| {
+            u64::from_be_bytes(hash[i * 8..i * 8 + 8].try_into().expect("8 bytes"))
+        }))
+    }
+
+    fn next(&mut self) -> u64 {
+        let s = &mut self.0;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    fn next_double(&mut self) -> f64 {
+        self.next() as f64 / (u64::MAX as f64 + 1.0)
+    }
+
+    /// Return an integer between `low` and `high` included.
+    fn next_int(&mut self, low: usize, high: usize) -> usize {
+        (self.next_double() * (high - low + 1) as f64) as usize + low
+    }
+
+    fn shuffle(&mut self, mut items: Vec<usize>) -> Vec<usize> {
+        let mut shuffled = Vec::with_capacity(items.len());
+        while !items.is_empty() {
+            let index = self.next_int(0, items.len() - 1);
+            shuffled.push(items.remove(index));
+        }
+        shuffled
+    }
+
+    /// Pick the number of fragments of a mixed part, `k` out of `count`
+    /// with a probability proportional to `1 / k`, using the alias method.
+    fn degree(&mut self, count: usize) -> usize {
+        let sum: f64 = (1..=count).map(|k| 1.0 / k as f64).sum();
+        let scale = count as f64 / sum;
+        let mut weights = (1..=count)
+            .map(|k| 1.0 / k as f64 * scale)
+            .collect::<Vec<_>>();
+
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) =
+            (0..count).rev().partition(|i| weights[*i] < 1.0);
+        let mut probabilities = vec![1.0; count];
+        let mut aliases = vec![0; count];
+        while let (Some(&a), Some(&g)) = (small.last(), large.last()) {
+            small.pop();
+            large.pop();
+            probabilities[a] = weights[a];
+            aliases[a] = g;
+            weights[g] += weights[a] - 1.0;
+            if weights[g] < 1.0 {
+                small.push(g);
+            } else {
+                large.push(g);
+            }
+        }
+
+        let r1 = self.next_double();
+        let r2 = self.next_double();
+        let i = (count as f64 * r1) as usize;
+        let degree = if r2 < probabilities[i] { i } else { aliases[i] };
+        degree + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xoshiro() {
+        // vector of the reference implementation
+        let mut rng = Xoshiro256::new(b"Wolf");
+        let numbers = (0..10).map(|_| rng.next() % 100).collect::<Vec<_>>();
+        assert_eq!(numbers, [42, 81, 85, 8, 82, 84, 76, 73, 70, 88]);
+    }
+
+    #[test]
+    fn test_indices() {
+        // vector of the reference implementation, a message of 1024 bytes
+        // drawn from the generator split into 11 fragments
+        let mut rng = Xoshiro256::new(b"Wolf");
+        let message = (0..1024)
+            .map(|_| rng.next_int(0, 255) as u8)
+            .collect::<Vec<_>>();
+        let mut encoder = FountainEncoder::new(&message, 100);
+        assert_eq!(encoder.fragments(), 11);
+
+        let expected: [&[usize]; 22] = [
+            &[0],
+            &[1],
+            &[2],
+            &[3],
+            &[4],
+            &[5],
+            &[6],
+            &[7],
+            &[8],
+            &[9],
+            &[10],
+            &[9],
+            &[2, 5, 6, 8, 9, 10],
+            &[8],
+            &[1, 5],
+            &[1],
+            &[0, 2, 4, 5, 8, 10],
+            &[5],
+            &[2],
+            &[2],
+            &[0, 1, 3, 4, 5, 7, 9, 10],
+            &[0, 1, 2, 3, 5, 6, 8, 9, 10],
+        ];
+        for indices in expected {
+            let mut actual = encoder.next_part().indices();
+            actual.sort_unstable();
+            assert_eq!(actual, indices);
+        }
+    }
+
+    #[test]
+    fn test_fountain() {
+        let message = (0..=255u8).cycle().take(1000).collect::<Vec<_>>();
+        let mut encoder = FountainEncoder::new(&message, 100);
+        assert_eq!(encoder.fragments(), 10);
+
+        // every third part is lost
+        let mut decoder = FountainDecoder::default();
+        let mut received = 0;
+        let decoded = loop {
+            let part = encoder.next_part();
+            if part.sequence.is_multiple_of(3) {
+                continue;
+            }
+            let part = Part::from_cbor(&part.to_cbor()).unwrap();
+            received += 1;
+            assert!(received < 100);
+            if let Some(decoded) = decoder.receive(part).unwrap() {
+                break decoded.to_vec();
+            }
+        };
+        assert_eq!(decoded, message);
+    }
+
+    #[test]
+    fn test_invalid_part() {
+        let mut encoder = FountainEncoder::new(&[7; 50], 10);
+        let part = encoder.next_part();
+        assert!(Part::from_cbor(
+            &Part {
+                sequence: 0,
+                ..part.clone()
+            }
+            .to_cbor()
+        )
+        .is_err());
+        assert!(Part::from_cbor(
+            &Part {
+                fragments: 2,
+                ..part.clone()
+            }
+            .to_cbor()
+        )
+        .is_err());
+        assert!(Part::from_cbor(
+            &Part {
+                message_len: 51,
+                ..part.clone()
+            }
+            .to_cbor()
+        )
+        .is_err());
+
+        let mut decoder = FountainDecoder::default();
+        decoder.receive(part).unwrap();
+        let other = FountainEncoder::new(&[8; 50], 10).next_part();
+        assert!(decoder.receive(other).is_err());
+    }
+}
@@ -0,0 +1,181 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::{keypath::TAG_KEYPATH, CborError, Decoder, Encoder, FromCbor, ToCbor};
+use crate::{
+    bips::{bip32::ExtendedKey, DerivationPath},
+    error::Result,
+    types::B256,
+};
+
+/// CBOR tag of the `crypto-hdkey` type.
+const TAG_HDKEY: u64 = 303;
+
+const KEY_DATA: u64 = 3;
+const KEY_CHAIN_CODE: u64 = 4;
+const KEY_ORIGIN: u64 = 6;
+const KEY_PARENT_FINGERPRINT: u64 = 8;
+
+/// The public part of an extended key, as carried by the `crypto-hdkey`
+/// type. It never contains private key material, so it is safe to hand over
+/// to watch-only wallets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HdKey {
+    key_data: Vec<u8>,
+    chain_code: Option<B256>,
+    origin: Option<DerivationPath>,
+    parent_fingerprint: Option<u32>,
+}
+
+impl HdKey {
+    /// Create a new `HdKey` from a compressed public key.
+    pub fn new(key_data: Vec<u8>, chain_code: Option<B256>) -> Self {
+        Self {
+            key_data,
+            chain_code,
+            origin: None,
+            parent_fingerprint: None,
+        }
+    }
+
+    /// Set the derivation path the key was derived from.
+    pub fn with_origin(mut self, origin: DerivationPath) -> Self {
+        self.origin = Some(origin);
+        self
+    }
+
+    /// Get the compressed public key.
+    pub fn key_data(&self) -> &[u8] {
+        &self.key_data
+    }
+
+    /// Get the chain code.
+    pub fn chain_code(&self) -> Option<&B256> {
+        self.chain_code.as_ref()
+    }
+
+    /// Get the derivation path the key was derived from.
+    pub fn origin(&self) -> Option<&DerivationPath> {
+        self.origin.as_ref()
+    }
+
+    /// Get the parent fingerprint.
+    pub fn parent_fingerprint(&self) -> Option<u32> {
+        self.parent_fingerprint
+    }
+}
+
+impl From<&ExtendedKey> for HdKey {
+    fn from(key: &ExtendedKey) -> Self {
+        let parent_fingerprint = match key.depth() {
+            0 => None,
+            _ => Some(u32::from_be_bytes(
+                key.parent_fingerprint().try_into().unwrap(),
+            )),
+        };
+
+        Self {
+            key_data: key.public_key().to_bytes().to_vec(),
+            chain_code: Some(*key.chain_code()),
+            origin: None,
+            parent_fingerprint,
+        }
+    }
+}
+
+impl ToCbor for HdKey {
+    fn encode(&self, encoder: &mut Encoder) {
+        let len = 1
+            + self.chain_code.is_some() as usize
+            + self.origin.is_some() as usize
+            + self.parent_fingerprint.is_some() as usize;
+
+        encoder.tag(TAG_HDKEY).map(len);
+        encoder.unsigned(KEY_DATA).bytes(&self.key_data);
+        if let Some(chain_code) = &self.chain_code {
+            encoder.unsigned(KEY_CHAIN_CODE).bytes(chain_code.as_ref());
+        }
+        if let Some(origin) = &self.origin {
+            encoder.unsigned(KEY_ORIGIN);
+            origin.encode(encoder);
+        }
+        if let Some(fingerprint) = self.parent_fingerprint {
+            encoder
+                .unsigned(KEY_PARENT_FINGERPRINT)
+                .unsigned(fingerprint as u64);
+        }
+    }
+}
+
+impl FromCbor for HdKey {
+    fn decode(decoder: &mut Decoder) -> Result<Self> {
+        decoder.optional_tag(TAG_HDKEY)?;
+
+        let mut key_data = None;
+        let mut chain_code = None;
+        let mut origin = None;
+        let mut parent_fingerprint = None;
+
+        for _ in 0..decoder.map()? {
+            match decoder.unsigned()? {
+                KEY_DATA => {
+                    let bytes = decoder.bytes()?;
+                    if bytes.len() != 33 {
+                        return Err(CborError::InvalidValue.into());
+                    }
+                    key_data = Some(bytes.to_vec());
+                }
+                KEY_CHAIN_CODE => chain_code = Some(B256::from_slice(decoder.bytes()?)?),
+                KEY_ORIGIN => {
+                    if decoder.tag()? != TAG_KEYPATH {
+                        return Err(CborError::InvalidValue.into());
+                    }
+                    origin = Some(DerivationPath::decode(decoder)?);
+                }
+                KEY_PARENT_FINGERPRINT => parent_fingerprint = Some(decoder.u32()?),
+                _ => decoder.skip()?,
+            }
+        }
+
+        Ok(Self {
+            key_data: key_data.ok_or(CborError::MissingKey(KEY_DATA))?,
+            chain_code,
+            origin,
+            parent_fingerprint,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bips::{bip39::Mnemonic, wordlists::Language};
+
+    #[test]
+    fn test_hdkey() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+        let master = ExtendedKey::new_master(&mnemonic.to_seed("")).unwrap();
+        let path = DerivationPath::parse("m/44'/60'/0'").unwrap();
+        let account = master.derive_path(&path).unwrap();
+
+        let hdkey = HdKey::from(&account).with_origin(path);
+        let decoded = HdKey::from_cbor(&hdkey.to_cbor()).unwrap();
+        assert_eq!(decoded, hdkey);
+        assert_eq!(
+            decoded.key_data(),
+            account.public_key().to_bytes().as_slice()
+        );
+        assert_eq!(decoded.chain_code(), Some(account.chain_code()));
+    }
+}
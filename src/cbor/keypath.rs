@@ -0,0 +1,131 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::{CborError, Decoder, Encoder, FromCbor, ToCbor};
+use crate::{
+    bips::{ChildNumber, DerivationPath},
+    error::Result,
+};
+
+/// CBOR tag of the `crypto-keypath` type.
+pub(crate) const TAG_KEYPATH: u64 = 304;
+
+const KEY_COMPONENTS: u64 = 1;
+const KEY_SOURCE_FINGERPRINT: u64 = 2;
+
+impl ToCbor for DerivationPath {
+    fn encode(&self, encoder: &mut Encoder) {
+        encode_keypath(encoder, self, None);
+    }
+}
+
+impl FromCbor for DerivationPath {
+    fn decode(decoder: &mut Decoder) -> Result<Self> {
+        Ok(decode_keypath(decoder)?.0)
+    }
+}
+
+/// Write a `crypto-keypath`, with the fingerprint of the master key the
+/// path starts from when given.
+pub(crate) fn encode_keypath(
+    encoder: &mut Encoder,
+    path: &DerivationPath,
+    source_fingerprint: Option<u32>,
+) {
+    encoder
+        .tag(TAG_KEYPATH)
+        .map(1 + source_fingerprint.is_some() as usize)
+        .unsigned(KEY_COMPONENTS);
+    encoder.array(path.iter().len() * 2);
+    for component in path.iter() {
+        encoder
+            .unsigned(component.index() as u64)
+            .bool(component.is_hardened());
+    }
+    if let Some(fingerprint) = source_fingerprint {
+        encoder
+            .unsigned(KEY_SOURCE_FINGERPRINT)
+            .unsigned(fingerprint as u64);
+    }
+}
+
+/// Read a `crypto-keypath` and the fingerprint of its master key.
+pub(crate) fn decode_keypath(decoder: &mut Decoder) -> Result<(DerivationPath, Option<u32>)> {
+    decoder.optional_tag(TAG_KEYPATH)?;
+
+    let mut components = None;
+    let mut source_fingerprint = None;
+    for _ in 0..decoder.map()? {
+        match decoder.unsigned()? {
+            KEY_COMPONENTS => {
+                let len = decoder.array()?;
+                if len % 2 != 0 {
+                    return Err(CborError::InvalidValue.into());
+                }
+
+                // grown as items are read, the length is untrusted
+                let mut path = Vec::new();
+                for _ in 0..len / 2 {
+                    let index = decoder.u32()?;
+                    if index & 0x80000000 != 0 {
+                        return Err(CborError::InvalidValue.into());
+                    }
+                    if decoder.bool()? {
+                        path.push(ChildNumber::hardened(index));
+                    } else {
+                        path.push(ChildNumber::normal(index));
+                    }
+                }
+                components = Some(path);
+            }
+            KEY_SOURCE_FINGERPRINT => source_fingerprint = Some(decoder.u32()?),
+            // the depth carries no information for a complete path
+            _ => decoder.skip()?,
+        }
+    }
+
+    let components = components.ok_or(CborError::MissingKey(KEY_COMPONENTS))?;
+    Ok((components.into_iter().collect(), source_fingerprint))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keypath() {
+        let path = DerivationPath::parse("m/44'/60'/0'/0/0").unwrap();
+        let bytes = path.to_cbor();
+        assert_eq!(hex::encode(&bytes), "d90130a1018a182cf5183cf500f500f400f4");
+        assert_eq!(DerivationPath::from_cbor(&bytes).unwrap(), path);
+
+        // the tag is optional when the payload is the top-level UR type
+        let untagged = &bytes[3..];
+        assert_eq!(DerivationPath::from_cbor(untagged).unwrap(), path);
+    }
+
+    #[test]
+    fn test_keypath_invalid() {
+        assert!(DerivationPath::from_cbor(&hex::decode("d90130a0").unwrap()).is_err());
+        assert!(DerivationPath::from_cbor(&hex::decode("d90130a10181182c").unwrap()).is_err());
+        assert!(DerivationPath::from_cbor(
+            &hex::decode("d90130a1018a182cf5183cf500f500f400f400").unwrap()
+        )
+        .is_err());
+
+        // a huge array header over a few bytes of input
+        assert!(
+            DerivationPath::from_cbor(&hex::decode("a1019bfffffffffffffffe").unwrap()).is_err()
+        );
+    }
+}
@@ -0,0 +1,82 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # CBOR Serialization
+//!
+//! Compact binary encoding of the wallet types, following the registry of
+//! [Blockchain Commons](https://github.com/BlockchainCommons/Research/blob/master/papers/bcr-2020-006-urtypes.md)
+//! so they can be exchanged with air-gapped devices over QR codes or NFC.
+//!
+//! The following types are supported:
+//! - `crypto-keypath` (tag 304) for [`DerivationPath`](crate::bips::DerivationPath)
+//! - `crypto-hdkey` (tag 303) for the public part of an
+//!   [`ExtendedKey`](crate::bips::bip32::ExtendedKey)
+//! - `eth-sign-request` (tag 401) and `eth-signature` (tag 402) of
+//!   [ERC-4527](https://eips.ethereum.org/EIPS/eip-4527), carrying unsigned
+//!   transactions to an air-gapped signer and its signatures back
+//!
+//! [`Ur`] wraps a payload into a `ur:type/...` string, split by
+//! [`UrEncoder`] into the frames of an animated QR code when it is too
+//! large for one, and joined back by [`UrDecoder`].
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::bips::DerivationPath;
+//! use wallet_rust::cbor::{FromCbor, ToCbor};
+//!
+//! let path = DerivationPath::parse("m/44'/60'/0'/0/0").unwrap();
+//! let bytes = path.to_cbor();
+//! assert_eq!(DerivationPath::from_cbor(&bytes).unwrap(), path);
+//! ```
+
+mod bytewords;
+mod codec;
+mod fountain;
+mod hdkey;
+mod keypath;
+mod sign_request;
+mod ur;
+
+pub use codec::*;
+pub use hdkey::*;
+pub use sign_request::*;
+pub use ur::*;
+
+use crate::error::Result;
+
+/// Types that can be encoded into CBOR.
+pub trait ToCbor {
+    /// Write the value into the given encoder.
+    fn encode(&self, encoder: &mut Encoder);
+
+    /// Return the CBOR encoding of the value.
+    fn to_cbor(&self) -> Vec<u8> {
+        let mut encoder = Encoder::new();
+        self.encode(&mut encoder);
+        encoder.into_bytes()
+    }
+}
+
+/// Types that can be decoded from CBOR.
+pub trait FromCbor: Sized {
+    /// Read the value from the given decoder.
+    fn decode(decoder: &mut Decoder) -> Result<Self>;
+
+    /// Decode the value from the given bytes, rejecting trailing data.
+    fn from_cbor(bytes: &[u8]) -> Result<Self> {
+        let mut decoder = Decoder::new(bytes);
+        let value = Self::decode(&mut decoder)?;
+        decoder.finish()?;
+        Ok(value)
+    }
+}
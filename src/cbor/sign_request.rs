@@ -0,0 +1,382 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::{
+    keypath::{decode_keypath, encode_keypath, TAG_KEYPATH},
+    CborError, Decoder, Encoder, FromCbor, ToCbor,
+};
+use crate::{bips::DerivationPath, error::Result};
+
+/// CBOR tag of the `eth-sign-request` type.
+const TAG_SIGN_REQUEST: u64 = 401;
+/// CBOR tag of the `eth-signature` type.
+const TAG_SIGNATURE: u64 = 402;
+/// CBOR tag of a UUID.
+const TAG_UUID: u64 = 37;
+
+const KEY_REQUEST_ID: u64 = 1;
+const KEY_SIGN_DATA: u64 = 2;
+const KEY_DATA_TYPE: u64 = 3;
+const KEY_CHAIN_ID: u64 = 4;
+const KEY_DERIVATION_PATH: u64 = 5;
+const KEY_ADDRESS: u64 = 6;
+const KEY_ORIGIN: u64 = 7;
+
+const KEY_SIGNATURE: u64 = 2;
+const KEY_SIGNATURE_ORIGIN: u64 = 3;
+
+/// The kind of data an [`EthSignRequest`] asks to sign.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignDataType {
+    /// The RLP of an unsigned legacy transaction.
+    Transaction = 1,
+    /// EIP-712 typed data, as JSON.
+    TypedData = 2,
+    /// A message signed with the `personal_sign` prefix.
+    PersonalMessage = 3,
+    /// An unsigned EIP-2718 typed transaction, its type byte followed by
+    /// its RLP.
+    TypedTransaction = 4,
+}
+
+/// A request to sign data with the key at a derivation path, the
+/// `eth-sign-request` type of
+/// [ERC-4527](https://eips.ethereum.org/EIPS/eip-4527) handed to an
+/// air-gapped signer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EthSignRequest {
+    request_id: Option<[u8; 16]>,
+    sign_data: Vec<u8>,
+    data_type: SignDataType,
+    chain_id: Option<u64>,
+    path: DerivationPath,
+    source_fingerprint: Option<u32>,
+    address: Option<[u8; 20]>,
+    origin: Option<String>,
+}
+
+impl EthSignRequest {
+    /// Create a request to sign `sign_data` with the key at `path`.
+    pub fn new(sign_data: Vec<u8>, data_type: SignDataType, path: DerivationPath) -> Self {
+        Self {
+            request_id: None,
+            sign_data,
+            data_type,
+            chain_id: None,
+            path,
+            source_fingerprint: None,
+            address: None,
+            origin: None,
+        }
+    }
+
+    /// Set the UUID the signature will be returned with.
+    pub fn with_request_id(mut self, request_id: [u8; 16]) -> Self {
+        self.request_id = Some(request_id);
+        self
+    }
+
+    /// Set the chain of the transaction.
+    pub fn with_chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    /// Set the fingerprint of the master key `path` starts from, which
+    /// signers check against their own.
+    pub fn with_source_fingerprint(mut self, fingerprint: u32) -> Self {
+        self.source_fingerprint = Some(fingerprint);
+        self
+    }
+
+    /// Set the address expected to sign.
+    pub fn with_address(mut self, address: [u8; 20]) -> Self {
+        self.address = Some(address);
+        self
+    }
+
+    /// Set the name of the wallet making the request.
+    pub fn with_origin(mut self, origin: impl Into<String>) -> Self {
+        self.origin = Some(origin.into());
+        self
+    }
+
+    /// Get the UUID of the request.
+    pub fn request_id(&self) -> Option<&[u8; 16]> {
+        self.request_id.as_ref()
+    }
+
+    /// Get the data to sign.
+    pub fn sign_data(&self) -> &[u8] {
+        &self.sign_data
+    }
+
+    /// Get the kind of data to sign.
+    pub fn data_type(&self) -> SignDataType {
+        self.data_type
+    }
+
+    /// Get the chain of the transaction.
+    pub fn chain_id(&self) -> Option<u64> {
+        self.chain_id
+    }
+
+    /// Get the derivation path of the signing key.
+    pub fn path(&self) -> &DerivationPath {
+        &self.path
+    }
+
+    /// Get the fingerprint of the master key.
+    pub fn source_fingerprint(&self) -> Option<u32> {
+        self.source_fingerprint
+    }
+
+    /// Get the address expected to sign.
+    pub fn address(&self) -> Option<&[u8; 20]> {
+        self.address.as_ref()
+    }
+
+    /// Get the name of the wallet making the request.
+    pub fn origin(&self) -> Option<&str> {
+        self.origin.as_deref()
+    }
+}
+
+impl ToCbor for EthSignRequest {
+    fn encode(&self, encoder: &mut Encoder) {
+        let len = 3
+            + self.request_id.is_some() as usize
+            + self.chain_id.is_some() as usize
+            + self.address.is_some() as usize
+            + self.origin.is_some() as usize;
+
+        encoder.tag(TAG_SIGN_REQUEST).map(len);
+        if let Some(request_id) = &self.request_id {
+            encoder
+                .unsigned(KEY_REQUEST_ID)
+                .tag(TAG_UUID)
+                .bytes(request_id);
+        }
+        encoder.unsigned(KEY_SIGN_DATA).bytes(&self.sign_data);
+        encoder
+            .unsigned(KEY_DATA_TYPE)
+            .unsigned(self.data_type as u64);
+        if let Some(chain_id) = self.chain_id {
+            encoder.unsigned(KEY_CHAIN_ID).unsigned(chain_id);
+        }
+        encoder.unsigned(KEY_DERIVATION_PATH);
+        encode_keypath(encoder, &self.path, self.source_fingerprint);
+        if let Some(address) = &self.address {
+            encoder.unsigned(KEY_ADDRESS).bytes(address);
+        }
+        if let Some(origin) = &self.origin {
+            encoder.unsigned(KEY_ORIGIN).text(origin);
+        }
+    }
+}
+
+impl FromCbor for EthSignRequest {
+    fn decode(decoder: &mut Decoder) -> Result<Self> {
+        decoder.optional_tag(TAG_SIGN_REQUEST)?;
+
+        let mut request_id = None;
+        let mut sign_data = None;
+        let mut data_type = SignDataType::Transaction;
+        let mut chain_id = None;
+        let mut path = None;
+        let mut address = None;
+        let mut origin = None;
+
+        for _ in 0..decoder.map()? {
+            match decoder.unsigned()? {
+                KEY_REQUEST_ID => request_id = Some(decode_uuid(decoder)?),
+                KEY_SIGN_DATA => sign_data = Some(decoder.bytes()?.to_vec()),
+                KEY_DATA_TYPE => {
+                    data_type = match decoder.unsigned()? {
+                        1 => SignDataType::Transaction,
+                        2 => SignDataType::TypedData,
+                        3 => SignDataType::PersonalMessage,
+                        4 => SignDataType::TypedTransaction,
+                        _ => return Err(CborError::InvalidValue.into()),
+                    }
+                }
+                KEY_CHAIN_ID => chain_id = Some(decoder.unsigned()?),
+                KEY_DERIVATION_PATH => {
+                    if decoder.tag()? != TAG_KEYPATH {
+                        return Err(CborError::InvalidValue.into());
+                    }
+                    path = Some(decode_keypath(decoder)?);
+                }
+                KEY_ADDRESS => {
+                    let bytes = decoder.bytes()?;
+                    address = Some(bytes.try_into().map_err(|_| CborError::InvalidValue)?);
+                }
+                KEY_ORIGIN => origin = Some(decoder.text()?.to_string()),
+                _ => decoder.skip()?,
+            }
+        }
+
+        let (path, source_fingerprint) = path.ok_or(CborError::MissingKey(KEY_DERIVATION_PATH))?;
+        Ok(Self {
+            request_id,
+            sign_data: sign_data.ok_or(CborError::MissingKey(KEY_SIGN_DATA))?,
+            data_type,
+            chain_id,
+            path,
+            source_fingerprint,
+            address,
+            origin,
+        })
+    }
+}
+
+/// The answer of a signer to an [`EthSignRequest`], the `eth-signature`
+/// type of ERC-4527.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EthSignature {
+    request_id: Option<[u8; 16]>,
+    signature: Vec<u8>,
+    origin: Option<String>,
+}
+
+impl EthSignature {
+    /// Create the answer to the request `request_id`, with the signature
+    /// as `r`, `s` and `v`.
+    pub fn new(request_id: Option<[u8; 16]>, signature: Vec<u8>) -> Self {
+        Self {
+            request_id,
+            signature,
+            origin: None,
+        }
+    }
+
+    /// Set the name of the signer.
+    pub fn with_origin(mut self, origin: impl Into<String>) -> Self {
+        self.origin = Some(origin.into());
+        self
+    }
+
+    /// Get the UUID of the request.
+    pub fn request_id(&self) -> Option<&[u8; 16]> {
+        self.request_id.as_ref()
+    }
+
+    /// Get the signature, `r`, `s` and `v`.
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    /// Get the name of the signer.
+    pub fn origin(&self) -> Option<&str> {
+        self.origin.as_deref()
+    }
+}
+
+impl ToCbor for EthSignature {
+    fn encode(&self, encoder: &mut Encoder) {
+        let len = 1 + self.request_id.is_some() as usize + self.origin.is_some() as usize;
+
+        encoder.tag(TAG_SIGNATURE).map(len);
+        if let Some(request_id) = &self.request_id {
+            encoder
+                .unsigned(KEY_REQUEST_ID)
+                .tag(TAG_UUID)
+                .bytes(request_id);
+        }
+        encoder.unsigned(KEY_SIGNATURE).bytes(&self.signature);
+        if let Some(origin) = &self.origin {
+            encoder.unsigned(KEY_SIGNATURE_ORIGIN).text(origin);
+        }
+    }
+}
+
+impl FromCbor for EthSignature {
+    fn decode(decoder: &mut Decoder) -> Result<Self> {
+        decoder.optional_tag(TAG_SIGNATURE)?;
+
+        let mut request_id = None;
+        let mut signature = None;
+        let mut origin = None;
+
+        for _ in 0..decoder.map()? {
+            match decoder.unsigned()? {
+                KEY_REQUEST_ID => request_id = Some(decode_uuid(decoder)?),
+                KEY_SIGNATURE => signature = Some(decoder.bytes()?.to_vec()),
+                KEY_SIGNATURE_ORIGIN => origin = Some(decoder.text()?.to_string()),
+                _ => decoder.skip()?,
+            }
+        }
+
+        Ok(Self {
+            request_id,
+            signature: signature.ok_or(CborError::MissingKey(KEY_SIGNATURE))?,
+            origin,
+        })
+    }
+}
+
+fn decode_uuid(decoder: &mut Decoder) -> Result<[u8; 16]> {
+    if decoder.tag()? != TAG_UUID {
+        return Err(CborError::InvalidValue.into());
+    }
+    Ok(decoder
+        .bytes()?
+        .try_into()
+        .map_err(|_| CborError::InvalidValue)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_request() {
+        // an EIP-155 transfer of 1 ether on mainnet, unsigned
+        let transaction = hex::decode("ec098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080018080").unwrap();
+        let path = DerivationPath::parse("m/44'/60'/0'/0/0").unwrap();
+        let request = EthSignRequest::new(transaction.clone(), SignDataType::Transaction, path)
+            .with_request_id([9; 16])
+            .with_chain_id(1)
+            .with_source_fingerprint(0x73c5da0a)
+            .with_address([0x35; 20])
+            .with_origin("wallet-rust");
+
+        let bytes = request.to_cbor();
+        assert_eq!(&bytes[..3], [0xd9, 0x01, 0x91]);
+        let decoded = EthSignRequest::from_cbor(&bytes).unwrap();
+        assert_eq!(decoded, request);
+        assert_eq!(decoded.sign_data(), transaction);
+        assert_eq!(decoded.source_fingerprint(), Some(0x73c5da0a));
+
+        // the fingerprint travels inside the keypath, which a bare path ignores
+        let minimal = EthSignRequest::new(
+            vec![1],
+            SignDataType::PersonalMessage,
+            request.path().clone(),
+        );
+        assert_eq!(
+            EthSignRequest::from_cbor(&minimal.to_cbor()).unwrap(),
+            minimal
+        );
+        assert!(EthSignRequest::from_cbor(&hex::decode("d90191a10241ff").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_signature() {
+        let signature = EthSignature::new(Some([9; 16]), vec![1; 65]).with_origin("signer");
+        let bytes = signature.to_cbor();
+        assert_eq!(&bytes[..3], [0xd9, 0x01, 0x92]);
+        assert_eq!(EthSignature::from_cbor(&bytes).unwrap(), signature);
+        assert!(EthSignature::from_cbor(&hex::decode("d90192a0").unwrap()).is_err());
+    }
+}
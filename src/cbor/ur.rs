@@ -0,0 +1,308 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::str::FromStr;
+
+use super::{
+    bytewords,
+    fountain::{FountainDecoder, FountainEncoder, Part},
+    CborError, FromCbor, ToCbor,
+};
+use crate::error::{Result, WalletError};
+
+/// A CBOR payload with its registered type, such as `crypto-hdkey`, the
+/// [Uniform Resource](https://github.com/BlockchainCommons/Research/blob/master/papers/bcr-2020-005-ur.md)
+/// carried by `ur:type/...` strings in QR codes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ur {
+    ur_type: String,
+    cbor: Vec<u8>,
+}
+
+impl Ur {
+    /// Create a UR of `ur_type`, made of lowercase letters, digits and
+    /// hyphens, over a non-empty CBOR payload.
+    pub fn new(ur_type: &str, cbor: Vec<u8>) -> Result<Self> {
+        if !is_type(ur_type) || cbor.is_empty() {
+            return Err(CborError::InvalidUr.into());
+        }
+        Ok(Self {
+            ur_type: ur_type.to_string(),
+            cbor,
+        })
+    }
+
+    /// Create a UR of `ur_type` carrying the encoding of `value`.
+    pub fn encode(ur_type: &str, value: &impl ToCbor) -> Result<Self> {
+        Self::new(ur_type, value.to_cbor())
+    }
+
+    /// Decode the payload, which must be of `ur_type`.
+    pub fn decode<T: FromCbor>(&self, ur_type: &str) -> Result<T> {
+        if self.ur_type != ur_type {
+            return Err(CborError::InvalidUr.into());
+        }
+        T::from_cbor(&self.cbor)
+    }
+
+    /// Return the type of the payload.
+    pub fn ur_type(&self) -> &str {
+        &self.ur_type
+    }
+
+    /// Return the CBOR payload.
+    pub fn cbor(&self) -> &[u8] {
+        &self.cbor
+    }
+}
+
+/// The single-part form, `ur:type/bytewords`.
+impl std::fmt::Display for Ur {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "ur:{}/{}", self.ur_type, bytewords::encode(&self.cbor))
+    }
+}
+
+/// Parse a single-part UR, in lowercase or in the uppercase of QR codes.
+impl FromStr for Ur {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.to_ascii_lowercase();
+        match split(&s)? {
+            (ur_type, None, body) => Self::new(ur_type, bytewords::decode(body)?),
+            _ => Err(CborError::InvalidUr.into()),
+        }
+    }
+}
+
+/// Emit the parts of a UR too large for one QR code, as the frames of an
+/// animated one.
+///
+/// ## Example
+/// ```rust
+/// use wallet_rust::cbor::{Ur, UrDecoder, UrEncoder};
+///
+/// let ur = Ur::new("bytes", vec![0x58, 0x64].into_iter().chain([7; 100]).collect()).unwrap();
+/// let mut encoder = UrEncoder::new(&ur, 30);
+/// assert_eq!(encoder.fragments(), 4);
+///
+/// let mut decoder = UrDecoder::new();
+/// while !decoder.is_complete() {
+///     decoder.receive(&encoder.next_part()).unwrap();
+/// }
+/// assert_eq!(decoder.ur(), Some(&ur));
+/// ```
+#[derive(Debug, Clone)]
+pub struct UrEncoder {
+    ur: Ur,
+    fountain: FountainEncoder,
+}
+
+impl UrEncoder {
+    /// Split `ur` into fragments of at most `max_fragment_len` bytes of
+    /// CBOR each.
+    pub fn new(ur: &Ur, max_fragment_len: usize) -> Self {
+        Self {
+            ur: ur.clone(),
+            fountain: FountainEncoder::new(&ur.cbor, max_fragment_len),
+        }
+    }
+
+    /// Return the number of fragments, the number of parts needed without
+    /// any loss.
+    pub fn fragments(&self) -> usize {
+        self.fountain.fragments()
+    }
+
+    /// Return the next part, the single-part UR when it fits in one
+    /// fragment. There is no last part: after the fragments come parts
+    /// mixing several of them, to be sent until the receiver is done.
+    pub fn next_part(&mut self) -> String {
+        if self.fragments() == 1 {
+            return self.ur.to_string();
+        }
+        let part = self.fountain.next_part();
+        format!(
+            "ur:{}/{}-{}/{}",
+            self.ur.ur_type,
+            part.sequence,
+            part.fragments,
+            bytewords::encode(&part.to_cbor())
+        )
+    }
+}
+
+/// Join the parts of a UR, received in any order.
+#[derive(Debug, Clone, Default)]
+pub struct UrDecoder {
+    ur_type: Option<String>,
+    fountain: FountainDecoder,
+    ur: Option<Ur>,
+}
+
+impl UrDecoder {
+    /// Create a decoder waiting for its first part.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a part, either a single-part UR or a part of a multipart one.
+    /// All parts must belong to the same UR.
+    pub fn receive(&mut self, part: &str) -> Result<()> {
+        let part = part.to_ascii_lowercase();
+        let (ur_type, sequence, body) = split(&part)?;
+        if self
+            .ur_type
+            .as_deref()
+            .is_some_and(|known| known != ur_type)
+        {
+            return Err(CborError::InvalidUr.into());
+        }
+        if self.ur.is_some() {
+            return Ok(());
+        }
+
+        let Some((sequence, fragments)) = sequence else {
+            self.ur = Some(Ur::new(ur_type, bytewords::decode(body)?)?);
+            self.ur_type = Some(ur_type.to_string());
+            return Ok(());
+        };
+        let part = Part::from_cbor(&bytewords::decode(body)?)?;
+        if (part.sequence, part.fragments) != (sequence, fragments) {
+            return Err(CborError::InvalidUr.into());
+        }
+
+        let message = self.fountain.receive(part)?;
+        self.ur_type = Some(ur_type.to_string());
+        if let Some(message) = message {
+            self.ur = Some(Ur::new(ur_type, message.to_vec())?);
+        }
+        Ok(())
+    }
+
+    /// Return whether the UR is complete.
+    pub fn is_complete(&self) -> bool {
+        self.ur.is_some()
+    }
+
+    /// Return the UR once complete.
+    pub fn ur(&self) -> Option<&Ur> {
+        self.ur.as_ref()
+    }
+}
+
+fn is_type(ur_type: &str) -> bool {
+    !ur_type.is_empty()
+        && ur_type
+            .bytes()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == b'-')
+}
+
+/// The number of a part of a multipart UR and the number of fragments.
+type Sequence = (u32, usize);
+
+/// Split a lowercase UR into its type, the sequence of a multipart UR and
+/// its bytewords.
+fn split(s: &str) -> Result<(&str, Option<Sequence>, &str)> {
+    let invalid = CborError::InvalidUr;
+    let path = s.strip_prefix("ur:").ok_or(invalid)?;
+    let mut components = path.split('/');
+    let ur_type = components.next().filter(|t| is_type(t)).ok_or(invalid)?;
+    match (components.next(), components.next(), components.next()) {
+        (Some(body), None, None) => Ok((ur_type, None, body)),
+        (Some(sequence), Some(body), None) => {
+            let (number, count) = sequence.split_once('-').ok_or(invalid)?;
+            let number = number.parse().map_err(|_| invalid)?;
+            let count = count.parse().map_err(|_| invalid)?;
+            Ok((ur_type, Some((number, count)), body))
+        }
+        _ => Err(invalid.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bips::DerivationPath;
+
+    #[test]
+    fn test_single_part() {
+        let path = DerivationPath::parse("m/44'/60'/0'/0/0").unwrap();
+        let ur = Ur::encode("crypto-keypath", &path).unwrap();
+        let text = ur.to_string();
+        assert!(text.starts_with("ur:crypto-keypath/"));
+
+        let parsed: Ur = text.to_uppercase().parse().unwrap();
+        assert_eq!(parsed, ur);
+        assert_eq!(
+            parsed.decode::<DerivationPath>("crypto-keypath").unwrap(),
+            path
+        );
+        assert!(parsed.decode::<DerivationPath>("crypto-hdkey").is_err());
+
+        let mut encoder = UrEncoder::new(&ur, 100);
+        assert_eq!(encoder.next_part(), text);
+        let mut decoder = UrDecoder::new();
+        decoder.receive(&text).unwrap();
+        assert_eq!(decoder.ur(), Some(&ur));
+    }
+
+    #[test]
+    fn test_multipart() {
+        let cbor = [&[0x59, 0x01, 0x2c][..], &[42; 300]].concat();
+        let ur = Ur::new("bytes", cbor).unwrap();
+        let mut encoder = UrEncoder::new(&ur, 40);
+        assert_eq!(encoder.fragments(), 8);
+
+        // join late, and miss a frame out of four
+        let parts = (0..60).map(|_| encoder.next_part()).collect::<Vec<_>>();
+        assert!(parts[0].starts_with("ur:bytes/1-8/"));
+        let mut decoder = UrDecoder::new();
+        for (i, part) in parts.iter().enumerate().skip(5) {
+            if i % 4 != 0 {
+                decoder.receive(part).unwrap();
+            }
+            if decoder.is_complete() {
+                break;
+            }
+        }
+        assert_eq!(decoder.ur(), Some(&ur));
+    }
+
+    #[test]
+    fn test_invalid() {
+        for invalid in [
+            "crypto-keypath/aeadaolazmjendeoti",
+            "ur:crypto_keypath/aeadaolazmjendeoti",
+            "ur:bytes/1-2/aeadaolazmjendeoti",
+            "ur:bytes/aeadaolazmjendeota",
+            "ur:bytes/aeaeaeae",
+            "ur:bytes/a/b/c",
+        ] {
+            assert!(invalid.parse::<Ur>().is_err(), "{}", invalid);
+        }
+
+        let ur = Ur::new(
+            "bytes",
+            vec![0x58, 0x64].into_iter().chain([7; 100]).collect(),
+        )
+        .unwrap();
+        let mut encoder = UrEncoder::new(&ur, 30);
+        let mut decoder = UrDecoder::new();
+        decoder.receive(&encoder.next_part()).unwrap();
+        let part = encoder.next_part();
+        assert!(decoder.receive(&part.replace("bytes", "other")).is_err());
+        assert!(decoder.receive(&part.replace("/2-4/", "/3-4/")).is_err());
+    }
+}
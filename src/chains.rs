@@ -0,0 +1,413 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Chains
+//!
+//! A registry of common networks with their chain id, native currency,
+//! default RPC endpoints and block explorer. The chain id is what signatures
+//! commit to, so signing APIs take `impl Into<Chain>`: a [`Chain`] of the
+//! registry or a bare id.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::chains::{self, Chain};
+//!
+//! let chain: Chain = "arb1".parse().unwrap();
+//! assert_eq!(chain, chains::ARBITRUM);
+//! assert_eq!(chain.id, 42161);
+//! assert_eq!(chain.currency.symbol, "ETH");
+//!
+//! assert_eq!(Chain::from_id(11155111), Some(chains::SEPOLIA));
+//! ```
+
+use crate::{
+    address::Address,
+    error::{Result, WalletError},
+    types::B256,
+};
+
+/// Error returned when looking up a chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainError {
+    /// No chain of the registry has this id or short name.
+    UnknownChain(String),
+}
+
+impl std::fmt::Display for ChainError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ChainError::UnknownChain(chain) => write!(f, "Unknown chain: {}", chain),
+        }
+    }
+}
+
+impl std::error::Error for ChainError {}
+
+/// The currency paying for gas on a chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NativeCurrency {
+    /// Name of the currency.
+    pub name: &'static str,
+    /// Ticker symbol of the currency.
+    pub symbol: &'static str,
+    /// Number of decimals of the currency.
+    pub decimals: u8,
+}
+
+/// An EVM network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Chain {
+    /// The chain id, as defined in EIP-155.
+    pub id: u64,
+    /// The human readable name.
+    pub name: &'static str,
+    /// The short name used by EIP-3770 addresses.
+    pub short_name: &'static str,
+    /// The native currency.
+    pub currency: NativeCurrency,
+    /// Public JSON-RPC endpoints, most reliable first.
+    pub rpc_urls: &'static [&'static str],
+    /// Base URL of the block explorer.
+    pub explorer: Option<&'static str>,
+    /// Whether the chain is a testnet, whose currency has no value.
+    pub testnet: bool,
+}
+
+impl Chain {
+    /// Return the chain of the registry with the given id.
+    pub fn from_id(id: u64) -> Option<Self> {
+        CHAINS.iter().find(|chain| chain.id == id).copied()
+    }
+
+    /// Return a chain missing from the registry, such as a local development
+    /// network, known by its id alone. Its currency is assumed to be ether.
+    pub const fn custom(id: u64) -> Self {
+        Chain {
+            id,
+            name: "Custom chain",
+            short_name: "",
+            currency: NativeCurrency {
+                name: "Ether",
+                symbol: "ETH",
+                decimals: 18,
+            },
+            rpc_urls: &[],
+            explorer: None,
+            testnet: false,
+        }
+    }
+
+    /// Return the chain of the registry with the given EIP-3770 short name.
+    pub fn from_short_name(short_name: &str) -> Option<Self> {
+        CHAINS
+            .iter()
+            .find(|chain| chain.short_name == short_name)
+            .copied()
+    }
+
+    /// Return the explorer page of an address.
+    pub fn address_url(&self, address: &Address) -> Option<String> {
+        self.explorer
+            .map(|explorer| format!("{}/address/{}", explorer, address))
+    }
+
+    /// Return the explorer page of a transaction.
+    pub fn transaction_url(&self, hash: &B256) -> Option<String> {
+        self.explorer
+            .map(|explorer| format!("{}/tx/{}", explorer, hash))
+    }
+}
+
+impl From<u64> for Chain {
+    /// Look the id up in the registry, falling back to a [custom](Chain::custom)
+    /// chain.
+    fn from(id: u64) -> Self {
+        Self::from_id(id).unwrap_or(Self::custom(id))
+    }
+}
+
+impl From<Chain> for u64 {
+    fn from(chain: Chain) -> Self {
+        chain.id
+    }
+}
+
+impl std::fmt::Display for Chain {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl std::str::FromStr for Chain {
+    type Err = WalletError;
+
+    /// Parse a chain from its short name or its decimal id.
+    fn from_str(s: &str) -> Result<Self> {
+        s.parse::<u64>()
+            .ok()
+            .and_then(Self::from_id)
+            .or_else(|| Self::from_short_name(s))
+            .ok_or_else(|| ChainError::UnknownChain(s.to_string()).into())
+    }
+}
+
+/// Ethereum Mainnet.
+pub const MAINNET: Chain = Chain {
+    id: 1,
+    name: "Ethereum Mainnet",
+    short_name: "eth",
+    currency: NativeCurrency {
+        name: "Ether",
+        symbol: "ETH",
+        decimals: 18,
+    },
+    rpc_urls: &[
+        "https://ethereum-rpc.publicnode.com",
+        "https://eth.llamarpc.com",
+    ],
+    explorer: Some("https://etherscan.io"),
+    testnet: false,
+};
+
+/// OP Mainnet.
+pub const OPTIMISM: Chain = Chain {
+    id: 10,
+    name: "OP Mainnet",
+    short_name: "oeth",
+    currency: NativeCurrency {
+        name: "Ether",
+        symbol: "ETH",
+        decimals: 18,
+    },
+    rpc_urls: &["https://mainnet.optimism.io"],
+    explorer: Some("https://optimistic.etherscan.io"),
+    testnet: false,
+};
+
+/// BNB Smart Chain.
+pub const BSC: Chain = Chain {
+    id: 56,
+    name: "BNB Smart Chain",
+    short_name: "bnb",
+    currency: NativeCurrency {
+        name: "BNB",
+        symbol: "BNB",
+        decimals: 18,
+    },
+    rpc_urls: &["https://bsc-dataseed.bnbchain.org"],
+    explorer: Some("https://bscscan.com"),
+    testnet: false,
+};
+
+/// Gnosis.
+pub const GNOSIS: Chain = Chain {
+    id: 100,
+    name: "Gnosis",
+    short_name: "gno",
+    currency: NativeCurrency {
+        name: "xDAI",
+        symbol: "XDAI",
+        decimals: 18,
+    },
+    rpc_urls: &["https://rpc.gnosischain.com"],
+    explorer: Some("https://gnosisscan.io"),
+    testnet: false,
+};
+
+/// Polygon Mainnet.
+pub const POLYGON: Chain = Chain {
+    id: 137,
+    name: "Polygon Mainnet",
+    short_name: "pol",
+    currency: NativeCurrency {
+        name: "POL",
+        symbol: "POL",
+        decimals: 18,
+    },
+    rpc_urls: &["https://polygon-rpc.com"],
+    explorer: Some("https://polygonscan.com"),
+    testnet: false,
+};
+
+/// zkSync Era.
+pub const ZKSYNC: Chain = Chain {
+    id: 324,
+    name: "zkSync Era",
+    short_name: "zksync",
+    currency: NativeCurrency {
+        name: "Ether",
+        symbol: "ETH",
+        decimals: 18,
+    },
+    rpc_urls: &["https://mainnet.era.zksync.io"],
+    explorer: Some("https://explorer.zksync.io"),
+    testnet: false,
+};
+
+/// Base.
+pub const BASE: Chain = Chain {
+    id: 8453,
+    name: "Base",
+    short_name: "base",
+    currency: NativeCurrency {
+        name: "Ether",
+        symbol: "ETH",
+        decimals: 18,
+    },
+    rpc_urls: &["https://mainnet.base.org"],
+    explorer: Some("https://basescan.org"),
+    testnet: false,
+};
+
+/// Holesky.
+pub const HOLESKY: Chain = Chain {
+    id: 17000,
+    name: "Holesky",
+    short_name: "holesky",
+    currency: NativeCurrency {
+        name: "Holesky Ether",
+        symbol: "ETH",
+        decimals: 18,
+    },
+    rpc_urls: &["https://ethereum-holesky-rpc.publicnode.com"],
+    explorer: Some("https://holesky.etherscan.io"),
+    testnet: true,
+};
+
+/// Arbitrum One.
+pub const ARBITRUM: Chain = Chain {
+    id: 42161,
+    name: "Arbitrum One",
+    short_name: "arb1",
+    currency: NativeCurrency {
+        name: "Ether",
+        symbol: "ETH",
+        decimals: 18,
+    },
+    rpc_urls: &["https://arb1.arbitrum.io/rpc"],
+    explorer: Some("https://arbiscan.io"),
+    testnet: false,
+};
+
+/// Avalanche C-Chain.
+pub const AVALANCHE: Chain = Chain {
+    id: 43114,
+    name: "Avalanche C-Chain",
+    short_name: "avax",
+    currency: NativeCurrency {
+        name: "Avalanche",
+        symbol: "AVAX",
+        decimals: 18,
+    },
+    rpc_urls: &["https://api.avax.network/ext/bc/C/rpc"],
+    explorer: Some("https://snowtrace.io"),
+    testnet: false,
+};
+
+/// Linea.
+pub const LINEA: Chain = Chain {
+    id: 59144,
+    name: "Linea",
+    short_name: "linea",
+    currency: NativeCurrency {
+        name: "Linea Ether",
+        symbol: "ETH",
+        decimals: 18,
+    },
+    rpc_urls: &["https://rpc.linea.build"],
+    explorer: Some("https://lineascan.build"),
+    testnet: false,
+};
+
+/// Scroll.
+pub const SCROLL: Chain = Chain {
+    id: 534352,
+    name: "Scroll",
+    short_name: "scr",
+    currency: NativeCurrency {
+        name: "Ether",
+        symbol: "ETH",
+        decimals: 18,
+    },
+    rpc_urls: &["https://rpc.scroll.io"],
+    explorer: Some("https://scrollscan.com"),
+    testnet: false,
+};
+
+/// Sepolia.
+pub const SEPOLIA: Chain = Chain {
+    id: 11155111,
+    name: "Sepolia",
+    short_name: "sep",
+    currency: NativeCurrency {
+        name: "Sepolia Ether",
+        symbol: "ETH",
+        decimals: 18,
+    },
+    rpc_urls: &[
+        "https://ethereum-sepolia-rpc.publicnode.com",
+        "https://rpc.sepolia.org",
+    ],
+    explorer: Some("https://sepolia.etherscan.io"),
+    testnet: true,
+};
+
+/// All the chains of the registry, by chain id.
+pub const CHAINS: &[Chain] = &[
+    MAINNET, OPTIMISM, BSC, GNOSIS, POLYGON, ZKSYNC, BASE, HOLESKY, ARBITRUM, AVALANCHE, LINEA,
+    SCROLL, SEPOLIA,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        for (i, chain) in CHAINS.iter().enumerate() {
+            assert_eq!(Chain::from_id(chain.id), Some(*chain));
+            assert_eq!(Chain::from_short_name(chain.short_name), Some(*chain));
+            assert!(!chain.rpc_urls.is_empty());
+            // unique and sorted by id
+            assert!(CHAINS[..i].iter().all(|other| other.id < chain.id));
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("1".parse::<Chain>().unwrap(), MAINNET);
+        assert_eq!("oeth".parse::<Chain>().unwrap(), OPTIMISM);
+        assert!("2".parse::<Chain>().is_err());
+        assert!("ethereum".parse::<Chain>().is_err());
+    }
+
+    #[test]
+    fn test_from_id() {
+        assert_eq!(Chain::from(10), OPTIMISM);
+        assert_eq!(Chain::from(31337), Chain::custom(31337));
+        assert_eq!(u64::from(Chain::from(31337)), 31337);
+    }
+
+    #[test]
+    fn test_explorer() {
+        let address = Address::new([0x11; 20]);
+        assert_eq!(
+            POLYGON.address_url(&address).unwrap(),
+            "https://polygonscan.com/address/0x1111111111111111111111111111111111111111"
+        );
+        assert_eq!(
+            MAINNET.transaction_url(&B256::new([0xab; 32])).unwrap(),
+            format!("https://etherscan.io/tx/0x{}", "ab".repeat(32))
+        );
+    }
+}
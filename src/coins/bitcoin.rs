@@ -0,0 +1,381 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Bitcoin
+//!
+//! Bitcoin addresses of the three single-key script types, each derived
+//! under its own purpose:
+//!
+//! - [BIP44](https://github.com/bitcoin/bips/blob/master/bip-0044.mediawiki)
+//!   legacy P2PKH addresses, `1...` on mainnet;
+//! - [BIP49](https://github.com/bitcoin/bips/blob/master/bip-0049.mediawiki)
+//!   P2WPKH nested in P2SH, `3...`;
+//! - [BIP84](https://github.com/bitcoin/bips/blob/master/bip-0084.mediawiki)
+//!   native segwit P2WPKH, `bc1q...`.
+//!
+//! Testnet addresses use their own version bytes and Bech32 prefix, and the
+//! coin type `1'` in their derivation paths.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::bips::{bip32::ExtendedKey, bip39::Mnemonic, wordlists::Language};
+//! use wallet_rust::coins::bitcoin::{self, Address, AddressType, Network};
+//!
+//! let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+//! let seed = Mnemonic::from_phrase(phrase, Language::English).unwrap().to_seed("");
+//! let master = ExtendedKey::new_master(&seed).unwrap();
+//!
+//! let path = bitcoin::derivation_path(AddressType::P2wpkh, Network::Mainnet, 0, false, 0).unwrap();
+//! assert_eq!(path.to_string(), "m/84'/0'/0'/0/0");
+//! let key = master.derive_path(&path.into()).unwrap();
+//! let address = Address::from_public_key(key.public_key(), AddressType::P2wpkh, Network::Mainnet).unwrap();
+//! assert_eq!(address.to_string(), "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu");
+//! assert_eq!(address.to_string().parse::<Address>().unwrap(), address);
+//! ```
+
+use k256::ecdsa::VerifyingKey;
+use laron_crypto::PublicKey;
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    base58, bech32,
+    bips::bip44::{self, Bip44Path},
+    error::{Result, WalletError},
+};
+
+/// Error returned when parsing a Bitcoin address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitcoinError {
+    /// The version byte of a Base58 address is unknown.
+    InvalidVersion(u8),
+    /// The address does not hold a 20 byte hash.
+    InvalidLength,
+    /// The Bech32 prefix is neither `bc` nor `tb`.
+    InvalidHrp,
+    /// The witness program is not a version 0 key hash.
+    UnsupportedWitnessProgram,
+}
+
+impl std::fmt::Display for BitcoinError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            BitcoinError::InvalidVersion(version) => {
+                write!(f, "Invalid Bitcoin address version: {}", version)
+            }
+            BitcoinError::InvalidLength => write!(f, "Invalid Bitcoin address length"),
+            BitcoinError::InvalidHrp => write!(f, "Invalid Bitcoin address prefix"),
+            BitcoinError::UnsupportedWitnessProgram => {
+                write!(f, "Unsupported witness program")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BitcoinError {}
+
+/// A Bitcoin network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Network {
+    /// The main network.
+    Mainnet,
+    /// The test networks, which share their address format.
+    Testnet,
+}
+
+impl Network {
+    /// Return the version byte of P2PKH addresses.
+    pub fn p2pkh_version(&self) -> u8 {
+        match self {
+            Network::Mainnet => 0x00,
+            Network::Testnet => 0x6f,
+        }
+    }
+
+    /// Return the version byte of P2SH addresses.
+    pub fn p2sh_version(&self) -> u8 {
+        match self {
+            Network::Mainnet => 0x05,
+            Network::Testnet => 0xc4,
+        }
+    }
+
+    /// Return the Bech32 prefix of segwit addresses.
+    pub fn hrp(&self) -> &'static str {
+        match self {
+            Network::Mainnet => "bc",
+            Network::Testnet => "tb",
+        }
+    }
+
+    /// Return the SLIP-44 coin type of derivation paths.
+    pub fn coin_type(&self) -> u32 {
+        match self {
+            Network::Mainnet => bip44::BITCOIN,
+            Network::Testnet => bip44::TESTNET,
+        }
+    }
+}
+
+/// The script type of a single-key address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AddressType {
+    /// Pay to public key hash.
+    P2pkh,
+    /// Pay to witness public key hash, nested in pay to script hash.
+    P2shP2wpkh,
+    /// Pay to witness public key hash.
+    P2wpkh,
+}
+
+impl AddressType {
+    /// Return the purpose of the derivation paths of the type.
+    pub fn purpose(&self) -> u32 {
+        match self {
+            AddressType::P2pkh => bip44::PURPOSE,
+            AddressType::P2shP2wpkh => 49,
+            AddressType::P2wpkh => 84,
+        }
+    }
+}
+
+/// Return the derivation path of an address,
+/// `m/purpose'/coin_type'/account'/change/index`.
+pub fn derivation_path(
+    address_type: AddressType,
+    network: Network,
+    account: u32,
+    change: bool,
+    index: u32,
+) -> Result<Bip44Path> {
+    Bip44Path::new(
+        address_type.purpose(),
+        network.coin_type(),
+        account,
+        change,
+        index,
+    )
+}
+
+/// A Bitcoin address.
+///
+/// The script behind a P2SH address cannot be told from the address, so
+/// parsed P2SH addresses report [`AddressType::P2shP2wpkh`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Address {
+    network: Network,
+    address_type: AddressType,
+    hash: [u8; 20],
+}
+
+impl Address {
+    /// Create the address of a public key.
+    pub fn from_public_key(
+        public_key: &PublicKey,
+        address_type: AddressType,
+        network: Network,
+    ) -> Result<Self> {
+        // segwit requires compressed keys, legacy wallets derive with them too
+        let key = VerifyingKey::from_sec1_bytes(&public_key.to_bytes())?;
+        let hash = hash160(key.to_encoded_point(true).as_bytes());
+        let hash = match address_type {
+            AddressType::P2shP2wpkh => hash160(&redeem_script(&hash)),
+            _ => hash,
+        };
+
+        Ok(Self {
+            network,
+            address_type,
+            hash,
+        })
+    }
+
+    /// Return the network of the address.
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    /// Return the script type of the address.
+    pub fn address_type(&self) -> AddressType {
+        self.address_type
+    }
+
+    /// Return the public key or script hash of the address.
+    pub fn as_bytes(&self) -> &[u8; 20] {
+        &self.hash
+    }
+}
+
+impl std::fmt::Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let version = match self.address_type {
+            AddressType::P2pkh => self.network.p2pkh_version(),
+            AddressType::P2shP2wpkh => self.network.p2sh_version(),
+            AddressType::P2wpkh => {
+                let program =
+                    bech32::convert_bits(&self.hash, 8, 5, true).map_err(|_| std::fmt::Error)?;
+                let encoded = bech32::encode_u5(self.network.hrp(), &[&[0][..], &program].concat())
+                    .map_err(|_| std::fmt::Error)?;
+                return write!(f, "{}", encoded);
+            }
+        };
+        write!(
+            f,
+            "{}",
+            base58::encode_check([&[version][..], &self.hash].concat())
+        )
+    }
+}
+
+impl std::str::FromStr for Address {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let segwit = [Network::Mainnet, Network::Testnet]
+            .into_iter()
+            .find(|network| s.to_lowercase().starts_with(&format!("{}1", network.hrp())));
+        if let Some(network) = segwit {
+            let (hrp, data) = bech32::decode_u5(s)?;
+            if hrp != network.hrp() {
+                return Err(BitcoinError::InvalidHrp.into());
+            }
+            let (version, program) = data.split_first().ok_or(BitcoinError::InvalidLength)?;
+            let program = bech32::convert_bits(program, 5, 8, false)?;
+            if *version != 0 || program.len() != 20 {
+                return Err(BitcoinError::UnsupportedWitnessProgram.into());
+            }
+            return Ok(Self {
+                network,
+                address_type: AddressType::P2wpkh,
+                hash: program.try_into().expect("20 bytes"),
+            });
+        }
+
+        let data = base58::decode_check(s)?;
+        let (version, hash) = data.split_first().ok_or(BitcoinError::InvalidLength)?;
+        let hash = hash.try_into().map_err(|_| BitcoinError::InvalidLength)?;
+        let (network, address_type) = [Network::Mainnet, Network::Testnet]
+            .into_iter()
+            .find_map(|network| {
+                if *version == network.p2pkh_version() {
+                    Some((network, AddressType::P2pkh))
+                } else if *version == network.p2sh_version() {
+                    Some((network, AddressType::P2shP2wpkh))
+                } else {
+                    None
+                }
+            })
+            .ok_or(BitcoinError::InvalidVersion(*version))?;
+
+        Ok(Self {
+            network,
+            address_type,
+            hash,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+crate::types::serde_string!(Address);
+
+fn hash160(data: &[u8]) -> [u8; 20] {
+    Ripemd160::digest(Sha256::digest(data)).into()
+}
+
+/// Return the P2WPKH script `OP_0 <hash>` redeemed by a nested address.
+fn redeem_script(hash: &[u8; 20]) -> Vec<u8> {
+    [&[0x00, 0x14][..], hash].concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bips::{bip32::ExtendedKey, bip39::Mnemonic, wordlists::Language};
+
+    fn address(address_type: AddressType, network: Network) -> Address {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let seed = Mnemonic::from_phrase(phrase, Language::English)
+            .unwrap()
+            .to_seed("");
+        let path = derivation_path(address_type, network, 0, false, 0).unwrap();
+        let key = ExtendedKey::new_master(&seed)
+            .unwrap()
+            .derive_path(&path.into())
+            .unwrap();
+        Address::from_public_key(key.public_key(), address_type, network).unwrap()
+    }
+
+    #[test]
+    fn test_addresses() {
+        let vectors = [
+            (
+                AddressType::P2pkh,
+                Network::Mainnet,
+                "1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA",
+            ),
+            (
+                AddressType::P2shP2wpkh,
+                Network::Mainnet,
+                "37VucYSaXLCAsxYyAPfbSi9eh4iEcbShgf",
+            ),
+            (
+                AddressType::P2wpkh,
+                Network::Mainnet,
+                "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+            ),
+            (
+                AddressType::P2pkh,
+                Network::Testnet,
+                "mkpZhYtJu2r87Js3pDiWJDmPte2NRZ8bJV",
+            ),
+            (
+                AddressType::P2shP2wpkh,
+                Network::Testnet,
+                "2Mww8dCYPUpKHofjgcXcBCEGmniw9CoaiD2",
+            ),
+            (
+                AddressType::P2wpkh,
+                Network::Testnet,
+                "tb1q6rz28mcfaxtmd6v789l9rrlrusdprr9pqcpvkl",
+            ),
+        ];
+        for (address_type, network, expected) in vectors {
+            let address = address(address_type, network);
+            assert_eq!(address.to_string(), expected);
+            assert_eq!(expected.parse::<Address>().unwrap(), address);
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        // BIP173 P2WSH and version 1 programs
+        assert!(
+            "bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3"
+                .parse::<Address>()
+                .is_err()
+        );
+        assert!(
+            "bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7k7grplx"
+                .parse::<Address>()
+                .is_err()
+        );
+        // bad checksum
+        assert!("1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabB"
+            .parse::<Address>()
+            .is_err());
+        // Litecoin P2PKH
+        assert!("Lf48XedjbkDbjcUu8b58YgtF5jgpff6imo"
+            .parse::<Address>()
+            .is_err());
+    }
+}
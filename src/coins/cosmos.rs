@@ -0,0 +1,295 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Cosmos
+//!
+//! Accounts of Cosmos SDK chains are secp256k1 keys derived along
+//! `m/44'/118'/account'/0/index`. The address is the RIPEMD-160 of the
+//! SHA-256 of the compressed public key, encoded as Bech32 under the prefix
+//! of the chain, so the same key has an address on every chain.
+//!
+//! Wallets also need the public key itself to build transactions: as the
+//! legacy Amino JSON and binary encodings, or as the protobuf `Any` of
+//! the current SDK.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::bips::{bip32::ExtendedKey, bip39::Mnemonic, wordlists::Language};
+//! use wallet_rust::coins::cosmos::{self, Secp256k1PubKey};
+//!
+//! let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+//! let seed = Mnemonic::from_phrase(phrase, Language::English).unwrap().to_seed("");
+//! let key = ExtendedKey::new_master(&seed)
+//!     .unwrap()
+//!     .derive_path(&cosmos::derivation_path(0, 0).unwrap())
+//!     .unwrap();
+//!
+//! let public_key = Secp256k1PubKey::from_public_key(key.public_key()).unwrap();
+//! let address = public_key.address(cosmos::COSMOS_HUB).unwrap();
+//! assert_eq!(address.to_string(), "cosmos19rl4cm2hmr8afy4kldpxz3fka4jguq0auqdal4");
+//! assert_eq!(
+//!     address.with_hrp(cosmos::OSMOSIS).unwrap().to_string(),
+//!     "osmo19rl4cm2hmr8afy4kldpxz3fka4jguq0a5m7df8"
+//! );
+//! ```
+
+use k256::ecdsa::VerifyingKey;
+use laron_crypto::PublicKey;
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    bech32,
+    bips::{bip44, ChildNumber, DerivationPath},
+    error::{Result, WalletError},
+};
+
+/// Bech32 prefix of the Cosmos Hub.
+pub const COSMOS_HUB: &str = "cosmos";
+/// Bech32 prefix of Osmosis.
+pub const OSMOSIS: &str = "osmo";
+/// Bech32 prefix of Juno.
+pub const JUNO: &str = "juno";
+/// Bech32 prefix of Celestia.
+pub const CELESTIA: &str = "celestia";
+
+/// Amino prefix of secp256k1 public keys: the disambiguation and prefix
+/// bytes of `tendermint/PubKeySecp256k1` and the length of the key.
+const AMINO_PREFIX: [u8; 5] = [0xeb, 0x5a, 0xe9, 0x87, 0x21];
+const AMINO_TYPE: &str = "tendermint/PubKeySecp256k1";
+const PROTO_TYPE_URL: &str = "/cosmos.crypto.secp256k1.PubKey";
+
+/// Error returned when parsing a Cosmos address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CosmosError {
+    /// The data of the address is not 20 bytes long.
+    InvalidAddress,
+}
+
+impl std::fmt::Display for CosmosError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CosmosError::InvalidAddress => write!(f, "Invalid Cosmos address"),
+        }
+    }
+}
+
+impl std::error::Error for CosmosError {}
+
+/// Return the derivation path of an address, `m/44'/118'/account'/0/index`.
+pub fn derivation_path(account: u32, index: u32) -> Result<DerivationPath> {
+    Ok([
+        ChildNumber::hardened(bip44::PURPOSE),
+        ChildNumber::hardened(bip44::COSMOS),
+        ChildNumber::try_hardened(account)?,
+        ChildNumber::normal(0),
+        ChildNumber::try_normal(index)?,
+    ]
+    .into_iter()
+    .collect())
+}
+
+/// A compressed secp256k1 public key, as used by Cosmos SDK chains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Secp256k1PubKey([u8; 33]);
+
+impl Secp256k1PubKey {
+    /// Compress a public key.
+    pub fn from_public_key(public_key: &PublicKey) -> Result<Self> {
+        let key = VerifyingKey::from_sec1_bytes(&public_key.to_bytes())?;
+        let point = key.to_encoded_point(true);
+        Ok(Self(point.as_bytes().try_into().expect("33 bytes")))
+    }
+
+    /// Return the compressed key.
+    pub fn as_bytes(&self) -> &[u8; 33] {
+        &self.0
+    }
+
+    /// Return the address of the key on the chain of prefix `hrp`.
+    pub fn address(&self, hrp: &str) -> Result<CosmosAddress> {
+        let hash = Ripemd160::digest(Sha256::digest(self.0));
+        CosmosAddress::new(hrp, hash.into())
+    }
+
+    /// Return the Amino JSON of the key, as found in legacy transactions
+    /// and in the output of `keys show`.
+    pub fn amino_json(&self) -> String {
+        use base64::{engine::general_purpose::STANDARD, Engine};
+
+        serde_json::json!({ "type": AMINO_TYPE, "value": STANDARD.encode(self.0) }).to_string()
+    }
+
+    /// Return the Amino binary encoding of the key.
+    pub fn amino_bytes(&self) -> Vec<u8> {
+        [&AMINO_PREFIX[..], &self.0].concat()
+    }
+
+    /// Return the Bech32 encoding of the Amino bytes under a public key
+    /// prefix such as `cosmospub`.
+    pub fn to_bech32(&self, hrp: &str) -> Result<String> {
+        bech32::encode(hrp, self.amino_bytes())
+    }
+
+    /// Return the key wrapped in the protobuf `Any` of signer infos.
+    pub fn proto_any(&self) -> Any {
+        // `PubKey { bytes key = 1; }`
+        let mut value = vec![0x0a, self.0.len() as u8];
+        value.extend_from_slice(&self.0);
+        Any {
+            type_url: PROTO_TYPE_URL.to_string(),
+            value,
+        }
+    }
+}
+
+/// A protobuf `google.protobuf.Any`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Any {
+    /// The type of the message.
+    pub type_url: String,
+    /// The encoded message.
+    pub value: Vec<u8>,
+}
+
+impl Any {
+    /// Encode the `Any` itself as protobuf.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut encoded = Vec::with_capacity(self.type_url.len() + self.value.len() + 6);
+        for (tag, field) in [(0x0a, self.type_url.as_bytes()), (0x12, &self.value[..])] {
+            encoded.push(tag);
+            let mut len = field.len();
+            while len >= 0x80 {
+                encoded.push(len as u8 | 0x80);
+                len >>= 7;
+            }
+            encoded.push(len as u8);
+            encoded.extend_from_slice(field);
+        }
+        encoded
+    }
+}
+
+/// The Bech32 address of an account on a Cosmos SDK chain.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CosmosAddress {
+    hrp: String,
+    hash: [u8; 20],
+}
+
+impl CosmosAddress {
+    /// Create an address from the prefix of its chain and the hash of its
+    /// public key.
+    pub fn new(hrp: &str, hash: [u8; 20]) -> Result<Self> {
+        // reject prefixes that cannot be encoded
+        bech32::encode(hrp, hash)?;
+        Ok(Self {
+            hrp: hrp.to_lowercase(),
+            hash,
+        })
+    }
+
+    /// Return the prefix of the chain.
+    pub fn hrp(&self) -> &str {
+        &self.hrp
+    }
+
+    /// Return the hash of the public key.
+    pub fn as_bytes(&self) -> &[u8; 20] {
+        &self.hash
+    }
+
+    /// Return the address of the same account on the chain of prefix `hrp`.
+    pub fn with_hrp(&self, hrp: &str) -> Result<Self> {
+        Self::new(hrp, self.hash)
+    }
+}
+
+impl std::fmt::Display for CosmosAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let encoded = bech32::encode(&self.hrp, self.hash).map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", encoded)
+    }
+}
+
+impl std::str::FromStr for CosmosAddress {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (hrp, data) = bech32::decode(s)?;
+        let hash = data.try_into().map_err(|_| CosmosError::InvalidAddress)?;
+        Ok(Self { hrp, hash })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bips::{bip32::ExtendedKey, bip39::Mnemonic, wordlists::Language};
+
+    fn public_key() -> Secp256k1PubKey {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let seed = Mnemonic::from_phrase(phrase, Language::English)
+            .unwrap()
+            .to_seed("");
+        let key = ExtendedKey::new_master(&seed)
+            .unwrap()
+            .derive_path(&derivation_path(0, 0).unwrap())
+            .unwrap();
+        Secp256k1PubKey::from_public_key(key.public_key()).unwrap()
+    }
+
+    #[test]
+    fn test_address() {
+        assert_eq!(
+            derivation_path(1, 2).unwrap().to_string(),
+            "m/44'/118'/1'/0/2"
+        );
+        let address = public_key().address(COSMOS_HUB).unwrap();
+        assert_eq!(
+            address.to_string(),
+            "cosmos19rl4cm2hmr8afy4kldpxz3fka4jguq0auqdal4"
+        );
+        let parsed: CosmosAddress = "cosmos19rl4cm2hmr8afy4kldpxz3fka4jguq0auqdal4"
+            .parse()
+            .unwrap();
+        assert_eq!(parsed, address);
+        assert!("cosmos1zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3pahzj1"
+            .parse::<CosmosAddress>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_public_key_encodings() {
+        let public_key = public_key();
+        assert_eq!(
+            hex::encode(public_key.as_bytes()),
+            "024f4e2ad99c34d60b9ba6283c9431a8418af8673212961f97a77b6377fcd05b62"
+        );
+        assert_eq!(
+            public_key.amino_json(),
+            r#"{"type":"tendermint/PubKeySecp256k1","value":"Ak9OKtmcNNYLm6YoPJQxqEGK+GcyEpYfl6d7Y3f80Fti"}"#
+        );
+        assert_eq!(
+            public_key.to_bech32("cosmospub").unwrap(),
+            "cosmospub1addwnpepqf85u2kens6dvzum5c5re9p34pqc47r8xgffv8uh5aakxalu6pdky2qr0sc"
+        );
+
+        let any = public_key.proto_any();
+        assert_eq!(any.type_url, "/cosmos.crypto.secp256k1.PubKey");
+        assert_eq!(any.value[..2], [0x0a, 0x21]);
+        let encoded = any.encode();
+        assert_eq!(encoded[..2], [0x0a, 31]);
+        assert_eq!(encoded[33..35], [0x12, 35]);
+    }
+}
@@ -0,0 +1,239 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Filecoin
+//!
+//! Filecoin accounts with secp256k1 keys, derived along
+//! `m/44'/461'/account'/0/index`, have `f1` addresses: the BLAKE2b-160 of
+//! the uncompressed public key and a BLAKE2b-32 checksum of the protocol
+//! and key hash, in unpadded lowercase Base32. Test networks use the `t`
+//! prefix.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::bips::{bip32::ExtendedKey, bip39::Mnemonic, wordlists::Language};
+//! use wallet_rust::coins::filecoin::{self, Address, Network};
+//!
+//! let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+//! let seed = Mnemonic::from_phrase(phrase, Language::English).unwrap().to_seed("");
+//! let key = ExtendedKey::new_master(&seed)
+//!     .unwrap()
+//!     .derive_path(&filecoin::derivation_path(0, 0).unwrap())
+//!     .unwrap();
+//!
+//! let address = Address::from_public_key(key.public_key(), Network::Mainnet).unwrap();
+//! assert_eq!(address.to_string(), "f1qode47ievxlxzk6z2viuovedabmn3tq6t57uqhq");
+//! assert_eq!(address.to_string().parse::<Address>().unwrap(), address);
+//! ```
+
+use blake2::{
+    digest::consts::{U20, U4},
+    Blake2b, Digest,
+};
+use k256::ecdsa::VerifyingKey;
+use laron_crypto::PublicKey;
+
+use crate::{
+    bips::{bip44, ChildNumber, DerivationPath},
+    error::{Result, WalletError},
+};
+
+/// The protocol byte of secp256k1 addresses.
+const PROTOCOL_SECP256K1: u8 = 1;
+const BASE32: base32::Alphabet = base32::Alphabet::RFC4648 { padding: false };
+
+/// Error returned when parsing a Filecoin address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilecoinError {
+    /// The address is not a network prefix, a protocol and Base32 data.
+    InvalidAddress,
+    /// The protocol is not secp256k1, the only one supported.
+    UnsupportedProtocol(char),
+    /// The checksum of the address does not match.
+    InvalidChecksum,
+}
+
+impl std::fmt::Display for FilecoinError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FilecoinError::InvalidAddress => write!(f, "Invalid Filecoin address"),
+            FilecoinError::UnsupportedProtocol(protocol) => {
+                write!(f, "Unsupported Filecoin address protocol: {}", protocol)
+            }
+            FilecoinError::InvalidChecksum => write!(f, "Invalid Filecoin address checksum"),
+        }
+    }
+}
+
+impl std::error::Error for FilecoinError {}
+
+/// Return the derivation path of an address, `m/44'/461'/account'/0/index`.
+pub fn derivation_path(account: u32, index: u32) -> Result<DerivationPath> {
+    Ok([
+        ChildNumber::hardened(bip44::PURPOSE),
+        ChildNumber::hardened(bip44::FILECOIN),
+        ChildNumber::try_hardened(account)?,
+        ChildNumber::normal(0),
+        ChildNumber::try_normal(index)?,
+    ]
+    .into_iter()
+    .collect())
+}
+
+/// A Filecoin network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Network {
+    /// The main network, `f` addresses.
+    Mainnet,
+    /// The test networks, `t` addresses.
+    Testnet,
+}
+
+impl Network {
+    fn prefix(&self) -> char {
+        match self {
+            Network::Mainnet => 'f',
+            Network::Testnet => 't',
+        }
+    }
+}
+
+/// An `f1` address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Address {
+    network: Network,
+    payload: [u8; 20],
+}
+
+impl Address {
+    /// Create the address of a public key.
+    pub fn from_public_key(public_key: &PublicKey, network: Network) -> Result<Self> {
+        let key = VerifyingKey::from_sec1_bytes(&public_key.to_bytes())?;
+        let payload = Blake2b::<U20>::digest(key.to_encoded_point(false).as_bytes());
+        Ok(Self {
+            network,
+            payload: payload.into(),
+        })
+    }
+
+    /// Return the network of the address.
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    /// Return the hash of the public key.
+    pub fn payload(&self) -> &[u8; 20] {
+        &self.payload
+    }
+
+    /// Return the binary form of the address found in messages: the
+    /// protocol byte followed by the payload.
+    pub fn to_bytes(&self) -> [u8; 21] {
+        let mut bytes = [0u8; 21];
+        bytes[0] = PROTOCOL_SECP256K1;
+        bytes[1..].copy_from_slice(&self.payload);
+        bytes
+    }
+
+    fn checksum(&self) -> [u8; 4] {
+        Blake2b::<U4>::digest(self.to_bytes()).into()
+    }
+}
+
+impl std::fmt::Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let data = [&self.payload[..], &self.checksum()].concat();
+        let encoded = base32::encode(BASE32, &data).to_lowercase();
+        write!(
+            f,
+            "{}{}{}",
+            self.network.prefix(),
+            PROTOCOL_SECP256K1,
+            encoded
+        )
+    }
+}
+
+impl std::str::FromStr for Address {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut chars = s.chars();
+        let network = match chars.next() {
+            Some('f') => Network::Mainnet,
+            Some('t') => Network::Testnet,
+            _ => return Err(FilecoinError::InvalidAddress.into()),
+        };
+        match chars.next() {
+            Some('1') => {}
+            Some(protocol) => return Err(FilecoinError::UnsupportedProtocol(protocol).into()),
+            None => return Err(FilecoinError::InvalidAddress.into()),
+        }
+
+        let data = base32::decode(BASE32, &chars.as_str().to_uppercase())
+            .filter(|data| data.len() == 24)
+            .ok_or(FilecoinError::InvalidAddress)?;
+
+        let address = Self {
+            network,
+            payload: data[..20].try_into().expect("20 bytes"),
+        };
+        if address.checksum() != data[20..] {
+            return Err(FilecoinError::InvalidChecksum.into());
+        }
+        // reject uppercase and unused trailing bits
+        if address.to_string() != s {
+            return Err(FilecoinError::InvalidAddress.into());
+        }
+        Ok(address)
+    }
+}
+
+#[cfg(feature = "serde")]
+crate::types::serde_string!(Address);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_address() {
+        assert_eq!(
+            derivation_path(0, 1).unwrap().to_string(),
+            "m/44'/461'/0'/0/1"
+        );
+
+        let address: Address = "t1qode47ievxlxzk6z2viuovedabmn3tq6t57uqhq".parse().unwrap();
+        assert_eq!(address.network(), Network::Testnet);
+        assert_eq!(
+            address.to_string(),
+            "t1qode47ievxlxzk6z2viuovedabmn3tq6t57uqhq"
+        );
+        assert_eq!(address.to_bytes()[0], 1);
+    }
+
+    #[test]
+    fn test_invalid() {
+        for address in [
+            "f1qode47ievxlxzk6z2viuovedabmn3tq6t57uqhr",
+            "f1qode47ievxlxzk6z2viuovedabmn3tq6t57vqhq",
+            "F1qode47ievxlxzk6z2viuovedabmn3tq6t57uqhq",
+            "f1QODE47IEVXLXZK6Z2VIUOVEDABMN3TQ6T57UQHQ",
+            "f1qode47ievxlxzk6z2viuovedabmn3tq6t57uq",
+            "f2qode47ievxlxzk6z2viuovedabmn3tq6t57uqhq",
+            "f1",
+        ] {
+            assert!(address.parse::<Address>().is_err(), "{}", address);
+        }
+    }
+}
@@ -0,0 +1,245 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Solana
+//!
+//! Solana accounts are ed25519 keys derived with SLIP-10 along
+//! `m/44'/501'/account'/0'`, the path of Phantom, Solflare and the Solana
+//! CLI. The address is the Base58 encoding of the public key. Messages
+//! are signed with ed25519-dalek, through [`crate::ed25519`].
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::bips::{bip39::Mnemonic, wordlists::Language};
+//! use wallet_rust::coins::solana::{self, Keypair};
+//!
+//! let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+//! let seed = Mnemonic::from_phrase(phrase, Language::English).unwrap().to_seed("");
+//! let keypair = Keypair::from_seed(&seed, 0).unwrap();
+//! assert_eq!(keypair.address().to_string(), "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk");
+//!
+//! let signature = keypair.sign_message(b"hello");
+//! assert!(solana::verify_message(&keypair.address(), b"hello", &signature).is_ok());
+//! ```
+
+use zeroize::Zeroizing;
+
+use crate::{
+    base58,
+    bips::{bip32::slip10::Ed25519ExtendedKey, bip39::Seed, bip44, ChildNumber, DerivationPath},
+    ed25519::{Signature, SigningKey, VerifyingKey},
+    error::Result,
+};
+
+/// Error returned when reading Solana addresses and keypairs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolanaError {
+    /// The address does not decode to 32 bytes.
+    InvalidAddress,
+    /// The keypair is not 64 bytes, or its public half does not match its
+    /// secret.
+    InvalidKeypair,
+}
+
+impl std::fmt::Display for SolanaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SolanaError::InvalidAddress => write!(f, "Invalid Solana address"),
+            SolanaError::InvalidKeypair => write!(f, "Invalid Solana keypair"),
+        }
+    }
+}
+
+impl std::error::Error for SolanaError {}
+
+/// Return the derivation path of an account, `m/44'/501'/account'/0'`.
+pub fn derivation_path(account: u32) -> Result<DerivationPath> {
+    Ok([
+        ChildNumber::hardened(bip44::PURPOSE),
+        ChildNumber::hardened(bip44::SOLANA),
+        ChildNumber::try_hardened(account)?,
+        ChildNumber::hardened(0),
+    ]
+    .into_iter()
+    .collect())
+}
+
+/// A Solana address, the public key of an account or a program derived
+/// address, which is not on the curve.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Address([u8; 32]);
+
+impl Address {
+    /// Create an address from its 32 bytes.
+    pub fn new(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// Return the 32 bytes of the address.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl From<&VerifyingKey> for Address {
+    fn from(key: &VerifyingKey) -> Self {
+        Self(*key.as_bytes())
+    }
+}
+
+impl std::fmt::Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", base58::encode(self.0))
+    }
+}
+
+impl std::fmt::Debug for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Address({})", self)
+    }
+}
+
+impl std::str::FromStr for Address {
+    type Err = crate::error::WalletError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let bytes = base58::decode(s)?;
+        let bytes = bytes.try_into().map_err(|_| SolanaError::InvalidAddress)?;
+        Ok(Self(bytes))
+    }
+}
+
+/// The signing key of a Solana account.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Keypair(SigningKey);
+
+impl Keypair {
+    /// Derive the keypair of an account from a seed.
+    pub fn from_seed(seed: &Seed, account: u32) -> Result<Self> {
+        let key = Ed25519ExtendedKey::new_master(seed)?.derive_path(&derivation_path(account)?)?;
+        Ok(Self(key.signing_key()))
+    }
+
+    /// Read the Base58 encoding of the 64 byte secret and public key, the
+    /// format exported by Phantom and Solflare.
+    pub fn from_base58(encoded: &str) -> Result<Self> {
+        let bytes = Zeroizing::new(base58::decode(encoded)?);
+        if bytes.len() != 64 {
+            return Err(SolanaError::InvalidKeypair.into());
+        }
+        let key = SigningKey::from_bytes(bytes[..32].try_into().expect("32 bytes"));
+        if key.verifying_key().as_bytes()[..] != bytes[32..] {
+            return Err(SolanaError::InvalidKeypair.into());
+        }
+        Ok(Self(key))
+    }
+
+    /// Return the Base58 encoding of the 64 byte secret and public key.
+    pub fn to_base58(&self) -> Zeroizing<String> {
+        let mut bytes = Zeroizing::new([0u8; 64]);
+        bytes[..32].copy_from_slice(&*self.0.to_bytes());
+        bytes[32..].copy_from_slice(self.0.verifying_key().as_bytes());
+        Zeroizing::new(base58::encode(bytes.as_slice()))
+    }
+
+    /// Return the address of the account.
+    pub fn address(&self) -> Address {
+        Address::from(self.0.verifying_key())
+    }
+
+    /// Return the ed25519 signing key.
+    pub fn signing_key(&self) -> &SigningKey {
+        &self.0
+    }
+
+    /// Sign an off-chain message, as `signMessage` of the wallet adapters.
+    pub fn sign_message(&self, message: &[u8]) -> Signature {
+        self.0.sign(message)
+    }
+}
+
+impl From<SigningKey> for Keypair {
+    fn from(key: SigningKey) -> Self {
+        Self(key)
+    }
+}
+
+impl std::fmt::Debug for Keypair {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_tuple("Keypair").field(&self.address()).finish()
+    }
+}
+
+/// Verify the signature of a message by the account of `address`.
+pub fn verify_message(address: &Address, message: &[u8], signature: &Signature) -> Result<()> {
+    VerifyingKey::from_bytes(&address.0)?.verify(message, signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bips::{bip39::Mnemonic, wordlists::Language};
+
+    fn account(account: u32) -> Keypair {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+        Keypair::from_seed(&mnemonic.to_seed(""), account).unwrap()
+    }
+
+    #[test]
+    fn test_derive() {
+        assert_eq!(derivation_path(2).unwrap().to_string(), "m/44'/501'/2'/0'");
+
+        let keypair = account(0);
+        assert_eq!(
+            keypair.address().to_string(),
+            "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk"
+        );
+        assert_eq!(
+            hex::encode(*keypair.signing_key().to_bytes()),
+            "37df573b3ac4ad5b522e064e25b63ea16bcbe79d449e81a0268d1047948bb445"
+        );
+
+        let exported = keypair.to_base58();
+        assert_eq!(Keypair::from_base58(&exported).unwrap(), keypair);
+        assert_ne!(exported, account(1).to_base58());
+    }
+
+    #[test]
+    fn test_sign_message() {
+        let keypair = account(0);
+        let signature = keypair.sign_message(b"hello solana");
+        assert_eq!(
+            hex::encode(signature.to_bytes()),
+            "4ba99d939df9a3ba6beaf448ced8580c2de6e84a2d217590e31c72fce7ce4c0367df1908a6d6e6d027f7078fa0c592dbaeddc69ef38e75cfc675e86493ed9a09"
+        );
+
+        let address: Address = "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk"
+            .parse()
+            .unwrap();
+        assert!(verify_message(&address, b"hello solana", &signature).is_ok());
+        assert!(verify_message(&address, b"hello", &signature).is_err());
+
+        let key = ed25519_dalek::VerifyingKey::from_bytes(&address.0).unwrap();
+        let signature = ed25519_dalek::Signature::from_bytes(&signature.to_bytes());
+        assert!(key.verify_strict(b"hello solana", &signature).is_ok());
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!("11111".parse::<Address>().is_err());
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&*account(0).signing_key().to_bytes());
+        assert!(Keypair::from_base58(&base58::encode(bytes)).is_err());
+    }
+}
@@ -0,0 +1,417 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Substrate
+//!
+//! Accounts of Polkadot, Kusama and other Substrate chains, derived the way
+//! polkadot.js and `subkey` do.
+//!
+//! The root key is not derived from the BIP39 seed but from the entropy of
+//! the mnemonic, stretched with the password into a mini secret key. Keys
+//! are then derived along junctions: `//name` for a hard junction and
+//! `/name` for a soft one, a name being a number or any other string.
+//! sr25519 keys accept both, ed25519 keys only hard junctions.
+//!
+//! Addresses are SS58 encoded: a network prefix, the public key and a
+//! BLAKE2b checksum in Base58.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::bips::{bip39::Mnemonic, wordlists::Language};
+//! use wallet_rust::coins::substrate::{self, Address};
+//!
+//! let phrase = "bottom drive obey lake curtain smoke basket hold race lonely fit walk";
+//! let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+//! let keypair = substrate::sr25519_keypair(&mnemonic, "", "//Alice").unwrap();
+//!
+//! let address = Address::new(substrate::GENERIC, *keypair.public_key().as_bytes());
+//! assert_eq!(address.to_string(), "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY");
+//! assert_eq!(
+//!     address.with_prefix(substrate::POLKADOT).to_string(),
+//!     "15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5"
+//! );
+//!
+//! let signature = keypair.sign(b"payload");
+//! assert!(keypair.public_key().verify(b"payload", &signature).is_ok());
+//! ```
+
+use blake2::{digest::consts::U32, Blake2b, Blake2b512, Digest};
+use hmac::Hmac;
+use sha2::Sha512;
+use zeroize::Zeroizing;
+
+use crate::{
+    base58,
+    bips::bip39::Mnemonic,
+    ed25519::SigningKey,
+    error::{Result, WalletError},
+    sr25519,
+};
+
+/// SS58 prefix of Polkadot.
+pub const POLKADOT: u16 = 0;
+/// SS58 prefix of Kusama.
+pub const KUSAMA: u16 = 2;
+/// SS58 prefix of generic Substrate chains and test networks.
+pub const GENERIC: u16 = 42;
+
+/// Error returned when deriving Substrate keys or parsing addresses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubstrateError {
+    /// The derivation path is not a sequence of `/soft` and `//hard`
+    /// junctions.
+    InvalidPath,
+    /// A soft junction was used with an ed25519 key.
+    SoftJunction,
+    /// The prefix is above the 14 bits SS58 allows.
+    InvalidPrefix,
+    /// The address is not a prefix followed by a 32 byte key.
+    InvalidAddress,
+    /// The checksum of the address does not match.
+    InvalidChecksum,
+}
+
+impl std::fmt::Display for SubstrateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SubstrateError::InvalidPath => write!(f, "Invalid Substrate derivation path"),
+            SubstrateError::SoftJunction => {
+                write!(f, "Soft junctions are not supported by ed25519 keys")
+            }
+            SubstrateError::InvalidPrefix => write!(f, "Invalid SS58 prefix"),
+            SubstrateError::InvalidAddress => write!(f, "Invalid SS58 address"),
+            SubstrateError::InvalidChecksum => write!(f, "Invalid SS58 checksum"),
+        }
+    }
+}
+
+impl std::error::Error for SubstrateError {}
+
+/// A step of a derivation path, holding its 32 byte chain code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Junction {
+    /// A junction whose public key derives from the parent public key.
+    Soft([u8; 32]),
+    /// A junction that needs the parent secret key.
+    Hard([u8; 32]),
+}
+
+impl Junction {
+    /// Create a soft junction from its name.
+    pub fn soft(name: &str) -> Self {
+        Junction::Soft(chain_code(name))
+    }
+
+    /// Create a hard junction from its name.
+    pub fn hard(name: &str) -> Self {
+        Junction::Hard(chain_code(name))
+    }
+}
+
+/// Parse a derivation path such as `//polkadot//0/1`.
+pub fn parse_path(path: &str) -> Result<Vec<Junction>> {
+    let mut junctions = Vec::new();
+    let mut rest = path;
+    while !rest.is_empty() {
+        let (hard, tail) = match rest.strip_prefix("//") {
+            Some(tail) => (true, tail),
+            None => (
+                false,
+                rest.strip_prefix('/').ok_or(SubstrateError::InvalidPath)?,
+            ),
+        };
+        let end = tail.find('/').unwrap_or(tail.len());
+        let name = &tail[..end];
+        if name.is_empty() {
+            return Err(SubstrateError::InvalidPath.into());
+        }
+        junctions.push(if hard {
+            Junction::hard(name)
+        } else {
+            Junction::soft(name)
+        });
+        rest = &tail[end..];
+    }
+    Ok(junctions)
+}
+
+/// Return the mini secret key of a mnemonic, from which the keys of both
+/// schemes are derived.
+pub fn mini_secret(mnemonic: &Mnemonic, password: &str) -> Zeroizing<[u8; 32]> {
+    let salt = Zeroizing::new(format!("mnemonic{}", password));
+    let mut seed = Zeroizing::new([0u8; 64]);
+    pbkdf2::pbkdf2::<Hmac<Sha512>>(mnemonic.entropy(), salt.as_bytes(), 2048, &mut *seed);
+    let mut mini_secret = Zeroizing::new([0u8; 32]);
+    mini_secret.copy_from_slice(&seed[..32]);
+    mini_secret
+}
+
+/// Derive the sr25519 key pair of a mnemonic at `path`.
+pub fn sr25519_keypair(
+    mnemonic: &Mnemonic,
+    password: &str,
+    path: &str,
+) -> Result<sr25519::Keypair> {
+    let junctions = parse_path(path)?;
+    let root = sr25519::Keypair::from_mini_secret(&mini_secret(mnemonic, password));
+    Ok(junctions
+        .iter()
+        .fold(root, |keypair, junction| match junction {
+            Junction::Soft(chain_code) => keypair.soft_derive(chain_code),
+            Junction::Hard(chain_code) => keypair.hard_derive(chain_code),
+        }))
+}
+
+/// Derive the ed25519 signing key of a mnemonic at `path`, which must
+/// only have hard junctions.
+pub fn ed25519_keypair(mnemonic: &Mnemonic, password: &str, path: &str) -> Result<SigningKey> {
+    let mut seed = mini_secret(mnemonic, password);
+    for junction in parse_path(path)? {
+        let Junction::Hard(chain_code) = junction else {
+            return Err(SubstrateError::SoftJunction.into());
+        };
+        // `("Ed25519HDKD", seed, chain_code)` in SCALE
+        let mut hasher = Blake2b::<U32>::new();
+        hasher.update(scale_bytes(b"Ed25519HDKD"));
+        hasher.update(*seed);
+        hasher.update(chain_code);
+        seed.copy_from_slice(&hasher.finalize());
+    }
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+/// An SS58 address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Address {
+    prefix: u16,
+    account: [u8; 32],
+}
+
+impl Address {
+    /// Create the address of a public key on the network of `prefix`.
+    ///
+    /// # Panics
+    ///
+    /// If the prefix does not fit in 14 bits.
+    pub fn new(prefix: u16, account: [u8; 32]) -> Self {
+        assert!(prefix < 1 << 14, "SS58 prefix above 14 bits");
+        Self { prefix, account }
+    }
+
+    /// Return the network prefix.
+    pub fn prefix(&self) -> u16 {
+        self.prefix
+    }
+
+    /// Return the public key.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.account
+    }
+
+    /// Return the address of the same account on the network of `prefix`.
+    ///
+    /// # Panics
+    ///
+    /// If the prefix does not fit in 14 bits.
+    pub fn with_prefix(&self, prefix: u16) -> Self {
+        Self::new(prefix, self.account)
+    }
+
+    fn prefix_bytes(&self) -> Vec<u8> {
+        let prefix = self.prefix;
+        if prefix < 64 {
+            vec![prefix as u8]
+        } else {
+            vec![
+                ((prefix & 0xfc) >> 2) as u8 | 0x40,
+                (prefix >> 8) as u8 | ((prefix & 0x03) << 6) as u8,
+            ]
+        }
+    }
+}
+
+impl std::fmt::Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut data = [self.prefix_bytes(), self.account.to_vec()].concat();
+        data.extend_from_slice(&checksum(&data));
+        write!(f, "{}", base58::encode(data))
+    }
+}
+
+impl std::str::FromStr for Address {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let data = base58::decode(s)?;
+        let (prefix, prefix_len) = match data.first() {
+            Some(&first) if first < 64 => (first as u16, 1),
+            Some(&first) if first < 128 && data.len() > 1 => {
+                let lower = (first << 2) | (data[1] >> 6);
+                let upper = data[1] & 0x3f;
+                (lower as u16 | (upper as u16) << 8, 2)
+            }
+            Some(_) => return Err(SubstrateError::InvalidPrefix.into()),
+            None => return Err(SubstrateError::InvalidAddress.into()),
+        };
+        if data.len() != prefix_len + 32 + 2 {
+            return Err(SubstrateError::InvalidAddress.into());
+        }
+
+        let (body, check) = data.split_at(prefix_len + 32);
+        if checksum(body) != check {
+            return Err(SubstrateError::InvalidChecksum.into());
+        }
+        Ok(Self {
+            prefix,
+            account: body[prefix_len..].try_into().expect("32 bytes"),
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+crate::types::serde_string!(Address);
+
+fn checksum(body: &[u8]) -> [u8; 2] {
+    let mut hasher = Blake2b512::new();
+    hasher.update(b"SS58PRE");
+    hasher.update(body);
+    let hash = hasher.finalize();
+    [hash[0], hash[1]]
+}
+
+/// Return the chain code of a junction: the SCALE encoding of its name as
+/// a `u64` or a string, hashed if longer than 32 bytes.
+fn chain_code(name: &str) -> [u8; 32] {
+    let encoded = match name.parse::<u64>() {
+        Ok(index) => index.to_le_bytes().to_vec(),
+        Err(_) => scale_bytes(name.as_bytes()),
+    };
+    if encoded.len() > 32 {
+        return Blake2b::<U32>::digest(&encoded).into();
+    }
+    let mut chain_code = [0u8; 32];
+    chain_code[..encoded.len()].copy_from_slice(&encoded);
+    chain_code
+}
+
+/// Encode bytes in SCALE, prefixed with their compact length.
+fn scale_bytes(bytes: &[u8]) -> Vec<u8> {
+    let len = bytes.len() as u64;
+    let mut encoded = match len {
+        0..=0x3f => vec![(len << 2) as u8],
+        0x40..=0x3fff => ((len << 2) as u16 | 0b01).to_le_bytes().to_vec(),
+        0x4000..=0x3fff_ffff => ((len << 2) as u32 | 0b10).to_le_bytes().to_vec(),
+        _ => {
+            let bytes = len.to_le_bytes();
+            let size = 8 - len.leading_zeros() as usize / 8;
+            [&[(((size - 4) << 2) | 0b11) as u8][..], &bytes[..size]].concat()
+        }
+    };
+    encoded.extend_from_slice(bytes);
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bips::wordlists::Language;
+
+    const DEV_PHRASE: &str =
+        "bottom drive obey lake curtain smoke basket hold race lonely fit walk";
+
+    fn dev_mnemonic() -> Mnemonic {
+        Mnemonic::from_phrase(DEV_PHRASE, Language::English).unwrap()
+    }
+
+    #[test]
+    fn test_sr25519() {
+        let mnemonic = dev_mnemonic();
+        let vectors = [
+            (
+                "",
+                "46ebddef8cd9bb167dc30878d7113b7e168e6f0646beffd77d69d39bad76b47a",
+            ),
+            (
+                "//Alice",
+                "d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d",
+            ),
+            (
+                "//Bob",
+                "8eaf04151687736326c9fea17e25fc5287613693c912909cb226aa4794f26a48",
+            ),
+            (
+                "/Alice",
+                "d6c71059dbbe9ad2b0ed3f289738b800836eb425544ce694825285b958ca755e",
+            ),
+        ];
+        for (path, public_key) in vectors {
+            let keypair = sr25519_keypair(&mnemonic, "", path).unwrap();
+            assert_eq!(hex::encode(keypair.public_key().as_bytes()), public_key);
+        }
+    }
+
+    #[test]
+    fn test_ed25519() {
+        let mnemonic = dev_mnemonic();
+        let key = ed25519_keypair(&mnemonic, "", "//Alice").unwrap();
+        assert_eq!(
+            hex::encode(key.verifying_key().as_bytes()),
+            "88dc3417d5058ec4b4503e0c12ea1a0a89be200fe98922423d4334014fa6b0ee"
+        );
+        assert!(ed25519_keypair(&mnemonic, "", "//Alice/0").is_err());
+    }
+
+    #[test]
+    fn test_path() {
+        assert_eq!(
+            parse_path("//polkadot//0/1").unwrap(),
+            vec![
+                Junction::hard("polkadot"),
+                Junction::hard("0"),
+                Junction::soft("1"),
+            ]
+        );
+        let mut code = [0u8; 32];
+        code[..9].copy_from_slice(b"\x20polkadot");
+        assert_eq!(Junction::hard("polkadot"), Junction::Hard(code));
+        assert_eq!(Junction::soft("1"), Junction::Soft(chain_code("1")));
+        assert_eq!(chain_code("1")[..8], 1u64.to_le_bytes());
+        assert!(parse_path("").unwrap().is_empty());
+        for path in ["Alice", "///password", "//Alice/", "//"] {
+            assert!(parse_path(path).is_err());
+        }
+    }
+
+    #[test]
+    fn test_address() {
+        let account: [u8; 32] =
+            hex::decode("d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let vectors = [
+            (POLKADOT, "15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5"),
+            (KUSAMA, "HNZata7iMYWmk5RvZRTiAsSDhV8366zq2YGb3tLH5Upf74F"),
+            (GENERIC, "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"),
+            (1284, "VdvKmYJfD4VXA9fzz1SbmCo2eYHSzUFbaDCZSuaNKJAe8YNg6"),
+        ];
+        for (prefix, expected) in vectors {
+            let address = Address::new(prefix, account);
+            assert_eq!(address.to_string(), expected);
+            assert_eq!(expected.parse::<Address>().unwrap(), address);
+        }
+        assert!("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQZ"
+            .parse::<Address>()
+            .is_err());
+    }
+}
@@ -0,0 +1,284 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # XRP Ledger
+//!
+//! XRP accounts are secp256k1 keys derived along
+//! `m/44'/144'/account'/0/index`. The classic address is the
+//! Base58Check encoding, over the Ripple alphabet, of the RIPEMD-160 of the
+//! SHA-256 of the compressed public key.
+//!
+//! Exchanges tell their customers apart by a destination tag sent along
+//! with the classic address. An
+//! [X-address](https://xrpaddress.info) packs both, and the network, into
+//! a single string so the tag cannot be forgotten.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::bips::{bip32::ExtendedKey, bip39::Mnemonic, wordlists::Language};
+//! use wallet_rust::coins::xrp::{self, Address, Network, XAddress};
+//!
+//! let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+//! let seed = Mnemonic::from_phrase(phrase, Language::English).unwrap().to_seed("");
+//! let key = ExtendedKey::new_master(&seed)
+//!     .unwrap()
+//!     .derive_path(&xrp::derivation_path(0, 0).unwrap())
+//!     .unwrap();
+//!
+//! let address = Address::from_public_key(key.public_key()).unwrap();
+//! assert_eq!(address.to_string(), "rHsMGQEkVNJmpGWs8XUBoTBiAAbwxZN5v3");
+//!
+//! let x_address = XAddress::new(address, Some(12345), Network::Mainnet);
+//! assert_eq!(x_address.to_string(), "XVMFQQBMhdouRqhPMuawgBMN1AVFToDJjGL3vhmPNrNxcgS");
+//! ```
+
+use k256::ecdsa::VerifyingKey;
+use laron_crypto::PublicKey;
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    base58::{self, Alphabet},
+    bips::{bip44, ChildNumber, DerivationPath},
+    error::{Result, WalletError},
+};
+
+/// Error returned when parsing XRP addresses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XrpError {
+    /// The classic address is not a version 0 account ID.
+    InvalidAddress,
+    /// The X-address has an unknown prefix or tag encoding.
+    InvalidXAddress,
+}
+
+impl std::fmt::Display for XrpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            XrpError::InvalidAddress => write!(f, "Invalid XRP address"),
+            XrpError::InvalidXAddress => write!(f, "Invalid XRP X-address"),
+        }
+    }
+}
+
+impl std::error::Error for XrpError {}
+
+/// Return the derivation path of an address, `m/44'/144'/account'/0/index`.
+pub fn derivation_path(account: u32, index: u32) -> Result<DerivationPath> {
+    Ok([
+        ChildNumber::hardened(bip44::PURPOSE),
+        ChildNumber::hardened(bip44::XRP),
+        ChildNumber::try_hardened(account)?,
+        ChildNumber::normal(0),
+        ChildNumber::try_normal(index)?,
+    ]
+    .into_iter()
+    .collect())
+}
+
+/// A classic XRP address, holding the 20 byte account ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Address([u8; 20]);
+
+impl Address {
+    /// Create the address of a public key.
+    pub fn from_public_key(public_key: &PublicKey) -> Result<Self> {
+        let key = VerifyingKey::from_sec1_bytes(&public_key.to_bytes())?;
+        let hash = Ripemd160::digest(Sha256::digest(key.to_encoded_point(true).as_bytes()));
+        Ok(Self(hash.into()))
+    }
+
+    /// Return the account ID.
+    pub fn as_bytes(&self) -> &[u8; 20] {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let data = [&[0x00][..], &self.0].concat();
+        write!(f, "{}", base58::encode_check_with(Alphabet::Ripple, data))
+    }
+}
+
+impl std::str::FromStr for Address {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let data = base58::decode_check_with(Alphabet::Ripple, s)?;
+        match data.split_first() {
+            Some((0x00, account)) => Ok(Self(
+                account.try_into().map_err(|_| XrpError::InvalidAddress)?,
+            )),
+            _ => Err(XrpError::InvalidAddress.into()),
+        }
+    }
+}
+
+/// The network an X-address is valid on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Network {
+    /// The main network.
+    Mainnet,
+    /// The test and development networks.
+    Testnet,
+}
+
+impl Network {
+    fn prefix(&self) -> [u8; 2] {
+        match self {
+            Network::Mainnet => [0x05, 0x44],
+            Network::Testnet => [0x04, 0x93],
+        }
+    }
+}
+
+/// An X-address: a classic address, an optional destination tag and the
+/// network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct XAddress {
+    address: Address,
+    tag: Option<u32>,
+    network: Network,
+}
+
+impl XAddress {
+    /// Create an X-address.
+    pub fn new(address: Address, tag: Option<u32>, network: Network) -> Self {
+        Self {
+            address,
+            tag,
+            network,
+        }
+    }
+
+    /// Return the classic address.
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// Return the destination tag.
+    pub fn tag(&self) -> Option<u32> {
+        self.tag
+    }
+
+    /// Return the network.
+    pub fn network(&self) -> Network {
+        self.network
+    }
+}
+
+impl std::fmt::Display for XAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut data = Vec::with_capacity(31);
+        data.extend_from_slice(&self.network.prefix());
+        data.extend_from_slice(self.address.as_bytes());
+        data.push(self.tag.is_some() as u8);
+        // the tag is followed by 4 bytes reserved for 64-bit tags
+        data.extend_from_slice(&(self.tag.unwrap_or_default() as u64).to_le_bytes());
+        write!(f, "{}", base58::encode_check_with(Alphabet::Ripple, data))
+    }
+}
+
+impl std::str::FromStr for XAddress {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let data = base58::decode_check_with(Alphabet::Ripple, s)?;
+        if data.len() != 31 {
+            return Err(XrpError::InvalidXAddress.into());
+        }
+
+        let network = [Network::Mainnet, Network::Testnet]
+            .into_iter()
+            .find(|network| data[..2] == network.prefix())
+            .ok_or(XrpError::InvalidXAddress)?;
+        let address = Address(data[2..22].try_into().expect("20 bytes"));
+        let tag = u64::from_le_bytes(data[23..].try_into().expect("8 bytes"));
+        let tag = match (data[22], u32::try_from(tag)) {
+            (0, Ok(0)) => None,
+            (1, Ok(tag)) => Some(tag),
+            _ => return Err(XrpError::InvalidXAddress.into()),
+        };
+
+        Ok(Self {
+            address,
+            tag,
+            network,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+crate::types::serde_string!(Address);
+
+#[cfg(feature = "serde")]
+crate::types::serde_string!(XAddress);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_address() {
+        assert_eq!(
+            derivation_path(0, 3).unwrap().to_string(),
+            "m/44'/144'/0'/0/3"
+        );
+        let address: Address = "rGWrZyQqhTp9Xu7G5Pkayo7bXjH4k4QYpf".parse().unwrap();
+        assert_eq!(address.to_string(), "rGWrZyQqhTp9Xu7G5Pkayo7bXjH4k4QYpf");
+        assert!("rGWrZyQqhTp9Xu7G5Pkayo7bXjH4k4QYpg"
+            .parse::<Address>()
+            .is_err());
+        // a Bitcoin address is not valid over the Ripple alphabet
+        assert!("1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA"
+            .parse::<Address>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_x_address() {
+        // vectors of the X-address specification
+        let address: Address = "rGWrZyQqhTp9Xu7G5Pkayo7bXjH4k4QYpf".parse().unwrap();
+        let vectors = [
+            (
+                None,
+                Network::Mainnet,
+                "XVLhHMPHU98es4dbozjVtdWzVrDjtV5fdx1mHp98tDMoQXb",
+            ),
+            (
+                Some(1),
+                Network::Mainnet,
+                "XVLhHMPHU98es4dbozjVtdWzVrDjtV8xvjGQTYPiAx6gwDC",
+            ),
+            (
+                Some(u32::MAX),
+                Network::Mainnet,
+                "XVLhHMPHU98es4dbozjVtdWzVrDjtV18pX8yuPT7y4xaEHi",
+            ),
+            (
+                None,
+                Network::Testnet,
+                "TVE26TYGhfLC7tQDno7G8dGtxSkYQn49b3qD26PK7FcGSKE",
+            ),
+        ];
+        for (tag, network, expected) in vectors {
+            let x_address = XAddress::new(address, tag, network);
+            assert_eq!(x_address.to_string(), expected);
+            assert_eq!(expected.parse::<XAddress>().unwrap(), x_address);
+        }
+        assert!("rGWrZyQqhTp9Xu7G5Pkayo7bXjH4k4QYpf"
+            .parse::<XAddress>()
+            .is_err());
+    }
+}
@@ -0,0 +1,273 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Public Key Encryption
+//!
+//! Encrypt data to a public key with ECIES over secp256k1: an ephemeral key
+//! agrees on a secret with the recipient, HKDF-SHA256 turns it into a key and
+//! XChaCha20-Poly1305 seals the payload.
+//!
+//! Encryption keys live on their own hardened branch of the account,
+//! `m/44'/60'/{account}'/2'/{index}'`, and are only exposed through
+//! [`EncryptionKey`], which cannot sign. A key used to decrypt notes or
+//! payloads therefore never doubles as a transaction-signing key, and leaking
+//! it does not expose the account's signing keys.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::bips::bip39::Mnemonic;
+//! use wallet_rust::bips::bip32::ExtendedKey;
+//! use wallet_rust::bips::wordlists::Language;
+//! use wallet_rust::ecies::EncryptionKey;
+//!
+//! let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+//! let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+//! let master = ExtendedKey::new_master(&mnemonic.to_seed("")).unwrap();
+//!
+//! let key = EncryptionKey::derive(&master, 0, 0).unwrap();
+//! let ciphertext = key.public_key().encrypt(b"secret note").unwrap();
+//! assert_eq!(key.decrypt(&ciphertext).unwrap(), b"secret note");
+//! ```
+
+use chacha20poly1305::{aead::Aead, KeyInit, XChaCha20Poly1305, XNonce};
+use hkdf::Hkdf;
+use k256::{ecdh, PublicKey, SecretKey};
+use rand::Rng;
+use sha2::Sha256;
+
+use crate::{
+    bips::{bip32::ExtendedKey, ChildNumber, DerivationPath},
+    error::Result,
+};
+
+/// Branch below the account node reserved for encryption keys.
+pub const ENCRYPTION_BRANCH: u32 = 2;
+
+const PUBLIC_KEY_LEN: usize = 33;
+const NONCE_LEN: usize = 24;
+const TAG_LEN: usize = 16;
+
+const ECIES_INFO: &[u8] = b"wallet-rust ecies v1";
+
+/// Error returned by encryption and decryption.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EciesError {
+    /// Encryption keys must be derived from the master key.
+    NotMaster,
+    /// The ciphertext is too short to be valid.
+    InvalidCiphertext,
+    /// The ciphertext was not encrypted to this key or was tampered with.
+    DecryptionFailed,
+}
+
+impl std::fmt::Display for EciesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            EciesError::NotMaster => {
+                write!(f, "Encryption keys must be derived from the master key")
+            }
+            EciesError::InvalidCiphertext => write!(f, "Invalid ciphertext"),
+            EciesError::DecryptionFailed => write!(f, "Decryption failed"),
+        }
+    }
+}
+
+impl std::error::Error for EciesError {}
+
+/// A private key reserved for decryption.
+#[derive(Clone)]
+pub struct EncryptionKey(SecretKey);
+
+impl EncryptionKey {
+    /// Derive the encryption key at `m/44'/60'/{account}'/2'/{index}'`.
+    pub fn derive(master: &ExtendedKey, account: u32, index: u32) -> Result<Self> {
+        if master.depth() != 0 {
+            return Err(EciesError::NotMaster.into());
+        }
+
+        let key = master.derive_path(&Self::path(account, index))?;
+        Ok(Self(SecretKey::from_slice(&key.private_key().to_bytes())?))
+    }
+
+    /// Return the derivation path of the encryption key.
+    pub fn path(account: u32, index: u32) -> DerivationPath {
+        [44, 60, account, ENCRYPTION_BRANCH, index]
+            .into_iter()
+            .map(ChildNumber::hardened)
+            .collect()
+    }
+
+    /// Return the public key to share with senders.
+    pub fn public_key(&self) -> EncryptionPublicKey {
+        EncryptionPublicKey(self.0.public_key())
+    }
+
+    /// Compute the ECDH shared secret with the given public key, the
+    /// x-coordinate of the shared point.
+    pub fn diffie_hellman(&self, public_key: &EncryptionPublicKey) -> [u8; 32] {
+        let shared = ecdh::diffie_hellman(self.0.to_nonzero_scalar(), public_key.0.as_affine());
+        (*shared.raw_secret_bytes()).into()
+    }
+
+    /// Decrypt a ciphertext produced by [`EncryptionPublicKey::encrypt`].
+    pub fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        if ciphertext.len() < PUBLIC_KEY_LEN + NONCE_LEN + TAG_LEN {
+            return Err(EciesError::InvalidCiphertext.into());
+        }
+
+        let (ephemeral, rest) = ciphertext.split_at(PUBLIC_KEY_LEN);
+        let ephemeral =
+            PublicKey::from_sec1_bytes(ephemeral).map_err(|_| EciesError::InvalidCiphertext)?;
+
+        let cipher = cipher(&self.0, &ephemeral, &ephemeral, &self.0.public_key());
+        open(&cipher, rest)
+    }
+}
+
+impl std::fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_tuple("EncryptionKey")
+            .field(&self.public_key())
+            .finish()
+    }
+}
+
+/// A public key data can be encrypted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncryptionPublicKey(PublicKey);
+
+impl EncryptionPublicKey {
+    /// Parse a SEC1 encoded public key.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(Self(PublicKey::from_sec1_bytes(bytes)?))
+    }
+
+    /// Return the compressed SEC1 encoding of the key.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_sec1_bytes().to_vec()
+    }
+
+    /// Encrypt the plaintext to this key. The output is the ephemeral public
+    /// key, followed by the nonce and the sealed payload.
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let ephemeral = SecretKey::random(&mut rand::thread_rng());
+        let ephemeral_public = ephemeral.public_key();
+
+        let cipher = cipher(&ephemeral, &self.0, &ephemeral_public, &self.0);
+
+        let mut output = ephemeral_public.to_sec1_bytes().to_vec();
+        output.extend_from_slice(&seal(&cipher, plaintext)?);
+        Ok(output)
+    }
+}
+
+/// Derive the cipher shared by `secret` and `public`, binding it to the
+/// ephemeral and recipient keys.
+fn cipher(
+    secret: &SecretKey,
+    public: &PublicKey,
+    ephemeral: &PublicKey,
+    recipient: &PublicKey,
+) -> XChaCha20Poly1305 {
+    let shared = ecdh::diffie_hellman(secret.to_nonzero_scalar(), public.as_affine());
+
+    let mut salt = ephemeral.to_sec1_bytes().to_vec();
+    salt.extend_from_slice(&recipient.to_sec1_bytes());
+
+    derive_cipher(shared.raw_secret_bytes(), &salt, ECIES_INFO)
+}
+
+pub(crate) fn derive_cipher(ikm: &[u8], salt: &[u8], info: &[u8]) -> XChaCha20Poly1305 {
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(salt), ikm)
+        .expand(info, &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+
+    XChaCha20Poly1305::new(&key.into())
+}
+
+/// Encrypt the plaintext with a random nonce, returning `nonce || ciphertext`.
+pub(crate) fn seal(cipher: &XChaCha20Poly1305, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill(&mut nonce);
+
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce), plaintext)
+        .map_err(|_| EciesError::InvalidCiphertext)?;
+
+    let mut output = nonce.to_vec();
+    output.extend_from_slice(&ciphertext);
+    Ok(output)
+}
+
+/// Decrypt a `nonce || ciphertext` payload produced by [`seal`].
+pub(crate) fn open(cipher: &XChaCha20Poly1305, sealed: &[u8]) -> Result<Vec<u8>> {
+    if sealed.len() < NONCE_LEN + TAG_LEN {
+        return Err(EciesError::InvalidCiphertext.into());
+    }
+
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    Ok(cipher
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| EciesError::DecryptionFailed)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bips::{bip39::Mnemonic, wordlists::Language};
+
+    fn master() -> ExtendedKey {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+        ExtendedKey::new_master(&mnemonic.to_seed("")).unwrap()
+    }
+
+    #[test]
+    fn test_encrypt() {
+        let key = EncryptionKey::derive(&master(), 0, 0).unwrap();
+        let public_key = EncryptionPublicKey::from_bytes(&key.public_key().to_bytes()).unwrap();
+
+        let ciphertext = public_key.encrypt(b"hello").unwrap();
+        assert_eq!(ciphertext.len(), 33 + 24 + 5 + 16);
+        assert_eq!(key.decrypt(&ciphertext).unwrap(), b"hello");
+
+        let mut tampered = ciphertext.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(key.decrypt(&tampered).is_err());
+
+        let other = EncryptionKey::derive(&master(), 0, 1).unwrap();
+        assert!(other.decrypt(&ciphertext).is_err());
+        assert!(key.decrypt(&ciphertext[..40]).is_err());
+    }
+
+    #[test]
+    fn test_key_separation() {
+        let master = master();
+        let key = EncryptionKey::derive(&master, 0, 0).unwrap();
+        assert_eq!(EncryptionKey::path(0, 0).to_string(), "m/44'/60'/0'/2'/0'");
+
+        let signing = master
+            .derive_path(&DerivationPath::parse("m/44'/60'/0'/0/0").unwrap())
+            .unwrap();
+        assert_ne!(
+            key.public_key().to_bytes(),
+            signing.public_key().to_bytes().to_vec()
+        );
+
+        let account = master
+            .derive_path(&DerivationPath::parse("m/44'/60'/0'").unwrap())
+            .unwrap();
+        assert!(EncryptionKey::derive(&account, 0, 0).is_err());
+    }
+}
@@ -0,0 +1,233 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Ed25519
+//!
+//! Keys and signatures of the ed25519 scheme of
+//! [RFC 8032](https://www.rfc-editor.org/rfc/rfc8032), used by Solana,
+//! Stellar and other chains whose keys are derived with SLIP-10.
+//!
+//! The curve arithmetic is that of
+//! [ed25519-dalek](https://github.com/dalek-cryptography/curve25519-dalek),
+//! which handles secrets in constant time.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::ed25519::SigningKey;
+//!
+//! let secret = hex::decode("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60").unwrap();
+//! let key = SigningKey::from_bytes(&secret.try_into().unwrap());
+//! assert_eq!(
+//!     hex::encode(key.verifying_key().as_bytes()),
+//!     "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+//! );
+//!
+//! let signature = key.sign(b"hello");
+//! assert!(key.verifying_key().verify(b"hello", &signature).is_ok());
+//! assert!(key.verifying_key().verify(b"hallo", &signature).is_err());
+//! ```
+
+use ed25519_dalek::Signer;
+use zeroize::Zeroizing;
+
+use crate::error::Result;
+
+/// Error returned when reading ed25519 keys or verifying signatures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ed25519Error {
+    /// The bytes do not encode a point of the curve.
+    InvalidPublicKey,
+    /// The signature does not match the message and key.
+    InvalidSignature,
+}
+
+impl std::fmt::Display for Ed25519Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Ed25519Error::InvalidPublicKey => write!(f, "Invalid ed25519 public key"),
+            Ed25519Error::InvalidSignature => write!(f, "Invalid ed25519 signature"),
+        }
+    }
+}
+
+impl std::error::Error for Ed25519Error {}
+
+/// An ed25519 signature, the encoded point `R` followed by the scalar `S`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Signature([u8; 64]);
+
+impl Signature {
+    /// Create a signature from its 64 bytes.
+    pub fn from_bytes(bytes: &[u8; 64]) -> Self {
+        Self(*bytes)
+    }
+
+    /// Return the 64 bytes of the signature.
+    pub fn to_bytes(&self) -> [u8; 64] {
+        self.0
+    }
+}
+
+impl std::fmt::Debug for Signature {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Signature({})", hex::encode(self.0))
+    }
+}
+
+/// An ed25519 private key, the 32 byte seed of RFC 8032.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SigningKey {
+    key: ed25519_dalek::SigningKey,
+    verifying_key: VerifyingKey,
+}
+
+impl SigningKey {
+    /// Create a key from its 32 byte secret.
+    pub fn from_bytes(secret: &[u8; 32]) -> Self {
+        let key = ed25519_dalek::SigningKey::from_bytes(secret);
+        let verifying_key = VerifyingKey(key.verifying_key().to_bytes());
+        Self { key, verifying_key }
+    }
+
+    /// Return the 32 byte secret.
+    pub fn to_bytes(&self) -> Zeroizing<[u8; 32]> {
+        Zeroizing::new(self.key.to_bytes())
+    }
+
+    /// Return the public key.
+    pub fn verifying_key(&self) -> &VerifyingKey {
+        &self.verifying_key
+    }
+
+    /// Sign a message. Signatures are deterministic.
+    pub fn sign(&self, message: &[u8]) -> Signature {
+        Signature(self.key.sign(message).to_bytes())
+    }
+}
+
+impl std::fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("SigningKey")
+            .field("verifying_key", &self.verifying_key)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An ed25519 public key, the compressed curve point.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VerifyingKey([u8; 32]);
+
+impl VerifyingKey {
+    /// Read a key from its 32 byte encoding, checking that it is the
+    /// canonical encoding of a point of the curve.
+    pub fn from_bytes(bytes: &[u8; 32]) -> Result<Self> {
+        decode(bytes)?;
+        Ok(Self(*bytes))
+    }
+
+    /// Verify the signature of a message, rejecting malleable signatures
+    /// and keys of small order.
+    pub fn verify(&self, message: &[u8], signature: &Signature) -> Result<()> {
+        let key = decode(&self.0)?;
+        let signature = ed25519_dalek::Signature::from_bytes(&signature.0);
+        key.verify_strict(message, &signature)
+            .map_err(|_| Ed25519Error::InvalidSignature.into())
+    }
+
+    /// Return the 32 byte encoding of the key.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for VerifyingKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "VerifyingKey({})", hex::encode(self.0))
+    }
+}
+
+/// Decompress a public key, rejecting a `y` coordinate that is not
+/// reduced modulo the field prime as RFC 8032 does.
+fn decode(bytes: &[u8; 32]) -> Result<ed25519_dalek::VerifyingKey> {
+    let key = ed25519_dalek::VerifyingKey::from_bytes(bytes)
+        .map_err(|_| Ed25519Error::InvalidPublicKey)?;
+    if key.to_edwards().compress().as_bytes() != bytes {
+        return Err(Ed25519Error::InvalidPublicKey.into());
+    }
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(secret: &str) -> SigningKey {
+        SigningKey::from_bytes(&hex::decode(secret).unwrap().try_into().unwrap())
+    }
+
+    #[test]
+    fn test_verifying_key() {
+        // RFC 8032 test vectors 1 to 3
+        let vectors = [
+            (
+                "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+                "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            ),
+            (
+                "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+                "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+            ),
+            (
+                "c5aa8df43f9f837bedb7442f31dcb7b166d38535076f094b85ce3a2e0b4458f7",
+                "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+            ),
+        ];
+        for (secret, public) in vectors {
+            assert_eq!(hex::encode(key(secret).verifying_key().as_bytes()), public);
+        }
+    }
+
+    #[test]
+    fn test_sign() {
+        // RFC 8032 test vectors 1 and 2
+        let key1 = key("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60");
+        let signature = key1.sign(b"");
+        assert_eq!(
+            hex::encode(signature.to_bytes()),
+            "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b"
+        );
+        let key2 = key("4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb");
+        assert_eq!(
+            hex::encode(key2.sign(&[0x72]).to_bytes()),
+            "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00"
+        );
+
+        let public = VerifyingKey::from_bytes(key1.verifying_key().as_bytes()).unwrap();
+        assert!(public.verify(b"", &signature).is_ok());
+        assert!(public.verify(b"x", &signature).is_err());
+        assert!(key2.verifying_key().verify(b"", &signature).is_err());
+
+        let mut bytes = signature.to_bytes();
+        bytes[63] |= 0x80;
+        assert!(public.verify(b"", &Signature::from_bytes(&bytes)).is_err());
+    }
+
+    #[test]
+    fn test_invalid_public_key() {
+        // y = 2 has no x on the curve
+        let mut bytes = [0u8; 32];
+        bytes[0] = 2;
+        assert!(VerifyingKey::from_bytes(&bytes).is_err());
+        assert!(VerifyingKey::from_bytes(&[0xff; 32]).is_err());
+    }
+}
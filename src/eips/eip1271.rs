@@ -0,0 +1,184 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Signature validation for contract accounts as defined in
+//! [EIP-1271](https://eips.ethereum.org/EIPS/eip-1271).
+//!
+//! Smart wallets like Safe cannot produce an ECDSA signature of their own.
+//! Instead they expose `isValidSignature(bytes32,bytes)`, which returns a
+//! magic value when the contract accepts the signature for the given hash.
+
+use crate::{
+    address::Address,
+    error::{Result, WalletError},
+    provider::{Provider, ProviderError},
+    signature::Signature,
+};
+
+/// Selector of `isValidSignature(bytes32,bytes)`, also the magic value
+/// returned on success.
+pub const MAGIC_VALUE: [u8; 4] = [0x16, 0x26, 0xba, 0x7e];
+
+/// Error returned when a signature cannot be checked through EIP-1271.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Eip1271Error {
+    /// The signer has no code deployed, it is not a contract account.
+    NotContract,
+}
+
+impl std::fmt::Display for Eip1271Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Eip1271Error::NotContract => write!(f, "signer is not a contract account"),
+        }
+    }
+}
+
+impl std::error::Error for Eip1271Error {}
+
+/// Return the ABI encoded calldata of `isValidSignature(hash, signature)`.
+pub fn encode_call(hash: &[u8; 32], signature: &[u8]) -> Vec<u8> {
+    let padded = signature.len().div_ceil(32) * 32;
+    let mut data = Vec::with_capacity(4 + 32 * 3 + padded);
+
+    data.extend_from_slice(&MAGIC_VALUE);
+    data.extend_from_slice(hash);
+    data.extend_from_slice(&word(0x40));
+    data.extend_from_slice(&word(signature.len() as u64));
+    data.extend_from_slice(signature);
+    data.resize(4 + 32 * 3 + padded, 0);
+
+    data
+}
+
+/// Return true if the returned data of `isValidSignature` is the magic value.
+pub fn is_magic_value(output: &[u8]) -> bool {
+    output.len() >= 32 && output[..4] == MAGIC_VALUE && output[4..32].iter().all(|b| *b == 0)
+}
+
+/// Check the signature of the given hash against the contract account at
+/// `signer`. Reverting calls are treated as an invalid signature, as the
+/// specification allows contracts to revert instead of returning. Any other
+/// failure of the provider is returned.
+pub fn is_valid_signature<P: Provider>(
+    provider: &P,
+    signer: &Address,
+    hash: &[u8; 32],
+    signature: &[u8],
+) -> Result<bool> {
+    if provider.get_code(signer)?.is_empty() {
+        return Err(Eip1271Error::NotContract.into());
+    }
+
+    match provider.call(signer, &encode_call(hash, signature)) {
+        Ok(output) => Ok(is_magic_value(&output)),
+        Err(WalletError::Provider(ProviderError::Reverted(_))) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Check the signature of the given hash for any kind of account: contract
+/// accounts are asked through `isValidSignature`, while the signer of an
+/// externally owned account is recovered from the signature.
+pub fn verify_signature<P: Provider>(
+    provider: &P,
+    signer: &Address,
+    hash: &[u8; 32],
+    signature: &[u8],
+) -> Result<bool> {
+    if !provider.get_code(signer)?.is_empty() {
+        return is_valid_signature(provider, signer, hash, signature);
+    }
+
+    let signature = Signature::from_bytes(signature)?;
+    Ok(signature.recover(hash)? == *signer)
+}
+
+fn word(value: u64) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::provider::mock::MockProvider;
+
+    /// A wallet contract at `address` accepting `signature`, reverting on
+    /// signatures starting with 0xdd and unreachable on 0xee.
+    fn wallet(address: Address, signature: Vec<u8>) -> MockProvider {
+        let mut wallet = MockProvider::new().with_call(move |_, data| {
+            let len = u64::from_be_bytes(data[4 + 64 + 24..4 + 96].try_into().unwrap());
+            let given = &data[4 + 96..4 + 96 + len as usize];
+            match given[0] {
+                0xdd => return Err(ProviderError::Reverted(Vec::new()).into()),
+                0xee => return Err(ProviderError::Transport("timeout".to_string()).into()),
+                _ => {}
+            }
+
+            let mut output = vec![0u8; 32];
+            if given == signature.as_slice() {
+                output[..4].copy_from_slice(&MAGIC_VALUE);
+            }
+            Ok(output)
+        });
+        wallet.code.insert(address, vec![0x60, 0x80]);
+        wallet
+    }
+
+    #[test]
+    fn test_encode_call() {
+        let data = encode_call(&[0xaa; 32], &[0xbb; 65]);
+        assert_eq!(data.len(), 4 + 32 * 3 + 96);
+        assert_eq!(hex::encode(&data[..4]), "1626ba7e");
+        assert_eq!(data[4 + 32 + 31], 0x40);
+        assert_eq!(data[4 + 64 + 31], 65);
+    }
+
+    #[test]
+    fn test_is_valid_signature() {
+        let contract = Address::new([1; 20]);
+        let wallet = wallet(contract, vec![0xbb; 65]);
+        let hash = [0xaa; 32];
+
+        assert!(is_valid_signature(&wallet, &contract, &hash, &[0xbb; 65]).unwrap());
+        assert!(!is_valid_signature(&wallet, &contract, &hash, &[0xcc; 65]).unwrap());
+        assert!(!is_valid_signature(&wallet, &contract, &hash, &[0xdd; 65]).unwrap());
+        assert_eq!(
+            is_valid_signature(&wallet, &contract, &hash, &[0xee; 65]).unwrap_err(),
+            ProviderError::Transport("timeout".to_string()).into()
+        );
+        assert!(is_valid_signature(&wallet, &Address::default(), &hash, &[0xbb; 65]).is_err());
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let contract = Address::new([1; 20]);
+        let wallet = wallet(contract, vec![0xbb; 65]);
+        let hash = crate::eips::eip191::hash_message("Some data");
+        let signature = hex::decode(
+            "b91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd\
+             6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a0291c",
+        )
+        .unwrap();
+        let signer = "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23"
+            .parse::<Address>()
+            .unwrap();
+
+        assert!(verify_signature(&wallet, &signer, &hash, &signature).unwrap());
+        assert!(!verify_signature(&wallet, &Address::default(), &hash, &signature).unwrap());
+        assert!(verify_signature(&wallet, &contract, &hash, &[0xbb; 65]).unwrap());
+    }
+}
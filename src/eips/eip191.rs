@@ -0,0 +1,41 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Signed data standard as defined in [EIP-191](https://eips.ethereum.org/EIPS/eip-191).
+//!
+//! Only version `0x45` (`personal_sign`) is implemented, which prefixes the
+//! message with its length so a signed message can never be a valid
+//! transaction.
+
+use crate::{types::B256, utils::keccak256};
+
+/// Return the hash of a `personal_sign` message.
+pub fn hash_message(message: impl AsRef<[u8]>) -> B256 {
+    let message = message.as_ref();
+    let mut data = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+    data.extend_from_slice(message);
+    B256::new(keccak256(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_message() {
+        assert_eq!(
+            hex::encode(hash_message("Hello World")),
+            "a1de988600a42c4b4ab089b619297c17d53cffae5d5120d82d8a92d0bb3b78f2"
+        );
+    }
+}
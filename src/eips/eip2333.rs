@@ -0,0 +1,161 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # EIP-2333
+//!
+//! The [BLS12-381 key derivation](https://eips.ethereum.org/EIPS/eip-2333)
+//! of Ethereum validators. Each child key is derived from a Lamport public
+//! key computed from its parent, so unlike BIP32 there is no public
+//! derivation and no hardened indices: every index is a full `u32`.
+//!
+//! The paths of the validator keys are defined by [`super::eip2334`].
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::eips::eip2333;
+//!
+//! let seed = [7u8; 32];
+//! let master = eip2333::derive_master_sk(&seed).unwrap();
+//! let child = eip2333::derive_child_sk(&master, 0);
+//! assert_eq!(eip2333::derive_path(&seed, "m/0").unwrap(), child);
+//! ```
+
+use crypto_bigint::{Encoding, NonZero, U384};
+use hkdf::Hkdf;
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+use crate::{
+    bls::{BlsError, SecretKey, R},
+    error::Result,
+};
+
+/// Number of 32 byte chunks of a Lamport secret key.
+const LAMPORT_CHUNKS: usize = 255;
+
+/// Derive the master key of a seed, which must be at least 32 bytes long.
+pub fn derive_master_sk(seed: &[u8]) -> Result<SecretKey> {
+    if seed.len() < 32 {
+        return Err(BlsError::SeedLength.into());
+    }
+    Ok(hkdf_mod_r(seed))
+}
+
+/// Derive the child of `parent` at `index`.
+pub fn derive_child_sk(parent: &SecretKey, index: u32) -> SecretKey {
+    hkdf_mod_r(&parent_sk_to_lamport_pk(parent, index))
+}
+
+/// Derive the key of a seed at a path such as `m/12381/3600/0/0/0`.
+pub fn derive_path(seed: &[u8], path: &str) -> Result<SecretKey> {
+    let mut components = path.split('/');
+    if components.next() != Some("m") {
+        return Err(BlsError::InvalidPath.into());
+    }
+
+    let indices = components
+        .map(|index| match index.bytes().all(|b| b.is_ascii_digit()) {
+            true => index.parse::<u32>().map_err(|_| BlsError::InvalidPath),
+            false => Err(BlsError::InvalidPath),
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    Ok(indices
+        .into_iter()
+        .fold(derive_master_sk(seed)?, |key, index| {
+            derive_child_sk(&key, index)
+        }))
+}
+
+/// Compress the Lamport public key of the parent key and its complement.
+fn parent_sk_to_lamport_pk(parent: &SecretKey, index: u32) -> [u8; 32] {
+    let salt = index.to_be_bytes();
+    let ikm = parent.to_bytes();
+    let not_ikm = Zeroizing::new(ikm.map(|b| !b));
+
+    let mut lamport_pk = Sha256::new();
+    for ikm in [&*ikm, &*not_ikm] {
+        let mut lamport_sk = Zeroizing::new([0u8; 32 * LAMPORT_CHUNKS]);
+        Hkdf::<Sha256>::new(Some(&salt), ikm)
+            .expand(&[], &mut *lamport_sk)
+            .expect("255 blocks is the largest valid HKDF-SHA256 output length");
+        for chunk in lamport_sk.chunks(32) {
+            lamport_pk.update(Sha256::digest(chunk));
+        }
+    }
+    lamport_pk.finalize().into()
+}
+
+/// The `KeyGen` of the BLS signature draft, hashing the key material to a
+/// non-zero scalar.
+fn hkdf_mod_r(ikm: &[u8]) -> SecretKey {
+    let r = NonZero::new(R.resize::<{ U384::LIMBS }>()).unwrap();
+    let mut ikm = Zeroizing::new(ikm.to_vec());
+    ikm.push(0);
+
+    let mut salt = Sha256::digest(b"BLS-SIG-KEYGEN-SALT-");
+    loop {
+        let mut okm = Zeroizing::new([0u8; 48]);
+        Hkdf::<Sha256>::new(Some(&salt), &ikm)
+            .expand(&48u16.to_be_bytes(), &mut *okm)
+            .expect("48 bytes is a valid HKDF-SHA256 output length");
+
+        let scalar = Zeroizing::new(U384::from_be_slice(&*okm).rem(&r));
+        let bytes = Zeroizing::new(scalar.to_be_bytes());
+        let secret: &[u8; 32] = bytes[16..].try_into().expect("the scalar is below r");
+        // zero is the only scalar below r that is rejected
+        if let Ok(key) = SecretKey::from_bytes(secret) {
+            return key;
+        }
+        salt = Sha256::digest(salt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal(key: &SecretKey) -> String {
+        primitive_types::U256::from_big_endian(&*key.to_bytes()).to_string()
+    }
+
+    #[test]
+    fn test_vectors() {
+        let seed = hex::decode("c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04").unwrap();
+        let master = derive_master_sk(&seed).unwrap();
+        assert_eq!(
+            decimal(&master),
+            "6083874454709270928345386274498605044986640685124978867557563392430687146096"
+        );
+        assert_eq!(
+            decimal(&derive_child_sk(&master, 0)),
+            "20397789859736650942317412262472558107875392172444076792671091975210932703118"
+        );
+
+        let seed = hex::decode("3141592653589793238462643383279502884197169399375105820974944592")
+            .unwrap();
+        assert_eq!(
+            decimal(&derive_path(&seed, "m/3141592653").unwrap()),
+            "25457201688850691947727629385191704516744796114925897962676248250929345014287"
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(derive_master_sk(&[1; 31]).is_err());
+        for path in ["", "0/1", "m/1'", "m/-1", "m/4294967296", "m//1"] {
+            assert!(derive_path(&[1; 32], path).is_err(), "{}", path);
+        }
+        assert!(derive_path(&[1; 32], "m").is_ok());
+    }
+}
@@ -0,0 +1,97 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # EIP-2334
+//!
+//! The [derivation paths](https://eips.ethereum.org/EIPS/eip-2334) of
+//! Ethereum validator keys, derived from a mnemonic with
+//! [`super::eip2333`]. Validator `i` signs with the key at
+//! `m/12381/3600/i/0/0` and withdraws with the key at `m/12381/3600/i/0`,
+//! as the deposit CLI and the consensus clients expect.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::bips::bip39::Mnemonic;
+//! use wallet_rust::eips::eip2334::{self, ValidatorKeys};
+//!
+//! let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+//! let seed = phrase.parse::<Mnemonic>().unwrap().to_seed("");
+//! let keys = ValidatorKeys::derive(&seed, 0).unwrap();
+//! assert_eq!(eip2334::signing_path(0), "m/12381/3600/0/0/0");
+//! println!("{:?}", keys.signing.public_key());
+//! ```
+
+use super::eip2333;
+use crate::{bips::bip39::Seed, bls::SecretKey, error::Result};
+
+/// Purpose of the BLS12-381 keys, the number of the curve.
+pub const PURPOSE: u32 = 12381;
+/// Coin type of Ethereum validators.
+pub const COIN_TYPE: u32 = 3600;
+
+/// Return the path of the signing key of validator `index`.
+pub fn signing_path(index: u32) -> String {
+    format!("{}/0", withdrawal_path(index))
+}
+
+/// Return the path of the withdrawal key of validator `index`.
+pub fn withdrawal_path(index: u32) -> String {
+    format!("m/{}/{}/{}/0", PURPOSE, COIN_TYPE, index)
+}
+
+/// The keys of a validator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidatorKeys {
+    /// The key signing attestations and blocks.
+    pub signing: SecretKey,
+    /// The key of BLS withdrawal credentials.
+    pub withdrawal: SecretKey,
+}
+
+impl ValidatorKeys {
+    /// Derive the keys of validator `index` from a BIP39 seed.
+    pub fn derive(seed: &Seed, index: u32) -> Result<Self> {
+        let withdrawal = eip2333::derive_path(seed.to_bytes(), &withdrawal_path(index))?;
+        Ok(Self {
+            signing: eip2333::derive_child_sk(&withdrawal, 0),
+            withdrawal,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bips::bip39::Mnemonic;
+
+    #[test]
+    fn test_validator_keys() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let seed = phrase.parse::<Mnemonic>().unwrap().to_seed("");
+        let keys = ValidatorKeys::derive(&seed, 0).unwrap();
+
+        assert_eq!(
+            hex::encode(keys.signing.to_bytes()),
+            "3ec45abb2792f1f287ab1434acfde9d7aac879eb74c45cf7b59d25f15ba7a650"
+        );
+        assert_eq!(
+            hex::encode(keys.withdrawal.to_bytes()),
+            "6b03a839551d1ec938176c1c61e98a881e382f8a6c94638fbeed435bdc0dd76b"
+        );
+        assert_eq!(
+            eip2333::derive_path(seed.to_bytes(), &signing_path(0)).unwrap(),
+            keys.signing
+        );
+        assert_ne!(ValidatorKeys::derive(&seed, 1).unwrap(), keys);
+    }
+}
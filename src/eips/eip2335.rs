@@ -0,0 +1,432 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # EIP-2335
+//!
+//! The [BLS12-381 keystores](https://eips.ethereum.org/EIPS/eip-2335) of
+//! Ethereum validators, the version 4 files written by the deposit CLI and
+//! imported by every consensus client.
+//!
+//! The format keeps the scrypt and PBKDF2 key derivation and the
+//! AES-128-CTR cipher of the [`crate::keystore`] files, with a SHA-256
+//! checksum instead of the Keccak-256 MAC. Passwords are normalized to NFKD
+//! and stripped of their control characters before use.
+//!
+//! ## Example
+//! ```rust,no_run
+//! use wallet_rust::{bls::SecretKey, eips::eip2335::BlsKeystore, keystore::Kdf};
+//!
+//! let key = SecretKey::from_bytes(&[1; 32]).unwrap();
+//! let keystore = BlsKeystore::encrypt(&key, "password", Kdf::scrypt(), "m/12381/3600/0/0/0")
+//!     .unwrap()
+//!     .with_description("validator 0");
+//! let json = keystore.to_json();
+//!
+//! let keystore = BlsKeystore::from_json(&json).unwrap();
+//! assert_eq!(keystore.decrypt("password").unwrap(), key);
+//! ```
+
+use aes::{
+    cipher::{KeyIvInit, StreamCipher},
+    Aes128,
+};
+use rand::Rng;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use unicode_normalization::UnicodeNormalization;
+use zeroize::Zeroizing;
+
+use crate::{
+    bls::SecretKey,
+    error::Result,
+    keystore::{random_uuid, Kdf},
+};
+
+/// Error returned when reading a BLS keystore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Eip2335Error {
+    /// The JSON is not a BLS keystore, the missing or malformed field is
+    /// given.
+    InvalidFormat(&'static str),
+    /// The version of the file is not 4.
+    UnsupportedVersion(u64),
+    /// The cipher is not supported.
+    UnsupportedCipher(String),
+    /// The key derivation function or its PRF is not supported.
+    UnsupportedKdf(String),
+    /// The checksum function is not supported.
+    UnsupportedChecksum(String),
+    /// The checksum does not match, the password is most likely wrong.
+    InvalidChecksum,
+    /// The decrypted key does not belong to the public key of the file.
+    PublicKeyMismatch,
+}
+
+impl std::fmt::Display for Eip2335Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Eip2335Error::InvalidFormat(field) => write!(f, "Invalid BLS keystore: {}", field),
+            Eip2335Error::UnsupportedVersion(version) => {
+                write!(f, "Unsupported BLS keystore version: {}", version)
+            }
+            Eip2335Error::UnsupportedCipher(cipher) => {
+                write!(f, "Unsupported BLS keystore cipher: {}", cipher)
+            }
+            Eip2335Error::UnsupportedKdf(kdf) => {
+                write!(f, "Unsupported BLS keystore KDF: {}", kdf)
+            }
+            Eip2335Error::UnsupportedChecksum(checksum) => {
+                write!(f, "Unsupported BLS keystore checksum: {}", checksum)
+            }
+            Eip2335Error::InvalidChecksum => write!(f, "Invalid BLS keystore checksum"),
+            Eip2335Error::PublicKeyMismatch => write!(f, "BLS keystore public key mismatch"),
+        }
+    }
+}
+
+impl std::error::Error for Eip2335Error {}
+
+/// An encrypted BLS secret key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlsKeystore {
+    kdf: Kdf,
+    checksum: [u8; 32],
+    iv: [u8; 16],
+    ciphertext: Vec<u8>,
+    pubkey: Option<[u8; 48]>,
+    path: String,
+    uuid: String,
+    description: Option<String>,
+}
+
+impl BlsKeystore {
+    /// Encrypt a secret key derived at `path`, empty when the key was not
+    /// derived, with a random IV and UUID.
+    pub fn encrypt(key: &SecretKey, password: &str, kdf: Kdf, path: &str) -> Result<Self> {
+        let derived = kdf.derive(&normalize(password))?;
+        if derived.len() != 32 {
+            return Err(Eip2335Error::InvalidFormat("dklen").into());
+        }
+        let iv: [u8; 16] = rand::thread_rng().gen();
+
+        let mut ciphertext = key.to_bytes().to_vec();
+        ctr::Ctr128BE::<Aes128>::new(derived[..16].into(), &iv.into())
+            .apply_keystream(&mut ciphertext);
+
+        Ok(Self {
+            checksum: checksum(&derived, &ciphertext),
+            kdf,
+            iv,
+            ciphertext,
+            pubkey: Some(*key.public_key().as_bytes()),
+            path: path.to_string(),
+            uuid: random_uuid(),
+            description: None,
+        })
+    }
+
+    /// Set the description shown to the user.
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Decrypt the secret key. The checksum is verified before decrypting,
+    /// and the key against the public key of the file when there is one.
+    pub fn decrypt(&self, password: &str) -> Result<SecretKey> {
+        let derived = self.kdf.derive(&normalize(password))?;
+        if checksum(&derived, &self.ciphertext) != self.checksum {
+            return Err(Eip2335Error::InvalidChecksum.into());
+        }
+
+        let mut secret = Zeroizing::new(self.ciphertext.clone());
+        ctr::Ctr128BE::<Aes128>::new(derived[..16].into(), &self.iv.into())
+            .apply_keystream(&mut secret);
+        let secret: &[u8; 32] = secret
+            .as_slice()
+            .try_into()
+            .map_err(|_| Eip2335Error::InvalidFormat("cipher message"))?;
+
+        let key = SecretKey::from_bytes(secret)?;
+        if let Some(pubkey) = &self.pubkey {
+            if key.public_key().as_bytes() != pubkey {
+                return Err(Eip2335Error::PublicKeyMismatch.into());
+            }
+        }
+        Ok(key)
+    }
+
+    /// Serialize the keystore.
+    pub fn to_json(&self) -> String {
+        let (function, params) = self.kdf.to_json();
+        let mut root = json!({
+            "crypto": {
+                "kdf": { "function": function, "params": params, "message": "" },
+                "checksum": {
+                    "function": "sha256",
+                    "params": {},
+                    "message": hex::encode(self.checksum),
+                },
+                "cipher": {
+                    "function": "aes-128-ctr",
+                    "params": { "iv": hex::encode(self.iv) },
+                    "message": hex::encode(&self.ciphertext),
+                },
+            },
+            "pubkey": self.pubkey.map(hex::encode).unwrap_or_default(),
+            "path": self.path,
+            "uuid": self.uuid,
+            "version": 4,
+        });
+        if let Some(description) = &self.description {
+            root["description"] = description.as_str().into();
+        }
+        root.to_string()
+    }
+
+    /// Parse a version 4 keystore.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let root: Value = serde_json::from_str(json)?;
+        let version = root["version"]
+            .as_u64()
+            .ok_or(Eip2335Error::InvalidFormat("version"))?;
+        if version != 4 {
+            return Err(Eip2335Error::UnsupportedVersion(version).into());
+        }
+
+        let crypto = &root["crypto"];
+        let kdf = parse_kdf(&crypto["kdf"])?;
+
+        let checksum = &crypto["checksum"];
+        let function = string(&checksum["function"], "checksum function")?;
+        if function != "sha256" {
+            return Err(Eip2335Error::UnsupportedChecksum(function.to_string()).into());
+        }
+
+        let cipher = &crypto["cipher"];
+        let function = string(&cipher["function"], "cipher function")?;
+        if function != "aes-128-ctr" {
+            return Err(Eip2335Error::UnsupportedCipher(function.to_string()).into());
+        }
+
+        let pubkey = match string(&root["pubkey"], "pubkey")? {
+            "" => None,
+            pubkey => Some(
+                hex::decode(pubkey)?
+                    .try_into()
+                    .map_err(|_| Eip2335Error::InvalidFormat("pubkey"))?,
+            ),
+        };
+
+        Ok(Self {
+            kdf,
+            checksum: array(&checksum["message"], "checksum message")?,
+            iv: array(&cipher["params"]["iv"], "iv")?,
+            ciphertext: hex::decode(string(&cipher["message"], "cipher message")?)?,
+            pubkey,
+            path: string(&root["path"], "path")?.to_string(),
+            uuid: string(&root["uuid"], "uuid")?.to_string(),
+            description: root["description"].as_str().map(str::to_string),
+        })
+    }
+
+    /// Return the public key stored in the clear in the file.
+    pub fn pubkey(&self) -> Option<&[u8; 48]> {
+        self.pubkey.as_ref()
+    }
+
+    /// Return the derivation path of the key, empty if unknown.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Return the UUID of the keystore.
+    pub fn uuid(&self) -> &str {
+        &self.uuid
+    }
+
+    /// Return the description of the keystore.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Return the key derivation function.
+    pub fn kdf(&self) -> &Kdf {
+        &self.kdf
+    }
+}
+
+/// Normalize a password to NFKD and strip the C0, C1 and Delete control
+/// characters.
+fn normalize(password: &str) -> Zeroizing<Vec<u8>> {
+    let password: Zeroizing<String> = Zeroizing::new(
+        password
+            .nfkd()
+            .filter(|c| !matches!(c, '\u{00}'..='\u{1f}' | '\u{7f}'..='\u{9f}'))
+            .collect(),
+    );
+    Zeroizing::new(password.as_bytes().to_vec())
+}
+
+fn checksum(derived: &[u8], ciphertext: &[u8]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(&derived[16..32])
+        .chain_update(ciphertext)
+        .finalize()
+        .into()
+}
+
+fn parse_kdf(kdf: &Value) -> Result<Kdf> {
+    let params = &kdf["params"];
+    let number = |name: &'static str| {
+        params[name]
+            .as_u64()
+            .ok_or(Eip2335Error::InvalidFormat(name))
+    };
+    let salt = hex::decode(string(&params["salt"], "salt")?)?;
+    let dklen = number("dklen")?;
+    if dklen != 32 {
+        return Err(Eip2335Error::InvalidFormat("dklen").into());
+    }
+
+    match string(&kdf["function"], "kdf function")? {
+        "scrypt" => Ok(Kdf::Scrypt {
+            n: number("n")?,
+            r: u32::try_from(number("r")?).map_err(|_| Eip2335Error::InvalidFormat("r"))?,
+            p: u32::try_from(number("p")?).map_err(|_| Eip2335Error::InvalidFormat("p"))?,
+            dklen: 32,
+            salt,
+        }),
+        "pbkdf2" => {
+            let prf = string(&params["prf"], "prf")?;
+            if prf != "hmac-sha256" {
+                return Err(Eip2335Error::UnsupportedKdf(prf.to_string()).into());
+            }
+            Ok(Kdf::Pbkdf2 {
+                c: u32::try_from(number("c")?).map_err(|_| Eip2335Error::InvalidFormat("c"))?,
+                dklen: 32,
+                salt,
+            })
+        }
+        function => Err(Eip2335Error::UnsupportedKdf(function.to_string()).into()),
+    }
+}
+
+fn string<'a>(value: &'a Value, name: &'static str) -> Result<&'a str> {
+    Ok(value.as_str().ok_or(Eip2335Error::InvalidFormat(name))?)
+}
+
+fn array<const N: usize>(value: &Value, name: &'static str) -> Result<[u8; N]> {
+    Ok(hex::decode(string(value, name)?)?
+        .try_into()
+        .map_err(|_| Eip2335Error::InvalidFormat(name))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test vector of the EIP
+    const PBKDF2: &str = r#"{
+        "crypto": {
+            "kdf": {
+                "function": "pbkdf2",
+                "params": {
+                    "dklen": 32,
+                    "c": 262144,
+                    "prf": "hmac-sha256",
+                    "salt": "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+                },
+                "message": ""
+            },
+            "checksum": {
+                "function": "sha256",
+                "params": {},
+                "message": "8a9f5d9912ed7e75ea794bc5a89bca5f193721d30868ade6f73043c6ea6febf1"
+            },
+            "cipher": {
+                "function": "aes-128-ctr",
+                "params": { "iv": "264daa3f303d7259501c93d997d84fe6" },
+                "message": "cee03fde2af33149775b7223e7845e4fb2c8ae1792e5f99fe9ecf474cc8c16ad"
+            }
+        },
+        "description": "This is a test keystore that uses PBKDF2 to secure the secret.",
+        "pubkey": "9612d7a727c9d0a22e185a1c768478dfe919cada9266988cb32359c11f2b7b27f4ae4040902382ae2910c15e2b420d07",
+        "path": "m/12381/60/0/0",
+        "uuid": "64625def-3331-4eea-ab6f-782f3ed16a83",
+        "version": 4
+    }"#;
+
+    const PASSWORD: &str = "𝔱𝔢𝔰𝔱𝔭𝔞𝔰𝔰𝔴𝔬𝔯𝔡🔑";
+    const SECRET: &str = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+
+    #[test]
+    fn test_pbkdf2() {
+        let keystore = BlsKeystore::from_json(PBKDF2).unwrap();
+        assert_eq!(keystore.path(), "m/12381/60/0/0");
+        assert_eq!(
+            keystore.description(),
+            Some("This is a test keystore that uses PBKDF2 to secure the secret.")
+        );
+
+        let key = keystore.decrypt(PASSWORD).unwrap();
+        assert_eq!(hex::encode(key.to_bytes()), SECRET);
+        // the password is normalized
+        assert!(keystore.decrypt("testpassword\u{7f}🔑").is_ok());
+        let error = keystore.decrypt("testpassword").unwrap_err();
+        assert_eq!(error.to_string(), "Invalid BLS keystore checksum");
+    }
+
+    #[test]
+    fn test_encrypt() {
+        let key = SecretKey::from_bytes(&hex::decode(SECRET).unwrap().try_into().unwrap()).unwrap();
+        let kdf = Kdf::Scrypt {
+            n: 1024,
+            r: 8,
+            p: 1,
+            dklen: 32,
+            salt: vec![1; 32],
+        };
+        let keystore = BlsKeystore::encrypt(&key, PASSWORD, kdf, "m/12381/3600/0/0/0")
+            .unwrap()
+            .with_description("validator 0");
+        assert_eq!(keystore.uuid().len(), 36);
+
+        let parsed = BlsKeystore::from_json(&keystore.to_json()).unwrap();
+        assert_eq!(parsed, keystore);
+        assert_eq!(parsed.pubkey(), Some(key.public_key().as_bytes()));
+        assert_eq!(parsed.decrypt(PASSWORD).unwrap(), key);
+
+        // the public key is checked
+        let other = SecretKey::from_bytes(&[1; 32]).unwrap();
+        let json = keystore.to_json().replace(
+            &hex::encode(key.public_key().as_bytes()),
+            &hex::encode(other.public_key().as_bytes()),
+        );
+        let error = BlsKeystore::from_json(&json)
+            .unwrap()
+            .decrypt(PASSWORD)
+            .unwrap_err();
+        assert_eq!(error.to_string(), "BLS keystore public key mismatch");
+    }
+
+    #[test]
+    fn test_invalid() {
+        let v3 = PBKDF2.replace("\"version\": 4", "\"version\": 3");
+        assert!(BlsKeystore::from_json(&v3).is_err());
+        let prf = PBKDF2.replace("hmac-sha256", "hmac-sha512");
+        assert!(BlsKeystore::from_json(&prf).is_err());
+        let cipher = PBKDF2.replace("aes-128-ctr", "aes-256-gcm");
+        assert!(BlsKeystore::from_json(&cipher).is_err());
+    }
+}
@@ -0,0 +1,144 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Chain-specific addresses as defined in [EIP-3770](https://eips.ethereum.org/EIPS/eip-3770).
+//!
+//! An address is prefixed with the short name of its chain, e.g.
+//! `oeth:0x...` for Optimism, so a wallet can refuse to send funds to an
+//! address meant for another network. Short names are those of the
+//! [`chains`](crate::chains) registry.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::eips::eip3770::ChainAddress;
+//!
+//! let address: ChainAddress = "oeth:0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".parse().unwrap();
+//! assert_eq!(address.chain_id(), 10);
+//! assert_eq!(address.chain(), wallet_rust::chains::OPTIMISM);
+//!
+//! // unknown networks are rejected rather than guessed
+//! assert!("foo:0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".parse::<ChainAddress>().is_err());
+//! ```
+
+use crate::{
+    address::Address,
+    chains::Chain,
+    error::{Result, WalletError},
+};
+
+/// Error returned when parsing a chain-specific address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Eip3770Error {
+    /// The string has no `shortName:` prefix.
+    MissingShortName,
+    /// The short name is not in the registry.
+    UnknownShortName(String),
+}
+
+impl std::fmt::Display for Eip3770Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Eip3770Error::MissingShortName => write!(f, "Missing chain short name"),
+            Eip3770Error::UnknownShortName(name) => write!(f, "Unknown chain short name: {}", name),
+        }
+    }
+}
+
+impl std::error::Error for Eip3770Error {}
+
+/// An address bound to the chain it lives on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChainAddress {
+    chain: Chain,
+    address: Address,
+}
+
+impl ChainAddress {
+    /// Bind an address to a chain.
+    pub fn new(chain: Chain, address: Address) -> Self {
+        Self { chain, address }
+    }
+
+    /// Return the chain.
+    pub fn chain(&self) -> Chain {
+        self.chain
+    }
+
+    /// Return the chain id.
+    pub fn chain_id(&self) -> u64 {
+        self.chain.id
+    }
+
+    /// Return the address.
+    pub fn address(&self) -> Address {
+        self.address
+    }
+}
+
+impl std::fmt::Display for ChainAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}:{}", self.chain.short_name, self.address)
+    }
+}
+
+impl std::str::FromStr for ChainAddress {
+    type Err = WalletError;
+
+    /// Parse a `shortName:address` string. The address follows the rules of
+    /// [`Address`], so a mixed-case address must carry a valid checksum.
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, address) = s.split_once(':').ok_or(Eip3770Error::MissingShortName)?;
+        let chain = Chain::from_short_name(name)
+            .ok_or_else(|| Eip3770Error::UnknownShortName(name.to_string()))?;
+
+        Ok(Self {
+            chain,
+            address: address.parse()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chains;
+
+    #[test]
+    fn test_chain_address() {
+        let s = "arb1:0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+        let address: ChainAddress = s.parse().unwrap();
+        assert_eq!(address.chain_id(), 42161);
+        assert_eq!(address.to_string(), s);
+
+        let lower: ChainAddress = "arb1:0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"
+            .parse()
+            .unwrap();
+        assert_eq!(lower, address);
+
+        let built = ChainAddress::new(chains::ARBITRUM, address.address());
+        assert_eq!(built, address);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+            .parse::<ChainAddress>()
+            .is_err());
+        assert!("eth:0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD"
+            .parse::<ChainAddress>()
+            .is_err());
+        assert!("ETH:0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+            .parse::<ChainAddress>()
+            .is_err());
+    }
+}
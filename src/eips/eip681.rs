@@ -0,0 +1,153 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Payment requests as defined in [EIP-681](https://eips.ethereum.org/EIPS/eip-681).
+//!
+//! A request is an `ethereum:` URI naming the recipient, optionally the
+//! chain and the amount, that a wallet scanning it turns into a transaction.
+//! Token requests target the token contract and call its `transfer`
+//! function.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::{address::Address, eips::eip681::PaymentRequest};
+//!
+//! let recipient: Address = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".parse().unwrap();
+//! let request = PaymentRequest::ether(recipient)
+//!     .with_chain_id(1)
+//!     .with_value(10_000_000_000_000_000);
+//! assert_eq!(
+//!     request.to_string(),
+//!     "ethereum:0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed@1?value=10000000000000000"
+//! );
+//! ```
+
+use crate::address::Address;
+
+/// What a payment request asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Payment {
+    /// Ether sent to the target, with an optional amount in wei.
+    Ether(Option<u128>),
+    /// Tokens of the target contract sent to `recipient`, in base units.
+    Token {
+        /// The receiver of the tokens.
+        recipient: Address,
+        /// The amount in base units of the token.
+        amount: u128,
+    },
+}
+
+/// An EIP-681 payment request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PaymentRequest {
+    target: Address,
+    chain_id: Option<u64>,
+    payment: Payment,
+}
+
+impl PaymentRequest {
+    /// Request ether sent to `recipient`.
+    pub fn ether(recipient: Address) -> Self {
+        Self {
+            target: recipient,
+            chain_id: None,
+            payment: Payment::Ether(None),
+        }
+    }
+
+    /// Request `amount` base units of the ERC-20 token at `token` sent to
+    /// `recipient`.
+    pub fn token(token: Address, recipient: Address, amount: u128) -> Self {
+        Self {
+            target: token,
+            chain_id: None,
+            payment: Payment::Token { recipient, amount },
+        }
+    }
+
+    /// Set the chain the payment must be made on.
+    pub fn with_chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    /// Set the amount of ether in wei. Token requests are left unchanged.
+    pub fn with_value(mut self, wei: u128) -> Self {
+        if let Payment::Ether(value) = &mut self.payment {
+            *value = Some(wei);
+        }
+        self
+    }
+
+    /// Return the address the transaction is sent to, the recipient for
+    /// ether and the token contract for tokens.
+    pub fn target(&self) -> Address {
+        self.target
+    }
+
+    /// Return the chain id.
+    pub fn chain_id(&self) -> Option<u64> {
+        self.chain_id
+    }
+
+    /// Return what is requested.
+    pub fn payment(&self) -> Payment {
+        self.payment
+    }
+}
+
+impl std::fmt::Display for PaymentRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "ethereum:{}", self.target)?;
+        if let Some(chain_id) = self.chain_id {
+            write!(f, "@{}", chain_id)?;
+        }
+        match self.payment {
+            Payment::Ether(None) => Ok(()),
+            Payment::Ether(Some(value)) => write!(f, "?value={}", value),
+            Payment::Token { recipient, amount } => {
+                write!(f, "/transfer?address={}&uint256={}", recipient, amount)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uri() {
+        let recipient: Address = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            PaymentRequest::ether(recipient).to_string(),
+            "ethereum:0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+        );
+
+        let token: Address = "0x6B175474E89094C44Da98b954EedeAC495271d0F"
+            .parse()
+            .unwrap();
+        let request = PaymentRequest::token(token, recipient, 1_000_000)
+            .with_chain_id(10)
+            .with_value(5);
+        assert_eq!(request.target(), token);
+        assert_eq!(
+            request.to_string(),
+            "ethereum:0x6B175474E89094C44Da98b954EedeAC495271d0F@10/transfer\
+             ?address=0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed&uint256=1000000"
+        );
+    }
+}
@@ -0,0 +1,139 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Typed structured data hashing as defined in [EIP-712](https://eips.ethereum.org/EIPS/eip-712).
+//!
+//! The domain separator is computed from a [`Domain`]. The struct hash of
+//! the message is left to the application, which knows its types, so
+//! [`TypedData`] pairs the two hashes that make up the signed hash. This is
+//! also the form hardware wallets without a full EIP-712 parser sign.
+
+use crate::{address::Address, types::B256, utils::keccak256};
+
+/// The `EIP712Domain` of a typed message. Only the fields that are set are
+/// part of the domain type.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Domain {
+    /// Name of the signing domain, such as the name of the dapp.
+    pub name: Option<String>,
+    /// Current major version of the signing domain.
+    pub version: Option<String>,
+    /// Chain id the signature is valid on.
+    pub chain_id: Option<u64>,
+    /// Address of the contract verifying the signature.
+    pub verifying_contract: Option<Address>,
+    /// Salt disambiguating the domain.
+    pub salt: Option<B256>,
+}
+
+impl Domain {
+    /// Return the domain separator, the struct hash of the domain.
+    pub fn separator(&self) -> B256 {
+        let mut members = Vec::new();
+        let mut data = Vec::new();
+        if let Some(name) = &self.name {
+            members.push("string name");
+            data.push(keccak256(name));
+        }
+        if let Some(version) = &self.version {
+            members.push("string version");
+            data.push(keccak256(version));
+        }
+        if let Some(chain_id) = self.chain_id {
+            members.push("uint256 chainId");
+            let mut word = [0; 32];
+            word[24..].copy_from_slice(&chain_id.to_be_bytes());
+            data.push(word);
+        }
+        if let Some(contract) = &self.verifying_contract {
+            members.push("address verifyingContract");
+            let mut word = [0; 32];
+            word[12..].copy_from_slice(contract.as_bytes());
+            data.push(word);
+        }
+        if let Some(salt) = &self.salt {
+            members.push("bytes32 salt");
+            data.push(salt.0);
+        }
+
+        let type_hash = keccak256(format!("EIP712Domain({})", members.join(",")));
+        data.insert(0, type_hash);
+        B256::new(keccak256(data.concat()))
+    }
+}
+
+/// A typed message reduced to the hashes that are signed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypedData {
+    /// The domain separator.
+    pub domain_separator: B256,
+    /// The struct hash of the message, `None` when the primary type is the
+    /// domain itself.
+    pub struct_hash: Option<B256>,
+}
+
+impl TypedData {
+    /// Create new typed data from its domain and the struct hash of the
+    /// message.
+    pub fn new(domain: &Domain, struct_hash: B256) -> Self {
+        Self {
+            domain_separator: domain.separator(),
+            struct_hash: Some(struct_hash),
+        }
+    }
+
+    /// Return the hash to sign, `keccak256(0x1901 || domainSeparator || structHash)`.
+    pub fn signing_hash(&self) -> B256 {
+        let mut data = vec![0x19, 0x01];
+        data.extend_from_slice(self.domain_separator.as_ref());
+        if let Some(hash) = &self.struct_hash {
+            data.extend_from_slice(hash.as_ref());
+        }
+        B256::new(keccak256(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signing_hash() {
+        // the Mail example of EIP-712
+        let domain = Domain {
+            name: Some("Ether Mail".to_string()),
+            version: Some("1".to_string()),
+            chain_id: Some(1),
+            verifying_contract: Some(
+                "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+                    .parse()
+                    .unwrap(),
+            ),
+            salt: None,
+        };
+        assert_eq!(
+            domain.separator().to_string(),
+            "0xf2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f"
+        );
+
+        let struct_hash = "0xc52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            TypedData::new(&domain, struct_hash)
+                .signing_hash()
+                .to_string(),
+            "0xbe609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"
+        );
+    }
+}
@@ -0,0 +1,25 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Ethereum Improvement Proposals
+//!
+//! Implementations of the EIPs the wallet relies on.
+
+pub mod eip1271;
+pub mod eip191;
+pub mod eip2333;
+pub mod eip2334;
+pub mod eip2335;
+pub mod eip3770;
+pub mod eip681;
+pub mod eip712;
@@ -0,0 +1,276 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Ethereum Name Service
+//!
+//! Resolve human-readable [ENS](https://docs.ens.domains) names such as
+//! `vitalik.eth` to addresses through the registry and resolver contracts,
+//! and look up the primary name of an address.
+//!
+//! Names are lowercased before hashing. Full UTS-46 normalization is not
+//! performed, so names with non-ASCII characters must be normalized by the
+//! caller.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::ens::namehash;
+//!
+//! assert_eq!(
+//!     hex::encode(namehash("eth")),
+//!     "93cdeb708b7545dc668eb9280176169d1c33cfd8ed6f04690a0bcc88a93fc4ae"
+//! );
+//! ```
+
+use crate::{address::Address, error::Result, provider::Provider, types::B256, utils::keccak256};
+
+/// Address of the ENS registry, the same on mainnet and the public testnets.
+pub const ENS_REGISTRY: Address = Address::new([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x2e, 0x07, 0x4e, 0xc6, 0x9a, 0x0d, 0xfb, 0x29, 0x97, 0xba,
+    0x6c, 0x7d, 0x2e, 0x1e,
+]);
+
+/// Error returned when a name cannot be resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnsError {
+    /// The name has no resolver set in the registry.
+    NoResolver(String),
+    /// The resolver has no address record for the name.
+    NoAddress(String),
+    /// The contract returned malformed data.
+    InvalidResponse,
+}
+
+impl std::fmt::Display for EnsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            EnsError::NoResolver(name) => write!(f, "No resolver for name: {}", name),
+            EnsError::NoAddress(name) => write!(f, "No address for name: {}", name),
+            EnsError::InvalidResponse => write!(f, "Invalid response from ENS contract"),
+        }
+    }
+}
+
+impl std::error::Error for EnsError {}
+
+/// Compute the namehash of the given name as defined in
+/// [EIP-137](https://eips.ethereum.org/EIPS/eip-137).
+pub fn namehash(name: &str) -> B256 {
+    let name = name.to_lowercase();
+
+    name.rsplit('.')
+        .filter(|label| !label.is_empty())
+        .fold(B256::ZERO, |node, label| {
+            let mut data = node.to_vec();
+            data.extend_from_slice(&keccak256(label));
+            B256::new(keccak256(data))
+        })
+}
+
+/// Resolve the name to the address of its `addr` record.
+pub fn resolve<P: Provider>(provider: &P, name: &str) -> Result<Address> {
+    let node = namehash(name);
+
+    let resolver = resolver(provider, &node)?.ok_or_else(|| EnsError::NoResolver(name.into()))?;
+    let output = provider.call(&resolver, &encode_call("addr(bytes32)", &node))?;
+    let address = decode_address(&output)?;
+
+    if address == Address::default() {
+        return Err(EnsError::NoAddress(name.into()).into());
+    }
+
+    Ok(address)
+}
+
+/// Return the primary name of the address, if it has one.
+///
+/// The reverse record is set by the owner of the address, but anyone can
+/// claim any name there. The name is only returned if it resolves back to the
+/// same address.
+pub fn lookup_address<P: Provider>(provider: &P, address: &Address) -> Result<Option<String>> {
+    let node = namehash(&format!("{}.addr.reverse", hex::encode(address)));
+
+    let resolver = match resolver(provider, &node)? {
+        Some(resolver) => resolver,
+        None => return Ok(None),
+    };
+    let output = provider.call(&resolver, &encode_call("name(bytes32)", &node))?;
+    let name = decode_string(&output)?;
+
+    if name.is_empty() {
+        return Ok(None);
+    }
+
+    match resolve(provider, &name) {
+        Ok(resolved) if resolved == *address => Ok(Some(name)),
+        _ => Ok(None),
+    }
+}
+
+/// Return the resolver of the given node, if any.
+pub(crate) fn resolver<P: Provider>(provider: &P, node: &[u8; 32]) -> Result<Option<Address>> {
+    let output = provider.call(&ENS_REGISTRY, &encode_call("resolver(bytes32)", node))?;
+    let resolver = decode_address(&output)?;
+
+    Ok(Some(resolver).filter(|resolver| *resolver != Address::default()))
+}
+
+/// Encode a call to a function taking a single `bytes32` argument.
+pub(crate) fn encode_call(signature: &str, node: &[u8; 32]) -> Vec<u8> {
+    let mut data = keccak256(signature)[..4].to_vec();
+    data.extend_from_slice(node);
+    data
+}
+
+fn decode_address(output: &[u8]) -> Result<Address> {
+    if output.len() < 32 || output[..12].iter().any(|b| *b != 0) {
+        return Err(EnsError::InvalidResponse.into());
+    }
+    Address::from_slice(&output[12..32])
+}
+
+fn decode_string(output: &[u8]) -> Result<String> {
+    let slice = |offset: usize, len: usize| -> Result<&[u8]> {
+        let end = offset.checked_add(len).ok_or(EnsError::InvalidResponse)?;
+        Ok(output.get(offset..end).ok_or(EnsError::InvalidResponse)?)
+    };
+    let word = |offset: usize| -> Result<usize> {
+        let word = slice(offset, 32)?;
+        if word[..24].iter().any(|b| *b != 0) {
+            return Err(EnsError::InvalidResponse.into());
+        }
+        let word = u64::from_be_bytes(word[24..].try_into().expect("8 bytes"));
+        Ok(usize::try_from(word).map_err(|_| EnsError::InvalidResponse)?)
+    };
+
+    let offset = word(0)?;
+    let len = word(offset)?;
+    let data = slice(offset + 32, len)?;
+
+    Ok(String::from_utf8(data.to_vec())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::mock::MockProvider;
+    use std::collections::HashMap;
+
+    /// A provider answering the calls loaded, and the others with a zero
+    /// word.
+    fn provider(calls: HashMap<(Address, Vec<u8>), Vec<u8>>) -> MockProvider {
+        MockProvider {
+            calls,
+            ..Default::default()
+        }
+        .with_call(|_, _| Ok(vec![0; 32]))
+    }
+
+    fn word(address: &Address) -> Vec<u8> {
+        let mut word = vec![0u8; 12];
+        word.extend_from_slice(address.as_bytes());
+        word
+    }
+
+    #[test]
+    fn test_namehash() {
+        assert_eq!(namehash(""), B256::ZERO);
+        assert_eq!(
+            hex::encode(namehash("eth")),
+            "93cdeb708b7545dc668eb9280176169d1c33cfd8ed6f04690a0bcc88a93fc4ae"
+        );
+        assert_eq!(
+            hex::encode(namehash("foo.eth")),
+            "de9b09fd7c5f901e23a3f19fecc54828e9c848539801e86591bd9801b019f84f"
+        );
+        assert_eq!(namehash("Foo.ETH"), namehash("foo.eth"));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let resolver = Address::new([1; 20]);
+        let target = Address::new([2; 20]);
+        let node = namehash("vitalik.eth");
+
+        let mut calls = HashMap::new();
+        calls.insert(
+            (ENS_REGISTRY, encode_call("resolver(bytes32)", &node)),
+            word(&resolver),
+        );
+        calls.insert(
+            (resolver, encode_call("addr(bytes32)", &node)),
+            word(&target),
+        );
+        let provider = provider(calls);
+
+        assert_eq!(resolve(&provider, "vitalik.eth").unwrap(), target);
+        assert!(resolve(&provider, "unknown.eth").is_err());
+        assert_eq!(
+            hex::encode(&encode_call("resolver(bytes32)", &node)[..4]),
+            "0178b8bf"
+        );
+        assert_eq!(
+            hex::encode(&encode_call("addr(bytes32)", &node)[..4]),
+            "3b3b57de"
+        );
+    }
+
+    fn string(s: &str) -> Vec<u8> {
+        let mut data = vec![0u8; 31];
+        data.push(0x20);
+        data.extend_from_slice(&[0u8; 31]);
+        data.push(s.len() as u8);
+        data.extend_from_slice(s.as_bytes());
+        data.resize(64 + s.len().div_ceil(32) * 32, 0);
+        data
+    }
+
+    #[test]
+    fn test_lookup_address() {
+        let resolver = Address::new([1; 20]);
+        let owner = Address::new([2; 20]);
+        let impostor = Address::new([3; 20]);
+
+        let mut calls = HashMap::new();
+        for (address, name) in [(owner, "alice.eth"), (impostor, "alice.eth")] {
+            let node = namehash(&format!("{}.addr.reverse", hex::encode(address)));
+            calls.insert(
+                (ENS_REGISTRY, encode_call("resolver(bytes32)", &node)),
+                word(&resolver),
+            );
+            calls.insert(
+                (resolver, encode_call("name(bytes32)", &node)),
+                string(name),
+            );
+        }
+        let node = namehash("alice.eth");
+        calls.insert(
+            (ENS_REGISTRY, encode_call("resolver(bytes32)", &node)),
+            word(&resolver),
+        );
+        calls.insert(
+            (resolver, encode_call("addr(bytes32)", &node)),
+            word(&owner),
+        );
+        let provider = provider(calls);
+
+        assert_eq!(
+            lookup_address(&provider, &owner).unwrap(),
+            Some("alice.eth".to_string())
+        );
+        assert_eq!(lookup_address(&provider, &impostor).unwrap(), None);
+        assert_eq!(
+            lookup_address(&provider, &Address::new([4; 20])).unwrap(),
+            None
+        );
+    }
+}
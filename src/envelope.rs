@@ -0,0 +1,223 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Encrypted Envelopes
+//!
+//! Authenticated messages between two wallet identities, for encrypted memos
+//! or sync payloads. The sender and recipient agree on a secret with static
+//! ECDH, HKDF-SHA256 derives a key bound to both public keys and
+//! XChaCha20-Poly1305 seals the payload, so only the recipient can open the
+//! envelope and only the holder of the sender key could have produced it.
+//!
+//! Identities are [`EncryptionKey`]s, keeping messaging apart from the keys
+//! that sign transactions.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::bips::bip39::{Mnemonic, MnemonicType};
+//! use wallet_rust::bips::bip32::ExtendedKey;
+//! use wallet_rust::bips::wordlists::Language;
+//! use wallet_rust::ecies::EncryptionKey;
+//! use wallet_rust::envelope::Envelope;
+//!
+//! let identity = |_| {
+//!     let mnemonic = Mnemonic::new(MnemonicType::Words12, Language::English);
+//!     let master = ExtendedKey::new_master(&mnemonic.to_seed("")).unwrap();
+//!     EncryptionKey::derive(&master, 0, 0).unwrap()
+//! };
+//! let (alice, bob) = (identity(0), identity(1));
+//!
+//! let envelope = Envelope::seal(&alice, &bob.public_key(), b"memo").unwrap();
+//! let received = Envelope::from_bytes(&envelope.to_bytes()).unwrap();
+//! assert_eq!(received.sender(), &alice.public_key());
+//! assert_eq!(received.open(&bob).unwrap(), b"memo");
+//! ```
+
+use k256::ecdh;
+use laron_crypto::{PrivateKey, PublicKey};
+
+use crate::{
+    ecies::{self, EncryptionKey, EncryptionPublicKey},
+    error::Result,
+};
+
+const VERSION: u8 = 1;
+const PUBLIC_KEY_LEN: usize = 33;
+
+const ENVELOPE_INFO: &[u8] = b"wallet-rust envelope v1";
+
+/// Error returned when decoding an envelope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvelopeError {
+    /// The envelope is truncated.
+    InvalidLength,
+    /// The envelope was produced by an unknown version.
+    UnsupportedVersion(u8),
+}
+
+impl std::fmt::Display for EnvelopeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            EnvelopeError::InvalidLength => write!(f, "Invalid envelope length"),
+            EnvelopeError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported envelope version: {}", version)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EnvelopeError {}
+
+/// Elliptic-curve Diffie-Hellman key agreement.
+///
+/// Prefer [`EncryptionKey::diffie_hellman`] in wallet code, this exists for
+/// protocols that require the agreement to happen with a given key.
+pub trait DiffieHellman {
+    /// Compute the shared secret with the given public key, the x-coordinate
+    /// of the shared point.
+    fn diffie_hellman(&self, public_key: &PublicKey) -> Result<[u8; 32]>;
+}
+
+impl DiffieHellman for PrivateKey {
+    fn diffie_hellman(&self, public_key: &PublicKey) -> Result<[u8; 32]> {
+        let secret = k256::SecretKey::from_slice(&self.to_bytes())?;
+        let public = k256::PublicKey::from_sec1_bytes(&public_key.to_bytes())?;
+
+        let shared = ecdh::diffie_hellman(secret.to_nonzero_scalar(), public.as_affine());
+        Ok((*shared.raw_secret_bytes()).into())
+    }
+}
+
+/// An authenticated message from one identity to another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope {
+    sender: EncryptionPublicKey,
+    sealed: Vec<u8>,
+}
+
+impl Envelope {
+    /// Seal the plaintext from `sender` to `recipient`.
+    pub fn seal(
+        sender: &EncryptionKey,
+        recipient: &EncryptionPublicKey,
+        plaintext: &[u8],
+    ) -> Result<Self> {
+        let sender_public = sender.public_key();
+        let shared = sender.diffie_hellman(recipient);
+        let cipher = ecies::derive_cipher(&shared, &salt(&sender_public, recipient), ENVELOPE_INFO);
+
+        Ok(Self {
+            sender: sender_public,
+            sealed: ecies::seal(&cipher, plaintext)?,
+        })
+    }
+
+    /// Open the envelope with the recipient key.
+    pub fn open(&self, recipient: &EncryptionKey) -> Result<Vec<u8>> {
+        let shared = recipient.diffie_hellman(&self.sender);
+        let cipher = ecies::derive_cipher(
+            &shared,
+            &salt(&self.sender, &recipient.public_key()),
+            ENVELOPE_INFO,
+        );
+
+        ecies::open(&cipher, &self.sealed)
+    }
+
+    /// Return the public key of the sender.
+    pub fn sender(&self) -> &EncryptionPublicKey {
+        &self.sender
+    }
+
+    /// Encode the envelope as `version || sender || nonce || ciphertext`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![VERSION];
+        bytes.extend_from_slice(&self.sender.to_bytes());
+        bytes.extend_from_slice(&self.sealed);
+        bytes
+    }
+
+    /// Decode an envelope produced by [`Envelope::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let (version, rest) = bytes.split_first().ok_or(EnvelopeError::InvalidLength)?;
+        if *version != VERSION {
+            return Err(EnvelopeError::UnsupportedVersion(*version).into());
+        }
+        if rest.len() < PUBLIC_KEY_LEN {
+            return Err(EnvelopeError::InvalidLength.into());
+        }
+
+        let (sender, sealed) = rest.split_at(PUBLIC_KEY_LEN);
+        Ok(Self {
+            sender: EncryptionPublicKey::from_bytes(sender)?,
+            sealed: sealed.to_vec(),
+        })
+    }
+}
+
+fn salt(sender: &EncryptionPublicKey, recipient: &EncryptionPublicKey) -> Vec<u8> {
+    let mut salt = sender.to_bytes();
+    salt.extend_from_slice(&recipient.to_bytes());
+    salt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bips::{bip32::ExtendedKey, bip39::Mnemonic, wordlists::Language, DerivationPath};
+
+    fn master() -> ExtendedKey {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+        ExtendedKey::new_master(&mnemonic.to_seed("")).unwrap()
+    }
+
+    #[test]
+    fn test_diffie_hellman() {
+        let master = master();
+        let a = master
+            .derive_path(&DerivationPath::parse("m/0").unwrap())
+            .unwrap();
+        let b = master
+            .derive_path(&DerivationPath::parse("m/1").unwrap())
+            .unwrap();
+
+        assert_eq!(
+            a.private_key().diffie_hellman(b.public_key()).unwrap(),
+            b.private_key().diffie_hellman(a.public_key()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_envelope() {
+        let master = master();
+        let alice = EncryptionKey::derive(&master, 0, 0).unwrap();
+        let bob = EncryptionKey::derive(&master, 1, 0).unwrap();
+        let eve = EncryptionKey::derive(&master, 2, 0).unwrap();
+
+        let envelope = Envelope::seal(&alice, &bob.public_key(), b"sync payload").unwrap();
+        let bytes = envelope.to_bytes();
+        let received = Envelope::from_bytes(&bytes).unwrap();
+        assert_eq!(received, envelope);
+        assert_eq!(received.open(&bob).unwrap(), b"sync payload");
+        assert!(received.open(&eve).is_err());
+
+        // swapping the sender breaks authentication
+        let mut forged = bytes.clone();
+        forged[1..34].copy_from_slice(&eve.public_key().to_bytes());
+        assert!(Envelope::from_bytes(&forged).unwrap().open(&bob).is_err());
+
+        assert!(Envelope::from_bytes(&bytes[..20]).is_err());
+        assert!(Envelope::from_bytes(&[2]).is_err());
+    }
+}
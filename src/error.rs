@@ -0,0 +1,553 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Errors
+//!
+//! Every module reports failures with its own error enum. [`WalletError`]
+//! gathers them so functions of different modules can be combined with `?`
+//! while callers can still match on what went wrong.
+//!
+//! Every module returns [`Result`]. Invalid keys reported by the elliptic
+//! curve implementation become [`WalletError::Crypto`], failed file access
+//! keeps its [`std::io::ErrorKind`] in [`WalletError::Io`].
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::bips::{bip39::{Mnemonic, MnemonicError}, wordlists::Language};
+//! use wallet_rust::error::WalletError;
+//!
+//! let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon";
+//! match Mnemonic::from_phrase(phrase, Language::English) {
+//!     Err(WalletError::Mnemonic(MnemonicError::InvalidChecksum)) => {}
+//!     _ => unreachable!(),
+//! }
+//! ```
+
+use crate::{
+    abi::AbiError,
+    address::AddressError,
+    argon2::Argon2Error,
+    base58::Base58Error,
+    bech32::Bech32Error,
+    bips::{
+        bip32::ExtendedKeyError,
+        bip38::Bip38Error,
+        bip39::{MnemonicError, PlateError, SeedError},
+        bip44::Bip44Error,
+        wordlists::WordListError,
+        Error as PathError,
+    },
+    bls::BlsError,
+    chains::ChainError,
+    coins::{
+        bitcoin::BitcoinError, cosmos::CosmosError, filecoin::FilecoinError, solana::SolanaError,
+        xrp::XrpError,
+    },
+    ecies::EciesError,
+    ed25519::Ed25519Error,
+    eips::{eip1271::Eip1271Error, eip2335::Eip2335Error, eip3770::Eip3770Error},
+    ens::EnsError,
+    envelope::EnvelopeError,
+    icap::IcapError,
+    import::{aezeed::AezeedError, metamask::VaultError, presale::PresaleError},
+    keystore::KeystoreError,
+    monero::MoneroError,
+    portfolio::PortfolioError,
+    provider::ProviderError,
+    signature::SignatureError,
+    signer::{KmsError, Pkcs11Error, PolicyError, TotpError},
+    slips::slip39::Slip39Error,
+    tokens::TokenError,
+    transaction::{
+        gas::GasError, l2::L2Error, DecodeError, OfflineError, ReplaceError, RequestError,
+        TrackerError,
+    },
+    types::FixedBytesError,
+    units::UnitsError,
+    vanity::VanityError,
+    wallet::{AccountsError, WalletFileError},
+};
+
+#[cfg(feature = "cbor")]
+use crate::cbor::CborError;
+
+#[cfg(feature = "explorer")]
+use crate::explorer::ExplorerError;
+
+#[cfg(feature = "nfc")]
+use crate::nfc::NdefError;
+
+#[cfg(feature = "qr")]
+use crate::qr::QrError;
+
+#[cfg(feature = "substrate")]
+use crate::coins::substrate::SubstrateError;
+
+#[cfg(feature = "substrate")]
+use crate::sr25519::Sr25519Error;
+
+#[cfg(feature = "trezor")]
+use crate::signer::trezor::TrezorError;
+
+/// Result type of the APIs returning a [`WalletError`].
+pub type Result<T, E = WalletError> = std::result::Result<T, E>;
+
+/// Error returned by the functions of this crate.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WalletError {
+    /// An invalid address.
+    Address(AddressError),
+    /// Bytes of the wrong length for a fixed-size type.
+    FixedBytes(FixedBytesError),
+    /// An invalid mnemonic.
+    Mnemonic(MnemonicError),
+    /// Invalid parameters for deriving a seed.
+    Seed(SeedError),
+    /// Invalid inputs of Argon2id.
+    Argon2(Argon2Error),
+    /// An invalid mnemonic backup plate.
+    Plate(PlateError),
+    /// A word missing from the wordlist, or a corrupted wordlist.
+    WordList(WordListError),
+    /// A malformed derivation path.
+    Path(PathError),
+    /// A derivation path that does not follow BIP44.
+    Bip44(Bip44Error),
+    /// A failed key derivation.
+    ExtendedKey(ExtendedKeyError),
+    /// An invalid BIP38 encrypted key or passphrase.
+    Bip38(Bip38Error),
+    /// An invalid vanity address pattern.
+    Vanity(VanityError),
+    /// An invalid SLIP-39 share or backup.
+    Slip39(Slip39Error),
+    /// An invalid ed25519 public key or signature.
+    Ed25519(Ed25519Error),
+    /// An invalid BLS key.
+    Bls(BlsError),
+    /// An invalid Monero mnemonic.
+    Monero(MoneroError),
+    /// An invalid Bitcoin address.
+    Bitcoin(BitcoinError),
+    /// An invalid Cosmos address.
+    Cosmos(CosmosError),
+    /// An invalid Filecoin address.
+    Filecoin(FilecoinError),
+    /// An invalid Solana address or keypair.
+    Solana(SolanaError),
+    /// An invalid XRP address.
+    Xrp(XrpError),
+    /// An invalid signature.
+    Signature(SignatureError),
+    /// An invalid amount.
+    Units(UnitsError),
+    /// An unknown chain.
+    Chain(ChainError),
+    /// An invalid ICAP.
+    Icap(IcapError),
+    /// An invalid chain-specific address.
+    Eip3770(Eip3770Error),
+    /// A signature that cannot be checked through EIP-1271.
+    Eip1271(Eip1271Error),
+    /// An invalid BLS keystore.
+    Eip2335(Eip2335Error),
+    /// An invalid keystore or a wrong password.
+    Keystore(KeystoreError),
+    /// An invalid wallet file or a wrong password.
+    WalletFile(WalletFileError),
+    /// An invalid account of a wallet.
+    Accounts(AccountsError),
+    /// An invalid MetaMask vault.
+    Vault(VaultError),
+    /// An invalid presale wallet.
+    Presale(PresaleError),
+    /// An invalid aezeed mnemonic.
+    Aezeed(AezeedError),
+    /// A message that cannot be encrypted or decrypted.
+    Ecies(EciesError),
+    /// An invalid encrypted envelope.
+    Envelope(EnvelopeError),
+    /// Malformed ABI data.
+    Abi(AbiError),
+    /// An ENS name that cannot be resolved.
+    Ens(EnsError),
+    /// An invalid token transfer.
+    Token(TokenError),
+    /// A malformed raw transaction.
+    Decode(DecodeError),
+    /// An incomplete transaction request.
+    Request(RequestError),
+    /// An invalid offline signing request.
+    Offline(OfflineError),
+    /// A transaction that cannot be replaced.
+    Replace(ReplaceError),
+    /// A transaction that was dropped or replaced.
+    Tracker(TrackerError),
+    /// Fees that cannot be estimated.
+    Gas(GasError),
+    /// A failed layer 2 fee estimate.
+    L2(L2Error),
+    /// A failed request to the node.
+    Provider(ProviderError),
+    /// A malformed Multicall3 response.
+    Portfolio(PortfolioError),
+    /// A signature refused by the signing policy.
+    Policy(PolicyError),
+    /// An invalid one-time password.
+    Totp(TotpError),
+    /// A failed signature of a cloud KMS.
+    Kms(KmsError),
+    /// A failed signature of a PKCS#11 token.
+    Pkcs11(Pkcs11Error),
+    /// A failed request to a Trezor device.
+    #[cfg(feature = "trezor")]
+    Trezor(TrezorError),
+    /// Malformed CBOR.
+    #[cfg(feature = "cbor")]
+    Cbor(CborError),
+    /// A malformed NDEF message.
+    #[cfg(feature = "nfc")]
+    Ndef(NdefError),
+    /// A failed request to a block explorer.
+    #[cfg(feature = "explorer")]
+    Explorer(ExplorerError),
+    /// An invalid Substrate derivation path or address.
+    #[cfg(feature = "substrate")]
+    Substrate(SubstrateError),
+    /// An invalid sr25519 key or signature.
+    #[cfg(feature = "substrate")]
+    Sr25519(Sr25519Error),
+    /// A failed QR code encoding.
+    #[cfg(feature = "qr")]
+    Qr(QrError),
+    /// Malformed Base58.
+    Base58(Base58Error),
+    /// Malformed Bech32.
+    Bech32(Bech32Error),
+    /// Malformed hex.
+    Hex(hex::FromHexError),
+    /// Malformed Base64.
+    Base64(base64::DecodeError),
+    /// Bytes that are not valid UTF-8.
+    Utf8(std::string::FromUtf8Error),
+    /// Malformed JSON.
+    Json(String),
+    /// A failed read or write of a file.
+    Io(std::io::ErrorKind, String),
+    /// An invalid key reported by the elliptic curve implementation.
+    Crypto(String),
+}
+
+impl WalletError {
+    /// Wrap an error of the elliptic curve implementation.
+    pub(crate) fn crypto(error: impl std::fmt::Display) -> Self {
+        WalletError::Crypto(error.to_string())
+    }
+}
+
+impl std::fmt::Display for WalletError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WalletError::Address(e) => write!(f, "{}", e),
+            WalletError::FixedBytes(e) => write!(f, "{}", e),
+            WalletError::Mnemonic(e) => write!(f, "{}", e),
+            WalletError::Seed(e) => write!(f, "{}", e),
+            WalletError::Argon2(e) => write!(f, "{}", e),
+            WalletError::Plate(e) => write!(f, "{}", e),
+            WalletError::WordList(e) => write!(f, "{}", e),
+            WalletError::Path(e) => write!(f, "{}", e),
+            WalletError::Bip44(e) => write!(f, "{}", e),
+            WalletError::ExtendedKey(e) => write!(f, "{}", e),
+            WalletError::Bip38(e) => write!(f, "{}", e),
+            WalletError::Vanity(e) => write!(f, "{}", e),
+            WalletError::Slip39(e) => write!(f, "{}", e),
+            WalletError::Ed25519(e) => write!(f, "{}", e),
+            WalletError::Bls(e) => write!(f, "{}", e),
+            WalletError::Monero(e) => write!(f, "{}", e),
+            WalletError::Bitcoin(e) => write!(f, "{}", e),
+            WalletError::Cosmos(e) => write!(f, "{}", e),
+            WalletError::Filecoin(e) => write!(f, "{}", e),
+            WalletError::Solana(e) => write!(f, "{}", e),
+            WalletError::Xrp(e) => write!(f, "{}", e),
+            WalletError::Signature(e) => write!(f, "{}", e),
+            WalletError::Units(e) => write!(f, "{}", e),
+            WalletError::Chain(e) => write!(f, "{}", e),
+            WalletError::Icap(e) => write!(f, "{}", e),
+            WalletError::Eip3770(e) => write!(f, "{}", e),
+            WalletError::Eip1271(e) => write!(f, "{}", e),
+            WalletError::Eip2335(e) => write!(f, "{}", e),
+            WalletError::Keystore(e) => write!(f, "{}", e),
+            WalletError::WalletFile(e) => write!(f, "{}", e),
+            WalletError::Accounts(e) => write!(f, "{}", e),
+            WalletError::Vault(e) => write!(f, "{}", e),
+            WalletError::Presale(e) => write!(f, "{}", e),
+            WalletError::Aezeed(e) => write!(f, "{}", e),
+            WalletError::Ecies(e) => write!(f, "{}", e),
+            WalletError::Envelope(e) => write!(f, "{}", e),
+            WalletError::Abi(e) => write!(f, "{}", e),
+            WalletError::Ens(e) => write!(f, "{}", e),
+            WalletError::Token(e) => write!(f, "{}", e),
+            WalletError::Decode(e) => write!(f, "{}", e),
+            WalletError::Request(e) => write!(f, "{}", e),
+            WalletError::Offline(e) => write!(f, "{}", e),
+            WalletError::Replace(e) => write!(f, "{}", e),
+            WalletError::Tracker(e) => write!(f, "{}", e),
+            WalletError::Gas(e) => write!(f, "{}", e),
+            WalletError::L2(e) => write!(f, "{}", e),
+            WalletError::Provider(e) => write!(f, "{}", e),
+            WalletError::Portfolio(e) => write!(f, "{}", e),
+            WalletError::Policy(e) => write!(f, "{}", e),
+            WalletError::Totp(e) => write!(f, "{}", e),
+            WalletError::Kms(e) => write!(f, "{}", e),
+            WalletError::Pkcs11(e) => write!(f, "{}", e),
+            #[cfg(feature = "trezor")]
+            WalletError::Trezor(e) => write!(f, "{}", e),
+            #[cfg(feature = "cbor")]
+            WalletError::Cbor(e) => write!(f, "{}", e),
+            #[cfg(feature = "nfc")]
+            WalletError::Ndef(e) => write!(f, "{}", e),
+            #[cfg(feature = "explorer")]
+            WalletError::Explorer(e) => write!(f, "{}", e),
+            #[cfg(feature = "substrate")]
+            WalletError::Substrate(e) => write!(f, "{}", e),
+            #[cfg(feature = "substrate")]
+            WalletError::Sr25519(e) => write!(f, "{}", e),
+            #[cfg(feature = "qr")]
+            WalletError::Qr(e) => write!(f, "{}", e),
+            WalletError::Base58(e) => write!(f, "{}", e),
+            WalletError::Bech32(e) => write!(f, "{}", e),
+            WalletError::Hex(e) => write!(f, "{}", e),
+            WalletError::Base64(e) => write!(f, "{}", e),
+            WalletError::Utf8(e) => write!(f, "{}", e),
+            WalletError::Json(e) => write!(f, "{}", e),
+            WalletError::Io(_, e) => write!(f, "{}", e),
+            WalletError::Crypto(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for WalletError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WalletError::Address(e) => Some(e),
+            WalletError::FixedBytes(e) => Some(e),
+            WalletError::Mnemonic(e) => Some(e),
+            WalletError::Seed(e) => Some(e),
+            WalletError::Argon2(e) => Some(e),
+            WalletError::Plate(e) => Some(e),
+            WalletError::WordList(e) => Some(e),
+            WalletError::Path(e) => Some(e),
+            WalletError::Bip44(e) => Some(e),
+            WalletError::ExtendedKey(e) => Some(e),
+            WalletError::Bip38(e) => Some(e),
+            WalletError::Vanity(e) => Some(e),
+            WalletError::Slip39(e) => Some(e),
+            WalletError::Ed25519(e) => Some(e),
+            WalletError::Bls(e) => Some(e),
+            WalletError::Monero(e) => Some(e),
+            WalletError::Bitcoin(e) => Some(e),
+            WalletError::Cosmos(e) => Some(e),
+            WalletError::Filecoin(e) => Some(e),
+            WalletError::Solana(e) => Some(e),
+            WalletError::Xrp(e) => Some(e),
+            WalletError::Signature(e) => Some(e),
+            WalletError::Units(e) => Some(e),
+            WalletError::Chain(e) => Some(e),
+            WalletError::Icap(e) => Some(e),
+            WalletError::Eip3770(e) => Some(e),
+            WalletError::Eip1271(e) => Some(e),
+            WalletError::Eip2335(e) => Some(e),
+            WalletError::Keystore(e) => Some(e),
+            WalletError::WalletFile(e) => Some(e),
+            WalletError::Accounts(e) => Some(e),
+            WalletError::Vault(e) => Some(e),
+            WalletError::Presale(e) => Some(e),
+            WalletError::Aezeed(e) => Some(e),
+            WalletError::Ecies(e) => Some(e),
+            WalletError::Envelope(e) => Some(e),
+            WalletError::Abi(e) => Some(e),
+            WalletError::Ens(e) => Some(e),
+            WalletError::Token(e) => Some(e),
+            WalletError::Decode(e) => Some(e),
+            WalletError::Request(e) => Some(e),
+            WalletError::Offline(e) => Some(e),
+            WalletError::Replace(e) => Some(e),
+            WalletError::Tracker(e) => Some(e),
+            WalletError::Gas(e) => Some(e),
+            WalletError::L2(e) => Some(e),
+            WalletError::Provider(e) => Some(e),
+            WalletError::Portfolio(e) => Some(e),
+            WalletError::Policy(e) => Some(e),
+            WalletError::Totp(e) => Some(e),
+            WalletError::Kms(e) => Some(e),
+            WalletError::Pkcs11(e) => Some(e),
+            #[cfg(feature = "trezor")]
+            WalletError::Trezor(e) => Some(e),
+            #[cfg(feature = "cbor")]
+            WalletError::Cbor(e) => Some(e),
+            #[cfg(feature = "nfc")]
+            WalletError::Ndef(e) => Some(e),
+            #[cfg(feature = "explorer")]
+            WalletError::Explorer(e) => Some(e),
+            #[cfg(feature = "substrate")]
+            WalletError::Substrate(e) => Some(e),
+            #[cfg(feature = "substrate")]
+            WalletError::Sr25519(e) => Some(e),
+            #[cfg(feature = "qr")]
+            WalletError::Qr(e) => Some(e),
+            WalletError::Base58(e) => Some(e),
+            WalletError::Bech32(e) => Some(e),
+            WalletError::Hex(e) => Some(e),
+            WalletError::Base64(e) => Some(e),
+            WalletError::Utf8(e) => Some(e),
+            WalletError::Json(_) | WalletError::Io(..) => None,
+            WalletError::Crypto(_) => None,
+        }
+    }
+}
+
+macro_rules! from_error {
+    ($($variant:ident($error:ty)),* $(,)?) => {
+        $(
+            impl From<$error> for WalletError {
+                fn from(error: $error) -> Self {
+                    WalletError::$variant(error)
+                }
+            }
+        )*
+    };
+}
+
+from_error!(
+    Address(AddressError),
+    FixedBytes(FixedBytesError),
+    Mnemonic(MnemonicError),
+    Seed(SeedError),
+    Argon2(Argon2Error),
+    Plate(PlateError),
+    WordList(WordListError),
+    Path(PathError),
+    Bip44(Bip44Error),
+    ExtendedKey(ExtendedKeyError),
+    Bip38(Bip38Error),
+    Vanity(VanityError),
+    Slip39(Slip39Error),
+    Ed25519(Ed25519Error),
+    Bls(BlsError),
+    Monero(MoneroError),
+    Bitcoin(BitcoinError),
+    Cosmos(CosmosError),
+    Filecoin(FilecoinError),
+    Solana(SolanaError),
+    Xrp(XrpError),
+    Signature(SignatureError),
+    Units(UnitsError),
+    Chain(ChainError),
+    Icap(IcapError),
+    Eip3770(Eip3770Error),
+    Eip1271(Eip1271Error),
+    Eip2335(Eip2335Error),
+    Keystore(KeystoreError),
+    WalletFile(WalletFileError),
+    Accounts(AccountsError),
+    Vault(VaultError),
+    Presale(PresaleError),
+    Aezeed(AezeedError),
+    Ecies(EciesError),
+    Envelope(EnvelopeError),
+    Abi(AbiError),
+    Ens(EnsError),
+    Token(TokenError),
+    Decode(DecodeError),
+    Request(RequestError),
+    Offline(OfflineError),
+    Replace(ReplaceError),
+    Tracker(TrackerError),
+    Gas(GasError),
+    L2(L2Error),
+    Provider(ProviderError),
+    Portfolio(PortfolioError),
+    Policy(PolicyError),
+    Totp(TotpError),
+    Kms(KmsError),
+    Pkcs11(Pkcs11Error),
+    Base58(Base58Error),
+    Bech32(Bech32Error),
+    Hex(hex::FromHexError),
+    Base64(base64::DecodeError),
+    Utf8(std::string::FromUtf8Error),
+);
+
+#[cfg(feature = "qr")]
+from_error!(Qr(QrError));
+
+#[cfg(feature = "substrate")]
+from_error!(Substrate(SubstrateError), Sr25519(Sr25519Error));
+
+#[cfg(feature = "trezor")]
+from_error!(Trezor(TrezorError));
+
+#[cfg(feature = "cbor")]
+from_error!(Cbor(CborError));
+
+#[cfg(feature = "nfc")]
+from_error!(Ndef(NdefError));
+
+#[cfg(feature = "explorer")]
+from_error!(Explorer(ExplorerError));
+
+impl From<k256::ecdsa::Error> for WalletError {
+    fn from(error: k256::ecdsa::Error) -> Self {
+        WalletError::crypto(error)
+    }
+}
+
+impl From<k256::elliptic_curve::Error> for WalletError {
+    fn from(error: k256::elliptic_curve::Error) -> Self {
+        WalletError::crypto(error)
+    }
+}
+
+impl From<serde_json::Error> for WalletError {
+    fn from(error: serde_json::Error) -> Self {
+        WalletError::Json(error.to_string())
+    }
+}
+
+impl From<std::io::Error> for WalletError {
+    fn from(error: std::io::Error) -> Self {
+        WalletError::Io(error.kind(), error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{address::Address, bips::DerivationPath, units::parse_ether};
+
+    #[test]
+    fn test_match() {
+        let error = "0x1234".parse::<Address>().unwrap_err();
+        assert_eq!(error, WalletError::Address(AddressError::InvalidLength(2)));
+
+        let error = DerivationPath::parse("44'/60'").unwrap_err();
+        assert_eq!(error, WalletError::Path(PathError::MissingMaster));
+        assert!(std::error::Error::source(&error).is_some());
+
+        let error = parse_ether("1.0000000000000000001").unwrap_err();
+        assert_eq!(error, WalletError::Units(UnitsError::TooManyDecimals(18)));
+
+        let error = WalletError::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert!(matches!(error, WalletError::Io(std::io::ErrorKind::NotFound, _)));
+    }
+}
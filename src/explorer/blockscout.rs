@@ -0,0 +1,253 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The Etherscan-compatible API of Blockscout.
+
+use serde_json::Value;
+
+use super::{
+    gwei, query, query_list, token_transfer, transaction, Explorer, ExplorerError,
+    ExplorerTransaction, GasOracle, HttpClient,
+};
+use crate::{
+    address::Address,
+    error::Result,
+    history::{self, Transfer},
+};
+
+/// A client of a Blockscout instance.
+#[derive(Debug, Clone)]
+pub struct Blockscout<C> {
+    client: C,
+    url: String,
+}
+
+impl<C: HttpClient> Blockscout<C> {
+    /// Create a client of the instance at the given URL, such as
+    /// `https://eth.blockscout.com`.
+    pub fn new(client: C, url: &str) -> Self {
+        Blockscout {
+            client,
+            url: url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Return the URL of an API action with its parameters.
+    fn action(&self, module: &str, action: &str, parameters: &str) -> String {
+        format!(
+            "{}/api?module={}&action={}{}",
+            self.url, module, action, parameters
+        )
+    }
+
+    /// Return the parameters of a list of the account's records.
+    fn range(account: &Address, from_block: u64, to_block: u64) -> String {
+        format!(
+            "&address={}&startblock={}&endblock={}&sort=asc",
+            account, from_block, to_block
+        )
+    }
+}
+
+impl<C: HttpClient> Explorer for Blockscout<C> {
+    fn transactions(
+        &self,
+        account: &Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<ExplorerTransaction>> {
+        let parameters = Self::range(account, from_block, to_block);
+        query_list(&self.client, &self.action("account", "txlist", &parameters))?
+            .iter()
+            .map(transaction)
+            .collect()
+    }
+
+    /// Return the transfers of `tokentx`, which lists the ERC-721 ones
+    /// along with the ERC-20 ones.
+    fn token_transfers(
+        &self,
+        account: &Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<Transfer>> {
+        let parameters = Self::range(account, from_block, to_block);
+        let mut transfers = query_list(
+            &self.client,
+            &self.action("account", "tokentx", &parameters),
+        )?
+        .iter()
+        .map(token_transfer)
+        .collect::<Result<Vec<_>>>()?;
+        history::sort(&mut transfers);
+        Ok(transfers)
+    }
+
+    fn contract_abi(&self, address: &Address) -> Result<String> {
+        let url = self.action("contract", "getabi", &format!("&address={}", address));
+        match query(&self.client, &url)? {
+            Value::String(abi) => Ok(abi),
+            _ => Err(ExplorerError::InvalidResponse("result").into()),
+        }
+    }
+
+    /// Return the prices of the `/api/v2/stats` endpoint. Instances give
+    /// them as numbers of gwei, or as objects also holding the base fee.
+    fn gas_oracle(&self) -> Result<GasOracle> {
+        let stats: Value =
+            serde_json::from_str(&self.client.get(&format!("{}/api/v2/stats", self.url))?)
+                .map_err(|_| ExplorerError::InvalidResponse("body"))?;
+        let prices = stats
+            .get("gas_prices")
+            .ok_or(ExplorerError::InvalidResponse("gas_prices"))?;
+        let price = |name: &'static str| -> Result<u128> {
+            let price = match prices.get(name) {
+                Some(Value::Object(price)) => price.get("price"),
+                price => price,
+            };
+            match price {
+                Some(Value::Number(price)) => gwei(&price.to_string()),
+                _ => Err(ExplorerError::InvalidResponse(name).into()),
+            }
+        };
+        Ok(GasOracle {
+            safe: price("slow")?,
+            standard: price("average")?,
+            fast: price("fast")?,
+            base_fee: match prices
+                .get("average")
+                .and_then(|price| price.get("base_fee"))
+            {
+                Some(Value::Number(fee)) => Some(gwei(&fee.to_string())?),
+                _ => None,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::{HistorySource, TransferKind};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    const ACCOUNT: Address = Address::new([0xa1; 20]);
+    const OTHER: Address = Address::new([0xb0; 20]);
+    const TOKEN: Address = Address::new([0x70; 20]);
+    const URL: &str = "https://eth.blockscout.com";
+
+    /// Answers the URLs it knows.
+    #[derive(Default)]
+    struct MockClient(HashMap<String, Value>);
+
+    impl MockClient {
+        fn list(&mut self, action: &str, result: Value) {
+            let url = format!(
+                "{}/api?module=account&action={}&address={}&startblock=0&endblock=99&sort=asc&page=1&offset=1000",
+                URL, action, ACCOUNT
+            );
+            let body = json!({ "status": "1", "message": "OK", "result": result });
+            self.0.insert(url, body);
+        }
+    }
+
+    impl HttpClient for MockClient {
+        fn get(&self, url: &str) -> Result<String> {
+            match self.0.get(url) {
+                Some(body) => Ok(body.to_string()),
+                None => Err(ExplorerError::Api(format!("404 {}", url)).into()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_transfers() {
+        let mut client = MockClient::default();
+        client.list("txlist", json!([]));
+        client.list(
+            "tokentx",
+            json!([
+                {
+                    "blockNumber": "8",
+                    "hash": format!("0x{}", hex::encode([8; 32])),
+                    "transactionIndex": "0",
+                    "logIndex": "3",
+                    "from": OTHER.to_string(),
+                    "to": ACCOUNT.to_string(),
+                    "contractAddress": TOKEN.to_string(),
+                    "value": "",
+                    "tokenID": "7",
+                },
+                {
+                    "blockNumber": "8",
+                    "hash": format!("0x{}", hex::encode([8; 32])),
+                    "transactionIndex": "0",
+                    "logIndex": "1",
+                    "from": ACCOUNT.to_string(),
+                    "to": OTHER.to_string(),
+                    "contractAddress": TOKEN.to_string(),
+                    "value": "1000000",
+                    "tokenID": "",
+                },
+            ]),
+        );
+        let blockscout = Blockscout::new(client, "https://eth.blockscout.com/");
+
+        let transfers = blockscout.transfers(&ACCOUNT, 0, 99).unwrap();
+        assert_eq!(
+            transfers[0].kind,
+            TransferKind::Erc20 {
+                token: TOKEN,
+                value: 1_000_000.into()
+            }
+        );
+        assert_eq!(transfers[0].log_index, Some(1));
+        assert_eq!(
+            transfers[1].kind,
+            TransferKind::Erc721 {
+                token: TOKEN,
+                token_id: 7.into()
+            }
+        );
+    }
+
+    #[test]
+    fn test_gas_oracle() {
+        let mut client = MockClient::default();
+        let url = format!("{}/api/v2/stats", URL);
+        let stats = json!({ "gas_prices": { "slow": 0.4, "average": 1.25, "fast": 3 } });
+        client.0.insert(url.clone(), stats);
+        let blockscout = Blockscout::new(client, URL);
+        assert_eq!(
+            blockscout.gas_oracle().unwrap(),
+            GasOracle {
+                safe: 400_000_000,
+                standard: 1_250_000_000,
+                fast: 3_000_000_000,
+                base_fee: None,
+            }
+        );
+
+        let mut client = MockClient::default();
+        let stats = json!({ "gas_prices": {
+            "slow": { "price": 0.4, "base_fee": 0.35 },
+            "average": { "price": 1.25, "base_fee": 0.35 },
+            "fast": { "price": 3.0, "base_fee": 0.35 },
+        } });
+        client.0.insert(url, stats);
+        let blockscout = Blockscout::new(client, URL);
+        assert_eq!(blockscout.gas_oracle().unwrap().base_fee, Some(350_000_000));
+        assert_eq!(blockscout.gas_oracle().unwrap().fast, 3_000_000_000);
+    }
+}
@@ -0,0 +1,332 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The Etherscan API.
+
+use super::{
+    gwei, parsed, query, query_list, token_transfer, transaction, Explorer, ExplorerError,
+    ExplorerTransaction, GasOracle, HttpClient,
+};
+use crate::{
+    address::Address,
+    error::Result,
+    history::{self, Transfer},
+};
+
+/// Multichain endpoint of the Etherscan API, serving every chain Etherscan
+/// indexes with the same key.
+pub const ETHERSCAN_URL: &str = "https://api.etherscan.io/v2/api";
+
+/// A client of the Etherscan API.
+#[derive(Debug, Clone)]
+pub struct Etherscan<C> {
+    client: C,
+    url: String,
+    chain_id: u64,
+    api_key: String,
+}
+
+impl<C: HttpClient> Etherscan<C> {
+    /// Create a client of the multichain endpoint for the given chain.
+    pub fn new(client: C, chain_id: u64, api_key: &str) -> Self {
+        Etherscan {
+            client,
+            url: ETHERSCAN_URL.to_string(),
+            chain_id,
+            api_key: api_key.to_string(),
+        }
+    }
+
+    /// Send the requests to another Etherscan-compatible endpoint.
+    pub fn url(mut self, url: &str) -> Self {
+        self.url = url.to_string();
+        self
+    }
+
+    /// Return the URL of an API action with its parameters.
+    fn action(&self, module: &str, action: &str, parameters: &str) -> String {
+        format!(
+            "{}?chainid={}&module={}&action={}{}&apikey={}",
+            self.url, self.chain_id, module, action, parameters, self.api_key
+        )
+    }
+
+    /// Return the parameters of a list of the account's records.
+    fn range(account: &Address, from_block: u64, to_block: u64) -> String {
+        format!(
+            "&address={}&startblock={}&endblock={}&sort=asc",
+            account, from_block, to_block
+        )
+    }
+}
+
+impl<C: HttpClient> Explorer for Etherscan<C> {
+    fn transactions(
+        &self,
+        account: &Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<ExplorerTransaction>> {
+        let parameters = Self::range(account, from_block, to_block);
+        query_list(&self.client, &self.action("account", "txlist", &parameters))?
+            .iter()
+            .map(transaction)
+            .collect()
+    }
+
+    /// Return the transfers of `tokentx` and `tokennfttx`, which Etherscan
+    /// keeps apart.
+    fn token_transfers(
+        &self,
+        account: &Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<Transfer>> {
+        let parameters = Self::range(account, from_block, to_block);
+        let mut transfers = Vec::new();
+        for action in ["tokentx", "tokennfttx"] {
+            for record in query_list(&self.client, &self.action("account", action, &parameters))? {
+                transfers.push(token_transfer(&record)?);
+            }
+        }
+        history::sort(&mut transfers);
+        Ok(transfers)
+    }
+
+    fn contract_abi(&self, address: &Address) -> Result<String> {
+        let url = self.action("contract", "getabi", &format!("&address={}", address));
+        match query(&self.client, &url)? {
+            serde_json::Value::String(abi) => Ok(abi),
+            _ => Err(ExplorerError::InvalidResponse("result").into()),
+        }
+    }
+
+    /// Return the prices of the Etherscan gas tracker.
+    fn gas_oracle(&self) -> Result<GasOracle> {
+        let result = query(&self.client, &self.action("gastracker", "gasoracle", ""))?;
+        let price = |name| gwei(&parsed::<String>(&result, name)?);
+        Ok(GasOracle {
+            safe: price("SafeGasPrice")?,
+            standard: price("ProposeGasPrice")?,
+            fast: price("FastGasPrice")?,
+            base_fee: match result.get("suggestBaseFee") {
+                Some(_) => Some(price("suggestBaseFee")?),
+                None => None,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        history::{HistorySource, TransferKind},
+        types::B256,
+    };
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+
+    const ACCOUNT: Address = Address::new([0xa1; 20]);
+    const OTHER: Address = Address::new([0xb0; 20]);
+    const TOKEN: Address = Address::new([0x70; 20]);
+
+    /// Answers the URLs it knows, and the others as Etherscan does a
+    /// wrong key.
+    #[derive(Default)]
+    struct MockClient(HashMap<String, Value>);
+
+    impl MockClient {
+        fn respond(&mut self, url: String, result: Value) {
+            let (status, message) = match &result {
+                Value::Array(list) if list.is_empty() => ("0", "No transactions found"),
+                _ => ("1", "OK"),
+            };
+            let body = json!({ "status": status, "message": message, "result": result });
+            self.0.insert(url, body);
+        }
+
+        fn list(&mut self, action: &str, page: usize, result: Value) {
+            let url = format!(
+                "{}&page={}&offset=1000",
+                url(
+                    "account",
+                    action,
+                    &format!("&address={}&startblock=0&endblock=99&sort=asc", ACCOUNT)
+                ),
+                page
+            );
+            self.respond(url, result);
+        }
+    }
+
+    impl HttpClient for MockClient {
+        fn get(&self, url: &str) -> Result<String> {
+            let invalid = json!({ "status": "0", "message": "NOTOK", "result": "Invalid API Key" });
+            Ok(self.0.get(url).unwrap_or(&invalid).to_string())
+        }
+    }
+
+    fn url(module: &str, action: &str, parameters: &str) -> String {
+        format!(
+            "{}?chainid=1&module={}&action={}{}&apikey=KEY",
+            ETHERSCAN_URL, module, action, parameters
+        )
+    }
+
+    fn tx(block: u64, from: &Address, to: &str, value: &str, is_error: &str) -> Value {
+        json!({
+            "blockNumber": block.to_string(),
+            "hash": format!("0x{}", hex::encode([block as u8; 32])),
+            "transactionIndex": "2",
+            "from": from.to_string(),
+            "to": to,
+            "value": value,
+            "input": "0xd0e30db0",
+            "isError": is_error,
+            "gasUsed": "21000",
+            "gasPrice": "30000000000",
+        })
+    }
+
+    fn token_tx(block: u64, from: &Address, to: &Address, extra: Value) -> Value {
+        let mut record = json!({
+            "blockNumber": block.to_string(),
+            "hash": format!("0x{}", hex::encode([block as u8; 32])),
+            "transactionIndex": "5",
+            "from": from.to_string(),
+            "to": to.to_string(),
+            "contractAddress": TOKEN.to_string(),
+        });
+        record
+            .as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        record
+    }
+
+    #[test]
+    fn test_transactions() {
+        let mut client = MockClient::default();
+        let first: Vec<Value> = (0..1000)
+            .map(|block| tx(block, &OTHER, &ACCOUNT.to_string(), "1000", "0"))
+            .collect();
+        client.list("txlist", 1, Value::Array(first));
+        client.list("txlist", 2, json!([tx(99, &ACCOUNT, "", "0", "1")]));
+        let etherscan = Etherscan::new(client, 1, "KEY");
+
+        let transactions = etherscan.transactions(&ACCOUNT, 0, 99).unwrap();
+        assert_eq!(transactions.len(), 1001);
+        assert_eq!(
+            transactions[10],
+            ExplorerTransaction {
+                block_number: 10,
+                hash: B256::new([10; 32]),
+                transaction_index: 2,
+                from: OTHER,
+                to: Some(ACCOUNT),
+                value: 1000,
+                input: vec![0xd0, 0xe3, 0x0d, 0xb0],
+                success: true,
+                gas_used: 21_000,
+                gas_price: 30_000_000_000,
+            }
+        );
+        assert_eq!(transactions[1000].to, None);
+        assert!(!transactions[1000].success);
+    }
+
+    #[test]
+    fn test_transfers() {
+        let mut client = MockClient::default();
+        client.list(
+            "txlist",
+            1,
+            json!([
+                tx(10, &OTHER, &ACCOUNT.to_string(), "1000", "0"),
+                tx(11, &ACCOUNT, "", "7", "0"),
+                tx(12, &ACCOUNT, &OTHER.to_string(), "5", "1"),
+                tx(13, &ACCOUNT, &TOKEN.to_string(), "0", "0"),
+            ]),
+        );
+        client.list(
+            "tokentx",
+            1,
+            json!([token_tx(13, &ACCOUNT, &OTHER, json!({ "value": "2500" }))]),
+        );
+        client.list(
+            "tokennfttx",
+            1,
+            json!([token_tx(9, &OTHER, &ACCOUNT, json!({ "tokenID": "42" }))]),
+        );
+        let etherscan = Etherscan::new(client, 1, "KEY");
+
+        let transfers = etherscan.transfers(&ACCOUNT, 0, 99).unwrap();
+        let kinds: Vec<_> = transfers.iter().map(|transfer| &transfer.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                &TransferKind::Erc721 {
+                    token: TOKEN,
+                    token_id: 42.into()
+                },
+                &TransferKind::Native(1000),
+                &TransferKind::Erc20 {
+                    token: TOKEN,
+                    value: 2500.into()
+                },
+            ]
+        );
+        assert_eq!((transfers[2].from, transfers[2].to), (ACCOUNT, OTHER));
+        assert_eq!(transfers[2].log_index, Some(0));
+    }
+
+    #[test]
+    fn test_contract_abi() {
+        let abi = r#"[{"type":"function","name":"deposit","inputs":[]}]"#;
+        let mut client = MockClient::default();
+        let parameters = format!("&address={}", TOKEN);
+        client.respond(url("contract", "getabi", &parameters), json!(abi));
+        let etherscan = Etherscan::new(client, 1, "KEY");
+
+        assert_eq!(etherscan.contract_abi(&TOKEN).unwrap(), abi);
+        let error = etherscan.contract_abi(&OTHER).unwrap_err();
+        assert_eq!(error.to_string(), "Explorer error: Invalid API Key");
+    }
+
+    #[test]
+    fn test_gas_oracle() {
+        let mut client = MockClient::default();
+        let prices = json!({
+            "LastBlock": "21000000",
+            "SafeGasPrice": "0.5",
+            "ProposeGasPrice": "0.537165587123",
+            "FastGasPrice": "2",
+            "suggestBaseFee": "0.48",
+        });
+        client.respond(url("gastracker", "gasoracle", ""), prices);
+        let etherscan = Etherscan::new(client, 1, "KEY");
+
+        assert_eq!(
+            etherscan.gas_oracle().unwrap(),
+            GasOracle {
+                safe: 500_000_000,
+                standard: 537_165_587,
+                fast: 2_000_000_000,
+                base_fee: Some(480_000_000),
+            }
+        );
+        let other = Etherscan::new(MockClient::default(), 1, "KEY").url("https://example.com/api");
+        assert!(other.gas_oracle().is_err());
+    }
+}
@@ -0,0 +1,314 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Explorer
+//!
+//! Clients of block explorer APIs (feature `explorer`).
+//!
+//! Explorers index every account, so the history of an address or the ABI
+//! of a verified contract is one request away, where a node would have to
+//! be scanned block by block or could not tell at all. [`Etherscan`] speaks
+//! the Etherscan API, through its multichain endpoint or any compatible
+//! deployment, and [`Blockscout`] the compatible API of Blockscout
+//! instances. Both implement [`Explorer`], and through it
+//! [`HistorySource`], so they can stand in for a [`History`] reading
+//! from a node.
+//!
+//! The crate does not ship an HTTP client. Requests go through the
+//! [`HttpClient`] trait, implemented over whichever client the application
+//! already uses.
+//!
+//! [`History`]: crate::history::History
+//!
+//! ## Example
+//! ```rust,no_run
+//! # struct Client;
+//! # impl wallet_rust::explorer::HttpClient for Client {
+//! #     fn get(&self, _: &str) -> wallet_rust::error::Result<String> { unimplemented!() }
+//! # }
+//! use wallet_rust::abi::SelectorRegistry;
+//! use wallet_rust::explorer::{Etherscan, Explorer};
+//! use wallet_rust::history::HistorySource;
+//!
+//! let etherscan = Etherscan::new(Client, 1, "YourApiKeyToken");
+//! let account = "0x9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F".parse().unwrap();
+//! for transfer in etherscan.transfers(&account, 0, u64::MAX).unwrap() {
+//!     println!("{}", transfer);
+//! }
+//!
+//! let mut registry = SelectorRegistry::new();
+//! let weth = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".parse().unwrap();
+//! registry.add_abi(&etherscan.contract_abi(&weth).unwrap()).unwrap();
+//! ```
+
+mod blockscout;
+mod etherscan;
+
+pub use blockscout::*;
+pub use etherscan::*;
+
+use serde_json::Value;
+
+use crate::{
+    address::Address,
+    error::Result,
+    history::{self, HistorySource, Transfer, TransferKind},
+    types::{B256, U256},
+    units::parse_gwei,
+};
+
+/// Number of records requested per page of a list.
+const PAGE_SIZE: usize = 1_000;
+
+/// Error returned by an explorer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExplorerError {
+    /// The API refused the request, with its message.
+    Api(String),
+    /// The response is missing the given field or it is malformed.
+    InvalidResponse(&'static str),
+}
+
+impl std::fmt::Display for ExplorerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ExplorerError::Api(message) => write!(f, "Explorer error: {}", message),
+            ExplorerError::InvalidResponse(field) => {
+                write!(f, "Invalid explorer response: {}", field)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExplorerError {}
+
+/// HTTP access for the explorer clients.
+pub trait HttpClient {
+    /// Send a GET request, returning the body of the response.
+    fn get(&self, url: &str) -> Result<String>;
+}
+
+/// A transaction sent or received by an account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExplorerTransaction {
+    /// Number of the block including the transaction.
+    pub block_number: u64,
+    /// Hash of the transaction.
+    pub hash: B256,
+    /// Position of the transaction in the block.
+    pub transaction_index: u64,
+    /// Sender of the transaction.
+    pub from: Address,
+    /// Recipient, `None` for a contract creation.
+    pub to: Option<Address>,
+    /// Value in wei.
+    pub value: u128,
+    /// Call data.
+    pub input: Vec<u8>,
+    /// Whether the transaction succeeded, `false` when it reverted.
+    pub success: bool,
+    /// Gas used by the transaction.
+    pub gas_used: u64,
+    /// Price paid per gas in wei.
+    pub gas_price: u128,
+}
+
+/// Gas prices suggested by an explorer, in wei.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasOracle {
+    /// A price likely to be included within a few minutes.
+    pub safe: u128,
+    /// A price likely to be included within a few blocks.
+    pub standard: u128,
+    /// A price likely to be included in the next block.
+    pub fast: u128,
+    /// The base fee of the next block, when the explorer gives it.
+    pub base_fee: Option<u128>,
+}
+
+/// A block explorer.
+pub trait Explorer {
+    /// Return the transactions sent or received by the account between two
+    /// blocks, both included, in the order of the chain (`txlist`).
+    fn transactions(
+        &self,
+        account: &Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<ExplorerTransaction>>;
+
+    /// Return the ERC-20 and ERC-721 transfers from or to the account
+    /// between two blocks, both included (`tokentx`).
+    fn token_transfers(
+        &self,
+        account: &Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<Transfer>>;
+
+    /// Return the JSON ABI of a verified contract (`getabi`).
+    fn contract_abi(&self, address: &Address) -> Result<String>;
+
+    /// Return the suggested gas prices.
+    fn gas_oracle(&self) -> Result<GasOracle>;
+}
+
+impl<E: Explorer> HistorySource for E {
+    /// Return the native transfers of the successful transactions of the
+    /// account, then its token transfers.
+    fn transfers(
+        &self,
+        account: &Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<Transfer>> {
+        let mut transfers: Vec<Transfer> = self
+            .transactions(account, from_block, to_block)?
+            .into_iter()
+            .filter(|tx| tx.success && tx.value > 0)
+            .filter_map(|tx| {
+                Some(Transfer {
+                    block_number: tx.block_number,
+                    transaction_hash: tx.hash,
+                    transaction_index: tx.transaction_index,
+                    log_index: None,
+                    from: tx.from,
+                    to: tx.to?,
+                    kind: TransferKind::Native(tx.value),
+                })
+            })
+            .collect();
+        transfers.extend(self.token_transfers(account, from_block, to_block)?);
+        history::sort(&mut transfers);
+        Ok(transfers)
+    }
+}
+
+/// Run a query of an Etherscan-compatible API, returning its `result`.
+/// Empty lists come with a failed status and are not an error.
+fn query<C: HttpClient>(client: &C, url: &str) -> Result<Value> {
+    let body: Value = serde_json::from_str(&client.get(url)?)
+        .map_err(|_| ExplorerError::InvalidResponse("body"))?;
+    let result = body
+        .get("result")
+        .ok_or(ExplorerError::InvalidResponse("result"))?;
+    match (body.get("status").and_then(Value::as_str), result) {
+        (Some("1"), _) => Ok(result.clone()),
+        (_, Value::Array(list)) if list.is_empty() => Ok(result.clone()),
+        (_, Value::String(message)) => Err(ExplorerError::Api(message.clone()).into()),
+        _ => {
+            let message = body
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown error");
+            Err(ExplorerError::Api(message.to_string()).into())
+        }
+    }
+}
+
+/// Run a list query page by page, returning all the records.
+fn query_list<C: HttpClient>(client: &C, url: &str) -> Result<Vec<Value>> {
+    let mut records = Vec::new();
+    for page in 1.. {
+        let result = query(
+            client,
+            &format!("{}&page={}&offset={}", url, page, PAGE_SIZE),
+        )?;
+        let Value::Array(list) = result else {
+            return Err(ExplorerError::InvalidResponse("result").into());
+        };
+        let full = list.len() == PAGE_SIZE;
+        records.extend(list);
+        if !full {
+            break;
+        }
+    }
+    Ok(records)
+}
+
+/// Return a string field of a record.
+fn field<'a>(record: &'a Value, name: &'static str) -> Result<&'a str> {
+    Ok(record
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or(ExplorerError::InvalidResponse(name))?)
+}
+
+/// Return a field of a record parsed, such as a decimal number, an
+/// address or a hash.
+fn parsed<T: std::str::FromStr>(record: &Value, name: &'static str) -> Result<T> {
+    Ok(field(record, name)?
+        .parse()
+        .map_err(|_| ExplorerError::InvalidResponse(name))?)
+}
+
+/// Read a `txlist` record.
+fn transaction(record: &Value) -> Result<ExplorerTransaction> {
+    let input = field(record, "input")?;
+    Ok(ExplorerTransaction {
+        block_number: parsed(record, "blockNumber")?,
+        hash: parsed(record, "hash")?,
+        transaction_index: parsed(record, "transactionIndex")?,
+        from: parsed(record, "from")?,
+        to: match field(record, "to")? {
+            "" => None,
+            _ => Some(parsed(record, "to")?),
+        },
+        value: parsed(record, "value")?,
+        input: hex::decode(input.strip_prefix("0x").unwrap_or(input))
+            .map_err(|_| ExplorerError::InvalidResponse("input"))?,
+        success: field(record, "isError")? == "0",
+        gas_used: parsed(record, "gasUsed")?,
+        gas_price: parsed(record, "gasPrice")?,
+    })
+}
+
+/// Read a `tokentx` or `tokennfttx` record. ERC-721 transfers carry a
+/// token id, ERC-20 ones a value. Explorers that do not give the position
+/// of the log have it numbered 0.
+fn token_transfer(record: &Value) -> Result<Transfer> {
+    let token = parsed(record, "contractAddress")?;
+    let kind = match record.get("tokenID").and_then(Value::as_str) {
+        Some(id) if !id.is_empty() => TransferKind::Erc721 {
+            token,
+            token_id: U256::from_dec_str(id)
+                .map_err(|_| ExplorerError::InvalidResponse("tokenID"))?,
+        },
+        _ => TransferKind::Erc20 {
+            token,
+            value: U256::from_dec_str(field(record, "value")?)
+                .map_err(|_| ExplorerError::InvalidResponse("value"))?,
+        },
+    };
+    Ok(Transfer {
+        block_number: parsed(record, "blockNumber")?,
+        transaction_hash: parsed(record, "hash")?,
+        transaction_index: parsed(record, "transactionIndex")?,
+        log_index: match record.get("logIndex") {
+            Some(_) => Some(parsed(record, "logIndex")?),
+            None => Some(0),
+        },
+        from: parsed(record, "from")?,
+        to: parsed(record, "to")?,
+        kind,
+    })
+}
+
+/// Parse a price in gwei, dropping the digits beyond a wei.
+fn gwei(price: &str) -> Result<u128> {
+    let price = match price.split_once('.') {
+        Some((whole, fraction)) if fraction.len() > 9 => &price[..whole.len() + 10],
+        _ => price,
+    };
+    parse_gwei(price)
+}
@@ -0,0 +1,505 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # C API
+//!
+//! A stable `extern "C"` interface for embedding the wallet in C and C++
+//! backends, enabled with the `ffi` feature. The declarations are in
+//! `include/wallet.h`.
+//!
+//! Mnemonics and keys are opaque handles owned by the caller and released
+//! with their `*_free` function. Functions return a [`WalletStatus`] and
+//! write their result through an out pointer; byte outputs are
+//! [`WalletBuffer`]s released with [`wallet_buffer_free`], and strings are
+//! released with [`wallet_string_free`]. Both are zeroed before being freed.
+//! The message of the last error of the calling thread is returned by
+//! [`wallet_last_error`].
+//!
+//! Mnemonics use the English wordlist.
+//!
+//! ## Example
+//! ```c
+//! WalletMnemonic *mnemonic;
+//! WalletKey *master, *key;
+//! char *address;
+//!
+//! wallet_mnemonic_from_phrase("abandon abandon ... about", &mnemonic);
+//! wallet_key_from_mnemonic(mnemonic, "", &master);
+//! if (wallet_key_derive(master, "m/44'/60'/0'/0/0", &key) != WALLET_OK) {
+//!     fprintf(stderr, "%s\n", wallet_last_error());
+//! }
+//! wallet_key_address(key, &address);
+//!
+//! wallet_string_free(address);
+//! wallet_key_free(key);
+//! wallet_key_free(master);
+//! wallet_mnemonic_free(mnemonic);
+//! ```
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr,
+};
+
+use zeroize::Zeroize;
+
+use crate::{
+    bips::{
+        bip32::ExtendedKey,
+        bip39::{Mnemonic, MnemonicType, Seed},
+        wordlists::Language,
+        DerivationPath,
+    },
+    error::Result,
+    signer::Signer,
+};
+
+/// Status returned by every function of the C API.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalletStatus {
+    /// The call succeeded.
+    Ok = 0,
+    /// A pointer was null or a string was not valid UTF-8.
+    InvalidArgument = 1,
+    /// The call failed, see [`wallet_last_error`].
+    Error = 2,
+    /// The call panicked.
+    Panic = 3,
+}
+
+/// Opaque handle of a mnemonic.
+pub struct WalletMnemonic(Mnemonic);
+
+/// Opaque handle of an extended private key.
+pub struct WalletKey(ExtendedKey);
+
+/// A byte buffer allocated by the library.
+#[repr(C)]
+#[derive(Debug)]
+pub struct WalletBuffer {
+    /// Pointer to the bytes, null for an empty buffer.
+    pub data: *mut u8,
+    /// Number of bytes.
+    pub len: usize,
+}
+
+impl WalletBuffer {
+    fn new(bytes: Vec<u8>) -> Self {
+        let len = bytes.len();
+        let data = Box::into_raw(bytes.into_boxed_slice()) as *mut u8;
+        Self { data, len }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Run `f`, turning its error or panic into a status.
+fn run(f: impl FnOnce() -> Result<Option<WalletStatus>>) -> WalletStatus {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(None)) => WalletStatus::Ok,
+        Ok(Ok(Some(status))) => status,
+        Ok(Err(e)) => {
+            set_last_error(e.to_string());
+            WalletStatus::Error
+        }
+        Err(_) => {
+            set_last_error("Panic in wallet library".to_string());
+            WalletStatus::Panic
+        }
+    }
+}
+
+/// Read a C string, `None` if it is null or not UTF-8.
+unsafe fn str_arg<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    CStr::from_ptr(s).to_str().ok()
+}
+
+/// Move `value` into the out pointer as a new handle.
+unsafe fn write_handle<T>(out: *mut *mut T, value: T) {
+    *out = Box::into_raw(Box::new(value));
+}
+
+fn invalid_argument() -> Result<Option<WalletStatus>> {
+    set_last_error("Invalid argument".to_string());
+    Ok(Some(WalletStatus::InvalidArgument))
+}
+
+/// Return the message of the last error of the calling thread, or null. The
+/// string is owned by the library and valid until the next failing call on
+/// the same thread.
+#[no_mangle]
+pub extern "C" fn wallet_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Generate a random mnemonic of 12, 15, 18, 21 or 24 words.
+///
+/// # Safety
+/// `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn wallet_mnemonic_generate(
+    word_count: u32,
+    out: *mut *mut WalletMnemonic,
+) -> WalletStatus {
+    run(|| {
+        if out.is_null() {
+            return invalid_argument();
+        }
+        let ty = MnemonicType::from_word_count(word_count as usize)?;
+        write_handle(out, WalletMnemonic(Mnemonic::new(ty, Language::English)));
+        Ok(None)
+    })
+}
+
+/// Parse and validate a mnemonic phrase.
+///
+/// # Safety
+/// `phrase` must be a NUL-terminated string and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn wallet_mnemonic_from_phrase(
+    phrase: *const c_char,
+    out: *mut *mut WalletMnemonic,
+) -> WalletStatus {
+    run(|| {
+        let Some(phrase) = str_arg(phrase).filter(|_| !out.is_null()) else {
+            return invalid_argument();
+        };
+        let mnemonic = Mnemonic::from_phrase(phrase, Language::English)?;
+        write_handle(out, WalletMnemonic(mnemonic));
+        Ok(None)
+    })
+}
+
+/// Write the phrase of a mnemonic as a new string.
+///
+/// # Safety
+/// `mnemonic` must be a live handle and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn wallet_mnemonic_phrase(
+    mnemonic: *const WalletMnemonic,
+    out: *mut *mut c_char,
+) -> WalletStatus {
+    run(|| {
+        let Some(mnemonic) = mnemonic.as_ref().filter(|_| !out.is_null()) else {
+            return invalid_argument();
+        };
+        *out = CString::new(mnemonic.0.phrase())
+            .expect("phrase without NUL")
+            .into_raw();
+        Ok(None)
+    })
+}
+
+/// Release a mnemonic. Null is ignored.
+///
+/// # Safety
+/// `mnemonic` must be null or a live handle, which is invalid afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn wallet_mnemonic_free(mnemonic: *mut WalletMnemonic) {
+    if !mnemonic.is_null() {
+        drop(Box::from_raw(mnemonic));
+    }
+}
+
+/// Create the master key of a mnemonic and passphrase.
+///
+/// # Safety
+/// `mnemonic` must be a live handle, `passphrase` a NUL-terminated string
+/// and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn wallet_key_from_mnemonic(
+    mnemonic: *const WalletMnemonic,
+    passphrase: *const c_char,
+    out: *mut *mut WalletKey,
+) -> WalletStatus {
+    run(|| {
+        let (Some(mnemonic), Some(passphrase)) = (mnemonic.as_ref(), str_arg(passphrase)) else {
+            return invalid_argument();
+        };
+        if out.is_null() {
+            return invalid_argument();
+        }
+        let key = ExtendedKey::new_master(&mnemonic.0.to_seed(passphrase))?;
+        write_handle(out, WalletKey(key));
+        Ok(None)
+    })
+}
+
+/// Create the master key of a BIP39 seed.
+///
+/// # Safety
+/// `seed` must be valid for reads of `len` bytes and `out` valid for
+/// writes.
+#[no_mangle]
+pub unsafe extern "C" fn wallet_key_from_seed(
+    seed: *const u8,
+    len: usize,
+    out: *mut *mut WalletKey,
+) -> WalletStatus {
+    run(|| {
+        if seed.is_null() || out.is_null() {
+            return invalid_argument();
+        }
+        let seed = Seed::from(std::slice::from_raw_parts(seed, len).to_vec());
+        write_handle(out, WalletKey(ExtendedKey::new_master(&seed)?));
+        Ok(None)
+    })
+}
+
+/// Derive the key at a path such as `m/44'/60'/0'/0/0` as a new handle.
+///
+/// # Safety
+/// `key` must be a live handle, `path` a NUL-terminated string and `out`
+/// valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn wallet_key_derive(
+    key: *const WalletKey,
+    path: *const c_char,
+    out: *mut *mut WalletKey,
+) -> WalletStatus {
+    run(|| {
+        let (Some(key), Some(path)) = (key.as_ref(), str_arg(path)) else {
+            return invalid_argument();
+        };
+        if out.is_null() {
+            return invalid_argument();
+        }
+        let path = DerivationPath::parse(path)?;
+        write_handle(out, WalletKey(key.0.derive_path(&path)?));
+        Ok(None)
+    })
+}
+
+/// Write the checksummed address of a key as a new string.
+///
+/// # Safety
+/// `key` must be a live handle and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn wallet_key_address(
+    key: *const WalletKey,
+    out: *mut *mut c_char,
+) -> WalletStatus {
+    run(|| {
+        let Some(key) = key.as_ref().filter(|_| !out.is_null()) else {
+            return invalid_argument();
+        };
+        *out = CString::new(key.0.address()?.to_checksum())
+            .expect("address without NUL")
+            .into_raw();
+        Ok(None)
+    })
+}
+
+/// Write the 33-byte compressed public key of a key.
+///
+/// # Safety
+/// `key` must be a live handle and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn wallet_key_public_key(
+    key: *const WalletKey,
+    out: *mut WalletBuffer,
+) -> WalletStatus {
+    run(|| {
+        let Some(key) = key.as_ref().filter(|_| !out.is_null()) else {
+            return invalid_argument();
+        };
+        *out = WalletBuffer::new(key.0.public_key().to_bytes().to_vec());
+        Ok(None)
+    })
+}
+
+/// Sign a 32-byte hash, writing the 65-byte `r || s || v` signature.
+///
+/// # Safety
+/// `key` must be a live handle, `hash` valid for reads of 32 bytes and
+/// `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn wallet_key_sign_hash(
+    key: *const WalletKey,
+    hash: *const u8,
+    out: *mut WalletBuffer,
+) -> WalletStatus {
+    run(|| {
+        let Some(key) = key.as_ref().filter(|_| !hash.is_null() && !out.is_null()) else {
+            return invalid_argument();
+        };
+        let signature = key.0.sign_hash(&*(hash as *const [u8; 32]))?;
+        *out = WalletBuffer::new(signature.to_bytes().to_vec());
+        Ok(None)
+    })
+}
+
+/// Sign a `personal_sign` message, writing the 65-byte signature.
+///
+/// # Safety
+/// `key` must be a live handle, `message` valid for reads of `len` bytes
+/// and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn wallet_key_sign_message(
+    key: *const WalletKey,
+    message: *const u8,
+    len: usize,
+    out: *mut WalletBuffer,
+) -> WalletStatus {
+    run(|| {
+        let Some(key) = key
+            .as_ref()
+            .filter(|_| !message.is_null() && !out.is_null())
+        else {
+            return invalid_argument();
+        };
+        let signature = key
+            .0
+            .sign_message(std::slice::from_raw_parts(message, len))?;
+        *out = WalletBuffer::new(signature.to_bytes().to_vec());
+        Ok(None)
+    })
+}
+
+/// Release a key. Null is ignored.
+///
+/// # Safety
+/// `key` must be null or a live handle, which is invalid afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn wallet_key_free(key: *mut WalletKey) {
+    if !key.is_null() {
+        drop(Box::from_raw(key));
+    }
+}
+
+/// Zero and release a buffer written by the library. An empty buffer is
+/// ignored.
+///
+/// # Safety
+/// `buffer` must have been written by the library and not freed before.
+#[no_mangle]
+pub unsafe extern "C" fn wallet_buffer_free(buffer: WalletBuffer) {
+    if !buffer.data.is_null() {
+        let mut bytes = Box::from_raw(ptr::slice_from_raw_parts_mut(buffer.data, buffer.len));
+        bytes.zeroize();
+    }
+}
+
+/// Zero and release a string written by the library. Null is ignored.
+///
+/// # Safety
+/// `s` must be null or a string written by the library and not freed
+/// before.
+#[no_mangle]
+pub unsafe extern "C" fn wallet_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        let mut bytes = CString::from_raw(s).into_bytes();
+        bytes.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign() {
+        let phrase = c"abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        unsafe {
+            let mut mnemonic = ptr::null_mut();
+            assert_eq!(
+                wallet_mnemonic_from_phrase(phrase.as_ptr(), &mut mnemonic),
+                WalletStatus::Ok
+            );
+            let mut master = ptr::null_mut();
+            assert_eq!(
+                wallet_key_from_mnemonic(mnemonic, c"".as_ptr(), &mut master),
+                WalletStatus::Ok
+            );
+            let mut key = ptr::null_mut();
+            assert_eq!(
+                wallet_key_derive(master, c"m/44'/60'/0'/0/0".as_ptr(), &mut key),
+                WalletStatus::Ok
+            );
+
+            let mut address = ptr::null_mut();
+            assert_eq!(wallet_key_address(key, &mut address), WalletStatus::Ok);
+            assert_eq!(
+                CStr::from_ptr(address).to_str().unwrap(),
+                "0x9858EfFD232B4033E47d90003D41EC34EcaEda94"
+            );
+
+            let mut signature = WalletBuffer {
+                data: ptr::null_mut(),
+                len: 0,
+            };
+            let message = b"hello";
+            assert_eq!(
+                wallet_key_sign_message(key, message.as_ptr(), message.len(), &mut signature),
+                WalletStatus::Ok
+            );
+            assert_eq!(signature.len, 65);
+
+            wallet_buffer_free(signature);
+            wallet_string_free(address);
+            wallet_key_free(key);
+            wallet_key_free(master);
+            wallet_mnemonic_free(mnemonic);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        unsafe {
+            let mut mnemonic = ptr::null_mut();
+            assert_eq!(
+                wallet_mnemonic_from_phrase(ptr::null(), &mut mnemonic),
+                WalletStatus::InvalidArgument
+            );
+            assert_eq!(
+                wallet_mnemonic_generate(13, &mut mnemonic),
+                WalletStatus::Error
+            );
+            assert!(!wallet_last_error().is_null());
+            assert!(mnemonic.is_null());
+
+            assert_eq!(
+                wallet_mnemonic_generate(24, &mut mnemonic),
+                WalletStatus::Ok
+            );
+            let mut phrase = ptr::null_mut();
+            assert_eq!(
+                wallet_mnemonic_phrase(mnemonic, &mut phrase),
+                WalletStatus::Ok
+            );
+            assert_eq!(
+                CStr::from_ptr(phrase).to_str().unwrap().split(' ').count(),
+                24
+            );
+            wallet_string_free(phrase);
+            wallet_mnemonic_free(mnemonic);
+        }
+    }
+}
@@ -0,0 +1,489 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # History
+//!
+//! The transfers in and out of an account, for activity feeds.
+//!
+//! [`History`] reads them from a plain node: ERC-20 and ERC-721 transfers
+//! through the `Transfer` logs naming the account, fetched in block ranges
+//! small enough for public nodes, and transfers of the native currency by
+//! scanning every block for transactions from or to the account. Block
+//! scanning costs a request per block, so it can be turned off and left to
+//! a [`HistorySource`] backed by an indexer or a block explorer. Native
+//! currency moved by contract calls, such as withdrawals from a contract
+//! wallet, leaves no log and is not seen.
+//!
+//! ## Example
+//! ```rust,no_run
+//! # fn run<P: wallet_rust::provider::Provider>(provider: P) -> wallet_rust::error::Result<()> {
+//! use wallet_rust::history::{History, HistorySource};
+//!
+//! let account = "0x9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F".parse()?;
+//! let history = History::new(&provider).native(false);
+//! for transfer in history.transfers(&account, 19_000_000, 19_100_000)? {
+//!     println!("{}", transfer);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    address::Address,
+    error::Result,
+    provider::{Log, LogFilter, Provider},
+    types::{B256, U256},
+    utils::keccak256,
+};
+
+/// Number of blocks searched by a single `eth_getLogs`, the limit of most
+/// public nodes.
+pub const DEFAULT_BLOCK_RANGE: u64 = 2_000;
+
+/// What a transfer moved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransferKind {
+    /// The native currency, in wei.
+    Native(u128),
+    /// An amount of an ERC-20 token, in its base units.
+    Erc20 {
+        /// The token contract.
+        token: Address,
+        /// The amount.
+        value: U256,
+    },
+    /// An ERC-721 token.
+    Erc721 {
+        /// The token contract.
+        token: Address,
+        /// The id of the token.
+        token_id: U256,
+    },
+}
+
+/// Which way a transfer went for an account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Received by the account.
+    Incoming,
+    /// Sent by the account.
+    Outgoing,
+    /// Sent by the account to itself.
+    ToSelf,
+}
+
+/// A transfer of value between two addresses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transfer {
+    /// Number of the block including the transaction.
+    pub block_number: u64,
+    /// Hash of the transaction.
+    pub transaction_hash: B256,
+    /// Position of the transaction in the block.
+    pub transaction_index: u64,
+    /// Position of the log in the block, `None` for the native currency.
+    pub log_index: Option<u64>,
+    /// The sender.
+    pub from: Address,
+    /// The recipient.
+    pub to: Address,
+    /// What was moved.
+    pub kind: TransferKind,
+}
+
+impl Transfer {
+    /// Read an ERC-20 or ERC-721 transfer from its `Transfer` log. Both
+    /// standards share the event and differ by the token id being indexed.
+    pub fn from_log(log: &Log) -> Option<Self> {
+        if log.topics.first() != Some(&transfer_topic()) {
+            return None;
+        }
+        let kind = match (&log.topics[1..], log.data.len()) {
+            ([_, _], 32) => TransferKind::Erc20 {
+                token: log.address,
+                value: U256::from_big_endian(&log.data),
+            },
+            ([_, _, token_id], 0) => TransferKind::Erc721 {
+                token: log.address,
+                token_id: U256::from_big_endian(&token_id.0),
+            },
+            _ => return None,
+        };
+        Some(Transfer {
+            block_number: log.block_number,
+            transaction_hash: log.transaction_hash,
+            transaction_index: log.transaction_index,
+            log_index: Some(log.log_index),
+            from: topic_address(&log.topics[1])?,
+            to: topic_address(&log.topics[2])?,
+            kind,
+        })
+    }
+
+    /// Return which way the transfer went for the account, `None` when the
+    /// account is neither the sender nor the recipient.
+    pub fn direction(&self, account: &Address) -> Option<Direction> {
+        match (self.from == *account, self.to == *account) {
+            (true, true) => Some(Direction::ToSelf),
+            (true, false) => Some(Direction::Outgoing),
+            (false, true) => Some(Direction::Incoming),
+            (false, false) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Transfer {
+    /// Print the block, the addresses and what was moved.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} {} -> {} ", self.block_number, self.from, self.to)?;
+        match &self.kind {
+            TransferKind::Native(value) => write!(f, "{} wei", value),
+            TransferKind::Erc20 { token, value } => write!(f, "{} of {}", value, token),
+            TransferKind::Erc721 { token, token_id } => write!(f, "#{} of {}", token_id, token),
+        }
+    }
+}
+
+/// Where the transfers of an account are read from.
+pub trait HistorySource {
+    /// Return the transfers from or to the account between two blocks,
+    /// both included, in the order of the chain.
+    fn transfers(&self, account: &Address, from_block: u64, to_block: u64)
+        -> Result<Vec<Transfer>>;
+}
+
+/// Reads the transfers of an account from a node.
+#[derive(Debug, Clone)]
+pub struct History<'a, P> {
+    provider: &'a P,
+    native: bool,
+    block_range: u64,
+}
+
+impl<'a, P: Provider> History<'a, P> {
+    /// Read from the given provider, scanning blocks for native transfers.
+    pub fn new(provider: &'a P) -> Self {
+        History {
+            provider,
+            native: true,
+            block_range: DEFAULT_BLOCK_RANGE,
+        }
+    }
+
+    /// Set whether blocks are scanned for transfers of the native currency.
+    pub fn native(mut self, native: bool) -> Self {
+        self.native = native;
+        self
+    }
+
+    /// Set the number of blocks searched by a single `eth_getLogs`.
+    pub fn block_range(mut self, blocks: u64) -> Self {
+        self.block_range = blocks.max(1);
+        self
+    }
+
+    /// Return the token transfers in the logs of the given blocks.
+    fn token_transfers(
+        &self,
+        account: &Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<Transfer>> {
+        let topic = Some(address_topic(account));
+        let mut transfers = Vec::new();
+        let mut start = from_block;
+        while start <= to_block {
+            let end = to_block.min(start.saturating_add(self.block_range - 1));
+            // sent, then received
+            for topics in [
+                vec![Some(transfer_topic()), topic],
+                vec![Some(transfer_topic()), None, topic],
+            ] {
+                let filter = LogFilter {
+                    from_block: start,
+                    to_block: end,
+                    address: None,
+                    topics,
+                };
+                let logs = self.provider.logs(&filter)?;
+                transfers.extend(logs.iter().filter_map(Transfer::from_log));
+            }
+            match end.checked_add(1) {
+                Some(next) => start = next,
+                None => break,
+            }
+        }
+        Ok(transfers)
+    }
+
+    /// Return the transfers of the native currency in the given blocks.
+    fn native_transfers(
+        &self,
+        account: &Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<Transfer>> {
+        let mut transfers = Vec::new();
+        for number in from_block..=to_block {
+            let transactions = self.provider.block_transactions(number)?;
+            for (index, decoded) in transactions.iter().enumerate() {
+                let (Some(to), value) = (decoded.transaction.to(), decoded.transaction.value())
+                else {
+                    continue;
+                };
+                if value == 0 || (decoded.from != *account && to != account) {
+                    continue;
+                }
+                transfers.push(Transfer {
+                    block_number: number,
+                    transaction_hash: decoded.hash,
+                    transaction_index: index as u64,
+                    log_index: None,
+                    from: decoded.from,
+                    to: *to,
+                    kind: TransferKind::Native(value),
+                });
+            }
+        }
+        Ok(transfers)
+    }
+}
+
+impl<P: Provider> HistorySource for History<'_, P> {
+    fn transfers(
+        &self,
+        account: &Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<Transfer>> {
+        let mut transfers = self.token_transfers(account, from_block, to_block)?;
+        if self.native {
+            transfers.extend(self.native_transfers(account, from_block, to_block)?);
+        }
+        sort(&mut transfers);
+        Ok(transfers)
+    }
+}
+
+/// Put transfers in the order of the chain, the native value of a
+/// transaction before its logs, and drop the duplicates of transfers found
+/// both as sent and received.
+pub(crate) fn sort(transfers: &mut Vec<Transfer>) {
+    transfers.sort_by_key(|transfer| {
+        (
+            transfer.block_number,
+            transfer.transaction_index,
+            transfer.log_index,
+        )
+    });
+    transfers.dedup();
+}
+
+/// Return the topic of `Transfer(address,address,uint256)`.
+fn transfer_topic() -> B256 {
+    B256::new(keccak256("Transfer(address,address,uint256)"))
+}
+
+/// Return an address as an indexed topic.
+fn address_topic(address: &Address) -> B256 {
+    let mut topic = [0; 32];
+    topic[12..].copy_from_slice(address.as_bytes());
+    B256::new(topic)
+}
+
+/// Return the address of an indexed topic, `None` when it is not one.
+fn topic_address(topic: &B256) -> Option<Address> {
+    match topic.0[..12].iter().all(|byte| *byte == 0) {
+        true => Address::from_slice(&topic.0[12..]).ok(),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        provider::mock::MockProvider,
+        signature::Signature,
+        transaction::{DecodedTransaction, Transaction},
+    };
+    use std::collections::HashMap;
+
+    const ACCOUNT: Address = Address::new([0xa1; 20]);
+    const OTHER: Address = Address::new([0xb0; 20]);
+    const TOKEN: Address = Address::new([0x70; 20]);
+    const NFT: Address = Address::new([0x72; 20]);
+
+    fn transfer_log(token: Address, from: Address, to: Address, value: u64, erc721: bool) -> Log {
+        let mut topics = vec![transfer_topic(), address_topic(&from), address_topic(&to)];
+        let value = U256::from(value).to_big_endian();
+        let data = match erc721 {
+            true => {
+                topics.push(B256::new(value));
+                Vec::new()
+            }
+            false => value.to_vec(),
+        };
+        Log {
+            address: token,
+            topics,
+            data,
+            block_number: 0,
+            transaction_hash: B256::default(),
+            transaction_index: 0,
+            log_index: 0,
+        }
+    }
+
+    fn at(mut log: Log, block_number: u64, transaction_index: u64, log_index: u64) -> Log {
+        log.block_number = block_number;
+        log.transaction_hash = B256::new([block_number as u8; 32]);
+        log.transaction_index = transaction_index;
+        log.log_index = log_index;
+        log
+    }
+
+    fn payment(from: Address, to: Address, value: u128) -> DecodedTransaction {
+        let transaction = Transaction {
+            to: Some(to),
+            value,
+            ..Default::default()
+        };
+        DecodedTransaction {
+            transaction: transaction.into(),
+            signature: Signature::new([1; 32], [1; 32], 27),
+            from,
+            hash: B256::new([0xee; 32]),
+        }
+    }
+
+    #[test]
+    fn test_from_log() {
+        assert_eq!(
+            hex::encode(transfer_topic().0),
+            "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+        );
+
+        let erc20 = Transfer::from_log(&transfer_log(TOKEN, ACCOUNT, OTHER, 5, false)).unwrap();
+        assert_eq!(
+            erc20.kind,
+            TransferKind::Erc20 {
+                token: TOKEN,
+                value: 5.into()
+            }
+        );
+        assert_eq!((erc20.from, erc20.to), (ACCOUNT, OTHER));
+        assert_eq!(erc20.direction(&ACCOUNT), Some(Direction::Outgoing));
+        assert_eq!(erc20.direction(&OTHER), Some(Direction::Incoming));
+        assert_eq!(erc20.direction(&TOKEN), None);
+
+        let erc721 = Transfer::from_log(&transfer_log(NFT, OTHER, ACCOUNT, 42, true)).unwrap();
+        assert_eq!(
+            erc721.kind,
+            TransferKind::Erc721 {
+                token: NFT,
+                token_id: 42.into()
+            }
+        );
+        assert_eq!(
+            erc721.to_string(),
+            format!("0 {} -> {} #42 of {}", OTHER, ACCOUNT, NFT)
+        );
+
+        let mut approval = transfer_log(TOKEN, ACCOUNT, OTHER, 5, false);
+        approval.topics[0] = B256::new(keccak256("Approval(address,address,uint256)"));
+        assert_eq!(Transfer::from_log(&approval), None);
+        let mut truncated = transfer_log(TOKEN, ACCOUNT, OTHER, 5, false);
+        truncated.data.pop();
+        assert_eq!(Transfer::from_log(&truncated), None);
+        let mut dirty = transfer_log(TOKEN, ACCOUNT, OTHER, 5, false);
+        dirty.topics[1].0[0] = 1;
+        assert_eq!(Transfer::from_log(&dirty), None);
+    }
+
+    #[test]
+    fn test_transfers() {
+        let provider = MockProvider {
+            logs: Some(vec![
+                at(transfer_log(TOKEN, OTHER, ACCOUNT, 100, false), 10, 0, 0),
+                at(transfer_log(NFT, ACCOUNT, OTHER, 7, true), 20, 3, 5),
+                at(transfer_log(TOKEN, ACCOUNT, ACCOUNT, 1, false), 20, 3, 4),
+                at(transfer_log(TOKEN, OTHER, TOKEN, 9, false), 30, 0, 0),
+                at(transfer_log(TOKEN, OTHER, ACCOUNT, 2, false), 4_500, 0, 0),
+            ]),
+            blocks: Some(HashMap::from([(
+                20,
+                vec![
+                    payment(OTHER, TOKEN, 1),
+                    payment(OTHER, OTHER, 1),
+                    payment(ACCOUNT, TOKEN, 0),
+                    payment(ACCOUNT, OTHER, 3),
+                ],
+            )])),
+            ..Default::default()
+        };
+
+        let history = History::new(&provider).block_range(1_000);
+        let transfers = history.transfers(&ACCOUNT, 0, 4_000).unwrap();
+        // two queries for each range of 1000 blocks
+        assert_eq!(provider.count("logs"), 10);
+
+        let kinds: Vec<_> = transfers.iter().map(|transfer| &transfer.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                &TransferKind::Erc20 {
+                    token: TOKEN,
+                    value: 100.into()
+                },
+                &TransferKind::Native(3),
+                &TransferKind::Erc20 {
+                    token: TOKEN,
+                    value: 1.into()
+                },
+                &TransferKind::Erc721 {
+                    token: NFT,
+                    token_id: 7.into()
+                },
+            ]
+        );
+        assert_eq!(transfers[2].direction(&ACCOUNT), Some(Direction::ToSelf));
+        assert_eq!(
+            transfers[1].to_string(),
+            format!("20 {} -> {} 3 wei", ACCOUNT, OTHER)
+        );
+    }
+
+    #[test]
+    fn test_without_native() {
+        let provider = MockProvider {
+            logs: Some(vec![at(
+                transfer_log(TOKEN, OTHER, ACCOUNT, 100, false),
+                10,
+                0,
+                0,
+            )]),
+            ..Default::default()
+        };
+
+        let error = History::new(&provider)
+            .transfers(&ACCOUNT, 0, 100)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The provider does not support eth_getBlockByNumber"
+        );
+        let history = History::new(&provider).native(false);
+        assert_eq!(history.transfers(&ACCOUNT, 0, 100).unwrap().len(), 1);
+    }
+}
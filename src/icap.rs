@@ -0,0 +1,261 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # ICAP
+//!
+//! The Inter exchange Client Address Protocol, an IBAN compatible encoding of
+//! Ethereum addresses still required by some exchanges and institutions.
+//!
+//! A direct ICAP is the address in base 36, `XE` followed by two check
+//! digits and 30 characters (31 for addresses above 155 bits). An indirect
+//! ICAP names a client at an institution, `XE` + check digits + `ETH` + a
+//! 4 character institution + a 9 character client, and must be resolved
+//! through a registry.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::address::Address;
+//! use wallet_rust::icap::Icap;
+//!
+//! let address: Address = "0x00c5496aee77c1ba1f0854206a26dda82a81d6d8".parse().unwrap();
+//! let icap = Icap::from(address);
+//! assert_eq!(icap.to_string(), "XE7338O073KYGTWWZN0F2WZ0R8PX5ZPPZS");
+//!
+//! let parsed: Icap = "XE7338O073KYGTWWZN0F2WZ0R8PX5ZPPZS".parse().unwrap();
+//! assert_eq!(parsed.address(), Some(address));
+//! ```
+
+use crate::{
+    address::Address,
+    error::{Result, WalletError},
+};
+
+const ALPHABET: &[u8; 36] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Error returned when parsing an ICAP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IcapError {
+    /// The string is not an `XE` ICAP of a valid length.
+    InvalidFormat,
+    /// The string contains a character outside of `0-9A-Z`.
+    InvalidCharacter(char),
+    /// The check digits do not match.
+    InvalidChecksum,
+    /// The encoded number does not fit in an address.
+    Overflow,
+}
+
+impl std::fmt::Display for IcapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            IcapError::InvalidFormat => write!(f, "Invalid ICAP format"),
+            IcapError::InvalidCharacter(c) => write!(f, "Invalid ICAP character: {}", c),
+            IcapError::InvalidChecksum => write!(f, "Invalid ICAP checksum"),
+            IcapError::Overflow => write!(f, "ICAP does not encode a valid address"),
+        }
+    }
+}
+
+impl std::error::Error for IcapError {}
+
+/// An ICAP account identifier.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Icap {
+    /// An address encoded in base 36.
+    Direct(Address),
+    /// A client account at an institution.
+    Indirect {
+        /// The 4 character institution code.
+        institution: String,
+        /// The 9 character client identifier.
+        client: String,
+    },
+}
+
+impl Icap {
+    /// Return the address of a direct ICAP.
+    pub fn address(&self) -> Option<Address> {
+        match self {
+            Icap::Direct(address) => Some(*address),
+            Icap::Indirect { .. } => None,
+        }
+    }
+
+    fn bban(&self) -> String {
+        match self {
+            Icap::Direct(address) => format!("{:0>30}", to_base36(address.as_bytes())),
+            Icap::Indirect {
+                institution,
+                client,
+            } => format!("ETH{}{}", institution, client),
+        }
+    }
+}
+
+impl From<Address> for Icap {
+    fn from(address: Address) -> Self {
+        Icap::Direct(address)
+    }
+}
+
+impl std::fmt::Display for Icap {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let bban = self.bban();
+        write!(f, "XE{:02}{}", 98 - iban_mod97(&bban, "XE00"), bban)
+    }
+}
+
+impl std::str::FromStr for Icap {
+    type Err = WalletError;
+
+    /// Parse a direct or indirect ICAP, case insensitive.
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.to_ascii_uppercase();
+        if let Some(c) = s.chars().find(|c| !c.is_ascii_alphanumeric()) {
+            return Err(IcapError::InvalidCharacter(c).into());
+        }
+        if !s.starts_with("XE") || !(20..=35).contains(&s.len()) {
+            return Err(IcapError::InvalidFormat.into());
+        }
+
+        let (country, bban) = s.split_at(4);
+        if iban_mod97(bban, country) != 1 {
+            return Err(IcapError::InvalidChecksum.into());
+        }
+
+        match bban.len() {
+            30 | 31 => Ok(Icap::Direct(Address::new(from_base36(bban)?))),
+            16 if bban.starts_with("ETH") => Ok(Icap::Indirect {
+                institution: bban[3..7].to_string(),
+                client: bban[7..].to_string(),
+            }),
+            _ => Err(IcapError::InvalidFormat.into()),
+        }
+    }
+}
+
+/// Return the IBAN remainder of `bban` followed by the country code and check
+/// digits, with letters expanded to two digit numbers.
+fn iban_mod97(bban: &str, country: &str) -> u32 {
+    bban.chars()
+        .chain(country.chars())
+        .fold(0, |acc, c| match c.to_digit(36) {
+            Some(n) if n >= 10 => (acc * 100 + n) % 97,
+            Some(n) => (acc * 10 + n) % 97,
+            None => acc,
+        })
+}
+
+/// Encode a big-endian number in uppercase base 36.
+fn to_base36(bytes: &[u8]) -> String {
+    let mut number = bytes.to_vec();
+    let mut digits = Vec::new();
+
+    while number.iter().any(|b| *b != 0) {
+        let mut remainder = 0u32;
+        for byte in number.iter_mut() {
+            let value = (remainder << 8) | *byte as u32;
+            *byte = (value / 36) as u8;
+            remainder = value % 36;
+        }
+        digits.push(ALPHABET[remainder as usize]);
+    }
+
+    digits.reverse();
+    String::from_utf8(digits).unwrap_or_default()
+}
+
+/// Decode an uppercase base 36 number into a 20-byte big-endian number.
+fn from_base36(s: &str) -> Result<[u8; 20]> {
+    let mut number = [0u8; 20];
+
+    for c in s.chars() {
+        let mut carry = c.to_digit(36).ok_or(IcapError::InvalidCharacter(c))?;
+        for byte in number.iter_mut().rev() {
+            let value = *byte as u32 * 36 + carry;
+            *byte = value as u8;
+            carry = value >> 8;
+        }
+        if carry != 0 {
+            return Err(IcapError::Overflow.into());
+        }
+    }
+
+    Ok(number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_direct() {
+        let vectors = [
+            (
+                "0x8ba1f109551bd432803012645ac136ddd64dba72",
+                "XE65GB6LDNXYOFTX0NSV3FUWKOWIXAMJK36",
+            ),
+            (
+                "0xffffffffffffffffffffffffffffffffffffffff",
+                "XE95TWJ4YIDKW7A8PN4G709KZMFOAOL3X8F",
+            ),
+            (
+                "0x0000000000000000000000000000000000000000",
+                "XE50000000000000000000000000000000",
+            ),
+        ];
+
+        for (address, icap) in vectors {
+            let address: Address = address.parse().unwrap();
+            assert_eq!(Icap::from(address).to_string(), icap);
+            assert_eq!(icap.parse::<Icap>().unwrap(), Icap::Direct(address));
+            assert_eq!(
+                icap.to_lowercase().parse::<Icap>().unwrap(),
+                Icap::Direct(address)
+            );
+        }
+    }
+
+    #[test]
+    fn test_indirect() {
+        let icap: Icap = "XE81ETHXREGGAVOFYORK".parse().unwrap();
+        assert_eq!(
+            icap,
+            Icap::Indirect {
+                institution: "XREG".to_string(),
+                client: "GAVOFYORK".to_string(),
+            }
+        );
+        assert_eq!(icap.address(), None);
+        assert_eq!(icap.to_string(), "XE81ETHXREGGAVOFYORK");
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!("XE66GB6LDNXYOFTX0NSV3FUWKOWIXAMJK36"
+            .parse::<Icap>()
+            .is_err());
+        assert!("DE65GB6LDNXYOFTX0NSV3FUWKOWIXAMJK36"
+            .parse::<Icap>()
+            .is_err());
+        assert!("XE65GB6LDNXYOFTX0NSV3FUWKOWIXAMJK3"
+            .parse::<Icap>()
+            .is_err());
+        assert!("XE65GB6LDNXYOFTX0NSV3FUWKOWIXAMJK3-"
+            .parse::<Icap>()
+            .is_err());
+        assert!("XE80ZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZ"
+            .parse::<Icap>()
+            .is_err());
+    }
+}
@@ -0,0 +1,274 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Identicons
+//!
+//! Deterministic account avatars compatible with
+//! [blockies](https://github.com/ethereum/blockies), the identicons shown by
+//! MetaMask, Etherscan and most wallets, so users recognize the same avatar
+//! for the same address everywhere.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::address::Address;
+//! use wallet_rust::identicon::Blockies;
+//!
+//! let address: Address = "0x9858EfFD232B4033E47d90003D41EC34EcaEda94".parse().unwrap();
+//! let icon = Blockies::from_address(&address);
+//! assert_eq!(icon.color().to_string(), "hsl(48,73.95176903344691%,55.65601648995653%)");
+//!
+//! let svg = icon.to_svg(4);
+//! assert!(svg.starts_with("<svg"));
+//! ```
+
+use crate::address::Address;
+
+/// Size of the grid used for addresses.
+pub const DEFAULT_SIZE: usize = 8;
+
+/// A color in the HSL space, as produced by blockies.
+///
+/// The hue may exceed 360 and the saturation 100%, both are kept as is to
+/// match the CSS the reference implementation emits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsl {
+    /// Hue in degrees.
+    pub hue: u32,
+    /// Saturation in percent.
+    pub saturation: f64,
+    /// Lightness in percent.
+    pub lightness: f64,
+}
+
+impl std::fmt::Display for Hsl {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "hsl({},{}%,{}%)",
+            self.hue, self.saturation, self.lightness
+        )
+    }
+}
+
+/// The cell of a blockies grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+    /// Filled with the background color.
+    Background,
+    /// Filled with the main color.
+    Color,
+    /// Filled with the spot color.
+    Spot,
+}
+
+/// A blockies identicon, a mirrored grid of cells and its three colors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Blockies {
+    size: usize,
+    cells: Vec<Cell>,
+    color: Hsl,
+    background: Hsl,
+    spot: Hsl,
+}
+
+impl Blockies {
+    /// Create the identicon of the given address, seeded with its lowercase
+    /// hex form like MetaMask does.
+    pub fn from_address(address: &Address) -> Self {
+        Self::new(&format!("0x{}", hex::encode(address)), DEFAULT_SIZE)
+    }
+
+    /// Create the identicon of an arbitrary seed with a `size` x `size` grid.
+    pub fn new(seed: &str, size: usize) -> Self {
+        let mut rng = Rng::new(seed);
+
+        let color = rng.color();
+        let background = rng.color();
+        let spot = rng.color();
+
+        let data_width = size.div_ceil(2);
+        let mirror_width = size - data_width;
+        let mut cells = Vec::with_capacity(size * size);
+        for _ in 0..size {
+            let row = (0..data_width)
+                .map(|_| match (rng.next() * 2.3).floor() as u8 {
+                    0 => Cell::Background,
+                    1 => Cell::Color,
+                    _ => Cell::Spot,
+                })
+                .collect::<Vec<_>>();
+
+            cells.extend_from_slice(&row);
+            cells.extend(row[..mirror_width].iter().rev());
+        }
+
+        Self {
+            size,
+            cells,
+            color,
+            background,
+            spot,
+        }
+    }
+
+    /// Return the width and height of the grid.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Return the cells of the grid, row by row.
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    /// Return the main color.
+    pub fn color(&self) -> Hsl {
+        self.color
+    }
+
+    /// Return the background color.
+    pub fn background(&self) -> Hsl {
+        self.background
+    }
+
+    /// Return the spot color.
+    pub fn spot(&self) -> Hsl {
+        self.spot
+    }
+
+    /// Render the identicon as an SVG image, each cell `scale` pixels wide.
+    pub fn to_svg(&self, scale: usize) -> String {
+        let width = self.size * scale;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" \
+             viewBox=\"0 0 {0} {0}\" shape-rendering=\"crispEdges\">\
+             <rect width=\"{0}\" height=\"{0}\" fill=\"{1}\"/>",
+            width, self.background
+        );
+
+        for (i, cell) in self.cells.iter().enumerate() {
+            let fill = match cell {
+                Cell::Background => continue,
+                Cell::Color => self.color,
+                Cell::Spot => self.spot,
+            };
+            svg.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{2}\" height=\"{2}\" fill=\"{3}\"/>",
+                i % self.size * scale,
+                i / self.size * scale,
+                scale,
+                fill
+            ));
+        }
+
+        svg.push_str("</svg>");
+        svg
+    }
+}
+
+/// The xorshift generator of blockies, reproducing the JavaScript number
+/// semantics of the reference implementation bit for bit.
+struct Rng([f64; 4]);
+
+impl Rng {
+    fn new(seed: &str) -> Self {
+        let mut state = [0f64; 4];
+        for (i, c) in seed.encode_utf16().enumerate() {
+            let s = state[i % 4];
+            state[i % 4] = ((to_int32(s) << 5) as f64 - s) + c as f64;
+        }
+        Self(state)
+    }
+
+    fn next(&mut self) -> f64 {
+        let s0 = to_int32(self.0[0]);
+        let t = s0 ^ (s0 << 11);
+
+        self.0[0] = self.0[1];
+        self.0[1] = self.0[2];
+        self.0[2] = self.0[3];
+
+        let s3 = to_int32(self.0[3]);
+        let s3 = s3 ^ (s3 >> 19) ^ t ^ (t >> 8);
+        self.0[3] = s3 as f64;
+
+        s3 as u32 as f64 / 2147483648.0
+    }
+
+    fn color(&mut self) -> Hsl {
+        let hue = (self.next() * 360.0).floor() as u32;
+        let saturation = self.next() * 60.0 + 40.0;
+        let lightness = (self.next() + self.next() + self.next() + self.next()) * 25.0;
+
+        Hsl {
+            hue,
+            saturation,
+            lightness,
+        }
+    }
+}
+
+/// The `ToInt32` conversion of JavaScript bitwise operators.
+fn to_int32(value: f64) -> i32 {
+    value as i64 as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blockies() {
+        let address: Address = "0x9858EfFD232B4033E47d90003D41EC34EcaEda94"
+            .parse()
+            .unwrap();
+        let icon = Blockies::from_address(&address);
+
+        assert_eq!(
+            icon.color().to_string(),
+            "hsl(48,73.95176903344691%,55.65601648995653%)"
+        );
+        assert_eq!(
+            icon.background().to_string(),
+            "hsl(304,95.62175297178328%,67.38309417851269%)"
+        );
+        assert_eq!(
+            icon.spot().to_string(),
+            "hsl(73,84.00667177513242%,65.52077601663768%)"
+        );
+
+        let cells = icon
+            .cells()
+            .iter()
+            .map(|cell| match cell {
+                Cell::Background => '0',
+                Cell::Color => '1',
+                Cell::Spot => '2',
+            })
+            .collect::<String>();
+        assert_eq!(
+            cells,
+            "0002200002111120122112210211112001100110110110111000000111000011"
+        );
+    }
+
+    #[test]
+    fn test_odd_size() {
+        let icon = Blockies::new("seed", 5);
+        assert_eq!(icon.cells().len(), 25);
+        for row in icon.cells().chunks(5) {
+            assert_eq!(row[0], row[4]);
+            assert_eq!(row[1], row[3]);
+        }
+    }
+}
@@ -0,0 +1,312 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The [AEZ v5](https://web.cs.ucdavis.edu/~rogaway/aez/) authenticated
+//! cipher, as used by aezeed.
+//!
+//! Messages are enciphered together with their tag, and AEZ-tiny handles
+//! those shorter than 32 bytes. aezeed encrypts 19 bytes with a 4 byte
+//! tag, so AEZ-core, which enciphers longer messages, is left out and
+//! [`encrypt`] returns `None` for them.
+//!
+//! The AES rounds are the constant time rounds of the `aes` crate.
+
+use aes::{hazmat::cipher_round, Block as AesBlock};
+use blake2::{digest::consts::U48, Blake2b, Digest};
+use zeroize::{Zeroize, Zeroizing};
+
+type Block = [u8; 16];
+
+/// Longest message with its tag enciphered by AEZ-tiny.
+const MAX_TINY_LENGTH: usize = 31;
+
+/// Encrypt `plaintext` under `key`, the nonce and the additional data,
+/// returning the ciphertext followed by a tag of `tag_length` bytes, or
+/// `None` when they are 32 bytes or more.
+pub(crate) fn encrypt(
+    key: &[u8],
+    nonce: &[u8],
+    additional_data: &[&[u8]],
+    tag_length: usize,
+    plaintext: &[u8],
+) -> Option<Vec<u8>> {
+    let aez = Aez::new(key);
+    let delta = aez.hash(tag_length, nonce, additional_data);
+    if plaintext.is_empty() {
+        return Some(aez.prf(&delta, tag_length));
+    }
+    if plaintext.len() + tag_length > MAX_TINY_LENGTH {
+        return None;
+    }
+
+    let mut message = Zeroizing::new(plaintext.to_vec());
+    message.resize(plaintext.len() + tag_length, 0);
+    Some(aez.tiny(&delta, &message, false))
+}
+
+/// Decrypt `ciphertext`, returning `None` if its tag does not match or if
+/// it is 32 bytes or more.
+pub(crate) fn decrypt(
+    key: &[u8],
+    nonce: &[u8],
+    additional_data: &[&[u8]],
+    tag_length: usize,
+    ciphertext: &[u8],
+) -> Option<Vec<u8>> {
+    if ciphertext.len() < tag_length || ciphertext.len() > MAX_TINY_LENGTH {
+        return None;
+    }
+    let aez = Aez::new(key);
+    let delta = aez.hash(tag_length, nonce, additional_data);
+    if ciphertext.len() == tag_length {
+        let tag = aez.prf(&delta, tag_length);
+        return is_zero(&xor_bytes(&tag, ciphertext)).then(Vec::new);
+    }
+
+    let mut message = aez.tiny(&delta, ciphertext, true);
+    let plaintext_length = ciphertext.len() - tag_length;
+    if !is_zero(&message[plaintext_length..]) {
+        message.zeroize();
+        return None;
+    }
+    message.truncate(plaintext_length);
+    Some(message)
+}
+
+/// The three keys extracted from the key, `I`, `J` and `L`.
+struct Aez {
+    i: Block,
+    j: Block,
+    l: Block,
+}
+
+impl Aez {
+    /// Extract the keys, taking a 48 byte key as it is and hashing any
+    /// other one with BLAKE2b.
+    fn new(key: &[u8]) -> Self {
+        let mut extracted = Zeroizing::new([0u8; 48]);
+        if key.len() == 48 {
+            extracted.copy_from_slice(key);
+        } else {
+            extracted.copy_from_slice(&Blake2b::<U48>::digest(key));
+        }
+        let part = |n: usize| -> Block { extracted[n * 16..][..16].try_into().expect("16 bytes") };
+        Self {
+            i: part(0),
+            j: part(1),
+            l: part(2),
+        }
+    }
+
+    /// Four AES rounds keyed by `J`, `I`, `L` and zero, over `x` offset
+    /// by the tweak.
+    fn aes4(&self, offset: &Block, x: &Block) -> Block {
+        let mut block = AesBlock::from(xor(x, offset));
+        for key in [&self.j, &self.i, &self.l, &[0; 16]] {
+            cipher_round(&mut block, AesBlock::from_slice(key));
+        }
+        block.into()
+    }
+
+    /// Ten AES rounds keyed by `I`, `J` and `L` in turn, over `x` offset
+    /// by the tweak.
+    fn aes10(&self, offset: &Block, x: &Block) -> Block {
+        let mut block = AesBlock::from(xor(x, offset));
+        for key in [&self.i, &self.j, &self.l].into_iter().cycle().take(10) {
+            cipher_round(&mut block, AesBlock::from_slice(key));
+        }
+        block.into()
+    }
+
+    /// The tweakable block cipher `E(j, i)`, for `j` from 0.
+    fn e(&self, j: u32, i: u32, x: &Block) -> Block {
+        let offset = xor(
+            &xor(&mult(j, &self.j), &mult(1 << i.div_ceil(8), &self.i)),
+            &mult(i % 8, &self.l),
+        );
+        self.aes4(&offset, x)
+    }
+
+    /// The tweakable block cipher `E(-1, i)`.
+    fn e_last(&self, i: u32, x: &Block) -> Block {
+        self.aes10(&mult(i, &self.l), x)
+    }
+
+    /// AEZ-hash of the tag length, the nonce and the additional data.
+    fn hash(&self, tag_length: usize, nonce: &[u8], additional_data: &[&[u8]]) -> Block {
+        let mut tau = [0u8; 16];
+        tau[12..].copy_from_slice(&(tag_length as u32 * 8).to_be_bytes());
+        let mut sum = self.e(3, 1, &tau);
+
+        for (j, data) in (4..).zip(std::iter::once(&nonce).chain(additional_data)) {
+            let mut blocks = data.chunks_exact(16);
+            for (i, block) in (1..).zip(&mut blocks) {
+                sum = xor(&sum, &self.e(j, i, block.try_into().expect("16 bytes")));
+            }
+            let rest = blocks.remainder();
+            if data.is_empty() || !rest.is_empty() {
+                sum = xor(&sum, &self.e(j, 0, &pad(rest, rest.len() * 8)));
+            }
+        }
+        sum
+    }
+
+    /// AEZ-prf, the tag of an empty message.
+    fn prf(&self, delta: &Block, tag_length: usize) -> Vec<u8> {
+        let mut tag = Vec::with_capacity(tag_length.next_multiple_of(16));
+        for counter in 0..tag_length.div_ceil(16) as u128 {
+            tag.extend_from_slice(&self.e_last(3, &xor(delta, &counter.to_be_bytes())));
+        }
+        tag.truncate(tag_length);
+        tag
+    }
+
+    /// AEZ-tiny, a Feistel network over the two halves of a message of
+    /// 1 to 31 bytes, which may end in the middle of a byte.
+    fn tiny(&self, delta: &Block, input: &[u8], decipher: bool) -> Vec<u8> {
+        let half = input.len() * 4;
+        let (rounds, i) = match input.len() {
+            1 => (24, 7),
+            2 => (16, 7),
+            3..=15 => (10, 7),
+            _ => (8, 6),
+        };
+
+        let mut input = Zeroizing::new(input.to_vec());
+        if decipher && input.len() < 16 {
+            self.tiny_flip(delta, &mut input);
+        }
+        let mut left = Zeroizing::new(read_bits(&input, 0, half));
+        let mut right = Zeroizing::new(read_bits(&input, half, half));
+        for round in 0..rounds {
+            let j = if decipher { rounds - 1 - round } else { round };
+            let (source, target) = if round % 2 == 0 {
+                (&right, &mut left)
+            } else {
+                (&left, &mut right)
+            };
+            let mut x = xor(&pad(&source[..], half), delta);
+            x[15] ^= j as u8;
+            **target = xor(target, &self.e(0, i, &x));
+        }
+
+        let mut output = vec![0u8; input.len()];
+        write_bits(&mut output, 0, &right, half);
+        write_bits(&mut output, half, &left, half);
+        if !decipher && input.len() < 16 {
+            self.tiny_flip(delta, &mut output);
+        }
+        output
+    }
+
+    /// Flip the first bit of a message shorter than 16 bytes, which would
+    /// otherwise not be mixed enough by the Feistel network.
+    fn tiny_flip(&self, delta: &Block, message: &mut [u8]) {
+        let mut x = [0u8; 16];
+        x[..message.len()].copy_from_slice(message);
+        x[0] |= 0x80;
+        message[0] ^= self.e(0, 3, &xor(&x, delta))[0] & 0x80;
+    }
+}
+
+impl Drop for Aez {
+    fn drop(&mut self) {
+        self.i.zeroize();
+        self.j.zeroize();
+        self.l.zeroize();
+    }
+}
+
+fn xor(a: &Block, b: &Block) -> Block {
+    std::array::from_fn(|n| a[n] ^ b[n])
+}
+
+fn xor_bytes(a: &[u8], b: &[u8]) -> Vec<u8> {
+    a.iter().zip(b).map(|(a, b)| a ^ b).collect()
+}
+
+fn is_zero(bytes: &[u8]) -> bool {
+    bytes.iter().fold(0, |acc, byte| acc | byte) == 0
+}
+
+/// Double a block in GF(2^128).
+fn double(block: &Block) -> Block {
+    let carry = block[0] >> 7;
+    let mut doubled: Block =
+        std::array::from_fn(|n| (block[n] << 1) | block.get(n + 1).map_or(0, |next| next >> 7));
+    doubled[15] ^= 0x87 * carry;
+    doubled
+}
+
+/// Multiply a block by a public integer in GF(2^128).
+fn mult(mut x: u32, block: &Block) -> Block {
+    let mut product = [0u8; 16];
+    let mut power = *block;
+    while x != 0 {
+        if x & 1 == 1 {
+            product = xor(&product, &power);
+        }
+        power = double(&power);
+        x >>= 1;
+    }
+    product
+}
+
+/// Return the first `len` bits of `bits` followed by a one bit and zeros.
+fn pad(bits: &[u8], len: usize) -> Block {
+    let mut block = read_bits(bits, 0, len);
+    block[len / 8] |= 0x80 >> (len % 8);
+    block
+}
+
+/// Read `len` bits, up to 128, from the bit `start` of `bytes`.
+fn read_bits(bytes: &[u8], start: usize, len: usize) -> Block {
+    let mut block = [0u8; 16];
+    for n in 0..len {
+        let bit = (bytes[(start + n) / 8] >> (7 - (start + n) % 8)) & 1;
+        block[n / 8] |= bit << (7 - n % 8);
+    }
+    block
+}
+
+/// Write the first `len` bits of `block` at the bit `start` of `bytes`.
+fn write_bits(bytes: &mut [u8], start: usize, block: &Block, len: usize) {
+    for n in 0..len {
+        let bit = (block[n / 8] >> (7 - n % 8)) & 1;
+        bytes[(start + n) / 8] |= bit << (7 - (start + n) % 8);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for len in 0..=MAX_TINY_LENGTH - 4 {
+            let plaintext = (0..len as u8).collect::<Vec<_>>();
+            let ciphertext = encrypt(b"key", b"nonce", &[b"ad"], 4, &plaintext).unwrap();
+            assert_eq!(ciphertext.len(), len + 4);
+            assert_eq!(
+                decrypt(b"key", b"nonce", &[b"ad"], 4, &ciphertext).unwrap(),
+                plaintext
+            );
+            assert!(decrypt(b"key", b"nonce", &[b"other"], 4, &ciphertext).is_none());
+
+            let mut forged = ciphertext.clone();
+            forged[len / 2] ^= 1;
+            assert!(decrypt(b"key", b"nonce", &[b"ad"], 4, &forged).is_none());
+        }
+        assert!(encrypt(b"key", b"", &[], 4, &[0; 28]).is_none());
+    }
+}
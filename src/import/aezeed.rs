@@ -0,0 +1,381 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Recovery of the aezeed cipher seeds of lnd.
+//!
+//! An aezeed is 24 words of the BIP39 English list without the BIP39
+//! checksum. The 33 bytes they encode hold a version byte, the encrypted
+//! seed, the salt of the encryption key and a CRC-32C checksum. The
+//! encrypted part carries 16 bytes of entropy and the birthday of the
+//! wallet, in days since the Bitcoin genesis block, so that a rescan does
+//! not need to start from the first block.
+//!
+//! The seed is encrypted with the AEZ cipher under a key derived from the
+//! passphrase with scrypt, `aezeed` being the passphrase when none is set.
+//!
+//! ## Example
+//! ```rust,no_run
+//! use wallet_rust::import::aezeed::CipherSeed;
+//!
+//! let words = std::fs::read_to_string("lnd-seed.txt").unwrap();
+//! let seed = CipherSeed::from_mnemonic(&words, "").unwrap();
+//! println!("born {} days after genesis", seed.birthday());
+//! let entropy = seed.entropy();
+//! ```
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rand::Rng;
+use zeroize::Zeroizing;
+
+use super::aez;
+use crate::{bips::wordlists::Language, error::Result};
+
+/// The only version of the aezeed format.
+pub const VERSION: u8 = 0;
+/// Unix timestamp of the Bitcoin genesis block, the origin of birthdays.
+pub const BITCOIN_GENESIS: u64 = 1_231_006_505;
+
+const DEFAULT_PASSPHRASE: &str = "aezeed";
+const ENTROPY_LENGTH: usize = 16;
+const SALT_LENGTH: usize = 5;
+/// Bytes of the authentication tag added by AEZ.
+const TAG_LENGTH: usize = 4;
+/// Internal version, birthday and entropy.
+const PLAINTEXT_LENGTH: usize = 1 + 2 + ENTROPY_LENGTH;
+/// Version, ciphertext, salt and checksum.
+const ENCIPHERED_LENGTH: usize = 1 + PLAINTEXT_LENGTH + TAG_LENGTH + SALT_LENGTH + 4;
+const WORDS: usize = ENCIPHERED_LENGTH * 8 / 11;
+/// scrypt parameters of aezeed: `n = 2^15`, `r = 8` and `p = 1`.
+const SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Error returned when reading an aezeed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AezeedError {
+    /// The mnemonic does not have 24 words.
+    InvalidLength(usize),
+    /// A word is not in the BIP39 English wordlist.
+    UnknownWord(String),
+    /// The checksum does not match, a word is wrong.
+    InvalidChecksum,
+    /// The external or internal version is not supported.
+    UnsupportedVersion(u8),
+    /// The passphrase does not decrypt the seed.
+    IncorrectPassphrase,
+    /// The seed could not be encrypted.
+    Encryption,
+}
+
+impl std::fmt::Display for AezeedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AezeedError::InvalidLength(len) => write!(f, "Invalid aezeed length: {} words", len),
+            AezeedError::UnknownWord(word) => write!(f, "Unknown aezeed word: {}", word),
+            AezeedError::InvalidChecksum => write!(f, "Invalid aezeed checksum"),
+            AezeedError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported aezeed version: {}", version)
+            }
+            AezeedError::IncorrectPassphrase => write!(f, "Incorrect aezeed passphrase"),
+            AezeedError::Encryption => write!(f, "Failed to encrypt the aezeed"),
+        }
+    }
+}
+
+impl std::error::Error for AezeedError {}
+
+/// The content of an aezeed.
+#[derive(Clone, PartialEq, Eq)]
+pub struct CipherSeed {
+    birthday: u16,
+    entropy: Zeroizing<[u8; ENTROPY_LENGTH]>,
+    salt: [u8; SALT_LENGTH],
+}
+
+impl CipherSeed {
+    /// Create a seed of `entropy` whose wallet was created `birthday` days
+    /// after the Bitcoin genesis block, with a random salt.
+    pub fn new(entropy: [u8; ENTROPY_LENGTH], birthday: u16) -> Self {
+        Self {
+            birthday,
+            entropy: Zeroizing::new(entropy),
+            salt: rand::thread_rng().gen(),
+        }
+    }
+
+    /// Generate a seed of random entropy born today.
+    pub fn generate() -> Self {
+        let mut entropy = Zeroizing::new([0u8; ENTROPY_LENGTH]);
+        rand::thread_rng().fill(&mut entropy[..]);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs());
+        let days = now.saturating_sub(BITCOIN_GENESIS) / SECONDS_PER_DAY;
+        Self::new(*entropy, days.min(u16::MAX as u64) as u16)
+    }
+
+    /// Use `salt` to derive the encryption key instead of a random one.
+    pub fn with_salt(mut self, salt: [u8; SALT_LENGTH]) -> Self {
+        self.salt = salt;
+        self
+    }
+
+    /// Return the entropy, the root key of the lnd wallet.
+    pub fn entropy(&self) -> &[u8; ENTROPY_LENGTH] {
+        &self.entropy
+    }
+
+    /// Return the birthday, in days since the Bitcoin genesis block.
+    pub fn birthday(&self) -> u16 {
+        self.birthday
+    }
+
+    /// Return the birthday as a Unix timestamp.
+    pub fn birthday_timestamp(&self) -> u64 {
+        BITCOIN_GENESIS + self.birthday as u64 * SECONDS_PER_DAY
+    }
+
+    /// Return the salt of the encryption key.
+    pub fn salt(&self) -> &[u8; SALT_LENGTH] {
+        &self.salt
+    }
+
+    /// Encrypt the seed with `passphrase` and encode it as 24 words.
+    pub fn to_mnemonic(&self, passphrase: &str) -> Result<Zeroizing<String>> {
+        self.encipher(passphrase, SCRYPT_LOG_N)
+    }
+
+    /// Decode 24 words and decrypt the seed with `passphrase`, empty when
+    /// none was set.
+    pub fn from_mnemonic(mnemonic: &str, passphrase: &str) -> Result<Self> {
+        Self::decipher(mnemonic, passphrase, SCRYPT_LOG_N)
+    }
+
+    /// Encode the seed with scrypt run at a cost of `2^log_n`.
+    fn encipher(&self, passphrase: &str, log_n: u8) -> Result<Zeroizing<String>> {
+        let mut plaintext = Zeroizing::new(Vec::with_capacity(PLAINTEXT_LENGTH));
+        plaintext.push(VERSION);
+        plaintext.extend_from_slice(&self.birthday.to_be_bytes());
+        plaintext.extend_from_slice(&*self.entropy);
+
+        let key = derive_key(passphrase, &self.salt, log_n);
+        let ciphertext = aez::encrypt(
+            &*key,
+            &[],
+            &[&additional_data(&self.salt)],
+            TAG_LENGTH,
+            &plaintext,
+        )
+        .filter(|ciphertext| ciphertext.len() == PLAINTEXT_LENGTH + TAG_LENGTH)
+        .ok_or(AezeedError::Encryption)?;
+
+        let mut enciphered = Vec::with_capacity(ENCIPHERED_LENGTH);
+        enciphered.push(VERSION);
+        enciphered.extend_from_slice(&ciphertext);
+        enciphered.extend_from_slice(&self.salt);
+        enciphered.extend_from_slice(&crc32c(&enciphered).to_be_bytes());
+
+        let wordlist = Language::English.wordlist();
+        let mut words = Vec::with_capacity(WORDS);
+        for i in 0..WORDS {
+            words.push(wordlist.get(read_bits(&enciphered, i * 11, 11) as usize)?);
+        }
+        Ok(Zeroizing::new(words.join(" ")))
+    }
+
+    /// Decode a seed with scrypt run at a cost of `2^log_n`.
+    fn decipher(mnemonic: &str, passphrase: &str, log_n: u8) -> Result<Self> {
+        let words: Vec<&str> = mnemonic.split_whitespace().collect();
+        if words.len() != WORDS {
+            return Err(AezeedError::InvalidLength(words.len()).into());
+        }
+        let wordmap = Language::English.wordmap();
+        let mut enciphered = [0u8; ENCIPHERED_LENGTH];
+        for (i, word) in words.iter().enumerate() {
+            let index = wordmap
+                .get_index(word)
+                .map_err(|_| AezeedError::UnknownWord(word.to_string()))?;
+            write_bits(&mut enciphered, i * 11, index as u32);
+        }
+
+        if enciphered[0] != VERSION {
+            return Err(AezeedError::UnsupportedVersion(enciphered[0]).into());
+        }
+        let (payload, checksum) = enciphered.split_at(ENCIPHERED_LENGTH - 4);
+        if crc32c(payload).to_be_bytes() != checksum {
+            return Err(AezeedError::InvalidChecksum.into());
+        }
+
+        let salt: [u8; SALT_LENGTH] = payload[payload.len() - SALT_LENGTH..].try_into().unwrap();
+        let ciphertext = &payload[1..payload.len() - SALT_LENGTH];
+        let key = derive_key(passphrase, &salt, log_n);
+        let plaintext = aez::decrypt(
+            &*key,
+            &[],
+            &[&additional_data(&salt)],
+            TAG_LENGTH,
+            ciphertext,
+        )
+        .map(Zeroizing::new)
+        .ok_or(AezeedError::IncorrectPassphrase)?;
+        if plaintext.len() != PLAINTEXT_LENGTH {
+            return Err(AezeedError::IncorrectPassphrase.into());
+        }
+        if plaintext[0] != VERSION {
+            return Err(AezeedError::UnsupportedVersion(plaintext[0]).into());
+        }
+
+        Ok(Self {
+            birthday: u16::from_be_bytes([plaintext[1], plaintext[2]]),
+            entropy: Zeroizing::new(plaintext[3..].try_into().unwrap()),
+            salt,
+        })
+    }
+}
+
+impl std::fmt::Debug for CipherSeed {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("CipherSeed")
+            .field("birthday", &self.birthday)
+            .finish_non_exhaustive()
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8; SALT_LENGTH], log_n: u8) -> Zeroizing<[u8; 32]> {
+    let passphrase = if passphrase.is_empty() {
+        DEFAULT_PASSPHRASE
+    } else {
+        passphrase
+    };
+    let params = scrypt::Params::new(log_n, SCRYPT_R, SCRYPT_P, 32)
+        .expect("aezeed scrypt parameters are valid");
+    let mut key = Zeroizing::new([0u8; 32]);
+    scrypt::scrypt(passphrase.as_bytes(), salt, &params, &mut *key)
+        .expect("the output length is valid");
+    key
+}
+
+/// The external version followed by the salt.
+fn additional_data(salt: &[u8; SALT_LENGTH]) -> [u8; 1 + SALT_LENGTH] {
+    let mut data = [VERSION; 1 + SALT_LENGTH];
+    data[1..].copy_from_slice(salt);
+    data
+}
+
+fn read_bits(bytes: &[u8], offset: usize, len: usize) -> u32 {
+    (offset..offset + len).fold(0, |value, bit| {
+        (value << 1) | ((bytes[bit / 8] >> (7 - bit % 8)) & 1) as u32
+    })
+}
+
+fn write_bits(bytes: &mut [u8], offset: usize, value: u32) {
+    for i in 0..11 {
+        if value >> (10 - i) & 1 == 1 {
+            let bit = offset + i;
+            bytes[bit / 8] |= 0x80 >> (bit % 8);
+        }
+    }
+}
+
+/// CRC-32 with the Castagnoli polynomial.
+fn crc32c(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0x82f6_3b78 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Entropy of the test vectors of lnd.
+    const ENTROPY: [u8; 16] = [
+        0x81, 0xb6, 0x37, 0xd8, 0x63, 0x59, 0xe6, 0x96, 0x0d, 0xe7, 0x95, 0xe4, 0x1e, 0x0b, 0x4c,
+        0xfd,
+    ];
+
+    #[test]
+    fn test_vectors() {
+        // aezeed/cipherseed_test.go of lnd, whose tests lower the cost of
+        // scrypt to 16
+        let vectors = [
+            (
+                "",
+                0,
+                "ability liquid travel stem barely drastic pact cupboard apple thrive morning oak \
+                 feature tissue couch old math inform success suggest drink motion know royal",
+            ),
+            (
+                "!very_safe_55345_password*",
+                3365,
+                "able tree stool crush transfer cloud cross three profit outside hen citizen \
+                 plate ride require leg siren drum success suggest drink require fiscal upgrade",
+            ),
+        ];
+        for (passphrase, birthday, mnemonic) in vectors {
+            let seed = CipherSeed::decipher(mnemonic, passphrase, 4).unwrap();
+            assert_eq!(seed.entropy(), &ENTROPY);
+            assert_eq!(seed.birthday(), birthday);
+            assert_eq!(seed.salt(), b"salt1");
+
+            let encoded = CipherSeed::new(ENTROPY, birthday)
+                .with_salt(*b"salt1")
+                .encipher(passphrase, 4)
+                .unwrap();
+            assert_eq!(
+                *encoded,
+                mnemonic.split_whitespace().collect::<Vec<_>>().join(" ")
+            );
+        }
+        assert_eq!(
+            CipherSeed::decipher(vectors[1].2, "", 4).unwrap_err(),
+            AezeedError::IncorrectPassphrase.into()
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let seed = CipherSeed::new([0x81; 16], 3365).with_salt(*b"salt1");
+        let mnemonic = seed.to_mnemonic("").unwrap();
+        assert_eq!(mnemonic.split_whitespace().count(), 24);
+
+        let decoded = CipherSeed::from_mnemonic(&mnemonic, "").unwrap();
+        assert_eq!(decoded, seed);
+        assert_eq!(decoded.birthday_timestamp(), 1_521_742_505);
+        // the default passphrase is used when none is set
+        assert!(CipherSeed::from_mnemonic(&mnemonic, "aezeed").is_ok());
+        assert_eq!(
+            CipherSeed::from_mnemonic(&mnemonic, "hunter2").unwrap_err(),
+            AezeedError::IncorrectPassphrase.into()
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        let seed = CipherSeed::generate();
+        let mnemonic = seed.to_mnemonic("pass").unwrap();
+        let mut words: Vec<&str> = mnemonic.split_whitespace().collect();
+
+        assert!(CipherSeed::from_mnemonic(&words[1..].join(" "), "pass").is_err());
+        words[5] = if words[5] == "zoo" { "abandon" } else { "zoo" };
+        assert!(CipherSeed::from_mnemonic(&words.join(" "), "pass").is_err());
+        assert_eq!(crc32c(b"123456789"), 0xe306_9283);
+    }
+}
@@ -0,0 +1,418 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Decryption of the vault of the MetaMask browser extension.
+//!
+//! The vault is the `KeyringController.vault` entry of the extension storage,
+//! a JSON object holding an AES-256-GCM ciphertext whose key is derived from
+//! the password with PBKDF2-HMAC-SHA256. Vaults written before the
+//! `keyMetadata` field existed use 10 000 iterations.
+//!
+//! [`Vault::from_storage`] finds the vault in the raw files of the browser
+//! storage, for users who can no longer open the extension.
+//!
+//! ## Example
+//! ```rust,no_run
+//! use wallet_rust::import::metamask::{Keyring, Vault};
+//!
+//! let json = std::fs::read_to_string("vault.json").unwrap();
+//! let vault = Vault::from_json(&json).unwrap();
+//! for keyring in vault.decrypt("password").unwrap() {
+//!     if let Keyring::HdKeyTree { mnemonic, .. } = &keyring {
+//!         println!("{}", mnemonic.phrase());
+//!     }
+//!     println!("{:?}", keyring.addresses().unwrap());
+//! }
+//! ```
+
+use aes_gcm::{
+    aead::{consts::U16, Aead, KeyInit},
+    aes::Aes256,
+    AesGcm, Key, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use laron_crypto::PrivateKey;
+use serde_json::Value;
+
+use crate::{
+    address::Address,
+    bips::{bip32::ExtendedKey, bip39::Mnemonic, wordlists::Language, ChildNumber, DerivationPath},
+    error::{Result, WalletError},
+    keystore::MAX_PBKDF2_ROUNDS,
+};
+
+/// Iterations of vaults without key metadata.
+const LEGACY_ITERATIONS: u32 = 10_000;
+
+/// Error returned when reading a vault.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VaultError {
+    /// The JSON is not a vault, the missing or malformed field is given.
+    InvalidFormat(&'static str),
+    /// The key derivation is not PBKDF2.
+    UnsupportedAlgorithm(String),
+    /// The password does not decrypt the vault.
+    IncorrectPassword,
+}
+
+impl std::fmt::Display for VaultError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            VaultError::InvalidFormat(field) => write!(f, "Invalid vault: {}", field),
+            VaultError::UnsupportedAlgorithm(algorithm) => {
+                write!(f, "Unsupported vault key derivation: {}", algorithm)
+            }
+            VaultError::IncorrectPassword => write!(f, "Incorrect vault password"),
+        }
+    }
+}
+
+impl std::error::Error for VaultError {}
+
+/// Parse the JSON value at the start of `text`, ignoring what follows.
+fn first_value(text: &str) -> Option<Value> {
+    serde_json::Deserializer::from_str(text)
+        .into_iter()
+        .next()?
+        .ok()
+}
+
+/// A keyring stored in the vault.
+pub enum Keyring {
+    /// The secret recovery phrase and the accounts derived from it.
+    HdKeyTree {
+        /// The secret recovery phrase.
+        mnemonic: Mnemonic,
+        /// Number of accounts created in the extension.
+        number_of_accounts: u32,
+        /// Path of the parent of the accounts.
+        hd_path: DerivationPath,
+    },
+    /// Imported private keys.
+    SimpleKeyPair(Vec<PrivateKey>),
+    /// A keyring holding no secret, such as a hardware wallet, by type.
+    Unsupported(String),
+}
+
+impl Keyring {
+    /// Return the addresses of the accounts of the keyring.
+    pub fn addresses(&self) -> Result<Vec<Address>> {
+        match self {
+            Keyring::HdKeyTree {
+                mnemonic,
+                number_of_accounts,
+                hd_path,
+            } => {
+                let parent =
+                    ExtendedKey::new_master(&mnemonic.to_seed(""))?.derive_path(hd_path)?;
+                (0..*number_of_accounts)
+                    .map(|i| {
+                        let key = parent.derive_child(ChildNumber::normal(i))?;
+                        Address::from_public_key(key.public_key())
+                    })
+                    .collect()
+            }
+            Keyring::SimpleKeyPair(keys) => keys
+                .iter()
+                .map(|key| Address::from_public_key(&key.public_key()))
+                .collect(),
+            Keyring::Unsupported(_) => Ok(Vec::new()),
+        }
+    }
+
+    fn from_json(keyring: &Value) -> Result<Self> {
+        let kind = keyring["type"]
+            .as_str()
+            .ok_or(VaultError::InvalidFormat("keyring type"))?;
+        let data = &keyring["data"];
+
+        match kind {
+            "HD Key Tree" => {
+                // recent versions store the phrase as its UTF-8 bytes
+                let phrase = match &data["mnemonic"] {
+                    Value::String(phrase) => phrase.clone(),
+                    Value::Array(bytes) => String::from_utf8(
+                        bytes
+                            .iter()
+                            .map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()))
+                            .collect::<Option<Vec<u8>>>()
+                            .ok_or(VaultError::InvalidFormat("mnemonic"))?,
+                    )?,
+                    _ => return Err(VaultError::InvalidFormat("mnemonic").into()),
+                };
+
+                Ok(Keyring::HdKeyTree {
+                    mnemonic: Mnemonic::from_phrase(&phrase, Language::English)?,
+                    number_of_accounts: data["numberOfAccounts"].as_u64().unwrap_or(1) as u32,
+                    hd_path: DerivationPath::parse(
+                        data["hdPath"].as_str().unwrap_or("m/44'/60'/0'/0"),
+                    )?,
+                })
+            }
+            "Simple Key Pair" => {
+                let keys = data
+                    .as_array()
+                    .ok_or(VaultError::InvalidFormat("private keys"))?
+                    .iter()
+                    .map(|key| {
+                        let key = key
+                            .as_str()
+                            .ok_or(VaultError::InvalidFormat("private key"))?;
+                        let bytes = hex::decode(key.strip_prefix("0x").unwrap_or(key))?;
+                        PrivateKey::from_bytes(&bytes).map_err(WalletError::crypto)
+                    })
+                    .collect::<Result<_>>()?;
+                Ok(Keyring::SimpleKeyPair(keys))
+            }
+            _ => Ok(Keyring::Unsupported(kind.to_string())),
+        }
+    }
+}
+
+/// An encrypted MetaMask vault.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vault {
+    data: Vec<u8>,
+    iv: Vec<u8>,
+    salt: Vec<u8>,
+    iterations: u32,
+}
+
+impl Vault {
+    /// Parse a vault, either the vault object itself or a dump of the
+    /// extension state holding it under `KeyringController.vault`.
+    pub fn from_json(json: &str) -> Result<Self> {
+        Self::from_value(serde_json::from_str(json)?)
+    }
+
+    /// Find the vault in the raw storage of the extension, such as the
+    /// `.log` and `.ldb` files of the LevelDB database of Chromium based
+    /// browsers, where it is stored as an escaped JSON string among binary
+    /// records. The last vault found is the most recent one.
+    ///
+    /// Blocks compressed by LevelDB are not decompressed, the vault is
+    /// usually also found uncompressed in the log of recent writes.
+    pub fn from_storage(contents: &[u8]) -> Result<Self> {
+        let text = String::from_utf8_lossy(contents);
+        if let Ok(vault) = Self::from_json(&text) {
+            return Ok(vault);
+        }
+
+        let escaped = text.match_indices("\"vault\":").map(|(i, m)| {
+            // the vault is a JSON string holding the vault object
+            let vault = first_value(&text[i + m.len()..])?;
+            Some((i, Self::from_json(vault.as_str()?).ok()?))
+        });
+        let objects = text.match_indices("{\"data\":\"").map(|(i, _)| {
+            let vault = Self::from_value(first_value(&text[i..])?).ok()?;
+            Some((i, vault))
+        });
+
+        escaped
+            .chain(objects)
+            .flatten()
+            .max_by_key(|(i, _)| *i)
+            .map(|(_, vault)| vault)
+            .ok_or_else(|| VaultError::InvalidFormat("no vault found").into())
+    }
+
+    fn from_value(mut value: Value) -> Result<Self> {
+        // recent versions store the state under `data`, next to `meta`
+        if value["data"].is_object() {
+            value = value["data"].take();
+        }
+        if let Some(vault) = value["KeyringController"]["vault"].as_str() {
+            value = serde_json::from_str(vault)?;
+        }
+
+        let field = |name: &'static str| -> Result<Vec<u8>> {
+            let encoded = value[name]
+                .as_str()
+                .ok_or(VaultError::InvalidFormat(name))?;
+            Ok(STANDARD.decode(encoded)?)
+        };
+
+        let iterations = match value.get("keyMetadata") {
+            None => LEGACY_ITERATIONS,
+            Some(metadata) => {
+                let algorithm = metadata["algorithm"].as_str().unwrap_or_default();
+                if algorithm != "PBKDF2" {
+                    return Err(VaultError::UnsupportedAlgorithm(algorithm.to_string()).into());
+                }
+                metadata["params"]["iterations"]
+                    .as_u64()
+                    .and_then(|n| u32::try_from(n).ok())
+                    .filter(|n| (1..=MAX_PBKDF2_ROUNDS).contains(n))
+                    .ok_or(VaultError::InvalidFormat("iterations"))?
+            }
+        };
+
+        let iv = field("iv")?;
+        if iv.len() != 16 {
+            return Err(VaultError::InvalidFormat("iv").into());
+        }
+
+        Ok(Self {
+            data: field("data")?,
+            iv,
+            salt: field("salt")?,
+            iterations,
+        })
+    }
+
+    /// Return the PBKDF2 iterations of the vault.
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+
+    /// Decrypt the vault and return its keyrings.
+    pub fn decrypt(&self, password: &str) -> Result<Vec<Keyring>> {
+        let mut key = [0u8; 32];
+        pbkdf2::pbkdf2::<hmac::Hmac<sha2::Sha256>>(
+            password.as_bytes(),
+            &self.salt,
+            self.iterations,
+            &mut key,
+        );
+
+        let cipher = AesGcm::<Aes256, U16>::new(Key::<Aes256>::from_slice(&key));
+        let plaintext = cipher
+            .decrypt(Nonce::<U16>::from_slice(&self.iv), self.data.as_slice())
+            .map_err(|_| VaultError::IncorrectPassword)?;
+
+        let keyrings: Value = serde_json::from_slice(&plaintext)?;
+        keyrings
+            .as_array()
+            .ok_or(VaultError::InvalidFormat("keyrings"))?
+            .iter()
+            .map(Keyring::from_json)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    const LEGACY_VAULT: &str = r#"{"data":"3VRfqgGrDdeZ8mcUo16gIG7HPPg3riTd/euOVjhQQz7ZRImL1O3g1cTT5kMssHhis++8Y1klAKdmu+0aH/vLh9QBVWQgwdyob/X4sQpG1M+DXZz4BqVPTnKfscBSfPjObb+QxuYHH8gBINlQeVCKwn4exDp1kcRJXMBWrZlORmTiCwpY/OjGahQeEwaqq9orB60YtUDCif1C91CGGp0kDvAt+EHDcvqqOYS5Dtp9jxfN8zBBU5tOPGYbyR6jDMU+e8VBWsgHrWK+b08j8YJWi4k2vSIpjT0f0TGkOKqbTzrUyuoj4eDsNm/ANXhftJCGhKIhc/0r/BBlvVoDhNzujWdqdDm5jOUNkt9aRV6WosR4bked+JnTu+0hEnpC27S3iEF/eojIsufxY5krPOF+a4Wr2qIdyrnS7MMDW4KW9X3yU/avINicuPRKqMNWDjH1m4YONlfkgehgNaWogn3k6Cp0CT2pyZaRE3e72aT8PTDBUsbXzS9058K/kUgHikOq+Y+DXNknqQaE6lTTn5BRSrkZX6dAxs45DGGJB8lXdSQ1gpzXGx9Zhfd6toddLrriUHDITQPmBXiMBgl4GFTY7bQoaNuXrwiDmpoyBzU77n7RAjYAbvDCWXvBYJSYY4Lf5hRpSL1ggCGWVbxy7Qumb5bKtDnPEl5LCzUGZ+wEhztjiHLammBa5r9V7LzB//DyJYN1seJRh7JcEA==","iv":"BwcHBwcHBwcHBwcHBwcHBw==","salt":"d2FsbGV0LXJ1c3QgdGVzdCBzYWx0IDAxMjM0NTY3ODk="}"#;
+
+    const VAULT: &str = r#"{"data":"am9aMT850y6BAGXXlRSvT9Ln4lvBYZISPTMAeeGsI6jpgFXqD4+GANtJ+oSw/lE7ju1dabQJ1UGtUNQa3oV9sGl7GfXWdPQbQQS4TFWG+dDzdFWVkvLG254VUI+2xYd44o1OG4z3QI/YR9lE/+Vx5szDsxv2aN3pVYoT5wjFd7gqN02wWkXeQpTov4C0gCZLbWGGCkMHNC6UDa+H9BFeCMLZWBATgJDk03NDNXsPu+XeFKxzjO1IESBiddGYvsD97KVaDJfxJdVj/p8=","iv":"BwcHBwcHBwcHBwcHBwcHBw==","salt":"d2FsbGV0LXJ1c3QgdGVzdCBzYWx0IDAxMjM0NTY3ODk=","keyMetadata":{"algorithm":"PBKDF2","params":{"iterations":1000}}}"#;
+
+    #[test]
+    fn test_legacy_vault() {
+        let vault = Vault::from_json(LEGACY_VAULT).unwrap();
+        assert_eq!(vault.iterations(), 10_000);
+        assert!(vault.decrypt("wrong").is_err());
+
+        let keyrings = vault.decrypt("correct horse").unwrap();
+        assert_eq!(keyrings.len(), 2);
+
+        match &keyrings[0] {
+            Keyring::HdKeyTree {
+                mnemonic,
+                number_of_accounts,
+                hd_path,
+            } => {
+                assert_eq!(mnemonic.phrase(), PHRASE);
+                assert_eq!(*number_of_accounts, 2);
+                assert_eq!(hd_path.to_string(), "m/44'/60'/0'/0");
+            }
+            _ => panic!("expected an HD keyring"),
+        }
+        let addresses = keyrings[0].addresses().unwrap();
+        assert_eq!(
+            addresses[0].to_string(),
+            "0x9858EfFD232B4033E47d90003D41EC34EcaEda94"
+        );
+        assert_eq!(addresses.len(), 2);
+
+        assert_eq!(
+            keyrings[1].addresses().unwrap()[0].to_string(),
+            "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23"
+        );
+    }
+
+    #[test]
+    fn test_vault() {
+        // as found in a dump of the extension storage
+        let state = serde_json::json!({ "KeyringController": { "vault": VAULT } }).to_string();
+        let vault = Vault::from_json(&state).unwrap();
+        assert_eq!(vault.iterations(), 1000);
+
+        let keyrings = vault.decrypt("correct horse").unwrap();
+        match &keyrings[0] {
+            Keyring::HdKeyTree { mnemonic, .. } => assert_eq!(mnemonic.phrase(), PHRASE),
+            _ => panic!("expected an HD keyring"),
+        }
+    }
+
+    #[test]
+    fn test_storage() {
+        // the state of recent versions, next to its metadata
+        let state = serde_json::json!({
+            "data": { "KeyringController": { "vault": VAULT } },
+            "meta": { "version": 110 },
+        });
+        let vault = Vault::from_json(&state.to_string()).unwrap();
+        assert_eq!(vault.iterations(), 1000);
+
+        // a LevelDB log with an older vault, then the current one
+        let log = |records: &[&str]| {
+            let mut log = b"\x01\x00\x00\x00\xffdata".to_vec();
+            for record in records {
+                log.extend_from_slice(record.as_bytes());
+                log.extend_from_slice(b"\x00\x9a\x8c");
+            }
+            log
+        };
+        let state = state.to_string();
+        assert_eq!(
+            Vault::from_storage(&log(&[LEGACY_VAULT, &state])).unwrap(),
+            vault
+        );
+        assert_eq!(
+            Vault::from_storage(&log(&[&state, LEGACY_VAULT])).unwrap(),
+            Vault::from_json(LEGACY_VAULT).unwrap()
+        );
+
+        assert!(Vault::from_storage(b"\x00\x01 no vault here").is_err());
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(Vault::from_json("{}").is_err());
+        assert!(Vault::from_json(r#"{"data":"AA==","iv":"AA==","salt":"AA=="}"#).is_err());
+
+        let scrypt = VAULT.replace("PBKDF2", "scrypt");
+        assert!(Vault::from_json(&scrypt).is_err());
+    }
+
+    #[test]
+    fn test_iterations_bounds() {
+        let iterations =
+            |n: u64| VAULT.replace("\"iterations\":1000", &format!("\"iterations\":{}", n));
+        let max = MAX_PBKDF2_ROUNDS as u64;
+        assert_eq!(
+            Vault::from_json(&iterations(max)).unwrap().iterations(),
+            MAX_PBKDF2_ROUNDS
+        );
+        for n in [0, max + 1] {
+            assert_eq!(
+                Vault::from_json(&iterations(n)).unwrap_err(),
+                VaultError::InvalidFormat("iterations").into()
+            );
+        }
+    }
+}
@@ -0,0 +1,203 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Decryption of the `wallet.json` files of the 2014 Ethereum presale.
+//!
+//! The `encseed` field holds an IV followed by an AES-128-CBC encrypted seed,
+//! under a key derived from the password with 2000 rounds of
+//! PBKDF2-HMAC-SHA256 salted with the password itself. The private key is the
+//! Keccak-256 hash of the seed, checked against the `ethaddr` field.
+//!
+//! ## Example
+//! ```rust,no_run
+//! use wallet_rust::{import::presale::PresaleWallet, keystore::Kdf};
+//!
+//! let json = std::fs::read_to_string("ethereum_wallet_backup.json").unwrap();
+//! let wallet = PresaleWallet::from_json(&json).unwrap();
+//! let key = wallet.decrypt("password").unwrap();
+//!
+//! // or convert it to a keystore, as `geth wallet import` does
+//! let keystore = wallet.to_keystore("password", Kdf::scrypt()).unwrap();
+//! ```
+
+use aes::{
+    cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit},
+    Aes128,
+};
+use laron_crypto::PrivateKey;
+use serde_json::Value;
+
+use crate::{
+    address::Address,
+    error::{Result, WalletError},
+    keystore::{Kdf, Keystore},
+    utils::keccak256,
+};
+
+/// Rounds of the key derivation.
+const ITERATIONS: u32 = 2000;
+
+/// Error returned when reading a presale wallet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PresaleError {
+    /// The JSON is not a presale wallet, the missing or malformed field is
+    /// given.
+    InvalidFormat(&'static str),
+    /// The password does not decrypt the wallet.
+    IncorrectPassword,
+}
+
+impl std::fmt::Display for PresaleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PresaleError::InvalidFormat(field) => write!(f, "Invalid presale wallet: {}", field),
+            PresaleError::IncorrectPassword => write!(f, "Incorrect presale wallet password"),
+        }
+    }
+}
+
+impl std::error::Error for PresaleError {}
+
+/// An encrypted presale wallet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresaleWallet {
+    iv: [u8; 16],
+    ciphertext: Vec<u8>,
+    address: Address,
+    email: Option<String>,
+}
+
+impl PresaleWallet {
+    /// Parse the JSON of a presale wallet.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(json)?;
+
+        let encseed = value["encseed"]
+            .as_str()
+            .ok_or(PresaleError::InvalidFormat("encseed"))?;
+        let encseed = hex::decode(encseed)?;
+        if encseed.len() < 32 || encseed.len() % 16 != 0 {
+            return Err(PresaleError::InvalidFormat("encseed").into());
+        }
+        let (iv, ciphertext) = encseed.split_at(16);
+
+        let address = value["ethaddr"]
+            .as_str()
+            .ok_or(PresaleError::InvalidFormat("ethaddr"))?
+            .parse()?;
+
+        Ok(Self {
+            iv: iv.try_into().expect("16 bytes"),
+            ciphertext: ciphertext.to_vec(),
+            address,
+            email: value["email"].as_str().map(str::to_string),
+        })
+    }
+
+    /// Return the address of the wallet.
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Return the email the wallet was registered with.
+    pub fn email(&self) -> Option<&str> {
+        self.email.as_deref()
+    }
+
+    /// Decrypt the private key of the wallet.
+    pub fn decrypt(&self, password: &str) -> Result<PrivateKey> {
+        let mut key = [0u8; 16];
+        pbkdf2::pbkdf2::<hmac::Hmac<sha2::Sha256>>(
+            password.as_bytes(),
+            password.as_bytes(),
+            ITERATIONS,
+            &mut key,
+        );
+
+        let seed = cbc::Decryptor::<Aes128>::new(&key.into(), &self.iv.into())
+            .decrypt_padded_vec_mut::<Pkcs7>(&self.ciphertext)
+            .map_err(|_| PresaleError::IncorrectPassword)?;
+
+        // a wrong password yields a valid padding once in a while
+        let private_key = PrivateKey::from_bytes(&keccak256(seed)).map_err(WalletError::crypto)?;
+        if Address::from_public_key(&private_key.public_key())? != self.address {
+            return Err(PresaleError::IncorrectPassword.into());
+        }
+
+        Ok(private_key)
+    }
+
+    /// Decrypt the wallet and encrypt its key into a version 3 keystore
+    /// under the same password.
+    pub fn to_keystore(&self, password: &str, kdf: Kdf) -> Result<Keystore> {
+        Keystore::encrypt(&self.decrypt(password)?, password, kdf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WALLET: &str = r#"{
+        "encseed": "42424242424242424242424242424242c37d26b95ef8f92b846f68357bfbe3362310d99dec63f74efe43d82e7769914301470f061353615ed5eda68ebf63ae923ff8678ca31749190ee237b523056e06367fc5f24c76c8779129e7cf83f0229c",
+        "ethaddr": "12daac25bc777321f4aa2455b146220a1bab911d",
+        "email": "holder@example.com",
+        "btcaddr": "1EVknXyFC68kKNLkh6YnKzW41svSRoaAcx"
+    }"#;
+
+    #[test]
+    fn test_decrypt() {
+        let wallet = PresaleWallet::from_json(WALLET).unwrap();
+        assert_eq!(wallet.email(), Some("holder@example.com"));
+
+        let key = wallet.decrypt("foobar").unwrap();
+        assert_eq!(
+            Address::from_public_key(&key.public_key()).unwrap(),
+            wallet.address()
+        );
+        assert!(wallet.decrypt("foobaz").is_err());
+    }
+
+    // cmd/geth/testdata/guswallet.json of go-ethereum
+    const GETH_WALLET: &str = r#"{
+        "encseed": "26d87f5f2bf9835f9a47eefae571bc09f9107bb13d54ff12a4ec095d01f83897494cf34f7bed2ed34126ecba9db7b62de56c9d7cd136520a0427bfb11b8954ba7ac39b90d4650d3448e31185affcd74226a68f1e94b1108e6e0a4a91cdd83eba",
+        "ethaddr": "d4584b5f6229b7be90727b0fc8c6b91bb427821f",
+        "email": "gustav.simonsson@gmail.com",
+        "btcaddr": "1EVknXyFC68kKNLkh6YnKzW41svSRoaAcx"
+    }"#;
+
+    #[test]
+    fn test_geth_wallet() {
+        let wallet = PresaleWallet::from_json(GETH_WALLET).unwrap();
+        let kdf = Kdf::Scrypt {
+            n: 1024,
+            r: 8,
+            p: 1,
+            dklen: 32,
+            salt: vec![1; 32],
+        };
+        let keystore = wallet.to_keystore("foo", kdf.clone()).unwrap();
+        assert_eq!(keystore.address(), Some(wallet.address()));
+        assert_eq!(
+            keystore.decrypt("foo").unwrap().to_bytes(),
+            wallet.decrypt("foo").unwrap().to_bytes()
+        );
+        assert!(wallet.to_keystore("bar", kdf).is_err());
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(PresaleWallet::from_json("{}").is_err());
+        assert!(PresaleWallet::from_json(r#"{"encseed":"00","ethaddr":"00"}"#).is_err());
+    }
+}
@@ -0,0 +1,267 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use laron_crypto::PrivateKey;
+
+use super::{Kdf, Keystore, KeystoreError};
+use crate::{address::Address, error::Result};
+
+/// A directory of keystore files, laid out like the `keystore` directory
+/// of geth.
+///
+/// Files are named `UTC--<creation time>--<address>` and written with
+/// owner-only permissions. Hidden files, backups ending with `~`, the
+/// `README` and anything that is not a keystore with an address are
+/// ignored when listing, as geth does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeystoreDir {
+    path: PathBuf,
+}
+
+impl KeystoreDir {
+    /// Manage the keystore directory at `path`, created with the first
+    /// key stored in it.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Return the path of the directory.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// List the accounts with the path of their file, sorted by path, so
+    /// by creation time.
+    pub fn accounts(&self) -> Result<Vec<(Address, PathBuf)>> {
+        let entries = match fs::read_dir(&self.path) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut accounts = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name.starts_with('.') || name.ends_with('~') || name == "README" {
+                continue;
+            }
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+
+            let address = fs::read_to_string(entry.path())
+                .ok()
+                .and_then(|json| Keystore::from_json_lenient(&json).ok())
+                .and_then(|keystore| keystore.address());
+            if let Some(address) = address {
+                accounts.push((address, entry.path()));
+            }
+        }
+
+        accounts.sort_by(|a, b| a.1.cmp(&b.1));
+        Ok(accounts)
+    }
+
+    /// Return the path of the file of `address`.
+    pub fn find(&self, address: &Address) -> Result<Option<PathBuf>> {
+        Ok(self
+            .accounts()?
+            .into_iter()
+            .find(|(account, _)| account == address)
+            .map(|(_, path)| path))
+    }
+
+    /// Read the keystore of `address`.
+    pub fn load(&self, address: &Address) -> Result<Keystore> {
+        let path = self
+            .find(address)?
+            .ok_or(KeystoreError::UnknownAccount(*address))?;
+        Keystore::from_json_lenient(&fs::read_to_string(path)?)
+    }
+
+    /// Write a keystore into the directory, returning the path of its file.
+    /// An account is stored only once.
+    pub fn store(&self, keystore: &Keystore) -> Result<PathBuf> {
+        let address = keystore.address().ok_or(KeystoreError::MissingAddress)?;
+        if self.find(&address)?.is_some() {
+            return Err(KeystoreError::AccountExists(address).into());
+        }
+
+        let name = file_name(&address, SystemTime::now());
+        let path = self.path.join(&name);
+        let temporary = self.path.join(format!(".{}.tmp", name));
+        create_dir(&self.path)?;
+        write_private(&temporary, keystore.to_json()?.as_bytes())?;
+        fs::rename(&temporary, &path)?;
+        Ok(path)
+    }
+
+    /// Encrypt a private key into a new file, returning its address and
+    /// path.
+    pub fn create(&self, key: &PrivateKey, password: &str, kdf: Kdf) -> Result<(Address, PathBuf)> {
+        let keystore = Keystore::encrypt(key, password, kdf)?;
+        let path = self.store(&keystore)?;
+        Ok((
+            keystore.address().ok_or(KeystoreError::MissingAddress)?,
+            path,
+        ))
+    }
+
+    /// Delete the file of `address`.
+    pub fn delete(&self, address: &Address) -> Result<()> {
+        let path = self
+            .find(address)?
+            .ok_or(KeystoreError::UnknownAccount(*address))?;
+        fs::remove_file(path)?;
+        Ok(())
+    }
+}
+
+/// Return the name geth gives to the keystore file of `address` created at
+/// `time`, e.g.
+/// `UTC--2016-03-22T12-57-55.920751759Z--7ef5a6135f1fd6a02593eedc869c6d41d934aef8`.
+pub fn file_name(address: &Address, time: SystemTime) -> String {
+    let time = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = time.as_secs();
+    let (year, month, day) = civil_date(seconds / 86400);
+
+    format!(
+        "UTC--{:04}-{:02}-{:02}T{:02}-{:02}-{:02}.{:09}Z--{}",
+        year,
+        month,
+        day,
+        seconds / 3600 % 24,
+        seconds / 60 % 60,
+        seconds % 60,
+        time.subsec_nanos(),
+        hex::encode(address.as_bytes())
+    )
+}
+
+/// Convert days since the Unix epoch to a date of the proleptic Gregorian
+/// calendar.
+fn civil_date(days: u64) -> (u64, u64, u64) {
+    // shift the epoch to 0000-03-01, so leap days end the 400 year eras
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    (year, month, day)
+}
+
+fn create_dir(path: &Path) -> std::io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(path)
+}
+
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn kdf() -> Kdf {
+        Kdf::Scrypt {
+            n: 1024,
+            r: 8,
+            p: 1,
+            dklen: 32,
+            salt: vec![1; 32],
+        }
+    }
+
+    #[test]
+    fn test_file_name() {
+        let address: Address = "0x7ef5a6135f1fd6a02593eedc869c6d41d934aef8"
+            .parse()
+            .unwrap();
+        let time = UNIX_EPOCH + Duration::new(1_458_651_475, 920_751_759);
+        assert_eq!(
+            file_name(&address, time),
+            "UTC--2016-03-22T12-57-55.920751759Z--7ef5a6135f1fd6a02593eedc869c6d41d934aef8"
+        );
+
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(11_016), (2000, 2, 29));
+        assert_eq!(civil_date(11_017), (2000, 3, 1));
+        assert_eq!(civil_date(47_541), (2100, 3, 1));
+    }
+
+    #[test]
+    fn test_keystore_dir() {
+        let path = std::env::temp_dir().join(format!("keystore-{}", std::process::id()));
+        let dir = KeystoreDir::new(&path);
+        assert!(dir.accounts().unwrap().is_empty());
+
+        let key = PrivateKey::from_bytes(&[1; 32]).unwrap();
+        let (address, file) = dir.create(&key, "password", kdf()).unwrap();
+        let name = file.file_name().unwrap().to_string_lossy().into_owned();
+        assert!(name.starts_with("UTC--"));
+        assert!(name.ends_with(&format!("Z--{}", hex::encode(address.as_bytes()))));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&file).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // geth skips these
+        fs::write(path.join("README"), "keys").unwrap();
+        fs::write(path.join(format!("{}~", name)), fs::read(&file).unwrap()).unwrap();
+        fs::create_dir(path.join("backup")).unwrap();
+        assert_eq!(dir.accounts().unwrap(), vec![(address, file.clone())]);
+
+        let keystore = dir.load(&address).unwrap();
+        assert_eq!(
+            keystore.decrypt("password").unwrap().to_bytes(),
+            key.to_bytes()
+        );
+        let error = dir.store(&keystore).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("Keystore for {} already exists", address)
+        );
+
+        dir.delete(&address).unwrap();
+        assert!(dir.find(&address).unwrap().is_none());
+        assert!(dir.load(&address).is_err());
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
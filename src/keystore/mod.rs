@@ -0,0 +1,786 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Keystore
+//!
+//! Password encrypted key files in the [Web3 Secret Storage](https://ethereum.org/en/developers/docs/data-structures-and-encoding/web3-secret-storage/)
+//! format, the `UTC--...` files of geth, MyEtherWallet and most wallets.
+//!
+//! [`Keystore::from_json`] accepts version 3 files as written by geth.
+//! [`Keystore::from_json_lenient`] also accepts the variants found in the
+//! wild: capitalized field names, uppercase or `0x` prefixed hex, numbers
+//! written as strings, missing `id`, `version` or `address`, other spellings
+//! of the PBKDF2 PRF, and the version 1 files of early geth releases.
+//!
+//! [`Keystore::encrypt`] creates version 3 files, written out with
+//! [`Keystore::to_json`].
+//!
+//! A [`KeystoreDir`] manages a directory of these files named like geth
+//! names them, so the accounts are shared with `geth account` and Clef.
+//!
+//! ## Example
+//! ```rust,no_run
+//! use wallet_rust::keystore::{Kdf, Keystore};
+//!
+//! let json = std::fs::read_to_string("UTC--2016-03-22T12-57-55.920751759Z--7ef5a6135f1fd6a02593eedc869c6d41d934aef8").unwrap();
+//! let keystore = Keystore::from_json_lenient(&json).unwrap();
+//! let key = keystore.decrypt("password").unwrap();
+//!
+//! let keystore = Keystore::encrypt(&key, "new password", Kdf::scrypt()).unwrap();
+//! std::fs::write("keystore.json", keystore.to_json().unwrap()).unwrap();
+//! ```
+
+use aes::{
+    cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit, StreamCipher},
+    Aes128,
+};
+use laron_crypto::PrivateKey;
+use rand::Rng;
+use serde_json::{json, Map, Value};
+use zeroize::Zeroizing;
+
+use crate::{
+    address::Address,
+    error::{Result, WalletError},
+    utils::keccak256,
+};
+
+mod dir;
+
+pub use dir::*;
+
+/// Error returned when reading a keystore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeystoreError {
+    /// The JSON is not a keystore, the missing or malformed field is given.
+    InvalidFormat(&'static str),
+    /// The version of the file is not supported.
+    UnsupportedVersion(String),
+    /// The cipher is not supported.
+    UnsupportedCipher(String),
+    /// The key derivation function or its PRF is not supported.
+    UnsupportedKdf(String),
+    /// The MAC does not match, the password is most likely wrong.
+    InvalidMac,
+    /// The decrypted key does not belong to the address of the file.
+    AddressMismatch,
+    /// The keystore does not store the address its file is named after.
+    MissingAddress,
+    /// A keystore directory already holds a key for the address.
+    AccountExists(Address),
+    /// A keystore directory holds no key for the address.
+    UnknownAccount(Address),
+}
+
+impl std::fmt::Display for KeystoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            KeystoreError::InvalidFormat(field) => write!(f, "Invalid keystore: {}", field),
+            KeystoreError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported keystore version: {}", version)
+            }
+            KeystoreError::UnsupportedCipher(cipher) => {
+                write!(f, "Unsupported keystore cipher: {}", cipher)
+            }
+            KeystoreError::UnsupportedKdf(kdf) => write!(f, "Unsupported keystore KDF: {}", kdf),
+            KeystoreError::InvalidMac => write!(f, "Invalid keystore MAC"),
+            KeystoreError::AddressMismatch => write!(f, "Keystore address mismatch"),
+            KeystoreError::MissingAddress => write!(f, "Keystore without address"),
+            KeystoreError::AccountExists(address) => {
+                write!(f, "Keystore for {} already exists", address)
+            }
+            KeystoreError::UnknownAccount(address) => write!(f, "No keystore for {}", address),
+        }
+    }
+}
+
+impl std::error::Error for KeystoreError {}
+
+/// Largest scrypt memory accepted, `128 * n * r` bytes: 1 GiB, four times
+/// that of the standard parameters of geth.
+pub const MAX_SCRYPT_MEMORY: u64 = 1 << 30;
+/// Largest scrypt parallelism accepted.
+pub const MAX_SCRYPT_P: u32 = 16;
+/// Largest number of PBKDF2 iterations accepted.
+pub const MAX_PBKDF2_ROUNDS: u32 = 10_000_000;
+/// Largest derived key accepted, only its first 32 bytes are used.
+pub const MAX_DKLEN: usize = 64;
+
+/// The key derivation function of a keystore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Kdf {
+    /// scrypt with a cost of `n`, a power of two.
+    Scrypt {
+        n: u64,
+        r: u32,
+        p: u32,
+        dklen: usize,
+        salt: Vec<u8>,
+    },
+    /// PBKDF2-HMAC-SHA256 with `c` iterations.
+    Pbkdf2 { c: u32, dklen: usize, salt: Vec<u8> },
+}
+
+impl Kdf {
+    /// scrypt with the standard parameters of geth, `n = 2^18`, `r = 8` and
+    /// `p = 1`, and a random salt.
+    pub fn scrypt() -> Self {
+        Kdf::Scrypt {
+            n: 1 << 18,
+            r: 8,
+            p: 1,
+            dklen: 32,
+            salt: rand::thread_rng().gen::<[u8; 32]>().to_vec(),
+        }
+    }
+
+    /// PBKDF2 with 262144 iterations, the parameters of geth, and a random
+    /// salt.
+    pub fn pbkdf2() -> Self {
+        Kdf::Pbkdf2 {
+            c: 262_144,
+            dklen: 32,
+            salt: rand::thread_rng().gen::<[u8; 32]>().to_vec(),
+        }
+    }
+
+    pub(crate) fn to_json(&self) -> (&'static str, Value) {
+        match self {
+            Kdf::Scrypt {
+                n,
+                r,
+                p,
+                dklen,
+                salt,
+            } => (
+                "scrypt",
+                json!({ "dklen": dklen, "n": n, "p": p, "r": r, "salt": hex::encode(salt) }),
+            ),
+            Kdf::Pbkdf2 { c, dklen, salt } => (
+                "pbkdf2",
+                json!({ "c": c, "dklen": dklen, "prf": "hmac-sha256", "salt": hex::encode(salt) }),
+            ),
+        }
+    }
+
+    /// Check that the derived key is 32 to [`MAX_DKLEN`] bytes, `n` a
+    /// power of two, and the costs at most [`MAX_SCRYPT_MEMORY`],
+    /// [`MAX_SCRYPT_P`] and [`MAX_PBKDF2_ROUNDS`], so that parameters read
+    /// from untrusted files cannot exhaust the machine.
+    fn validate(&self) -> Result<()> {
+        let dklen = match self {
+            Kdf::Scrypt { n, r, p, dklen, .. } => {
+                if !n.is_power_of_two() {
+                    return Err(KeystoreError::InvalidFormat("n").into());
+                }
+                let memory = n
+                    .checked_mul(u64::from(*r))
+                    .and_then(|memory| memory.checked_mul(128));
+                if memory.is_none_or(|memory| memory > MAX_SCRYPT_MEMORY) {
+                    return Err(KeystoreError::InvalidFormat("kdfparams").into());
+                }
+                if *p > MAX_SCRYPT_P {
+                    return Err(KeystoreError::InvalidFormat("p").into());
+                }
+                dklen
+            }
+            Kdf::Pbkdf2 { c, dklen, .. } => {
+                if !(1..=MAX_PBKDF2_ROUNDS).contains(c) {
+                    return Err(KeystoreError::InvalidFormat("c").into());
+                }
+                dklen
+            }
+        };
+        if !(32..=MAX_DKLEN).contains(dklen) {
+            return Err(KeystoreError::InvalidFormat("dklen").into());
+        }
+        Ok(())
+    }
+
+    /// Derive the key protecting the keystore from the password.
+    pub(crate) fn derive(&self, password: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+        self.validate()?;
+        match self {
+            Kdf::Scrypt {
+                n,
+                r,
+                p,
+                dklen,
+                salt,
+            } => {
+                let params = scrypt::Params::new(n.trailing_zeros() as u8, *r, *p, *dklen)
+                    .map_err(|_| KeystoreError::InvalidFormat("kdfparams"))?;
+
+                let mut key = Zeroizing::new(vec![0u8; *dklen]);
+                scrypt::scrypt(password, salt, &params, &mut key)
+                    .map_err(|_| KeystoreError::InvalidFormat("dklen"))?;
+                Ok(key)
+            }
+            Kdf::Pbkdf2 { c, dklen, salt } => {
+                let mut key = Zeroizing::new(vec![0u8; *dklen]);
+                pbkdf2::pbkdf2::<hmac::Hmac<sha2::Sha256>>(password, salt, *c, &mut key);
+                Ok(key)
+            }
+        }
+    }
+}
+
+/// An encrypted key file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keystore {
+    version: u8,
+    id: Option<String>,
+    address: Option<Address>,
+    iv: Vec<u8>,
+    ciphertext: Vec<u8>,
+    kdf: Kdf,
+    mac: [u8; 32],
+}
+
+impl Keystore {
+    /// Encrypt a private key into a version 3 keystore with a random IV and
+    /// id.
+    pub fn encrypt(private_key: &PrivateKey, password: &str, kdf: Kdf) -> Result<Self> {
+        let mut rng = rand::thread_rng();
+        let key = kdf.derive(password.as_bytes())?;
+        let iv = rng.gen::<[u8; 16]>().to_vec();
+
+        let mut ciphertext = Zeroizing::new(private_key.to_bytes()).to_vec();
+        ctr::Ctr128BE::<Aes128>::new_from_slices(&key[..16], &iv)
+            .map_err(|_| KeystoreError::InvalidFormat("iv"))?
+            .apply_keystream(&mut ciphertext);
+
+        let mut data = Zeroizing::new(key[16..32].to_vec());
+        data.extend_from_slice(&ciphertext);
+
+        Ok(Keystore {
+            version: 3,
+            id: Some(random_uuid()),
+            address: Some(Address::from_public_key(&private_key.public_key())?),
+            iv,
+            ciphertext,
+            kdf,
+            mac: keccak256(&*data),
+        })
+    }
+
+    /// Serialize a version 3 keystore to the JSON written by geth.
+    pub fn to_json(&self) -> Result<String> {
+        if self.version != 3 {
+            return Err(KeystoreError::UnsupportedVersion(self.version.to_string()).into());
+        }
+
+        let (kdf, kdfparams) = self.kdf.to_json();
+        let mut root = json!({
+            "crypto": {
+                "cipher": "aes-128-ctr",
+                "cipherparams": { "iv": hex::encode(&self.iv) },
+                "ciphertext": hex::encode(&self.ciphertext),
+                "kdf": kdf,
+                "kdfparams": kdfparams,
+                "mac": hex::encode(self.mac),
+            },
+            "id": self.id,
+            "version": 3,
+        });
+        if let Some(address) = self.address {
+            root["address"] = hex::encode(address.as_bytes()).into();
+        }
+        Ok(root.to_string())
+    }
+
+    /// Parse a version 3 keystore, rejecting anything that deviates from
+    /// the format written by geth. KDF costs above [`MAX_SCRYPT_MEMORY`],
+    /// [`MAX_SCRYPT_P`] or [`MAX_PBKDF2_ROUNDS`] are refused.
+    pub fn from_json(json: &str) -> Result<Self> {
+        Parser { lenient: false }.parse(json)
+    }
+
+    /// Parse a keystore, accepting the non-standard variants written by
+    /// older or third party wallets.
+    pub fn from_json_lenient(json: &str) -> Result<Self> {
+        Parser { lenient: true }.parse(json)
+    }
+
+    /// Return the version of the file format.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Return the UUID of the keystore.
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Return the address stored in the clear in the file.
+    pub fn address(&self) -> Option<Address> {
+        self.address
+    }
+
+    /// Return the key derivation function.
+    pub fn kdf(&self) -> &Kdf {
+        &self.kdf
+    }
+
+    /// Decrypt the private key. The MAC is checked before decrypting, and
+    /// the key against the address of the file when there is one.
+    pub fn decrypt(&self, password: &str) -> Result<PrivateKey> {
+        let key = self.kdf.derive(password.as_bytes())?;
+
+        let mut data = Zeroizing::new(key[16..32].to_vec());
+        data.extend_from_slice(&self.ciphertext);
+        if keccak256(&*data) != self.mac {
+            return Err(KeystoreError::InvalidMac.into());
+        }
+
+        let plaintext = Zeroizing::new(if self.version == 1 {
+            // version 1 encrypts with a hash of the key, in CBC mode
+            let key = Zeroizing::new(keccak256(&key[..16]));
+            cbc::Decryptor::<Aes128>::new_from_slices(&key[..16], &self.iv)
+                .map_err(|_| KeystoreError::InvalidFormat("iv"))?
+                .decrypt_padded_vec_mut::<Pkcs7>(&self.ciphertext)
+                .map_err(|_| KeystoreError::InvalidFormat("ciphertext"))?
+        } else {
+            let mut plaintext = self.ciphertext.clone();
+            ctr::Ctr128BE::<Aes128>::new_from_slices(&key[..16], &self.iv)
+                .map_err(|_| KeystoreError::InvalidFormat("iv"))?
+                .apply_keystream(&mut plaintext);
+            plaintext
+        });
+
+        let private_key = PrivateKey::from_bytes(&plaintext).map_err(WalletError::crypto)?;
+        if let Some(address) = self.address {
+            if Address::from_public_key(&private_key.public_key())? != address {
+                return Err(KeystoreError::AddressMismatch.into());
+            }
+        }
+
+        Ok(private_key)
+    }
+}
+
+/// Return a random UUID, version 4.
+pub(crate) fn random_uuid() -> String {
+    let mut id: [u8; 16] = rand::thread_rng().gen();
+    id[6] = (id[6] & 0x0f) | 0x40;
+    id[8] = (id[8] & 0x3f) | 0x80;
+    let id = hex::encode(id);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &id[..8],
+        &id[8..12],
+        &id[12..16],
+        &id[16..20],
+        &id[20..]
+    )
+}
+
+/// Field access following the strict or lenient rules.
+struct Parser {
+    lenient: bool,
+}
+
+impl Parser {
+    fn parse(&self, json: &str) -> Result<Keystore> {
+        let value: Value = serde_json::from_str(json)?;
+        let root = value
+            .as_object()
+            .ok_or(KeystoreError::InvalidFormat("root"))?;
+
+        let version = match self.get(root, "version") {
+            Some(version) => self.number(version, "version")?,
+            None if self.lenient => 3,
+            None => return Err(KeystoreError::InvalidFormat("version").into()),
+        };
+        let version = match version {
+            3 => 3,
+            1 | 2 if self.lenient => version as u8,
+            _ => return Err(KeystoreError::UnsupportedVersion(version.to_string()).into()),
+        };
+
+        let id = match self.get(root, "id") {
+            Some(id) => Some(
+                id.as_str()
+                    .ok_or(KeystoreError::InvalidFormat("id"))?
+                    .to_string(),
+            ),
+            None if self.lenient => None,
+            None => return Err(KeystoreError::InvalidFormat("id").into()),
+        };
+
+        // the address is optional in the specification
+        let address = match self.get(root, "address") {
+            Some(address) => Some(Address::from_slice(&self.hex(address, "address")?)?),
+            None => None,
+        };
+
+        let crypto = self.object(root, "crypto")?;
+        let ciphertext = self.hex(self.field(crypto, "ciphertext")?, "ciphertext")?;
+        let mac = self
+            .hex(self.field(crypto, "mac")?, "mac")?
+            .try_into()
+            .map_err(|_| KeystoreError::InvalidFormat("mac"))?;
+
+        let (iv, kdf) = if version == 1 {
+            self.parse_v1(crypto)?
+        } else {
+            self.parse_v3(crypto)?
+        };
+
+        Ok(Keystore {
+            version,
+            id,
+            address,
+            iv,
+            ciphertext,
+            kdf,
+            mac,
+        })
+    }
+
+    fn parse_v3(&self, crypto: &Map<String, Value>) -> Result<(Vec<u8>, Kdf)> {
+        let cipher = self.string(crypto, "cipher")?;
+        if cipher != "aes-128-ctr" {
+            return Err(KeystoreError::UnsupportedCipher(cipher.to_string()).into());
+        }
+        let iv = self.hex(
+            self.field(self.object(crypto, "cipherparams")?, "iv")?,
+            "iv",
+        )?;
+
+        let kdf = self.string(crypto, "kdf")?;
+        let params = self.object(crypto, "kdfparams")?;
+        let salt = self.hex(self.field(params, "salt")?, "salt")?;
+        let dklen = self.integer(params, "dklen")?;
+
+        let kdf = match kdf {
+            "scrypt" => Kdf::Scrypt {
+                n: self.number(self.field(params, "n")?, "n")?,
+                r: self.integer(params, "r")?,
+                p: self.integer(params, "p")?,
+                dklen,
+                salt,
+            },
+            "pbkdf2" => {
+                let prf = self.string(params, "prf")?;
+                let known = if self.lenient {
+                    matches!(
+                        prf.to_ascii_lowercase().replace(['-', '_'], "").as_str(),
+                        "hmacsha256" | "sha256"
+                    )
+                } else {
+                    prf == "hmac-sha256"
+                };
+                if !known {
+                    return Err(KeystoreError::UnsupportedKdf(prf.to_string()).into());
+                }
+
+                Kdf::Pbkdf2 {
+                    c: self.integer(params, "c")?,
+                    dklen,
+                    salt,
+                }
+            }
+            _ => return Err(KeystoreError::UnsupportedKdf(kdf.to_string()).into()),
+        };
+        kdf.validate()?;
+
+        Ok((iv, kdf))
+    }
+
+    /// Version 1 files keep the salt next to the ciphertext and only use
+    /// scrypt.
+    fn parse_v1(&self, crypto: &Map<String, Value>) -> Result<(Vec<u8>, Kdf)> {
+        let header = self.object(crypto, "keyheader")?;
+        let kdf = self.string(header, "kdf")?;
+        if kdf != "scrypt" {
+            return Err(KeystoreError::UnsupportedKdf(kdf.to_string()).into());
+        }
+        let params = self.object(header, "kdfparams")?;
+
+        let kdf = Kdf::Scrypt {
+            n: self.number(self.field(params, "n")?, "n")?,
+            r: self.integer(params, "r")?,
+            p: self.integer(params, "p")?,
+            dklen: self.integer(params, "dklen")?,
+            salt: self.hex(self.field(crypto, "salt")?, "salt")?,
+        };
+        kdf.validate()?;
+
+        Ok((self.hex(self.field(crypto, "iv")?, "iv")?, kdf))
+    }
+
+    /// Look up a field, ignoring the case of its name in lenient mode.
+    fn get<'a>(&self, object: &'a Map<String, Value>, name: &str) -> Option<&'a Value> {
+        object.get(name).or_else(|| {
+            self.lenient
+                .then(|| {
+                    object
+                        .iter()
+                        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                        .map(|(_, value)| value)
+                })
+                .flatten()
+        })
+    }
+
+    fn field<'a>(&self, object: &'a Map<String, Value>, name: &'static str) -> Result<&'a Value> {
+        Ok(self
+            .get(object, name)
+            .ok_or(KeystoreError::InvalidFormat(name))?)
+    }
+
+    fn object<'a>(
+        &self,
+        object: &'a Map<String, Value>,
+        name: &'static str,
+    ) -> Result<&'a Map<String, Value>> {
+        Ok(self
+            .field(object, name)?
+            .as_object()
+            .ok_or(KeystoreError::InvalidFormat(name))?)
+    }
+
+    fn string<'a>(&self, object: &'a Map<String, Value>, name: &'static str) -> Result<&'a str> {
+        Ok(self
+            .field(object, name)?
+            .as_str()
+            .ok_or(KeystoreError::InvalidFormat(name))?)
+    }
+
+    fn number(&self, value: &Value, name: &'static str) -> Result<u64> {
+        let number = match value {
+            Value::Number(n) => n.as_u64(),
+            Value::String(s) if self.lenient => s.parse().ok(),
+            _ => None,
+        };
+        Ok(number.ok_or(KeystoreError::InvalidFormat(name))?)
+    }
+
+    /// Read a number field that must fit in `T`.
+    fn integer<T: TryFrom<u64>>(
+        &self,
+        object: &Map<String, Value>,
+        name: &'static str,
+    ) -> Result<T> {
+        let number = self.number(self.field(object, name)?, name)?;
+        Ok(T::try_from(number).map_err(|_| KeystoreError::InvalidFormat(name))?)
+    }
+
+    fn hex(&self, value: &Value, name: &'static str) -> Result<Vec<u8>> {
+        let s = value.as_str().ok_or(KeystoreError::InvalidFormat(name))?;
+        let s = match self.lenient {
+            true => s.strip_prefix("0x").unwrap_or(s),
+            false if s.bytes().any(|b| b.is_ascii_uppercase()) => {
+                return Err(KeystoreError::InvalidFormat(name).into())
+            }
+            false => s,
+        };
+        Ok(hex::decode(s).map_err(|_| KeystoreError::InvalidFormat(name))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test vectors of the Web3 Secret Storage definition
+    const PBKDF2: &str = r#"{
+        "crypto": {
+            "cipher": "aes-128-ctr",
+            "cipherparams": { "iv": "6087dab2f9fdbbfaddc31a909735c1e6" },
+            "ciphertext": "5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46",
+            "kdf": "pbkdf2",
+            "kdfparams": {
+                "c": 262144,
+                "dklen": 32,
+                "prf": "hmac-sha256",
+                "salt": "ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd"
+            },
+            "mac": "517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2"
+        },
+        "id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+        "version": 3
+    }"#;
+
+    // scrypt with the light parameters of geth, the r = 1 vector of the
+    // definition is rejected by RFC 7914 implementations
+    const SCRYPT: &str = r#"{
+        "address": "008aeeda4d805471df9b2a5b0f38a0c3bcba786b",
+        "crypto": {
+            "cipher": "aes-128-ctr",
+            "cipherparams": { "iv": "0c8e2f4a6b1d3c5e7f9a0b2c4d6e8f10" },
+            "ciphertext": "ba30613c54f8edd36d2456d451c282d1dd56ea14e5aa9978308186f75bb682ec",
+            "kdf": "scrypt",
+            "kdfparams": {
+                "dklen": 32,
+                "n": 4096,
+                "p": 6,
+                "r": 8,
+                "salt": "4e0e2a6d9c2e3f6f8b8a7d1b1c0f5e6a9d3b2c1a0f9e8d7c6b5a493827161504"
+            },
+            "mac": "c0de44d1c40309f0248140d880cc0ed7e707ef36f646c18d9467c70e2af66442"
+        },
+        "id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+        "version": 3
+    }"#;
+
+    // the layout of early geth releases
+    const V1: &str = r#"{
+        "Address": "008aeeda4d805471df9b2a5b0f38a0c3bcba786b",
+        "Crypto": {
+            "CipherText": "1a256e4a3c66935c493248279cadc80ada37ed4d5a3c8a29a976f72cbc8899207b0241e60be72d54917379f1d468706a",
+            "IV": "0c8e2f4a6b1d3c5e7f9a0b2c4d6e8f10",
+            "KeyHeader": {
+                "Kdf": "scrypt",
+                "KdfParams": { "DkLen": 32, "N": 4096, "P": 6, "R": 8, "SaltLen": 32 },
+                "Version": "1"
+            },
+            "MAC": "94797561acb6a8efbdeefe4c10197383047a956f6ec958434301c8749409f695",
+            "Salt": "4e0e2a6d9c2e3f6f8b8a7d1b1c0f5e6a9d3b2c1a0f9e8d7c6b5a493827161504"
+        },
+        "Id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+        "Version": "1"
+    }"#;
+
+    const PRIVATE_KEY: &str = "7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d";
+
+    #[test]
+    fn test_pbkdf2() {
+        let keystore = Keystore::from_json(PBKDF2).unwrap();
+        assert_eq!(keystore.id(), Some("3198bc9c-6672-5ab3-d995-4942343ae5b6"));
+        assert_eq!(keystore.address(), None);
+
+        let key = keystore.decrypt("testpassword").unwrap();
+        assert_eq!(hex::encode(key.to_bytes()), PRIVATE_KEY);
+        assert!(keystore.decrypt("wrong").is_err());
+    }
+
+    #[test]
+    fn test_scrypt() {
+        let keystore = Keystore::from_json(SCRYPT).unwrap();
+        let key = keystore.decrypt("testpassword").unwrap();
+        assert_eq!(hex::encode(key.to_bytes()), PRIVATE_KEY);
+    }
+
+    #[test]
+    fn test_v1() {
+        assert!(Keystore::from_json(V1).is_err());
+
+        let keystore = Keystore::from_json_lenient(V1).unwrap();
+        assert_eq!(keystore.version(), 1);
+        let key = keystore.decrypt("testpassword").unwrap();
+        assert_eq!(hex::encode(key.to_bytes()), PRIVATE_KEY);
+    }
+
+    #[test]
+    fn test_lenient() {
+        // the quirks of legacy files, applied to the PBKDF2 vector
+        let legacy = PBKDF2
+            .replace("\"crypto\"", "\"Crypto\"")
+            .replace("\"hmac-sha256\"", "\"HMAC_SHA256\"")
+            .replace("262144", "\"262144\"")
+            .replace(
+                "6087dab2f9fdbbfaddc31a909735c1e6",
+                "0x6087DAB2F9FDBBFADDC31A909735C1E6",
+            )
+            .replace(
+                r#""id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+        "version": 3"#,
+                r#""address": "008aeeda4d805471df9b2a5b0f38a0c3bcba786b""#,
+            );
+
+        assert!(Keystore::from_json(&legacy).is_err());
+        let keystore = Keystore::from_json_lenient(&legacy).unwrap();
+        assert_eq!(keystore.version(), 3);
+        assert_eq!(keystore.id(), None);
+
+        let key = keystore.decrypt("testpassword").unwrap();
+        assert_eq!(hex::encode(key.to_bytes()), PRIVATE_KEY);
+    }
+
+    #[test]
+    fn test_address_mismatch() {
+        let keystore = Keystore::from_json(&PBKDF2.replace(
+            r#""version": 3"#,
+            r#""version": 3, "address": "0000000000000000000000000000000000000000""#,
+        ))
+        .unwrap();
+        assert!(keystore.decrypt("testpassword").is_err());
+    }
+
+    #[test]
+    fn test_encrypt() {
+        let key = PrivateKey::from_bytes(&hex::decode(PRIVATE_KEY).unwrap()).unwrap();
+        let kdf = Kdf::Scrypt {
+            n: 1024,
+            r: 8,
+            p: 1,
+            dklen: 32,
+            salt: vec![1; 32],
+        };
+        let keystore = Keystore::encrypt(&key, "testpassword", kdf).unwrap();
+        assert_eq!(keystore.id().unwrap().len(), 36);
+
+        let parsed = Keystore::from_json(&keystore.to_json().unwrap()).unwrap();
+        assert_eq!(parsed, keystore);
+        let decrypted = parsed.decrypt("testpassword").unwrap();
+        assert_eq!(hex::encode(decrypted.to_bytes()), PRIVATE_KEY);
+        assert!(parsed.decrypt("wrong").is_err());
+
+        let legacy = Keystore::from_json_lenient(V1).unwrap();
+        assert!(legacy.to_json().is_err());
+    }
+
+    #[test]
+    fn test_strict() {
+        assert!(Keystore::from_json(&PBKDF2.replace("\"version\": 3", "\"version\": 2")).is_err());
+        assert!(Keystore::from_json(&PBKDF2.replace("aes-128-ctr", "aes-128-cbc")).is_err());
+        assert!(Keystore::from_json(&PBKDF2.replace("hmac-sha256", "hmac-sha512")).is_err());
+        assert!(
+            Keystore::from_json_lenient(&PBKDF2.replace("hmac-sha256", "hmac-sha512")).is_err()
+        );
+    }
+
+    #[test]
+    fn test_kdf_params() {
+        let invalid = |json: &str, field| {
+            assert_eq!(
+                Keystore::from_json_lenient(json).unwrap_err(),
+                KeystoreError::InvalidFormat(field).into()
+            );
+        };
+        invalid(&V1.replace("\"DkLen\": 32", "\"DkLen\": 16"), "dklen");
+        invalid(
+            &SCRYPT.replace("\"dklen\": 32", "\"dklen\": 100000000"),
+            "dklen",
+        );
+        invalid(&SCRYPT.replace("4096", "4095"), "n");
+        invalid(&SCRYPT.replace("4096", "1073741824"), "kdfparams");
+        invalid(&SCRYPT.replace("\"r\": 8", "\"r\": 4294967304"), "r");
+        invalid(&SCRYPT.replace("\"p\": 6", "\"p\": 1000"), "p");
+        invalid(&PBKDF2.replace("262144", "4294967296"), "c");
+        invalid(&PBKDF2.replace("262144", "100000000"), "c");
+
+        let key = PrivateKey::from_bytes(&hex::decode(PRIVATE_KEY).unwrap()).unwrap();
+        let kdf = Kdf::Pbkdf2 {
+            c: 1,
+            dklen: 16,
+            salt: vec![1; 32],
+        };
+        assert_eq!(
+            Keystore::encrypt(&key, "testpassword", kdf).unwrap_err(),
+            KeystoreError::InvalidFormat("dklen").into()
+        );
+    }
+}
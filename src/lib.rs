@@ -1 +1,61 @@
+pub mod aa;
+pub mod abi;
+pub mod address;
+pub mod argon2;
+pub mod base58;
+pub mod bech32;
 pub mod bips;
+pub mod bls;
+pub mod chains;
+pub mod coins;
+pub mod ecies;
+pub mod ed25519;
+pub mod eips;
+pub mod ens;
+pub mod envelope;
+pub mod error;
+pub mod history;
+pub mod icap;
+pub mod identicon;
+pub mod import;
+pub mod keystore;
+pub mod monero;
+pub mod portfolio;
+pub mod prelude;
+pub mod provider;
+pub mod signature;
+pub mod signer;
+pub mod slips;
+pub mod tokens;
+pub mod transaction;
+pub mod types;
+pub mod units;
+#[doc(hidden)]
+pub mod utils;
+pub mod vanity;
+pub mod wallet;
+pub mod watchtower;
+
+#[cfg(feature = "cbor")]
+pub mod cbor;
+
+#[cfg(feature = "nfc")]
+pub mod nfc;
+
+#[cfg(feature = "qr")]
+pub mod qr;
+
+#[cfg(feature = "substrate")]
+pub mod sr25519;
+
+#[cfg(feature = "explorer")]
+pub mod explorer;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(feature = "uniffi")]
+pub mod mobile;
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
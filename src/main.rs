@@ -1,6 +1,928 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::OpenOptions,
+    io::{BufRead, IsTerminal, Lines, Write},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
-fn main(){
+use rustyline::{
+    completion::Completer, highlight::Highlighter, hint::Hinter, history::DefaultHistory,
+    validate::Validator, Context, Editor, Helper,
+};
+use wallet_rust::{
+    abi::SelectorRegistry,
+    address::Address,
+    bips::{
+        bip32::ExtendedKey,
+        bip39::{Mnemonic, MnemonicType},
+        wordlists::{Language, WordList},
+        ChildNumber, DerivationPath, ETHEREUM_DEFAULT,
+    },
+    keystore::{Kdf, Keystore},
+    signer::Signer,
+    transaction::{SignedTransaction, Transaction, TypedTransaction, UnsignedTransaction},
+    types::B256,
+    units::{format_ether, format_gwei},
+    vanity::{Pattern, Search, VanityKey},
+};
+#[cfg(feature = "qr")]
+use wallet_rust::{eips::eip681::PaymentRequest, qr::QrCode, units::parse_ether};
+use zeroize::{Zeroize, Zeroizing};
 
+type Result<T, E = Box<dyn std::error::Error>> = std::result::Result<T, E>;
+
+const USAGE: &str = "\
+usage: wallet <command> [options]
+
+Commands:
+  generate [--words N]                  print a new mnemonic (default 12 words)
+  recover [--interactive [--words N]] [--passphrase]
+                                        check a mnemonic and print its first address,
+                                        entered word by word with tab completion
+                                        when interactive
+  derive <path> [--passphrase]          print the address and public key at a path
+  address [--account N] [--count N] [--passphrase]
+                                        list the first addresses of an account
+  sign-message <message> [--path P] [--passphrase]
+                                        sign a personal_sign message
+  sign-tx <hash> [--chain-id N] [--path P] [--passphrase]
+                                        sign the signing hash of a transaction,
+                                        with an EIP-155 v when a chain id is given
+  sign-offline <file> [--out F] [--path P] [--passphrase]
+                                        sign an unsigned transaction file, with the
+                                        key at the path it records unless --path is
+                                        given, and write the signed file
+  export-signed <file>                  check a signed transaction file and print
+                                        the raw transaction to broadcast
+  decode-tx <raw>                       print the fields and the sender of a raw
+                                        signed transaction of any type
+  decode-calldata <data> [--abi F] [--signatures F]
+                                        print the function called and its arguments,
+                                        looked up in a JSON ABI, a file of one
+                                        signature per line, or the common functions
+  vanity [--prefix P] [--suffix S] [--regex R] [--mnemonic [--words N]] [--threads N]
+                                        search a key, or a mnemonic, whose address
+                                        matches; uppercase letters match the
+                                        checksum, --regex needs the regex feature
+  qr <address> [--value ETH] [--chain-id N] [--token T --amount N] [--svg F | --png F]
+                                        print the QR code of an address or payment
+                                        request, or save it; needs the qr feature
+  keystore import <file>                encrypt a private key into a new keystore
+  keystore export <file>                decrypt the private key of a keystore
+  bench [--seeds N] [--addresses N] [--target-ms MS]
+                                        measure the key derivation performance
+
+Keys are derived from a mnemonic, at m/44'/60'/0'/0/0 unless --path is
+given. --passphrase asks for a BIP39 passphrase as well.
+
+Mnemonics, passphrases, private keys and passwords are never taken from the
+arguments: they are read from the terminal without echo, or one per line
+from standard input when it is redirected. Interactive recovery echoes the
+words to complete them.
+
+Bench options:
+  --seeds N        BIP39 seeds to derive (default 10)
+  --addresses N    addresses to derive from one account (default 1000)
+  --target-ms MS   unlock time to calibrate the PBKDF2 rounds for (default 1000)";
+
+/// Error returned by the commands.
+#[derive(Debug)]
+enum CliError {
+    InvalidPrivateKey,
+    PasswordMismatch,
+    UnknownWord(usize),
+    NotATerminal,
+}
+
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CliError::InvalidPrivateKey => write!(f, "Invalid private key"),
+            CliError::PasswordMismatch => write!(f, "The passwords do not match"),
+            CliError::UnknownWord(position) => {
+                write!(f, "Word {} is not in the wordlist", position)
+            }
+            CliError::NotATerminal => write!(f, "Interactive recovery needs a terminal"),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// A parsed command line.
+enum Command {
+    Generate(MnemonicType),
+    Recover {
+        interactive: Option<MnemonicType>,
+        passphrase: bool,
+    },
+    Derive {
+        path: DerivationPath,
+        passphrase: bool,
+    },
+    Address {
+        account: u32,
+        count: u32,
+        passphrase: bool,
+    },
+    SignMessage {
+        message: String,
+        path: DerivationPath,
+        passphrase: bool,
+    },
+    SignTx {
+        hash: B256,
+        chain_id: Option<u64>,
+        path: DerivationPath,
+        passphrase: bool,
+    },
+    SignOffline {
+        file: PathBuf,
+        out: Option<PathBuf>,
+        path: Option<DerivationPath>,
+        passphrase: bool,
+    },
+    ExportSigned(PathBuf),
+    DecodeTx(String),
+    DecodeCalldata {
+        data: String,
+        abi: Option<PathBuf>,
+        signatures: Option<PathBuf>,
+    },
+    Vanity {
+        pattern: Pattern,
+        mnemonic: Option<MnemonicType>,
+        threads: Option<usize>,
+    },
+    #[cfg(feature = "qr")]
+    Qr {
+        request: PaymentRequest,
+        output: Option<QrOutput>,
+    },
+    KeystoreImport(PathBuf),
+    KeystoreExport(PathBuf),
+    Bench(BenchOptions),
+}
+
+impl Command {
+    fn parse(args: &[String]) -> std::result::Result<Self, String> {
+        let Some((command, args)) = args.split_first() else {
+            return Err("missing command".to_string());
+        };
+        if command == "bench" {
+            return BenchOptions::parse(args).map(Command::Bench);
+        }
+
+        let known = known_options(command).ok_or_else(|| format!("unknown command {}", command))?;
+        let options = Options::parse(args, known)?;
+        let passphrase = options.flag("--passphrase");
+        let command = match command.as_str() {
+            "generate" => Command::Generate(options.mnemonic_type()?),
+            "recover" => Command::Recover {
+                interactive: match options.flag("--interactive") {
+                    true => Some(options.mnemonic_type()?),
+                    false => None,
+                },
+                passphrase,
+            },
+            "derive" => Command::Derive {
+                path: parse_path(options.positional(0, "path")?)?,
+                passphrase,
+            },
+            "address" => Command::Address {
+                account: options.get("--account")?.unwrap_or(0),
+                count: options.get("--count")?.unwrap_or(10),
+                passphrase,
+            },
+            "sign-message" => Command::SignMessage {
+                message: options.positional(0, "message")?.to_string(),
+                path: options.path()?,
+                passphrase,
+            },
+            "sign-tx" => Command::SignTx {
+                hash: options
+                    .positional(0, "hash")?
+                    .parse()
+                    .map_err(|_| "the hash must be 32 bytes of hex".to_string())?,
+                chain_id: options.get("--chain-id")?,
+                path: options.path()?,
+                passphrase,
+            },
+            "sign-offline" => Command::SignOffline {
+                file: PathBuf::from(options.positional(0, "file")?),
+                out: options.get("--out")?,
+                path: options
+                    .values
+                    .get("--path")
+                    .copied()
+                    .map(parse_path)
+                    .transpose()?,
+                passphrase,
+            },
+            "export-signed" => Command::ExportSigned(PathBuf::from(options.positional(0, "file")?)),
+            "decode-tx" => Command::DecodeTx(options.positional(0, "raw transaction")?.to_string()),
+            "decode-calldata" => Command::DecodeCalldata {
+                data: options.positional(0, "calldata")?.to_string(),
+                abi: options.get("--abi")?,
+                signatures: options.get("--signatures")?,
+            },
+            "vanity" => Command::Vanity {
+                pattern: options.pattern()?,
+                mnemonic: match options.flag("--mnemonic") {
+                    true => Some(options.mnemonic_type()?),
+                    false => None,
+                },
+                threads: options.get("--threads")?,
+            },
+            #[cfg(not(feature = "qr"))]
+            "qr" => return Err("built without the qr feature".to_string()),
+            #[cfg(feature = "qr")]
+            "qr" => Command::Qr {
+                request: options.payment_request()?,
+                output: match (options.get("--svg")?, options.get("--png")?) {
+                    (None, None) => None,
+                    (Some(svg), None) => Some(QrOutput::Svg(svg)),
+                    (None, Some(png)) => Some(QrOutput::Png(png)),
+                    _ => return Err("expected either --svg or --png".to_string()),
+                },
+            },
+            "keystore" => {
+                let file = PathBuf::from(options.positional(1, "file")?);
+                match options.positional(0, "import or export")? {
+                    "import" => Command::KeystoreImport(file),
+                    "export" => Command::KeystoreExport(file),
+                    other => return Err(format!("unknown keystore command {}", other)),
+                }
+            }
+            _ => unreachable!("the options of {} are known", command),
+        };
+
+        Ok(command)
+    }
+}
+
+/// Return the options a command accepts, or `None` for an unknown command.
+fn known_options(command: &str) -> Option<&'static [&'static str]> {
+    let options: &[&str] = match command {
+        "generate" => &["--words"],
+        "recover" => &["--interactive", "--words", "--passphrase"],
+        "derive" => &["--passphrase"],
+        "address" => &["--account", "--count", "--passphrase"],
+        "sign-message" => &["--path", "--passphrase"],
+        "sign-tx" => &["--chain-id", "--path", "--passphrase"],
+        "sign-offline" => &["--out", "--path", "--passphrase"],
+        "export-signed" | "decode-tx" | "keystore" => &[],
+        "decode-calldata" => &["--abi", "--signatures"],
+        "vanity" => &[
+            "--prefix",
+            "--suffix",
+            "--regex",
+            "--mnemonic",
+            "--words",
+            "--threads",
+        ],
+        "qr" => &[
+            "--value",
+            "--chain-id",
+            "--token",
+            "--amount",
+            "--svg",
+            "--png",
+        ],
+        _ => return None,
+    };
+    Some(options)
+}
+
+/// Options taking no value.
+const FLAGS: [&str; 3] = ["--interactive", "--mnemonic", "--passphrase"];
+
+/// Image file a QR code is saved to.
+#[cfg(feature = "qr")]
+enum QrOutput {
+    Svg(PathBuf),
+    Png(PathBuf),
+}
+
+/// The arguments following a command: positional arguments, `--name value`
+/// options and flags.
+struct Options<'a> {
+    positional: Vec<&'a str>,
+    values: HashMap<&'a str, &'a str>,
+    flags: HashSet<&'a str>,
+}
+
+impl<'a> Options<'a> {
+    /// Parse the arguments, rejecting the options not in `known`.
+    fn parse(args: &'a [String], known: &[&str]) -> std::result::Result<Self, String> {
+        let mut options = Self {
+            positional: Vec::new(),
+            values: HashMap::new(),
+            flags: HashSet::new(),
+        };
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg.starts_with("--") && !known.contains(&arg.as_str()) {
+                return Err(format!("unknown option {}", arg));
+            }
+            if FLAGS.contains(&arg.as_str()) {
+                options.flags.insert(arg);
+            } else if arg.starts_with("--") {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("{} expects a value", arg))?;
+                options.values.insert(arg, value);
+            } else {
+                options.positional.push(arg);
+            }
+        }
+
+        Ok(options)
+    }
+
+    fn positional(&self, index: usize, name: &str) -> std::result::Result<&'a str, String> {
+        self.positional
+            .get(index)
+            .copied()
+            .ok_or_else(|| format!("missing {}", name))
+    }
+
+    fn get<T: std::str::FromStr>(&self, name: &str) -> std::result::Result<Option<T>, String> {
+        self.values
+            .get(name)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| format!("invalid value {} for {}", value, name))
+            })
+            .transpose()
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.contains(name)
+    }
+
+    fn mnemonic_type(&self) -> std::result::Result<MnemonicType, String> {
+        let words = self.get("--words")?.unwrap_or(12);
+        MnemonicType::from_word_count(words).map_err(|_| format!("invalid word count {}", words))
+    }
+
+    fn pattern(&self) -> std::result::Result<Pattern, String> {
+        let prefix = self.values.get("--prefix");
+        let suffix = self.values.get("--suffix");
+        let pattern = match (prefix, suffix, self.values.get("--regex")) {
+            (Some(prefix), None, None) => Pattern::prefix(prefix),
+            (None, Some(suffix), None) => Pattern::suffix(suffix),
+            (Some(prefix), Some(suffix), None) => Pattern::prefix_suffix(prefix, suffix),
+            #[cfg(feature = "regex")]
+            (None, None, Some(regex)) => Pattern::regex(regex),
+            _ => return Err("expected a --prefix, a --suffix or both, or a --regex".to_string()),
+        };
+        pattern.map_err(|e| e.to_string())
+    }
+
+    #[cfg(feature = "qr")]
+    fn payment_request(&self) -> std::result::Result<PaymentRequest, String> {
+        let address = |s: &str| {
+            s.parse::<Address>()
+                .map_err(|e| format!("invalid address {}: {}", s, e))
+        };
+        let target = address(self.positional(0, "address")?)?;
+
+        let request = match (self.values.get("--token"), self.get("--amount")?) {
+            (Some(token), Some(amount)) => PaymentRequest::token(address(token)?, target, amount),
+            (None, None) => match self.values.get("--value") {
+                Some(value) => PaymentRequest::ether(target).with_value(
+                    parse_ether(value).map_err(|e| format!("invalid value {}: {}", value, e))?,
+                ),
+                None => PaymentRequest::ether(target),
+            },
+            _ => return Err("--token and --amount go together".to_string()),
+        };
+        Ok(match self.get("--chain-id")? {
+            Some(chain_id) => request.with_chain_id(chain_id),
+            None => request,
+        })
+    }
+
+    fn path(&self) -> std::result::Result<DerivationPath, String> {
+        match self.values.get("--path") {
+            Some(path) => parse_path(path),
+            None => Ok(ETHEREUM_DEFAULT),
+        }
+    }
+}
+
+fn parse_path(path: &str) -> std::result::Result<DerivationPath, String> {
+    DerivationPath::parse(path).map_err(|e| format!("invalid path {}: {}", path, e))
+}
+
+/// Reads secrets from the terminal without echo, or line by line from
+/// standard input when it is redirected.
+struct Secrets {
+    lines: Option<Lines<Box<dyn BufRead>>>,
+}
+
+impl Secrets {
+    fn new() -> Self {
+        let stdin = std::io::stdin();
+        match stdin.is_terminal() {
+            true => Self { lines: None },
+            false => Self::from_reader(stdin.lock()),
+        }
+    }
+
+    /// Read the secrets one per line from `reader`.
+    fn from_reader(reader: impl BufRead + 'static) -> Self {
+        let reader: Box<dyn BufRead> = Box::new(reader);
+        Self {
+            lines: Some(reader.lines()),
+        }
+    }
+
+    fn read(&mut self, prompt: &str) -> Result<Zeroizing<String>> {
+        let secret = match &mut self.lines {
+            Some(lines) => lines.next().transpose()?.unwrap_or_default(),
+            None => rpassword::prompt_password(prompt)?,
+        };
+        Ok(Zeroizing::new(secret))
+    }
+
+    /// Read the mnemonic, and the passphrase if asked for, returning the
+    /// master key.
+    fn master_key(&mut self, passphrase: bool) -> Result<ExtendedKey> {
+        let mnemonic = check_phrase(&self.read("Mnemonic: ")?)?;
+        self.seed_key(&mnemonic, passphrase)
+    }
+
+    /// Read the passphrase if asked for, returning the master key of a
+    /// mnemonic.
+    fn seed_key(&mut self, mnemonic: &Mnemonic, passphrase: bool) -> Result<ExtendedKey> {
+        let passphrase = match passphrase {
+            true => self.read("Passphrase: ")?,
+            false => Zeroizing::new(String::new()),
+        };
+        Ok(ExtendedKey::new_master(&mnemonic.to_seed(&passphrase))?)
+    }
+}
+
+/// Parse an English phrase. When a word is not in the wordlist, its position
+/// is shown with the other words masked.
+fn check_phrase(phrase: &str) -> Result<Mnemonic> {
+    let wordmap = Language::English.wordmap();
+    let words = phrase.split_whitespace().collect::<Vec<_>>();
+    if let Some(position) = words.iter().position(|w| wordmap.get_index(w).is_err()) {
+        let masked = words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| match i == position {
+                true => word.to_string(),
+                false => "_".repeat(word.chars().count()),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let offset = masked.split(' ').take(position).map(|w| w.len() + 1).sum();
+        eprintln!(
+            "{}\n{}{}",
+            masked,
+            " ".repeat(offset),
+            "^".repeat(words[position].chars().count())
+        );
+        return Err(CliError::UnknownWord(position + 1).into());
+    }
+
+    Ok(Mnemonic::from_phrase(&words.join(" "), Language::English)?)
+}
+
+/// Completion of BIP39 words for the interactive recovery.
+struct WordHelper(&'static WordList);
+
+impl WordHelper {
+    fn candidates(&self, line: &str) -> &[&'static str] {
+        match line.trim() {
+            "" => &[],
+            prefix => self.0.get_word_by_prefix(prefix),
+        }
+    }
+}
+
+impl Completer for WordHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        _pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let candidates = self.candidates(line);
+        Ok((0, candidates.iter().map(|w| w.to_string()).collect()))
+    }
+}
+
+impl Hinter for WordHelper {
+    type Hint = String;
+
+    /// Hint the rest of the word once the prefix is unique.
+    fn hint(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> Option<String> {
+        match self.candidates(line) {
+            [word] if pos == line.len() => word.strip_prefix(line.trim()).map(str::to_string),
+            _ => None,
+        }
+    }
+}
+
+impl Highlighter for WordHelper {}
+
+impl Validator for WordHelper {}
+
+impl Helper for WordHelper {}
+
+/// Ask for the words of a mnemonic one by one, with tab completion. A unique
+/// prefix is accepted for a word, `-` goes back one word, and the checksum
+/// is checked once the last word is entered.
+fn recover_interactive(ty: MnemonicType) -> Result<Mnemonic> {
+    if !std::io::stdin().is_terminal() {
+        return Err(CliError::NotATerminal.into());
+    }
+
+    let helper = WordHelper(Language::English.wordlist());
+    let mut editor = Editor::<WordHelper, DefaultHistory>::new()?;
+    editor.set_helper(Some(helper));
+
+    let count = ty.word_count();
+    let mut words = Vec::with_capacity(count);
+    loop {
+        let prompt = format!("Word {:>2}/{}: ", words.len() + 1, count);
+        let line = Zeroizing::new(editor.readline(&prompt)?);
+        let input = line.trim();
+        if input == "-" {
+            words.pop();
+            continue;
+        }
+
+        let candidates = editor.helper().map_or(&[][..], |h| h.candidates(input));
+        let word = match (candidates.iter().find(|w| **w == input), candidates) {
+            (Some(word), _) | (None, [word]) => *word,
+            (None, []) => {
+                eprintln!("  {} is not in the wordlist", input);
+                continue;
+            }
+            (None, _) => {
+                eprintln!("  {} could be {}", input, candidates.join(", "));
+                continue;
+            }
+        };
+        if word != input {
+            eprintln!("  {}", word);
+        }
+        words.push(word);
+
+        if words.len() == count {
+            match Mnemonic::from_phrase(&words.join(" "), Language::English) {
+                Ok(mnemonic) => return Ok(mnemonic),
+                Err(e) => {
+                    eprintln!("  {}: a word is wrong, enter - to go back", e);
+                    words.pop();
+                }
+            }
+        }
+    }
+}
+
+/// Options of the `bench` command.
+struct BenchOptions {
+    seeds: u32,
+    addresses: u32,
+    target: Duration,
+}
+
+impl BenchOptions {
+    fn parse(args: &[String]) -> std::result::Result<Self, String> {
+        let mut options = Self {
+            seeds: 10,
+            addresses: 1000,
+            target: Duration::from_millis(1000),
+        };
+
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            let value = args
+                .next()
+                .and_then(|v| v.parse::<u32>().ok())
+                .filter(|v| *v > 0)
+                .ok_or_else(|| format!("{} expects a positive number", flag))?;
+            match flag.as_str() {
+                "--seeds" => options.seeds = value,
+                "--addresses" => options.addresses = value,
+                "--target-ms" => options.target = Duration::from_millis(value as u64),
+                _ => return Err(format!("unknown option {}", flag)),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let command = Command::parse(&args).unwrap_or_else(|e| {
+        eprintln!("error: {}\n\n{}", e, USAGE);
+        std::process::exit(2);
+    });
+    if let Err(e) = run(command) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(command: Command) -> Result<()> {
+    let mut secrets = Secrets::new();
+
+    match command {
+        Command::Generate(ty) => println!("{}", Mnemonic::new(ty, Language::English).phrase()),
+        Command::Recover {
+            interactive,
+            passphrase,
+        } => {
+            let master = match interactive {
+                Some(ty) => secrets.seed_key(&recover_interactive(ty)?, passphrase)?,
+                None => secrets.master_key(passphrase)?,
+            };
+            let key = master.derive_path(&ETHEREUM_DEFAULT)?;
+            println!("Valid mnemonic, first address {}", key.address()?);
+        }
+        Command::Derive { path, passphrase } => {
+            let key = secrets.master_key(passphrase)?.derive_path(&path)?;
+            println!("path        {}", path);
+            println!("address     {}", key.address()?);
+            println!("public key  0x{}", hex::encode(key.public_key().to_bytes()));
+        }
+        Command::Address {
+            account,
+            count,
+            passphrase,
+        } => {
+            let path = DerivationPath::parse(&format!("m/44'/60'/{}'/0", account))?;
+            let account = secrets.master_key(passphrase)?.derive_path(&path)?;
+            for i in 0..count {
+                let key = account.derive_child(ChildNumber::try_normal(i)?)?;
+                println!("{}/{}  {}", path, i, key.address()?);
+            }
+        }
+        Command::SignMessage {
+            message,
+            path,
+            passphrase,
+        } => {
+            let key = secrets.master_key(passphrase)?.derive_path(&path)?;
+            let signature = key.sign_message(&message)?;
+            println!("0x{}", hex::encode(signature.to_bytes()));
+        }
+        Command::SignTx {
+            hash,
+            chain_id,
+            path,
+            passphrase,
+        } => {
+            let key = secrets.master_key(passphrase)?.derive_path(&path)?;
+            let mut signature = key.sign_hash(&hash)?;
+            if let Some(chain_id) = chain_id {
+                signature = signature.with_chain_id(chain_id)?;
+            }
+            println!("r  0x{}", hex::encode(signature.r()));
+            println!("s  0x{}", hex::encode(signature.s()));
+            println!("v  {}", signature.v());
+        }
+        Command::SignOffline {
+            file,
+            out,
+            path,
+            passphrase,
+        } => {
+            let unsigned = UnsignedTransaction::load(&file)?;
+            if let Some(from) = &unsigned.from {
+                eprintln!("from       {}", from);
+            }
+            for line in describe(&unsigned.transaction.clone().into()) {
+                eprintln!("{}", line);
+            }
+            if let Some(description) = &unsigned.description {
+                eprintln!("           {}", description);
+            }
+
+            let path = path.or(unsigned.path.clone()).unwrap_or(ETHEREUM_DEFAULT);
+            let key = secrets.master_key(passphrase)?.derive_path(&path)?;
+            let signed = unsigned.sign(&key)?;
+            match out {
+                Some(out) => {
+                    signed.save(&out)?;
+                    println!("Saved {} to {}", signed.hash(), out.display());
+                }
+                None => println!("{}", signed.export_signed()),
+            }
+        }
+        Command::ExportSigned(file) => {
+            let signed = SignedTransaction::load(&file)?;
+            eprintln!("from  {}", signed.from);
+            eprintln!("hash  {}", signed.hash());
+            println!("{}", signed.raw_hex());
+        }
+        Command::DecodeTx(raw) => {
+            let decoded = Transaction::decode(&raw)?;
+            println!("type       {}", decoded.transaction.tx_type());
+            println!("hash       {}", decoded.hash);
+            println!("from       {}", decoded.from);
+            for line in describe(&decoded.transaction) {
+                println!("{}", line);
+            }
+            if !decoded.transaction.data().is_empty() {
+                println!("           0x{}", hex::encode(decoded.transaction.data()));
+            }
+        }
+        Command::DecodeCalldata {
+            data,
+            abi,
+            signatures,
+        } => {
+            let mut registry = SelectorRegistry::new();
+            if let Some(signatures) = signatures {
+                registry.add_signatures(&std::fs::read_to_string(signatures)?)?;
+            }
+            if let Some(abi) = abi {
+                registry.add_abi(&std::fs::read_to_string(abi)?)?;
+            }
+            let data = hex::decode(data.trim_start_matches("0x"))?;
+            println!("{}", registry.decode(&data)?);
+        }
+        Command::Vanity {
+            pattern,
+            mnemonic,
+            threads,
+        } => {
+            if let Some(difficulty) = pattern.difficulty() {
+                eprintln!("Expecting about {:.0} attempts", difficulty);
+            }
+            let mut search = Search::new(pattern);
+            if let Some(threads) = threads {
+                search = search.threads(threads);
+            }
+            if let Some(ty) = mnemonic {
+                search = search.mnemonic(ty, 20);
+            }
+
+            let found = search.run(|progress| {
+                eprint!(
+                    "\r{} addresses, {:.0}/s",
+                    progress.attempts,
+                    progress.rate()
+                )
+            })?;
+            eprintln!(
+                "\x1b[2K\rFound after {} addresses in {:.1?}",
+                found.progress.attempts, found.progress.elapsed
+            );
+            println!("address      {}", found.address);
+            match &found.key {
+                VanityKey::PrivateKey(key) => {
+                    let bytes = Zeroizing::new(key.to_bytes());
+                    println!("private key  0x{}", hex::encode(bytes.as_slice()));
+                }
+                VanityKey::Mnemonic { mnemonic, path } => {
+                    println!("mnemonic     {}", mnemonic.phrase());
+                    println!("path         {}", path);
+                }
+            }
+        }
+        #[cfg(feature = "qr")]
+        Command::Qr { request, output } => {
+            let code = QrCode::payment(&request)?;
+            match output {
+                None => println!("{}\n{}", code.to_terminal(), request),
+                Some(QrOutput::Svg(file)) => std::fs::write(file, code.to_svg())?,
+                Some(QrOutput::Png(file)) => std::fs::write(file, code.to_png(8)?)?,
+            }
+        }
+        Command::KeystoreImport(file) => {
+            let mut key = secrets.read("Private key: ")?;
+            let bytes = Zeroizing::new(hex::decode(key.trim().trim_start_matches("0x"))?);
+            key.zeroize();
+            let private_key = laron_crypto::PrivateKey::from_bytes(&bytes)
+                .map_err(|_| CliError::InvalidPrivateKey)?;
+
+            let password = secrets.read("Password: ")?;
+            if *secrets.read("Repeat password: ")? != *password {
+                return Err(CliError::PasswordMismatch.into());
+            }
+            let keystore = Keystore::encrypt(&private_key, &password, Kdf::scrypt())?;
+
+            // never overwrite an existing key file
+            let mut out = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&file)?;
+            out.write_all(keystore.to_json()?.as_bytes())?;
+            if let Some(address) = keystore.address() {
+                println!("Saved {} to {}", address, file.display());
+            }
+        }
+        Command::KeystoreExport(file) => {
+            let keystore = Keystore::from_json_lenient(&std::fs::read_to_string(&file)?)?;
+            let password = secrets.read("Password: ")?;
+            let private_key = keystore.decrypt(&password)?;
+            let bytes = Zeroizing::new(private_key.to_bytes());
+            println!("0x{}", hex::encode(bytes.as_slice()));
+        }
+        Command::Bench(options) => bench(&options)?,
+    }
+
+    Ok(())
+}
+
+/// Describe the fields of a transaction, one per line, for review.
+fn describe(tx: &TypedTransaction) -> Vec<String> {
+    let mut lines = Vec::new();
+    match tx.chain_id() {
+        Some(chain_id) => lines.push(format!("chain id   {}", chain_id)),
+        None => lines.push("chain id   none, valid on every chain".to_string()),
+    }
+    match tx.to() {
+        Some(to) => lines.push(format!("to         {}", to)),
+        None => lines.push("to         (contract creation)".to_string()),
+    }
+    lines.push(format!("value      {} ETH", format_ether(tx.value())));
+    lines.push(format!("nonce      {}", tx.nonce()));
+    let fees = match tx {
+        TypedTransaction::Eip1559(tx) => format!(
+            "up to {} gwei, tip {} gwei",
+            format_gwei(tx.max_fee_per_gas),
+            format_gwei(tx.max_priority_fee_per_gas)
+        ),
+        TypedTransaction::Eip4844(tx) => format!(
+            "up to {} gwei, tip {} gwei, {} gwei per blob gas",
+            format_gwei(tx.max_fee_per_gas),
+            format_gwei(tx.max_priority_fee_per_gas),
+            format_gwei(tx.max_fee_per_blob_gas)
+        ),
+        _ => format!("{} gwei", format_gwei(tx.max_fee_per_gas())),
+    };
+    lines.push(format!("gas        {} at {}", tx.gas_limit(), fees));
+    lines.push(format!("data       {} bytes", tx.data().len()));
+    if let Ok(call) = SelectorRegistry::new().decode(tx.data()) {
+        for (index, line) in call.to_string().lines().enumerate() {
+            let label = if index == 0 { "call" } else { "" };
+            lines.push(format!("{:<10} {}", label, line));
+        }
+    }
+    if !tx.access_list().is_empty() {
+        lines.push(format!("access     {} accounts", tx.access_list().len()));
+    }
+    lines
+}
+
+fn bench(options: &BenchOptions) -> Result<()> {
+    let mnemonic = Mnemonic::new(MnemonicType::Words12, Language::English);
+
+    println!("BIP39 seed derivation (PBKDF2-HMAC-SHA512, 2048 rounds)");
+    let start = Instant::now();
+    for _ in 0..options.seeds {
+        mnemonic.to_seed("");
+    }
+    println!("  {:?} per seed", start.elapsed() / options.seeds);
+
+    println!("Address derivation (m/44'/60'/0'/0/i)");
+    let account = ExtendedKey::new_master(&mnemonic.to_seed(""))?
+        .derive_path(&DerivationPath::parse("m/44'/60'/0'/0")?)?;
+    let start = Instant::now();
+    for i in 0..options.addresses {
+        let key = account.derive_child(ChildNumber::normal(i))?;
+        Address::from_public_key(key.public_key())?;
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "  {} addresses in {:?}, {:.0} addresses/s",
+        options.addresses,
+        elapsed,
+        options.addresses as f64 / elapsed.as_secs_f64()
+    );
+
+    println!("PBKDF2-HMAC-SHA256 calibration");
+    let probe = 100_000;
+    let mut key = [0u8; 32];
+    let start = Instant::now();
+    pbkdf2::pbkdf2::<hmac::Hmac<sha2::Sha256>>(b"password", b"salt", probe, &mut key);
+    let rounds_per_sec = probe as f64 / start.elapsed().as_secs_f64();
+    println!(
+        "  {:.0} rounds/s, {:.0} rounds for a {:?} unlock",
+        rounds_per_sec,
+        rounds_per_sec * options.target.as_secs_f64(),
+        options.target
+    );
+
+    Ok(())
 }
 
 
@@ -16,7 +938,7 @@ mod test{
         use wallet_rust::bips::DerivationPath;
         
         let mnemonic = Mnemonic::new(MnemonicType::Words12, Language::English);
-        println!("{}",mnemonic);
+        println!("{}",mnemonic.phrase());
         let seed = mnemonic.to_seed("password");
         let master_key = ExtendedKey::new_master(&seed).unwrap();
         
@@ -29,4 +951,319 @@ mod test{
         println!("{}",address)
     }
 
+    use super::*;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon \
+        abandon abandon abandon about";
+
+    fn parse(line: &str) -> std::result::Result<Command, String> {
+        let args: Vec<String> = line.split_whitespace().map(String::from).collect();
+        Command::parse(&args)
+    }
+
+    fn error(line: &str) -> String {
+        parse(line).err().expect("the command line is invalid")
+    }
+
+    #[test]
+    fn test_parse_keys() {
+        assert!(matches!(
+            parse("generate").unwrap(),
+            Command::Generate(MnemonicType::Words12)
+        ));
+        assert!(matches!(
+            parse("generate --words 24").unwrap(),
+            Command::Generate(MnemonicType::Words24)
+        ));
+        assert!(matches!(
+            parse("recover").unwrap(),
+            Command::Recover {
+                interactive: None,
+                passphrase: false
+            }
+        ));
+        assert!(matches!(
+            parse("recover --interactive --words 15 --passphrase").unwrap(),
+            Command::Recover {
+                interactive: Some(MnemonicType::Words15),
+                passphrase: true
+            }
+        ));
+
+        let Command::Derive { path, passphrase } = parse("derive m/44'/60'/1'/0/7").unwrap() else {
+            panic!("expected derive");
+        };
+        assert_eq!(path, DerivationPath::parse("m/44'/60'/1'/0/7").unwrap());
+        assert!(!passphrase);
+
+        assert!(matches!(
+            parse("address --account 2 --count 5 --passphrase").unwrap(),
+            Command::Address {
+                account: 2,
+                count: 5,
+                passphrase: true
+            }
+        ));
+        assert!(matches!(
+            parse("address").unwrap(),
+            Command::Address {
+                account: 0,
+                count: 10,
+                passphrase: false
+            }
+        ));
+    }
+
+    #[test]
+    fn test_parse_signing() {
+        let Command::SignMessage {
+            message,
+            path,
+            passphrase,
+        } = parse("sign-message hello --path m/44'/60'/0'/0/3").unwrap()
+        else {
+            panic!("expected sign-message");
+        };
+        assert_eq!(message, "hello");
+        assert_eq!(path, DerivationPath::parse("m/44'/60'/0'/0/3").unwrap());
+        assert!(!passphrase);
+
+        let hash = format!("0x{}", "ab".repeat(32));
+        let Command::SignTx {
+            hash: parsed,
+            chain_id,
+            path,
+            passphrase,
+        } = parse(&format!("sign-tx {} --chain-id 10 --passphrase", hash)).unwrap()
+        else {
+            panic!("expected sign-tx");
+        };
+        assert_eq!(parsed, hash.parse::<B256>().unwrap());
+        assert_eq!(chain_id, Some(10));
+        assert_eq!(path, ETHEREUM_DEFAULT);
+        assert!(passphrase);
+
+        let Command::SignOffline {
+            file,
+            out,
+            path,
+            passphrase,
+        } = parse("sign-offline tx.json --out signed.json").unwrap()
+        else {
+            panic!("expected sign-offline");
+        };
+        assert_eq!(file, PathBuf::from("tx.json"));
+        assert_eq!(out, Some(PathBuf::from("signed.json")));
+        assert_eq!(path, None);
+        assert!(!passphrase);
+        let Command::SignOffline { path, .. } =
+            parse("sign-offline tx.json --path m/44'/60'/0'/0/1").unwrap()
+        else {
+            panic!("expected sign-offline");
+        };
+        assert_eq!(path, Some(DerivationPath::parse("m/44'/60'/0'/0/1").unwrap()));
+
+        assert!(matches!(
+            parse("export-signed signed.json").unwrap(),
+            Command::ExportSigned(file) if file.as_os_str() == "signed.json"
+        ));
+        assert!(matches!(
+            parse("decode-tx 0x02f8").unwrap(),
+            Command::DecodeTx(raw) if raw == "0x02f8"
+        ));
+        let Command::DecodeCalldata {
+            data,
+            abi,
+            signatures,
+        } = parse("decode-calldata 0xa9059cbb --abi erc20.json").unwrap()
+        else {
+            panic!("expected decode-calldata");
+        };
+        assert_eq!(data, "0xa9059cbb");
+        assert_eq!(abi, Some(PathBuf::from("erc20.json")));
+        assert_eq!(signatures, None);
+    }
+
+    #[test]
+    fn test_parse_tools() {
+        assert!(matches!(
+            parse("vanity --prefix dead --threads 2").unwrap(),
+            Command::Vanity {
+                mnemonic: None,
+                threads: Some(2),
+                ..
+            }
+        ));
+        assert!(matches!(
+            parse("vanity --prefix 00 --suffix beef --mnemonic --words 24").unwrap(),
+            Command::Vanity {
+                mnemonic: Some(MnemonicType::Words24),
+                threads: None,
+                ..
+            }
+        ));
+
+        assert!(matches!(
+            parse("keystore import key.json").unwrap(),
+            Command::KeystoreImport(file) if file.as_os_str() == "key.json"
+        ));
+        assert!(matches!(
+            parse("keystore export key.json").unwrap(),
+            Command::KeystoreExport(file) if file.as_os_str() == "key.json"
+        ));
+        assert_eq!(
+            error("keystore delete key.json"),
+            "unknown keystore command delete"
+        );
+
+        let Command::Bench(options) = parse("bench --seeds 3 --target-ms 250").unwrap() else {
+            panic!("expected bench");
+        };
+        assert_eq!(options.seeds, 3);
+        assert_eq!(options.addresses, 1000);
+        assert_eq!(options.target, Duration::from_millis(250));
+        assert_eq!(error("bench --seeds 0"), "--seeds expects a positive number");
+        assert_eq!(error("bench --addresses"), "--addresses expects a positive number");
+        assert_eq!(error("bench --threads 2"), "unknown option --threads");
+    }
+
+    #[cfg(feature = "qr")]
+    #[test]
+    fn test_parse_qr() {
+        let address = "0x9858EfFD232B4033E47d90003D41EC34EcaEda94";
+        let Command::Qr { request, output } =
+            parse(&format!("qr {} --value 1.5 --chain-id 1 --svg code.svg", address)).unwrap()
+        else {
+            panic!("expected qr");
+        };
+        assert!(request.to_string().starts_with("ethereum:"));
+        assert!(matches!(output, Some(QrOutput::Svg(file)) if file.as_os_str() == "code.svg"));
+        assert!(matches!(
+            parse(&format!("qr {}", address)).unwrap(),
+            Command::Qr { output: None, .. }
+        ));
+
+        assert_eq!(
+            error(&format!("qr {} --svg a.svg --png a.png", address)),
+            "expected either --svg or --png"
+        );
+        assert_eq!(
+            error(&format!("qr {} --token {}", address, address)),
+            "--token and --amount go together"
+        );
+        assert!(error("qr 0x1234").starts_with("invalid address 0x1234"));
+    }
+
+    #[cfg(not(feature = "qr"))]
+    #[test]
+    fn test_parse_qr() {
+        assert_eq!(
+            error("qr 0x9858EfFD232B4033E47d90003D41EC34EcaEda94"),
+            "built without the qr feature"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(error(""), "missing command");
+        assert_eq!(error("frobnicate"), "unknown command frobnicate");
+
+        // options of other commands are rejected, not ignored
+        assert_eq!(error("generate --count 3"), "unknown option --count");
+        assert_eq!(error("derive m/0 --path m/1"), "unknown option --path");
+        assert_eq!(error("decode-tx 0x02 --passphrase"), "unknown option --passphrase");
+        assert_eq!(error("recover --mnemonic"), "unknown option --mnemonic");
+
+        // missing values and arguments
+        assert_eq!(error("address --account"), "--account expects a value");
+        assert_eq!(error("sign-tx --chain-id"), "--chain-id expects a value");
+        assert_eq!(error("derive"), "missing path");
+        assert_eq!(error("derive --passphrase"), "missing path");
+        assert_eq!(error("sign-message"), "missing message");
+        assert_eq!(error("sign-tx"), "missing hash");
+        assert_eq!(error("sign-offline"), "missing file");
+        assert_eq!(error("export-signed"), "missing file");
+        assert_eq!(error("decode-tx"), "missing raw transaction");
+        assert_eq!(error("decode-calldata"), "missing calldata");
+        assert_eq!(error("keystore"), "missing file");
+        assert_eq!(error("keystore import"), "missing file");
+
+        // invalid values
+        assert_eq!(error("address --count ten"), "invalid value ten for --count");
+        assert_eq!(error("generate --words 13"), "invalid word count 13");
+        assert_eq!(error("sign-tx 0x1234"), "the hash must be 32 bytes of hex");
+        assert_eq!(
+            error("vanity --threads 2"),
+            "expected a --prefix, a --suffix or both, or a --regex"
+        );
+        assert_eq!(
+            error("vanity --prefix xyz"),
+            wallet_rust::vanity::VanityError::InvalidPattern("xyz".to_string()).to_string()
+        );
+    }
+
+    #[test]
+    fn test_parse_bad_paths() {
+        for path in ["m/44'/60'/x", "m//0", "44'/60'", "m/0''", "m/4294967296"] {
+            let expected = format!("invalid path {}: ", path);
+            assert!(error(&format!("derive {}", path)).starts_with(&expected), "{}", path);
+            assert!(
+                error(&format!("sign-message hi --path {}", path)).starts_with(&expected),
+                "{}",
+                path
+            );
+            assert!(
+                error(&format!("sign-tx 0x{} --path {}", "00".repeat(32), path))
+                    .starts_with(&expected),
+                "{}",
+                path
+            );
+            assert!(
+                error(&format!("sign-offline tx.json --path {}", path)).starts_with(&expected),
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn test_secrets_from_reader() {
+        let mut secrets = Secrets::from_reader(std::io::Cursor::new(format!("{}\n", PHRASE)));
+        let key = secrets
+            .master_key(false)
+            .unwrap()
+            .derive_path(&ETHEREUM_DEFAULT)
+            .unwrap();
+        assert_eq!(
+            key.address().unwrap().to_string(),
+            "0x9858EfFD232B4033E47d90003D41EC34EcaEda94"
+        );
+        // past the end of the input, secrets are empty
+        assert_eq!(*secrets.read("Password: ").unwrap(), "");
+
+        // the passphrase is the next line, trailing carriage returns removed
+        let input = format!("{}\r\nTREZOR\r\n", PHRASE);
+        let mut secrets = Secrets::from_reader(std::io::Cursor::new(input));
+        let master = secrets.master_key(true).unwrap();
+        let expected = ExtendedKey::new_master(
+            &Mnemonic::from_phrase(PHRASE, Language::English)
+                .unwrap()
+                .to_seed("TREZOR"),
+        )
+        .unwrap();
+        assert_eq!(master.public_key(), expected.public_key());
+
+        let mut secrets = Secrets::from_reader(std::io::Cursor::new("password\nrepeat\n"));
+        assert_eq!(*secrets.read("Password: ").unwrap(), "password");
+        assert_eq!(*secrets.read("Repeat password: ").unwrap(), "repeat");
+
+        let typo = PHRASE.replacen("abandon", "abandn", 1);
+        let mut secrets = Secrets::from_reader(std::io::Cursor::new(typo));
+        assert_eq!(
+            secrets.master_key(false).unwrap_err().to_string(),
+            "Word 1 is not in the wordlist"
+        );
+        let mut secrets = Secrets::from_reader(std::io::Cursor::new(""));
+        assert!(secrets.master_key(false).is_err());
+    }
 }
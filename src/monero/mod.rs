@@ -0,0 +1,549 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Monero Mnemonics
+//!
+//! Monero seeds are not BIP39 mnemonics. Every 4 bytes of the seed are
+//! encoded as 3 words of a 1626 word list, and a checksum word is appended,
+//! picked by the CRC32 of the unique prefixes of the other words. The usual
+//! 25 word seeds encode 32 bytes, the 13 word seeds of MyMonero 16 bytes.
+//!
+//! The private spend and view keys are derived from the seed with
+//! Keccak-256 and a reduction modulo the order of ed25519. The public keys
+//! are their multiples of the ed25519 base point, and the standard address
+//! encodes both public keys with the block-wise Base58 of Monero.
+//!
+//! Only the English wordlist of Monero is vendored, its SHA-256 is checked
+//! by [`Language::verify`]. Mnemonics in the other languages of Monero are
+//! not supported.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::monero::{Language, MoneroMnemonic, Network};
+//!
+//! let phrase = "sequence atlas unveil summon pebbles tuesday beer rudely snake rockets different \
+//!     fuselage woven tagged bested dented vegan hover rapid fawns obvious muppet randomly seasons \
+//!     randomly";
+//! let mnemonic = MoneroMnemonic::from_phrase(phrase, Language::English).unwrap();
+//! let keys = mnemonic.keys();
+//! assert_eq!(
+//!     hex::encode(keys.spend_key()),
+//!     "b0ef6bd527b9b23b9ceef70dc8b4cd1ee83ca14541964e764ad23f5151204f0f"
+//! );
+//! let address = keys.address(Network::Mainnet);
+//! assert!(address.starts_with('4'));
+//!
+//! let new = MoneroMnemonic::generate(Language::English);
+//! assert_eq!(new.phrase().split_whitespace().count(), 25);
+//! ```
+
+use std::sync::OnceLock;
+
+use curve25519_dalek::{EdwardsPoint, Scalar};
+use rand::Rng;
+use sha2::Digest;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::{error::Result, utils::keccak256};
+
+/// Alphabet of the Base58 of Monero, the one of Bitcoin.
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+/// Number of Base58 characters encoding a block of 0 to 8 bytes.
+const BASE58_BLOCK_SIZES: [usize; 9] = [0, 2, 3, 5, 6, 7, 9, 10, 11];
+
+/// Error returned by Monero mnemonic operations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoneroError {
+    /// A word of the mnemonic is not in the wordlist.
+    UnknownWord(String),
+    /// The mnemonic does not have 13 or 25 words.
+    InvalidLength(usize),
+    /// The checksum word does not match.
+    InvalidChecksum,
+    /// Three words do not encode a 32-bit value.
+    InvalidWords,
+    /// The seed is not 16 or 32 bytes long.
+    InvalidSeedLength(usize),
+    /// The embedded wordlist does not match its pinned hash.
+    IntegrityCheckFailed(Language),
+}
+
+impl std::fmt::Display for MoneroError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MoneroError::UnknownWord(word) => write!(f, "Unknown Monero word: {}", word),
+            MoneroError::InvalidLength(len) => {
+                write!(f, "Invalid Monero mnemonic length: {} words", len)
+            }
+            MoneroError::InvalidChecksum => write!(f, "Invalid Monero mnemonic checksum"),
+            MoneroError::InvalidWords => write!(f, "Invalid Monero mnemonic words"),
+            MoneroError::InvalidSeedLength(len) => {
+                write!(f, "Invalid Monero seed length: {} bytes", len)
+            }
+            MoneroError::IntegrityCheckFailed(language) => {
+                write!(f, "Monero wordlist integrity check failed: {:?}", language)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MoneroError {}
+
+/// Language of a Monero wordlist. The lists differ from the BIP39 ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Language {
+    English,
+}
+
+impl Language {
+    /// Return the wordlist of the language.
+    pub fn wordlist(self) -> &'static [&'static str] {
+        match self {
+            Language::English => {
+                static WORDS: OnceLock<Vec<&'static str>> = OnceLock::new();
+                WORDS.get_or_init(|| self.raw().lines().collect())
+            }
+        }
+    }
+
+    /// Check the embedded wordlist against the SHA-256 of the list of
+    /// Monero, one word per line, failing if the vendored file was altered.
+    pub fn verify(self) -> Result<()> {
+        let hash = hex::encode(sha2::Sha256::digest(self.raw()));
+        if hash != self.official_hash() {
+            return Err(MoneroError::IntegrityCheckFailed(self).into());
+        }
+        Ok(())
+    }
+
+    fn raw(self) -> &'static str {
+        match self {
+            Language::English => include_str!("./wordlists/english.txt"),
+        }
+    }
+
+    fn official_hash(self) -> &'static str {
+        match self {
+            Language::English => "1c2d9ae594c8bd559ee0c72528e7b0e7ab1e9414744757ea37f5c51a76411b94",
+        }
+    }
+
+    /// Number of leading characters identifying a word, which feed the
+    /// checksum.
+    pub fn prefix_len(self) -> usize {
+        match self {
+            Language::English => 3,
+        }
+    }
+
+    fn index(self, word: &str) -> Result<usize> {
+        self.wordlist()
+            .binary_search(&word)
+            .map_err(|_| MoneroError::UnknownWord(word.to_string()).into())
+    }
+}
+
+/// A Monero mnemonic seed of 25 words, or 13 words for MyMonero seeds.
+#[derive(Clone, PartialEq, Eq)]
+pub struct MoneroMnemonic {
+    phrase: Zeroizing<String>,
+    seed: Zeroizing<Vec<u8>>,
+    language: Language,
+}
+
+impl MoneroMnemonic {
+    /// Generate a random 25 word mnemonic.
+    pub fn generate(language: Language) -> Self {
+        let mut seed = Zeroizing::new([0u8; 32]);
+        rand::thread_rng().fill(&mut seed[..]);
+        // wallets reduce the seed so that it is the spend key itself
+        let seed = sc_reduce32(&seed);
+        Self::from_seed(&*seed, language).expect("the seed is 32 bytes")
+    }
+
+    /// Encode a seed of 32 bytes as 25 words, or 16 bytes as 13 words.
+    pub fn from_seed(seed: &[u8], language: Language) -> Result<Self> {
+        if seed.len() != 16 && seed.len() != 32 {
+            return Err(MoneroError::InvalidSeedLength(seed.len()).into());
+        }
+        let wordlist = language.wordlist();
+        let n = wordlist.len() as u64;
+
+        let mut words = Vec::with_capacity(seed.len() / 4 * 3 + 1);
+        for chunk in seed.chunks(4) {
+            let x = u32::from_le_bytes(chunk.try_into().unwrap()) as u64;
+            let w1 = x % n;
+            let w2 = (x / n + w1) % n;
+            let w3 = (x / n / n + w2) % n;
+            words.extend([w1, w2, w3].map(|w| wordlist[w as usize]));
+        }
+        words.push(words[checksum_index(&words, language)]);
+
+        Ok(Self {
+            phrase: Zeroizing::new(words.join(" ")),
+            seed: Zeroizing::new(seed.to_vec()),
+            language,
+        })
+    }
+
+    /// Parse a mnemonic, verifying its checksum word.
+    pub fn from_phrase(phrase: &str, language: Language) -> Result<Self> {
+        let words: Vec<&str> = phrase.split_whitespace().collect();
+        if words.len() != 13 && words.len() != 25 {
+            return Err(MoneroError::InvalidLength(words.len()).into());
+        }
+        let indices = words
+            .iter()
+            .map(|word| language.index(word).map(|index| index as u64))
+            .collect::<Result<Vec<_>>>()?;
+        let (words, checksum) = words.split_at(words.len() - 1);
+        let prefix_len = language.prefix_len();
+        if prefix(words[checksum_index(words, language)], prefix_len)
+            != prefix(checksum[0], prefix_len)
+        {
+            return Err(MoneroError::InvalidChecksum.into());
+        }
+
+        let n = language.wordlist().len() as u64;
+        let mut seed = Zeroizing::new(Vec::with_capacity(words.len() / 3 * 4));
+        for triple in indices[..words.len()].chunks(3) {
+            let (w1, w2, w3) = (triple[0], triple[1], triple[2]);
+            let x = w1 + n * ((n - w1 + w2) % n) + n * n * ((n - w2 + w3) % n);
+            if x % n != w1 || x > u32::MAX as u64 {
+                return Err(MoneroError::InvalidWords.into());
+            }
+            seed.extend_from_slice(&(x as u32).to_le_bytes());
+        }
+
+        Self::from_seed(&seed, language)
+    }
+
+    /// Return the words of the mnemonic, separated by spaces.
+    pub fn phrase(&self) -> &str {
+        &self.phrase
+    }
+
+    /// Return the seed encoded by the mnemonic.
+    pub fn seed(&self) -> &[u8] {
+        &self.seed
+    }
+
+    /// Return the language of the mnemonic.
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    /// Derive the private spend and view keys.
+    ///
+    /// The spend key of a 25 word seed is the seed reduced modulo the group
+    /// order, the view key the reduced Keccak-256 of the spend key. MyMonero
+    /// hashes its 16 byte seed into the spend key first, and the hash again
+    /// into the view key.
+    pub fn keys(&self) -> Keys {
+        if self.seed.len() == 32 {
+            let spend = sc_reduce32(self.seed[..].try_into().unwrap());
+            return Keys::from_spend_key(&spend);
+        }
+        let hash = Zeroizing::new(keccak256(&*self.seed));
+        Keys {
+            spend: sc_reduce32(&hash),
+            view: sc_reduce32(&keccak256(hash.as_slice())),
+        }
+    }
+}
+
+impl std::fmt::Debug for MoneroMnemonic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("MoneroMnemonic")
+            .field("language", &self.language)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The Monero network an address belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Network {
+    Mainnet,
+    Testnet,
+    Stagenet,
+}
+
+impl Network {
+    /// Return the prefix of the standard addresses of the network.
+    pub fn address_prefix(self) -> u8 {
+        match self {
+            Network::Mainnet => 18,
+            Network::Testnet => 53,
+            Network::Stagenet => 24,
+        }
+    }
+}
+
+/// The private keys of a Monero wallet.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Keys {
+    spend: Zeroizing<[u8; 32]>,
+    view: Zeroizing<[u8; 32]>,
+}
+
+impl Keys {
+    /// Derive the view key from a private spend key, reducing it first.
+    pub fn from_spend_key(spend: &[u8; 32]) -> Self {
+        let spend = sc_reduce32(spend);
+        let view = sc_reduce32(&keccak256(spend.as_slice()));
+        Self { spend, view }
+    }
+
+    /// Return the private spend key, little endian.
+    pub fn spend_key(&self) -> &[u8; 32] {
+        &self.spend
+    }
+
+    /// Return the private view key, little endian.
+    pub fn view_key(&self) -> &[u8; 32] {
+        &self.view
+    }
+
+    /// Return the public spend key, the compressed ed25519 point.
+    pub fn public_spend_key(&self) -> [u8; 32] {
+        public_key(&self.spend)
+    }
+
+    /// Return the public view key, the compressed ed25519 point.
+    pub fn public_view_key(&self) -> [u8; 32] {
+        public_key(&self.view)
+    }
+
+    /// Return the standard address of the wallet on `network`.
+    pub fn address(&self, network: Network) -> String {
+        let mut data = Vec::with_capacity(1 + 32 + 32 + 4);
+        data.push(network.address_prefix());
+        data.extend_from_slice(&self.public_spend_key());
+        data.extend_from_slice(&self.public_view_key());
+        let checksum = keccak256(&data);
+        data.extend_from_slice(&checksum[..4]);
+        base58_encode(&data)
+    }
+}
+
+impl std::fmt::Debug for Keys {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Keys").finish_non_exhaustive()
+    }
+}
+
+/// Reduce a little endian integer modulo the order of ed25519, in
+/// constant time.
+fn sc_reduce32(bytes: &[u8; 32]) -> Zeroizing<[u8; 32]> {
+    let mut scalar = Scalar::from_bytes_mod_order(*bytes);
+    let reduced = Zeroizing::new(scalar.to_bytes());
+    scalar.zeroize();
+    reduced
+}
+
+/// Multiply the base point by a reduced private key.
+fn public_key(key: &[u8; 32]) -> [u8; 32] {
+    let mut scalar = Scalar::from_bytes_mod_order(*key);
+    let point = EdwardsPoint::mul_base(&scalar);
+    scalar.zeroize();
+    point.compress().to_bytes()
+}
+
+/// Encode bytes with the Base58 of Monero, which encodes blocks of 8
+/// bytes into 11 characters each so that the length does not depend on
+/// the value.
+fn base58_encode(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(8) * 11);
+    for block in data.chunks(8) {
+        let mut value = block
+            .iter()
+            .fold(0u64, |value, byte| (value << 8) | *byte as u64);
+        let mut chars = [BASE58_ALPHABET[0]; 11];
+        let size = BASE58_BLOCK_SIZES[block.len()];
+        for c in chars[..size].iter_mut().rev() {
+            *c = BASE58_ALPHABET[(value % 58) as usize];
+            value /= 58;
+        }
+        encoded.extend(chars[..size].iter().map(|c| *c as char));
+    }
+    encoded
+}
+
+fn prefix(word: &str, len: usize) -> &str {
+    word.char_indices()
+        .nth(len)
+        .map_or(word, |(end, _)| &word[..end])
+}
+
+/// Index among `words` of the checksum word.
+fn checksum_index(words: &[&str], language: Language) -> usize {
+    let prefixes: String = words
+        .iter()
+        .map(|word| prefix(word, language.prefix_len()))
+        .collect();
+    crc32(prefixes.as_bytes()) as usize % words.len()
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::WalletError;
+
+    const PHRASE: &str = "sequence atlas unveil summon pebbles tuesday beer rudely snake rockets \
+        different fuselage woven tagged bested dented vegan hover rapid fawns obvious muppet \
+        randomly seasons randomly";
+
+    #[test]
+    fn test_wordlist() {
+        let words = Language::English.wordlist();
+        assert_eq!(words.len(), 1626);
+        assert!(words.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!((words[0], words[1625]), ("abbey", "zoom"));
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn test_verify() {
+        Language::English.verify().unwrap();
+
+        // the checksum tells words apart by their unique prefixes
+        let words = Language::English.wordlist();
+        let len = Language::English.prefix_len();
+        let prefixes = words
+            .iter()
+            .map(|word| word.chars().take(len).collect::<String>())
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(prefixes.len(), words.len());
+    }
+
+    #[test]
+    fn test_keys() {
+        let mnemonic = MoneroMnemonic::from_phrase(PHRASE, Language::English).unwrap();
+        let keys = mnemonic.keys();
+        assert_eq!(
+            hex::encode(keys.spend_key()),
+            "b0ef6bd527b9b23b9ceef70dc8b4cd1ee83ca14541964e764ad23f5151204f0f"
+        );
+        assert_eq!(
+            hex::encode(keys.view_key()),
+            "42ba20adb337e5eca797565be11c9adb0a8bef8c830bccc2df712535d3b8f608"
+        );
+        assert_eq!(mnemonic.seed(), keys.spend_key());
+        assert_eq!(
+            mnemonic.phrase(),
+            PHRASE.split_whitespace().collect::<Vec<_>>().join(" ")
+        );
+
+        // the first wallet of the Monero functional tests
+        let phrase = "velvet lymph giddy number token physics poetry unquoted nibs useful \
+            sabotage limits benches lifestyle eden nitrogen anvil fewest avoid batch vials \
+            washing fences goat unquoted";
+        let mnemonic = MoneroMnemonic::from_phrase(phrase, Language::English).unwrap();
+        let keys = mnemonic.keys();
+        assert_eq!(
+            hex::encode(keys.spend_key()),
+            "148d78d2aba7dbca5cd8f6abcfb0b3c009ffbdbea1ff373d50ed94d78286640e"
+        );
+        assert_eq!(
+            keys.address(Network::Mainnet),
+            "42ey1afDFnn4886T7196doS9GPMzexD9gXpsZJDwVjeRVdFCSoHnv7KPbBeGpzJBzHRCAs9UxqeoyFQMYbqSWYTfJJQAWDm"
+        );
+    }
+
+    #[test]
+    fn test_reduce() {
+        let order = hex::decode("edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010")
+            .unwrap();
+        let mut bytes: [u8; 32] = order.try_into().unwrap();
+        assert_eq!(*sc_reduce32(&bytes), [0; 32]);
+        bytes[0] += 1;
+        let mut one = [0; 32];
+        one[0] = 1;
+        assert_eq!(*sc_reduce32(&bytes), one);
+        assert_eq!(*sc_reduce32(&one), one);
+    }
+
+    #[test]
+    fn test_mymonero() {
+        let seed: Vec<u8> = (0..16).collect();
+        let mnemonic = MoneroMnemonic::from_seed(&seed, Language::English).unwrap();
+        assert_eq!(
+            mnemonic.phrase(),
+            "amaze buffet cake entrance symptoms tiger lamb maze nestle python dusted faxed faxed"
+        );
+        let parsed = MoneroMnemonic::from_phrase(mnemonic.phrase(), Language::English).unwrap();
+        assert_eq!(parsed.seed(), &seed[..]);
+
+        let keys = parsed.keys();
+        assert_eq!(
+            hex::encode(keys.spend_key()),
+            "0b17585249f228afc549b5ee67823d4ebfc62961bfeb10c1f79c756f7294b003"
+        );
+        assert_eq!(
+            hex::encode(keys.view_key()),
+            "2e8616c7e1e469de319b925fd673bfbcc1b58f5ec82f23ec0cf39c614b453708"
+        );
+    }
+
+    #[test]
+    fn test_generate() {
+        let mnemonic = MoneroMnemonic::generate(Language::English);
+        let parsed = MoneroMnemonic::from_phrase(mnemonic.phrase(), Language::English).unwrap();
+        assert_eq!(parsed.seed(), mnemonic.seed());
+        assert_eq!(parsed.keys().spend_key()[..], mnemonic.seed()[..]);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let checksum = PHRASE.replace("seasons randomly", "seasons rapid");
+        assert_eq!(
+            MoneroMnemonic::from_phrase(&checksum, Language::English),
+            Err(WalletError::Monero(MoneroError::InvalidChecksum))
+        );
+        assert_eq!(
+            MoneroMnemonic::from_phrase("sequence atlas unveil", Language::English),
+            Err(WalletError::Monero(MoneroError::InvalidLength(3)))
+        );
+        assert_eq!(
+            MoneroMnemonic::from_phrase(&PHRASE.replace("atlas", "abandon"), Language::English),
+            Err(WalletError::Monero(MoneroError::UnknownWord(
+                "abandon".into()
+            )))
+        );
+        assert_eq!(
+            MoneroMnemonic::from_seed(&[0; 20], Language::English),
+            Err(WalletError::Monero(MoneroError::InvalidSeedLength(20)))
+        );
+    }
+}
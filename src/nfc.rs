@@ -0,0 +1,367 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # NFC Data Exchange Format
+//!
+//! Encode and decode [NDEF](https://nfc-forum.org/build/specifications) messages
+//! carrying addresses and payment URIs, so they can be written to NFC tags or
+//! exchanged between phones for tap-to-pay style flows. Only the message
+//! bytes are handled here, the transport to the tag is left to the platform.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::nfc::NdefMessage;
+//!
+//! let address = "0x9858EfFD232B4033E47d90003D41EC34EcaEda94";
+//! let bytes = NdefMessage::address(address).to_bytes();
+//!
+//! let message = NdefMessage::from_bytes(&bytes).unwrap();
+//! assert_eq!(message.read_address().unwrap(), address);
+//! ```
+
+use crate::error::Result;
+
+const FLAG_MB: u8 = 0x80;
+const FLAG_ME: u8 = 0x40;
+const FLAG_CF: u8 = 0x20;
+const FLAG_SR: u8 = 0x10;
+const FLAG_IL: u8 = 0x08;
+
+const TNF_WELL_KNOWN: u8 = 0x01;
+const RTD_URI: &[u8] = b"U";
+
+const ETHEREUM_SCHEME: &str = "ethereum:";
+
+/// URI identifier codes defined by the NFC Forum URI Record Type Definition.
+const URI_PREFIXES: [&str; 36] = [
+    "",
+    "http://www.",
+    "https://www.",
+    "http://",
+    "https://",
+    "tel:",
+    "mailto:",
+    "ftp://anonymous:anonymous@",
+    "ftp://ftp.",
+    "ftps://",
+    "sftp://",
+    "smb://",
+    "nfs://",
+    "ftp://",
+    "dav://",
+    "news:",
+    "telnet://",
+    "imap:",
+    "rtsp://",
+    "urn:",
+    "pop:",
+    "sip:",
+    "sips:",
+    "tftp:",
+    "btspp://",
+    "btl2cap://",
+    "btgoep://",
+    "tcpobex://",
+    "irdaobex://",
+    "file://",
+    "urn:epc:id:",
+    "urn:epc:tag:",
+    "urn:epc:pat:",
+    "urn:epc:raw:",
+    "urn:epc:",
+    "urn:nfc:",
+];
+
+/// Error returned when an NDEF message cannot be decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NdefError {
+    /// The message ended before the record was complete.
+    UnexpectedEnd,
+    /// The message does not contain any record.
+    Empty,
+    /// Chunked records are not supported.
+    Chunked,
+    /// The URI record is malformed.
+    InvalidUri,
+}
+
+impl std::fmt::Display for NdefError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            NdefError::UnexpectedEnd => write!(f, "unexpected end of NDEF message"),
+            NdefError::Empty => write!(f, "empty NDEF message"),
+            NdefError::Chunked => write!(f, "chunked NDEF records are not supported"),
+            NdefError::InvalidUri => write!(f, "invalid NDEF URI record"),
+        }
+    }
+}
+
+impl std::error::Error for NdefError {}
+
+/// A single NDEF record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NdefRecord {
+    tnf: u8,
+    record_type: Vec<u8>,
+    id: Vec<u8>,
+    payload: Vec<u8>,
+}
+
+impl NdefRecord {
+    /// Create a new record from its raw parts.
+    pub fn new(tnf: u8, record_type: Vec<u8>, payload: Vec<u8>) -> Self {
+        Self {
+            tnf: tnf & 0x07,
+            record_type,
+            id: Vec::new(),
+            payload,
+        }
+    }
+
+    /// Create a well-known URI record, abbreviating the URI prefix if possible.
+    pub fn uri(uri: &str) -> Self {
+        let (code, prefix) = URI_PREFIXES
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter(|(_, prefix)| uri.starts_with(*prefix))
+            .max_by_key(|(_, prefix)| prefix.len())
+            .unwrap_or((0, &""));
+
+        let mut payload = vec![code as u8];
+        payload.extend_from_slice(&uri.as_bytes()[prefix.len()..]);
+
+        Self::new(TNF_WELL_KNOWN, RTD_URI.to_vec(), payload)
+    }
+
+    /// Return the URI if this is a well-known URI record.
+    pub fn to_uri(&self) -> Option<Result<String>> {
+        if self.tnf != TNF_WELL_KNOWN || self.record_type != RTD_URI {
+            return None;
+        }
+
+        Some(self.decode_uri())
+    }
+
+    fn decode_uri(&self) -> Result<String> {
+        let (code, rest) = self.payload.split_first().ok_or(NdefError::InvalidUri)?;
+        let prefix = URI_PREFIXES
+            .get(*code as usize)
+            .ok_or(NdefError::InvalidUri)?;
+        let rest = std::str::from_utf8(rest).map_err(|_| NdefError::InvalidUri)?;
+
+        Ok(format!("{}{}", prefix, rest))
+    }
+
+    /// Get the type name format.
+    pub fn tnf(&self) -> u8 {
+        self.tnf
+    }
+
+    /// Get the record type.
+    pub fn record_type(&self) -> &[u8] {
+        &self.record_type
+    }
+
+    /// Get the record id.
+    pub fn id(&self) -> &[u8] {
+        &self.id
+    }
+
+    /// Get the payload.
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    fn write(&self, buf: &mut Vec<u8>, first: bool, last: bool) {
+        let short = self.payload.len() <= u8::MAX as usize;
+
+        let mut header = self.tnf;
+        if first {
+            header |= FLAG_MB;
+        }
+        if last {
+            header |= FLAG_ME;
+        }
+        if short {
+            header |= FLAG_SR;
+        }
+        if !self.id.is_empty() {
+            header |= FLAG_IL;
+        }
+
+        buf.push(header);
+        buf.push(self.record_type.len() as u8);
+        if short {
+            buf.push(self.payload.len() as u8);
+        } else {
+            buf.extend_from_slice(&(self.payload.len() as u32).to_be_bytes());
+        }
+        if !self.id.is_empty() {
+            buf.push(self.id.len() as u8);
+        }
+        buf.extend_from_slice(&self.record_type);
+        buf.extend_from_slice(&self.id);
+        buf.extend_from_slice(&self.payload);
+    }
+}
+
+/// An NDEF message, a sequence of records as stored on a tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NdefMessage(Vec<NdefRecord>);
+
+impl NdefMessage {
+    /// Create a new message from the given records.
+    pub fn new(records: Vec<NdefRecord>) -> Self {
+        Self(records)
+    }
+
+    /// Create a message holding an `ethereum:` URI for the given address.
+    pub fn address(address: &str) -> Self {
+        Self::payment_uri(&format!("{}{}", ETHEREUM_SCHEME, address))
+    }
+
+    /// Create a message holding the given payment URI.
+    pub fn payment_uri(uri: &str) -> Self {
+        Self(vec![NdefRecord::uri(uri)])
+    }
+
+    /// Return the records of the message.
+    pub fn records(&self) -> &[NdefRecord] {
+        &self.0
+    }
+
+    /// Return the first URI found in the message.
+    pub fn read_uri(&self) -> Result<String> {
+        self.0
+            .iter()
+            .find_map(|record| record.to_uri())
+            .ok_or(NdefError::Empty)?
+    }
+
+    /// Return the target address of the first `ethereum:` URI in the message.
+    pub fn read_address(&self) -> Result<String> {
+        let uri = self.read_uri()?;
+        let target = uri
+            .strip_prefix(ETHEREUM_SCHEME)
+            .ok_or(NdefError::InvalidUri)?;
+        let target = target.strip_prefix("pay-").unwrap_or(target);
+        let end = target.find(['@', '/', '?']).unwrap_or(target.len());
+
+        if end == 0 {
+            return Err(NdefError::InvalidUri.into());
+        }
+
+        Ok(target[..end].to_string())
+    }
+
+    /// Encode the message into bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let last = self.0.len().saturating_sub(1);
+
+        for (i, record) in self.0.iter().enumerate() {
+            record.write(&mut buf, i == 0, i == last);
+        }
+
+        buf
+    }
+
+    /// Decode a message from bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut records = Vec::new();
+        let mut rest = bytes;
+
+        while let Some((&header, tail)) = rest.split_first() {
+            if header & FLAG_CF != 0 {
+                return Err(NdefError::Chunked.into());
+            }
+            rest = tail;
+
+            let type_len = take(&mut rest, 1)?[0] as usize;
+            let payload_len = if header & FLAG_SR != 0 {
+                take(&mut rest, 1)?[0] as usize
+            } else {
+                u32::from_be_bytes(take(&mut rest, 4)?.try_into().expect("4 bytes")) as usize
+            };
+            let id_len = if header & FLAG_IL != 0 {
+                take(&mut rest, 1)?[0] as usize
+            } else {
+                0
+            };
+
+            records.push(NdefRecord {
+                tnf: header & 0x07,
+                record_type: take(&mut rest, type_len)?.to_vec(),
+                id: take(&mut rest, id_len)?.to_vec(),
+                payload: take(&mut rest, payload_len)?.to_vec(),
+            });
+
+            if header & FLAG_ME != 0 {
+                break;
+            }
+        }
+
+        if records.is_empty() {
+            return Err(NdefError::Empty.into());
+        }
+
+        Ok(Self(records))
+    }
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if bytes.len() < len {
+        return Err(NdefError::UnexpectedEnd.into());
+    }
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+    Ok(head)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uri_record() {
+        let message = NdefMessage::payment_uri("https://example.com");
+        let bytes = message.to_bytes();
+        assert_eq!(hex::encode(&bytes), "d1010c55046578616d706c652e636f6d");
+        assert_eq!(
+            NdefMessage::from_bytes(&bytes).unwrap().read_uri().unwrap(),
+            "https://example.com"
+        );
+    }
+
+    #[test]
+    fn test_address() {
+        let address = "0x9858EfFD232B4033E47d90003D41EC34EcaEda94";
+        let message = NdefMessage::from_bytes(&NdefMessage::address(address).to_bytes()).unwrap();
+        assert_eq!(message.read_address().unwrap(), address);
+
+        let uri = format!("ethereum:pay-{}@1/transfer?value=1", address);
+        let message = NdefMessage::from_bytes(&NdefMessage::payment_uri(&uri).to_bytes()).unwrap();
+        assert_eq!(message.read_uri().unwrap(), uri);
+        assert_eq!(message.read_address().unwrap(), address);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(NdefMessage::from_bytes(&[]).is_err());
+        assert!(NdefMessage::from_bytes(&[0xd1, 0x01, 0x0c, 0x55, 0x04]).is_err());
+        assert!(NdefMessage::payment_uri("https://example.com")
+            .read_address()
+            .is_err());
+    }
+}
@@ -0,0 +1,455 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Portfolio
+//!
+//! The native and ERC-20 balances of a set of accounts across chains.
+//!
+//! Balances are read through the [Multicall3](https://www.multicall3.com)
+//! contract, deployed at the same address on every major chain, so a chain
+//! costs one `eth_call` per few hundred balances instead of one each. The
+//! tokens queried are those of a [`TokenRegistry`], and chains are queried
+//! concurrently, each on its own thread.
+//!
+//! ## Example
+//! ```rust,no_run
+//! # fn run<P: wallet_rust::provider::Provider + Sync>(mainnet: P, base: P) -> wallet_rust::error::Result<()> {
+//! use wallet_rust::{address::Address, chains, portfolio::portfolio, tokens::{TokenList, TokenRegistry}};
+//!
+//! let mut registry = TokenRegistry::new();
+//! registry.add_list(&TokenList::load("tokens.json")?);
+//! let accounts: Vec<Address> = vec!["0x9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F".parse()?];
+//!
+//! let report = portfolio(&[(chains::MAINNET.id, mainnet), (chains::BASE.id, base)], &accounts, &registry)?;
+//! print!("{}", report);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    abi::{decode, Function, ParamType, Token},
+    address::Address,
+    chains::Chain,
+    error::Result,
+    provider::Provider,
+    tokens::{TokenInfo, TokenRegistry},
+    types::U256,
+    units::format_units,
+};
+
+/// Address of the Multicall3 contract.
+pub const MULTICALL3: Address = Address::new([
+    0xca, 0x11, 0xbd, 0xe0, 0x59, 0x77, 0xb3, 0x63, 0x11, 0x67, 0x02, 0x88, 0x62, 0xbe, 0x2a, 0x17,
+    0x39, 0x76, 0xca, 0x11,
+]);
+
+/// Number of balances read by a single call, keeping it under the gas
+/// limit of `eth_call` on public nodes.
+const BATCH_SIZE: usize = 500;
+
+/// Error returned when reading balances.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PortfolioError {
+    /// Multicall3 on the given chain answered with data that is not the
+    /// result of the batch.
+    InvalidResponse(u64),
+}
+
+impl std::fmt::Display for PortfolioError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PortfolioError::InvalidResponse(chain_id) => {
+                write!(f, "Invalid Multicall3 response on chain {}", chain_id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PortfolioError {}
+
+/// What a balance is held in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Asset {
+    /// The native currency of the chain.
+    Native,
+    /// An ERC-20 token.
+    Token(TokenInfo),
+}
+
+/// The balance of an account in an asset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Balance {
+    /// Chain the balance is held on.
+    pub chain_id: u64,
+    /// The account holding the balance.
+    pub account: Address,
+    /// The asset held.
+    pub asset: Asset,
+    /// Amount in base units.
+    pub amount: u128,
+}
+
+impl Balance {
+    /// Format the amount with the decimals and the symbol of the asset.
+    /// Native currencies of unknown chains are taken as 18 decimals.
+    pub fn format_amount(&self) -> String {
+        match &self.asset {
+            Asset::Token(token) => token.format_amount(self.amount),
+            Asset::Native => match Chain::from_id(self.chain_id) {
+                Some(chain) => format!(
+                    "{} {}",
+                    format_units(self.amount, chain.currency.decimals as u32),
+                    chain.currency.symbol
+                ),
+                None => format!("{} native", format_units(self.amount, 18)),
+            },
+        }
+    }
+}
+
+/// The balances of a set of accounts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Portfolio {
+    /// The balances that are not zero, by chain in the order queried, then
+    /// by account, the native currency first.
+    pub balances: Vec<Balance>,
+    /// Tokens whose balance could not be read, such as contracts that are
+    /// not ERC-20 tokens, with their chain.
+    pub failed: Vec<(u64, Address)>,
+}
+
+impl Portfolio {
+    /// Return the balances of an account.
+    pub fn account<'a>(&'a self, account: &'a Address) -> impl Iterator<Item = &'a Balance> {
+        self.balances
+            .iter()
+            .filter(move |balance| balance.account == *account)
+    }
+
+    /// Return the balances on a chain.
+    pub fn chain(&self, chain_id: u64) -> impl Iterator<Item = &Balance> {
+        self.balances
+            .iter()
+            .filter(move |balance| balance.chain_id == chain_id)
+    }
+
+    /// Return the amount of an asset held by all the accounts on a chain,
+    /// the native currency when `token` is `None`.
+    pub fn total(&self, chain_id: u64, token: Option<&Address>) -> u128 {
+        self.chain(chain_id)
+            .filter(|balance| match &balance.asset {
+                Asset::Native => token.is_none(),
+                Asset::Token(info) => token == Some(&info.address),
+            })
+            .map(|balance| balance.amount)
+            .fold(0, u128::saturating_add)
+    }
+}
+
+impl std::fmt::Display for Portfolio {
+    /// Print a balance per line.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for balance in &self.balances {
+            let chain = Chain::from_id(balance.chain_id).map_or_else(
+                || balance.chain_id.to_string(),
+                |chain| chain.name.to_string(),
+            );
+            writeln!(
+                f,
+                "{:<18} {}  {}",
+                chain,
+                balance.account,
+                balance.format_amount()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Read the native and token balances of `accounts` on every chain, given
+/// with its provider. Chains are queried concurrently.
+pub fn portfolio<P: Provider + Sync>(
+    networks: &[(u64, P)],
+    accounts: &[Address],
+    registry: &TokenRegistry,
+) -> Result<Portfolio> {
+    let results: Vec<Result<Portfolio>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = networks
+            .iter()
+            .map(|(chain_id, provider)| {
+                scope.spawn(move || chain_portfolio(provider, *chain_id, accounts, registry))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect()
+    });
+
+    let mut portfolio = Portfolio::default();
+    for result in results {
+        let chain = result?;
+        portfolio.balances.extend(chain.balances);
+        portfolio.failed.extend(chain.failed);
+    }
+    Ok(portfolio)
+}
+
+/// Read the balances on one chain.
+fn chain_portfolio<P: Provider>(
+    provider: &P,
+    chain_id: u64,
+    accounts: &[Address],
+    registry: &TokenRegistry,
+) -> Result<Portfolio> {
+    let get_eth_balance = Function::parse("getEthBalance(address)")?;
+    let balance_of = Function::parse("balanceOf(address)")?;
+    let tokens: Vec<&TokenInfo> = registry.on_chain(chain_id).collect();
+
+    let mut queries = Vec::new();
+    for account in accounts {
+        let argument = [Token::Address(*account)];
+        queries.push((
+            *account,
+            Asset::Native,
+            MULTICALL3,
+            get_eth_balance.encode_input(&argument)?,
+        ));
+        for token in &tokens {
+            queries.push((
+                *account,
+                Asset::Token((*token).clone()),
+                token.address,
+                balance_of.encode_input(&argument)?,
+            ));
+        }
+    }
+
+    let mut portfolio = Portfolio::default();
+    for batch in queries.chunks(BATCH_SIZE) {
+        let calls: Vec<(Address, Vec<u8>)> = batch
+            .iter()
+            .map(|(_, _, target, calldata)| (*target, calldata.clone()))
+            .collect();
+        let amounts = aggregate(provider, chain_id, &calls)?;
+
+        for ((account, asset, target, _), amount) in batch.iter().zip(amounts) {
+            match amount {
+                Some(0) => {}
+                Some(amount) => portfolio.balances.push(Balance {
+                    chain_id,
+                    account: *account,
+                    asset: asset.clone(),
+                    amount,
+                }),
+                None if !portfolio.failed.contains(&(chain_id, *target)) => {
+                    portfolio.failed.push((chain_id, *target))
+                }
+                None => {}
+            }
+        }
+    }
+    Ok(portfolio)
+}
+
+/// Run the calls through Multicall3, allowing them to fail, and decode
+/// the amount each returns. Failed calls and results that are not an
+/// amount are `None`.
+fn aggregate<P: Provider>(
+    provider: &P,
+    chain_id: u64,
+    calls: &[(Address, Vec<u8>)],
+) -> Result<Vec<Option<u128>>> {
+    let aggregate3 = Function::parse("aggregate3((address,bool,bytes)[])")?;
+    let count = calls.len();
+    let calls = calls
+        .iter()
+        .map(|(target, calldata)| {
+            Token::Tuple(vec![
+                Token::Address(*target),
+                Token::Bool(true),
+                Token::Bytes(calldata.clone()),
+            ])
+        })
+        .collect();
+    let output = provider.call(
+        &MULTICALL3,
+        &aggregate3.encode_input(&[Token::Array(calls)])?,
+    )?;
+
+    let invalid = || PortfolioError::InvalidResponse(chain_id);
+    let results = decode(&[ParamType::parse("(bool,bytes)[]")?], &output).map_err(|_| invalid())?;
+    let [Token::Array(results)] = &results[..] else {
+        return Err(invalid().into());
+    };
+    if results.len() != count {
+        return Err(invalid().into());
+    }
+
+    Ok(results
+        .iter()
+        .map(|result| match result {
+            Token::Tuple(fields) => match &fields[..] {
+                [Token::Bool(true), Token::Bytes(data)] if data.len() == 32 => {
+                    U256::from_big_endian(data).try_into().ok()
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        abi::encode,
+        chains,
+        provider::mock::MockProvider,
+        tokens::{TokenInfo, TokenRegistry},
+    };
+    use std::collections::HashMap;
+
+    /// Balances held on a chain, the native ones under [`MULTICALL3`].
+    #[derive(Default)]
+    struct Balances {
+        native: HashMap<Address, u128>,
+        tokens: HashMap<(Address, Address), u128>,
+        broken: Option<Address>,
+    }
+
+    /// A chain answering Multicall3 from the balances it holds.
+    fn chain(balances: Balances) -> MockProvider {
+        MockProvider::new().with_call(move |to, data| {
+            assert_eq!(*to, MULTICALL3);
+            let aggregate3 = Function::parse("aggregate3((address,bool,bytes)[])")?;
+            let [Token::Array(calls)] = &aggregate3.decode_input(data)?[..] else {
+                unreachable!()
+            };
+
+            let results = calls
+                .iter()
+                .map(|call| {
+                    let Token::Tuple(fields) = call else {
+                        unreachable!()
+                    };
+                    let [Token::Address(target), Token::Bool(true), Token::Bytes(calldata)] =
+                        &fields[..]
+                    else {
+                        unreachable!()
+                    };
+                    let account = Address::from_slice(&calldata[16..36]).unwrap();
+                    let amount = match *target {
+                        MULTICALL3 => balances.native.get(&account),
+                        token if Some(token) == balances.broken => {
+                            return Token::Tuple(vec![Token::Bool(false), Token::Bytes(Vec::new())])
+                        }
+                        token => balances.tokens.get(&(token, account)),
+                    };
+                    let amount = U256::from(amount.copied().unwrap_or_default());
+                    Token::Tuple(vec![
+                        Token::Bool(true),
+                        Token::Bytes(amount.to_big_endian().to_vec()),
+                    ])
+                })
+                .collect();
+            encode(
+                &[ParamType::parse("(bool,bytes)[]")?],
+                &[Token::Array(results)],
+            )
+        })
+    }
+
+    fn token(chain_id: u64, byte: u8, symbol: &str) -> TokenInfo {
+        TokenInfo {
+            chain_id,
+            address: Address::new([byte; 20]),
+            name: symbol.to_string(),
+            symbol: symbol.to_string(),
+            decimals: 6,
+            logo_uri: None,
+        }
+    }
+
+    #[test]
+    fn test_portfolio() {
+        let (alice, bob) = (Address::new([0xa1; 20]), Address::new([0xb0; 20]));
+        let mut registry = TokenRegistry::new();
+        registry.insert(token(chains::MAINNET.id, 0x10, "USDC"));
+        registry.insert(token(chains::MAINNET.id, 0x20, "FAKE"));
+        registry.insert(token(chains::BASE.id, 0x30, "USDC"));
+
+        let mut mainnet = Balances {
+            broken: Some(Address::new([0x20; 20])),
+            ..Default::default()
+        };
+        mainnet.native.insert(alice, 1_500_000_000_000_000_000);
+        mainnet
+            .tokens
+            .insert((Address::new([0x10; 20]), alice), 2_000_000);
+        mainnet
+            .tokens
+            .insert((Address::new([0x10; 20]), bob), 500_000);
+        let mut base = Balances::default();
+        base.tokens
+            .insert((Address::new([0x30; 20]), bob), 7_000_000);
+
+        let networks = [
+            (chains::MAINNET.id, chain(mainnet)),
+            (chains::BASE.id, chain(base)),
+        ];
+        let report = portfolio(&networks, &[alice, bob], &registry).unwrap();
+
+        assert_eq!(report.balances.len(), 4);
+        assert_eq!(report.account(&alice).count(), 2);
+        assert_eq!(report.chain(chains::BASE.id).count(), 1);
+        let usdc = Address::new([0x10; 20]);
+        assert_eq!(report.total(chains::MAINNET.id, Some(&usdc)), 2_500_000);
+        assert_eq!(
+            report.total(chains::MAINNET.id, None),
+            1_500_000_000_000_000_000
+        );
+        assert_eq!(
+            report.failed,
+            [(chains::MAINNET.id, Address::new([0x20; 20]))]
+        );
+
+        let lines: Vec<String> = report.to_string().lines().map(str::to_string).collect();
+        assert_eq!(lines[0], format!("Ethereum Mainnet   {}  1.5 ETH", alice));
+        assert_eq!(lines[3], format!("Base               {}  7.0 USDC", bob));
+    }
+
+    #[test]
+    fn test_batches() {
+        let mut registry = TokenRegistry::new();
+        for byte in 1..=200 {
+            registry.insert(token(chains::MAINNET.id, byte, "T"));
+        }
+        let accounts = [
+            Address::new([0xa1; 20]),
+            Address::new([0xb0; 20]),
+            Address::new([0xc0; 20]),
+        ];
+
+        // 603 balance reads split into two Multicall3 batches
+        let networks = [(chains::MAINNET.id, chain(Balances::default()))];
+        let report = portfolio(&networks, &accounts, &registry).unwrap();
+        assert!(report.balances.is_empty());
+        assert_eq!(networks[0].1.count("call"), 2);
+    }
+}
@@ -0,0 +1,50 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Prelude
+//!
+//! The types most applications need to create a wallet, derive its accounts
+//! and sign with them, importable at once:
+//!
+//! ```rust
+//! use wallet_rust::prelude::*;
+//!
+//! let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+//! let wallet = HdWallet::from_phrase(phrase, "").unwrap();
+//! let account: EthereumWallet = wallet.account(0).unwrap();
+//!
+//! let address = account.address();
+//! assert_eq!(address.to_string(), "0x9858EfFD232B4033E47d90003D41EC34EcaEda94");
+//! assert!(MAINNET.address_url(&address).is_some());
+//!
+//! let signature = account.sign_message("hello").unwrap();
+//! assert_eq!(signature.recover_personal("hello").unwrap(), address);
+//! assert_eq!(Amount::parse_ether("1.5").unwrap().to_string(), "1.5");
+//! ```
+//!
+//! The prelude is sealed: items are only added here once their API is
+//! considered stable, and breaking changes to them are reserved for major
+//! releases. The other modules expose the building blocks these are made
+//! of and may change more often.
+
+pub use crate::address::Address;
+pub use crate::bips::bip32::ExtendedKey;
+pub use crate::bips::bip39::{Mnemonic, MnemonicType, Seed};
+pub use crate::bips::wordlists::Language;
+pub use crate::bips::{ChildNumber, DerivationPath};
+pub use crate::chains::{Chain, MAINNET};
+pub use crate::error::WalletError;
+pub use crate::signature::Signature;
+pub use crate::signer::Signer;
+pub use crate::units::{format_ether, format_units, parse_ether, parse_units, Amount, Unit};
+pub use crate::wallet::{Account, EthereumWallet, HdWallet, Wallet};
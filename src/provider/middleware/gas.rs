@@ -0,0 +1,74 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Gas limits and fees filled in from a [`GasStrategy`].
+
+use super::{Layer, Middleware};
+use crate::{
+    error::Result,
+    provider::Provider,
+    transaction::{gas::GasStrategy, TransactionRequest},
+};
+
+/// Puts a [`GasFiller`] over a provider, with a copy of the strategy.
+#[derive(Debug, Clone)]
+pub struct GasLayer<G> {
+    strategy: G,
+}
+
+impl<G: GasStrategy + Clone> GasLayer<G> {
+    /// Price the gas with `strategy`.
+    pub fn new(strategy: G) -> Self {
+        GasLayer { strategy }
+    }
+}
+
+impl<P: Provider, G: GasStrategy + Clone> Layer<P> for GasLayer<G> {
+    type Provider = GasFiller<P, G>;
+
+    fn layer(&self, inner: P) -> Self::Provider {
+        GasFiller::new(inner, self.strategy.clone())
+    }
+}
+
+/// Estimates the gas limit of transaction requests and prices their gas
+/// with a strategy, when they are not set.
+#[derive(Debug, Clone)]
+pub struct GasFiller<P, G> {
+    inner: P,
+    strategy: G,
+}
+
+impl<P: Provider, G: GasStrategy> GasFiller<P, G> {
+    /// Fill in the gas of the transactions sent to `inner` with
+    /// `strategy`.
+    pub fn new(inner: P, strategy: G) -> Self {
+        GasFiller { inner, strategy }
+    }
+}
+
+impl<P: Provider, G: GasStrategy> Middleware for GasFiller<P, G> {
+    type Inner = P;
+
+    fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    fn prepare(&self, request: &mut TransactionRequest) -> Result<()> {
+        if request.gas_limit.is_none() {
+            request.gas_limit = Some(self.inner.estimate_gas(request)?);
+        }
+        request.fill_fees(&self.inner, &self.strategy)?;
+        self.inner.fill_transaction(request)
+    }
+}
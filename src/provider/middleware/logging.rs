@@ -0,0 +1,75 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! A line logged for every request.
+
+use std::time::Instant;
+
+use super::{Layer, Middleware};
+use crate::{error::Result, provider::Provider};
+
+/// Puts a [`Logging`] over a provider, with a copy of the logger.
+#[derive(Debug, Clone)]
+pub struct LoggingLayer<F> {
+    log: F,
+}
+
+impl<F: Fn(&str) + Clone> LoggingLayer<F> {
+    /// Log with `log`, such as a closure printing to stderr or forwarding
+    /// to the `log` crate.
+    pub fn new(log: F) -> Self {
+        LoggingLayer { log }
+    }
+}
+
+impl<P: Provider, F: Fn(&str) + Clone> Layer<P> for LoggingLayer<F> {
+    type Provider = Logging<P, F>;
+
+    fn layer(&self, inner: P) -> Self::Provider {
+        Logging::new(inner, self.log.clone())
+    }
+}
+
+/// Logs the JSON-RPC method and the duration of every request, and the
+/// error of those failing, such as `eth_call took 12ms`.
+#[derive(Debug, Clone)]
+pub struct Logging<P, F> {
+    inner: P,
+    log: F,
+}
+
+impl<P: Provider, F: Fn(&str)> Logging<P, F> {
+    /// Log the requests to `inner` with `log`.
+    pub fn new(inner: P, log: F) -> Self {
+        Logging { inner, log }
+    }
+}
+
+impl<P: Provider, F: Fn(&str)> Middleware for Logging<P, F> {
+    type Inner = P;
+
+    fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    fn around<T>(&self, method: &'static str, request: &dyn Fn(&P) -> Result<T>) -> Result<T> {
+        let start = Instant::now();
+        let result = request(&self.inner);
+        let elapsed = start.elapsed().as_millis();
+        match &result {
+            Ok(_) => (self.log)(&format!("{} took {}ms", method, elapsed)),
+            Err(error) => (self.log)(&format!("{} failed after {}ms: {}", method, elapsed, error)),
+        }
+        result
+    }
+}
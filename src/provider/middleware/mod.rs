@@ -0,0 +1,367 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Middleware
+//!
+//! Providers stacked over one another, each adding a concern to the one
+//! below, in the manner of tower.
+//!
+//! A [`Middleware`] wraps an inner [`Provider`] and is one itself. Every
+//! request goes through [`Middleware::around`], where [`Retry`] and
+//! [`Logging`] act. Transaction requests are filled in on their way down
+//! by [`Middleware::prepare`], where [`NonceManager`] hands out nonces and
+//! [`GasFiller`] prices the gas, and sent by [`Middleware::submit`], where
+//! [`SignerMiddleware`] signs them with its key.
+//!
+//! A [`Layer`] puts a middleware over any provider, and a
+//! [`ProviderBuilder`] stacks layers, the first one added on top.
+//!
+//! ## Example
+//! ```rust,no_run
+//! # fn run<P: wallet_rust::provider::Provider>(node: P) -> wallet_rust::error::Result<()> {
+//! use laron_crypto::PrivateKey;
+//! use std::time::Duration;
+//! use wallet_rust::{address::Address, provider::Provider, transaction::TransactionRequest};
+//! use wallet_rust::provider::middleware::{
+//!     GasLayer, LoggingLayer, NonceLayer, ProviderBuilder, RetryLayer, SignerLayer,
+//! };
+//! use wallet_rust::transaction::gas::FeeHistoryPercentile;
+//!
+//! let key = PrivateKey::from_bytes(&[0x46; 32]).unwrap();
+//! let provider = ProviderBuilder::new()
+//!     .layer(SignerLayer::new(key))
+//!     .layer(NonceLayer)
+//!     .layer(GasLayer::new(FeeHistoryPercentile::default()))
+//!     .layer(RetryLayer::new(3, Duration::from_millis(250)))
+//!     .layer(LoggingLayer::new(|line: &str| eprintln!("{}", line)))
+//!     .provider(node);
+//!
+//! let request = TransactionRequest::new().to(Address::new([0x35; 20])).value(1);
+//! let hash = provider.send_transaction(&request)?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod gas;
+mod logging;
+mod nonce;
+mod retry;
+mod signer;
+
+pub use gas::*;
+pub use logging::*;
+pub use nonce::*;
+pub use retry::*;
+pub use signer::*;
+
+use super::{FeeHistory, Log, LogFilter, Provider, TransactionReceipt};
+use crate::{
+    address::Address,
+    error::Result,
+    transaction::{DecodedTransaction, TransactionRequest},
+    types::B256,
+};
+
+/// A provider wrapping another one. It implements [`Provider`] by handing
+/// every request to the inner provider through its hooks.
+pub trait Middleware {
+    /// The provider below.
+    type Inner: Provider;
+
+    /// Return the provider below.
+    fn inner(&self) -> &Self::Inner;
+
+    /// Run a request on the provider below, given with its JSON-RPC
+    /// method.
+    fn around<T>(
+        &self,
+        _method: &'static str,
+        request: &dyn Fn(&Self::Inner) -> Result<T>,
+    ) -> Result<T> {
+        request(self.inner())
+    }
+
+    /// Fill in fields of a transaction request, then let the provider below
+    /// fill in its own.
+    fn prepare(&self, request: &mut TransactionRequest) -> Result<()> {
+        self.inner().fill_transaction(request)
+    }
+
+    /// Send a transaction request.
+    fn submit(&self, request: &TransactionRequest) -> Result<B256> {
+        self.inner().send_transaction(request)
+    }
+}
+
+impl<M: Middleware> Provider for M {
+    fn get_code(&self, address: &Address) -> Result<Vec<u8>> {
+        self.around("eth_getCode", &|inner| inner.get_code(address))
+    }
+
+    fn call(&self, to: &Address, data: &[u8]) -> Result<Vec<u8>> {
+        self.around("eth_call", &|inner| inner.call(to, data))
+    }
+
+    fn transaction_count(&self, address: &Address) -> Result<u64> {
+        self.around("eth_getTransactionCount", &|inner| {
+            inner.transaction_count(address)
+        })
+    }
+
+    fn chain_id(&self) -> Result<u64> {
+        self.around("eth_chainId", &|inner| inner.chain_id())
+    }
+
+    fn estimate_gas(&self, tx: &TransactionRequest) -> Result<u64> {
+        self.around("eth_estimateGas", &|inner| inner.estimate_gas(tx))
+    }
+
+    fn gas_price(&self) -> Result<u128> {
+        self.around("eth_gasPrice", &|inner| inner.gas_price())
+    }
+
+    fn base_fee_per_gas(&self) -> Result<Option<u128>> {
+        self.around("eth_getBlockByNumber", &|inner| inner.base_fee_per_gas())
+    }
+
+    fn max_priority_fee_per_gas(&self) -> Result<u128> {
+        self.around("eth_maxPriorityFeePerGas", &|inner| {
+            inner.max_priority_fee_per_gas()
+        })
+    }
+
+    fn fee_history(&self, block_count: u64, percentiles: &[f64]) -> Result<FeeHistory> {
+        self.around("eth_feeHistory", &|inner| {
+            inner.fee_history(block_count, percentiles)
+        })
+    }
+
+    fn transaction_by_hash(&self, hash: &B256) -> Result<Option<DecodedTransaction>> {
+        self.around("eth_getTransactionByHash", &|inner| {
+            inner.transaction_by_hash(hash)
+        })
+    }
+
+    fn pending_transaction_count(&self, address: &Address) -> Result<u64> {
+        self.around("eth_getTransactionCount", &|inner| {
+            inner.pending_transaction_count(address)
+        })
+    }
+
+    fn pool_nonces(&self, address: &Address) -> Result<Vec<u64>> {
+        self.around("txpool_contentFrom", &|inner| inner.pool_nonces(address))
+    }
+
+    fn transaction_receipt(&self, hash: &B256) -> Result<Option<TransactionReceipt>> {
+        self.around("eth_getTransactionReceipt", &|inner| {
+            inner.transaction_receipt(hash)
+        })
+    }
+
+    fn block_number(&self) -> Result<u64> {
+        self.around("eth_blockNumber", &|inner| inner.block_number())
+    }
+
+    fn finalized_block_number(&self) -> Result<u64> {
+        self.around("eth_getBlockByNumber", &|inner| {
+            inner.finalized_block_number()
+        })
+    }
+
+    fn logs(&self, filter: &LogFilter) -> Result<Vec<Log>> {
+        self.around("eth_getLogs", &|inner| inner.logs(filter))
+    }
+
+    fn block_transactions(&self, number: u64) -> Result<Vec<DecodedTransaction>> {
+        self.around("eth_getBlockByNumber", &|inner| {
+            inner.block_transactions(number)
+        })
+    }
+
+    fn fill_transaction(&self, request: &mut TransactionRequest) -> Result<()> {
+        self.prepare(request)
+    }
+
+    fn send_transaction(&self, request: &TransactionRequest) -> Result<B256> {
+        self.submit(request)
+    }
+
+    fn send_raw_transaction(&self, raw: &[u8]) -> Result<B256> {
+        self.around("eth_sendRawTransaction", &|inner| {
+            inner.send_raw_transaction(raw)
+        })
+    }
+}
+
+/// Puts a middleware over a provider.
+pub trait Layer<P> {
+    /// The middleware.
+    type Provider: Provider;
+
+    /// Put the middleware over `inner`.
+    fn layer(&self, inner: P) -> Self::Provider;
+}
+
+/// The layer leaving a provider as is.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Identity;
+
+impl<P: Provider> Layer<P> for Identity {
+    type Provider = P;
+
+    fn layer(&self, inner: P) -> P {
+        inner
+    }
+}
+
+/// Two layers, `outer` put over `inner`.
+#[derive(Debug, Clone, Copy)]
+pub struct Stack<Inner, Outer> {
+    inner: Inner,
+    outer: Outer,
+}
+
+impl<P, Inner: Layer<P>, Outer: Layer<Inner::Provider>> Layer<P> for Stack<Inner, Outer> {
+    type Provider = Outer::Provider;
+
+    fn layer(&self, inner: P) -> Self::Provider {
+        self.outer.layer(self.inner.layer(inner))
+    }
+}
+
+/// Stacks layers over a provider, the first one added on top.
+#[derive(Debug, Clone, Copy)]
+pub struct ProviderBuilder<L> {
+    layer: L,
+}
+
+impl ProviderBuilder<Identity> {
+    /// Start an empty stack.
+    pub fn new() -> Self {
+        ProviderBuilder { layer: Identity }
+    }
+}
+
+impl Default for ProviderBuilder<Identity> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<L> ProviderBuilder<L> {
+    /// Add a layer below the ones already added.
+    pub fn layer<T>(self, layer: T) -> ProviderBuilder<Stack<T, L>> {
+        ProviderBuilder {
+            layer: Stack {
+                inner: layer,
+                outer: self.layer,
+            },
+        }
+    }
+
+    /// Put the stack over a provider.
+    pub fn provider<P>(&self, provider: P) -> L::Provider
+    where
+        L: Layer<P>,
+    {
+        self.layer.layer(provider)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        provider::mock::MockProvider,
+        signer::Signer,
+        transaction::{
+            gas::{Fixed, GasFees},
+            Transaction, TypedTransaction,
+        },
+    };
+    use laron_crypto::PrivateKey;
+    use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+    #[test]
+    fn test_stack() {
+        let key = PrivateKey::from_bytes(&[0x46; 32]).unwrap();
+        let lines = Rc::new(RefCell::new(Vec::new()));
+        let log = {
+            let lines = lines.clone();
+            move |line: &str| lines.borrow_mut().push(line.to_string())
+        };
+        let fees = GasFees::Eip1559 {
+            max_fee_per_gas: 30_000_000_000,
+            max_priority_fee_per_gas: 2_000_000_000,
+        };
+        // one transaction of the key pending
+        let address = key.address().unwrap();
+        let node = MockProvider {
+            nonces: Some(HashMap::from([(address, 6)])),
+            pool: Some(HashMap::from([(address, vec![6])])),
+            chain_id: Some(1),
+            gas: Some(21_000),
+            ..Default::default()
+        };
+        let provider = ProviderBuilder::new()
+            .layer(SignerLayer::new(key.clone()))
+            .layer(NonceLayer)
+            .layer(GasLayer::new(Fixed(fees)))
+            .layer(LoggingLayer::new(log))
+            .provider(node.clone());
+
+        let request = TransactionRequest::new().to(Address::new([0x35; 20]));
+        for value in [1, 2] {
+            let request = request.clone().value(value);
+            let hash = provider.send_transaction(&request).unwrap();
+            let raw = node.sent().last().unwrap().clone();
+            let decoded = Transaction::decode(&hex::encode(raw)).unwrap();
+            assert_eq!(decoded.hash, hash);
+            assert_eq!(decoded.from, key.address().unwrap());
+            let TypedTransaction::Eip1559(tx) = decoded.transaction else {
+                panic!("not an EIP-1559 transaction");
+            };
+            assert_eq!(
+                (tx.chain_id, tx.nonce, tx.value),
+                (1, 6 + value as u64, value)
+            );
+            assert_eq!(tx.max_fee_per_gas, 30_000_000_000);
+        }
+
+        // the second nonce is counted by the manager
+        let methods: Vec<String> = lines
+            .borrow()
+            .iter()
+            .map(|line| line.split(' ').next().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            methods,
+            [
+                "eth_getTransactionCount",
+                "eth_estimateGas",
+                "eth_chainId",
+                "eth_sendRawTransaction",
+                "eth_estimateGas",
+                "eth_chainId",
+                "eth_sendRawTransaction",
+            ]
+        );
+        assert!(lines.borrow()[0].ends_with("ms"));
+
+        let other = TransactionRequest::new().from(Address::new([0x9d; 20]));
+        let error = provider.send_transaction(&other).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("The transaction request is from"));
+    }
+}
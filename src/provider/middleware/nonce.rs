@@ -0,0 +1,192 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Nonces handed out without waiting for the node to see the transactions.
+
+use std::{collections::HashMap, sync::Mutex};
+
+use super::{Layer, Middleware};
+use crate::{
+    address::Address,
+    error::{Result, WalletError},
+    provider::{Provider, ProviderError},
+    transaction::{RequestError, TransactionRequest},
+};
+
+/// Puts a [`NonceManager`] over a provider.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NonceLayer;
+
+impl<P: Provider> Layer<P> for NonceLayer {
+    type Provider = NonceManager<P>;
+
+    fn layer(&self, inner: P) -> Self::Provider {
+        NonceManager::new(inner)
+    }
+}
+
+/// Hands out the nonces of the senders in sequence. The first one is read
+/// from the node, counting the transactions it has pending, the next ones
+/// are counted locally, so transactions sent in a burst do not reuse a
+/// nonce before the node has seen the previous one.
+///
+/// A nonce is only taken once the layers below filled the request, a
+/// request failing there leaves it to the next one.
+#[derive(Debug)]
+pub struct NonceManager<P> {
+    inner: P,
+    nonces: Mutex<HashMap<Address, u64>>,
+}
+
+impl<P: Provider> NonceManager<P> {
+    /// Manage the nonces of the transactions sent to `inner`.
+    pub fn new(inner: P) -> Self {
+        NonceManager {
+            inner,
+            nonces: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Forget the next nonce of an address, to read it from the node again,
+    /// such as after a transaction failed to broadcast.
+    pub fn reset(&self, address: &Address) {
+        self.nonces.lock().unwrap().remove(address);
+    }
+
+    /// Read the next nonce of an address from the node, from the latest
+    /// block if it does not report its pending transactions.
+    fn read_nonce(&self, address: &Address) -> Result<u64> {
+        match self.inner.pending_transaction_count(address) {
+            Err(WalletError::Provider(ProviderError::Unsupported(_))) => {
+                self.inner.transaction_count(address)
+            }
+            result => result,
+        }
+    }
+}
+
+impl<P: Provider> Middleware for NonceManager<P> {
+    type Inner = P;
+
+    fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Set the next nonce of the sender, which must be set.
+    fn prepare(&self, request: &mut TransactionRequest) -> Result<()> {
+        if request.nonce.is_some() {
+            return self.inner.fill_transaction(request);
+        }
+
+        let from = request.from.ok_or(RequestError::MissingField("from"))?;
+        // held until the request is filled, so no other request takes the
+        // nonce while it may still be handed back
+        let mut nonces = self.nonces.lock().unwrap();
+        let nonce = match nonces.get(&from) {
+            Some(nonce) => *nonce,
+            None => self.read_nonce(&from)?,
+        };
+
+        request.nonce = Some(nonce);
+        if let Err(error) = self.inner.fill_transaction(request) {
+            request.nonce = None;
+            return Err(error);
+        }
+        nonces.insert(from, nonce + 1);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::mock::MockProvider;
+    use std::collections::HashMap;
+
+    /// A node with one pending transaction per sender, and refusing to
+    /// fill requests without gas limit.
+    fn node() -> MockProvider {
+        let (alice, bob) = (Address::new([5; 20]), Address::new([9; 20]));
+        let node = MockProvider {
+            nonces: Some(HashMap::from([(alice, 5), (bob, 9)])),
+            pool: Some(HashMap::from([(alice, vec![5]), (bob, vec![9])])),
+            ..Default::default()
+        };
+        node.with_fill(|request| match request.gas_limit {
+            Some(_) => Ok(()),
+            None => Err(RequestError::MissingField("gasLimit").into()),
+        })
+    }
+
+    fn next_nonce<P: Provider>(provider: &P, request: &TransactionRequest) -> Result<u64> {
+        let mut request = request.clone();
+        provider.fill_transaction(&mut request)?;
+        Ok(request.nonce.unwrap())
+    }
+
+    #[test]
+    fn test_nonces() {
+        let manager = NonceManager::new(node());
+        let (alice, bob) = (Address::new([5; 20]), Address::new([9; 20]));
+        let request = TransactionRequest::new().from(alice).gas_limit(21_000);
+
+        // the pending transaction is counted
+        assert_eq!(next_nonce(&manager, &request).unwrap(), 6);
+        assert_eq!(next_nonce(&manager, &request).unwrap(), 7);
+        assert_eq!(
+            next_nonce(&manager, &request.clone().from(bob)).unwrap(),
+            10
+        );
+        // a nonce set is kept and does not move the sequence
+        assert_eq!(next_nonce(&manager, &request.clone().nonce(1)).unwrap(), 1);
+        assert_eq!(next_nonce(&manager, &request).unwrap(), 8);
+        assert_eq!(manager.inner().count("pending_transaction_count"), 2);
+
+        manager.reset(&alice);
+        assert_eq!(next_nonce(&manager, &request).unwrap(), 6);
+        assert_eq!(manager.inner().count("pending_transaction_count"), 3);
+
+        let error = next_nonce(&manager, &TransactionRequest::new()).unwrap_err();
+        assert_eq!(error.to_string(), "The transaction request has no from");
+    }
+
+    #[test]
+    fn test_failed_fill() {
+        let manager = NonceManager::new(node());
+        let request = TransactionRequest::new().from(Address::new([5; 20]));
+
+        let mut failed = request.clone();
+        assert!(manager.fill_transaction(&mut failed).is_err());
+        assert_eq!(failed.nonce, None);
+        // the nonce of the failed request is handed out again
+        assert_eq!(
+            next_nonce(&manager, &request.clone().gas_limit(21_000)).unwrap(),
+            6
+        );
+        assert!(manager.fill_transaction(&mut failed).is_err());
+        assert_eq!(next_nonce(&manager, &request.gas_limit(21_000)).unwrap(), 7);
+    }
+
+    #[test]
+    fn test_latest_nonce() {
+        // a node that does not report its pending transactions
+        let node = MockProvider {
+            nonces: Some(HashMap::from([(Address::new([5; 20]), 5)])),
+            ..Default::default()
+        };
+        let manager = NonceManager::new(node);
+        let request = TransactionRequest::new().from(Address::new([5; 20]));
+        assert_eq!(next_nonce(&manager, &request).unwrap(), 5);
+        assert_eq!(next_nonce(&manager, &request).unwrap(), 6);
+    }
+}
@@ -0,0 +1,144 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Requests retried after transient failures.
+
+use std::time::Duration;
+
+use super::{Layer, Middleware};
+use crate::{
+    error::{Result, WalletError},
+    provider::{Provider, ProviderError},
+};
+
+/// Puts a [`Retry`] over a provider.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryLayer {
+    attempts: u32,
+    backoff: Duration,
+}
+
+impl RetryLayer {
+    /// Make up to `attempts` attempts, waiting `backoff` after the first
+    /// failure and twice as long after each next one.
+    pub fn new(attempts: u32, backoff: Duration) -> Self {
+        RetryLayer { attempts, backoff }
+    }
+}
+
+impl<P: Provider> Layer<P> for RetryLayer {
+    type Provider = Retry<P>;
+
+    fn layer(&self, inner: P) -> Self::Provider {
+        Retry::new(inner, self.attempts, self.backoff)
+    }
+}
+
+/// Retries requests failed by a transport error or a rate limit with an
+/// exponential backoff.
+///
+/// Other errors fail right away, and broadcasts are not retried: a
+/// transaction that reached the node before the error would come back as
+/// already known.
+#[derive(Debug, Clone)]
+pub struct Retry<P> {
+    inner: P,
+    attempts: u32,
+    backoff: Duration,
+}
+
+impl<P: Provider> Retry<P> {
+    /// Retry the requests to `inner`, see [`RetryLayer::new`].
+    pub fn new(inner: P, attempts: u32, backoff: Duration) -> Self {
+        Retry {
+            inner,
+            attempts: attempts.max(1),
+            backoff,
+        }
+    }
+}
+
+impl<P: Provider> Middleware for Retry<P> {
+    type Inner = P;
+
+    fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    fn around<T>(&self, method: &'static str, request: &dyn Fn(&P) -> Result<T>) -> Result<T> {
+        let mut delay = self.backoff;
+        let mut attempt = 1;
+        loop {
+            match request(&self.inner) {
+                Err(WalletError::Provider(
+                    ProviderError::Transport(_) | ProviderError::RateLimited,
+                )) if attempt < self.attempts && method != "eth_sendRawTransaction" => {
+                    std::thread::sleep(delay);
+                    delay = delay.saturating_mul(2);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{address::Address, provider::mock::MockProvider};
+    use std::collections::HashMap;
+
+    /// A node failing its first requests, alternating transport errors and
+    /// rate limits, and reverting the calls with data.
+    fn node(failures: usize) -> MockProvider {
+        let errors = [
+            ProviderError::Transport("connection reset".to_string()),
+            ProviderError::RateLimited,
+        ];
+        let node = MockProvider {
+            nonces: Some(HashMap::from([(Address::new([0x9d; 20]), 7)])),
+            ..Default::default()
+        };
+        node.with_failures(errors.into_iter().cycle().take(failures))
+            .with_call(|_, data| match data.is_empty() {
+                true => Ok(Vec::new()),
+                false => Err(ProviderError::Reverted(data.to_vec()).into()),
+            })
+    }
+
+    #[test]
+    fn test_retry() {
+        let address = Address::new([0x9d; 20]);
+        let layer = RetryLayer::new(3, Duration::from_millis(1));
+
+        let provider = layer.layer(node(2));
+        assert_eq!(provider.transaction_count(&address).unwrap(), 7);
+        assert_eq!(provider.inner().requests().len(), 3);
+
+        let provider = layer.layer(node(3));
+        assert!(provider.transaction_count(&address).is_err());
+        assert_eq!(provider.inner().requests().len(), 3);
+
+        // neither unsupported methods, reverts nor broadcasts are retried
+        let provider = layer.layer(node(0));
+        assert!(provider.chain_id().is_err());
+        assert_eq!(provider.inner().requests().len(), 1);
+        let provider = layer.layer(node(0));
+        assert!(provider.call(&address, &[0x08]).is_err());
+        assert_eq!(provider.inner().requests().len(), 1);
+        let provider = layer.layer(node(1));
+        assert!(provider.send_raw_transaction(&[0xc0]).is_err());
+        assert_eq!(provider.inner().requests().len(), 1);
+    }
+}
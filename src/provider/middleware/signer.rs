@@ -0,0 +1,93 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Signing of the transactions sent through the stack.
+
+use super::{Layer, Middleware};
+use crate::{
+    error::Result,
+    provider::Provider,
+    signer::Signer,
+    transaction::{RequestError, TransactionRequest},
+    types::B256,
+};
+
+/// Puts a [`SignerMiddleware`] over a provider, with a copy of the signer.
+#[derive(Debug, Clone)]
+pub struct SignerLayer<S> {
+    signer: S,
+}
+
+impl<S: Signer + Clone> SignerLayer<S> {
+    /// Sign with `signer`.
+    pub fn new(signer: S) -> Self {
+        SignerLayer { signer }
+    }
+}
+
+impl<P: Provider, S: Signer + Clone> Layer<P> for SignerLayer<S> {
+    type Provider = SignerMiddleware<P, S>;
+
+    fn layer(&self, inner: P) -> Self::Provider {
+        SignerMiddleware::new(inner, self.signer.clone())
+    }
+}
+
+/// Sends transaction requests from the address of a signer: they are filled
+/// in by the providers below, signed, and broadcast raw.
+#[derive(Debug, Clone)]
+pub struct SignerMiddleware<P, S> {
+    inner: P,
+    signer: S,
+}
+
+impl<P: Provider, S: Signer> SignerMiddleware<P, S> {
+    /// Sign the transactions sent to `inner` with `signer`.
+    pub fn new(inner: P, signer: S) -> Self {
+        SignerMiddleware { inner, signer }
+    }
+
+    /// Return the signer.
+    pub fn signer(&self) -> &S {
+        &self.signer
+    }
+}
+
+impl<P: Provider, S: Signer> Middleware for SignerMiddleware<P, S> {
+    type Inner = P;
+
+    fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Set the sender to the signer, refusing requests from another
+    /// address.
+    fn prepare(&self, request: &mut TransactionRequest) -> Result<()> {
+        let address = self.signer.address()?;
+        match request.from {
+            Some(expected) if expected != address => {
+                return Err(RequestError::SignerMismatch {
+                    expected,
+                    actual: address,
+                }
+                .into())
+            }
+            _ => request.from = Some(address),
+        }
+        self.inner.fill_transaction(request)
+    }
+
+    fn submit(&self, request: &TransactionRequest) -> Result<B256> {
+        request.send(&self.inner, &self.signer)
+    }
+}
@@ -0,0 +1,307 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! A [`Provider`] for the tests, answering from canned responses.
+//!
+//! A method answers once its response is loaded and fails with
+//! [`ProviderError::Unsupported`] otherwise, as a node without it would.
+//! Loaded tables answer for the keys they lack as a node would for an
+//! unknown account, block or transaction: no code, nonce 0, no receipt.
+//!
+//! Every request is recorded with its arguments, the calls and the
+//! broadcasts also as bytes. Clones share the record, so a provider moved
+//! into a middleware stack can still be inspected.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use super::{FeeHistory, Log, LogFilter, Provider, ProviderError, TransactionReceipt};
+use crate::{
+    address::Address,
+    error::Result,
+    transaction::{DecodedTransaction, TransactionRequest},
+    types::B256,
+    utils::keccak256,
+};
+
+type CallFn = dyn Fn(&Address, &[u8]) -> Result<Vec<u8>> + Send + Sync;
+type FillFn = dyn Fn(&mut TransactionRequest) -> Result<()> + Send + Sync;
+
+/// A node answering from the responses loaded in its fields.
+#[derive(Clone, Default)]
+pub(crate) struct MockProvider {
+    /// Code deployed at each address.
+    pub code: HashMap<Address, Vec<u8>>,
+    /// Output of `eth_call` for a target and calldata.
+    pub calls: HashMap<(Address, Vec<u8>), Vec<u8>>,
+    /// Answers the calls missing from `calls`.
+    pub call: Option<Arc<CallFn>>,
+    /// Nonce of each address at the latest block.
+    pub nonces: Option<HashMap<Address, u64>>,
+    /// Nonces each address has in the pool. The pending nonce follows the
+    /// ones of the pool from the latest nonce.
+    pub pool: Option<HashMap<Address, Vec<u64>>>,
+    pub chain_id: Option<u64>,
+    /// Gas estimated for any transaction.
+    pub gas: Option<u64>,
+    pub gas_price: Option<u128>,
+    /// Base fee of the latest block, `Some(None)` on a chain without
+    /// EIP-1559.
+    pub base_fee: Option<Option<u128>>,
+    pub priority_fee: Option<u128>,
+    pub fee_history: Option<FeeHistory>,
+    /// Transactions known to the node, pending or mined.
+    pub transactions: Option<HashMap<B256, DecodedTransaction>>,
+    pub receipts: Option<HashMap<B256, TransactionReceipt>>,
+    pub block_number: Option<u64>,
+    pub finalized_block_number: Option<u64>,
+    /// Logs of the chain, filtered for each query.
+    pub logs: Option<Vec<Log>>,
+    /// Transactions of each block.
+    pub blocks: Option<HashMap<u64, Vec<DecodedTransaction>>>,
+    /// Fills in transaction requests, left as they are without it.
+    pub fill: Option<Arc<FillFn>>,
+    /// What the provider has been asked, shared by its clones.
+    pub record: Arc<Mutex<Record>>,
+}
+
+/// What a [`MockProvider`] has been asked.
+#[derive(Default)]
+pub(crate) struct Record {
+    requests: Vec<String>,
+    calls: Vec<(Address, Vec<u8>)>,
+    sent: Vec<Vec<u8>>,
+    failures: VecDeque<ProviderError>,
+}
+
+impl MockProvider {
+    /// Return a provider without any response loaded.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer the calls missing from `calls` with `call`.
+    pub fn with_call(
+        mut self,
+        call: impl Fn(&Address, &[u8]) -> Result<Vec<u8>> + Send + Sync + 'static,
+    ) -> Self {
+        self.call = Some(Arc::new(call));
+        self
+    }
+
+    /// Fill in transaction requests with `fill`.
+    pub fn with_fill(
+        mut self,
+        fill: impl Fn(&mut TransactionRequest) -> Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        self.fill = Some(Arc::new(fill));
+        self
+    }
+
+    /// Fail the next requests with `failures`, one each, before answering.
+    pub fn with_failures(self, failures: impl IntoIterator<Item = ProviderError>) -> Self {
+        self.lock().failures.extend(failures);
+        self
+    }
+
+    /// Return the requests received, each a method with its arguments such
+    /// as `fee_history(5, [75.0])`.
+    pub fn requests(&self) -> Vec<String> {
+        self.lock().requests.clone()
+    }
+
+    /// Return the number of requests of a method.
+    pub fn count(&self, method: &str) -> usize {
+        self.lock()
+            .requests
+            .iter()
+            .filter(|request| request.split('(').next() == Some(method))
+            .count()
+    }
+
+    /// Return the targets and calldata of the calls received.
+    pub fn received_calls(&self) -> Vec<(Address, Vec<u8>)> {
+        self.lock().calls.clone()
+    }
+
+    /// Return the raw transactions broadcast.
+    pub fn sent(&self) -> Vec<Vec<u8>> {
+        self.lock().sent.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Record> {
+        self.record.lock().unwrap()
+    }
+
+    /// Record a request, failing it if a failure is queued.
+    fn request(&self, request: String) -> Result<()> {
+        let mut record = self.lock();
+        record.requests.push(request);
+        match record.failures.pop_front() {
+            Some(failure) => Err(failure.into()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Return a loaded response, or fail as a node without the method.
+fn loaded<T: Clone>(response: &Option<T>, method: &'static str) -> Result<T> {
+    response
+        .clone()
+        .ok_or_else(|| ProviderError::Unsupported(method).into())
+}
+
+impl Provider for MockProvider {
+    fn get_code(&self, address: &Address) -> Result<Vec<u8>> {
+        self.request(format!("get_code({})", address))?;
+        Ok(self.code.get(address).cloned().unwrap_or_default())
+    }
+
+    fn call(&self, to: &Address, data: &[u8]) -> Result<Vec<u8>> {
+        self.request(format!("call({}, 0x{})", to, hex::encode(data)))?;
+        self.lock().calls.push((*to, data.to_vec()));
+        match (self.calls.get(&(*to, data.to_vec())), &self.call) {
+            (Some(output), _) => Ok(output.clone()),
+            (None, Some(call)) => call(to, data),
+            (None, None) => Err(ProviderError::Unsupported("eth_call").into()),
+        }
+    }
+
+    fn transaction_count(&self, address: &Address) -> Result<u64> {
+        self.request(format!("transaction_count({})", address))?;
+        let nonces = self
+            .nonces
+            .as_ref()
+            .ok_or(ProviderError::Unsupported("eth_getTransactionCount"))?;
+        Ok(nonces.get(address).copied().unwrap_or_default())
+    }
+
+    fn chain_id(&self) -> Result<u64> {
+        self.request("chain_id".to_string())?;
+        loaded(&self.chain_id, "eth_chainId")
+    }
+
+    fn estimate_gas(&self, tx: &TransactionRequest) -> Result<u64> {
+        self.request(format!("estimate_gas({:?})", tx))?;
+        loaded(&self.gas, "eth_estimateGas")
+    }
+
+    fn gas_price(&self) -> Result<u128> {
+        self.request("gas_price".to_string())?;
+        loaded(&self.gas_price, "eth_gasPrice")
+    }
+
+    fn base_fee_per_gas(&self) -> Result<Option<u128>> {
+        self.request("base_fee_per_gas".to_string())?;
+        loaded(&self.base_fee, "eth_getBlockByNumber")
+    }
+
+    fn max_priority_fee_per_gas(&self) -> Result<u128> {
+        self.request("max_priority_fee_per_gas".to_string())?;
+        loaded(&self.priority_fee, "eth_maxPriorityFeePerGas")
+    }
+
+    fn fee_history(&self, block_count: u64, percentiles: &[f64]) -> Result<FeeHistory> {
+        self.request(format!("fee_history({}, {:?})", block_count, percentiles))?;
+        loaded(&self.fee_history, "eth_feeHistory")
+    }
+
+    fn transaction_by_hash(&self, hash: &B256) -> Result<Option<DecodedTransaction>> {
+        self.request(format!("transaction_by_hash({:?})", hash))?;
+        let transactions = self
+            .transactions
+            .as_ref()
+            .ok_or(ProviderError::Unsupported("eth_getTransactionByHash"))?;
+        Ok(transactions.get(hash).cloned())
+    }
+
+    fn pending_transaction_count(&self, address: &Address) -> Result<u64> {
+        self.request(format!("pending_transaction_count({})", address))?;
+        let pool = self
+            .pool
+            .as_ref()
+            .ok_or(ProviderError::Unsupported("eth_getTransactionCount"))?;
+        let pool = pool.get(address).map(Vec::as_slice).unwrap_or_default();
+        let latest = self.nonces.as_ref().and_then(|nonces| nonces.get(address));
+        let mut nonce = latest.copied().unwrap_or_default();
+        while pool.contains(&nonce) {
+            nonce += 1;
+        }
+        Ok(nonce)
+    }
+
+    fn pool_nonces(&self, address: &Address) -> Result<Vec<u64>> {
+        self.request(format!("pool_nonces({})", address))?;
+        let pool = self
+            .pool
+            .as_ref()
+            .ok_or(ProviderError::Unsupported("txpool_contentFrom"))?;
+        Ok(pool.get(address).cloned().unwrap_or_default())
+    }
+
+    fn transaction_receipt(&self, hash: &B256) -> Result<Option<TransactionReceipt>> {
+        self.request(format!("transaction_receipt({:?})", hash))?;
+        let receipts = self
+            .receipts
+            .as_ref()
+            .ok_or(ProviderError::Unsupported("eth_getTransactionReceipt"))?;
+        Ok(receipts.get(hash).cloned())
+    }
+
+    fn block_number(&self) -> Result<u64> {
+        self.request("block_number".to_string())?;
+        loaded(&self.block_number, "eth_blockNumber")
+    }
+
+    fn finalized_block_number(&self) -> Result<u64> {
+        self.request("finalized_block_number".to_string())?;
+        loaded(&self.finalized_block_number, "eth_getBlockByNumber")
+    }
+
+    fn logs(&self, filter: &LogFilter) -> Result<Vec<Log>> {
+        self.request(format!("logs({:?})", filter))?;
+        let logs = self
+            .logs
+            .as_ref()
+            .ok_or(ProviderError::Unsupported("eth_getLogs"))?;
+        Ok(logs
+            .iter()
+            .filter(|log| filter.matches(log))
+            .cloned()
+            .collect())
+    }
+
+    fn block_transactions(&self, number: u64) -> Result<Vec<DecodedTransaction>> {
+        self.request(format!("block_transactions({})", number))?;
+        let blocks = self
+            .blocks
+            .as_ref()
+            .ok_or(ProviderError::Unsupported("eth_getBlockByNumber"))?;
+        Ok(blocks.get(&number).cloned().unwrap_or_default())
+    }
+
+    fn fill_transaction(&self, request: &mut TransactionRequest) -> Result<()> {
+        match &self.fill {
+            Some(fill) => fill(request),
+            None => Ok(()),
+        }
+    }
+
+    fn send_raw_transaction(&self, raw: &[u8]) -> Result<B256> {
+        self.request(format!("send_raw_transaction(0x{})", hex::encode(raw)))?;
+        self.lock().sent.push(raw.to_vec());
+        Ok(B256::new(keccak256(raw)))
+    }
+}
@@ -0,0 +1,259 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Provider
+//!
+//! The crate does not ship a JSON-RPC transport. Anything that needs chain
+//! state goes through the [`Provider`] trait instead, so applications can
+//! plug in whichever client they already use.
+//!
+//! Only the code and call reads are required. The nonce, fee and gas
+//! queries, the receipts, block numbers and pool contents used to follow
+//! transactions, the logs and blocks scanned for history, and the broadcast
+//! used to send them have default implementations failing with
+//! [`ProviderError::Unsupported`], so read-only clients need not provide
+//! them.
+//!
+//! Retries, logging, nonce management, gas pricing and signing are layered
+//! over a provider by the [`middleware`] module.
+
+pub mod middleware;
+#[cfg(test)]
+pub(crate) mod mock;
+
+use crate::{
+    address::Address,
+    error::Result,
+    transaction::{DecodedTransaction, TransactionRequest},
+    types::B256,
+};
+
+/// Error returned by a [`Provider`]. Implementations report failures of
+/// their client with these variants so callers can tell a revert from an
+/// unreachable node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProviderError {
+    /// The provider does not implement this JSON-RPC method.
+    Unsupported(&'static str),
+    /// The node could not be reached or its response could not be read.
+    Transport(String),
+    /// The node refused the request for exceeding its rate limit.
+    RateLimited,
+    /// The call reverted, with the revert data returned by the node.
+    Reverted(Vec<u8>),
+}
+
+impl std::fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ProviderError::Unsupported(method) => {
+                write!(f, "The provider does not support {}", method)
+            }
+            ProviderError::Transport(e) => write!(f, "Provider transport error: {}", e),
+            ProviderError::RateLimited => write!(f, "Provider rate limit exceeded"),
+            ProviderError::Reverted(data) => {
+                write!(f, "Execution reverted: 0x{}", hex::encode(data))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProviderError {}
+
+/// The receipt of a mined transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionReceipt {
+    /// Number of the block including the transaction.
+    pub block_number: u64,
+    /// Whether the transaction succeeded, `false` when it reverted.
+    pub success: bool,
+    /// Gas used by the transaction.
+    pub gas_used: u64,
+    /// Price paid per gas in wei.
+    pub effective_gas_price: u128,
+}
+
+/// Fees of recent blocks, as returned by `eth_feeHistory`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeeHistory {
+    /// Number of the first block.
+    pub oldest_block: u64,
+    /// Base fee per gas of each block, followed by the one of the next
+    /// block.
+    pub base_fee_per_gas: Vec<u128>,
+    /// Priority fees per gas paid in each block, at each of the requested
+    /// percentiles.
+    pub reward: Vec<Vec<u128>>,
+}
+
+/// A log emitted by a mined transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Log {
+    /// The contract that emitted the log.
+    pub address: Address,
+    /// The indexed topics, the event signature first.
+    pub topics: Vec<B256>,
+    /// The non-indexed data.
+    pub data: Vec<u8>,
+    /// Number of the block including the transaction.
+    pub block_number: u64,
+    /// Hash of the transaction.
+    pub transaction_hash: B256,
+    /// Position of the transaction in the block.
+    pub transaction_index: u64,
+    /// Position of the log in the block.
+    pub log_index: u64,
+}
+
+/// The logs to return from [`Provider::logs`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogFilter {
+    /// First block, included.
+    pub from_block: u64,
+    /// Last block, included.
+    pub to_block: u64,
+    /// Only the logs of this contract, any contract when `None`.
+    pub address: Option<Address>,
+    /// The topic expected at each position, any topic when `None`.
+    pub topics: Vec<Option<B256>>,
+}
+
+impl LogFilter {
+    /// Return whether the log passes the filter.
+    pub fn matches(&self, log: &Log) -> bool {
+        (self.from_block..=self.to_block).contains(&log.block_number)
+            && self.address.is_none_or(|address| address == log.address)
+            && self
+                .topics
+                .iter()
+                .enumerate()
+                .all(|(i, topic)| match topic {
+                    Some(topic) => log.topics.get(i) == Some(topic),
+                    None => true,
+                })
+    }
+}
+
+/// Access to an Ethereum node.
+pub trait Provider {
+    /// Return the code deployed at the given address (`eth_getCode`).
+    fn get_code(&self, address: &Address) -> Result<Vec<u8>>;
+
+    /// Execute a read-only call against the latest block (`eth_call`).
+    fn call(&self, to: &Address, data: &[u8]) -> Result<Vec<u8>>;
+
+    /// Return the number of transactions sent from the given address, its
+    /// next nonce (`eth_getTransactionCount` at the latest block).
+    fn transaction_count(&self, _address: &Address) -> Result<u64> {
+        Err(ProviderError::Unsupported("eth_getTransactionCount").into())
+    }
+
+    /// Return the chain id of the node (`eth_chainId`).
+    fn chain_id(&self) -> Result<u64> {
+        Err(ProviderError::Unsupported("eth_chainId").into())
+    }
+
+    /// Return the gas a transaction would use (`eth_estimateGas`).
+    fn estimate_gas(&self, _tx: &TransactionRequest) -> Result<u64> {
+        Err(ProviderError::Unsupported("eth_estimateGas").into())
+    }
+
+    /// Return the gas price of a legacy transaction (`eth_gasPrice`).
+    fn gas_price(&self) -> Result<u128> {
+        Err(ProviderError::Unsupported("eth_gasPrice").into())
+    }
+
+    /// Return the base fee per gas of the latest block, `None` on chains
+    /// without EIP-1559 (`eth_getBlockByNumber`).
+    fn base_fee_per_gas(&self) -> Result<Option<u128>> {
+        Err(ProviderError::Unsupported("eth_getBlockByNumber").into())
+    }
+
+    /// Return a priority fee per gas likely to get a transaction included
+    /// (`eth_maxPriorityFeePerGas`).
+    fn max_priority_fee_per_gas(&self) -> Result<u128> {
+        Err(ProviderError::Unsupported("eth_maxPriorityFeePerGas").into())
+    }
+
+    /// Return the base fees and the priority fees at the given percentiles
+    /// of the latest blocks (`eth_feeHistory`).
+    fn fee_history(&self, _block_count: u64, _percentiles: &[f64]) -> Result<FeeHistory> {
+        Err(ProviderError::Unsupported("eth_feeHistory").into())
+    }
+
+    /// Return a transaction known to the node, pending or mined, `None`
+    /// when it is unknown (`eth_getTransactionByHash`).
+    fn transaction_by_hash(&self, _hash: &B256) -> Result<Option<DecodedTransaction>> {
+        Err(ProviderError::Unsupported("eth_getTransactionByHash").into())
+    }
+
+    /// Return the next nonce of the given address counting its pending
+    /// transactions, which stops at the first missing nonce
+    /// (`eth_getTransactionCount` at the pending block).
+    fn pending_transaction_count(&self, _address: &Address) -> Result<u64> {
+        Err(ProviderError::Unsupported("eth_getTransactionCount").into())
+    }
+
+    /// Return the nonces of the transactions the given address has in the
+    /// pool, pending and queued (`txpool_contentFrom`).
+    fn pool_nonces(&self, _address: &Address) -> Result<Vec<u64>> {
+        Err(ProviderError::Unsupported("txpool_contentFrom").into())
+    }
+
+    /// Return the receipt of a mined transaction, `None` while it is
+    /// pending or unknown (`eth_getTransactionReceipt`).
+    fn transaction_receipt(&self, _hash: &B256) -> Result<Option<TransactionReceipt>> {
+        Err(ProviderError::Unsupported("eth_getTransactionReceipt").into())
+    }
+
+    /// Return the number of the latest block (`eth_blockNumber`).
+    fn block_number(&self) -> Result<u64> {
+        Err(ProviderError::Unsupported("eth_blockNumber").into())
+    }
+
+    /// Return the number of the latest finalized block
+    /// (`eth_getBlockByNumber`).
+    fn finalized_block_number(&self) -> Result<u64> {
+        Err(ProviderError::Unsupported("eth_getBlockByNumber").into())
+    }
+
+    /// Return the logs passing the filter, in the order of the chain
+    /// (`eth_getLogs`).
+    fn logs(&self, _filter: &LogFilter) -> Result<Vec<Log>> {
+        Err(ProviderError::Unsupported("eth_getLogs").into())
+    }
+
+    /// Return the transactions of a block, in their order in the block
+    /// (`eth_getBlockByNumber` with full transactions).
+    fn block_transactions(&self, _number: u64) -> Result<Vec<DecodedTransaction>> {
+        Err(ProviderError::Unsupported("eth_getBlockByNumber").into())
+    }
+
+    /// Fill in fields of a request before it is built, as the middleware
+    /// of a stack do. Plain providers leave it as is.
+    fn fill_transaction(&self, _request: &mut TransactionRequest) -> Result<()> {
+        Ok(())
+    }
+
+    /// Fill in, sign and broadcast a request with a key of the provider,
+    /// returning its hash (`eth_sendTransaction`).
+    fn send_transaction(&self, _request: &TransactionRequest) -> Result<B256> {
+        Err(ProviderError::Unsupported("eth_sendTransaction").into())
+    }
+
+    /// Broadcast a raw signed transaction, returning its hash
+    /// (`eth_sendRawTransaction`).
+    fn send_raw_transaction(&self, _raw: &[u8]) -> Result<B256> {
+        Err(ProviderError::Unsupported("eth_sendRawTransaction").into())
+    }
+}
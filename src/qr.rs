@@ -0,0 +1,174 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # QR Codes
+//!
+//! QR codes of addresses and [EIP-681](crate::eips::eip681) payment requests
+//! for receive screens, rendered as SVG, PNG or text for a terminal. Enabled
+//! with the `qr` feature.
+//!
+//! Addresses are encoded as `ethereum:` URIs in their checksummed form, which
+//! every wallet scanner understands.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::{address::Address, eips::eip681::PaymentRequest, qr::QrCode};
+//!
+//! let address: Address = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".parse().unwrap();
+//! let code = QrCode::address(&address).unwrap();
+//! println!("{}", code.to_terminal());
+//!
+//! let request = PaymentRequest::ether(address).with_value(10_000_000_000_000_000);
+//! let svg = QrCode::payment(&request).unwrap().to_svg();
+//! assert!(svg.starts_with("<?xml"));
+//! ```
+
+use qrcode::{
+    render::{svg, unicode},
+    Color, EcLevel,
+};
+
+use crate::{
+    address::Address,
+    eips::eip681::PaymentRequest,
+    error::{Result, WalletError},
+};
+
+/// Error returned when creating a QR code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QrError {
+    /// The data does not fit in a QR code.
+    DataTooLong,
+    /// The image could not be encoded.
+    Encoding(String),
+}
+
+impl std::fmt::Display for QrError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            QrError::DataTooLong => write!(f, "Data too long for a QR code"),
+            QrError::Encoding(e) => write!(f, "QR code encoding failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for QrError {}
+
+/// Width of the light border around the code, in modules, as required by the
+/// QR code specification.
+const QUIET_ZONE: usize = 4;
+
+/// A QR code.
+pub struct QrCode(qrcode::QrCode);
+
+impl QrCode {
+    /// Encode arbitrary text, with the medium error correction level.
+    pub fn new(data: &str) -> Result<Self> {
+        qrcode::QrCode::with_error_correction_level(data, EcLevel::M)
+            .map(Self)
+            .map_err(|_| QrError::DataTooLong.into())
+    }
+
+    /// Encode an address as an `ethereum:` URI.
+    pub fn address(address: &Address) -> Result<Self> {
+        Self::new(&format!("ethereum:{}", address))
+    }
+
+    /// Encode a payment request.
+    pub fn payment(request: &PaymentRequest) -> Result<Self> {
+        Self::new(&request.to_string())
+    }
+
+    /// Return the number of modules on a side, without the quiet zone.
+    pub fn width(&self) -> usize {
+        self.0.width()
+    }
+
+    /// Render as an SVG document, 8 pixels per module.
+    pub fn to_svg(&self) -> String {
+        self.0
+            .render::<svg::Color>()
+            .module_dimensions(8, 8)
+            .build()
+    }
+
+    /// Render as text using half block characters, two rows of modules per
+    /// line, for printing to a terminal with a dark background.
+    pub fn to_terminal(&self) -> String {
+        self.0
+            .render::<unicode::Dense1x2>()
+            .dark_color(unicode::Dense1x2::Light)
+            .light_color(unicode::Dense1x2::Dark)
+            .build()
+    }
+
+    /// Render as a grayscale PNG image, `scale` pixels per module.
+    pub fn to_png(&self, scale: usize) -> Result<Vec<u8>> {
+        let scale = scale.max(1);
+        let width = self.width();
+        let size = (width + 2 * QUIET_ZONE) * scale;
+        let colors = self.0.to_colors();
+
+        let mut pixels = vec![0xff; size * size];
+        for (i, color) in colors.iter().enumerate() {
+            if *color == Color::Light {
+                continue;
+            }
+            let (x, y) = (i % width + QUIET_ZONE, i / width + QUIET_ZONE);
+            for row in y * scale..(y + 1) * scale {
+                pixels[row * size + x * scale..row * size + (x + 1) * scale].fill(0);
+            }
+        }
+
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, size as u32, size as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&pixels))
+            .map_err(|e| WalletError::from(QrError::Encoding(e.to_string())))?;
+        Ok(png)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let address: Address = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+            .parse()
+            .unwrap();
+        let code = QrCode::address(&address).unwrap();
+        let width = code.width();
+        assert!(width >= 21);
+
+        let svg = code.to_svg();
+        assert!(svg.contains("<svg") && svg.ends_with("</svg>"));
+
+        let text = code.to_terminal();
+        assert_eq!(text.lines().count(), (width + 2 * QUIET_ZONE).div_ceil(2));
+
+        let png = code.to_png(2).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        let size = ((width + 2 * QUIET_ZONE) * 2) as u32;
+        assert_eq!(
+            &png[16..24],
+            [size.to_be_bytes(), size.to_be_bytes()].concat()
+        );
+
+        assert!(QrCode::new(&"a".repeat(5000)).is_err());
+    }
+}
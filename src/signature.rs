@@ -0,0 +1,424 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # ECDSA Signatures
+//!
+//! A secp256k1 signature in the `(r, s, v)` form used by Ethereum, where `v`
+//! carries the recovery id and, for [EIP-155](https://eips.ethereum.org/EIPS/eip-155)
+//! transaction signatures, the chain id.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::signature::Signature;
+//!
+//! let sig: Signature = "0x68a020a209d3d56c46f38cc50a33f704f4a9a10a59377f8dd762ac66910e9b90\
+//!     7e865ad05c4035ab5792787d4a0297a43617ae897930a6fe4d822b8faea520641b"
+//!     .parse()
+//!     .unwrap();
+//! assert_eq!(sig.v(), 27);
+//! assert_eq!(sig.recovery_id().unwrap(), 0);
+//! assert_eq!(Signature::from_bytes(&sig.to_compact()).unwrap(), sig);
+//! ```
+
+use k256::ecdsa::{RecoveryId, VerifyingKey};
+
+use crate::{
+    address::Address,
+    chains::Chain,
+    eips::eip191,
+    error::{Result, WalletError},
+};
+
+/// Order of the secp256k1 curve.
+const CURVE_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+
+/// Half of the order of the secp256k1 curve, the upper bound of a low `s`.
+const HALF_CURVE_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// Error returned when parsing or interpreting a signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureError {
+    /// The signature is neither 64 nor 65 bytes long.
+    InvalidLength(usize),
+    /// The `v` value does not encode a recovery id.
+    InvalidV(u64),
+    /// The EIP-155 `v` value was computed for another chain than the one
+    /// of the transaction.
+    ChainIdMismatch {
+        /// The chain id of the transaction.
+        expected: u64,
+        /// The chain id encoded in `v`.
+        actual: u64,
+    },
+}
+
+impl std::fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SignatureError::InvalidLength(len) => write!(f, "Invalid signature length: {}", len),
+            SignatureError::InvalidV(v) => write!(f, "Invalid signature v value: {}", v),
+            SignatureError::ChainIdMismatch { expected, actual } => write!(
+                f,
+                "Signature for chain {} used on chain {}",
+                actual, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+/// An ECDSA signature over secp256k1. With the `serde` feature it serializes
+/// as an object of `r`, `s` and `v`, keeping any EIP-155 chain id in `v`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "SignatureRepr", into = "SignatureRepr")
+)]
+pub struct Signature {
+    r: [u8; 32],
+    s: [u8; 32],
+    v: u64,
+}
+
+impl Signature {
+    /// Create a new signature from its components.
+    pub fn new(r: [u8; 32], s: [u8; 32], v: u64) -> Self {
+        Self { r, s, v }
+    }
+
+    /// Create a new signature from a recovery id, using the `27 + recovery_id`
+    /// convention of signed messages.
+    pub fn from_recovery_id(r: [u8; 32], s: [u8; 32], recovery_id: u8) -> Self {
+        Self::new(r, s, 27 + (recovery_id & 1) as u64)
+    }
+
+    /// Parse a signature from its 65-byte `r || s || v` encoding or its
+    /// 64-byte [EIP-2098](https://eips.ethereum.org/EIPS/eip-2098) compact
+    /// encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        match bytes.len() {
+            65 => {
+                let sig = Self::new(
+                    bytes[..32].try_into().expect("32 bytes"),
+                    bytes[32..64].try_into().expect("32 bytes"),
+                    bytes[64] as u64,
+                );
+                sig.recovery_id()?;
+                Ok(sig)
+            }
+            64 => {
+                let mut s: [u8; 32] = bytes[32..].try_into().expect("32 bytes");
+                let y_parity = s[0] >> 7;
+                s[0] &= 0x7f;
+                Ok(Self::from_recovery_id(
+                    bytes[..32].try_into().expect("32 bytes"),
+                    s,
+                    y_parity,
+                ))
+            }
+            len => Err(SignatureError::InvalidLength(len).into()),
+        }
+    }
+
+    /// Return the `r` component.
+    pub fn r(&self) -> &[u8; 32] {
+        &self.r
+    }
+
+    /// Return the `s` component.
+    pub fn s(&self) -> &[u8; 32] {
+        &self.s
+    }
+
+    /// Return the raw `v` component.
+    pub fn v(&self) -> u64 {
+        self.v
+    }
+
+    /// Return the recovery id (y parity) encoded in `v`. Supports the raw
+    /// `0`/`1` form, the `27`/`28` form and EIP-155 values.
+    pub fn recovery_id(&self) -> Result<u8> {
+        match self.v {
+            0 | 1 => Ok(self.v as u8),
+            27 | 28 => Ok((self.v - 27) as u8),
+            v if v >= 35 => Ok(((v - 35) % 2) as u8),
+            v => Err(SignatureError::InvalidV(v).into()),
+        }
+    }
+
+    /// Return the chain id if `v` was computed following EIP-155.
+    pub fn chain_id(&self) -> Option<u64> {
+        match self.v {
+            v if v >= 35 => Some((v - 35) / 2),
+            _ => None,
+        }
+    }
+
+    /// Return the signature with `v` encoding the id of the given chain
+    /// following EIP-155.
+    pub fn with_chain_id(self, chain: impl Into<Chain>) -> Result<Self> {
+        let recovery_id = self.recovery_id()?;
+        Ok(Self::new(
+            self.r,
+            self.s,
+            recovery_id as u64 + 35 + chain.into().id * 2,
+        ))
+    }
+
+    /// Return the signature with `v` encoding the id of the given chain.
+    pub fn with_chain(self, chain: &Chain) -> Result<Self> {
+        self.with_chain_id(*chain)
+    }
+
+    /// Return true if `s` is in the lower half of the curve order, as
+    /// required by [EIP-2](https://eips.ethereum.org/EIPS/eip-2).
+    pub fn is_low_s(&self) -> bool {
+        self.s <= HALF_CURVE_ORDER
+    }
+
+    /// Return the equivalent signature with a low `s`, flipping the recovery
+    /// id when `s` had to be negated.
+    pub fn normalize_s(self) -> Result<Self> {
+        if self.is_low_s() {
+            return Ok(self);
+        }
+
+        let mut s = [0u8; 32];
+        let mut borrow = 0i16;
+        for i in (0..32).rev() {
+            let diff = CURVE_ORDER[i] as i16 - self.s[i] as i16 - borrow;
+            borrow = (diff < 0) as i16;
+            s[i] = diff.rem_euclid(256) as u8;
+        }
+
+        let recovery_id = self.recovery_id()?;
+        let v = match self.v {
+            0 | 1 => self.v ^ 1,
+            v if recovery_id == 0 => v + 1,
+            v => v - 1,
+        };
+
+        Ok(Self::new(self.r, s, v))
+    }
+
+    /// Recover the address of the signer of the given message hash.
+    ///
+    /// High `s` values are accepted, like the `ecrecover` precompile does.
+    pub fn recover(&self, message_hash: &[u8; 32]) -> Result<Address> {
+        let sig = self.normalize_s()?;
+        let recovery_id =
+            RecoveryId::from_byte(sig.recovery_id()?).ok_or(SignatureError::InvalidV(self.v))?;
+
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&sig.r);
+        bytes[32..].copy_from_slice(&sig.s);
+        let signature = k256::ecdsa::Signature::from_slice(&bytes)?;
+
+        let key = VerifyingKey::recover_from_prehash(message_hash, &signature, recovery_id)?;
+        Ok(Address::from(&key))
+    }
+
+    /// Recover the address of the signer of an [EIP-191](https://eips.ethereum.org/EIPS/eip-191)
+    /// `personal_sign` message.
+    pub fn recover_personal(&self, message: impl AsRef<[u8]>) -> Result<Address> {
+        self.recover(&eip191::hash_message(message))
+    }
+
+    /// Return the 65-byte `r || s || v` encoding, with `v` as `27` or `28`.
+    pub fn to_bytes(&self) -> [u8; 65] {
+        let mut bytes = [0u8; 65];
+        bytes[..32].copy_from_slice(&self.r);
+        bytes[32..64].copy_from_slice(&self.s);
+        bytes[64] = 27 + self.recovery_id().unwrap_or(0);
+        bytes
+    }
+
+    /// Return the 64-byte EIP-2098 compact encoding, with the recovery id
+    /// stored in the top bit of `s`.
+    pub fn to_compact(&self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&self.r);
+        bytes[32..].copy_from_slice(&self.s);
+        bytes[32] |= self.recovery_id().unwrap_or(0) << 7;
+        bytes
+    }
+}
+
+/// The serialized form of a [`Signature`].
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SignatureRepr {
+    r: crate::types::B256,
+    s: crate::types::B256,
+    v: u64,
+}
+
+#[cfg(feature = "serde")]
+impl From<Signature> for SignatureRepr {
+    fn from(signature: Signature) -> Self {
+        Self {
+            r: signature.r.into(),
+            s: signature.s.into(),
+            v: signature.v,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<SignatureRepr> for Signature {
+    fn from(repr: SignatureRepr) -> Self {
+        Self::new(repr.r.into(), repr.s.into(), repr.v)
+    }
+}
+
+impl std::fmt::Display for Signature {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "0x{}", hex::encode(self.to_bytes()))
+    }
+}
+
+impl std::str::FromStr for Signature {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.strip_prefix("0x").unwrap_or(s);
+        Self::from_bytes(&hex::decode(s)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eip2098() {
+        let vectors = [
+            (
+                "68a020a209d3d56c46f38cc50a33f704f4a9a10a59377f8dd762ac66910e9b90",
+                "7e865ad05c4035ab5792787d4a0297a43617ae897930a6fe4d822b8faea52064",
+                27,
+                "7e865ad05c4035ab5792787d4a0297a43617ae897930a6fe4d822b8faea52064",
+            ),
+            (
+                "9328da16089fcba9bececa81663203989f2df5fe1faa6291a45381c81bd17f76",
+                "139c6d6b623b42da56557e5e734a43dc83345ddfadec52cbe24d0cc64f550793",
+                28,
+                "939c6d6b623b42da56557e5e734a43dc83345ddfadec52cbe24d0cc64f550793",
+            ),
+        ];
+
+        for (r, s, v, y_parity_and_s) in vectors {
+            let sig = Signature::new(
+                hex::decode(r).unwrap().try_into().unwrap(),
+                hex::decode(s).unwrap().try_into().unwrap(),
+                v,
+            );
+            let compact = sig.to_compact();
+            assert_eq!(hex::encode(&compact[32..]), y_parity_and_s);
+            assert_eq!(Signature::from_bytes(&compact).unwrap(), sig);
+            assert_eq!(Signature::from_bytes(&sig.to_bytes()).unwrap(), sig);
+            assert_eq!(sig.to_string().parse::<Signature>().unwrap(), sig);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let sig = Signature::new([1; 32], [2; 32], 28)
+            .with_chain_id(1)
+            .unwrap();
+        let json = serde_json::to_string(&sig).unwrap();
+        assert_eq!(
+            json,
+            format!(
+                "{{\"r\":\"0x{}\",\"s\":\"0x{}\",\"v\":38}}",
+                "01".repeat(32),
+                "02".repeat(32)
+            )
+        );
+        assert_eq!(serde_json::from_str::<Signature>(&json).unwrap(), sig);
+    }
+
+    #[test]
+    fn test_chain_id() {
+        let sig = Signature::new([1; 32], [2; 32], 28);
+        assert_eq!(sig.chain_id(), None);
+
+        let sig = sig.with_chain_id(1).unwrap();
+        assert_eq!(sig.v(), 38);
+        assert_eq!(sig.chain_id(), Some(1));
+        assert_eq!(sig.recovery_id().unwrap(), 1);
+        assert_eq!(sig.to_bytes()[64], 28);
+
+        let sig = sig.with_chain(&crate::chains::SEPOLIA).unwrap();
+        assert_eq!(sig.chain_id(), Some(11155111));
+        assert_eq!(sig.recovery_id().unwrap(), 1);
+
+        assert!(Signature::new([1; 32], [2; 32], 30).recovery_id().is_err());
+        assert!(Signature::from_bytes(&[0; 63]).is_err());
+    }
+
+    #[test]
+    fn test_recover() {
+        let sig: Signature = "0xb91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd\
+            6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a0291c"
+            .parse()
+            .unwrap();
+        let address = sig.recover_personal("Some data").unwrap();
+        assert_eq!(
+            address.to_string(),
+            "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23"
+        );
+
+        // the high-s twin of a signature recovers the same signer
+        let mut s = [0u8; 32];
+        let mut borrow = 0i16;
+        for i in (0..32).rev() {
+            let diff = CURVE_ORDER[i] as i16 - sig.s()[i] as i16 - borrow;
+            borrow = (diff < 0) as i16;
+            s[i] = diff.rem_euclid(256) as u8;
+        }
+        let high = Signature::new(*sig.r(), s, 27);
+        assert!(!high.is_low_s());
+        assert_eq!(high.recover_personal("Some data").unwrap(), address);
+
+        assert_ne!(sig.recover_personal("Other data").unwrap(), address);
+    }
+
+    #[test]
+    fn test_normalize_s() {
+        let sig = Signature::new([1; 32], HALF_CURVE_ORDER, 27);
+        assert!(sig.is_low_s());
+        assert_eq!(sig.normalize_s().unwrap(), sig);
+
+        let mut high = CURVE_ORDER;
+        high[31] -= 1;
+        let sig = Signature::new([1; 32], high, 27).normalize_s().unwrap();
+        let mut one = [0u8; 32];
+        one[31] = 1;
+        assert_eq!(sig.s(), &one);
+        assert_eq!(sig.v(), 28);
+
+        let sig = Signature::new([1; 32], high, 37).normalize_s().unwrap();
+        assert_eq!(sig.v(), 38);
+        assert_eq!(sig.chain_id(), Some(1));
+    }
+}
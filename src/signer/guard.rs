@@ -0,0 +1,555 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Rate limiting and circuit breaking of signing requests.
+//!
+//! A [`GuardedSigner`] wraps any [`Signer`] behind a token bucket. Requests
+//! over the limit are refused, and when too many are refused in a short
+//! window the circuit breaker trips and refuses everything until a cooldown
+//! elapses, containing a compromised caller of a signing service.
+//!
+//! Sends above a value threshold can further require a [`Totp`] code from a
+//! secret provisioned at wallet setup, checked before the signer is invoked.
+//! Transactions signed through the [`Signer`] methods are checked without a
+//! code, [`GuardedSigner::sign_send`] supplies one. Hashes, messages and
+//! typed data carry no value and are refused once a confirmation is set.
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use super::{Signer, Totp};
+use crate::{
+    address::Address,
+    eips::eip712::TypedData,
+    error::Result,
+    signature::Signature,
+    transaction::{Transaction, TypedTransaction},
+};
+
+/// Error returned when a signing request is refused by the policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyError {
+    /// The request exceeds the rate limit.
+    RateLimited,
+    /// The circuit breaker is open.
+    CircuitOpen,
+    /// The request needs a one-time code.
+    ConfirmationRequired,
+    /// The one-time code is wrong or was already used.
+    InvalidConfirmation,
+}
+
+impl std::fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PolicyError::RateLimited => write!(f, "Signing rate limit exceeded"),
+            PolicyError::CircuitOpen => write!(f, "Signing circuit breaker is open"),
+            PolicyError::ConfirmationRequired => write!(f, "Signing requires a one-time code"),
+            PolicyError::InvalidConfirmation => write!(f, "Invalid one-time code"),
+        }
+    }
+}
+
+impl std::error::Error for PolicyError {}
+
+/// Event emitted by a [`GuardedSigner`] when its policy acts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyEvent {
+    /// A request was refused by the rate limiter.
+    RateLimited,
+    /// The circuit breaker tripped and refuses all requests.
+    CircuitOpened,
+    /// A request was refused because the circuit breaker is open.
+    CircuitRejected,
+    /// The cooldown elapsed and requests are accepted again.
+    CircuitClosed,
+    /// A request was refused because of a wrong or reused one-time code.
+    ConfirmationFailed,
+}
+
+/// Token bucket parameters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// Tokens added per second.
+    pub rate: f64,
+    /// Maximum number of tokens, the largest burst accepted at once.
+    pub burst: u32,
+}
+
+/// One-time code requirement for high-value sends.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Confirmation {
+    /// Generator of the expected codes.
+    pub totp: Totp,
+    /// Value in wei above which a code is required.
+    pub threshold: u128,
+}
+
+/// Circuit breaker parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreaker {
+    /// Number of refused requests that trips the breaker.
+    pub threshold: u32,
+    /// Window over which rate limited requests are counted.
+    pub window: Duration,
+    /// Time the breaker stays open once tripped.
+    pub cooldown: Duration,
+}
+
+struct State {
+    tokens: f64,
+    refilled_at: Instant,
+    rejections: Vec<Instant>,
+    opened_at: Option<Instant>,
+    last_step: Option<u64>,
+}
+
+/// A signing request as seen by the policy.
+struct Request<'a> {
+    now: Instant,
+    unix_time: u64,
+    value: Option<u128>,
+    code: Option<&'a str>,
+}
+
+/// A [`Signer`] guarded by a rate limiter and an optional circuit breaker.
+pub struct GuardedSigner<S> {
+    signer: S,
+    limit: RateLimit,
+    breaker: Option<CircuitBreaker>,
+    confirmation: Option<Confirmation>,
+    listener: Option<Box<dyn Fn(PolicyEvent) + Send + Sync>>,
+    state: Mutex<State>,
+}
+
+impl<S: Signer> GuardedSigner<S> {
+    /// Guard a signer with the given rate limit, starting with a full bucket.
+    pub fn new(signer: S, limit: RateLimit) -> Self {
+        Self {
+            signer,
+            limit,
+            breaker: None,
+            confirmation: None,
+            listener: None,
+            state: Mutex::new(State {
+                tokens: limit.burst as f64,
+                refilled_at: Instant::now(),
+                rejections: Vec::new(),
+                opened_at: None,
+                last_step: None,
+            }),
+        }
+    }
+
+    /// Trip a circuit breaker on bursts of refused requests.
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.breaker = Some(breaker);
+        self
+    }
+
+    /// Require a one-time code for sends above a threshold.
+    pub fn with_confirmation(mut self, confirmation: Confirmation) -> Self {
+        self.confirmation = Some(confirmation);
+        self
+    }
+
+    /// Call `listener` on every policy event.
+    pub fn on_event(mut self, listener: impl Fn(PolicyEvent) + Send + Sync + 'static) -> Self {
+        self.listener = Some(Box::new(listener));
+        self
+    }
+
+    /// Return the guarded signer.
+    pub fn inner(&self) -> &S {
+        &self.signer
+    }
+
+    /// Sign a transaction, checking its value against the confirmation
+    /// threshold. Above it a valid one-time code is required, and each code
+    /// is accepted once.
+    pub fn sign_send(&self, tx: &TypedTransaction, code: Option<&str>) -> Result<Signature> {
+        self.check(&Request {
+            now: Instant::now(),
+            unix_time: unix_time(),
+            value: Some(tx.value()),
+            code,
+        })?;
+        self.signer.sign_typed_transaction(tx)
+    }
+
+    /// Return whether the circuit breaker is currently open.
+    pub fn is_open(&self) -> bool {
+        self.lock().opened_at.is_some()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        // the state stays consistent even if a listener panicked
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn emit(&self, event: PolicyEvent) {
+        if let Some(listener) = &self.listener {
+            listener(event);
+        }
+    }
+
+    /// Take a token for a request of unknown value made at `now`.
+    fn acquire(&self, now: Instant) -> std::result::Result<(), PolicyError> {
+        self.check(&Request {
+            now,
+            unix_time: unix_time(),
+            value: None,
+            code: None,
+        })
+    }
+
+    /// Take a token for a send of `value` wei made without a one-time code.
+    fn admit(&self, value: u128) -> std::result::Result<(), PolicyError> {
+        self.check(&Request {
+            now: Instant::now(),
+            unix_time: unix_time(),
+            value: Some(value),
+            code: None,
+        })
+    }
+
+    fn check(&self, request: &Request) -> std::result::Result<(), PolicyError> {
+        let mut events = Vec::new();
+        let result = self.check_locked(&mut self.lock(), request, &mut events);
+
+        // listeners run without the lock so they may query the signer
+        for event in events {
+            self.emit(event);
+        }
+        result
+    }
+
+    fn check_locked(
+        &self,
+        state: &mut State,
+        request: &Request,
+        events: &mut Vec<PolicyEvent>,
+    ) -> std::result::Result<(), PolicyError> {
+        let now = request.now;
+        if let (Some(opened_at), Some(breaker)) = (state.opened_at, self.breaker) {
+            if now.duration_since(opened_at) < breaker.cooldown {
+                events.push(PolicyEvent::CircuitRejected);
+                return Err(PolicyError::CircuitOpen);
+            }
+            state.opened_at = None;
+            state.rejections.clear();
+            events.push(PolicyEvent::CircuitClosed);
+        }
+
+        let elapsed = now.duration_since(state.refilled_at).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * self.limit.rate).min(self.limit.burst as f64);
+        state.refilled_at = now;
+
+        if state.tokens < 1.0 {
+            events.push(PolicyEvent::RateLimited);
+            self.reject(state, now, events);
+            return Err(PolicyError::RateLimited);
+        }
+        state.tokens -= 1.0;
+
+        let confirmation = match &self.confirmation {
+            // a request of unknown value is treated as a high-value send
+            Some(confirmation) if request.value.is_none_or(|v| v > confirmation.threshold) => {
+                confirmation
+            }
+            _ => return Ok(()),
+        };
+        let code = request.code.ok_or(PolicyError::ConfirmationRequired)?;
+
+        match confirmation.totp.verify(code, request.unix_time) {
+            Some(step) if state.last_step.is_none_or(|last| step > last) => {
+                state.last_step = Some(step);
+                Ok(())
+            }
+            _ => {
+                events.push(PolicyEvent::ConfirmationFailed);
+                self.reject(state, now, events);
+                Err(PolicyError::InvalidConfirmation)
+            }
+        }
+    }
+
+    /// Count a refused request towards the circuit breaker.
+    fn reject(&self, state: &mut State, now: Instant, events: &mut Vec<PolicyEvent>) {
+        if let Some(breaker) = self.breaker {
+            state
+                .rejections
+                .retain(|at| now.duration_since(*at) < breaker.window);
+            state.rejections.push(now);
+
+            if state.rejections.len() >= breaker.threshold as usize {
+                state.opened_at = Some(now);
+                events.push(PolicyEvent::CircuitOpened);
+            }
+        }
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+impl<S: Signer> Signer for GuardedSigner<S> {
+    fn address(&self) -> Result<Address> {
+        self.signer.address()
+    }
+
+    fn chain_id(&self) -> Option<u64> {
+        self.signer.chain_id()
+    }
+
+    fn sign_hash(&self, hash: &[u8; 32]) -> Result<Signature> {
+        self.acquire(Instant::now())?;
+        self.signer.sign_hash(hash)
+    }
+
+    fn sign_message(&self, message: impl AsRef<[u8]>) -> Result<Signature> {
+        self.acquire(Instant::now())?;
+        self.signer.sign_message(message)
+    }
+
+    fn sign_transaction(&self, tx: &Transaction) -> Result<Signature> {
+        self.admit(tx.value)?;
+        self.signer.sign_transaction(tx)
+    }
+
+    fn sign_typed_transaction(&self, tx: &TypedTransaction) -> Result<Signature> {
+        self.admit(tx.value())?;
+        self.signer.sign_typed_transaction(tx)
+    }
+
+    fn sign_typed_data(&self, data: &TypedData) -> Result<Signature> {
+        self.acquire(Instant::now())?;
+        self.signer.sign_typed_data(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ProviderError;
+    use std::sync::{Arc, Mutex};
+
+    struct MockSigner;
+
+    impl Signer for MockSigner {
+        fn address(&self) -> Result<Address> {
+            Ok(Address::default())
+        }
+
+        fn sign_hash(&self, _hash: &[u8; 32]) -> Result<Signature> {
+            Ok(Signature::new([1; 32], [1; 32], 27))
+        }
+    }
+
+    /// A backend that cannot sign bare hashes, like a hardware wallet.
+    struct TransactionSigner;
+
+    impl Signer for TransactionSigner {
+        fn address(&self) -> Result<Address> {
+            Ok(Address::default())
+        }
+
+        fn sign_hash(&self, _hash: &[u8; 32]) -> Result<Signature> {
+            Err(ProviderError::Unsupported("eth_sign").into())
+        }
+
+        fn sign_transaction(&self, _tx: &Transaction) -> Result<Signature> {
+            Ok(Signature::new([2; 32], [2; 32], 27))
+        }
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let signer = GuardedSigner::new(
+            MockSigner,
+            RateLimit {
+                rate: 2.0,
+                burst: 3,
+            },
+        );
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            assert!(signer.acquire(start).is_ok());
+        }
+        assert_eq!(signer.acquire(start), Err(PolicyError::RateLimited));
+
+        // one token every 500ms
+        assert!(signer.acquire(start + Duration::from_millis(500)).is_ok());
+        assert!(signer.acquire(start + Duration::from_millis(500)).is_err());
+        assert!(signer.sign_hash(&[0; 32]).is_err());
+    }
+
+    #[test]
+    fn test_circuit_breaker() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        let signer = GuardedSigner::new(
+            MockSigner,
+            RateLimit {
+                rate: 1.0,
+                burst: 1,
+            },
+        )
+        .with_circuit_breaker(CircuitBreaker {
+            threshold: 2,
+            window: Duration::from_secs(10),
+            cooldown: Duration::from_secs(60),
+        })
+        .on_event(move |event| recorded.lock().unwrap().push(event));
+        let start = Instant::now();
+
+        assert!(signer.acquire(start).is_ok());
+        assert_eq!(signer.acquire(start), Err(PolicyError::RateLimited));
+        assert_eq!(signer.acquire(start), Err(PolicyError::RateLimited));
+        assert!(signer.is_open());
+
+        // refilled, but still in cooldown
+        let later = start + Duration::from_secs(30);
+        assert_eq!(signer.acquire(later), Err(PolicyError::CircuitOpen));
+
+        let after = start + Duration::from_secs(60);
+        assert!(signer.acquire(after).is_ok());
+        assert!(!signer.is_open());
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                PolicyEvent::RateLimited,
+                PolicyEvent::RateLimited,
+                PolicyEvent::CircuitOpened,
+                PolicyEvent::CircuitRejected,
+                PolicyEvent::CircuitClosed,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_confirmation() {
+        let totp = Totp::new(b"12345678901234567890".to_vec());
+        let signer = GuardedSigner::new(
+            MockSigner,
+            RateLimit {
+                rate: 1.0,
+                burst: 10,
+            },
+        )
+        .with_confirmation(Confirmation {
+            totp: totp.clone(),
+            threshold: 1_000,
+        });
+        let request = |value, code| Request {
+            now: Instant::now(),
+            unix_time: 59,
+            value,
+            code,
+        };
+
+        assert!(signer.check(&request(Some(1_000), None)).is_ok());
+        assert_eq!(
+            signer.check(&request(Some(1_001), None)),
+            Err(PolicyError::ConfirmationRequired)
+        );
+        assert_eq!(
+            signer.check(&request(None, None)),
+            Err(PolicyError::ConfirmationRequired)
+        );
+        assert_eq!(
+            signer.check(&request(Some(1_001), Some("000000"))),
+            Err(PolicyError::InvalidConfirmation)
+        );
+
+        let code = totp.generate(59);
+        assert!(signer.check(&request(Some(1_001), Some(&code))).is_ok());
+        // a code is accepted once
+        assert_eq!(
+            signer.check(&request(Some(1_001), Some(&code))),
+            Err(PolicyError::InvalidConfirmation)
+        );
+    }
+
+    #[test]
+    fn test_sign_send() {
+        let totp = Totp::new(b"12345678901234567890".to_vec());
+        let signer = GuardedSigner::new(
+            MockSigner,
+            RateLimit {
+                rate: 1.0,
+                burst: 10,
+            },
+        )
+        .with_confirmation(Confirmation {
+            totp: totp.clone(),
+            threshold: 1_000,
+        });
+        let send = |value| {
+            TypedTransaction::Legacy(Transaction {
+                value,
+                ..Default::default()
+            })
+        };
+
+        assert!(signer.sign_send(&send(1_000), None).is_ok());
+        // the value is read from the signed transaction
+        assert_eq!(
+            signer.sign_send(&send(1_001), None).unwrap_err(),
+            PolicyError::ConfirmationRequired.into()
+        );
+        let code = totp.generate(unix_time());
+        assert!(signer.sign_send(&send(1_001), Some(&code)).is_ok());
+    }
+
+    #[test]
+    fn test_forwarding() {
+        let signer = GuardedSigner::new(
+            TransactionSigner,
+            RateLimit {
+                rate: 1.0,
+                burst: 2,
+            },
+        )
+        .with_confirmation(Confirmation {
+            totp: Totp::new(b"12345678901234567890".to_vec()),
+            threshold: 1_000,
+        });
+        let tx = Transaction {
+            value: 1_000,
+            ..Default::default()
+        };
+
+        // signed by the backend, not through sign_hash
+        assert_eq!(
+            signer.sign_transaction(&tx).unwrap(),
+            Signature::new([2; 32], [2; 32], 27)
+        );
+        assert!(signer
+            .sign_typed_transaction(&TypedTransaction::Legacy(tx.clone()))
+            .is_ok());
+        // the bucket is shared by every entry point
+        assert_eq!(
+            signer.sign_transaction(&tx).unwrap_err(),
+            PolicyError::RateLimited.into()
+        );
+    }
+}
@@ -0,0 +1,274 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Signing with secp256k1 keys held by a cloud key management service, such
+//! as AWS KMS (`ECC_SECG_P256K1`) or Google Cloud KMS
+//! (`EC_SIGN_SECP256K1_SHA256`).
+//!
+//! The services sign a digest and return a DER encoded signature without a
+//! recovery id. [`KmsSigner`] parses it, lowers `s` as required by EIP-2
+//! and finds the recovery id by matching the recovered address with the one
+//! of the key. Authenticating and calling the service is left to the
+//! application through the [`KmsClient`] trait, so any SDK can be used.
+//!
+//! ## Example
+//! ```rust,no_run
+//! # struct Sdk;
+//! # impl Sdk {
+//! #     fn get_public_key(&self, _: &str) -> wallet_rust::error::Result<Vec<u8>> { unimplemented!() }
+//! #     fn sign(&self, _: &str, _: &[u8]) -> wallet_rust::error::Result<Vec<u8>> { unimplemented!() }
+//! # }
+//! use wallet_rust::signer::{KmsClient, KmsSigner, Signer};
+//!
+//! struct AwsKey { sdk: Sdk, key_id: String }
+//!
+//! impl KmsClient for AwsKey {
+//!     fn public_key(&self) -> wallet_rust::error::Result<Vec<u8>> {
+//!         // GetPublicKey
+//!         self.sdk.get_public_key(&self.key_id)
+//!     }
+//!
+//!     fn sign_digest(&self, digest: &[u8; 32]) -> wallet_rust::error::Result<Vec<u8>> {
+//!         // Sign with MessageType DIGEST and SigningAlgorithm ECDSA_SHA_256
+//!         self.sdk.sign(&self.key_id, digest)
+//!     }
+//! }
+//!
+//! let key = AwsKey { sdk: Sdk, key_id: "alias/wallet".to_string() };
+//! let signer = KmsSigner::new(key).unwrap();
+//! let signature = signer.sign_message("hello").unwrap();
+//! assert_eq!(signature.recover_personal("hello").unwrap(), signer.address().unwrap());
+//! ```
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use k256::ecdsa::VerifyingKey;
+
+use crate::{address::Address, error::Result, signature::Signature};
+
+use super::{recoverable, Signer};
+
+/// DER header of a secp256k1 `SubjectPublicKeyInfo` holding an uncompressed
+/// point.
+const SPKI_HEADER: [u8; 23] = [
+    0x30, 0x56, 0x30, 0x10, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06, 0x05, 0x2b,
+    0x81, 0x04, 0x00, 0x0a, 0x03, 0x42, 0x00,
+];
+
+/// Error returned when decoding a response of a key management service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KmsError {
+    /// The public key is not a DER or PEM encoded secp256k1 key.
+    InvalidPublicKey,
+    /// The signature is not a DER encoded ECDSA signature.
+    InvalidSignature,
+    /// The signature was not made by the key.
+    KeyMismatch,
+}
+
+impl std::fmt::Display for KmsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            KmsError::InvalidPublicKey => write!(f, "Invalid KMS public key"),
+            KmsError::InvalidSignature => write!(f, "Invalid KMS signature"),
+            KmsError::KeyMismatch => write!(f, "KMS signature does not match the key"),
+        }
+    }
+}
+
+impl std::error::Error for KmsError {}
+
+/// Access to a secp256k1 key of a key management service.
+pub trait KmsClient {
+    /// Return the public key as a DER or PEM encoded `SubjectPublicKeyInfo`.
+    fn public_key(&self) -> Result<Vec<u8>>;
+
+    /// Sign a 32-byte digest, returning the DER encoded ECDSA signature.
+    fn sign_digest(&self, digest: &[u8; 32]) -> Result<Vec<u8>>;
+}
+
+/// A [`Signer`] backed by a key management service.
+pub struct KmsSigner<C> {
+    client: C,
+    address: Address,
+}
+
+impl<C: KmsClient> KmsSigner<C> {
+    /// Create a new signer, reading the public key from the service.
+    pub fn new(client: C) -> Result<Self> {
+        let key = parse_public_key(&client.public_key()?)?;
+        Ok(Self {
+            client,
+            address: Address::from(&key),
+        })
+    }
+
+    /// Return the client.
+    pub fn client(&self) -> &C {
+        &self.client
+    }
+}
+
+impl<C: KmsClient> Signer for KmsSigner<C> {
+    fn address(&self) -> Result<Address> {
+        Ok(self.address)
+    }
+
+    fn sign_hash(&self, hash: &[u8; 32]) -> Result<Signature> {
+        let (r, s) = parse_signature(&self.client.sign_digest(hash)?)?;
+        recoverable(r, s, hash, &self.address).ok_or_else(|| KmsError::KeyMismatch.into())
+    }
+}
+
+/// Parse a secp256k1 public key from its DER or PEM encoded
+/// `SubjectPublicKeyInfo`.
+fn parse_public_key(spki: &[u8]) -> Result<VerifyingKey> {
+    let der = match std::str::from_utf8(spki) {
+        Ok(pem) if pem.trim_start().starts_with("-----BEGIN PUBLIC KEY-----") => {
+            let body: String = pem
+                .lines()
+                .filter(|line| !line.starts_with("-----"))
+                .map(str::trim)
+                .collect();
+            STANDARD
+                .decode(body)
+                .map_err(|_| KmsError::InvalidPublicKey)?
+        }
+        _ => spki.to_vec(),
+    };
+
+    let point = der
+        .strip_prefix(&SPKI_HEADER[..])
+        .ok_or(KmsError::InvalidPublicKey)?;
+    if point.len() != 65 {
+        return Err(KmsError::InvalidPublicKey.into());
+    }
+    Ok(VerifyingKey::from_sec1_bytes(point).map_err(|_| KmsError::InvalidPublicKey)?)
+}
+
+/// Parse the `r` and `s` components of a DER encoded ECDSA signature.
+fn parse_signature(der: &[u8]) -> Result<([u8; 32], [u8; 32])> {
+    let body = match der {
+        [0x30, len, body @ ..] if *len as usize == body.len() => body,
+        _ => return Err(KmsError::InvalidSignature.into()),
+    };
+    let (r, rest) = parse_integer(body)?;
+    let (s, rest) = parse_integer(rest)?;
+    if !rest.is_empty() {
+        return Err(KmsError::InvalidSignature.into());
+    }
+    Ok((r, s))
+}
+
+/// Parse a DER integer of at most 32 bytes, returning it and the remaining
+/// input.
+fn parse_integer(der: &[u8]) -> std::result::Result<([u8; 32], &[u8]), KmsError> {
+    let (value, rest) = match der {
+        [0x02, len, rest @ ..] if *len as usize <= rest.len() => rest.split_at(*len as usize),
+        _ => return Err(KmsError::InvalidSignature),
+    };
+    // a leading zero keeps positive integers with the high bit set positive
+    let value = match value {
+        [0, tail @ ..] if !tail.is_empty() => tail,
+        _ => value,
+    };
+    if value.is_empty() || value.len() > 32 {
+        return Err(KmsError::InvalidSignature);
+    }
+    let mut word = [0; 32];
+    word[32 - value.len()..].copy_from_slice(value);
+    Ok((word, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::keccak256;
+    use k256::ecdsa::SigningKey;
+
+    struct MockKms {
+        key: SigningKey,
+        pem: bool,
+        high_s: bool,
+    }
+
+    fn der_integer(bytes: &[u8]) -> Vec<u8> {
+        let bytes = &bytes[bytes.iter().take_while(|b| **b == 0).count()..];
+        let padding = (bytes[0] & 0x80 != 0) as usize;
+        let mut out = vec![0x02, (bytes.len() + padding) as u8];
+        out.extend(std::iter::repeat_n(0, padding));
+        out.extend_from_slice(bytes);
+        out
+    }
+
+    impl KmsClient for MockKms {
+        fn public_key(&self) -> Result<Vec<u8>> {
+            let point = self.key.verifying_key().to_encoded_point(false);
+            let der = [&SPKI_HEADER[..], point.as_bytes()].concat();
+            if !self.pem {
+                return Ok(der);
+            }
+            let pem = format!(
+                "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----\n",
+                STANDARD.encode(der)
+            );
+            Ok(pem.into_bytes())
+        }
+
+        fn sign_digest(&self, digest: &[u8; 32]) -> Result<Vec<u8>> {
+            let (signature, _): (k256::ecdsa::Signature, _) =
+                self.key.sign_prehash_recoverable(digest)?;
+            let (r, mut s) = signature.split_scalars();
+            if self.high_s {
+                s = -s;
+            }
+            let body = [der_integer(&r.to_bytes()), der_integer(&s.to_bytes())].concat();
+            Ok([vec![0x30, body.len() as u8], body].concat())
+        }
+    }
+
+    #[test]
+    fn test_sign_hash() {
+        let key = SigningKey::from_slice(&[0x46; 32]).unwrap();
+        let expected = Address::from(key.verifying_key());
+
+        for (pem, high_s) in [(false, false), (true, true)] {
+            let signer = KmsSigner::new(MockKms {
+                key: key.clone(),
+                pem,
+                high_s,
+            })
+            .unwrap();
+            assert_eq!(signer.address().unwrap(), expected);
+
+            let hash = keccak256("hello");
+            let signature = signer.sign_hash(&hash).unwrap();
+            assert!(signature.is_low_s());
+            assert_eq!(signature.recover(&hash).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_parse_signature() {
+        let mut der = vec![0x30, 0x26, 0x02, 0x21, 0x00, 0x80];
+        der.extend([0x11; 31]);
+        der.extend([0x02, 0x01, 0x05]);
+        let (r, s) = parse_signature(&der).unwrap();
+        assert_eq!(r[0], 0x80);
+        assert_eq!(s[31], 5);
+        assert_eq!(s[..31], [0; 31]);
+
+        assert!(parse_signature(&der[..der.len() - 1]).is_err());
+        assert!(parse_signature(&[0x30, 0x00]).is_err());
+        assert!(parse_public_key(&[0x30; 88]).is_err());
+    }
+}
@@ -0,0 +1,275 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Signers
+//!
+//! The [`Signer`] trait abstracts over where a signing key lives, so code
+//! producing signatures does not depend on a concrete key type.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::bips::{bip32::ExtendedKey, bip39::Mnemonic, wordlists::Language, DerivationPath};
+//! use wallet_rust::signer::Signer;
+//!
+//! let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+//! let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+//! let path = DerivationPath::parse("m/44'/60'/0'/0/0").unwrap();
+//! let key = ExtendedKey::new_master(&mnemonic.to_seed("")).unwrap().derive_path(&path).unwrap();
+//!
+//! let signature = key.sign_message("hello").unwrap();
+//! assert_eq!(signature.recover_personal("hello").unwrap(), key.address().unwrap());
+//! ```
+
+mod guard;
+mod kms;
+mod pkcs11;
+mod totp;
+#[cfg(feature = "trezor")]
+pub mod trezor;
+
+pub use guard::*;
+pub use kms::*;
+pub use pkcs11::*;
+pub use totp::*;
+
+use k256::ecdsa::SigningKey;
+use laron_crypto::PrivateKey;
+
+use crate::{
+    address::Address,
+    bips::bip32::ExtendedKey,
+    chains::Chain,
+    eips::{eip191, eip712::TypedData},
+    error::Result,
+    signature::Signature,
+    transaction::{Transaction, TypedTransaction},
+};
+
+/// A key able to produce Ethereum signatures.
+///
+/// Only [`address`](Signer::address) and [`sign_hash`](Signer::sign_hash)
+/// are required, the other methods sign the hash of their input. Backends
+/// that cannot sign arbitrary hashes, such as hardware wallets, override
+/// them instead and refuse [`sign_hash`](Signer::sign_hash).
+pub trait Signer {
+    /// Return the address of the key.
+    fn address(&self) -> Result<Address>;
+
+    /// Return the chain id used for transactions that do not set one.
+    fn chain_id(&self) -> Option<u64> {
+        None
+    }
+
+    /// Sign a 32-byte hash. The signature has a low `s` and `v` is 27 or 28.
+    fn sign_hash(&self, hash: &[u8; 32]) -> Result<Signature>;
+
+    /// Sign a `personal_sign` message as defined in EIP-191.
+    fn sign_message(&self, message: impl AsRef<[u8]>) -> Result<Signature>
+    where
+        Self: Sized,
+    {
+        self.sign_hash(&eip191::hash_message(message))
+    }
+
+    /// Sign a transaction. Without a chain id on the transaction the one
+    /// of the signer is used, and `v` encodes it as defined in EIP-155.
+    fn sign_transaction(&self, tx: &Transaction) -> Result<Signature> {
+        let chain_id = tx.chain_id.or(self.chain_id());
+        let tx = Transaction {
+            chain_id,
+            ..tx.clone()
+        };
+        let signature = self.sign_hash(&tx.signing_hash())?;
+        match chain_id {
+            Some(chain_id) => Ok(signature.with_chain_id(chain_id)?),
+            None => Ok(signature),
+        }
+    }
+
+    /// Sign a transaction of any type. Legacy transactions are signed with
+    /// [`sign_transaction`](Signer::sign_transaction), the others sign
+    /// their hash and carry their own chain id.
+    fn sign_typed_transaction(&self, tx: &TypedTransaction) -> Result<Signature> {
+        match tx {
+            TypedTransaction::Legacy(tx) => self.sign_transaction(tx),
+            _ => self.sign_hash(&tx.signing_hash()),
+        }
+    }
+
+    /// Sign EIP-712 typed data.
+    fn sign_typed_data(&self, data: &TypedData) -> Result<Signature> {
+        self.sign_hash(&data.signing_hash())
+    }
+
+    /// Use the id of `chain` for the transactions that do not set one.
+    fn with_chain_id(self, chain: impl Into<Chain>) -> ChainSigner<Self>
+    where
+        Self: Sized,
+    {
+        ChainSigner {
+            signer: self,
+            chain: chain.into(),
+        }
+    }
+}
+
+/// A [`Signer`] with a default chain id, created by
+/// [`Signer::with_chain_id`].
+#[derive(Debug, Clone)]
+pub struct ChainSigner<S> {
+    signer: S,
+    chain: Chain,
+}
+
+impl<S> ChainSigner<S> {
+    /// Return the wrapped signer.
+    pub fn inner(&self) -> &S {
+        &self.signer
+    }
+
+    /// Return the chain of the transactions that do not set one.
+    pub fn chain(&self) -> &Chain {
+        &self.chain
+    }
+}
+
+impl<S: Signer> Signer for ChainSigner<S> {
+    fn address(&self) -> Result<Address> {
+        self.signer.address()
+    }
+
+    fn chain_id(&self) -> Option<u64> {
+        Some(self.chain.id)
+    }
+
+    fn sign_hash(&self, hash: &[u8; 32]) -> Result<Signature> {
+        self.signer.sign_hash(hash)
+    }
+
+    fn sign_message(&self, message: impl AsRef<[u8]>) -> Result<Signature> {
+        self.signer.sign_message(message)
+    }
+
+    fn sign_transaction(&self, tx: &Transaction) -> Result<Signature> {
+        let tx = Transaction {
+            chain_id: tx.chain_id.or(Some(self.chain.id)),
+            ..tx.clone()
+        };
+        self.signer.sign_transaction(&tx)
+    }
+
+    fn sign_typed_transaction(&self, tx: &TypedTransaction) -> Result<Signature> {
+        match tx {
+            TypedTransaction::Legacy(tx) => self.sign_transaction(tx),
+            _ => self.signer.sign_typed_transaction(tx),
+        }
+    }
+
+    fn sign_typed_data(&self, data: &TypedData) -> Result<Signature> {
+        self.signer.sign_typed_data(data)
+    }
+}
+
+impl Signer for PrivateKey {
+    fn address(&self) -> Result<Address> {
+        Address::from_public_key(&self.public_key())
+    }
+
+    fn sign_hash(&self, hash: &[u8; 32]) -> Result<Signature> {
+        let key = SigningKey::from_slice(&self.to_bytes())?;
+        let (signature, recovery_id) = key.sign_prehash_recoverable(hash)?;
+        Ok(Signature::from_recovery_id(
+            signature.r().to_bytes().into(),
+            signature.s().to_bytes().into(),
+            recovery_id.to_byte(),
+        ))
+    }
+}
+
+impl Signer for ExtendedKey {
+    fn address(&self) -> Result<Address> {
+        Address::from_public_key(self.public_key())
+    }
+
+    fn sign_hash(&self, hash: &[u8; 32]) -> Result<Signature> {
+        self.private_key().sign_hash(hash)
+    }
+}
+
+/// Complete a signature without recovery id, as returned by HSMs, with the
+/// recovery id matching `address`. `s` is lowered as required by EIP-2.
+fn recoverable(r: [u8; 32], s: [u8; 32], hash: &[u8; 32], address: &Address) -> Option<Signature> {
+    (0..2)
+        .filter_map(|id| Signature::from_recovery_id(r, s, id).normalize_s().ok())
+        .find(|signature| signature.recover(hash).ok().as_ref() == Some(address))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bips::{bip39::Mnemonic, wordlists::Language, DerivationPath},
+        utils::keccak256,
+    };
+
+    #[test]
+    fn test_sign_hash() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+        let path = DerivationPath::parse("m/44'/60'/0'/0/0").unwrap();
+        let key = ExtendedKey::new_master(&mnemonic.to_seed(""))
+            .unwrap()
+            .derive_path(&path)
+            .unwrap();
+
+        assert_eq!(
+            key.address().unwrap().to_string(),
+            "0x9858EfFD232B4033E47d90003D41EC34EcaEda94"
+        );
+
+        let hash = keccak256("hello");
+        let signature = key.sign_hash(&hash).unwrap();
+        assert!(signature.is_low_s());
+        assert_eq!(signature.recover(&hash).unwrap(), key.address().unwrap());
+    }
+
+    #[test]
+    fn test_sign_transaction() {
+        // example from EIP-155
+        let key = PrivateKey::from_bytes(&[0x46; 32]).unwrap();
+        let mut tx = Transaction {
+            nonce: 9,
+            gas_price: 20_000_000_000,
+            gas_limit: 21_000,
+            to: Some(Address::new([0x35; 20])),
+            value: 1_000_000_000_000_000_000,
+            data: Vec::new(),
+            chain_id: Some(1),
+        };
+        let signature = key.sign_transaction(&tx).unwrap();
+        assert_eq!(
+            hex::encode(signature.to_compact()),
+            "28ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa63627667cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+        );
+        assert_eq!(signature.v(), 37);
+
+        tx.chain_id = None;
+        assert_eq!(key.sign_transaction(&tx).unwrap().chain_id(), None);
+        let signer = key.clone().with_chain_id(1);
+        assert_eq!(signer.chain_id(), Some(1));
+        assert_eq!(signer.sign_transaction(&tx).unwrap(), signature);
+        let signer = key.with_chain_id(crate::chains::MAINNET);
+        assert_eq!(signer.chain(), &crate::chains::MAINNET);
+        assert_eq!(signer.sign_transaction(&tx).unwrap(), signature);
+    }
+}
@@ -0,0 +1,248 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Signing with secp256k1 keys resident on a PKCS#11 token, such as a
+//! smartcard, a YubiKey or a YubiHSM.
+//!
+//! The PKCS#11 library is loaded by the application, for example with the
+//! `cryptoki` crate, and exposed through the [`Pkcs11Module`] and
+//! [`Pkcs11Session`] traits: opening a session on a slot, reading the
+//! `CKA_EC_POINT` of a key and signing a digest with `CKM_ECDSA`.
+//! [`Pkcs11Signer`] turns the raw `r || s` signatures into Ethereum ones.
+//!
+//! ## Example
+//! ```rust,no_run
+//! # use wallet_rust::signer::{Pkcs11Module, Pkcs11Session};
+//! # struct Module;
+//! # struct Session;
+//! # impl Pkcs11Module for Module {
+//! #     type Session = Session;
+//! #     fn open(&self, _: u64, _: Option<&str>) -> wallet_rust::error::Result<Session> { unimplemented!() }
+//! # }
+//! # impl Pkcs11Session for Session {
+//! #     fn ec_point(&self, _: &str) -> wallet_rust::error::Result<Vec<u8>> { unimplemented!() }
+//! #     fn sign(&self, _: &str, _: &[u8; 32]) -> wallet_rust::error::Result<Vec<u8>> { unimplemented!() }
+//! # }
+//! # fn load_module(_: &str) -> Module { Module }
+//! use wallet_rust::signer::{Pkcs11Config, Pkcs11Signer, Signer};
+//!
+//! let module = load_module("/usr/lib/libykcs11.so");
+//! let config = Pkcs11Config::new(0, "wallet").with_pin("123456");
+//! let signer = Pkcs11Signer::open(&module, &config).unwrap();
+//! println!("{}", signer.address().unwrap());
+//! ```
+
+use k256::ecdsa::VerifyingKey;
+use zeroize::Zeroizing;
+
+use crate::{address::Address, error::Result, signature::Signature};
+
+use super::{recoverable, Signer};
+
+/// Error returned when decoding the response of a PKCS#11 token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pkcs11Error {
+    /// The `CKA_EC_POINT` is not an uncompressed secp256k1 point.
+    InvalidPublicKey,
+    /// The signature is not a 64-byte `r || s` pair.
+    InvalidSignature,
+    /// The signature was not made by the key.
+    KeyMismatch,
+}
+
+impl std::fmt::Display for Pkcs11Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Pkcs11Error::InvalidPublicKey => write!(f, "Invalid PKCS#11 public key"),
+            Pkcs11Error::InvalidSignature => write!(f, "Invalid PKCS#11 signature"),
+            Pkcs11Error::KeyMismatch => write!(f, "PKCS#11 signature does not match the key"),
+        }
+    }
+}
+
+impl std::error::Error for Pkcs11Error {}
+
+/// Location of a key on a PKCS#11 token.
+#[derive(Clone)]
+pub struct Pkcs11Config {
+    /// Slot the token is inserted in.
+    pub slot: u64,
+    /// `CKA_LABEL` of the key.
+    pub label: String,
+    /// User PIN, `None` for tokens with a protected authentication path
+    /// such as a PIN pad.
+    pub pin: Option<Zeroizing<String>>,
+}
+
+impl Pkcs11Config {
+    /// Create a new configuration for the key labelled `label` on `slot`.
+    pub fn new(slot: u64, label: impl Into<String>) -> Self {
+        Self {
+            slot,
+            label: label.into(),
+            pin: None,
+        }
+    }
+
+    /// Log in with a user PIN. YubiHSM expects the authentication key id
+    /// as four hex digits followed by its password.
+    pub fn with_pin(mut self, pin: impl Into<String>) -> Self {
+        self.pin = Some(Zeroizing::new(pin.into()));
+        self
+    }
+}
+
+impl std::fmt::Debug for Pkcs11Config {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Pkcs11Config")
+            .field("slot", &self.slot)
+            .field("label", &self.label)
+            .field("pin", &self.pin.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+/// A loaded PKCS#11 library.
+pub trait Pkcs11Module {
+    /// Session type of the library.
+    type Session: Pkcs11Session;
+
+    /// Open a session on `slot`, logged in as the user with `pin` if set.
+    fn open(&self, slot: u64, pin: Option<&str>) -> Result<Self::Session>;
+}
+
+/// A logged in session on a PKCS#11 token.
+pub trait Pkcs11Session {
+    /// Return the `CKA_EC_POINT` of the public key labelled `label`.
+    fn ec_point(&self, label: &str) -> Result<Vec<u8>>;
+
+    /// Sign a 32-byte digest with `CKM_ECDSA` and the private key labelled
+    /// `label`, returning `r || s`.
+    fn sign(&self, label: &str, digest: &[u8; 32]) -> Result<Vec<u8>>;
+}
+
+/// A [`Signer`] backed by a key on a PKCS#11 token.
+pub struct Pkcs11Signer<S> {
+    session: S,
+    label: String,
+    address: Address,
+}
+
+impl<S: Pkcs11Session> Pkcs11Signer<S> {
+    /// Open a session as configured and read the address of the key.
+    pub fn open<M>(module: &M, config: &Pkcs11Config) -> Result<Self>
+    where
+        M: Pkcs11Module<Session = S>,
+    {
+        let session = module.open(config.slot, config.pin.as_deref().map(String::as_str))?;
+        Self::new(session, &config.label)
+    }
+
+    /// Use the key labelled `label` of an open session.
+    pub fn new(session: S, label: &str) -> Result<Self> {
+        let key = parse_ec_point(&session.ec_point(label)?)?;
+        Ok(Self {
+            session,
+            label: label.to_string(),
+            address: Address::from(&key),
+        })
+    }
+
+    /// Return the session.
+    pub fn session(&self) -> &S {
+        &self.session
+    }
+}
+
+impl<S: Pkcs11Session> Signer for Pkcs11Signer<S> {
+    fn address(&self) -> Result<Address> {
+        Ok(self.address)
+    }
+
+    fn sign_hash(&self, hash: &[u8; 32]) -> Result<Signature> {
+        let signature = self.session.sign(&self.label, hash)?;
+        if signature.len() != 64 {
+            return Err(Pkcs11Error::InvalidSignature.into());
+        }
+        let (r, s) = signature.split_at(32);
+        recoverable(
+            r.try_into().expect("32 bytes"),
+            s.try_into().expect("32 bytes"),
+            hash,
+            &self.address,
+        )
+        .ok_or_else(|| Pkcs11Error::KeyMismatch.into())
+    }
+}
+
+/// Parse a `CKA_EC_POINT`, a DER octet string wrapping the uncompressed
+/// point. Some tokens return the bare point.
+fn parse_ec_point(ec_point: &[u8]) -> Result<VerifyingKey> {
+    let point = match ec_point {
+        [0x04, 0x41, point @ ..] if point.len() == 65 => point,
+        _ if ec_point.len() == 65 => ec_point,
+        _ => return Err(Pkcs11Error::InvalidPublicKey.into()),
+    };
+    Ok(VerifyingKey::from_sec1_bytes(point).map_err(|_| Pkcs11Error::InvalidPublicKey)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::keccak256;
+    use k256::ecdsa::SigningKey;
+    use std::collections::HashMap;
+
+    struct MockModule(HashMap<String, SigningKey>);
+
+    struct MockSession(HashMap<String, SigningKey>);
+
+    impl Pkcs11Module for MockModule {
+        type Session = MockSession;
+
+        fn open(&self, slot: u64, pin: Option<&str>) -> Result<MockSession> {
+            assert_eq!((slot, pin), (1, Some("123456")));
+            Ok(MockSession(self.0.clone()))
+        }
+    }
+
+    impl Pkcs11Session for MockSession {
+        fn ec_point(&self, label: &str) -> Result<Vec<u8>> {
+            let point = self.0[label].verifying_key().to_encoded_point(false);
+            Ok([&[0x04, 0x41][..], point.as_bytes()].concat())
+        }
+
+        fn sign(&self, label: &str, digest: &[u8; 32]) -> Result<Vec<u8>> {
+            let (signature, _): (k256::ecdsa::Signature, _) =
+                self.0[label].sign_prehash_recoverable(digest)?;
+            Ok(signature.to_bytes().to_vec())
+        }
+    }
+
+    #[test]
+    fn test_sign_hash() {
+        let key = SigningKey::from_slice(&[0x46; 32]).unwrap();
+        let expected = Address::from(key.verifying_key());
+        let module = MockModule(HashMap::from([("wallet".to_string(), key)]));
+
+        let config = Pkcs11Config::new(1, "wallet").with_pin("123456");
+        assert!(!format!("{:?}", config).contains("123456"));
+        let signer = Pkcs11Signer::open(&module, &config).unwrap();
+        assert_eq!(signer.address().unwrap(), expected);
+
+        let hash = keccak256("hello");
+        let signature = signer.sign_hash(&hash).unwrap();
+        assert_eq!(signature.recover(&hash).unwrap(), expected);
+        assert!(parse_ec_point(&[0x04; 10]).is_err());
+    }
+}
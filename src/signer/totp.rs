@@ -0,0 +1,207 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Time-based one-time passwords as defined in [RFC 6238](https://www.rfc-editor.org/rfc/rfc6238),
+//! with the HMAC-SHA1, 6 digits and 30 seconds parameters expected by
+//! authenticator apps.
+
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha1::Sha1;
+
+use crate::error::Result;
+
+/// Length of a generated secret, as recommended by RFC 4226.
+const SECRET_LENGTH: usize = 20;
+
+/// Error returned when decoding a TOTP secret.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TotpError {
+    /// The secret is not valid base32.
+    InvalidSecret,
+}
+
+impl std::fmt::Display for TotpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TotpError::InvalidSecret => write!(f, "Invalid TOTP secret"),
+        }
+    }
+}
+
+impl std::error::Error for TotpError {}
+
+/// A TOTP generator and verifier for a shared secret.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Totp {
+    secret: Vec<u8>,
+    digits: u32,
+    step: u64,
+}
+
+impl Totp {
+    /// Create a generator of 6 digit codes changing every 30 seconds.
+    pub fn new(secret: Vec<u8>) -> Self {
+        Self {
+            secret,
+            digits: 6,
+            step: 30,
+        }
+    }
+
+    /// Create a generator with a new random secret, to be provisioned in the
+    /// authenticator of the user with [`Totp::provisioning_uri`].
+    pub fn random() -> Self {
+        let mut secret = vec![0u8; SECRET_LENGTH];
+        rand::thread_rng().fill_bytes(&mut secret);
+        Self::new(secret)
+    }
+
+    /// Create a generator from a base32 secret, as shown by authenticators.
+    pub fn from_base32(secret: &str) -> Result<Self> {
+        let secret: String = secret.split_whitespace().collect();
+        let secret = base32::decode(
+            base32::Alphabet::RFC4648 { padding: false },
+            secret.trim_end_matches('='),
+        )
+        .ok_or(TotpError::InvalidSecret)?;
+        Ok(Self::new(secret))
+    }
+
+    /// Return the secret encoded in base32.
+    pub fn to_base32(&self) -> String {
+        base32::encode(base32::Alphabet::RFC4648 { padding: false }, &self.secret)
+    }
+
+    /// Return the `otpauth://` URI to provision the secret, usually shown as
+    /// a QR code.
+    pub fn provisioning_uri(&self, issuer: &str, account: &str) -> String {
+        format!(
+            "otpauth://totp/{}:{}?secret={}&issuer={}&digits={}&period={}",
+            percent_encode(issuer),
+            percent_encode(account),
+            self.to_base32(),
+            percent_encode(issuer),
+            self.digits,
+            self.step
+        )
+    }
+
+    /// Return the code valid at the given unix time.
+    pub fn generate(&self, unix_time: u64) -> String {
+        self.code(unix_time / self.step)
+    }
+
+    /// Verify a code at the given unix time, tolerating one step of clock
+    /// drift, and return the time step it belongs to.
+    pub fn verify(&self, code: &str, unix_time: u64) -> Option<u64> {
+        let step = unix_time / self.step;
+        [step, step.saturating_sub(1), step + 1]
+            .into_iter()
+            .find(|step| constant_time_eq(self.code(*step).as_bytes(), code.as_bytes()))
+    }
+
+    fn code(&self, step: u64) -> String {
+        let mut mac = Hmac::<Sha1>::new_from_slice(&self.secret).expect("any key length");
+        mac.update(&step.to_be_bytes());
+        let hash = mac.finalize().into_bytes();
+
+        // dynamic truncation of RFC 4226
+        let offset = (hash[19] & 0x0f) as usize;
+        let value = u32::from_be_bytes([
+            hash[offset] & 0x7f,
+            hash[offset + 1],
+            hash[offset + 2],
+            hash[offset + 3],
+        ]);
+
+        format!(
+            "{:0width$}",
+            value % 10u32.pow(self.digits),
+            width = self.digits as usize
+        )
+    }
+}
+
+impl std::fmt::Debug for Totp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Totp")
+            .field("secret", &"<redacted>")
+            .field("digits", &self.digits)
+            .field("step", &self.step)
+            .finish()
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc6238() {
+        let totp = Totp {
+            secret: b"12345678901234567890".to_vec(),
+            digits: 8,
+            step: 30,
+        };
+
+        let vectors = [
+            (59, "94287082"),
+            (1111111109, "07081804"),
+            (1111111111, "14050471"),
+            (1234567890, "89005924"),
+            (2000000000, "69279037"),
+            (20000000000, "65353130"),
+        ];
+        for (time, code) in vectors {
+            assert_eq!(totp.generate(time), code);
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let totp = Totp::from_base32("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ").unwrap();
+        assert_eq!(totp.to_base32(), "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+        assert_eq!(totp.generate(59), "287082");
+
+        assert_eq!(totp.verify("287082", 59), Some(1));
+        assert_eq!(totp.verify("287082", 89), Some(1));
+        assert_eq!(totp.verify("287082", 119), None);
+        assert_eq!(totp.verify("28708", 59), None);
+    }
+
+    #[test]
+    fn test_provisioning_uri() {
+        let totp = Totp::from_base32("GEZDGNBVGY3TQOJQ").unwrap();
+        assert_eq!(
+            totp.provisioning_uri("My Wallet", "alice@example.com"),
+            "otpauth://totp/My%20Wallet:alice%40example.com?secret=GEZDGNBVGY3TQOJQ\
+             &issuer=My%20Wallet&digits=6&period=30"
+        );
+    }
+}
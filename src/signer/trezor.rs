@@ -0,0 +1,768 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Signing with a [Trezor](https://trezor.io) hardware wallet.
+//!
+//! [`Trezor`] speaks the device protocol: protobuf messages split into
+//! 64-byte packets, as exchanged over USB HID and WebUSB. Opening the device
+//! is left to the platform, which provides the packets through the
+//! [`Transport`] trait. Requests the device makes along the way are answered
+//! here: button requests are acknowledged after notifying the application,
+//! PIN and passphrase requests are forwarded to the registered callbacks.
+//!
+//! The device only signs what it can show to the user, so instead of raw
+//! hashes it signs messages, transactions and EIP-712 hashes.
+//! [`TrezorSigner`] exposes one of its keys through the [`Signer`] trait.
+//!
+//! ## Example
+//! ```rust,no_run
+//! # use wallet_rust::signer::trezor::{Transport, PACKET_SIZE};
+//! # struct Device;
+//! # impl Transport for Device {
+//! #     fn write(&mut self, _: &[u8; PACKET_SIZE]) -> wallet_rust::error::Result<()> { unimplemented!() }
+//! #     fn read(&mut self) -> wallet_rust::error::Result<[u8; PACKET_SIZE]> { unimplemented!() }
+//! # }
+//! # fn open_device() -> Device { Device }
+//! use wallet_rust::bips::DerivationPath;
+//! use wallet_rust::signer::trezor::Trezor;
+//!
+//! let path = DerivationPath::parse("m/44'/60'/0'/0/0").unwrap();
+//! let mut trezor = Trezor::new(open_device());
+//! trezor.on_button(|| println!("Confirm on your Trezor"));
+//!
+//! let address = trezor.address(&path, true).unwrap();
+//! let signature = trezor.sign_message(&path, "hello").unwrap();
+//! assert_eq!(signature.recover_personal("hello").unwrap(), address);
+//! ```
+
+use zeroize::Zeroizing;
+
+use std::sync::Mutex;
+
+use crate::{
+    address::Address,
+    bips::DerivationPath,
+    eips::eip712::TypedData,
+    error::Result,
+    signature::Signature,
+    signer::Signer,
+    transaction::{be_bytes, Transaction},
+};
+
+/// Size of a USB packet.
+pub const PACKET_SIZE: usize = 64;
+
+/// Largest data chunk sent with a transaction or acknowledgement.
+const DATA_CHUNK_SIZE: usize = 1024;
+
+const FAILURE: u16 = 3;
+const PIN_MATRIX_REQUEST: u16 = 18;
+const PIN_MATRIX_ACK: u16 = 19;
+const BUTTON_REQUEST: u16 = 26;
+const BUTTON_ACK: u16 = 27;
+const PASSPHRASE_REQUEST: u16 = 41;
+const PASSPHRASE_ACK: u16 = 42;
+const ETHEREUM_GET_ADDRESS: u16 = 56;
+const ETHEREUM_ADDRESS: u16 = 57;
+const ETHEREUM_SIGN_TX: u16 = 58;
+const ETHEREUM_TX_REQUEST: u16 = 59;
+const ETHEREUM_TX_ACK: u16 = 60;
+const ETHEREUM_SIGN_MESSAGE: u16 = 64;
+const ETHEREUM_MESSAGE_SIGNATURE: u16 = 66;
+const ETHEREUM_TYPED_DATA_SIGNATURE: u16 = 469;
+const ETHEREUM_SIGN_TYPED_HASH: u16 = 470;
+
+/// Error returned by a [`Trezor`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrezorError {
+    /// The device refused the request, for example because the user
+    /// cancelled it.
+    Failure {
+        /// The failure code sent by the device.
+        code: u32,
+        /// The reason sent by the device.
+        message: String,
+    },
+    /// The device answered with a message of an unexpected type.
+    UnexpectedMessage(u16),
+    /// A packet read from the transport is malformed.
+    InvalidPacket,
+    /// A message sent by the device is malformed.
+    InvalidMessage,
+    /// The device asked for a PIN but no callback is registered.
+    PinRequired,
+    /// The device asked for a passphrase but no callback is registered.
+    PassphraseRequired,
+    /// The device only signs transactions bound to a chain.
+    ChainIdRequired,
+    /// The device does not sign raw hashes.
+    HashUnsupported,
+}
+
+impl std::fmt::Display for TrezorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TrezorError::Failure { code, message } => {
+                write!(f, "Trezor failure {}: {}", code, message)
+            }
+            TrezorError::UnexpectedMessage(ty) => {
+                write!(f, "Unexpected Trezor message type {}", ty)
+            }
+            TrezorError::InvalidPacket => write!(f, "Invalid Trezor packet"),
+            TrezorError::InvalidMessage => write!(f, "Invalid Trezor message"),
+            TrezorError::PinRequired => write!(f, "Trezor PIN required"),
+            TrezorError::PassphraseRequired => write!(f, "Trezor passphrase required"),
+            TrezorError::ChainIdRequired => write!(f, "Trezor requires a chain id"),
+            TrezorError::HashUnsupported => write!(f, "Trezor does not sign raw hashes"),
+        }
+    }
+}
+
+impl std::error::Error for TrezorError {}
+
+/// Packet level access to a device, such as a USB HID or WebUSB interface.
+pub trait Transport {
+    /// Write one packet.
+    fn write(&mut self, packet: &[u8; PACKET_SIZE]) -> Result<()>;
+
+    /// Read one packet, blocking until the device sends it.
+    fn read(&mut self) -> Result<[u8; PACKET_SIZE]>;
+}
+
+type PinCallback = Box<dyn FnMut() -> Result<Zeroizing<String>> + Send>;
+type ButtonCallback = Box<dyn FnMut() + Send>;
+
+/// A Trezor device.
+pub struct Trezor<T> {
+    transport: T,
+    pin: Option<PinCallback>,
+    passphrase: Option<PinCallback>,
+    button: Option<ButtonCallback>,
+}
+
+impl<T: Transport> Trezor<T> {
+    /// Create a new device over a transport.
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport,
+            pin: None,
+            passphrase: None,
+            button: None,
+        }
+    }
+
+    /// Call `callback` when the device waits for the user to press its
+    /// button, to tell the user to look at the device.
+    pub fn on_button(&mut self, callback: impl FnMut() + Send + 'static) {
+        self.button = Some(Box::new(callback));
+    }
+
+    /// Call `callback` when the device asks for its PIN. The device shows
+    /// a scrambled keypad, the callback returns the positions the user picks
+    /// on it, numbered like a phone keypad upside down: `7 8 9` on the top
+    /// row and `1 2 3` on the bottom one.
+    pub fn on_pin(&mut self, callback: impl FnMut() -> Result<Zeroizing<String>> + Send + 'static) {
+        self.pin = Some(Box::new(callback));
+    }
+
+    /// Call `callback` when the device asks for the BIP-39 passphrase. The
+    /// passphrase is entered on the device itself if no callback is
+    /// registered.
+    pub fn on_passphrase(
+        &mut self,
+        callback: impl FnMut() -> Result<Zeroizing<String>> + Send + 'static,
+    ) {
+        self.passphrase = Some(Box::new(callback));
+    }
+
+    /// Return the transport.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Return the address at `path`. With `display` the device shows the
+    /// address and waits for the user to confirm it matches.
+    pub fn address(&mut self, path: &DerivationPath, display: bool) -> Result<Address> {
+        let request = Message::new().path(path).varint(2, display as u64);
+        let response = self.call(ETHEREUM_GET_ADDRESS, request, ETHEREUM_ADDRESS)?;
+        match (response.string(2)?, response.bytes(1)) {
+            (Some(address), _) => Ok(address.parse()?),
+            // firmware older than 1.8 sends raw bytes
+            (None, Some(bytes)) => Ok(Address::from_slice(bytes)?),
+            (None, None) => Err(TrezorError::InvalidMessage.into()),
+        }
+    }
+
+    /// Sign a `personal_sign` message as defined in EIP-191 with the key at
+    /// `path`.
+    pub fn sign_message(
+        &mut self,
+        path: &DerivationPath,
+        message: impl AsRef<[u8]>,
+    ) -> Result<Signature> {
+        let request = Message::new().path(path).bytes(2, message.as_ref());
+        let response = self.call(ETHEREUM_SIGN_MESSAGE, request, ETHEREUM_MESSAGE_SIGNATURE)?;
+        Signature::from_bytes(response.bytes(2).ok_or(TrezorError::InvalidMessage)?)
+    }
+
+    /// Sign EIP-712 typed data with the key at `path`. The device shows the
+    /// domain separator and struct hash for the user to confirm.
+    pub fn sign_typed_data(
+        &mut self,
+        path: &DerivationPath,
+        data: &TypedData,
+    ) -> Result<Signature> {
+        let mut request = Message::new()
+            .path(path)
+            .bytes(2, data.domain_separator.as_ref());
+        if let Some(hash) = &data.struct_hash {
+            request = request.bytes(3, hash.as_ref());
+        }
+        let response = self.call(
+            ETHEREUM_SIGN_TYPED_HASH,
+            request,
+            ETHEREUM_TYPED_DATA_SIGNATURE,
+        )?;
+        Signature::from_bytes(response.bytes(1).ok_or(TrezorError::InvalidMessage)?)
+    }
+
+    /// Sign a transaction with the key at `path`. The device shows the
+    /// recipient, value and fee for the user to confirm. `v` of the
+    /// signature includes the chain id as defined in EIP-155, which the
+    /// device requires.
+    pub fn sign_transaction(
+        &mut self,
+        path: &DerivationPath,
+        tx: &Transaction,
+    ) -> Result<Signature> {
+        let chain_id = tx.chain_id.ok_or(TrezorError::ChainIdRequired)?;
+        let (initial, mut rest) = tx.data.split_at(tx.data.len().min(DATA_CHUNK_SIZE));
+        let mut request = Message::new()
+            .path(path)
+            .bytes(2, &be_bytes(tx.nonce as u128))
+            .bytes(3, &be_bytes(tx.gas_price))
+            .bytes(4, &be_bytes(tx.gas_limit as u128))
+            .bytes(6, &be_bytes(tx.value))
+            .varint(9, chain_id);
+        if !tx.data.is_empty() {
+            request = request.bytes(7, initial).varint(8, tx.data.len() as u64);
+        }
+        let to = tx.to.map(|to| to.to_string()).unwrap_or_default();
+        request = request.bytes(11, to.as_bytes());
+
+        let mut response = self.call(ETHEREUM_SIGN_TX, request, ETHEREUM_TX_REQUEST)?;
+        while let Some(length) = response.varint(1) {
+            let length = (length as usize).min(DATA_CHUNK_SIZE);
+            if length > rest.len() {
+                return Err(TrezorError::InvalidMessage.into());
+            }
+            let (chunk, remaining) = rest.split_at(length);
+            rest = remaining;
+            let ack = Message::new().bytes(1, chunk);
+            response = self.call(ETHEREUM_TX_ACK, ack, ETHEREUM_TX_REQUEST)?;
+        }
+
+        let v = response.varint(2).ok_or(TrezorError::InvalidMessage)?;
+        let r = response.word(3)?;
+        let s = response.word(4)?;
+        // the device sends the bare recovery id for chain ids that do not
+        // fit in 32 bits
+        let v = if v <= 1 { v + 35 + 2 * chain_id } else { v };
+        Ok(Signature::new(r, s, v))
+    }
+
+    /// Send a request and read the response of type `expected`, answering
+    /// the interaction requests in between.
+    fn call(&mut self, ty: u16, request: Message, expected: u16) -> Result<Fields> {
+        self.write_message(ty, &request.0)?;
+        loop {
+            let (ty, payload) = read_message(|| self.transport.read())?;
+            let fields = Fields::decode(&payload)?;
+            match ty {
+                ty if ty == expected => return Ok(fields),
+                BUTTON_REQUEST => {
+                    if let Some(callback) = &mut self.button {
+                        callback();
+                    }
+                    self.write_message(BUTTON_ACK, &[])?;
+                }
+                PIN_MATRIX_REQUEST => {
+                    let callback = self.pin.as_mut().ok_or(TrezorError::PinRequired)?;
+                    let pin = callback()?;
+                    let ack = Message::new().bytes(1, pin.as_bytes());
+                    self.write_message(PIN_MATRIX_ACK, &Zeroizing::new(ack.0))?;
+                }
+                PASSPHRASE_REQUEST => {
+                    let ack = match &mut self.passphrase {
+                        Some(callback) => Message::new().bytes(1, callback()?.as_bytes()),
+                        None => Message::new().varint(3, 1),
+                    };
+                    self.write_message(PASSPHRASE_ACK, &Zeroizing::new(ack.0))?;
+                }
+                FAILURE => {
+                    return Err(TrezorError::Failure {
+                        code: fields.varint(1).unwrap_or_default() as u32,
+                        message: fields.string(2)?.unwrap_or_default().to_string(),
+                    }
+                    .into())
+                }
+                ty => return Err(TrezorError::UnexpectedMessage(ty).into()),
+            }
+        }
+    }
+
+    fn write_message(&mut self, ty: u16, payload: &[u8]) -> Result<()> {
+        for packet in packets(ty, payload) {
+            self.transport.write(&packet)?;
+        }
+        Ok(())
+    }
+}
+
+/// The key of a [`Trezor`] at a derivation path, as a [`Signer`].
+///
+/// Raw hashes are refused, as the device cannot show what they stand for.
+pub struct TrezorSigner<T> {
+    trezor: Mutex<Trezor<T>>,
+    path: DerivationPath,
+    address: Address,
+}
+
+impl<T: Transport> TrezorSigner<T> {
+    /// Use the key at `path`, reading its address from the device.
+    pub fn new(mut trezor: Trezor<T>, path: DerivationPath) -> Result<Self> {
+        let address = trezor.address(&path, false)?;
+        Ok(Self {
+            trezor: Mutex::new(trezor),
+            path,
+            address,
+        })
+    }
+
+    /// Return the derivation path of the key.
+    pub fn path(&self) -> &DerivationPath {
+        &self.path
+    }
+
+    /// Show the address on the device and wait for the user to confirm it.
+    pub fn confirm_address(&self) -> Result<Address> {
+        self.lock().address(&self.path, true)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Trezor<T>> {
+        // a failed request leaves no state behind
+        self.trezor.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<T: Transport> Signer for TrezorSigner<T> {
+    fn address(&self) -> Result<Address> {
+        Ok(self.address)
+    }
+
+    fn sign_hash(&self, _hash: &[u8; 32]) -> Result<Signature> {
+        Err(TrezorError::HashUnsupported.into())
+    }
+
+    fn sign_message(&self, message: impl AsRef<[u8]>) -> Result<Signature> {
+        self.lock().sign_message(&self.path, message)
+    }
+
+    fn sign_transaction(&self, tx: &Transaction) -> Result<Signature> {
+        self.lock().sign_transaction(&self.path, tx)
+    }
+
+    fn sign_typed_data(&self, data: &TypedData) -> Result<Signature> {
+        self.lock().sign_typed_data(&self.path, data)
+    }
+}
+
+/// Split a message into packets. The first one starts with `?##`, the type
+/// and the length of the payload, the following ones with `?`.
+fn packets(ty: u16, payload: &[u8]) -> Vec<[u8; PACKET_SIZE]> {
+    let mut data = Vec::with_capacity(8 + payload.len());
+    data.extend_from_slice(b"##");
+    data.extend_from_slice(&ty.to_be_bytes());
+    data.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    data.extend_from_slice(payload);
+
+    data.chunks(PACKET_SIZE - 1)
+        .map(|chunk| {
+            let mut packet = [0; PACKET_SIZE];
+            packet[0] = b'?';
+            packet[1..=chunk.len()].copy_from_slice(chunk);
+            packet
+        })
+        .collect()
+}
+
+/// Read a message split by [`packets`].
+fn read_message(mut read: impl FnMut() -> Result<[u8; PACKET_SIZE]>) -> Result<(u16, Vec<u8>)> {
+    let packet = read()?;
+    if &packet[..3] != b"?##" {
+        return Err(TrezorError::InvalidPacket.into());
+    }
+    let ty = u16::from_be_bytes([packet[3], packet[4]]);
+    let length = u32::from_be_bytes(packet[5..9].try_into().expect("4 bytes")) as usize;
+
+    let mut payload = packet[9..].to_vec();
+    while payload.len() < length {
+        let packet = read()?;
+        if packet[0] != b'?' {
+            return Err(TrezorError::InvalidPacket.into());
+        }
+        payload.extend_from_slice(&packet[1..]);
+    }
+    payload.truncate(length);
+    Ok((ty, payload))
+}
+
+/// A protobuf message being encoded.
+#[derive(Default)]
+struct Message(Vec<u8>);
+
+impl Message {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn varint(mut self, field: u32, value: u64) -> Self {
+        put_varint(&mut self.0, (field as u64) << 3);
+        put_varint(&mut self.0, value);
+        self
+    }
+
+    fn bytes(mut self, field: u32, value: &[u8]) -> Self {
+        put_varint(&mut self.0, (field as u64) << 3 | 2);
+        put_varint(&mut self.0, value.len() as u64);
+        self.0.extend_from_slice(value);
+        self
+    }
+
+    /// Encode a derivation path as the repeated `address_n` field.
+    fn path(self, path: &DerivationPath) -> Self {
+        path.iter().fold(self, |message, child| {
+            message.varint(1, u32::from(*child) as u64)
+        })
+    }
+}
+
+fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn get_varint(buf: &mut &[u8]) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (byte, rest) = buf.split_first().ok_or(TrezorError::InvalidMessage)?;
+        *buf = rest;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(TrezorError::InvalidMessage.into())
+}
+
+/// A field of a decoded protobuf message.
+enum Field {
+    Varint(u64),
+    Bytes(Vec<u8>),
+}
+
+/// The fields of a decoded protobuf message.
+struct Fields(Vec<(u32, Field)>);
+
+impl Fields {
+    fn decode(mut buf: &[u8]) -> Result<Self> {
+        let mut fields = Vec::new();
+        while !buf.is_empty() {
+            let key = get_varint(&mut buf)?;
+            let field = match key & 7 {
+                0 => Field::Varint(get_varint(&mut buf)?),
+                2 => {
+                    let length = get_varint(&mut buf)? as usize;
+                    if length > buf.len() {
+                        return Err(TrezorError::InvalidMessage.into());
+                    }
+                    let (value, rest) = buf.split_at(length);
+                    buf = rest;
+                    Field::Bytes(value.to_vec())
+                }
+                // fixed size fields are not used by the Ethereum messages
+                1 | 5 => {
+                    let size = if key & 7 == 1 { 8 } else { 4 };
+                    buf = buf.get(size..).ok_or(TrezorError::InvalidMessage)?;
+                    continue;
+                }
+                _ => return Err(TrezorError::InvalidMessage.into()),
+            };
+            fields.push(((key >> 3) as u32, field));
+        }
+        Ok(Self(fields))
+    }
+
+    fn varint(&self, field: u32) -> Option<u64> {
+        self.0.iter().find_map(|(number, value)| match value {
+            Field::Varint(v) if *number == field => Some(*v),
+            _ => None,
+        })
+    }
+
+    fn bytes(&self, field: u32) -> Option<&[u8]> {
+        self.0.iter().find_map(|(number, value)| match value {
+            Field::Bytes(v) if *number == field => Some(v.as_slice()),
+            _ => None,
+        })
+    }
+
+    fn string(&self, field: u32) -> Result<Option<&str>> {
+        self.bytes(field)
+            .map(|bytes| std::str::from_utf8(bytes).map_err(|_| TrezorError::InvalidMessage.into()))
+            .transpose()
+    }
+
+    /// Return a 32-byte big-endian integer, left padding shorter values.
+    fn word(&self, field: u32) -> Result<[u8; 32]> {
+        let bytes = self.bytes(field).ok_or(TrezorError::InvalidMessage)?;
+        if bytes.len() > 32 {
+            return Err(TrezorError::InvalidMessage.into());
+        }
+        let mut word = [0; 32];
+        word[32 - bytes.len()..].copy_from_slice(bytes);
+        Ok(word)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bips::{bip32::ExtendedKey, bip39::Mnemonic, wordlists::Language},
+        types::B256,
+    };
+    use std::collections::VecDeque;
+
+    /// A device replaying canned responses and recording the requests.
+    #[derive(Default)]
+    struct MockDevice {
+        responses: VecDeque<[u8; PACKET_SIZE]>,
+        written: VecDeque<[u8; PACKET_SIZE]>,
+    }
+
+    impl MockDevice {
+        fn respond(&mut self, ty: u16, message: Message) {
+            self.responses.extend(packets(ty, &message.0));
+        }
+
+        fn request(&mut self) -> (u16, Fields) {
+            let (ty, payload) = read_message(|| Ok(self.written.pop_front().unwrap())).unwrap();
+            (ty, Fields::decode(&payload).unwrap())
+        }
+    }
+
+    impl Transport for MockDevice {
+        fn write(&mut self, packet: &[u8; PACKET_SIZE]) -> Result<()> {
+            self.written.push_back(*packet);
+            Ok(())
+        }
+
+        fn read(&mut self) -> Result<[u8; PACKET_SIZE]> {
+            Ok(self.responses.pop_front().unwrap())
+        }
+    }
+
+    fn key() -> ExtendedKey {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+        ExtendedKey::new_master(&mnemonic.to_seed(""))
+            .unwrap()
+            .derive_path(&path())
+            .unwrap()
+    }
+
+    fn path() -> DerivationPath {
+        DerivationPath::parse("m/44'/60'/0'/0/0").unwrap()
+    }
+
+    #[test]
+    fn test_packets() {
+        let payload: Vec<u8> = (0..=255).collect();
+        let packets = packets(ETHEREUM_SIGN_MESSAGE, &payload);
+        assert_eq!(packets.len(), 5);
+        assert_eq!(&packets[0][..9], b"?##\x00\x40\x00\x00\x01\x00");
+        assert!(packets[1..].iter().all(|packet| packet[0] == b'?'));
+
+        let mut packets = packets.into_iter();
+        let read = read_message(|| Ok(packets.next().unwrap())).unwrap();
+        assert_eq!(read, (ETHEREUM_SIGN_MESSAGE, payload));
+
+        let mut invalid = Some([0; PACKET_SIZE]);
+        assert!(read_message(|| Ok(invalid.take().unwrap())).is_err());
+    }
+
+    #[test]
+    fn test_address() {
+        let mut device = MockDevice::default();
+        device.respond(BUTTON_REQUEST, Message::new());
+        device.respond(
+            ETHEREUM_ADDRESS,
+            Message::new().bytes(2, b"0x9858EfFD232B4033E47d90003D41EC34EcaEda94"),
+        );
+
+        let pressed = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let mut trezor = Trezor::new(device);
+        let flag = pressed.clone();
+        trezor.on_button(move || flag.store(true, std::sync::atomic::Ordering::SeqCst));
+
+        let address = trezor.address(&path(), true).unwrap();
+        assert_eq!(address, key().address().unwrap());
+        assert!(pressed.load(std::sync::atomic::Ordering::SeqCst));
+
+        let mut device = trezor.transport;
+        let (ty, request) = device.request();
+        assert_eq!(ty, ETHEREUM_GET_ADDRESS);
+        let path: Vec<u64> = request
+            .0
+            .iter()
+            .filter_map(|(number, field)| match field {
+                Field::Varint(v) if *number == 1 => Some(*v),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(path, [0x8000002c, 0x8000003c, 0x80000000, 0, 0]);
+        assert_eq!(request.varint(2), Some(1));
+        assert_eq!(device.request().0, BUTTON_ACK);
+    }
+
+    #[test]
+    fn test_sign_message() {
+        let signature = key().sign_message("hello").unwrap();
+        let mut device = MockDevice::default();
+        device.respond(PIN_MATRIX_REQUEST, Message::new());
+        device.respond(
+            ETHEREUM_MESSAGE_SIGNATURE,
+            Message::new().bytes(2, &signature.to_bytes()),
+        );
+
+        let mut trezor = Trezor::new(device);
+        assert!(trezor.sign_message(&path(), "hello").is_err());
+
+        let mut device = MockDevice::default();
+        device.respond(PIN_MATRIX_REQUEST, Message::new());
+        device.respond(
+            ETHEREUM_MESSAGE_SIGNATURE,
+            Message::new().bytes(2, &signature.to_bytes()),
+        );
+        let mut trezor = Trezor::new(device);
+        trezor.on_pin(|| Ok(Zeroizing::new("1234".to_string())));
+        assert_eq!(trezor.sign_message(&path(), "hello").unwrap(), signature);
+
+        let mut device = trezor.transport;
+        assert_eq!(device.request().1.bytes(2), Some(&b"hello"[..]));
+        assert_eq!(device.request().1.bytes(1), Some(&b"1234"[..]));
+    }
+
+    #[test]
+    fn test_sign_transaction() {
+        let tx = Transaction {
+            nonce: 0,
+            gas_price: 20_000_000_000,
+            gas_limit: 21_000,
+            to: Some(Address::new([0x35; 20])),
+            value: 1_000_000_000_000_000_000,
+            data: vec![0xab; 1500],
+            chain_id: Some(1),
+        };
+
+        let mut device = MockDevice::default();
+        device.respond(ETHEREUM_TX_REQUEST, Message::new().varint(1, 476));
+        device.respond(
+            ETHEREUM_TX_REQUEST,
+            Message::new()
+                .varint(2, 37)
+                .bytes(3, &[0x11; 32])
+                .bytes(4, &[0x22; 31]),
+        );
+
+        let mut trezor = Trezor::new(device);
+        let signature = trezor.sign_transaction(&path(), &tx).unwrap();
+        assert_eq!(signature.v(), 37);
+        assert_eq!(signature.chain_id(), Some(1));
+        assert_eq!(signature.r(), &[0x11; 32]);
+        assert_eq!(signature.s()[0], 0);
+
+        let mut device = trezor.transport;
+        let (ty, request) = device.request();
+        assert_eq!(ty, ETHEREUM_SIGN_TX);
+        assert_eq!(request.bytes(2), Some(&[][..]));
+        assert_eq!(request.bytes(4), Some(&[0x52, 0x08][..]));
+        assert_eq!(request.bytes(7).unwrap().len(), DATA_CHUNK_SIZE);
+        assert_eq!(request.varint(8), Some(1500));
+        assert_eq!(request.varint(9), Some(1));
+        assert_eq!(
+            request.string(11).unwrap(),
+            Some(tx.to.unwrap().to_string().as_str())
+        );
+        let (ty, ack) = device.request();
+        assert_eq!(ty, ETHEREUM_TX_ACK);
+        assert_eq!(ack.bytes(1).unwrap().len(), 476);
+    }
+
+    #[test]
+    fn test_failure() {
+        let mut device = MockDevice::default();
+        device.respond(
+            FAILURE,
+            Message::new()
+                .varint(1, 4)
+                .bytes(2, b"Action cancelled by user"),
+        );
+
+        let mut trezor = Trezor::new(device);
+        let data = TypedData {
+            domain_separator: B256::new([0; 32]),
+            struct_hash: Some(B256::new([1; 32])),
+        };
+        let error = trezor.sign_typed_data(&path(), &data).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Trezor failure 4: Action cancelled by user"
+        );
+    }
+
+    #[test]
+    fn test_signer() {
+        let key = key();
+        let signature = key.sign_message("hello").unwrap();
+        let mut device = MockDevice::default();
+        device.respond(
+            ETHEREUM_ADDRESS,
+            Message::new().bytes(2, key.address().unwrap().to_string().as_bytes()),
+        );
+        device.respond(
+            ETHEREUM_MESSAGE_SIGNATURE,
+            Message::new().bytes(2, &signature.to_bytes()),
+        );
+
+        let signer = TrezorSigner::new(Trezor::new(device), path()).unwrap();
+        assert_eq!(signer.address().unwrap(), key.address().unwrap());
+        assert!(signer.sign_hash(&[0; 32]).is_err());
+        assert_eq!(signer.sign_message("hello").unwrap(), signature);
+        assert!(signer.sign_transaction(&Transaction::default()).is_err());
+    }
+}
@@ -0,0 +1,678 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # SLIP-39 Shamir Backups
+//!
+//! [SLIP-39](https://github.com/satoshilabs/slips/blob/master/slip-0039.md)
+//! splits a master secret into mnemonic shares organised in groups. The
+//! secret is recovered from a threshold of shares in each of a threshold of
+//! groups, so a backup can be spread among places or people without any of
+//! them holding the whole secret. The master secret is encrypted with a
+//! passphrase before it is split, and is used directly as the BIP32 seed.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::slips::slip39::{combine, Group, Share, Split};
+//!
+//! let secret = [0x42; 16];
+//! // the owner's share alone, or two of three family members
+//! let split = Split::new(1, vec![Group::new(1, 1), Group::new(2, 3)]);
+//! let groups = split.split(&secret, "TREZOR").unwrap();
+//!
+//! let phrase = groups[1][0].to_phrase();
+//! assert_eq!(phrase.split_whitespace().count(), 20);
+//!
+//! let shares = [Share::from_phrase(&phrase).unwrap(), groups[1][2].clone()];
+//! assert_eq!(*combine(&shares, "TREZOR").unwrap(), secret);
+//! ```
+
+mod shamir;
+
+use std::{collections::BTreeMap, sync::OnceLock};
+
+use hmac::Hmac;
+use rand::Rng;
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use crate::error::Result;
+
+/// Number of bits encoded by a word.
+const RADIX_BITS: usize = 10;
+/// Number of words of the header preceding the share value.
+const HEADER_WORDS: usize = 4;
+/// Number of words of the checksum.
+const CHECKSUM_WORDS: usize = 3;
+/// Shortest master secret, in bytes.
+const MIN_SECRET_LENGTH: usize = 16;
+/// Largest number of groups, and of shares in a group.
+const MAX_SHARE_COUNT: u8 = 16;
+/// PBKDF2 iterations of a Feistel round for an iteration exponent of 0.
+const BASE_ITERATIONS: u32 = 2500;
+const ROUNDS: u8 = 4;
+
+/// Error returned by SLIP-39 operations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Slip39Error {
+    /// A word of the share is not in the SLIP-39 wordlist.
+    UnknownWord(String),
+    /// The share does not have a valid number of words.
+    InvalidLength(usize),
+    /// The checksum of the share does not match.
+    InvalidChecksum,
+    /// The padding bits of the share value are not zero.
+    InvalidPadding,
+    /// The master secret is shorter than 128 bits or has an odd length.
+    InvalidSecretLength(usize),
+    /// The thresholds or share counts are out of range.
+    InvalidThreshold,
+    /// The iteration exponent does not fit in 4 bits.
+    InvalidIterationExponent(u8),
+    /// The passphrase contains characters other than printable ASCII.
+    InvalidPassphrase,
+    /// The shares do not belong to the same backup.
+    MismatchedShares,
+    /// Not enough groups or shares to recover the secret.
+    InsufficientShares,
+    /// The recovered secret does not match its digest, a share is wrong.
+    InvalidDigest,
+}
+
+impl std::fmt::Display for Slip39Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Slip39Error::UnknownWord(word) => write!(f, "Unknown SLIP-39 word: {}", word),
+            Slip39Error::InvalidLength(len) => write!(f, "Invalid SLIP-39 share length: {}", len),
+            Slip39Error::InvalidChecksum => write!(f, "Invalid SLIP-39 share checksum"),
+            Slip39Error::InvalidPadding => write!(f, "Invalid SLIP-39 share padding"),
+            Slip39Error::InvalidSecretLength(len) => {
+                write!(f, "Invalid master secret length: {} bytes", len)
+            }
+            Slip39Error::InvalidThreshold => write!(f, "Invalid SLIP-39 threshold or share count"),
+            Slip39Error::InvalidIterationExponent(e) => {
+                write!(f, "Invalid SLIP-39 iteration exponent: {}", e)
+            }
+            Slip39Error::InvalidPassphrase => {
+                write!(f, "SLIP-39 passphrases must be printable ASCII")
+            }
+            Slip39Error::MismatchedShares => write!(f, "Shares of different SLIP-39 backups"),
+            Slip39Error::InsufficientShares => write!(f, "Not enough SLIP-39 shares"),
+            Slip39Error::InvalidDigest => write!(f, "Invalid SLIP-39 digest, a share is wrong"),
+        }
+    }
+}
+
+impl std::error::Error for Slip39Error {}
+
+/// Return the SLIP-39 wordlist.
+pub fn wordlist() -> &'static [&'static str] {
+    static WORDS: OnceLock<Vec<&'static str>> = OnceLock::new();
+    WORDS.get_or_init(|| include_str!("./wordlist.txt").lines().collect())
+}
+
+/// Thresholds of a group of shares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Group {
+    /// Number of shares needed to recover the group.
+    pub threshold: u8,
+    /// Number of shares of the group.
+    pub count: u8,
+}
+
+impl Group {
+    /// Create a new group of `count` shares, `threshold` of which recover it.
+    pub fn new(threshold: u8, count: u8) -> Self {
+        Self { threshold, count }
+    }
+}
+
+/// Parameters splitting a master secret into shares.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Split {
+    group_threshold: u8,
+    groups: Vec<Group>,
+    iteration_exponent: u8,
+    extendable: bool,
+}
+
+impl Split {
+    /// Split into `groups`, `group_threshold` of which recover the secret.
+    /// The iteration exponent defaults to 1 and the backup is extendable.
+    pub fn new(group_threshold: u8, groups: Vec<Group>) -> Self {
+        Self {
+            group_threshold,
+            groups,
+            iteration_exponent: 1,
+            extendable: true,
+        }
+    }
+
+    /// Run `2500 << exponent` PBKDF2 iterations in each of the four rounds
+    /// encrypting the secret.
+    pub fn iteration_exponent(mut self, exponent: u8) -> Self {
+        self.iteration_exponent = exponent;
+        self
+    }
+
+    /// Whether shares of other backups of the same secret and passphrase
+    /// can be created later. Non-extendable backups bind the encryption to
+    /// the random identifier, as in the first version of SLIP-39.
+    pub fn extendable(mut self, extendable: bool) -> Self {
+        self.extendable = extendable;
+        self
+    }
+
+    /// Split `master_secret` encrypted with `passphrase`, returning the
+    /// shares of each group.
+    pub fn split(&self, master_secret: &[u8], passphrase: &str) -> Result<Vec<Vec<Share>>> {
+        check_secret(master_secret)?;
+        check_passphrase(passphrase)?;
+        if self.iteration_exponent > 15 {
+            return Err(Slip39Error::InvalidIterationExponent(self.iteration_exponent).into());
+        }
+        let group_count = self.groups.len();
+        if self.group_threshold == 0
+            || self.group_threshold as usize > group_count
+            || group_count > MAX_SHARE_COUNT as usize
+        {
+            return Err(Slip39Error::InvalidThreshold.into());
+        }
+        for group in &self.groups {
+            // a single share recovering the group would be copied as is
+            if group.threshold == 0
+                || group.threshold > group.count
+                || group.count > MAX_SHARE_COUNT
+                || (group.threshold == 1 && group.count > 1)
+            {
+                return Err(Slip39Error::InvalidThreshold.into());
+            }
+        }
+
+        let identifier = rand::thread_rng().gen::<u16>() & 0x7fff;
+        let encrypted = feistel(
+            master_secret,
+            passphrase,
+            self.iteration_exponent,
+            identifier,
+            self.extendable,
+            false,
+        );
+
+        let group_shares = shamir::split(self.group_threshold, group_count as u8, &encrypted);
+        Ok(self
+            .groups
+            .iter()
+            .zip(group_shares)
+            .map(|(group, (group_index, group_share))| {
+                shamir::split(group.threshold, group.count, &group_share)
+                    .into_iter()
+                    .map(|(member_index, value)| Share {
+                        identifier,
+                        extendable: self.extendable,
+                        iteration_exponent: self.iteration_exponent,
+                        group_index,
+                        group_threshold: self.group_threshold,
+                        group_count: group_count as u8,
+                        member_index,
+                        member_threshold: group.threshold,
+                        value,
+                    })
+                    .collect()
+            })
+            .collect())
+    }
+}
+
+/// A SLIP-39 share.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Share {
+    identifier: u16,
+    extendable: bool,
+    iteration_exponent: u8,
+    group_index: u8,
+    group_threshold: u8,
+    group_count: u8,
+    member_index: u8,
+    member_threshold: u8,
+    value: Zeroizing<Vec<u8>>,
+}
+
+impl Share {
+    /// Parse a share from its mnemonic.
+    pub fn from_phrase(phrase: &str) -> Result<Self> {
+        let words = Zeroizing::new(
+            phrase
+                .split_whitespace()
+                .map(|word| {
+                    let word = word.to_lowercase();
+                    wordlist()
+                        .binary_search(&word.as_str())
+                        .map(|index| index as u16)
+                        .map_err(|_| Slip39Error::UnknownWord(word))
+                })
+                .collect::<std::result::Result<Vec<_>, _>>()?,
+        );
+
+        let value_words = words
+            .len()
+            .checked_sub(HEADER_WORDS + CHECKSUM_WORDS)
+            .ok_or(Slip39Error::InvalidLength(words.len()))?;
+        let padding = value_words * RADIX_BITS % 16;
+        if padding > 8 || (value_words * RADIX_BITS - padding) / 8 < MIN_SECRET_LENGTH {
+            return Err(Slip39Error::InvalidLength(words.len()).into());
+        }
+
+        let extendable = words[1] >> 4 & 1 == 1;
+        if checksum(extendable, &words) != 1 {
+            return Err(Slip39Error::InvalidChecksum.into());
+        }
+
+        let mut bits = Bits::default();
+        for word in &words[..words.len() - CHECKSUM_WORDS] {
+            bits.push(*word as u32, RADIX_BITS);
+        }
+        let mut bits = bits.reader();
+        let identifier = bits.read(15) as u16;
+        bits.read(1);
+        let iteration_exponent = bits.read(4) as u8;
+        let group_index = bits.read(4) as u8;
+        let group_threshold = bits.read(4) as u8 + 1;
+        let group_count = bits.read(4) as u8 + 1;
+        let member_index = bits.read(4) as u8;
+        let member_threshold = bits.read(4) as u8 + 1;
+        if bits.read(padding) != 0 {
+            return Err(Slip39Error::InvalidPadding.into());
+        }
+        let value = Zeroizing::new(
+            (0..(value_words * RADIX_BITS - padding) / 8)
+                .map(|_| bits.read(8) as u8)
+                .collect(),
+        );
+
+        if group_threshold > group_count {
+            return Err(Slip39Error::InvalidThreshold.into());
+        }
+        Ok(Self {
+            identifier,
+            extendable,
+            iteration_exponent,
+            group_index,
+            group_threshold,
+            group_count,
+            member_index,
+            member_threshold,
+            value,
+        })
+    }
+
+    /// Return the mnemonic of the share.
+    pub fn to_phrase(&self) -> Zeroizing<String> {
+        let mut bits = Bits::default();
+        bits.push(self.identifier as u32, 15);
+        bits.push(self.extendable as u32, 1);
+        bits.push(self.iteration_exponent as u32, 4);
+        bits.push(self.group_index as u32, 4);
+        bits.push(self.group_threshold as u32 - 1, 4);
+        bits.push(self.group_count as u32 - 1, 4);
+        bits.push(self.member_index as u32, 4);
+        bits.push(self.member_threshold as u32 - 1, 4);
+        let value_bits = self.value.len() * 8;
+        bits.push(0, (RADIX_BITS - value_bits % RADIX_BITS) % RADIX_BITS);
+        for byte in self.value.iter() {
+            bits.push(*byte as u32, 8);
+        }
+
+        let mut reader = bits.reader();
+        let mut words: Zeroizing<Vec<u16>> = Zeroizing::new(
+            (0..bits.len() / RADIX_BITS)
+                .map(|_| reader.read(RADIX_BITS) as u16)
+                .collect(),
+        );
+        words.extend([0; CHECKSUM_WORDS]);
+        let checksum = checksum(self.extendable, &words) ^ 1;
+        let len = words.len();
+        for (i, word) in words[len - CHECKSUM_WORDS..].iter_mut().enumerate() {
+            *word = (checksum >> (RADIX_BITS * (CHECKSUM_WORDS - 1 - i)) & 0x3ff) as u16;
+        }
+
+        let words: Vec<&str> = words.iter().map(|w| wordlist()[*w as usize]).collect();
+        Zeroizing::new(words.join(" "))
+    }
+
+    /// Return the random identifier shared by the shares of a backup.
+    pub fn identifier(&self) -> u16 {
+        self.identifier
+    }
+
+    /// Return whether the backup is extendable.
+    pub fn extendable(&self) -> bool {
+        self.extendable
+    }
+
+    /// Return the iteration exponent of the encryption.
+    pub fn iteration_exponent(&self) -> u8 {
+        self.iteration_exponent
+    }
+
+    /// Return the index of the group of the share.
+    pub fn group_index(&self) -> u8 {
+        self.group_index
+    }
+
+    /// Return the number of groups needed to recover the secret.
+    pub fn group_threshold(&self) -> u8 {
+        self.group_threshold
+    }
+
+    /// Return the number of groups.
+    pub fn group_count(&self) -> u8 {
+        self.group_count
+    }
+
+    /// Return the index of the share in its group.
+    pub fn member_index(&self) -> u8 {
+        self.member_index
+    }
+
+    /// Return the number of shares needed to recover the group.
+    pub fn member_threshold(&self) -> u8 {
+        self.member_threshold
+    }
+}
+
+impl std::fmt::Debug for Share {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Share")
+            .field("identifier", &self.identifier)
+            .field("group_index", &self.group_index)
+            .field("member_index", &self.member_index)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Recover the master secret from shares of a backup and its passphrase.
+/// A wrong passphrase does not fail but recovers a different secret.
+pub fn combine(shares: &[Share], passphrase: &str) -> Result<Zeroizing<Vec<u8>>> {
+    check_passphrase(passphrase)?;
+    let first = shares.first().ok_or(Slip39Error::InsufficientShares)?;
+    let same_backup = |share: &Share| {
+        share.identifier == first.identifier
+            && share.extendable == first.extendable
+            && share.iteration_exponent == first.iteration_exponent
+            && share.group_threshold == first.group_threshold
+            && share.group_count == first.group_count
+            && share.value.len() == first.value.len()
+    };
+    if !shares.iter().all(same_backup) {
+        return Err(Slip39Error::MismatchedShares.into());
+    }
+
+    let mut groups: BTreeMap<u8, BTreeMap<u8, &Share>> = BTreeMap::new();
+    for share in shares {
+        let group = groups.entry(share.group_index).or_default();
+        match group.insert(share.member_index, share) {
+            Some(other) if other != share => return Err(Slip39Error::MismatchedShares.into()),
+            _ => {}
+        }
+    }
+
+    let mut group_shares = Vec::new();
+    for (group_index, members) in &groups {
+        let threshold = members.values().next().unwrap().member_threshold;
+        if members.values().any(|m| m.member_threshold != threshold) {
+            return Err(Slip39Error::MismatchedShares.into());
+        }
+        if members.len() < threshold as usize {
+            continue;
+        }
+        let members: Vec<(u8, &[u8])> = members
+            .iter()
+            .take(threshold as usize)
+            .map(|(index, share)| (*index, share.value.as_slice()))
+            .collect();
+        group_shares.push((*group_index, shamir::recover(threshold, &members)?));
+    }
+
+    if group_shares.len() < first.group_threshold as usize {
+        return Err(Slip39Error::InsufficientShares.into());
+    }
+    let group_shares: Vec<(u8, &[u8])> = group_shares
+        .iter()
+        .take(first.group_threshold as usize)
+        .map(|(index, value)| (*index, value.as_slice()))
+        .collect();
+    let encrypted = shamir::recover(first.group_threshold, &group_shares)?;
+
+    Ok(feistel(
+        &encrypted,
+        passphrase,
+        first.iteration_exponent,
+        first.identifier,
+        first.extendable,
+        true,
+    ))
+}
+
+fn check_secret(secret: &[u8]) -> Result<()> {
+    if secret.len() < MIN_SECRET_LENGTH || !secret.len().is_multiple_of(2) {
+        return Err(Slip39Error::InvalidSecretLength(secret.len()).into());
+    }
+    Ok(())
+}
+
+fn check_passphrase(passphrase: &str) -> Result<()> {
+    if !passphrase.bytes().all(|b| (32..=126).contains(&b)) {
+        return Err(Slip39Error::InvalidPassphrase.into());
+    }
+    Ok(())
+}
+
+/// Encrypt or decrypt a secret with the four round Feistel network of
+/// SLIP-39, whose round function is PBKDF2-HMAC-SHA256.
+fn feistel(
+    secret: &[u8],
+    passphrase: &str,
+    iteration_exponent: u8,
+    identifier: u16,
+    extendable: bool,
+    decrypt: bool,
+) -> Zeroizing<Vec<u8>> {
+    let half = secret.len() / 2;
+    let mut left = Zeroizing::new(secret[..half].to_vec());
+    let mut right = Zeroizing::new(secret[half..].to_vec());
+    let mut salt = Vec::new();
+    if !extendable {
+        salt.extend_from_slice(b"shamir");
+        salt.extend_from_slice(&identifier.to_be_bytes());
+    }
+    let prefix = salt.len();
+
+    for i in 0..ROUNDS {
+        let round = if decrypt { ROUNDS - 1 - i } else { i };
+        let mut password = Zeroizing::new(vec![round]);
+        password.extend_from_slice(passphrase.as_bytes());
+        salt.truncate(prefix);
+        salt.extend_from_slice(&right);
+
+        let mut output = Zeroizing::new(vec![0; half]);
+        pbkdf2::pbkdf2::<Hmac<Sha256>>(
+            &password,
+            &salt,
+            BASE_ITERATIONS << iteration_exponent,
+            &mut output,
+        );
+        for (out, l) in output.iter_mut().zip(left.iter()) {
+            *out ^= l;
+        }
+        left = std::mem::replace(&mut right, output);
+    }
+
+    right.extend_from_slice(&left);
+    right
+}
+
+/// Return the RS1024 checksum of `words`, which is 1 for valid shares.
+fn checksum(extendable: bool, words: &[u16]) -> u32 {
+    const GENERATOR: [u32; 10] = [
+        0xe0e040, 0x1c1c080, 0x3838100, 0x7070200, 0xe0e0009, 0x1c0c2412, 0x38086c24, 0x3090fc48,
+        0x21b1f890, 0x3f3f120,
+    ];
+    let customization: &[u8] = if extendable {
+        b"shamir_extendable"
+    } else {
+        b"shamir"
+    };
+
+    let values = customization.iter().map(|b| *b as u32);
+    values
+        .chain(words.iter().map(|w| *w as u32))
+        .fold(1, |checksum, value| {
+            let top = checksum >> 20;
+            let mut checksum = (checksum & 0xfffff) << 10 ^ value;
+            for (i, generator) in GENERATOR.iter().enumerate() {
+                if top >> i & 1 == 1 {
+                    checksum ^= generator;
+                }
+            }
+            checksum
+        })
+}
+
+/// A string of bits, most significant first.
+#[derive(Default)]
+struct Bits(Zeroizing<Vec<u8>>);
+
+impl Bits {
+    fn push(&mut self, value: u32, len: usize) {
+        self.0
+            .extend((0..len).rev().map(|i| (value >> i & 1) as u8));
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn reader(&self) -> BitReader<'_> {
+        BitReader(self.0.iter())
+    }
+}
+
+struct BitReader<'a>(std::slice::Iter<'a, u8>);
+
+impl BitReader<'_> {
+    fn read(&mut self, len: usize) -> u32 {
+        (0..len).fold(0, |value, _| value << 1 | *self.0.next().unwrap() as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn share(phrase: &str) -> Share {
+        Share::from_phrase(phrase).unwrap()
+    }
+
+    #[test]
+    fn test_wordlist() {
+        let words = wordlist();
+        assert_eq!(words.len(), 1024);
+        assert_eq!((words[0], words[1023]), ("academic", "zero"));
+        assert!(words.windows(2).all(|w| w[0][..4] < w[1][..4]));
+    }
+
+    #[test]
+    fn test_vectors() {
+        // test vectors of SLIP-39
+        let phrase = "duckling enlarge academic academic agency result length solution fridge kidney coal piece deal husband erode duke ajar critical decision keyboard";
+        let single = share(phrase);
+        assert!(!single.extendable());
+        assert_eq!(&*single.to_phrase(), phrase);
+        assert_eq!(
+            hex::encode(&*combine(&[single], "TREZOR").unwrap()),
+            "bb54aac4b89dc868ba37d9cc21b2cece"
+        );
+
+        let invalid = phrase.replace("keyboard", "kidney");
+        assert_eq!(
+            Share::from_phrase(&invalid).unwrap_err(),
+            Slip39Error::InvalidChecksum.into()
+        );
+
+        let shares = [
+            share("shadow pistol academic always adequate wildlife fancy gross oasis cylinder mustang wrist rescue view short owner flip making coding armed"),
+            share("shadow pistol academic acid actress prayer class unknown daughter sweater depict flip twice unkind craft early superior advocate guest smoking"),
+        ];
+        assert_eq!(
+            hex::encode(&*combine(&shares, "TREZOR").unwrap()),
+            "b43ceb7e57a0ea8766221624d01b0864"
+        );
+        assert_eq!(
+            combine(&shares[..1], "TREZOR").unwrap_err(),
+            Slip39Error::InsufficientShares.into()
+        );
+    }
+
+    #[test]
+    fn test_split_combine() {
+        let secret: Vec<u8> = (0..32).collect();
+        let groups = Split::new(
+            2,
+            vec![Group::new(1, 1), Group::new(2, 3), Group::new(3, 5)],
+        )
+        .iteration_exponent(0)
+        .split(&secret, "passphrase")
+        .unwrap();
+        assert_eq!(groups.iter().map(Vec::len).collect::<Vec<_>>(), [1, 3, 5]);
+        assert_eq!(groups[0][0].to_phrase().split_whitespace().count(), 33);
+
+        let phrases: Vec<_> = groups.iter().flatten().map(|s| s.to_phrase()).collect();
+        let parsed: Vec<Share> = phrases.iter().map(|p| share(p)).collect();
+        assert_eq!(parsed, groups.concat());
+
+        let recover = |shares: &[&Share]| {
+            let shares: Vec<Share> = shares.iter().map(|s| (*s).clone()).collect();
+            combine(&shares, "passphrase")
+        };
+        let (a, b, c) = (&groups[0], &groups[1], &groups[2]);
+        assert_eq!(*recover(&[&a[0], &b[2], &b[0]]).unwrap(), secret);
+        assert_eq!(
+            *recover(&[&c[4], &b[1], &c[0], &b[2], &c[2]]).unwrap(),
+            secret
+        );
+        assert!(recover(&[&a[0], &b[2], &c[0], &c[1]]).is_err());
+        assert_ne!(
+            *combine(&[a[0].clone(), b[0].clone(), b[1].clone()], "").unwrap(),
+            secret
+        );
+
+        let other = Split::new(1, vec![Group::new(1, 1)])
+            .iteration_exponent(0)
+            .split(&secret, "passphrase")
+            .unwrap();
+        assert_eq!(
+            recover(&[&a[0], &other[0][0]]).unwrap_err(),
+            Slip39Error::MismatchedShares.into()
+        );
+
+        assert!(Split::new(1, vec![Group::new(1, 2)])
+            .split(&secret, "")
+            .is_err());
+        assert!(Split::new(3, vec![Group::new(1, 1); 2])
+            .split(&secret, "")
+            .is_err());
+        assert!(Split::new(1, vec![Group::new(1, 1)])
+            .split(&secret[..15], "")
+            .is_err());
+    }
+}
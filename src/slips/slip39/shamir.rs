@@ -0,0 +1,162 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Shamir's secret sharing over GF(256) as specified by SLIP-39, with a
+//! digest share that detects wrong or mismatched shares.
+
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use super::Slip39Error;
+use crate::error::Result;
+
+/// x coordinate of the shared secret.
+const SECRET_INDEX: u8 = 255;
+/// x coordinate of the digest of the shared secret.
+const DIGEST_INDEX: u8 = 254;
+const DIGEST_LENGTH: usize = 4;
+
+/// Exponent and logarithm tables of GF(256) with the Rijndael polynomial
+/// `x^8 + x^4 + x^3 + x + 1` and generator `x + 1`.
+const TABLES: ([u8; 255], [u8; 256]) = {
+    let mut exp = [0; 255];
+    let mut log = [0; 256];
+    let mut poly: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = poly as u8;
+        log[poly as usize] = i as u8;
+        // multiply by the generator
+        poly = (poly << 1) ^ poly;
+        if poly & 0x100 != 0 {
+            poly ^= 0x11b;
+        }
+        i += 1;
+    }
+    (exp, log)
+};
+
+/// A share: its x coordinate and the y coordinates of every byte.
+pub(super) type Share = (u8, Zeroizing<Vec<u8>>);
+
+/// Split `secret` into `count` shares, any `threshold` of which recover it.
+pub(super) fn split(threshold: u8, count: u8, secret: &[u8]) -> Vec<Share> {
+    if threshold == 1 {
+        return (0..count)
+            .map(|i| (i, Zeroizing::new(secret.to_vec())))
+            .collect();
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut shares: Vec<Share> = (0..threshold - 2)
+        .map(|i| {
+            let mut value = Zeroizing::new(vec![0; secret.len()]);
+            rng.fill_bytes(&mut value);
+            (i, value)
+        })
+        .collect();
+
+    let mut digest = Zeroizing::new(vec![0; secret.len()]);
+    rng.fill_bytes(&mut digest[DIGEST_LENGTH..]);
+    let tag = self::digest(&digest[DIGEST_LENGTH..], secret);
+    digest[..DIGEST_LENGTH].copy_from_slice(&tag);
+
+    let mut base: Vec<(u8, &[u8])> = shares.iter().map(|(x, y)| (*x, y.as_slice())).collect();
+    base.push((DIGEST_INDEX, &digest));
+    base.push((SECRET_INDEX, secret));
+    let rest: Vec<Share> = (threshold - 2..count)
+        .map(|x| (x, interpolate(&base, x)))
+        .collect();
+
+    shares.extend(rest);
+    shares
+}
+
+/// Recover the secret from `threshold` shares, checking its digest.
+pub(super) fn recover(threshold: u8, shares: &[(u8, &[u8])]) -> Result<Zeroizing<Vec<u8>>> {
+    if threshold == 1 {
+        return Ok(Zeroizing::new(shares[0].1.to_vec()));
+    }
+
+    let secret = interpolate(shares, SECRET_INDEX);
+    let digest = interpolate(shares, DIGEST_INDEX);
+    if digest[..DIGEST_LENGTH] != self::digest(&digest[DIGEST_LENGTH..], &secret) {
+        return Err(Slip39Error::InvalidDigest.into());
+    }
+    Ok(secret)
+}
+
+fn digest(key: &[u8], secret: &[u8]) -> [u8; DIGEST_LENGTH] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(secret);
+    let tag = mac.finalize().into_bytes();
+    tag[..DIGEST_LENGTH].try_into().unwrap()
+}
+
+/// Evaluate at `x` the polynomial going through `shares`, whose x
+/// coordinates must be distinct.
+fn interpolate(shares: &[(u8, &[u8])], x: u8) -> Zeroizing<Vec<u8>> {
+    if let Some((_, y)) = shares.iter().find(|(share_x, _)| *share_x == x) {
+        return Zeroizing::new(y.to_vec());
+    }
+
+    let (exp, log) = &TABLES;
+    let log_product: usize = shares
+        .iter()
+        .map(|(share_x, _)| log[(share_x ^ x) as usize] as usize)
+        .sum();
+
+    let mut result = Zeroizing::new(vec![0; shares[0].1.len()]);
+    for (share_x, y) in shares {
+        // logarithm of the Lagrange basis polynomial of the share at x
+        let denominator: usize = shares
+            .iter()
+            .filter(|(other_x, _)| other_x != share_x)
+            .map(|(other_x, _)| log[(share_x ^ other_x) as usize] as usize)
+            .sum();
+        let basis =
+            (log_product + 255 * shares.len() - log[(share_x ^ x) as usize] as usize - denominator)
+                % 255;
+
+        for (out, value) in result.iter_mut().zip(y.iter()) {
+            if *value != 0 {
+                *out ^= exp[(log[*value as usize] as usize + basis) % 255];
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_recover() {
+        let secret = [0x5a; 16];
+        let shares = split(3, 5, &secret);
+        assert_eq!(shares.len(), 5);
+
+        let pick = |indices: &[usize]| -> Vec<(u8, &[u8])> {
+            indices
+                .iter()
+                .map(|i| (shares[*i].0, shares[*i].1.as_slice()))
+                .collect()
+        };
+        assert_eq!(*recover(3, &pick(&[0, 2, 4])).unwrap(), secret);
+        assert_eq!(*recover(3, &pick(&[4, 3, 1])).unwrap(), secret);
+        assert!(recover(3, &pick(&[0, 1, 1])).is_err());
+    }
+}
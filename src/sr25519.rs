@@ -0,0 +1,226 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Sr25519
+//!
+//! Schnorr keys over the Ristretto group of Curve25519, as used by
+//! Substrate chains for their accounts, implemented with
+//! [schnorrkel](https://github.com/w3f/schnorrkel).
+//!
+//! A [`Keypair`] is expanded from a 32 byte mini secret key the way
+//! Substrate does, derives children along hard or soft junctions, and
+//! signs messages in the `substrate` signing context.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::sr25519::Keypair;
+//!
+//! let keypair = Keypair::from_mini_secret(&[1; 32]);
+//! let hard = keypair.hard_derive(&[2; 32]);
+//! let soft = keypair.soft_derive(&[2; 32]);
+//! assert_ne!(hard.public_key(), soft.public_key());
+//! assert_eq!(soft.public_key(), &keypair.public_key().soft_derive(&[2; 32]));
+//!
+//! let signature = soft.sign(b"hello");
+//! assert!(soft.public_key().verify(b"hello", &signature).is_ok());
+//! ```
+
+use schnorrkel::{
+    derive::{ChainCode, Derivation},
+    ExpansionMode, MiniSecretKey,
+};
+
+use crate::error::Result;
+
+/// Signing context of Substrate.
+const CONTEXT: &[u8] = b"substrate";
+
+/// Error returned when reading sr25519 keys or verifying signatures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sr25519Error {
+    /// The bytes do not encode a Ristretto point.
+    InvalidPublicKey,
+    /// The signature does not match the message and key.
+    InvalidSignature,
+}
+
+impl std::fmt::Display for Sr25519Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Sr25519Error::InvalidPublicKey => write!(f, "Invalid sr25519 public key"),
+            Sr25519Error::InvalidSignature => write!(f, "Invalid sr25519 signature"),
+        }
+    }
+}
+
+impl std::error::Error for Sr25519Error {}
+
+/// An sr25519 signature.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Signature([u8; 64]);
+
+impl Signature {
+    /// Create a signature from its 64 bytes.
+    pub fn from_bytes(bytes: &[u8; 64]) -> Self {
+        Self(*bytes)
+    }
+
+    /// Return the 64 bytes of the signature.
+    pub fn to_bytes(&self) -> [u8; 64] {
+        self.0
+    }
+}
+
+impl std::fmt::Debug for Signature {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Signature({})", hex::encode(self.0))
+    }
+}
+
+/// An sr25519 key pair.
+#[derive(Clone)]
+pub struct Keypair {
+    keypair: schnorrkel::Keypair,
+    public: PublicKey,
+}
+
+impl Keypair {
+    /// Expand a mini secret key, in the Ed25519 compatible mode of
+    /// Substrate.
+    pub fn from_mini_secret(mini_secret: &[u8; 32]) -> Self {
+        let mini_secret = MiniSecretKey::from_bytes(mini_secret).expect("32 bytes");
+        Self::new(mini_secret.expand_to_keypair(ExpansionMode::Ed25519))
+    }
+
+    fn new(keypair: schnorrkel::Keypair) -> Self {
+        let public = PublicKey(keypair.public.to_bytes());
+        Self { keypair, public }
+    }
+
+    /// Return the public key.
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public
+    }
+
+    /// Derive the child of a hard junction, whose public key cannot be
+    /// derived from the parent public key.
+    pub fn hard_derive(&self, chain_code: &[u8; 32]) -> Self {
+        let (mini_secret, _) = self
+            .keypair
+            .hard_derive_mini_secret_key(Some(ChainCode(*chain_code)), b"");
+        Self::new(mini_secret.expand_to_keypair(ExpansionMode::Ed25519))
+    }
+
+    /// Derive the child of a soft junction, see [`PublicKey::soft_derive`].
+    pub fn soft_derive(&self, chain_code: &[u8; 32]) -> Self {
+        let (keypair, _) = self.keypair.derived_key_simple(ChainCode(*chain_code), []);
+        Self::new(keypair)
+    }
+
+    /// Sign a message in the `substrate` context. Signatures are
+    /// randomized.
+    pub fn sign(&self, message: &[u8]) -> Signature {
+        Signature(self.keypair.sign_simple(CONTEXT, message).to_bytes())
+    }
+}
+
+impl PartialEq for Keypair {
+    fn eq(&self, other: &Self) -> bool {
+        self.keypair.secret == other.keypair.secret
+    }
+}
+
+impl Eq for Keypair {}
+
+impl std::fmt::Debug for Keypair {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Keypair")
+            .field("public", &self.public)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An sr25519 public key, a compressed Ristretto point.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PublicKey([u8; 32]);
+
+impl PublicKey {
+    /// Read a key from its 32 byte encoding, checking that it is a
+    /// Ristretto point.
+    pub fn from_bytes(bytes: &[u8; 32]) -> Result<Self> {
+        schnorrkel::PublicKey::from_bytes(bytes).map_err(|_| Sr25519Error::InvalidPublicKey)?;
+        Ok(Self(*bytes))
+    }
+
+    /// Return the compressed point.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Derive the public key of the child of a soft junction, which is
+    /// also the public key of the soft derived [`Keypair`].
+    pub fn soft_derive(&self, chain_code: &[u8; 32]) -> Self {
+        let (public, _) = self.key().derived_key_simple(ChainCode(*chain_code), []);
+        PublicKey(public.to_bytes())
+    }
+
+    /// Verify the signature of a message in the `substrate` context.
+    pub fn verify(&self, message: &[u8], signature: &Signature) -> Result<()> {
+        let signature = schnorrkel::Signature::from_bytes(&signature.0)
+            .map_err(|_| Sr25519Error::InvalidSignature)?;
+        self.key()
+            .verify_simple(CONTEXT, message, &signature)
+            .map_err(|_| Sr25519Error::InvalidSignature.into())
+    }
+
+    fn key(&self) -> schnorrkel::PublicKey {
+        schnorrkel::PublicKey::from_bytes(&self.0).expect("valid public key")
+    }
+}
+
+impl std::fmt::Debug for PublicKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "PublicKey({})", hex::encode(self.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_soft_derive() {
+        let keypair = Keypair::from_mini_secret(&[7; 32]);
+        let child = keypair.soft_derive(&[9; 32]);
+        assert_eq!(
+            child.public_key(),
+            &keypair.public_key().soft_derive(&[9; 32])
+        );
+        assert_ne!(child.public_key(), keypair.public_key());
+    }
+
+    #[test]
+    fn test_sign() {
+        let keypair = Keypair::from_mini_secret(&[7; 32]);
+        let signature = keypair.sign(b"hello");
+        let public = PublicKey::from_bytes(keypair.public_key().as_bytes()).unwrap();
+        assert!(public.verify(b"hello", &signature).is_ok());
+        assert!(public.verify(b"hallo", &signature).is_err());
+        assert!(keypair
+            .hard_derive(&[9; 32])
+            .public_key()
+            .verify(b"hello", &signature)
+            .is_err());
+        assert!(PublicKey::from_bytes(&[0xff; 32]).is_err());
+    }
+}
@@ -0,0 +1,342 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Tokens
+//!
+//! ERC-20 token metadata read from [token lists](https://tokenlists.org),
+//! the JSON format published by Uniswap and most wallets, so amounts can
+//! be shown with the right decimals and symbol without hardcoding tokens.
+//!
+//! A [`TokenList`] is parsed from JSON and added to a [`TokenRegistry`],
+//! which looks tokens up by chain and address, or by symbol.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::{chains, tokens::{TokenList, TokenRegistry}};
+//!
+//! let list = TokenList::from_json(r#"{
+//!     "name": "Example",
+//!     "version": {"major": 1, "minor": 0, "patch": 0},
+//!     "tokens": [{
+//!         "chainId": 1,
+//!         "address": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+//!         "name": "USD Coin",
+//!         "symbol": "USDC",
+//!         "decimals": 6
+//!     }]
+//! }"#).unwrap();
+//!
+//! let mut registry = TokenRegistry::new();
+//! registry.add_list(&list);
+//!
+//! let usdc = &registry.by_symbol(chains::MAINNET.id, "usdc")[0];
+//! assert_eq!(usdc.format_amount(1_234_500), "1.2345 USDC");
+//! assert_eq!(registry.get(chains::MAINNET.id, &usdc.address), Some(*usdc));
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+use serde_json::Value;
+
+use crate::{
+    address::Address,
+    error::Result,
+    units::{format_units, parse_units},
+};
+
+/// Error returned when reading a token list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenError {
+    /// The JSON is not a token list, the malformed field is given.
+    InvalidFormat(&'static str),
+    /// A token of the list is malformed.
+    InvalidToken {
+        /// Index of the token in the list.
+        index: usize,
+        /// The malformed field.
+        field: &'static str,
+    },
+}
+
+impl std::fmt::Display for TokenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TokenError::InvalidFormat(field) => write!(f, "Invalid token list: {}", field),
+            TokenError::InvalidToken { index, field } => {
+                write!(f, "Invalid token {} in the list: {}", index, field)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TokenError {}
+
+/// An ERC-20 token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenInfo {
+    /// Chain the token is deployed on.
+    pub chain_id: u64,
+    /// Address of the token contract.
+    pub address: Address,
+    /// Name of the token.
+    pub name: String,
+    /// Symbol of the token.
+    pub symbol: String,
+    /// Number of decimals of an amount.
+    pub decimals: u8,
+    /// URI of the logo of the token.
+    pub logo_uri: Option<String>,
+}
+
+impl TokenInfo {
+    /// Format an amount in base units with the decimals and the symbol of
+    /// the token.
+    pub fn format_amount(&self, amount: u128) -> String {
+        format!(
+            "{} {}",
+            format_units(amount, self.decimals as u32),
+            self.symbol
+        )
+    }
+
+    /// Parse a decimal amount into base units of the token.
+    pub fn parse_amount(&self, amount: &str) -> Result<u128> {
+        parse_units(amount, self.decimals as u32)
+    }
+}
+
+/// A token list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenList {
+    /// Name of the list.
+    pub name: String,
+    /// Version of the list, as major, minor and patch.
+    pub version: (u64, u64, u64),
+    /// Tokens of the list.
+    pub tokens: Vec<TokenInfo>,
+}
+
+impl TokenList {
+    /// Parse a token list.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let root: Value =
+            serde_json::from_str(json).map_err(|_| TokenError::InvalidFormat("json"))?;
+        let name = root["name"]
+            .as_str()
+            .ok_or(TokenError::InvalidFormat("name"))?
+            .to_string();
+
+        let version = &root["version"];
+        let number = |field| {
+            version[field]
+                .as_u64()
+                .ok_or(TokenError::InvalidFormat("version"))
+        };
+        let version = (number("major")?, number("minor")?, number("patch")?);
+
+        let tokens = root["tokens"]
+            .as_array()
+            .ok_or(TokenError::InvalidFormat("tokens"))?
+            .iter()
+            .enumerate()
+            .map(|(index, token)| token_from_json(index, token))
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            name,
+            version,
+            tokens,
+        })
+    }
+
+    /// Read a token list from a file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+}
+
+fn token_from_json(index: usize, token: &Value) -> Result<TokenInfo> {
+    let invalid = |field| TokenError::InvalidToken { index, field };
+    let string = |field| {
+        token[field]
+            .as_str()
+            .map(str::to_string)
+            .ok_or(invalid(field))
+    };
+
+    Ok(TokenInfo {
+        chain_id: token["chainId"].as_u64().ok_or(invalid("chainId"))?,
+        address: string("address")?.parse().map_err(|_| invalid("address"))?,
+        name: string("name")?,
+        symbol: string("symbol")?,
+        decimals: token["decimals"]
+            .as_u64()
+            .and_then(|decimals| decimals.try_into().ok())
+            .ok_or(invalid("decimals"))?,
+        logo_uri: token["logoURI"].as_str().map(str::to_string),
+    })
+}
+
+/// Tokens of any chain, looked up by address or symbol.
+#[derive(Debug, Clone, Default)]
+pub struct TokenRegistry {
+    tokens: BTreeMap<(u64, Address), TokenInfo>,
+    symbols: HashMap<(u64, String), Vec<Address>>,
+}
+
+impl TokenRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a token, replacing the one known at the same address.
+    pub fn insert(&mut self, token: TokenInfo) {
+        let key = (token.chain_id, token.address);
+        if let Some(previous) = self.tokens.remove(&key) {
+            if let Some(addresses) = self.symbols.get_mut(&symbol_key(&previous)) {
+                addresses.retain(|address| *address != token.address);
+            }
+        }
+        self.symbols
+            .entry(symbol_key(&token))
+            .or_default()
+            .push(token.address);
+        self.tokens.insert(key, token);
+    }
+
+    /// Add the tokens of a list, those of the lists added later winning.
+    /// Returns the number of tokens added.
+    pub fn add_list(&mut self, list: &TokenList) -> usize {
+        for token in &list.tokens {
+            self.insert(token.clone());
+        }
+        list.tokens.len()
+    }
+
+    /// Return the token at `address` on a chain.
+    pub fn get(&self, chain_id: u64, address: &Address) -> Option<&TokenInfo> {
+        self.tokens.get(&(chain_id, *address))
+    }
+
+    /// Return the tokens with a symbol on a chain, ignoring case. Symbols
+    /// are not unique, anyone can deploy a token named like another.
+    pub fn by_symbol(&self, chain_id: u64, symbol: &str) -> Vec<&TokenInfo> {
+        self.symbols
+            .get(&(chain_id, symbol.to_uppercase()))
+            .into_iter()
+            .flatten()
+            .filter_map(|address| self.get(chain_id, address))
+            .collect()
+    }
+
+    /// Return the tokens of a chain, ordered by address.
+    pub fn on_chain(&self, chain_id: u64) -> impl Iterator<Item = &TokenInfo> {
+        self.tokens
+            .range((chain_id, Address::default())..)
+            .take_while(move |((chain, _), _)| *chain == chain_id)
+            .map(|(_, token)| token)
+    }
+
+    /// Return every token, ordered by chain and address.
+    pub fn iter(&self) -> impl Iterator<Item = &TokenInfo> {
+        self.tokens.values()
+    }
+
+    /// Return the number of tokens.
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Whether the registry holds no token.
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+}
+
+fn symbol_key(token: &TokenInfo) -> (u64, String) {
+    (token.chain_id, token.symbol.to_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chains;
+
+    const LIST: &str = r#"{
+        "name": "Test List",
+        "timestamp": "2024-01-01T00:00:00.000Z",
+        "version": {"major": 2, "minor": 1, "patch": 0},
+        "tokens": [
+            {"chainId": 1, "address": "0x6B175474E89094C44Da98b954EedeAC495271d0F", "name": "Dai Stablecoin", "symbol": "DAI", "decimals": 18, "logoURI": "ipfs://dai"},
+            {"chainId": 1, "address": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48", "name": "USD Coin", "symbol": "USDC", "decimals": 6},
+            {"chainId": 8453, "address": "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913", "name": "USD Coin", "symbol": "USDC", "decimals": 6, "tags": ["stablecoin"]}
+        ]
+    }"#;
+
+    #[test]
+    fn test_token_list() {
+        let list = TokenList::from_json(LIST).unwrap();
+        assert_eq!((list.name.as_str(), list.version), ("Test List", (2, 1, 0)));
+        assert_eq!(list.tokens.len(), 3);
+        assert_eq!(list.tokens[0].logo_uri.as_deref(), Some("ipfs://dai"));
+
+        let dai = &list.tokens[0];
+        assert_eq!(dai.format_amount(1_500_000_000_000_000_000), "1.5 DAI");
+        assert_eq!(dai.parse_amount("0.25").unwrap(), 250_000_000_000_000_000);
+
+        let invalid = LIST.replace("\"decimals\": 6}", "\"decimals\": 256}");
+        assert_eq!(
+            TokenList::from_json(&invalid).unwrap_err().to_string(),
+            "Invalid token 1 in the list: decimals"
+        );
+        assert!(TokenList::from_json(&LIST.replace("0x6B17", "0x6b17")).is_err());
+        assert!(TokenList::from_json("{\"name\": \"Empty\"}").is_err());
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut registry = TokenRegistry::new();
+        assert_eq!(registry.add_list(&TokenList::from_json(LIST).unwrap()), 3);
+        assert_eq!(registry.len(), 3);
+
+        let usdc = registry.by_symbol(chains::BASE.id, "usdc");
+        assert_eq!(usdc.len(), 1);
+        assert_eq!(usdc[0].chain_id, chains::BASE.id);
+        assert!(registry.by_symbol(chains::OPTIMISM.id, "USDC").is_empty());
+
+        let dai: Address = "0x6B175474E89094C44Da98b954EedeAC495271d0F"
+            .parse()
+            .unwrap();
+        assert_eq!(registry.get(chains::MAINNET.id, &dai).unwrap().decimals, 18);
+        assert!(registry.get(chains::BASE.id, &dai).is_none());
+        let symbols: Vec<&str> = registry
+            .on_chain(chains::MAINNET.id)
+            .map(|token| token.symbol.as_str())
+            .collect();
+        assert_eq!(symbols, ["DAI", "USDC"]);
+
+        // a token added again replaces the known one, symbol included
+        let renamed = TokenInfo {
+            symbol: "SAI".to_string(),
+            ..registry.get(chains::MAINNET.id, &dai).unwrap().clone()
+        };
+        registry.insert(renamed);
+        assert_eq!(registry.len(), 3);
+        assert!(registry.by_symbol(chains::MAINNET.id, "DAI").is_empty());
+        assert_eq!(registry.by_symbol(chains::MAINNET.id, "sai").len(), 1);
+    }
+}
@@ -0,0 +1,469 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::{
+    AccessListItem, Eip1559Transaction, Eip2930Transaction, Eip4844Transaction, Transaction,
+    TypedTransaction,
+};
+use crate::{address::Address, error::Result, signature::Signature, types::B256, utils::keccak256};
+
+/// Error returned when decoding a raw transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input is not hex or not a canonical RLP encoding.
+    InvalidEncoding,
+    /// The EIP-2718 type is not one of the supported transaction types.
+    UnsupportedType(u8),
+    /// A field is missing or malformed, its name is given.
+    InvalidField(&'static str),
+    /// The RLP lists are nested deeper than any transaction.
+    TooDeep,
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DecodeError::InvalidEncoding => write!(f, "Invalid transaction encoding"),
+            DecodeError::UnsupportedType(ty) => {
+                write!(f, "Unsupported transaction type: {:#04x}", ty)
+            }
+            DecodeError::InvalidField(field) => write!(f, "Invalid transaction field: {}", field),
+            DecodeError::TooDeep => write!(f, "RLP lists nested too deep"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// A signed transaction decoded from its raw encoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedTransaction {
+    /// The transaction.
+    pub transaction: TypedTransaction,
+    /// The signature. For typed transactions `v` is `27` or `28`.
+    pub signature: Signature,
+    /// The sender, recovered from the signature.
+    pub from: Address,
+    /// The transaction hash.
+    pub hash: B256,
+}
+
+impl Transaction {
+    /// Decode a raw signed transaction of any type, `0x` prefixed or not,
+    /// and recover its sender.
+    ///
+    /// Blob transactions are accepted in their canonical form and in the
+    /// network form wrapping the blobs, which are dropped.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use wallet_rust::transaction::{Transaction, TypedTransaction};
+    ///
+    /// let raw = "0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+    /// let decoded = Transaction::decode(raw).unwrap();
+    ///
+    /// assert_eq!(decoded.transaction.tx_type(), 0);
+    /// assert_eq!(decoded.transaction.chain_id(), Some(1));
+    /// assert_eq!(decoded.from.to_string(), "0x9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F");
+    /// ```
+    pub fn decode(raw: &str) -> Result<DecodedTransaction> {
+        let raw =
+            hex::decode(raw.trim_start_matches("0x")).map_err(|_| DecodeError::InvalidEncoding)?;
+        let (&first, payload) = raw.split_first().ok_or(DecodeError::InvalidEncoding)?;
+
+        let (transaction, signature) = match first {
+            0xc0..=0xff => decode_legacy(list(&rlp_decode(&raw)?, "transaction")?)?,
+            0x01 => {
+                let item = rlp_decode(payload)?;
+                let (fields, signature) = split_signature(list(&item, "transaction")?, 8)?;
+                let tx = Eip2930Transaction {
+                    chain_id: uint(&fields[0], "chainId")?,
+                    nonce: uint(&fields[1], "nonce")?,
+                    gas_price: uint(&fields[2], "gasPrice")?,
+                    gas_limit: uint(&fields[3], "gas")?,
+                    to: to(&fields[4])?,
+                    value: uint(&fields[5], "value")?,
+                    data: bytes(&fields[6], "data")?.to_vec(),
+                    access_list: access_list(&fields[7])?,
+                };
+                (tx.into(), signature)
+            }
+            0x02 => {
+                let item = rlp_decode(payload)?;
+                let (fields, signature) = split_signature(list(&item, "transaction")?, 9)?;
+                let tx = Eip1559Transaction {
+                    chain_id: uint(&fields[0], "chainId")?,
+                    nonce: uint(&fields[1], "nonce")?,
+                    max_priority_fee_per_gas: uint(&fields[2], "maxPriorityFeePerGas")?,
+                    max_fee_per_gas: uint(&fields[3], "maxFeePerGas")?,
+                    gas_limit: uint(&fields[4], "gas")?,
+                    to: to(&fields[5])?,
+                    value: uint(&fields[6], "value")?,
+                    data: bytes(&fields[7], "data")?.to_vec(),
+                    access_list: access_list(&fields[8])?,
+                };
+                (tx.into(), signature)
+            }
+            0x03 => {
+                let item = rlp_decode(payload)?;
+                let mut items = list(&item, "transaction")?;
+                // the network form is [transaction, blobs, commitments, proofs]
+                if let [Item::List(inner), _, _, _] = items {
+                    items = inner;
+                }
+                let (fields, signature) = split_signature(items, 11)?;
+                let tx = Eip4844Transaction {
+                    chain_id: uint(&fields[0], "chainId")?,
+                    nonce: uint(&fields[1], "nonce")?,
+                    max_priority_fee_per_gas: uint(&fields[2], "maxPriorityFeePerGas")?,
+                    max_fee_per_gas: uint(&fields[3], "maxFeePerGas")?,
+                    gas_limit: uint(&fields[4], "gas")?,
+                    to: to(&fields[5])?.ok_or(DecodeError::InvalidField("to"))?,
+                    value: uint(&fields[6], "value")?,
+                    data: bytes(&fields[7], "data")?.to_vec(),
+                    access_list: access_list(&fields[8])?,
+                    max_fee_per_blob_gas: uint(&fields[9], "maxFeePerBlobGas")?,
+                    blob_versioned_hashes: list(&fields[10], "blobVersionedHashes")?
+                        .iter()
+                        .map(|hash| word(hash, "blobVersionedHashes"))
+                        .collect::<Result<_>>()?,
+                };
+                (tx.into(), signature)
+            }
+            ty => return Err(DecodeError::UnsupportedType(ty).into()),
+        };
+
+        let from = transaction.recover_sender(&signature)?;
+        let hash = B256::new(keccak256(transaction.encode_signed(&signature)));
+        Ok(DecodedTransaction {
+            transaction,
+            signature,
+            from,
+            hash,
+        })
+    }
+}
+
+fn decode_legacy(items: &[Item]) -> Result<(TypedTransaction, Signature)> {
+    let [nonce, gas_price, gas_limit, to_item, value, data, v, r, s] = items else {
+        return Err(DecodeError::InvalidField("transaction").into());
+    };
+    let signature = Signature::new(word(r, "r")?, word(s, "s")?, uint(v, "v")?);
+    // pre EIP-155 signatures have a v of 27 or 28 and no chain id
+    let chain_id = match signature.v() {
+        27 | 28 => None,
+        _ => Some(signature.chain_id().ok_or(DecodeError::InvalidField("v"))?),
+    };
+
+    let tx = Transaction {
+        nonce: uint(nonce, "nonce")?,
+        gas_price: uint(gas_price, "gasPrice")?,
+        gas_limit: uint(gas_limit, "gas")?,
+        to: to(to_item)?,
+        value: uint(value, "value")?,
+        data: bytes(data, "data")?.to_vec(),
+        chain_id,
+    };
+    Ok((tx.into(), signature))
+}
+
+/// Split the `y_parity`, `r` and `s` following the `count` fields of a
+/// typed transaction.
+fn split_signature<'a, 'b>(
+    items: &'b [Item<'a>],
+    count: usize,
+) -> Result<(&'b [Item<'a>], Signature)> {
+    let [parity, r, s] = &items[count.min(items.len())..] else {
+        return Err(DecodeError::InvalidField("transaction").into());
+    };
+    let fields = &items[..count];
+    let parity: u8 = uint(parity, "yParity")?;
+    if parity > 1 {
+        return Err(DecodeError::InvalidField("yParity").into());
+    }
+    Ok((
+        fields,
+        Signature::from_recovery_id(word(r, "r")?, word(s, "s")?, parity),
+    ))
+}
+
+fn access_list(item: &Item) -> Result<Vec<AccessListItem>> {
+    list(item, "accessList")?
+        .iter()
+        .map(|entry| match list(entry, "accessList")? {
+            [address, keys] => Ok(AccessListItem {
+                address: to(address)?.ok_or(DecodeError::InvalidField("accessList"))?,
+                storage_keys: list(keys, "accessList")?
+                    .iter()
+                    .map(|key| Ok(B256::new(word(key, "accessList")?)))
+                    .collect::<Result<_>>()?,
+            }),
+            _ => Err(DecodeError::InvalidField("accessList").into()),
+        })
+        .collect()
+}
+
+fn list<'a, 'b>(item: &'b Item<'a>, field: &'static str) -> Result<&'b [Item<'a>]> {
+    match item {
+        Item::List(items) => Ok(items),
+        Item::Bytes(_) => Err(DecodeError::InvalidField(field).into()),
+    }
+}
+
+fn bytes<'a>(item: &Item<'a>, field: &'static str) -> Result<&'a [u8]> {
+    match item {
+        Item::Bytes(bytes) => Ok(bytes),
+        Item::List(_) => Err(DecodeError::InvalidField(field).into()),
+    }
+}
+
+/// Parse an integer without leading zeros.
+fn uint<T: TryFrom<u128>>(item: &Item, field: &'static str) -> Result<T> {
+    let bytes = bytes(item, field)?;
+    if bytes.len() > 16 || bytes.first() == Some(&0) {
+        return Err(DecodeError::InvalidField(field).into());
+    }
+    let value = bytes
+        .iter()
+        .fold(0u128, |value, byte| value << 8 | *byte as u128);
+    Ok(T::try_from(value).map_err(|_| DecodeError::InvalidField(field))?)
+}
+
+/// Parse a word of at most 32 bytes, left padded with zeros.
+fn word<T: From<[u8; 32]>>(item: &Item, field: &'static str) -> Result<T> {
+    let bytes = bytes(item, field)?;
+    if bytes.len() > 32 {
+        return Err(DecodeError::InvalidField(field).into());
+    }
+    let mut word = [0u8; 32];
+    word[32 - bytes.len()..].copy_from_slice(bytes);
+    Ok(word.into())
+}
+
+/// Parse a recipient, empty for a contract creation.
+fn to(item: &Item) -> Result<Option<Address>> {
+    match bytes(item, "to")? {
+        [] => Ok(None),
+        bytes => Ok(Some(Address::new(
+            bytes
+                .try_into()
+                .map_err(|_| DecodeError::InvalidField("to"))?,
+        ))),
+    }
+}
+
+/// Deepest nesting of RLP lists decoded before giving up, well past the
+/// blob network form and access lists.
+const MAX_DEPTH: usize = 16;
+
+/// A decoded RLP item, borrowing from the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Item<'a> {
+    Bytes(&'a [u8]),
+    List(Vec<Item<'a>>),
+}
+
+/// Decode the RLP item spanning all of `input`, rejecting non-canonical
+/// encodings so a transaction has a single encoding and a single hash.
+fn rlp_decode(input: &[u8]) -> Result<Item<'_>> {
+    match rlp_item(input, 0)? {
+        (item, []) => Ok(item),
+        _ => Err(DecodeError::InvalidEncoding.into()),
+    }
+}
+
+fn rlp_item(input: &[u8], depth: usize) -> std::result::Result<(Item<'_>, &[u8]), DecodeError> {
+    let (&prefix, rest) = input.split_first().ok_or(DecodeError::InvalidEncoding)?;
+    match prefix {
+        0x00..=0x7f => Ok((Item::Bytes(&input[..1]), rest)),
+        0x80..=0xbf => {
+            let (payload, rest) =
+                rlp_payload(rest, prefix - 0x80).ok_or(DecodeError::InvalidEncoding)?;
+            match payload {
+                [byte] if *byte < 0x80 => Err(DecodeError::InvalidEncoding),
+                _ => Ok((Item::Bytes(payload), rest)),
+            }
+        }
+        0xc0..=0xff => {
+            if depth >= MAX_DEPTH {
+                return Err(DecodeError::TooDeep);
+            }
+            let (mut payload, rest) =
+                rlp_payload(rest, prefix - 0xc0).ok_or(DecodeError::InvalidEncoding)?;
+            let mut items = Vec::new();
+            while !payload.is_empty() {
+                let (item, remaining) = rlp_item(payload, depth + 1)?;
+                items.push(item);
+                payload = remaining;
+            }
+            Ok((Item::List(items), rest))
+        }
+    }
+}
+
+/// Split the payload announced by a header from the bytes following it.
+/// `length` is the header minus its offset.
+fn rlp_payload(input: &[u8], length: u8) -> Option<(&[u8], &[u8])> {
+    let (length, input) = match length {
+        0..=55 => (length as usize, input),
+        _ => {
+            let size = (length - 55) as usize;
+            let bytes = input.get(..size)?;
+            if bytes[0] == 0 {
+                return None;
+            }
+            let length = bytes
+                .iter()
+                .fold(0u64, |length, byte| length << 8 | *byte as u64);
+            if length < 56 {
+                return None;
+            }
+            (usize::try_from(length).ok()?, &input[size..])
+        }
+    };
+    (length <= input.len()).then(|| input.split_at(length))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        signer::Signer,
+        transaction::{rlp_bytes, rlp_list},
+    };
+    use laron_crypto::PrivateKey;
+
+    /// Return `depth` RLP lists, each wrapping the next, the innermost empty.
+    fn nested_lists(depth: usize) -> Vec<u8> {
+        let header = |length: usize| match length {
+            0..=55 => vec![0xc0 + length as u8],
+            _ => {
+                let bytes = length.to_be_bytes();
+                let bytes = &bytes[length.leading_zeros() as usize / 8..];
+                [&[0xf7 + bytes.len() as u8][..], bytes].concat()
+            }
+        };
+        // the payload lengths from the innermost list out
+        let mut lengths = vec![0];
+        for _ in 1..depth {
+            let length = *lengths.last().unwrap();
+            lengths.push(length + header(length).len());
+        }
+        lengths.into_iter().rev().flat_map(header).collect()
+    }
+
+    #[test]
+    fn test_decode_legacy() {
+        let raw = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+        let decoded = Transaction::decode(raw).unwrap();
+        let TypedTransaction::Legacy(tx) = &decoded.transaction else {
+            panic!("not a legacy transaction");
+        };
+        assert_eq!(tx.chain_id, Some(1));
+        assert_eq!(tx.value, 1_000_000_000_000_000_000);
+        assert_eq!(decoded.signature.v(), 37);
+        assert_eq!(hex::encode(tx.encode_signed(&decoded.signature)), raw);
+
+        // without EIP-155 the signature does not commit to a chain
+        let key = PrivateKey::from_bytes(&[0x46; 32]).unwrap();
+        let tx = Transaction {
+            chain_id: None,
+            ..tx.clone()
+        };
+        let raw = tx.encode_signed(&key.sign_transaction(&tx).unwrap());
+        let decoded = Transaction::decode(&hex::encode(raw)).unwrap();
+        assert_eq!(decoded.transaction.chain_id(), None);
+        assert_eq!(decoded.from, key.address().unwrap());
+    }
+
+    #[test]
+    fn test_decode_blob_network_form() {
+        let key = PrivateKey::from_bytes(&[0x46; 32]).unwrap();
+        let tx = TypedTransaction::from(Eip4844Transaction {
+            chain_id: 1,
+            to: Address::new([0x35; 20]),
+            blob_versioned_hashes: vec![B256::new([1; 32])],
+            ..Default::default()
+        });
+        let signature = key.sign_hash(&tx.signing_hash()).unwrap();
+        let raw = tx.encode_signed(&signature);
+
+        // [transaction, blobs, commitments, proofs], with dummy blob data
+        let sidecar = rlp_list(&[
+            raw[1..].to_vec(),
+            rlp_list(&[rlp_bytes(&[0; 64])]),
+            rlp_list(&[rlp_bytes(&[0; 48])]),
+            rlp_list(&[rlp_bytes(&[0; 48])]),
+        ]);
+        let network = [&[3][..], &sidecar].concat();
+        let decoded = Transaction::decode(&hex::encode(network)).unwrap();
+        assert_eq!(decoded.transaction, tx);
+        assert_eq!(decoded.hash, B256::new(keccak256(&raw)));
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        let raw = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+        assert!(Transaction::decode(&format!("{}00", raw)).is_err());
+        assert!(Transaction::decode(&raw[..raw.len() - 2]).is_err());
+        assert!(Transaction::decode("0x").is_err());
+        assert!(Transaction::decode("zz").is_err());
+        assert_eq!(
+            Transaction::decode("05c0").unwrap_err().to_string(),
+            "Unsupported transaction type: 0x05"
+        );
+
+        // nonce 9 encoded as 0x8109 instead of 0x09
+        let padded = raw.replacen("f86c09", "f86d8109", 1);
+        assert!(Transaction::decode(&padded).is_err());
+
+        assert_eq!(
+            rlp_decode(&[0x83, b'd', b'o', b'g']).unwrap(),
+            Item::Bytes(b"dog")
+        );
+        assert!(rlp_decode(&[0x81, 0x05]).is_err());
+        assert!(rlp_decode(&[0xb8, 0x02, 0x00, 0x00]).is_err());
+    }
+
+    #[test]
+    fn test_decode_depth() {
+        assert!(rlp_decode(&nested_lists(MAX_DEPTH)).is_ok());
+        assert_eq!(
+            rlp_decode(&nested_lists(MAX_DEPTH + 1)).unwrap_err(),
+            DecodeError::TooDeep.into()
+        );
+
+        let raw = hex::encode(nested_lists(200_000));
+        assert_eq!(
+            Transaction::decode(&raw).unwrap_err(),
+            DecodeError::TooDeep.into()
+        );
+    }
+
+    #[test]
+    fn test_decode_mainnet() {
+        let raw = "0x02f86f0102843b9aca0085029e7822d68298f094d9e1459a7a482635700cbc20bbaf52d495ab9c9680841b55ba3ac080a0c199674fcb29f353693dd779c017823b954b3c69dffa3cd6b2a6ff7888798039a028ca912de909e7e6cdef9cdcaf24c54dd8c1032946dfa1d85c206b32a9064fe8";
+        let decoded = Transaction::decode(raw).unwrap();
+        assert_eq!(decoded.transaction.tx_type(), 2);
+        assert_eq!(decoded.transaction.nonce(), 2);
+        assert_eq!(decoded.transaction.data(), [0x1b, 0x55, 0xba, 0x3a]);
+        assert_eq!(
+            decoded.from.to_string(),
+            "0x001e2b7dE757bA469a57bF6b23d982458a07eFcE"
+        );
+        assert_eq!(
+            decoded.hash.to_string(),
+            "0xce4dc6d7a7549a98ee3b071b67e970879ff51b5b95d1c340bacd80fa1e1aab31"
+        );
+    }
+}
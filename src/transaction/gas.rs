@@ -0,0 +1,388 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Gas
+//!
+//! How a [`TransactionRequest`] prices the gas when its fees are left unset.
+//!
+//! The request asks a [`GasStrategy`] for the fees, through
+//! [`fill_with`](TransactionRequest::fill_with) and
+//! [`send_with`](TransactionRequest::send_with). The built-in ones:
+//!
+//! - [`NodeFees`], used by [`fill`](TransactionRequest::fill), takes the
+//!   priority fee the node suggests on top of twice the latest base fee, or
+//!   the node's gas price on chains without EIP-1559.
+//! - [`GasPriceOracle`] always gives a legacy gas price, scaled from the
+//!   one of the node.
+//! - [`FeeHistoryPercentile`] pays the priority fee that got transactions
+//!   included in recent blocks at a chosen percentile.
+//! - [`Fixed`] sets the same fees on every transaction.
+//! - [`Capped`] bounds the fees of another strategy.
+//!
+//! ## Example
+//! ```rust,no_run
+//! # fn run<P: wallet_rust::provider::Provider>(provider: P) -> wallet_rust::error::Result<()> {
+//! use wallet_rust::{address::Address, transaction::TransactionRequest, units::parse_gwei};
+//! use wallet_rust::transaction::gas::{Capped, FeeHistoryPercentile};
+//!
+//! // the 75th percentile of the last 20 blocks, never above 50 gwei
+//! let strategy = Capped::new(FeeHistoryPercentile::new(20, 75), parse_gwei("50")?);
+//! let request = TransactionRequest::new()
+//!     .from(Address::new([0x9d; 20]))
+//!     .to(Address::new([0x35; 20]));
+//! let tx = request.fill_with(&provider, &strategy)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use super::TransactionRequest;
+use crate::{error::Result, provider::Provider};
+
+/// Error returned by a [`GasStrategy`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GasError {
+    /// The fee history holds no base fee or no priority fee.
+    EmptyFeeHistory,
+}
+
+impl std::fmt::Display for GasError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            GasError::EmptyFeeHistory => write!(f, "The fee history is empty"),
+        }
+    }
+}
+
+impl std::error::Error for GasError {}
+
+/// The fees of a transaction, in wei per gas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GasFees {
+    /// A gas price, for legacy and EIP-2930 transactions.
+    Legacy {
+        /// Gas price.
+        gas_price: u128,
+    },
+    /// EIP-1559 fees.
+    Eip1559 {
+        /// Maximum fee per gas.
+        max_fee_per_gas: u128,
+        /// Maximum priority fee per gas.
+        max_priority_fee_per_gas: u128,
+    },
+}
+
+/// Decides the fees of a transaction.
+pub trait GasStrategy {
+    /// Return the fees of the request, whose fees are not all set.
+    fn fees(&self, provider: &dyn Provider, request: &TransactionRequest) -> Result<GasFees>;
+}
+
+/// Return EIP-1559 fees leaving room for the base fee to double, which
+/// takes six full blocks.
+fn with_headroom(base_fee: u128, priority_fee: u128) -> GasFees {
+    GasFees::Eip1559 {
+        max_fee_per_gas: base_fee.saturating_mul(2).saturating_add(priority_fee),
+        max_priority_fee_per_gas: priority_fee,
+    }
+}
+
+/// The fees suggested by the node: its priority fee above twice the latest
+/// base fee, or its gas price on chains without EIP-1559.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NodeFees;
+
+impl GasStrategy for NodeFees {
+    fn fees(&self, provider: &dyn Provider, _request: &TransactionRequest) -> Result<GasFees> {
+        match provider.base_fee_per_gas()? {
+            Some(base_fee) => Ok(with_headroom(
+                base_fee,
+                provider.max_priority_fee_per_gas()?,
+            )),
+            None => Ok(GasFees::Legacy {
+                gas_price: provider.gas_price()?,
+            }),
+        }
+    }
+}
+
+/// A legacy gas price, the one of the node (`eth_gasPrice`) scaled by a
+/// percentage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasPriceOracle {
+    percent: u32,
+}
+
+impl GasPriceOracle {
+    /// Take the gas price of the node as is.
+    pub fn new() -> Self {
+        GasPriceOracle { percent: 100 }
+    }
+
+    /// Scale the gas price of the node, e.g. by `110` percent to outbid
+    /// the transactions priced at it.
+    pub fn percent(mut self, percent: u32) -> Self {
+        self.percent = percent;
+        self
+    }
+}
+
+impl Default for GasPriceOracle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GasStrategy for GasPriceOracle {
+    fn fees(&self, provider: &dyn Provider, _request: &TransactionRequest) -> Result<GasFees> {
+        let gas_price = provider.gas_price()?;
+        Ok(GasFees::Legacy {
+            gas_price: gas_price.saturating_mul(self.percent as u128) / 100,
+        })
+    }
+}
+
+/// EIP-1559 fees from `eth_feeHistory`: the median over recent blocks of
+/// the priority fee paid at a percentile of each block, above twice the
+/// base fee of the next block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeHistoryPercentile {
+    blocks: u64,
+    percentile: u8,
+}
+
+impl FeeHistoryPercentile {
+    /// Look at the priority fees of the given number of latest blocks, at
+    /// a percentile up to `100`. Higher percentiles get included sooner
+    /// and cost more.
+    pub fn new(blocks: u64, percentile: u8) -> Self {
+        FeeHistoryPercentile {
+            blocks: blocks.max(1),
+            percentile: percentile.min(100),
+        }
+    }
+}
+
+impl Default for FeeHistoryPercentile {
+    /// The median of the last 10 blocks.
+    fn default() -> Self {
+        Self::new(10, 50)
+    }
+}
+
+impl GasStrategy for FeeHistoryPercentile {
+    fn fees(&self, provider: &dyn Provider, _request: &TransactionRequest) -> Result<GasFees> {
+        let history = provider.fee_history(self.blocks, &[self.percentile as f64])?;
+        let base_fee = *history
+            .base_fee_per_gas
+            .last()
+            .ok_or(GasError::EmptyFeeHistory)?;
+        let mut rewards: Vec<u128> = history
+            .reward
+            .iter()
+            .filter_map(|rewards| rewards.first().copied())
+            .collect();
+        if rewards.is_empty() {
+            return Err(GasError::EmptyFeeHistory.into());
+        }
+        rewards.sort_unstable();
+        Ok(with_headroom(base_fee, rewards[rewards.len() / 2]))
+    }
+}
+
+/// The same fees for every transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fixed(pub GasFees);
+
+impl GasStrategy for Fixed {
+    fn fees(&self, _provider: &dyn Provider, _request: &TransactionRequest) -> Result<GasFees> {
+        Ok(self.0)
+    }
+}
+
+/// The fees of another strategy, lowered to a maximum fee per gas.
+/// Transactions priced at the cap may wait for the base fee to come down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capped<S> {
+    strategy: S,
+    max_fee_per_gas: u128,
+}
+
+impl<S: GasStrategy> Capped<S> {
+    /// Cap the gas price or the maximum fee per gas of `strategy`.
+    pub fn new(strategy: S, max_fee_per_gas: u128) -> Self {
+        Capped {
+            strategy,
+            max_fee_per_gas,
+        }
+    }
+}
+
+impl<S: GasStrategy> GasStrategy for Capped<S> {
+    fn fees(&self, provider: &dyn Provider, request: &TransactionRequest) -> Result<GasFees> {
+        let cap = self.max_fee_per_gas;
+        Ok(match self.strategy.fees(provider, request)? {
+            GasFees::Legacy { gas_price } => GasFees::Legacy {
+                gas_price: gas_price.min(cap),
+            },
+            GasFees::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => GasFees::Eip1559 {
+                max_fee_per_gas: max_fee_per_gas.min(cap),
+                max_priority_fee_per_gas: max_priority_fee_per_gas.min(cap),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        address::Address,
+        provider::{mock::MockProvider, FeeHistory},
+        transaction::TypedTransaction,
+    };
+
+    const GWEI: u128 = 1_000_000_000;
+
+    /// A provider with fixed fees and the given base fee and fee history.
+    fn node(base_fee: Option<u128>, history: FeeHistory) -> MockProvider {
+        MockProvider {
+            chain_id: Some(1),
+            gas: Some(21_000),
+            gas_price: Some(20 * GWEI),
+            base_fee: Some(base_fee),
+            priority_fee: Some(GWEI),
+            fee_history: Some(history),
+            ..Default::default()
+        }
+    }
+
+    fn eip1559(max_fee_per_gas: u128, max_priority_fee_per_gas: u128) -> GasFees {
+        GasFees::Eip1559 {
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+        }
+    }
+
+    #[test]
+    fn test_node_fees() {
+        let request = TransactionRequest::new();
+        let mut provider = node(Some(10 * GWEI), FeeHistory::default());
+        assert_eq!(
+            NodeFees.fees(&provider, &request).unwrap(),
+            eip1559(21 * GWEI, GWEI)
+        );
+        provider.base_fee = Some(None);
+        assert_eq!(
+            NodeFees.fees(&provider, &request).unwrap(),
+            GasFees::Legacy {
+                gas_price: 20 * GWEI
+            }
+        );
+
+        let oracle = GasPriceOracle::new().percent(110);
+        assert_eq!(
+            oracle.fees(&provider, &request).unwrap(),
+            GasFees::Legacy {
+                gas_price: 22 * GWEI
+            }
+        );
+    }
+
+    #[test]
+    fn test_fee_history() {
+        let rewards = [3, 1, 5, 2, 4];
+        let history = FeeHistory {
+            oldest_block: 100,
+            base_fee_per_gas: vec![8 * GWEI, 9 * GWEI, 10 * GWEI, 9 * GWEI, 8 * GWEI, 7 * GWEI],
+            reward: rewards.iter().map(|reward| vec![reward * GWEI]).collect(),
+        };
+        let provider = node(None, history);
+
+        let strategy = FeeHistoryPercentile::new(5, 75);
+        let fees = strategy
+            .fees(&provider, &TransactionRequest::new())
+            .unwrap();
+        // the median of the rewards above twice the next base fee
+        assert_eq!(fees, eip1559(17 * GWEI, 3 * GWEI));
+        assert_eq!(provider.requests(), ["fee_history(5, [75.0])"]);
+
+        let empty = node(None, FeeHistory::default());
+        let error = FeeHistoryPercentile::default()
+            .fees(&empty, &TransactionRequest::new())
+            .unwrap_err();
+        assert_eq!(error.to_string(), "The fee history is empty");
+    }
+
+    #[test]
+    fn test_capped() {
+        let provider = node(None, FeeHistory::default());
+        let request = TransactionRequest::new();
+
+        let strategy = Capped::new(Fixed(eip1559(100, 30)), 50);
+        assert_eq!(strategy.fees(&provider, &request).unwrap(), eip1559(50, 30));
+        let strategy = Capped::new(Fixed(eip1559(100, 30)), 20);
+        assert_eq!(strategy.fees(&provider, &request).unwrap(), eip1559(20, 20));
+        let strategy = Capped::new(GasPriceOracle::new(), 15 * GWEI);
+        assert_eq!(
+            strategy.fees(&provider, &request).unwrap(),
+            GasFees::Legacy {
+                gas_price: 15 * GWEI
+            }
+        );
+    }
+
+    #[test]
+    fn test_fill_with() {
+        let provider = node(Some(10 * GWEI), FeeHistory::default());
+        let request = TransactionRequest::new()
+            .to(Address::new([0x35; 20]))
+            .nonce(0);
+
+        let strategies: [&dyn GasStrategy; 2] =
+            [&GasPriceOracle::new(), &Fixed(eip1559(50 * GWEI, 2 * GWEI))];
+        let legacy = request.fill_with(&provider, strategies[0]).unwrap();
+        assert_eq!((legacy.tx_type(), legacy.max_fee_per_gas()), (0, 20 * GWEI));
+
+        // the priority fee set replaces the strategy's, keeping its headroom
+        let tx = request
+            .clone()
+            .max_priority_fee_per_gas(5 * GWEI)
+            .fill_with(&provider, strategies[1])
+            .unwrap();
+        let TypedTransaction::Eip1559(tx) = tx else {
+            panic!("not an EIP-1559 transaction");
+        };
+        assert_eq!(
+            (tx.max_fee_per_gas, tx.max_priority_fee_per_gas),
+            (53 * GWEI, 5 * GWEI)
+        );
+
+        // a maximum fee set alone takes the priority fee of the node when
+        // the strategy prices in gas price
+        let tx = request
+            .max_fee_per_gas(30 * GWEI)
+            .fill_with(&provider, strategies[0])
+            .unwrap();
+        let TypedTransaction::Eip1559(tx) = tx else {
+            panic!("not an EIP-1559 transaction");
+        };
+        assert_eq!(
+            (tx.max_fee_per_gas, tx.max_priority_fee_per_gas),
+            (30 * GWEI, GWEI)
+        );
+    }
+}
@@ -0,0 +1,135 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Arbitrum
+//!
+//! Arbitrum charges the cost of posting a transaction to Ethereum as extra
+//! gas, so `eth_estimateGas` already covers it. The `NodeInterface`
+//! virtual contract splits the estimate into its L1 and L2 parts.
+
+use super::{decode_uint, encode_bytes, word, L2Error};
+use crate::{address::Address, error::Result, provider::Provider};
+
+/// Address of the `NodeInterface` virtual contract, only reachable
+/// through `eth_call`.
+pub const NODE_INTERFACE: Address = Address::new([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0xc8,
+]);
+
+/// Selector of `gasEstimateComponents(address,bool,bytes)`.
+const GAS_ESTIMATE_COMPONENTS: [u8; 4] = [0xc9, 0x4e, 0x6e, 0xeb];
+
+/// The components of the gas estimate of a transaction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GasComponents {
+    /// Total gas estimate.
+    pub gas_estimate: u64,
+    /// Part of the estimate paying for the L1 data.
+    pub l1_gas: u64,
+    /// Base fee per gas of the L2 in wei.
+    pub base_fee: u128,
+    /// Estimated L1 base fee per gas in wei.
+    pub l1_base_fee_estimate: u128,
+}
+
+impl GasComponents {
+    /// Return the part of the estimate paying for the execution on the L2.
+    pub fn l2_gas(&self) -> u64 {
+        self.gas_estimate.saturating_sub(self.l1_gas)
+    }
+}
+
+/// Return the ABI encoded calldata of
+/// `gasEstimateComponents(to, contractCreation, data)`.
+pub fn encode_call(to: Option<&Address>, data: &[u8]) -> Vec<u8> {
+    let mut address = [0u8; 32];
+    if let Some(to) = to {
+        address[12..].copy_from_slice(to.as_bytes());
+    }
+    [
+        &GAS_ESTIMATE_COMPONENTS[..],
+        &address,
+        &word(to.is_none() as u128),
+        &word(0x60),
+        &encode_bytes(data),
+    ]
+    .concat()
+}
+
+/// Return the components of the gas estimate of a transaction to `to`,
+/// `None` for a contract creation.
+pub fn gas_components<P: Provider>(
+    provider: &P,
+    to: Option<&Address>,
+    data: &[u8],
+) -> Result<GasComponents> {
+    let output = provider.call(&NODE_INTERFACE, &encode_call(to, data))?;
+    let gas = |index| -> Result<u64> {
+        Ok(decode_uint(&output, index)?
+            .try_into()
+            .map_err(|_| L2Error::InvalidResponse)?)
+    };
+    Ok(GasComponents {
+        gas_estimate: gas(0)?,
+        l1_gas: gas(1)?,
+        base_fee: decode_uint(&output, 2)?,
+        l1_base_fee_estimate: decode_uint(&output, 3)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::mock::MockProvider;
+
+    /// A node answering the NodeInterface with `output`.
+    fn node(output: Vec<u8>) -> MockProvider {
+        MockProvider::new().with_call(move |to, _| {
+            assert_eq!(*to, NODE_INTERFACE);
+            Ok(output.clone())
+        })
+    }
+
+    #[test]
+    fn test_encode_call() {
+        let to = Address::new([0x35; 20]);
+        let data = encode_call(Some(&to), &[0xa9, 0x05, 0x9c, 0xbb]);
+        assert_eq!(data.len(), 4 + 32 * 5);
+        assert_eq!(data[..4], GAS_ESTIMATE_COMPONENTS);
+        assert_eq!(data[16..36], *to.as_bytes());
+        assert_eq!(data[36..68], word(0));
+        assert_eq!(data[68..100], word(0x60));
+        assert_eq!(data[100..132], word(4));
+        assert_eq!(data[132..136], [0xa9, 0x05, 0x9c, 0xbb]);
+
+        let creation = encode_call(None, &[]);
+        assert_eq!(creation[4..36], [0; 32]);
+        assert_eq!(creation[36..68], word(1));
+    }
+
+    #[test]
+    fn test_gas_components() {
+        let output = [
+            word(700_000),
+            word(400_000),
+            word(10_000_000),
+            word(30_000_000_000),
+        ];
+        let components = gas_components(&node(output.concat()), None, &[]).unwrap();
+        assert_eq!((components.l1_gas, components.l2_gas()), (400_000, 300_000));
+        assert_eq!(components.l1_base_fee_estimate, 30_000_000_000);
+
+        assert!(gas_components(&node(output[..3].concat()), None, &[]).is_err());
+    }
+}
@@ -0,0 +1,200 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Layer 2
+//!
+//! Rollups charge for the data they post to Ethereum on top of the gas
+//! spent on their own chain. [`TransactionRequest::estimate_fee`] accounts
+//! for it according to the [`Rollup`] the chain belongs to:
+//!
+//! - OP Stack chains, such as OP Mainnet and Base, charge an L1 data fee
+//!   outside of the gas, quoted by the [`optimism`] gas price oracle.
+//! - Arbitrum folds the L1 cost into the gas limit, which [`arbitrum`]
+//!   splits into its components.
+//! - zkSync Era folds the pubdata into the gas as well, and has its own
+//!   [`zksync`] transaction type, signed as EIP-712 typed data, for
+//!   paymasters.
+//!
+//! [`TransactionRequest::estimate_fee`]: super::TransactionRequest::estimate_fee
+
+pub mod arbitrum;
+pub mod optimism;
+pub mod zksync;
+
+use crate::{chains, error::Result};
+
+/// Error returned by the rollup specific helpers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum L2Error {
+    /// A system contract returned data that cannot be decoded.
+    InvalidResponse,
+    /// The transaction cannot be expressed on the rollup, the reason is
+    /// given.
+    UnsupportedTransaction(&'static str),
+}
+
+impl std::fmt::Display for L2Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            L2Error::InvalidResponse => write!(f, "Invalid response from a rollup system contract"),
+            L2Error::UnsupportedTransaction(reason) => {
+                write!(f, "Unsupported transaction: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for L2Error {}
+
+/// The family of rollups a chain belongs to, deciding how its fees are
+/// estimated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rollup {
+    /// An OP Stack chain, charging a separate L1 data fee.
+    OpStack,
+    /// Arbitrum, charging the L1 data as gas.
+    Arbitrum,
+    /// zkSync Era, charging the pubdata as gas.
+    ZkSync,
+}
+
+impl Rollup {
+    /// Return the rollup of a known chain, `None` for Ethereum and the
+    /// chains that are not rollups.
+    pub fn of(chain_id: u64) -> Option<Self> {
+        match chain_id {
+            id if id == chains::OPTIMISM.id || id == chains::BASE.id => Some(Rollup::OpStack),
+            id if id == chains::ARBITRUM.id => Some(Rollup::Arbitrum),
+            id if id == chains::ZKSYNC.id => Some(Rollup::ZkSync),
+            _ => None,
+        }
+    }
+}
+
+/// The fees a transaction may cost, L1 data included.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeeEstimate {
+    /// Gas limit, on Arbitrum and zkSync including the gas paying for the
+    /// L1 data.
+    pub gas_limit: u64,
+    /// Maximum price per gas in wei, the gas price of a legacy transaction.
+    pub max_fee_per_gas: u128,
+    /// Part of the gas limit paying for the L1 data, on Arbitrum.
+    pub l1_gas: u64,
+    /// Fee in wei charged on top of the gas for the L1 data, on OP Stack
+    /// chains.
+    pub l1_fee: u128,
+}
+
+impl FeeEstimate {
+    /// Return the most the transaction can cost in wei, the value aside.
+    pub fn max_cost(&self) -> u128 {
+        self.gas_limit as u128 * self.max_fee_per_gas + self.l1_fee
+    }
+}
+
+/// ABI encode a `bytes` argument without its offset: the length followed
+/// by the data padded to a word.
+fn encode_bytes(data: &[u8]) -> Vec<u8> {
+    let mut out = word(data.len() as u128).to_vec();
+    out.extend_from_slice(data);
+    out.resize(32 + data.len().div_ceil(32) * 32, 0);
+    out
+}
+
+fn word(value: u128) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[16..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+/// Decode the `index`th word of returned data as an integer.
+fn decode_uint(output: &[u8], index: usize) -> Result<u128> {
+    let word = output
+        .get(index * 32..(index + 1) * 32)
+        .ok_or(L2Error::InvalidResponse)?;
+    if word[..16].iter().any(|b| *b != 0) {
+        return Err(L2Error::InvalidResponse.into());
+    }
+    Ok(u128::from_be_bytes(word[16..].try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        address::Address,
+        provider::mock::MockProvider,
+        transaction::{TransactionRequest, TypedTransaction},
+    };
+    use std::collections::HashMap;
+
+    /// A rollup node answering the system contracts with `output`.
+    fn node(chain_id: u64, output: Vec<u8>) -> MockProvider {
+        let node = MockProvider {
+            nonces: Some(HashMap::new()),
+            chain_id: Some(chain_id),
+            base_fee: Some(Some(10_000_000)),
+            priority_fee: Some(1_000_000),
+            ..Default::default()
+        };
+        node.with_call(move |_, _| Ok(output.clone()))
+    }
+
+    #[test]
+    fn test_rollup() {
+        assert_eq!(Rollup::of(chains::BASE.id), Some(Rollup::OpStack));
+        assert_eq!(Rollup::of(chains::ARBITRUM.id), Some(Rollup::Arbitrum));
+        assert_eq!(Rollup::of(chains::ZKSYNC.id), Some(Rollup::ZkSync));
+        assert_eq!(Rollup::of(chains::MAINNET.id), None);
+    }
+
+    #[test]
+    fn test_estimate_fee() {
+        let request = TransactionRequest::new()
+            .from(Address::new([0x11; 20]))
+            .to(Address::new([0x35; 20]))
+            .gas_limit(100_000);
+
+        let provider = node(chains::OPTIMISM.id, word(42_000_000_000).to_vec());
+        let estimate = request.estimate_fee(&provider).unwrap();
+        assert_eq!(estimate.max_fee_per_gas, 21_000_000);
+        assert_eq!((estimate.l1_gas, estimate.l1_fee), (0, 42_000_000_000));
+        assert_eq!(estimate.max_cost(), 100_000 * 21_000_000 + 42_000_000_000);
+
+        let components = [word(100_000), word(60_000), word(10_000_000), word(1)].concat();
+        let provider = node(chains::ARBITRUM.id, components);
+        let estimate = request.estimate_fee(&provider).unwrap();
+        assert_eq!((estimate.l1_gas, estimate.l1_fee), (60_000, 0));
+        assert_eq!(estimate.max_cost(), 100_000 * 21_000_000);
+
+        // other chains pay for their gas only
+        let provider = node(chains::MAINNET.id, Vec::new());
+        let estimate = request.estimate_fee(&provider).unwrap();
+        assert_eq!((estimate.l1_gas, estimate.l1_fee), (0, 0));
+        assert!(matches!(
+            request.fill(&provider).unwrap(),
+            TypedTransaction::Eip1559(_)
+        ));
+    }
+
+    #[test]
+    fn test_decode_uint() {
+        let output = [word(7), [0xff; 32]].concat();
+        assert_eq!(decode_uint(&output, 0).unwrap(), 7);
+        assert!(decode_uint(&output, 1).is_err());
+        assert!(decode_uint(&output, 2).is_err());
+        assert_eq!(encode_bytes(&[1, 2]).len(), 64);
+        assert_eq!(encode_bytes(&[]), word(0));
+    }
+}
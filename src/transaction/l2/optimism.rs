@@ -0,0 +1,75 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # OP Stack
+//!
+//! Transactions on OP Stack chains pay their gas like on Ethereum, plus an
+//! L1 data fee for posting them to Ethereum. The fee depends on the size
+//! of the transaction and the L1 fees, and is quoted by the
+//! `GasPriceOracle` predeploy.
+
+use super::{decode_uint, encode_bytes, word};
+use crate::{address::Address, error::Result, provider::Provider, transaction::TypedTransaction};
+
+/// Address of the `GasPriceOracle` predeploy.
+pub const GAS_PRICE_ORACLE: Address = Address::new([
+    0x42, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x0f,
+]);
+
+/// Selector of `getL1Fee(bytes)`.
+const GET_L1_FEE: [u8; 4] = [0x49, 0x94, 0x8e, 0x0e];
+
+/// Return the ABI encoded calldata of `getL1Fee(tx)`.
+pub fn encode_call(tx: &[u8]) -> Vec<u8> {
+    [&GET_L1_FEE[..], &word(0x20), &encode_bytes(tx)].concat()
+}
+
+/// Return the L1 data fee in wei of a transaction, from its unsigned
+/// encoding. The oracle accounts for the signature.
+pub fn l1_fee<P: Provider>(provider: &P, tx: &TypedTransaction) -> Result<u128> {
+    let output = provider.call(&GAS_PRICE_ORACLE, &encode_call(&tx.encode_unsigned()))?;
+    decode_uint(&output, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{provider::mock::MockProvider, transaction::Eip1559Transaction};
+
+    #[test]
+    fn test_l1_fee() {
+        let tx: TypedTransaction = Eip1559Transaction {
+            chain_id: 10,
+            nonce: 1,
+            gas_limit: 21_000,
+            to: Some(Address::new([0x35; 20])),
+            ..Default::default()
+        }
+        .into();
+
+        let oracle = MockProvider::new().with_call(|_, _| Ok(word(1_234_567).to_vec()));
+        assert_eq!(l1_fee(&oracle, &tx).unwrap(), 1_234_567);
+
+        let unsigned = tx.encode_unsigned();
+        let [(to, data)] = &oracle.received_calls()[..] else {
+            panic!("expected a single call");
+        };
+        assert_eq!(*to, GAS_PRICE_ORACLE);
+        assert_eq!(data[..4], GET_L1_FEE);
+        assert_eq!(data[4..36], word(0x20));
+        assert_eq!(data[36..68], word(unsigned.len() as u128));
+        assert_eq!(data[68..68 + unsigned.len()], unsigned);
+        assert_eq!(data.len() % 32, 4);
+    }
+}
@@ -0,0 +1,277 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # zkSync Era
+//!
+//! Besides the Ethereum transaction types, zkSync Era accepts transactions
+//! of type `0x71`, signed as EIP-712 typed data. They set the gas paid per
+//! byte of pubdata and can name a paymaster, a contract paying the fees on
+//! behalf of the sender.
+//!
+//! Contract deployments, which carry the bytecode of their factory
+//! dependencies, are not supported.
+
+use super::L2Error;
+use crate::{
+    address::Address,
+    eips::eip712::{Domain, TypedData},
+    error::Result,
+    signature::Signature,
+    transaction::{rlp_bytes, rlp_list, rlp_uint, trim_zeros, TypedTransaction},
+    types::B256,
+    utils::keccak256,
+};
+
+/// Type of the EIP-712 transactions.
+pub const EIP712_TX_TYPE: u8 = 0x71;
+
+/// Gas paid per byte of pubdata unless set otherwise.
+pub const DEFAULT_GAS_PER_PUBDATA: u64 = 50_000;
+
+/// The EIP-712 type of a transaction.
+const TRANSACTION_TYPE: &str = "Transaction(uint256 txType,uint256 from,uint256 to,uint256 gasLimit,uint256 gasPerPubdataByteLimit,uint256 maxFeePerGas,uint256 maxPriorityFeePerGas,uint256 paymaster,uint256 nonce,uint256 value,bytes data,bytes32[] factoryDeps,bytes paymasterInput)";
+
+/// A contract paying the fees of a transaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Paymaster {
+    /// Address of the paymaster.
+    pub address: Address,
+    /// Input passed to the paymaster, selecting its flow.
+    pub input: Vec<u8>,
+}
+
+/// A zkSync Era EIP-712 transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Eip712Transaction {
+    /// Chain id.
+    pub chain_id: u64,
+    /// Nonce of the sender.
+    pub nonce: u64,
+    /// Sender, part of the signed data.
+    pub from: Address,
+    /// Recipient.
+    pub to: Address,
+    /// Value in wei.
+    pub value: u128,
+    /// Call data.
+    pub data: Vec<u8>,
+    /// Gas limit, pubdata included.
+    pub gas_limit: u64,
+    /// Maximum gas paid per byte of pubdata.
+    pub gas_per_pubdata_byte_limit: u64,
+    /// Maximum fee per gas in wei.
+    pub max_fee_per_gas: u128,
+    /// Maximum priority fee per gas in wei.
+    pub max_priority_fee_per_gas: u128,
+    /// Paymaster paying the fees, the sender pays when `None`.
+    pub paymaster: Option<Paymaster>,
+}
+
+impl Eip712Transaction {
+    /// Convert a transaction sent by `from`, such as a filled
+    /// [`TransactionRequest`](crate::transaction::TransactionRequest).
+    /// A gas price is used for both fees.
+    pub fn new(tx: &TypedTransaction, from: Address) -> Result<Self> {
+        let (max_fee_per_gas, max_priority_fee_per_gas) = match tx {
+            TypedTransaction::Eip1559(tx) => (tx.max_fee_per_gas, tx.max_priority_fee_per_gas),
+            TypedTransaction::Legacy(_) | TypedTransaction::Eip2930(_) => {
+                (tx.max_fee_per_gas(), tx.max_fee_per_gas())
+            }
+            TypedTransaction::Eip4844(_) => {
+                return Err(L2Error::UnsupportedTransaction("blob transaction").into())
+            }
+        };
+        let chain_id = tx
+            .chain_id()
+            .ok_or(L2Error::UnsupportedTransaction("no chain id"))?;
+        let to = *tx
+            .to()
+            .ok_or(L2Error::UnsupportedTransaction("contract creation"))?;
+
+        Ok(Self {
+            chain_id,
+            nonce: tx.nonce(),
+            from,
+            to,
+            value: tx.value(),
+            data: tx.data().to_vec(),
+            gas_limit: tx.gas_limit(),
+            gas_per_pubdata_byte_limit: DEFAULT_GAS_PER_PUBDATA,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            paymaster: None,
+        })
+    }
+
+    /// Have `paymaster` pay the fees, called with `input`.
+    pub fn paymaster(mut self, paymaster: Address, input: impl Into<Vec<u8>>) -> Self {
+        self.paymaster = Some(Paymaster {
+            address: paymaster,
+            input: input.into(),
+        });
+        self
+    }
+
+    /// Return the typed data signed by the sender, for
+    /// [`Signer::sign_typed_data`](crate::signer::Signer::sign_typed_data).
+    pub fn typed_data(&self) -> TypedData {
+        let domain = Domain {
+            name: Some("zkSync".to_string()),
+            version: Some("2".to_string()),
+            chain_id: Some(self.chain_id),
+            ..Default::default()
+        };
+
+        let address = |address: &Address| {
+            let mut word = [0u8; 32];
+            word[12..].copy_from_slice(address.as_bytes());
+            word
+        };
+        let uint = |value: u128| {
+            let mut word = [0u8; 32];
+            word[16..].copy_from_slice(&value.to_be_bytes());
+            word
+        };
+        let (paymaster, input) = match &self.paymaster {
+            Some(paymaster) => (paymaster.address, &paymaster.input[..]),
+            None => (Address::default(), &[][..]),
+        };
+
+        let members = [
+            keccak256(TRANSACTION_TYPE),
+            uint(EIP712_TX_TYPE as u128),
+            address(&self.from),
+            address(&self.to),
+            uint(self.gas_limit as u128),
+            uint(self.gas_per_pubdata_byte_limit as u128),
+            uint(self.max_fee_per_gas),
+            uint(self.max_priority_fee_per_gas),
+            address(&paymaster),
+            uint(self.nonce as u128),
+            uint(self.value),
+            keccak256(&self.data),
+            // no factory dependencies, the hash of an empty array
+            keccak256([]),
+            keccak256(input),
+        ];
+        TypedData::new(&domain, B256::new(keccak256(members.concat())))
+    }
+
+    /// Return the hash signed by the sender.
+    pub fn signing_hash(&self) -> B256 {
+        self.typed_data().signing_hash()
+    }
+
+    /// Return the raw transaction signed with `signature`.
+    pub fn encode_signed(&self, signature: &Signature) -> Vec<u8> {
+        let paymaster = match &self.paymaster {
+            Some(paymaster) => vec![
+                rlp_bytes(paymaster.address.as_bytes()),
+                rlp_bytes(&paymaster.input),
+            ],
+            None => Vec::new(),
+        };
+        let fields = [
+            rlp_uint(self.nonce as u128),
+            rlp_uint(self.max_priority_fee_per_gas),
+            rlp_uint(self.max_fee_per_gas),
+            rlp_uint(self.gas_limit as u128),
+            rlp_bytes(self.to.as_bytes()),
+            rlp_uint(self.value),
+            rlp_bytes(&self.data),
+            rlp_uint(signature.recovery_id().unwrap_or(0) as u128),
+            rlp_bytes(trim_zeros(signature.r())),
+            rlp_bytes(trim_zeros(signature.s())),
+            rlp_uint(self.chain_id as u128),
+            rlp_bytes(self.from.as_bytes()),
+            rlp_uint(self.gas_per_pubdata_byte_limit as u128),
+            rlp_list(&[]),
+            rlp_bytes(&signature.to_bytes()),
+            rlp_list(&paymaster),
+        ];
+        [&[EIP712_TX_TYPE][..], &rlp_list(&fields)].concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{signer::Signer, transaction::Eip1559Transaction};
+    use laron_crypto::PrivateKey;
+
+    fn transaction() -> Eip712Transaction {
+        let tx: TypedTransaction = Eip1559Transaction {
+            chain_id: 324,
+            nonce: 5,
+            max_fee_per_gas: 250_000_000,
+            gas_limit: 300_000,
+            to: Some(Address::new([0x35; 20])),
+            value: 1_000_000_000_000_000_000,
+            ..Default::default()
+        }
+        .into();
+        let from = "0x9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F"
+            .parse()
+            .unwrap();
+        Eip712Transaction::new(&tx, from).unwrap()
+    }
+
+    #[test]
+    fn test_signing_hash() {
+        let tx = transaction();
+        assert_eq!(
+            tx.signing_hash().to_string(),
+            "0x9f04c2e582c7ba81fa09e051815c14ec55e91a883c8857f71a34a579fdd13629"
+        );
+
+        let tx = Eip712Transaction {
+            value: 0,
+            data: vec![0xa9, 0x05, 0x9c, 0xbb],
+            ..tx
+        }
+        .paymaster(Address::new([0x11; 20]), [0x8c, 0x5a, 0x34, 0x45]);
+        assert_eq!(
+            tx.signing_hash().to_string(),
+            "0x6ec5d568feb6fb688f4dacf44706bf33816c4635bd9a0f8dec7010b1eb16f733"
+        );
+    }
+
+    #[test]
+    fn test_encode_signed() {
+        let mut r = [0xaa; 32];
+        r[0] = 0;
+        let signature = Signature::new(r, [0xbb; 32], 28);
+        let raw = "71f8cc0580840ee6b280830493e0943535353535353535353535353535353535353535880de0b6b3a764000080019faaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa0bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb820144949d8a62f656a8d1615c1294fd71e9cfb3e4855a4f82c350c0b84100aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaabbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb1cc0";
+        assert_eq!(hex::encode(transaction().encode_signed(&signature)), raw);
+
+        let tx = transaction().paymaster(Address::new([0x11; 20]), [0x8c, 0x5a, 0x34, 0x45]);
+        let raw = hex::encode(tx.encode_signed(&signature));
+        assert!(raw.ends_with("da941111111111111111111111111111111111111111848c5a3445"));
+    }
+
+    #[test]
+    fn test_sign() {
+        let key = PrivateKey::from_bytes(&[0x46; 32]).unwrap();
+        let tx = transaction();
+        let signature = key.sign_typed_data(&tx.typed_data()).unwrap();
+        assert_eq!(signature.recover(&tx.signing_hash()).unwrap(), tx.from);
+
+        let blob = TypedTransaction::Eip4844(Default::default());
+        assert_eq!(
+            Eip712Transaction::new(&blob, tx.from)
+                .unwrap_err()
+                .to_string(),
+            "Unsupported transaction: blob transaction"
+        );
+    }
+}
@@ -0,0 +1,336 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Transactions
+//!
+//! Legacy transactions and their signing hash, with the chain id bound to
+//! the signature as defined in [EIP-155](https://eips.ethereum.org/EIPS/eip-155).
+//!
+//! The typed transactions of the EIP-2718 envelope are a
+//! [`TypedTransaction`], and [`Transaction::decode`] reads a raw signed
+//! transaction of any type back, with its sender.
+//!
+//! A [`TransactionRequest`] assembles a transaction field by field and
+//! fills in the nonce, gas limit and fees from a provider when sent, the
+//! fees priced by one of the strategies of the [`gas`] module.
+//! A stuck transaction is sped up with [`replace`] or dropped with
+//! [`cancel`], both resending its nonce with higher fees. A [`Tracker`]
+//! follows sent transactions until they are finalized, dropped or replaced.
+//! Nonces skipped by transactions broadcast out of order are found with
+//! [`nonce_gaps`] and filled with [`fill_nonce_gaps`].
+//!
+//! The [`l2`] module covers what rollups do differently: the L1 data fees
+//! of OP Stack chains and Arbitrum, and the EIP-712 transactions of zkSync.
+//!
+//! An [`UnsignedTransaction`] carries a transaction to an offline machine
+//! as a JSON file, and the [`SignedTransaction`] coming back holds the raw
+//! transaction to broadcast.
+//!
+//! ## Example
+//! ```rust
+//! use laron_crypto::PrivateKey;
+//! use wallet_rust::{address::Address, signer::Signer, transaction::Transaction};
+//!
+//! let key = PrivateKey::from_bytes(&[0x46; 32]).unwrap();
+//! let tx = Transaction {
+//!     nonce: 9,
+//!     gas_price: 20_000_000_000,
+//!     gas_limit: 21_000,
+//!     to: Some(Address::new([0x35; 20])),
+//!     value: 1_000_000_000_000_000_000,
+//!     data: Vec::new(),
+//!     chain_id: Some(1),
+//! };
+//!
+//! let signature = key.sign_transaction(&tx).unwrap();
+//! assert_eq!(signature.chain_id(), Some(1));
+//! assert_eq!(signature.recover(&tx.signing_hash()).unwrap(), key.address().unwrap());
+//! ```
+
+mod decode;
+pub mod gas;
+pub mod l2;
+mod nonce;
+mod offline;
+mod replace;
+mod request;
+mod tracker;
+mod typed;
+
+pub use decode::*;
+pub use nonce::*;
+pub use offline::*;
+pub use replace::*;
+pub use request::*;
+pub use tracker::*;
+pub use typed::*;
+
+use std::path::Path;
+
+use crate::{
+    address::Address,
+    error::Result,
+    signature::{Signature, SignatureError},
+    types::B256,
+    utils::keccak256,
+};
+
+/// Gas limit of a plain transfer.
+const TRANSFER_GAS: u64 = 21_000;
+
+/// A legacy transaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Transaction {
+    /// Nonce of the sender.
+    pub nonce: u64,
+    /// Gas price in wei.
+    pub gas_price: u128,
+    /// Gas limit.
+    pub gas_limit: u64,
+    /// Recipient, `None` for a contract creation.
+    pub to: Option<Address>,
+    /// Value in wei.
+    pub value: u128,
+    /// Call data.
+    pub data: Vec<u8>,
+    /// Chain id the signature is bound to, `None` for a transaction valid
+    /// on every chain.
+    pub chain_id: Option<u64>,
+}
+
+impl Transaction {
+    /// Return the hash signed by the sender, the Keccak-256 of
+    /// [`encode_unsigned`](Self::encode_unsigned).
+    pub fn signing_hash(&self) -> B256 {
+        B256::new(keccak256(self.encode_unsigned()))
+    }
+
+    /// Return the RLP encoded fields followed by the chain id and two zeros
+    /// if set, the payload signed by the sender.
+    pub fn encode_unsigned(&self) -> Vec<u8> {
+        let mut fields = vec![
+            rlp_uint(self.nonce as u128),
+            rlp_uint(self.gas_price),
+            rlp_uint(self.gas_limit as u128),
+            rlp_bytes(self.to.as_ref().map_or(&[][..], |to| to.as_bytes())),
+            rlp_uint(self.value),
+            rlp_bytes(&self.data),
+        ];
+        if let Some(chain_id) = self.chain_id {
+            fields.extend([rlp_uint(chain_id as u128), rlp_uint(0), rlp_uint(0)]);
+        }
+        rlp_list(&fields)
+    }
+
+    /// Recover the sender of the transaction signed with `signature`.
+    ///
+    /// An EIP-155 `v` gives the chain the transaction was signed for, which
+    /// must be the chain of the transaction when it has one. A `v` of `27`
+    /// or `28` recovers from the signing hash of the transaction as it is.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use wallet_rust::{address::Address, transaction::{Transaction, TypedTransaction}};
+    ///
+    /// let raw = "0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+    /// let expected: Address = "0x9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F".parse().unwrap();
+    ///
+    /// let decoded = Transaction::decode(raw).unwrap();
+    /// let TypedTransaction::Legacy(tx) = &decoded.transaction else { unreachable!() };
+    /// assert_eq!(tx.recover_sender(&decoded.signature).unwrap(), expected);
+    /// ```
+    pub fn recover_sender(&self, signature: &Signature) -> Result<Address> {
+        let chain_id = match (self.chain_id, signature.chain_id()) {
+            (Some(expected), Some(actual)) if expected != actual => {
+                return Err(SignatureError::ChainIdMismatch { expected, actual }.into())
+            }
+            (chain_id, None) | (_, chain_id @ Some(_)) => chain_id,
+        };
+        let tx = Transaction {
+            chain_id,
+            ..self.clone()
+        };
+        signature.recover(&tx.signing_hash())
+    }
+
+    /// Return the RLP encoding of the transaction signed with `signature`,
+    /// the raw transaction sent with `eth_sendRawTransaction`.
+    pub fn encode_signed(&self, signature: &Signature) -> Vec<u8> {
+        let fields = [
+            rlp_uint(self.nonce as u128),
+            rlp_uint(self.gas_price),
+            rlp_uint(self.gas_limit as u128),
+            rlp_bytes(self.to.as_ref().map_or(&[][..], |to| to.as_bytes())),
+            rlp_uint(self.value),
+            rlp_bytes(&self.data),
+            rlp_uint(signature.v() as u128),
+            rlp_bytes(trim_zeros(signature.r())),
+            rlp_bytes(trim_zeros(signature.s())),
+        ];
+        rlp_list(&fields)
+    }
+}
+
+/// Write a file through a temporary one, so a crash cannot leave a
+/// truncated file behind.
+fn save(path: &Path, contents: &str) -> Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    std::fs::write(&temporary, contents)?;
+    std::fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+/// Big-endian encoding of an integer without leading zeros.
+pub(crate) fn be_bytes(value: u128) -> Vec<u8> {
+    trim_zeros(&value.to_be_bytes()).to_vec()
+}
+
+fn trim_zeros(bytes: &[u8]) -> &[u8] {
+    let zeros = bytes.iter().take_while(|b| **b == 0).count();
+    &bytes[zeros..]
+}
+
+fn rlp_uint(value: u128) -> Vec<u8> {
+    rlp_bytes(&be_bytes(value))
+}
+
+fn rlp_bytes(bytes: &[u8]) -> Vec<u8> {
+    match bytes {
+        [byte] if *byte < 0x80 => vec![*byte],
+        _ => {
+            let mut out = rlp_header(0x80, bytes.len());
+            out.extend_from_slice(bytes);
+            out
+        }
+    }
+}
+
+fn rlp_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload = items.concat();
+    let mut out = rlp_header(0xc0, payload.len());
+    out.extend(payload);
+    out
+}
+
+fn rlp_header(offset: u8, length: usize) -> Vec<u8> {
+    if length < 56 {
+        return vec![offset + length as u8];
+    }
+    let length = be_bytes(length as u128);
+    let mut out = vec![offset + 55 + length.len() as u8];
+    out.extend(length);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signing_hash() {
+        // example from EIP-155
+        let mut tx = Transaction {
+            nonce: 9,
+            gas_price: 20_000_000_000,
+            gas_limit: 21_000,
+            to: Some(Address::new([0x35; 20])),
+            value: 1_000_000_000_000_000_000,
+            data: Vec::new(),
+            chain_id: Some(1),
+        };
+        assert_eq!(
+            tx.signing_hash().to_string(),
+            "0xdaf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53"
+        );
+
+        let signature = Signature::new(
+            hex::decode("28ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+            hex::decode("67cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+            37,
+        );
+        assert_eq!(
+            hex::encode(tx.encode_signed(&signature)),
+            "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+        );
+
+        tx.chain_id = None;
+        assert_ne!(
+            tx.signing_hash().to_string(),
+            "0xdaf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53"
+        );
+    }
+
+    #[test]
+    fn test_recover_sender() {
+        let mut tx = Transaction {
+            nonce: 9,
+            gas_price: 20_000_000_000,
+            gas_limit: 21_000,
+            to: Some(Address::new([0x35; 20])),
+            value: 1_000_000_000_000_000_000,
+            data: Vec::new(),
+            chain_id: Some(1),
+        };
+        let signature = Signature::new(
+            hex::decode("28ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+            hex::decode("67cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+            37,
+        );
+        let sender = "0x9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F";
+        assert_eq!(tx.recover_sender(&signature).unwrap().to_string(), sender);
+
+        // the raw signature over the EIP-155 signing hash
+        let raw = Signature::from_recovery_id(*signature.r(), *signature.s(), 0);
+        assert_eq!(tx.recover_sender(&raw).unwrap().to_string(), sender);
+
+        // the chain is taken from v
+        tx.chain_id = None;
+        assert_eq!(tx.recover_sender(&signature).unwrap().to_string(), sender);
+        assert_ne!(tx.recover_sender(&raw).unwrap().to_string(), sender);
+
+        tx.chain_id = Some(5);
+        assert_eq!(
+            tx.recover_sender(&signature).unwrap_err().to_string(),
+            "Signature for chain 1 used on chain 5"
+        );
+    }
+
+    #[test]
+    fn test_rlp() {
+        assert_eq!(rlp_uint(0), [0x80]);
+        assert_eq!(rlp_uint(0x7f), [0x7f]);
+        assert_eq!(rlp_uint(0x400), [0x82, 0x04, 0x00]);
+        assert_eq!(rlp_bytes(b"dog"), b"\x83dog");
+        assert_eq!(
+            rlp_list(&[rlp_bytes(b"cat"), rlp_bytes(b"dog")]),
+            b"\xc8\x83cat\x83dog"
+        );
+
+        let long = rlp_bytes(&[0xaa; 60]);
+        assert_eq!(&long[..2], [0xb8, 60]);
+        assert_eq!(long.len(), 62);
+    }
+}
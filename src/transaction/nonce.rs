@@ -0,0 +1,148 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::{TransactionRequest, TRANSFER_GAS};
+use crate::{address::Address, error::Result, provider::Provider, signer::Signer, types::B256};
+
+/// The nonces of an address, and the gaps holding back its queued
+/// transactions.
+///
+/// Nodes only mine a transaction once every lower nonce of its sender is
+/// used. A transaction broadcast before one with a lower nonce, or after it
+/// was dropped, waits in the queue until the gap is filled.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NonceGaps {
+    /// The address checked.
+    pub address: Address,
+    /// Next nonce at the latest block.
+    pub latest: u64,
+    /// Next nonce counting the pending transactions.
+    pub pending: u64,
+    /// Nonces of the transactions queued behind a gap.
+    pub queued: Vec<u64>,
+    /// Nonces used by no transaction, lower than a queued one.
+    pub missing: Vec<u64>,
+}
+
+impl NonceGaps {
+    /// Whether transactions are stuck behind missing nonces.
+    pub fn has_gaps(&self) -> bool {
+        !self.missing.is_empty()
+    }
+}
+
+/// Compare the latest and pending nonces of `address` with the
+/// transactions it has in the pool, listing the missing nonces.
+pub fn nonce_gaps<P: Provider>(provider: &P, address: &Address) -> Result<NonceGaps> {
+    let latest = provider.transaction_count(address)?;
+    let pending = provider.pending_transaction_count(address)?.max(latest);
+
+    let mut queued: Vec<u64> = provider
+        .pool_nonces(address)?
+        .into_iter()
+        .filter(|nonce| *nonce >= pending)
+        .collect();
+    queued.sort_unstable();
+    queued.dedup();
+
+    let missing = match queued.last() {
+        Some(last) => (pending..*last)
+            .filter(|nonce| queued.binary_search(nonce).is_err())
+            .collect(),
+        None => Vec::new(),
+    };
+
+    Ok(NonceGaps {
+        address: *address,
+        latest,
+        pending,
+        queued,
+        missing,
+    })
+}
+
+/// Send a transfer of nothing to the signer for every missing nonce of its
+/// address, releasing the queued transactions. Returns the hashes of the
+/// transfers sent, none when there is no gap.
+pub fn fill_nonce_gaps<P: Provider, S: Signer>(provider: &P, signer: &S) -> Result<Vec<B256>> {
+    let address = signer.address()?;
+    let gaps = nonce_gaps(provider, &address)?;
+    gaps.missing
+        .iter()
+        .map(|nonce| {
+            TransactionRequest::new()
+                .to(address)
+                .nonce(*nonce)
+                .gas_limit(TRANSFER_GAS)
+                .send(provider, signer)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{provider::mock::MockProvider, transaction::Transaction};
+    use laron_crypto::PrivateKey;
+
+    /// A provider whose pool holds the given nonces of the key at
+    /// [0x46; 32] and of the address [0x11; 20].
+    fn node(latest: u64, pool: &[u64]) -> MockProvider {
+        let key = PrivateKey::from_bytes(&[0x46; 32]).unwrap();
+        let addresses = [key.address().unwrap(), Address::new([0x11; 20])];
+        MockProvider {
+            nonces: Some(addresses.map(|address| (address, latest)).into()),
+            pool: Some(addresses.map(|address| (address, pool.to_vec())).into()),
+            chain_id: Some(1),
+            base_fee: Some(Some(10_000_000_000)),
+            priority_fee: Some(1_000_000_000),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_nonce_gaps() {
+        let address = Address::new([0x11; 20]);
+
+        let gaps = nonce_gaps(&node(5, &[5, 6]), &address).unwrap();
+        assert_eq!((gaps.latest, gaps.pending), (5, 7));
+        assert!(!gaps.has_gaps());
+
+        let gaps = nonce_gaps(&node(5, &[5, 8, 10, 8]), &address).unwrap();
+        assert_eq!((gaps.latest, gaps.pending), (5, 6));
+        assert_eq!(gaps.queued, [8, 10]);
+        assert_eq!(gaps.missing, [6, 7, 9]);
+        assert!(gaps.has_gaps());
+    }
+
+    #[test]
+    fn test_fill_nonce_gaps() {
+        let key = PrivateKey::from_bytes(&[0x46; 32]).unwrap();
+        let address = key.address().unwrap();
+
+        let provider = node(3, &[5]);
+        let hashes = fill_nonce_gaps(&provider, &key).unwrap();
+        let sent = provider.sent();
+        assert_eq!(hashes.len(), 2);
+        for (raw, nonce) in sent.iter().zip([3, 4]) {
+            let tx = Transaction::decode(&hex::encode(raw)).unwrap();
+            assert_eq!((tx.from, tx.transaction.to()), (address, Some(&address)));
+            assert_eq!((tx.transaction.nonce(), tx.transaction.value()), (nonce, 0));
+            assert_eq!(tx.transaction.gas_limit(), TRANSFER_GAS);
+        }
+
+        let provider = node(3, &[3, 4]);
+        assert!(fill_nonce_gaps(&provider, &key).unwrap().is_empty());
+        assert!(provider.sent().is_empty());
+    }
+}
@@ -0,0 +1,503 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::{json, Value};
+
+use super::{save, Transaction};
+use crate::{
+    address::Address, bips::DerivationPath, chains::Chain, error::Result, signature::Signature,
+    signer::Signer, types::B256, utils::keccak256,
+};
+
+/// Version of the files written by [`UnsignedTransaction::to_json`] and
+/// [`SignedTransaction::export_signed`].
+const VERSION: u64 = 1;
+
+/// Error returned when reading or signing an offline transaction file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OfflineError {
+    /// The JSON is not a transaction file, the malformed field is given.
+    InvalidFormat(&'static str),
+    /// The version of the file is not supported.
+    UnsupportedVersion(u64),
+    /// The file is an unsigned transaction where a signed one is expected,
+    /// or the other way around.
+    UnexpectedKind(String),
+    /// The key signing is not the sender the transaction was built for.
+    SignerMismatch {
+        /// The sender recorded in the file.
+        expected: Address,
+        /// The address of the signer.
+        actual: Address,
+    },
+    /// The raw transaction or the signature of a signed file does not
+    /// match its fields.
+    Inconsistent,
+}
+
+impl std::fmt::Display for OfflineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            OfflineError::InvalidFormat(field) => {
+                write!(f, "Invalid transaction file: {}", field)
+            }
+            OfflineError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported transaction file version: {}", version)
+            }
+            OfflineError::UnexpectedKind(kind) => {
+                write!(f, "Unexpected {} transaction file", kind)
+            }
+            OfflineError::SignerMismatch { expected, actual } => write!(
+                f,
+                "The transaction is to be signed by {}, not {}",
+                expected, actual
+            ),
+            OfflineError::Inconsistent => {
+                write!(f, "The signed transaction does not match its fields")
+            }
+        }
+    }
+}
+
+impl std::error::Error for OfflineError {}
+
+/// A transaction built on an online machine, to be carried to an offline
+/// one for signing.
+///
+/// The file holds the fields of the transaction with its chain id, and what
+/// the offline machine needs to pick and check the key: the expected
+/// sender and its derivation path, when known. The description is shown
+/// before signing.
+///
+/// ## Example
+/// ```rust
+/// use laron_crypto::PrivateKey;
+/// use wallet_rust::{address::Address, signer::Signer};
+/// use wallet_rust::transaction::{SignedTransaction, Transaction, UnsignedTransaction};
+///
+/// // online: fill in the nonce and fees, then write the file
+/// let key = PrivateKey::from_bytes(&[0x46; 32]).unwrap();
+/// let tx = Transaction {
+///     nonce: 9,
+///     gas_price: 20_000_000_000,
+///     gas_limit: 21_000,
+///     to: Some(Address::new([0x35; 20])),
+///     value: 1_000_000_000_000_000_000,
+///     ..Default::default()
+/// };
+/// let mut unsigned = UnsignedTransaction::new(tx, 1);
+/// unsigned.from = Some(key.address().unwrap());
+/// let file = unsigned.to_json();
+///
+/// // offline: sign and export
+/// let signed = UnsignedTransaction::from_json(&file).unwrap().sign(&key).unwrap();
+/// let file = signed.export_signed();
+///
+/// // online: check and broadcast the raw transaction
+/// let signed = SignedTransaction::from_json(&file).unwrap();
+/// assert_eq!(signed.from, key.address().unwrap());
+/// assert!(signed.raw_hex().starts_with("0xf86c"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsignedTransaction {
+    /// The transaction, with its chain id set.
+    pub transaction: Transaction,
+    /// The account expected to sign.
+    pub from: Option<Address>,
+    /// Derivation path of the account expected to sign.
+    pub path: Option<DerivationPath>,
+    /// What the transaction does, in words.
+    pub description: Option<String>,
+    /// Creation time, in seconds since the Unix epoch.
+    pub created_at: u64,
+}
+
+impl UnsignedTransaction {
+    /// Prepare `transaction` for signing on `chain`.
+    pub fn new(transaction: Transaction, chain: impl Into<Chain>) -> Self {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        Self {
+            transaction: Transaction {
+                chain_id: Some(chain.into().id),
+                ..transaction
+            },
+            from: None,
+            path: None,
+            description: None,
+            created_at,
+        }
+    }
+
+    /// Return the chain id of the transaction.
+    pub fn chain_id(&self) -> u64 {
+        self.transaction.chain_id.unwrap_or_default()
+    }
+
+    /// Parse a file written by [`UnsignedTransaction::to_json`].
+    pub fn from_json(json: &str) -> Result<Self> {
+        let root = parse_file(json, "unsigned")?;
+        let transaction = transaction_from_json(&root)?;
+        let from = optional(&root["from"], "from")?;
+        let path = optional(&root["path"], "path")?;
+        let description = match &root["description"] {
+            Value::Null => None,
+            value => Some(
+                value
+                    .as_str()
+                    .ok_or(OfflineError::InvalidFormat("description"))?
+                    .to_string(),
+            ),
+        };
+        let created_at = root["createdAt"]
+            .as_u64()
+            .ok_or(OfflineError::InvalidFormat("createdAt"))?;
+
+        Ok(Self {
+            transaction,
+            from,
+            path,
+            description,
+            created_at,
+        })
+    }
+
+    /// Serialize the transaction and its metadata.
+    pub fn to_json(&self) -> String {
+        let mut root = json!({
+            "version": VERSION,
+            "kind": "unsigned",
+            "chainId": self.chain_id(),
+            "transaction": transaction_to_json(&self.transaction),
+            "createdAt": self.created_at,
+        });
+        if let Some(from) = &self.from {
+            root["from"] = from.to_checksum().into();
+        }
+        if let Some(path) = &self.path {
+            root["path"] = path.to_string().into();
+        }
+        if let Some(description) = &self.description {
+            root["description"] = description.as_str().into();
+        }
+        root.to_string()
+    }
+
+    /// Read an unsigned transaction from a file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Write the unsigned transaction to a file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        save(path.as_ref(), &self.to_json())
+    }
+
+    /// Sign the transaction, checking that the signer is the expected
+    /// sender when one is recorded.
+    pub fn sign<S: Signer>(&self, signer: &S) -> Result<SignedTransaction> {
+        let address = signer.address()?;
+        if let Some(expected) = self.from {
+            if expected != address {
+                return Err(OfflineError::SignerMismatch {
+                    expected,
+                    actual: address,
+                }
+                .into());
+            }
+        }
+
+        Ok(SignedTransaction {
+            signature: signer.sign_transaction(&self.transaction)?,
+            transaction: self.transaction.clone(),
+            from: address,
+            description: self.description.clone(),
+        })
+    }
+}
+
+/// Sign the unsigned transaction file at `path`, the offline step of the
+/// air-gapped flow.
+pub fn sign_offline<S: Signer>(path: impl AsRef<Path>, signer: &S) -> Result<SignedTransaction> {
+    UnsignedTransaction::load(path)?.sign(signer)
+}
+
+/// A transaction signed offline, carried back to an online machine to be
+/// broadcast.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedTransaction {
+    /// The transaction, with its chain id set.
+    pub transaction: Transaction,
+    /// The EIP-155 signature of the transaction.
+    pub signature: Signature,
+    /// The signer.
+    pub from: Address,
+    /// What the transaction does, in words.
+    pub description: Option<String>,
+}
+
+impl SignedTransaction {
+    /// Return the raw transaction.
+    pub fn raw(&self) -> Vec<u8> {
+        self.transaction.encode_signed(&self.signature)
+    }
+
+    /// Return the raw transaction as the `0x` prefixed hex parameter of
+    /// `eth_sendRawTransaction`.
+    pub fn raw_hex(&self) -> String {
+        format!("0x{}", hex::encode(self.raw()))
+    }
+
+    /// Return the hash the transaction will have once broadcast.
+    pub fn hash(&self) -> B256 {
+        B256::new(keccak256(self.raw()))
+    }
+
+    /// Serialize the signed transaction, its fields next to the raw
+    /// transaction so they can be reviewed before broadcasting.
+    pub fn export_signed(&self) -> String {
+        let mut root = json!({
+            "version": VERSION,
+            "kind": "signed",
+            "chainId": self.transaction.chain_id,
+            "from": self.from.to_checksum(),
+            "transaction": transaction_to_json(&self.transaction),
+            "signature": {
+                "r": format!("0x{}", hex::encode(self.signature.r())),
+                "s": format!("0x{}", hex::encode(self.signature.s())),
+                "v": format!("{:#x}", self.signature.v()),
+            },
+            "hash": self.hash().to_string(),
+            "raw": self.raw_hex(),
+        });
+        if let Some(description) = &self.description {
+            root["description"] = description.as_str().into();
+        }
+        root.to_string()
+    }
+
+    /// Parse a file written by [`SignedTransaction::export_signed`]. The
+    /// raw transaction must match the fields and be signed by `from`.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let root = parse_file(json, "signed")?;
+        let transaction = transaction_from_json(&root)?;
+        let from = optional(&root["from"], "from")?.ok_or(OfflineError::InvalidFormat("from"))?;
+        let raw = root["raw"]
+            .as_str()
+            .and_then(|raw| hex::decode(raw.trim_start_matches("0x")).ok())
+            .ok_or(OfflineError::InvalidFormat("raw"))?;
+        let description = root["description"].as_str().map(str::to_string);
+
+        let signature = signature_from_json(&root["signature"])?;
+        if signature.chain_id() != transaction.chain_id
+            || transaction.encode_signed(&signature) != raw
+            || transaction.recover_sender(&signature)? != from
+        {
+            return Err(OfflineError::Inconsistent.into());
+        }
+
+        Ok(Self {
+            transaction,
+            signature,
+            from,
+            description,
+        })
+    }
+
+    /// Read a signed transaction from a file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Write the signed transaction to a file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        save(path.as_ref(), &self.export_signed())
+    }
+}
+
+/// Parse the JSON of a file, checking its version and kind.
+fn parse_file(json: &str, kind: &str) -> Result<Value> {
+    let root: Value = serde_json::from_str(json)?;
+    let version = root["version"]
+        .as_u64()
+        .ok_or(OfflineError::InvalidFormat("version"))?;
+    if version != VERSION {
+        return Err(OfflineError::UnsupportedVersion(version).into());
+    }
+    match root["kind"].as_str() {
+        Some(found) if found == kind => Ok(root),
+        Some(found) => Err(OfflineError::UnexpectedKind(found.to_string()).into()),
+        None => Err(OfflineError::InvalidFormat("kind").into()),
+    }
+}
+
+/// The fields of a transaction, with the integers as hex quantities like
+/// in JSON-RPC.
+fn transaction_to_json(tx: &Transaction) -> Value {
+    json!({
+        "nonce": format!("{:#x}", tx.nonce),
+        "gasPrice": format!("{:#x}", tx.gas_price),
+        "gas": format!("{:#x}", tx.gas_limit),
+        "to": tx.to.map(|to| to.to_checksum()),
+        "value": format!("{:#x}", tx.value),
+        "data": format!("0x{}", hex::encode(&tx.data)),
+    })
+}
+
+fn transaction_from_json(root: &Value) -> Result<Transaction> {
+    let chain_id = root["chainId"]
+        .as_u64()
+        .ok_or(OfflineError::InvalidFormat("chainId"))?;
+    let tx = &root["transaction"];
+    let data = tx["data"]
+        .as_str()
+        .and_then(|data| hex::decode(data.trim_start_matches("0x")).ok())
+        .ok_or(OfflineError::InvalidFormat("data"))?;
+
+    Ok(Transaction {
+        nonce: quantity(&tx["nonce"], "nonce")?
+            .try_into()
+            .map_err(|_| OfflineError::InvalidFormat("nonce"))?,
+        gas_price: quantity(&tx["gasPrice"], "gasPrice")?,
+        gas_limit: quantity(&tx["gas"], "gas")?
+            .try_into()
+            .map_err(|_| OfflineError::InvalidFormat("gas"))?,
+        to: optional(&tx["to"], "to")?,
+        value: quantity(&tx["value"], "value")?,
+        data,
+        chain_id: Some(chain_id),
+    })
+}
+
+/// Parse a `0x` prefixed hex quantity.
+fn quantity(value: &Value, field: &'static str) -> Result<u128> {
+    Ok(value
+        .as_str()
+        .and_then(|value| value.strip_prefix("0x"))
+        .and_then(|digits| u128::from_str_radix(digits, 16).ok())
+        .ok_or(OfflineError::InvalidFormat(field))?)
+}
+
+/// Parse an optional string field.
+fn optional<T: std::str::FromStr>(value: &Value, field: &'static str) -> Result<Option<T>> {
+    match value {
+        Value::Null => Ok(None),
+        value => Ok(Some(
+            value
+                .as_str()
+                .and_then(|value| value.parse().ok())
+                .ok_or(OfflineError::InvalidFormat(field))?,
+        )),
+    }
+}
+
+fn signature_from_json(value: &Value) -> Result<Signature> {
+    let word = |field: &'static str| -> Result<[u8; 32]> {
+        Ok(value[field]
+            .as_str()
+            .and_then(|word| hex::decode(word.trim_start_matches("0x")).ok())
+            .and_then(|word| word.try_into().ok())
+            .ok_or(OfflineError::InvalidFormat("signature"))?)
+    };
+    let v = quantity(&value["v"], "signature")?
+        .try_into()
+        .map_err(|_| OfflineError::InvalidFormat("signature"))?;
+    Ok(Signature::new(word("r")?, word("s")?, v))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use laron_crypto::PrivateKey;
+
+    // example from EIP-155
+    const RAW: &str = "0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+
+    fn unsigned() -> UnsignedTransaction {
+        let tx = Transaction {
+            nonce: 9,
+            gas_price: 20_000_000_000,
+            gas_limit: 21_000,
+            to: Some(Address::new([0x35; 20])),
+            value: 1_000_000_000_000_000_000,
+            data: Vec::new(),
+            chain_id: None,
+        };
+        UnsignedTransaction::new(tx, 1)
+    }
+
+    #[test]
+    fn test_unsigned() {
+        let mut unsigned = unsigned();
+        assert_eq!(
+            UnsignedTransaction::from_json(&unsigned.to_json()).unwrap(),
+            unsigned
+        );
+
+        unsigned.from = Some(Address::new([0x9d; 20]));
+        unsigned.path = Some(DerivationPath::parse("m/44'/60'/0'/0/3").unwrap());
+        unsigned.description = Some("Rent".to_string());
+        unsigned.transaction.to = None;
+        unsigned.transaction.data = vec![0x60, 0x80];
+        let json = unsigned.to_json();
+        assert_eq!(UnsignedTransaction::from_json(&json).unwrap(), unsigned);
+
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["chainId"], 1);
+        assert_eq!(value["transaction"]["gas"], "0x5208");
+        assert_eq!(value["path"], "m/44'/60'/0'/0/3");
+
+        let json = json.replace("\"version\":1", "\"version\":2");
+        assert!(UnsignedTransaction::from_json(&json).is_err());
+    }
+
+    #[test]
+    fn test_sign() {
+        let key = PrivateKey::from_bytes(&[0x46; 32]).unwrap();
+        let mut unsigned = unsigned();
+        let signed = unsigned.sign(&key).unwrap();
+        assert_eq!(signed.raw_hex(), RAW);
+        assert_eq!(signed.from, key.address().unwrap());
+        assert_eq!(signed.hash(), B256::new(keccak256(signed.raw())));
+
+        unsigned.from = Some(Address::new([0x9d; 20]));
+        assert!(unsigned.sign(&key).is_err());
+    }
+
+    #[test]
+    fn test_signed() {
+        let key = PrivateKey::from_bytes(&[0x46; 32]).unwrap();
+        let path = std::env::temp_dir().join(format!("unsigned-{}.json", std::process::id()));
+        unsigned().save(&path).unwrap();
+        let signed = sign_offline(&path, &key).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let json = signed.export_signed();
+        assert_eq!(SignedTransaction::from_json(&json).unwrap(), signed);
+        // an unsigned file is not accepted in place of a signed one
+        assert!(SignedTransaction::from_json(&unsigned().to_json()).is_err());
+
+        // the fields shown for review must be the ones signed
+        let tampered = json.replace("\"value\":\"0xde0b6b3a7640000\"", "\"value\":\"0x1\"");
+        assert_ne!(tampered, json);
+        assert!(SignedTransaction::from_json(&tampered).is_err());
+        let tampered = json.replace("\"chainId\":1", "\"chainId\":5");
+        assert!(SignedTransaction::from_json(&tampered).is_err());
+    }
+}
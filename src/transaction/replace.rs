@@ -0,0 +1,366 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::{DecodedTransaction, Eip1559Transaction, Transaction, TypedTransaction, TRANSFER_GAS};
+use crate::{address::Address, error::Result, provider::Provider, signer::Signer, types::B256};
+
+/// The smallest fee increase, in percent, nodes accept to replace a pending
+/// transaction.
+pub const MIN_FEE_BUMP_PERCENT: f64 = 10.0;
+
+/// Error returned when replacing a pending transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplaceError {
+    /// The node does not know the transaction.
+    UnknownTransaction(B256),
+    /// The transaction was sent by another account than the signer's.
+    SignerMismatch {
+        /// The sender of the transaction.
+        expected: Address,
+        /// The address of the signer.
+        actual: Address,
+    },
+    /// The fee increase is below [`MIN_FEE_BUMP_PERCENT`], nodes would
+    /// reject the replacement.
+    BumpTooLow,
+    /// Blob transactions cannot be rebuilt without their blobs.
+    BlobTransaction,
+}
+
+impl std::fmt::Display for ReplaceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ReplaceError::UnknownTransaction(hash) => write!(f, "Unknown transaction {}", hash),
+            ReplaceError::SignerMismatch { expected, actual } => write!(
+                f,
+                "The transaction was sent by {}, not {}",
+                expected, actual
+            ),
+            ReplaceError::BumpTooLow => write!(
+                f,
+                "Fees must rise by at least {}% to replace a transaction",
+                MIN_FEE_BUMP_PERCENT
+            ),
+            ReplaceError::BlobTransaction => {
+                write!(
+                    f,
+                    "Blob transactions cannot be replaced without their blobs"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplaceError {}
+
+/// How to raise the fees of a replacement transaction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeeBump {
+    /// Raise the gas price, or both EIP-1559 fees, by a percentage, at
+    /// least [`MIN_FEE_BUMP_PERCENT`].
+    Percent(f64),
+    /// Use these fees. A legacy transaction takes the maximum fee as its
+    /// gas price.
+    Fees {
+        /// Maximum fee per gas in wei.
+        max_fee_per_gas: u128,
+        /// Maximum priority fee per gas in wei.
+        max_priority_fee_per_gas: u128,
+    },
+}
+
+impl Default for FeeBump {
+    fn default() -> Self {
+        FeeBump::Percent(12.5)
+    }
+}
+
+impl FeeBump {
+    /// Return `tx` with its fees raised.
+    pub fn apply(&self, tx: &TypedTransaction) -> Result<TypedTransaction> {
+        let (max_fee, priority_fee) = match *self {
+            FeeBump::Percent(percent) => {
+                if percent.is_nan() || percent < MIN_FEE_BUMP_PERCENT {
+                    return Err(ReplaceError::BumpTooLow.into());
+                }
+                let (max_fee, priority_fee) = fees(tx)?;
+                (raise(max_fee, percent), raise(priority_fee, percent))
+            }
+            FeeBump::Fees {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => (max_fee_per_gas, max_priority_fee_per_gas),
+        };
+        with_fees(tx, max_fee, priority_fee)
+    }
+}
+
+/// Rebuild the pending transaction `tx_hash` with the same nonce and
+/// higher fees, sign it with `signer` and broadcast it, returning the hash
+/// of the replacement.
+///
+/// A percentage bump is raised further to the fees a new transaction would
+/// pay when they are higher, so a transaction stuck behind a rising base
+/// fee gets through. Once the original is mined, the node rejects the
+/// replacement for its nonce.
+pub fn replace<P: Provider, S: Signer>(
+    provider: &P,
+    signer: &S,
+    tx_hash: &B256,
+    bump: FeeBump,
+) -> Result<B256> {
+    let pending = pending(provider, signer, tx_hash)?;
+    let mut tx = bump.apply(&pending.transaction)?;
+    if let FeeBump::Percent(_) = bump {
+        tx = at_market(provider, &tx)?;
+    }
+    send(provider, signer, &tx)
+}
+
+/// Replace the pending transaction `tx_hash` with a transfer of nothing
+/// to the sender, at the default fee bump, so the original never executes.
+/// Returns the hash of the cancellation.
+pub fn cancel<P: Provider, S: Signer>(provider: &P, signer: &S, tx_hash: &B256) -> Result<B256> {
+    let pending = pending(provider, signer, tx_hash)?;
+    let original = &pending.transaction;
+    let (max_fee, priority_fee) = fees(original)?;
+    let noop = match original {
+        TypedTransaction::Eip1559(tx) => Eip1559Transaction {
+            chain_id: tx.chain_id,
+            nonce: tx.nonce,
+            max_priority_fee_per_gas: priority_fee,
+            max_fee_per_gas: max_fee,
+            gas_limit: TRANSFER_GAS,
+            to: Some(pending.from),
+            ..Default::default()
+        }
+        .into(),
+        _ => Transaction {
+            nonce: original.nonce(),
+            gas_price: max_fee,
+            gas_limit: TRANSFER_GAS,
+            to: Some(pending.from),
+            chain_id: original.chain_id(),
+            ..Default::default()
+        }
+        .into(),
+    };
+
+    let tx = at_market(provider, &FeeBump::default().apply(&noop)?)?;
+    send(provider, signer, &tx)
+}
+
+/// Look up a pending transaction of the signer.
+fn pending<P: Provider, S: Signer>(
+    provider: &P,
+    signer: &S,
+    tx_hash: &B256,
+) -> Result<DecodedTransaction> {
+    let pending = provider
+        .transaction_by_hash(tx_hash)?
+        .ok_or(ReplaceError::UnknownTransaction(*tx_hash))?;
+    let address = signer.address()?;
+    if pending.from != address {
+        return Err(ReplaceError::SignerMismatch {
+            expected: pending.from,
+            actual: address,
+        }
+        .into());
+    }
+    Ok(pending)
+}
+
+fn send<P: Provider, S: Signer>(provider: &P, signer: &S, tx: &TypedTransaction) -> Result<B256> {
+    let signature = signer.sign_typed_transaction(tx)?;
+    provider.send_raw_transaction(&tx.encode_signed(&signature))
+}
+
+/// Raise the fees of `tx` to what the provider asks of a new transaction
+/// when that is more.
+fn at_market<P: Provider>(provider: &P, tx: &TypedTransaction) -> Result<TypedTransaction> {
+    let (max_fee, priority_fee) = fees(tx)?;
+    let (market_max_fee, market_priority_fee) = match tx {
+        TypedTransaction::Legacy(_) | TypedTransaction::Eip2930(_) => {
+            let gas_price = provider.gas_price()?;
+            (gas_price, gas_price)
+        }
+        _ => {
+            let priority_fee = provider.max_priority_fee_per_gas()?;
+            let base_fee = provider.base_fee_per_gas()?.unwrap_or_default();
+            (base_fee * 2 + priority_fee, priority_fee)
+        }
+    };
+    with_fees(
+        tx,
+        max_fee.max(market_max_fee),
+        priority_fee.max(market_priority_fee),
+    )
+}
+
+/// Return the maximum and priority fees per gas, both the gas price for a
+/// legacy or EIP-2930 transaction.
+fn fees(tx: &TypedTransaction) -> Result<(u128, u128)> {
+    match tx {
+        TypedTransaction::Legacy(tx) => Ok((tx.gas_price, tx.gas_price)),
+        TypedTransaction::Eip2930(tx) => Ok((tx.gas_price, tx.gas_price)),
+        TypedTransaction::Eip1559(tx) => Ok((tx.max_fee_per_gas, tx.max_priority_fee_per_gas)),
+        TypedTransaction::Eip4844(_) => Err(ReplaceError::BlobTransaction.into()),
+    }
+}
+
+fn with_fees(tx: &TypedTransaction, max_fee: u128, priority_fee: u128) -> Result<TypedTransaction> {
+    let mut tx = tx.clone();
+    match &mut tx {
+        TypedTransaction::Legacy(tx) => tx.gas_price = max_fee,
+        TypedTransaction::Eip2930(tx) => tx.gas_price = max_fee,
+        TypedTransaction::Eip1559(tx) => {
+            tx.max_fee_per_gas = max_fee;
+            tx.max_priority_fee_per_gas = priority_fee.min(max_fee);
+        }
+        TypedTransaction::Eip4844(_) => return Err(ReplaceError::BlobTransaction.into()),
+    }
+    Ok(tx)
+}
+
+/// Raise a fee by `percent`, rounding up to the wei.
+fn raise(fee: u128, percent: f64) -> u128 {
+    let basis_points = (percent * 100.0).ceil() as u128;
+    fee.saturating_add(fee.saturating_mul(basis_points).div_ceil(10_000))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{provider::mock::MockProvider, transaction::Eip4844Transaction};
+    use laron_crypto::PrivateKey;
+    use std::collections::HashMap;
+
+    /// A provider knowing a transaction, with a base fee.
+    fn node(base_fee: u128, tx: &DecodedTransaction) -> MockProvider {
+        MockProvider {
+            gas_price: Some(base_fee),
+            base_fee: Some(Some(base_fee)),
+            priority_fee: Some(1_000_000_000),
+            transactions: Some(HashMap::from([(tx.hash, tx.clone())])),
+            ..Default::default()
+        }
+    }
+
+    /// Return the transactions sent.
+    fn sent(provider: &MockProvider) -> Vec<DecodedTransaction> {
+        let raw = provider.sent().into_iter();
+        raw.map(|raw| Transaction::decode(&hex::encode(raw)).unwrap())
+            .collect()
+    }
+
+    fn signed(key: &PrivateKey, tx: TypedTransaction) -> DecodedTransaction {
+        let signature = key.sign_typed_transaction(&tx).unwrap();
+        Transaction::decode(&hex::encode(tx.encode_signed(&signature))).unwrap()
+    }
+
+    fn legacy() -> TypedTransaction {
+        Transaction {
+            nonce: 4,
+            gas_price: 20_000_000_000,
+            gas_limit: 50_000,
+            to: Some(Address::new([0x35; 20])),
+            value: 1_000,
+            data: vec![0xa9, 0x05, 0x9c, 0xbb],
+            chain_id: Some(1),
+        }
+        .into()
+    }
+
+    fn dynamic() -> TypedTransaction {
+        Eip1559Transaction {
+            chain_id: 1,
+            nonce: 4,
+            max_priority_fee_per_gas: 2_000_000_000,
+            max_fee_per_gas: 30_000_000_000,
+            gas_limit: 50_000,
+            to: Some(Address::new([0x35; 20])),
+            value: 1_000,
+            ..Default::default()
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_apply() {
+        let tx = FeeBump::Percent(12.5).apply(&legacy()).unwrap();
+        assert_eq!(tx.max_fee_per_gas(), 22_500_000_000);
+
+        let TypedTransaction::Eip1559(tx) = FeeBump::default().apply(&dynamic()).unwrap() else {
+            panic!("the type changed");
+        };
+        assert_eq!(tx.max_fee_per_gas, 33_750_000_000);
+        assert_eq!(tx.max_priority_fee_per_gas, 2_250_000_000);
+        assert_eq!(raise(1, 10.0), 2);
+
+        assert!(FeeBump::Percent(5.0).apply(&legacy()).is_err());
+        assert!(FeeBump::Percent(f64::NAN).apply(&legacy()).is_err());
+        let bump = FeeBump::Fees {
+            max_fee_per_gas: 50,
+            max_priority_fee_per_gas: 80,
+        };
+        let TypedTransaction::Eip1559(tx) = bump.apply(&dynamic()).unwrap() else {
+            panic!("the type changed");
+        };
+        assert_eq!((tx.max_fee_per_gas, tx.max_priority_fee_per_gas), (50, 50));
+
+        let blob = Eip4844Transaction::default().into();
+        assert!(FeeBump::default().apply(&blob).is_err());
+    }
+
+    #[test]
+    fn test_replace() {
+        let key = PrivateKey::from_bytes(&[0x46; 32]).unwrap();
+        let original = signed(&key, dynamic());
+        let provider = node(10_000_000_000, &original);
+
+        let hash = replace(&provider, &key, &original.hash, FeeBump::default()).unwrap();
+        let replacement = sent(&provider)[0].clone();
+        assert_eq!(replacement.hash, hash);
+        assert_eq!(replacement.from, original.from);
+        assert_eq!(replacement.transaction.nonce(), 4);
+        assert_eq!(replacement.transaction.data(), original.transaction.data());
+        assert_eq!(replacement.transaction.max_fee_per_gas(), 33_750_000_000);
+
+        // a base fee that rose past the bump sets the fees
+        let provider = node(40_000_000_000, &original);
+        replace(&provider, &key, &original.hash, FeeBump::default()).unwrap();
+        let replacement = &sent(&provider)[0];
+        assert_eq!(replacement.transaction.max_fee_per_gas(), 81_000_000_000);
+
+        assert!(replace(&provider, &key, &B256::ZERO, FeeBump::default()).is_err());
+        let other = PrivateKey::from_bytes(&[1; 32]).unwrap();
+        assert!(replace(&provider, &other, &original.hash, FeeBump::default()).is_err());
+    }
+
+    #[test]
+    fn test_cancel() {
+        let key = PrivateKey::from_bytes(&[0x46; 32]).unwrap();
+        let original = signed(&key, legacy());
+        let provider = node(10_000_000_000, &original);
+
+        cancel(&provider, &key, &original.hash).unwrap();
+        let TypedTransaction::Legacy(tx) = &sent(&provider)[0].transaction else {
+            panic!("the type changed");
+        };
+        assert_eq!(tx.to, Some(original.from));
+        assert_eq!((tx.nonce, tx.value, tx.gas_limit), (4, 0, 21_000));
+        assert!(tx.data.is_empty());
+        assert_eq!(tx.gas_price, 22_500_000_000);
+        assert_eq!(tx.chain_id, Some(1));
+    }
+}
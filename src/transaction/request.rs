@@ -0,0 +1,475 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::{
+    gas::{GasFees, GasStrategy, NodeFees},
+    l2::{arbitrum, optimism, FeeEstimate, Rollup},
+    AccessListItem, Eip1559Transaction, Eip2930Transaction, Transaction, TypedTransaction,
+};
+use crate::{
+    address::Address, chains::Chain, error::Result, provider::Provider, signer::Signer, types::B256,
+};
+
+/// Error returned when building a transaction from a [`TransactionRequest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequestError {
+    /// A field needed to build the transaction is not set, its JSON-RPC
+    /// name is given.
+    MissingField(&'static str),
+    /// Both a gas price and a maximum fee per gas are set.
+    ConflictingFees,
+    /// The request is sent from another account than the signer's.
+    SignerMismatch {
+        /// The sender of the request.
+        expected: Address,
+        /// The address of the signer.
+        actual: Address,
+    },
+}
+
+impl std::fmt::Display for RequestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RequestError::MissingField(field) => {
+                write!(f, "The transaction request has no {}", field)
+            }
+            RequestError::ConflictingFees => {
+                write!(f, "A transaction has either a gas price or EIP-1559 fees")
+            }
+            RequestError::SignerMismatch { expected, actual } => write!(
+                f,
+                "The transaction request is from {}, not {}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RequestError {}
+
+/// A transaction being assembled. The fields left unset are filled in from
+/// a [`Provider`] when the request is sent.
+///
+/// The type follows from the fees: a gas price gives a legacy transaction,
+/// or an EIP-2930 one with an access list, a maximum fee per gas gives an
+/// EIP-1559 transaction. Without either, a [`GasStrategy`] decides, by
+/// default [`NodeFees`]: EIP-1559 fees when the latest block has a base
+/// fee, a gas price otherwise.
+///
+/// ## Example
+/// ```rust
+/// use wallet_rust::{address::Address, chains, transaction::{TransactionRequest, TypedTransaction}};
+/// use wallet_rust::units::{parse_ether, parse_gwei};
+///
+/// let request = TransactionRequest::new()
+///     .to(Address::new([0x35; 20]))
+///     .value(parse_ether("0.1").unwrap())
+///     .chain(&chains::MAINNET)
+///     .nonce(9)
+///     .gas_limit(21_000)
+///     .max_fee_per_gas(parse_gwei("30").unwrap())
+///     .max_priority_fee_per_gas(parse_gwei("2").unwrap());
+///
+/// // with a provider, request.send(&provider, &signer) fills in what is missing
+/// let tx = request.build().unwrap();
+/// assert!(matches!(tx, TypedTransaction::Eip1559(_)));
+/// assert_eq!(tx.value(), 100_000_000_000_000_000);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionRequest {
+    /// Sender, the signer when sent.
+    pub from: Option<Address>,
+    /// Recipient, `None` for a contract creation.
+    pub to: Option<Address>,
+    /// Value in wei.
+    pub value: u128,
+    /// Call data.
+    pub data: Vec<u8>,
+    /// Chain id.
+    pub chain_id: Option<u64>,
+    /// Nonce of the sender.
+    pub nonce: Option<u64>,
+    /// Gas limit.
+    pub gas_limit: Option<u64>,
+    /// Gas price in wei, for legacy and EIP-2930 transactions.
+    pub gas_price: Option<u128>,
+    /// Maximum fee per gas in wei, for EIP-1559 transactions.
+    pub max_fee_per_gas: Option<u128>,
+    /// Maximum priority fee per gas in wei, for EIP-1559 transactions.
+    pub max_priority_fee_per_gas: Option<u128>,
+    /// Accounts and storage slots warmed up front.
+    pub access_list: Vec<AccessListItem>,
+}
+
+impl TransactionRequest {
+    /// Start an empty request.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the sender.
+    pub fn from(mut self, from: Address) -> Self {
+        self.from = Some(from);
+        self
+    }
+
+    /// Set the recipient.
+    pub fn to(mut self, to: Address) -> Self {
+        self.to = Some(to);
+        self
+    }
+
+    /// Set the value in wei.
+    pub fn value(mut self, value: u128) -> Self {
+        self.value = value;
+        self
+    }
+
+    /// Set the call data.
+    pub fn data(mut self, data: impl Into<Vec<u8>>) -> Self {
+        self.data = data.into();
+        self
+    }
+
+    /// Set the chain.
+    pub fn chain(mut self, chain: &Chain) -> Self {
+        self.chain_id = Some(chain.id);
+        self
+    }
+
+    /// Set the chain from a [`Chain`] or its id.
+    pub fn chain_id(mut self, chain: impl Into<Chain>) -> Self {
+        self.chain_id = Some(chain.into().id);
+        self
+    }
+
+    /// Set the nonce.
+    pub fn nonce(mut self, nonce: u64) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    /// Set the gas limit.
+    pub fn gas_limit(mut self, gas_limit: u64) -> Self {
+        self.gas_limit = Some(gas_limit);
+        self
+    }
+
+    /// Set the gas price in wei, making a legacy or EIP-2930 transaction.
+    pub fn gas_price(mut self, gas_price: u128) -> Self {
+        self.gas_price = Some(gas_price);
+        self
+    }
+
+    /// Set the maximum fee per gas in wei, making an EIP-1559 transaction.
+    pub fn max_fee_per_gas(mut self, max_fee_per_gas: u128) -> Self {
+        self.max_fee_per_gas = Some(max_fee_per_gas);
+        self
+    }
+
+    /// Set the maximum priority fee per gas in wei.
+    pub fn max_priority_fee_per_gas(mut self, max_priority_fee_per_gas: u128) -> Self {
+        self.max_priority_fee_per_gas = Some(max_priority_fee_per_gas);
+        self
+    }
+
+    /// Set the access list.
+    pub fn access_list(mut self, access_list: Vec<AccessListItem>) -> Self {
+        self.access_list = access_list;
+        self
+    }
+
+    /// Build the transaction from the fields set, failing when one is
+    /// missing.
+    pub fn build(&self) -> Result<TypedTransaction> {
+        let chain_id = self.chain_id.ok_or(RequestError::MissingField("chainId"))?;
+        let nonce = self.nonce.ok_or(RequestError::MissingField("nonce"))?;
+        let gas_limit = self.gas_limit.ok_or(RequestError::MissingField("gas"))?;
+
+        let tx = match (self.gas_price, self.max_fee_per_gas) {
+            (Some(_), Some(_)) => return Err(RequestError::ConflictingFees.into()),
+            (Some(gas_price), None) if self.access_list.is_empty() => Transaction {
+                nonce,
+                gas_price,
+                gas_limit,
+                to: self.to,
+                value: self.value,
+                data: self.data.clone(),
+                chain_id: Some(chain_id),
+            }
+            .into(),
+            (Some(gas_price), None) => Eip2930Transaction {
+                chain_id,
+                nonce,
+                gas_price,
+                gas_limit,
+                to: self.to,
+                value: self.value,
+                data: self.data.clone(),
+                access_list: self.access_list.clone(),
+            }
+            .into(),
+            (None, Some(max_fee_per_gas)) => Eip1559Transaction {
+                chain_id,
+                nonce,
+                max_priority_fee_per_gas: self
+                    .max_priority_fee_per_gas
+                    .ok_or(RequestError::MissingField("maxPriorityFeePerGas"))?,
+                max_fee_per_gas,
+                gas_limit,
+                to: self.to,
+                value: self.value,
+                data: self.data.clone(),
+                access_list: self.access_list.clone(),
+            }
+            .into(),
+            (None, None) => return Err(RequestError::MissingField("gasPrice").into()),
+        };
+        Ok(tx)
+    }
+
+    /// Fill in the unset fields from `provider` and build the transaction.
+    /// Without a nonce, the sender must be set.
+    ///
+    /// The fees left unset are the ones suggested by the node, see
+    /// [`NodeFees`].
+    pub fn fill<P: Provider>(&self, provider: &P) -> Result<TypedTransaction> {
+        self.fill_with(provider, &NodeFees)
+    }
+
+    /// Fill in the unset fields from `provider`, the fees from `strategy`,
+    /// and build the transaction. The middleware of a provider stack fill
+    /// in their fields first.
+    ///
+    /// A priority fee set alone replaces the one of the strategy, keeping
+    /// its room for the base fee. A maximum fee per gas set alone gets the
+    /// priority fee of the strategy, or of the node when the strategy
+    /// gives a gas price.
+    pub fn fill_with<P: Provider, G: GasStrategy + ?Sized>(
+        &self,
+        provider: &P,
+        strategy: &G,
+    ) -> Result<TypedTransaction> {
+        let mut request = self.clone();
+        provider.fill_transaction(&mut request)?;
+        if request.chain_id.is_none() {
+            request.chain_id = Some(provider.chain_id()?);
+        }
+        if request.nonce.is_none() {
+            let from = request.from.ok_or(RequestError::MissingField("from"))?;
+            request.nonce = Some(provider.transaction_count(&from)?);
+        }
+        if request.gas_limit.is_none() {
+            request.gas_limit = Some(provider.estimate_gas(&request)?);
+        }
+
+        request.fill_fees(provider, strategy)?;
+        request.build()
+    }
+
+    /// Set the fees left unset from `strategy`, as
+    /// [`fill_with`](Self::fill_with) does.
+    pub fn fill_fees<G: GasStrategy + ?Sized>(
+        &mut self,
+        provider: &dyn Provider,
+        strategy: &G,
+    ) -> Result<()> {
+        match (self.gas_price, self.max_fee_per_gas) {
+            (None, None) => match strategy.fees(provider, self)? {
+                GasFees::Legacy { gas_price } => self.gas_price = Some(gas_price),
+                GasFees::Eip1559 {
+                    max_fee_per_gas,
+                    max_priority_fee_per_gas,
+                } => {
+                    let priority_fee = self
+                        .max_priority_fee_per_gas
+                        .unwrap_or(max_priority_fee_per_gas);
+                    self.max_priority_fee_per_gas = Some(priority_fee);
+                    let headroom = max_fee_per_gas.saturating_sub(max_priority_fee_per_gas);
+                    self.max_fee_per_gas = Some(headroom + priority_fee);
+                }
+            },
+            (None, Some(_)) if self.max_priority_fee_per_gas.is_none() => {
+                let priority_fee = match strategy.fees(provider, self)? {
+                    GasFees::Eip1559 {
+                        max_priority_fee_per_gas,
+                        ..
+                    } => max_priority_fee_per_gas,
+                    GasFees::Legacy { .. } => provider.max_priority_fee_per_gas()?,
+                };
+                self.max_priority_fee_per_gas = Some(priority_fee);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Fill in the unset fields from `provider` and estimate the most the
+    /// transaction can cost, with the L1 data fee on rollups.
+    pub fn estimate_fee<P: Provider>(&self, provider: &P) -> Result<FeeEstimate> {
+        let tx = self.fill(provider)?;
+        let mut estimate = FeeEstimate {
+            gas_limit: tx.gas_limit(),
+            max_fee_per_gas: tx.max_fee_per_gas(),
+            ..Default::default()
+        };
+        match tx.chain_id().and_then(Rollup::of) {
+            Some(Rollup::OpStack) => estimate.l1_fee = optimism::l1_fee(provider, &tx)?,
+            Some(Rollup::Arbitrum) => {
+                estimate.l1_gas = arbitrum::gas_components(provider, tx.to(), tx.data())?.l1_gas
+            }
+            Some(Rollup::ZkSync) | None => {}
+        }
+        Ok(estimate)
+    }
+
+    /// Fill in the unset fields from `provider`, sign the transaction with
+    /// `signer` and broadcast it, returning its hash.
+    pub fn send<P: Provider, S: Signer>(&self, provider: &P, signer: &S) -> Result<B256> {
+        self.send_with(provider, signer, &NodeFees)
+    }
+
+    /// Like [`send`](Self::send), with the fees left unset from `strategy`.
+    pub fn send_with<P: Provider, S: Signer, G: GasStrategy + ?Sized>(
+        &self,
+        provider: &P,
+        signer: &S,
+        strategy: &G,
+    ) -> Result<B256> {
+        let address = signer.address()?;
+        if let Some(expected) = self.from {
+            if expected != address {
+                return Err(RequestError::SignerMismatch {
+                    expected,
+                    actual: address,
+                }
+                .into());
+            }
+        }
+
+        let request = TransactionRequest {
+            from: Some(address),
+            ..self.clone()
+        };
+        let tx = request.fill_with(provider, strategy)?;
+        let signature = signer.sign_typed_transaction(&tx)?;
+        provider.send_raw_transaction(&tx.encode_signed(&signature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chains, provider::mock::MockProvider};
+    use laron_crypto::PrivateKey;
+    use std::collections::HashMap;
+
+    /// A provider with fixed fees, where the address [0x9d; 20] has sent
+    /// 7 transactions.
+    fn node(base_fee: Option<u128>) -> MockProvider {
+        MockProvider {
+            nonces: Some(HashMap::from([(Address::new([0x9d; 20]), 7)])),
+            chain_id: Some(10),
+            gas: Some(21_032),
+            gas_price: Some(5_000_000_000),
+            base_fee: Some(base_fee),
+            priority_fee: Some(1_000_000_000),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_build() {
+        let request = TransactionRequest::new()
+            .to(Address::new([0x35; 20]))
+            .chain(&chains::MAINNET)
+            .nonce(1)
+            .gas_limit(21_000);
+        assert_eq!(
+            request.build().unwrap_err().to_string(),
+            "The transaction request has no gasPrice"
+        );
+
+        let legacy = request.clone().gas_price(1).build().unwrap();
+        assert_eq!((legacy.tx_type(), legacy.chain_id()), (0, Some(1)));
+        let access_list = vec![AccessListItem::default()];
+        let tx = request.clone().gas_price(1).access_list(access_list);
+        assert_eq!(tx.build().unwrap().tx_type(), 1);
+
+        let tx = request.clone().max_fee_per_gas(2);
+        assert!(tx.build().is_err());
+        assert_eq!(tx.max_priority_fee_per_gas(1).build().unwrap().tx_type(), 2);
+
+        let tx = request.gas_price(1).max_fee_per_gas(2);
+        assert!(tx.build().is_err());
+    }
+
+    #[test]
+    fn test_fill() {
+        let from = Address::new([0x9d; 20]);
+        let request = TransactionRequest::new()
+            .from(from)
+            .to(Address::new([0x35; 20]))
+            .data(vec![1, 2]);
+
+        let mut provider = node(Some(10_000_000_000));
+        let TypedTransaction::Eip1559(tx) = request.fill(&provider).unwrap() else {
+            panic!("not an EIP-1559 transaction");
+        };
+        assert_eq!((tx.chain_id, tx.nonce, tx.gas_limit), (10, 7, 21_032));
+        assert_eq!(tx.max_priority_fee_per_gas, 1_000_000_000);
+        assert_eq!(tx.max_fee_per_gas, 21_000_000_000);
+
+        // what is set is kept
+        let tx = request
+            .clone()
+            .nonce(3)
+            .max_fee_per_gas(40_000_000_000)
+            .fill(&provider)
+            .unwrap();
+        assert_eq!(tx.nonce(), 3);
+        assert_eq!(tx.max_fee_per_gas(), 40_000_000_000);
+
+        provider.base_fee = Some(None);
+        let tx = request.fill(&provider).unwrap();
+        assert_eq!(tx.tx_type(), 0);
+        assert_eq!(tx.max_fee_per_gas(), 5_000_000_000);
+
+        // the nonce is looked up for the sender
+        let request = TransactionRequest {
+            from: None,
+            ..request
+        };
+        assert!(request.fill(&provider).is_err());
+    }
+
+    #[test]
+    fn test_send() {
+        let key = PrivateKey::from_bytes(&[0x46; 32]).unwrap();
+        let provider = node(Some(10_000_000_000));
+        let request = TransactionRequest::new()
+            .to(Address::new([0x35; 20]))
+            .value(1_000_000_000_000_000_000);
+
+        let hash = request.send(&provider, &key).unwrap();
+        let raw = provider.sent()[0].clone();
+        let decoded = Transaction::decode(&hex::encode(raw)).unwrap();
+        assert_eq!(decoded.hash, hash);
+        assert_eq!(decoded.from, key.address().unwrap());
+        assert_eq!(decoded.transaction.chain_id(), Some(10));
+
+        let other = request.from(Address::new([0x9d; 20]));
+        assert!(other.send(&provider, &key).is_err());
+        assert_eq!(provider.sent().len(), 1);
+    }
+}
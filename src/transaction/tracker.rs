@@ -0,0 +1,586 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver, Sender},
+};
+
+use serde_json::{json, Value};
+
+use super::save;
+use crate::{
+    address::Address,
+    error::Result,
+    provider::{Provider, TransactionReceipt},
+    types::B256,
+};
+
+/// Number of blocks, the including one counted, after which a transaction
+/// is confirmed.
+pub const DEFAULT_CONFIRMATIONS: u64 = 12;
+
+/// Version of the files written by the [`Tracker`].
+const VERSION: u64 = 1;
+
+/// Error returned when reading the state of a [`Tracker`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrackerError {
+    /// The JSON is not a tracker file, the malformed field is given.
+    InvalidFormat(&'static str),
+    /// The version of the file is not supported.
+    UnsupportedVersion(u64),
+}
+
+impl std::fmt::Display for TrackerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TrackerError::InvalidFormat(field) => write!(f, "Invalid tracker file: {}", field),
+            TrackerError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported tracker file version {}", version)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TrackerError {}
+
+/// Where a transaction stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// Known to the node, waiting to be mined.
+    Pending,
+    /// Included in the given block, with fewer confirmations than required.
+    Mined(u64),
+    /// Included in the given block, with enough confirmations.
+    Confirmed(u64),
+    /// Included in the given block, which is finalized.
+    Finalized(u64),
+    /// Unknown to the node while its nonce is still unused, it may be
+    /// broadcast again.
+    Dropped,
+    /// Its nonce was used by another transaction.
+    Replaced,
+}
+
+impl Status {
+    /// Return the block including the transaction.
+    pub fn block(&self) -> Option<u64> {
+        match self {
+            Status::Mined(block) | Status::Confirmed(block) | Status::Finalized(block) => {
+                Some(*block)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the status can no longer change.
+    pub fn is_final(&self) -> bool {
+        matches!(self, Status::Finalized(_) | Status::Replaced)
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Status::Pending => "pending",
+            Status::Mined(_) => "mined",
+            Status::Confirmed(_) => "confirmed",
+            Status::Finalized(_) => "finalized",
+            Status::Dropped => "dropped",
+            Status::Replaced => "replaced",
+        }
+    }
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.block() {
+            Some(block) => write!(f, "{} in block {}", self.name(), block),
+            None => write!(f, "{}", self.name()),
+        }
+    }
+}
+
+/// A transaction followed by the [`Tracker`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackedTransaction {
+    /// Hash of the transaction.
+    pub hash: B256,
+    /// Sender of the transaction.
+    pub from: Address,
+    /// Nonce of the transaction.
+    pub nonce: u64,
+    /// Current status.
+    pub status: Status,
+    /// Receipt of the transaction once mined.
+    pub receipt: Option<TransactionReceipt>,
+}
+
+/// A change of status of a tracked transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Event {
+    /// Hash of the transaction.
+    pub hash: B256,
+    /// The new status.
+    pub status: Status,
+}
+
+impl std::fmt::Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Transaction {} {}", self.hash, self.status)
+    }
+}
+
+/// Follows sent transactions until they are finalized or replaced.
+///
+/// A tracker follows the transactions of a single chain. Opened on a file,
+/// it saves its state after every change, so the transactions are still
+/// followed after a restart.
+pub struct Tracker {
+    transactions: Vec<TrackedTransaction>,
+    confirmations: u64,
+    path: Option<PathBuf>,
+    subscribers: Vec<Sender<Event>>,
+}
+
+impl Default for Tracker {
+    fn default() -> Self {
+        Self {
+            transactions: Vec::new(),
+            confirmations: DEFAULT_CONFIRMATIONS,
+            path: None,
+            subscribers: Vec::new(),
+        }
+    }
+}
+
+impl Tracker {
+    /// Create a tracker kept in memory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open a tracker saved to `path`, starting empty when the file does
+    /// not exist yet.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let mut tracker = if path.exists() {
+            Self::from_json(&std::fs::read_to_string(path)?)?
+        } else {
+            Self::new()
+        };
+        tracker.path = Some(path.to_path_buf());
+        Ok(tracker)
+    }
+
+    /// Set the number of blocks, the including one counted, after which a
+    /// transaction is confirmed.
+    pub fn confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = confirmations.max(1);
+        self
+    }
+
+    /// Return a receiver of every later [`Event`].
+    pub fn subscribe(&mut self) -> Receiver<Event> {
+        let (sender, receiver) = channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    /// Start following a sent transaction.
+    pub fn track(&mut self, hash: B256, from: Address, nonce: u64) -> Result<()> {
+        if self.get(&hash).is_some() {
+            return Ok(());
+        }
+        self.transactions.push(TrackedTransaction {
+            hash,
+            from,
+            nonce,
+            status: Status::Pending,
+            receipt: None,
+        });
+        self.persist()?;
+        self.emit(&Event {
+            hash,
+            status: Status::Pending,
+        });
+        Ok(())
+    }
+
+    /// Return a tracked transaction.
+    pub fn get(&self, hash: &B256) -> Option<&TrackedTransaction> {
+        self.transactions.iter().find(|tx| tx.hash == *hash)
+    }
+
+    /// Return the tracked transactions, in the order they were added.
+    pub fn transactions(&self) -> impl Iterator<Item = &TrackedTransaction> {
+        self.transactions.iter()
+    }
+
+    /// Stop following the finalized and replaced transactions, returning
+    /// them.
+    pub fn prune(&mut self) -> Result<Vec<TrackedTransaction>> {
+        let (done, pending) = std::mem::take(&mut self.transactions)
+            .into_iter()
+            .partition(|tx| tx.status.is_final());
+        self.transactions = pending;
+        self.persist()?;
+        Ok(done)
+    }
+
+    /// Query the status of every transaction not final yet, returning the
+    /// events of those whose status changed.
+    pub fn poll<P: Provider>(&mut self, provider: &P) -> Result<Vec<Event>> {
+        if self.transactions.iter().all(|tx| tx.status.is_final()) {
+            return Ok(Vec::new());
+        }
+        let head = provider.block_number()?;
+        let finalized = provider.finalized_block_number()?;
+
+        let mut events = Vec::new();
+        for tx in self.transactions.iter_mut() {
+            if tx.status.is_final() {
+                continue;
+            }
+
+            let mut receipt = provider.transaction_receipt(&tx.hash)?;
+            if receipt.is_none() && provider.transaction_by_hash(&tx.hash)?.is_none() {
+                // the transaction may have been mined since its receipt was
+                // queried, using the nonce it is checked against
+                if provider.transaction_count(&tx.from)? > tx.nonce {
+                    receipt = provider.transaction_receipt(&tx.hash)?;
+                }
+            }
+
+            let status = match &receipt {
+                Some(receipt) if receipt.block_number <= finalized => {
+                    Status::Finalized(receipt.block_number)
+                }
+                Some(receipt)
+                    if head.saturating_sub(receipt.block_number) + 1 >= self.confirmations =>
+                {
+                    Status::Confirmed(receipt.block_number)
+                }
+                Some(receipt) => Status::Mined(receipt.block_number),
+                None if provider.transaction_by_hash(&tx.hash)?.is_some() => Status::Pending,
+                None if provider.transaction_count(&tx.from)? > tx.nonce => Status::Replaced,
+                None => Status::Dropped,
+            };
+
+            tx.receipt = receipt;
+            if status != tx.status {
+                tx.status = status;
+                events.push(Event {
+                    hash: tx.hash,
+                    status,
+                });
+            }
+        }
+
+        if !events.is_empty() {
+            self.persist()?;
+        }
+        for event in &events {
+            self.emit(event);
+        }
+        Ok(events)
+    }
+
+    fn emit(&mut self, event: &Event) {
+        // receivers dropped by their subscriber are forgotten
+        self.subscribers
+            .retain(|subscriber| subscriber.send(*event).is_ok());
+    }
+
+    fn persist(&self) -> Result<()> {
+        match &self.path {
+            Some(path) => save(path, &self.to_json()),
+            None => Ok(()),
+        }
+    }
+
+    fn to_json(&self) -> String {
+        let transactions: Vec<Value> = self
+            .transactions
+            .iter()
+            .map(|tx| {
+                let mut value = json!({
+                    "hash": tx.hash.to_string(),
+                    "from": tx.from.to_string(),
+                    "nonce": format!("{:#x}", tx.nonce),
+                    "status": tx.status.name(),
+                });
+                if let Some(block) = tx.status.block() {
+                    value["block"] = json!(format!("{:#x}", block));
+                }
+                if let Some(receipt) = &tx.receipt {
+                    value["receipt"] = json!({
+                        "blockNumber": format!("{:#x}", receipt.block_number),
+                        "success": receipt.success,
+                        "gasUsed": format!("{:#x}", receipt.gas_used),
+                        "effectiveGasPrice": format!("{:#x}", receipt.effective_gas_price),
+                    });
+                }
+                value
+            })
+            .collect();
+        serde_json::to_string_pretty(&json!({
+            "version": VERSION,
+            "transactions": transactions,
+        }))
+        .expect("JSON values always serialize")
+    }
+
+    fn from_json(json: &str) -> Result<Self> {
+        let root: Value =
+            serde_json::from_str(json).map_err(|_| TrackerError::InvalidFormat("json"))?;
+        match root["version"].as_u64() {
+            Some(VERSION) => {}
+            Some(version) => return Err(TrackerError::UnsupportedVersion(version).into()),
+            None => return Err(TrackerError::InvalidFormat("version").into()),
+        }
+
+        let transactions = root["transactions"]
+            .as_array()
+            .ok_or(TrackerError::InvalidFormat("transactions"))?
+            .iter()
+            .map(tracked_from_json)
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            transactions,
+            ..Self::default()
+        })
+    }
+}
+
+fn tracked_from_json(value: &Value) -> Result<TrackedTransaction> {
+    let block = || quantity(&value["block"], "block");
+    let status = match value["status"].as_str() {
+        Some("pending") => Status::Pending,
+        Some("mined") => Status::Mined(block()?),
+        Some("confirmed") => Status::Confirmed(block()?),
+        Some("finalized") => Status::Finalized(block()?),
+        Some("dropped") => Status::Dropped,
+        Some("replaced") => Status::Replaced,
+        _ => return Err(TrackerError::InvalidFormat("status").into()),
+    };
+
+    let receipt = match &value["receipt"] {
+        Value::Null => None,
+        receipt => Some(TransactionReceipt {
+            block_number: quantity(&receipt["blockNumber"], "receipt")?,
+            success: receipt["success"]
+                .as_bool()
+                .ok_or(TrackerError::InvalidFormat("receipt"))?,
+            gas_used: quantity(&receipt["gasUsed"], "receipt")?,
+            effective_gas_price: quantity(&receipt["effectiveGasPrice"], "receipt")?,
+        }),
+    };
+
+    Ok(TrackedTransaction {
+        hash: parse(&value["hash"], "hash")?,
+        from: parse(&value["from"], "from")?,
+        nonce: quantity(&value["nonce"], "nonce")?,
+        status,
+        receipt,
+    })
+}
+
+/// Parse a hex quantity.
+fn quantity<T: TryFrom<u128>>(value: &Value, field: &'static str) -> Result<T> {
+    Ok(value
+        .as_str()
+        .and_then(|value| value.strip_prefix("0x"))
+        .and_then(|digits| u128::from_str_radix(digits, 16).ok())
+        .and_then(|value| value.try_into().ok())
+        .ok_or(TrackerError::InvalidFormat(field))?)
+}
+
+/// Parse a string field.
+fn parse<T: std::str::FromStr>(value: &Value, field: &'static str) -> Result<T> {
+    Ok(value
+        .as_str()
+        .and_then(|value| value.parse().ok())
+        .ok_or(TrackerError::InvalidFormat(field))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{provider::mock::MockProvider, transaction::DecodedTransaction};
+    use std::collections::HashMap;
+
+    /// A chain at block `head` without any transaction.
+    fn chain(head: u64) -> MockProvider {
+        MockProvider {
+            nonces: Some(HashMap::new()),
+            transactions: Some(HashMap::new()),
+            receipts: Some(HashMap::new()),
+            block_number: Some(head),
+            finalized_block_number: Some(0),
+            ..Default::default()
+        }
+    }
+
+    /// Put a transaction of the sender in the mempool.
+    fn broadcast(provider: &mut MockProvider, hash: B256) {
+        // only the presence matters to the tracker
+        let tx = DecodedTransaction {
+            transaction: crate::transaction::Transaction::default().into(),
+            signature: crate::signature::Signature::new([1; 32], [1; 32], 27),
+            from: sender(),
+            hash,
+        };
+        provider.transactions.as_mut().unwrap().insert(hash, tx);
+    }
+
+    /// Mine a transaction of the sender in `block`.
+    fn mine(provider: &mut MockProvider, hash: B256, block: u64) {
+        broadcast(provider, hash);
+        let receipt = TransactionReceipt {
+            block_number: block,
+            success: true,
+            gas_used: 21_000,
+            effective_gas_price: 1_000_000_000,
+        };
+        provider.receipts.as_mut().unwrap().insert(hash, receipt);
+        let nonces = provider.nonces.as_mut().unwrap();
+        *nonces.entry(sender()).or_default() += 1;
+    }
+
+    fn sender() -> Address {
+        Address::new([0x11; 20])
+    }
+
+    fn hash(byte: u8) -> B256 {
+        B256::new([byte; 32])
+    }
+
+    #[test]
+    fn test_lifecycle() {
+        let mut provider = chain(100);
+        provider.finalized_block_number = Some(64);
+        let mut tracker = Tracker::new().confirmations(3);
+        let events = tracker.subscribe();
+
+        broadcast(&mut provider, hash(1));
+        tracker.track(hash(1), sender(), 0).unwrap();
+        assert!(tracker.poll(&provider).unwrap().is_empty());
+
+        provider.block_number = Some(101);
+        mine(&mut provider, hash(1), 101);
+        assert_eq!(
+            tracker.poll(&provider).unwrap(),
+            [Event {
+                hash: hash(1),
+                status: Status::Mined(101)
+            }]
+        );
+        assert!(
+            tracker
+                .get(&hash(1))
+                .unwrap()
+                .receipt
+                .as_ref()
+                .unwrap()
+                .success
+        );
+
+        provider.block_number = Some(103);
+        tracker.poll(&provider).unwrap();
+        provider.finalized_block_number = Some(128);
+        tracker.poll(&provider).unwrap();
+        assert!(tracker.poll(&provider).unwrap().is_empty());
+
+        let statuses: Vec<Status> = events.try_iter().map(|event| event.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                Status::Pending,
+                Status::Mined(101),
+                Status::Confirmed(101),
+                Status::Finalized(101)
+            ]
+        );
+        assert_eq!(tracker.prune().unwrap().len(), 1);
+        assert_eq!(tracker.transactions().count(), 0);
+    }
+
+    #[test]
+    fn test_dropped_and_replaced() {
+        let mut provider = chain(100);
+        let mut tracker = Tracker::new();
+        tracker.track(hash(1), sender(), 0).unwrap();
+        tracker.track(hash(2), sender(), 1).unwrap();
+
+        // the node forgot both transactions, then another one took nonce 0
+        tracker.poll(&provider).unwrap();
+        assert_eq!(tracker.get(&hash(1)).unwrap().status, Status::Dropped);
+        provider.nonces.as_mut().unwrap().insert(sender(), 1);
+        tracker.poll(&provider).unwrap();
+        assert_eq!(tracker.get(&hash(1)).unwrap().status, Status::Replaced);
+        assert_eq!(tracker.get(&hash(2)).unwrap().status, Status::Dropped);
+
+        // a dropped transaction broadcast again is followed again
+        mine(&mut provider, hash(2), 101);
+        tracker.poll(&provider).unwrap();
+        assert_eq!(tracker.get(&hash(2)).unwrap().status, Status::Mined(101));
+    }
+
+    #[test]
+    fn test_reorg() {
+        let mut provider = chain(100);
+        let mut tracker = Tracker::new();
+        tracker.track(hash(1), sender(), 0).unwrap();
+        mine(&mut provider, hash(1), 100);
+        tracker.poll(&provider).unwrap();
+
+        // the block was reorganized away, the transaction is back in the
+        // mempool
+        provider = chain(100);
+        broadcast(&mut provider, hash(1));
+        let events = tracker.poll(&provider).unwrap();
+        assert_eq!(events[0].status, Status::Pending);
+        assert_eq!(tracker.get(&hash(1)).unwrap().receipt, None);
+    }
+
+    #[test]
+    fn test_persistence() {
+        let path = std::env::temp_dir().join(format!("tracker-{}.json", std::process::id()));
+        let mut provider = chain(100);
+
+        let mut tracker = Tracker::open(&path).unwrap();
+        tracker.track(hash(1), sender(), 0).unwrap();
+        tracker.track(hash(2), sender(), 1).unwrap();
+        mine(&mut provider, hash(1), 100);
+        broadcast(&mut provider, hash(2));
+        tracker.poll(&provider).unwrap();
+
+        let reopened = Tracker::open(&path).unwrap();
+        let transactions: Vec<_> = reopened.transactions().cloned().collect();
+        assert_eq!(
+            transactions,
+            tracker.transactions().cloned().collect::<Vec<_>>()
+        );
+        assert_eq!(transactions[0].status, Status::Mined(100));
+        assert_eq!(transactions[1].status, Status::Pending);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            Tracker::from_json(r#"{"version":2,"transactions":[]}"#)
+                .err()
+                .unwrap()
+                .to_string(),
+            "Unsupported tracker file version 2"
+        );
+    }
+}
@@ -0,0 +1,455 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::{rlp_bytes, rlp_list, rlp_uint, trim_zeros, Transaction};
+use crate::{
+    address::Address,
+    error::Result,
+    signature::{Signature, SignatureError},
+    types::B256,
+    utils::keccak256,
+};
+
+/// An entry of an [EIP-2930](https://eips.ethereum.org/EIPS/eip-2930)
+/// access list: a contract and the storage slots the transaction touches.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccessListItem {
+    /// The contract accessed.
+    pub address: Address,
+    /// The storage slots accessed.
+    pub storage_keys: Vec<B256>,
+}
+
+/// An [EIP-2930](https://eips.ethereum.org/EIPS/eip-2930) transaction,
+/// type 1, with a gas price and an access list.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Eip2930Transaction {
+    /// Chain id.
+    pub chain_id: u64,
+    /// Nonce of the sender.
+    pub nonce: u64,
+    /// Gas price in wei.
+    pub gas_price: u128,
+    /// Gas limit.
+    pub gas_limit: u64,
+    /// Recipient, `None` for a contract creation.
+    pub to: Option<Address>,
+    /// Value in wei.
+    pub value: u128,
+    /// Call data.
+    pub data: Vec<u8>,
+    /// Accounts and storage slots warmed up front.
+    pub access_list: Vec<AccessListItem>,
+}
+
+/// An [EIP-1559](https://eips.ethereum.org/EIPS/eip-1559) transaction,
+/// type 2, paying a base fee and a priority fee.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Eip1559Transaction {
+    /// Chain id.
+    pub chain_id: u64,
+    /// Nonce of the sender.
+    pub nonce: u64,
+    /// Maximum priority fee per gas, in wei.
+    pub max_priority_fee_per_gas: u128,
+    /// Maximum fee per gas, in wei.
+    pub max_fee_per_gas: u128,
+    /// Gas limit.
+    pub gas_limit: u64,
+    /// Recipient, `None` for a contract creation.
+    pub to: Option<Address>,
+    /// Value in wei.
+    pub value: u128,
+    /// Call data.
+    pub data: Vec<u8>,
+    /// Accounts and storage slots warmed up front.
+    pub access_list: Vec<AccessListItem>,
+}
+
+/// An [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844) transaction,
+/// type 3, carrying blobs. Only the versioned hashes of the blobs are part
+/// of the transaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Eip4844Transaction {
+    /// Chain id.
+    pub chain_id: u64,
+    /// Nonce of the sender.
+    pub nonce: u64,
+    /// Maximum priority fee per gas, in wei.
+    pub max_priority_fee_per_gas: u128,
+    /// Maximum fee per gas, in wei.
+    pub max_fee_per_gas: u128,
+    /// Gas limit.
+    pub gas_limit: u64,
+    /// Recipient, blob transactions cannot create contracts.
+    pub to: Address,
+    /// Value in wei.
+    pub value: u128,
+    /// Call data.
+    pub data: Vec<u8>,
+    /// Accounts and storage slots warmed up front.
+    pub access_list: Vec<AccessListItem>,
+    /// Maximum fee per blob gas, in wei.
+    pub max_fee_per_blob_gas: u128,
+    /// Versioned hashes of the KZG commitments of the blobs.
+    pub blob_versioned_hashes: Vec<B256>,
+}
+
+/// A transaction of any type of the [EIP-2718](https://eips.ethereum.org/EIPS/eip-2718)
+/// envelope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypedTransaction {
+    /// A legacy transaction, with or without EIP-155 replay protection.
+    Legacy(Transaction),
+    /// An EIP-2930 access list transaction.
+    Eip2930(Eip2930Transaction),
+    /// An EIP-1559 dynamic fee transaction.
+    Eip1559(Eip1559Transaction),
+    /// An EIP-4844 blob transaction.
+    Eip4844(Eip4844Transaction),
+}
+
+impl TypedTransaction {
+    /// Return the EIP-2718 type, `0` for legacy transactions.
+    pub fn tx_type(&self) -> u8 {
+        match self {
+            TypedTransaction::Legacy(_) => 0,
+            TypedTransaction::Eip2930(_) => 1,
+            TypedTransaction::Eip1559(_) => 2,
+            TypedTransaction::Eip4844(_) => 3,
+        }
+    }
+
+    /// Return the chain id, `None` for a legacy transaction valid on every
+    /// chain.
+    pub fn chain_id(&self) -> Option<u64> {
+        match self {
+            TypedTransaction::Legacy(tx) => tx.chain_id,
+            TypedTransaction::Eip2930(tx) => Some(tx.chain_id),
+            TypedTransaction::Eip1559(tx) => Some(tx.chain_id),
+            TypedTransaction::Eip4844(tx) => Some(tx.chain_id),
+        }
+    }
+
+    /// Return the nonce of the sender.
+    pub fn nonce(&self) -> u64 {
+        match self {
+            TypedTransaction::Legacy(tx) => tx.nonce,
+            TypedTransaction::Eip2930(tx) => tx.nonce,
+            TypedTransaction::Eip1559(tx) => tx.nonce,
+            TypedTransaction::Eip4844(tx) => tx.nonce,
+        }
+    }
+
+    /// Return the gas limit.
+    pub fn gas_limit(&self) -> u64 {
+        match self {
+            TypedTransaction::Legacy(tx) => tx.gas_limit,
+            TypedTransaction::Eip2930(tx) => tx.gas_limit,
+            TypedTransaction::Eip1559(tx) => tx.gas_limit,
+            TypedTransaction::Eip4844(tx) => tx.gas_limit,
+        }
+    }
+
+    /// Return the most the sender pays per gas: the gas price, or the
+    /// maximum fee of a dynamic fee transaction.
+    pub fn max_fee_per_gas(&self) -> u128 {
+        match self {
+            TypedTransaction::Legacy(tx) => tx.gas_price,
+            TypedTransaction::Eip2930(tx) => tx.gas_price,
+            TypedTransaction::Eip1559(tx) => tx.max_fee_per_gas,
+            TypedTransaction::Eip4844(tx) => tx.max_fee_per_gas,
+        }
+    }
+
+    /// Return the recipient, `None` for a contract creation.
+    pub fn to(&self) -> Option<&Address> {
+        match self {
+            TypedTransaction::Legacy(tx) => tx.to.as_ref(),
+            TypedTransaction::Eip2930(tx) => tx.to.as_ref(),
+            TypedTransaction::Eip1559(tx) => tx.to.as_ref(),
+            TypedTransaction::Eip4844(tx) => Some(&tx.to),
+        }
+    }
+
+    /// Return the value in wei.
+    pub fn value(&self) -> u128 {
+        match self {
+            TypedTransaction::Legacy(tx) => tx.value,
+            TypedTransaction::Eip2930(tx) => tx.value,
+            TypedTransaction::Eip1559(tx) => tx.value,
+            TypedTransaction::Eip4844(tx) => tx.value,
+        }
+    }
+
+    /// Return the call data.
+    pub fn data(&self) -> &[u8] {
+        match self {
+            TypedTransaction::Legacy(tx) => &tx.data,
+            TypedTransaction::Eip2930(tx) => &tx.data,
+            TypedTransaction::Eip1559(tx) => &tx.data,
+            TypedTransaction::Eip4844(tx) => &tx.data,
+        }
+    }
+
+    /// Return the access list, empty for legacy transactions.
+    pub fn access_list(&self) -> &[AccessListItem] {
+        match self {
+            TypedTransaction::Legacy(_) => &[],
+            TypedTransaction::Eip2930(tx) => &tx.access_list,
+            TypedTransaction::Eip1559(tx) => &tx.access_list,
+            TypedTransaction::Eip4844(tx) => &tx.access_list,
+        }
+    }
+
+    /// Return the hash signed by the sender, the Keccak-256 of
+    /// [`encode_unsigned`](Self::encode_unsigned).
+    pub fn signing_hash(&self) -> B256 {
+        match self {
+            TypedTransaction::Legacy(tx) => tx.signing_hash(),
+            _ => B256::new(keccak256(self.encode_unsigned())),
+        }
+    }
+
+    /// Return the payload signed by the sender. For typed transactions it
+    /// is the type followed by the RLP encoded fields.
+    pub fn encode_unsigned(&self) -> Vec<u8> {
+        match self {
+            TypedTransaction::Legacy(tx) => tx.encode_unsigned(),
+            _ => [&[self.tx_type()][..], &rlp_list(&self.fields())].concat(),
+        }
+    }
+
+    /// Recover the sender of the transaction signed with `signature`, see
+    /// [`Transaction::recover_sender`]. Typed transactions carry their chain
+    /// id, an EIP-155 `v` must agree with it.
+    pub fn recover_sender(&self, signature: &Signature) -> Result<Address> {
+        match (self, self.chain_id(), signature.chain_id()) {
+            (TypedTransaction::Legacy(tx), _, _) => tx.recover_sender(signature),
+            (_, Some(expected), Some(actual)) if expected != actual => {
+                Err(SignatureError::ChainIdMismatch { expected, actual }.into())
+            }
+            _ => signature.recover(&self.signing_hash()),
+        }
+    }
+
+    /// Return the raw transaction signed with `signature`. Typed
+    /// transactions store the y parity of the signature in place of `v`.
+    pub fn encode_signed(&self, signature: &Signature) -> Vec<u8> {
+        match self {
+            TypedTransaction::Legacy(tx) => tx.encode_signed(signature),
+            _ => {
+                let mut fields = self.fields();
+                fields.extend([
+                    rlp_uint(signature.recovery_id().unwrap_or(0) as u128),
+                    rlp_bytes(trim_zeros(signature.r())),
+                    rlp_bytes(trim_zeros(signature.s())),
+                ]);
+                [&[self.tx_type()][..], &rlp_list(&fields)].concat()
+            }
+        }
+    }
+
+    /// Return the RLP encoded fields of a typed transaction.
+    fn fields(&self) -> Vec<Vec<u8>> {
+        let to = |to: Option<&Address>| rlp_bytes(to.map_or(&[][..], |to| to.as_bytes()));
+        match self {
+            TypedTransaction::Legacy(_) => unreachable!("legacy transactions have no type"),
+            TypedTransaction::Eip2930(tx) => vec![
+                rlp_uint(tx.chain_id as u128),
+                rlp_uint(tx.nonce as u128),
+                rlp_uint(tx.gas_price),
+                rlp_uint(tx.gas_limit as u128),
+                to(tx.to.as_ref()),
+                rlp_uint(tx.value),
+                rlp_bytes(&tx.data),
+                rlp_access_list(&tx.access_list),
+            ],
+            TypedTransaction::Eip1559(tx) => vec![
+                rlp_uint(tx.chain_id as u128),
+                rlp_uint(tx.nonce as u128),
+                rlp_uint(tx.max_priority_fee_per_gas),
+                rlp_uint(tx.max_fee_per_gas),
+                rlp_uint(tx.gas_limit as u128),
+                to(tx.to.as_ref()),
+                rlp_uint(tx.value),
+                rlp_bytes(&tx.data),
+                rlp_access_list(&tx.access_list),
+            ],
+            TypedTransaction::Eip4844(tx) => {
+                let hashes = tx
+                    .blob_versioned_hashes
+                    .iter()
+                    .map(|hash| rlp_bytes(hash.as_ref()))
+                    .collect::<Vec<_>>();
+                vec![
+                    rlp_uint(tx.chain_id as u128),
+                    rlp_uint(tx.nonce as u128),
+                    rlp_uint(tx.max_priority_fee_per_gas),
+                    rlp_uint(tx.max_fee_per_gas),
+                    rlp_uint(tx.gas_limit as u128),
+                    to(Some(&tx.to)),
+                    rlp_uint(tx.value),
+                    rlp_bytes(&tx.data),
+                    rlp_access_list(&tx.access_list),
+                    rlp_uint(tx.max_fee_per_blob_gas),
+                    rlp_list(&hashes),
+                ]
+            }
+        }
+    }
+}
+
+impl From<Transaction> for TypedTransaction {
+    fn from(tx: Transaction) -> Self {
+        TypedTransaction::Legacy(tx)
+    }
+}
+
+impl From<Eip2930Transaction> for TypedTransaction {
+    fn from(tx: Eip2930Transaction) -> Self {
+        TypedTransaction::Eip2930(tx)
+    }
+}
+
+impl From<Eip1559Transaction> for TypedTransaction {
+    fn from(tx: Eip1559Transaction) -> Self {
+        TypedTransaction::Eip1559(tx)
+    }
+}
+
+impl From<Eip4844Transaction> for TypedTransaction {
+    fn from(tx: Eip4844Transaction) -> Self {
+        TypedTransaction::Eip4844(tx)
+    }
+}
+
+fn rlp_access_list(access_list: &[AccessListItem]) -> Vec<u8> {
+    let items = access_list
+        .iter()
+        .map(|item| {
+            let keys = item
+                .storage_keys
+                .iter()
+                .map(|key| rlp_bytes(key.as_ref()))
+                .collect::<Vec<_>>();
+            rlp_list(&[rlp_bytes(item.address.as_bytes()), rlp_list(&keys)])
+        })
+        .collect::<Vec<_>>();
+    rlp_list(&items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::Signer;
+    use laron_crypto::PrivateKey;
+
+    fn transactions() -> Vec<TypedTransaction> {
+        let to = Address::new([0x35; 20]);
+        let mut slot = [0u8; 32];
+        slot[31] = 1;
+        vec![
+            Eip2930Transaction {
+                chain_id: 5,
+                nonce: 9,
+                gas_price: 20_000_000_000,
+                gas_limit: 30_000,
+                to: Some(to),
+                value: 1_000_000_000_000_000_000,
+                data: vec![0xa9, 0x05, 0x9c, 0xbb],
+                access_list: vec![AccessListItem {
+                    address: Address::new([0xde; 20]),
+                    storage_keys: vec![B256::new(slot), B256::new([0x07; 32])],
+                }],
+            }
+            .into(),
+            Eip1559Transaction {
+                chain_id: 1,
+                nonce: 9,
+                max_priority_fee_per_gas: 2_000_000_000,
+                max_fee_per_gas: 30_000_000_000,
+                gas_limit: 21_000,
+                to: Some(to),
+                value: 1_000_000_000_000_000_000,
+                ..Default::default()
+            }
+            .into(),
+            Eip4844Transaction {
+                chain_id: 1,
+                nonce: 9,
+                max_priority_fee_per_gas: 2_000_000_000,
+                max_fee_per_gas: 30_000_000_000,
+                gas_limit: 21_000,
+                to,
+                max_fee_per_blob_gas: 1_000_000_000,
+                blob_versioned_hashes: vec![{
+                    let mut hash = [0xaa; 32];
+                    hash[0] = 1;
+                    B256::new(hash)
+                }],
+                ..Default::default()
+            }
+            .into(),
+        ]
+    }
+
+    #[test]
+    fn test_signing_hash() {
+        let hashes = transactions()
+            .iter()
+            .map(|tx| tx.signing_hash().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            hashes,
+            [
+                "0xe07047cd9489bad844802d6701a9c01009e0437322dc4e5a7c51d6c59ed4bf38",
+                "0xfae77debb64203fbaea6213fcde74f1b138c6854c3d7b44ba1c2ced52c2d8c4d",
+                "0xfa0502cc03ad23b8bf579026078a98fd0b87532ea5e99abc6d2451da6f3754f3",
+            ]
+        );
+
+        let legacy = TypedTransaction::from(Transaction {
+            chain_id: Some(1),
+            ..Default::default()
+        });
+        assert_eq!(legacy.tx_type(), 0);
+        assert_eq!(
+            legacy.signing_hash(),
+            Transaction {
+                chain_id: Some(1),
+                ..Default::default()
+            }
+            .signing_hash()
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        let key = PrivateKey::from_bytes(&[0x46; 32]).unwrap();
+        for tx in transactions() {
+            let signature = key.sign_hash(&tx.signing_hash()).unwrap();
+            let raw = tx.encode_signed(&signature);
+            assert_eq!(raw[0], tx.tx_type());
+
+            let decoded = Transaction::decode(&hex::encode(&raw)).unwrap();
+            assert_eq!(decoded.transaction, tx);
+            assert_eq!(decoded.signature, signature);
+            assert_eq!(decoded.from, key.address().unwrap());
+            assert_eq!(decoded.hash, B256::new(keccak256(&raw)));
+
+            let other_chain = signature.with_chain_id(99).unwrap();
+            assert!(tx.recover_sender(&other_chain).is_err());
+        }
+    }
+}
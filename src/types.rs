@@ -0,0 +1,363 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Primitive Types
+//!
+//! Fixed-size integer and hash types shared by the modules dealing with
+//! chain data. The integers and `H*` hashes are re-exported from
+//! [`primitive_types`] so applications do not have to depend on a matching
+//! version themselves. [`FixedBytes`] and its `B*` aliases are the byte
+//! strings this crate uses in its own API for transaction hashes, name
+//! hashes and chain codes.
+//!
+//! With the `serde` feature they serialize as `0x` prefixed hex strings, the
+//! encoding used by JSON-RPC.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::types::{B256, H256, U256};
+//!
+//! let value = U256::from_dec_str("1000000000000000000").unwrap();
+//! assert_eq!(value, U256::exp10(18));
+//! assert_eq!(format!("{:#x}", value), "0xde0b6b3a7640000");
+//!
+//! let hash: H256 = "0x0000000000000000000000000000000000000000000000000000000000000001"
+//!     .parse()
+//!     .unwrap();
+//! assert_eq!(hash.to_low_u64_be(), 1);
+//!
+//! let bytes: B256 = "0x0000000000000000000000000000000000000000000000000000000000000001"
+//!     .parse()
+//!     .unwrap();
+//! assert_eq!(H256::from(bytes), hash);
+//! assert_eq!(bytes[31], 1);
+//! ```
+
+pub use primitive_types::{H160, H256, H512, U128, U256, U512};
+
+use crate::error::{Result, WalletError};
+
+use crate::address::Address;
+
+/// Error returned when parsing fixed-size bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixedBytesError {
+    /// The input does not hold the expected number of bytes.
+    InvalidLength {
+        /// The length of the type.
+        expected: usize,
+        /// The length of the input.
+        actual: usize,
+    },
+}
+
+impl std::fmt::Display for FixedBytesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FixedBytesError::InvalidLength { expected, actual } => {
+                write!(f, "Expected {} bytes, found {}", expected, actual)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FixedBytesError {}
+
+/// A byte string of `N` bytes, displayed as `0x` prefixed lowercase hex.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FixedBytes<const N: usize>(pub [u8; N]);
+
+/// 20 bytes, the size of an address.
+pub type B160 = FixedBytes<20>;
+/// 32 bytes, the size of a hash, storage key or chain code.
+pub type B256 = FixedBytes<32>;
+/// 64 bytes, the size of an uncompressed public key without its prefix.
+pub type B512 = FixedBytes<64>;
+
+impl<const N: usize> FixedBytes<N> {
+    /// All bytes zero.
+    pub const ZERO: Self = Self([0; N]);
+
+    /// Create new fixed bytes from an array.
+    pub const fn new(bytes: [u8; N]) -> Self {
+        Self(bytes)
+    }
+
+    /// Create new fixed bytes from a slice, which must be `N` bytes long.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self> {
+        let bytes = bytes
+            .try_into()
+            .map_err(|_| FixedBytesError::InvalidLength {
+                expected: N,
+                actual: bytes.len(),
+            })?;
+        Ok(Self(bytes))
+    }
+
+    /// Return the bytes as an array.
+    pub const fn as_bytes(&self) -> &[u8; N] {
+        &self.0
+    }
+
+    /// Return whether all bytes are zero.
+    pub fn is_zero(&self) -> bool {
+        self.0.iter().all(|b| *b == 0)
+    }
+}
+
+impl<const N: usize> zeroize::Zeroize for FixedBytes<N> {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<const N: usize> Default for FixedBytes<N> {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl<const N: usize> std::ops::Deref for FixedBytes<N> {
+    type Target = [u8; N];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for FixedBytes<N> {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for FixedBytes<N> {
+    fn from(bytes: [u8; N]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl<const N: usize> From<FixedBytes<N>> for [u8; N] {
+    fn from(bytes: FixedBytes<N>) -> Self {
+        bytes.0
+    }
+}
+
+impl<const N: usize> PartialEq<[u8; N]> for FixedBytes<N> {
+    fn eq(&self, other: &[u8; N]) -> bool {
+        self.0 == *other
+    }
+}
+
+impl<const N: usize> std::fmt::LowerHex for FixedBytes<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if f.alternate() {
+            write!(f, "0x")?;
+        }
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+impl<const N: usize> std::fmt::Display for FixedBytes<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:#x}", self)
+    }
+}
+
+impl<const N: usize> std::fmt::Debug for FixedBytes<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:#x}", self)
+    }
+}
+
+impl<const N: usize> std::str::FromStr for FixedBytes<N> {
+    type Err = WalletError;
+
+    /// Parse hex with or without the `0x` prefix.
+    fn from_str(s: &str) -> Result<Self> {
+        Self::from_slice(&hex::decode(s.strip_prefix("0x").unwrap_or(s))?)
+    }
+}
+
+/// Implement serde for a type as its `Display` string, parsed back with
+/// `FromStr`.
+#[cfg(feature = "serde")]
+macro_rules! serde_string {
+    ($ty:ty) => {
+        impl serde::Serialize for $ty {
+            fn serialize<S: serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $ty {
+            fn deserialize<D: serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> std::result::Result<Self, D::Error> {
+                let s = <std::borrow::Cow<str>>::deserialize(deserializer)?;
+                s.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "serde")]
+pub(crate) use serde_string;
+
+#[cfg(feature = "serde")]
+impl<const N: usize> serde::Serialize for FixedBytes<N> {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, const N: usize> serde::Deserialize<'de> for FixedBytes<N> {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let s = <std::borrow::Cow<str>>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl From<B160> for H160 {
+    fn from(bytes: B160) -> Self {
+        H160(bytes.0)
+    }
+}
+
+impl From<H160> for B160 {
+    fn from(hash: H160) -> Self {
+        Self(hash.0)
+    }
+}
+
+impl From<B256> for H256 {
+    fn from(bytes: B256) -> Self {
+        H256(bytes.0)
+    }
+}
+
+impl From<H256> for B256 {
+    fn from(hash: H256) -> Self {
+        Self(hash.0)
+    }
+}
+
+impl From<B512> for H512 {
+    fn from(bytes: B512) -> Self {
+        H512(bytes.0)
+    }
+}
+
+impl From<H512> for B512 {
+    fn from(hash: H512) -> Self {
+        Self(hash.0)
+    }
+}
+
+impl From<Address> for B160 {
+    fn from(address: Address) -> Self {
+        Self(*address.as_bytes())
+    }
+}
+
+impl From<B160> for Address {
+    fn from(bytes: B160) -> Self {
+        Address::new(bytes.0)
+    }
+}
+
+impl From<Address> for H160 {
+    fn from(address: Address) -> Self {
+        H160(*address.as_bytes())
+    }
+}
+
+impl From<H160> for Address {
+    fn from(hash: H160) -> Self {
+        Address::new(hash.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_address_conversion() {
+        let address: Address = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+            .parse()
+            .unwrap();
+        let hash = H160::from(address);
+        assert_eq!(
+            format!("{:?}", hash),
+            "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"
+        );
+        assert_eq!(Address::from(hash), address);
+    }
+
+    #[test]
+    fn test_fixed_bytes() {
+        let hash: B256 = "0xabababababababababababababababababababababababababababababababab"
+            .parse()
+            .unwrap();
+        assert_eq!(hash, [0xab; 32]);
+        assert_eq!(hash.to_string(), format!("0x{}", "ab".repeat(32)));
+        assert_eq!(format!("{:x}", hash), "ab".repeat(32));
+        assert_eq!(hash.to_string().parse::<B256>().unwrap(), hash);
+        assert_eq!("ab".repeat(32).parse::<B256>().unwrap(), hash);
+        assert_eq!(B256::from(H256::from(hash)), hash);
+
+        assert!(B256::default().is_zero());
+        assert!("0xabab".parse::<B256>().is_err());
+        assert!(B160::from_slice(&[0; 32]).is_err());
+
+        let address: Address = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+            .parse()
+            .unwrap();
+        assert_eq!(Address::from(B160::from(address)), address);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let a = U256::from_str_radix("ffffffffffffffffffffffffffffffff", 16).unwrap();
+        assert_eq!(a + 1, U256::from(1) << 128);
+        assert_eq!(U256::MAX.checked_add(U256::one()), None);
+        assert_eq!(U256::from(7) / 2, U256::from(3));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let value = U256::from(1_000_000_000u64);
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, "\"0x3b9aca00\"");
+        assert_eq!(serde_json::from_str::<U256>(&json).unwrap(), value);
+
+        let hash = H256::repeat_byte(0xab);
+        let json = serde_json::to_string(&hash).unwrap();
+        assert_eq!(serde_json::from_str::<H256>(&json).unwrap(), hash);
+
+        let bytes = B256::from(hash);
+        assert_eq!(serde_json::to_string(&bytes).unwrap(), json);
+        assert_eq!(serde_json::from_str::<B256>(&json).unwrap(), bytes);
+    }
+}
@@ -0,0 +1,309 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Units
+//!
+//! Lossless conversions between decimal strings and integer amounts of the
+//! smallest unit, for ether (18 decimals) and tokens of any precision.
+//! Amounts are never converted through floating point numbers.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::units::{format_units, parse_ether, Unit};
+//!
+//! let wei = parse_ether("1.5").unwrap();
+//! assert_eq!(wei, 1_500_000_000_000_000_000);
+//! assert_eq!(Unit::Gwei.format(wei), "1500000000.0");
+//!
+//! // 6 decimals, like USDC
+//! assert_eq!(format_units(1_234_500, 6), "1.2345");
+//! ```
+
+use crate::error::{Result, WalletError};
+
+/// Error returned when parsing an amount.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnitsError {
+    /// The string is not a decimal number.
+    InvalidNumber(String),
+    /// The number has more fractional digits than the unit allows.
+    TooManyDecimals(u32),
+    /// The amount does not fit in 128 bits.
+    Overflow,
+    /// The unit name is unknown.
+    UnknownUnit(String),
+}
+
+impl std::fmt::Display for UnitsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            UnitsError::InvalidNumber(s) => write!(f, "Invalid number: {}", s),
+            UnitsError::TooManyDecimals(decimals) => {
+                write!(f, "Too many decimals, at most {} allowed", decimals)
+            }
+            UnitsError::Overflow => write!(f, "Amount overflow"),
+            UnitsError::UnknownUnit(s) => write!(f, "Unknown unit: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for UnitsError {}
+
+/// The named denominations of ether.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Unit {
+    /// The smallest unit.
+    Wei,
+    /// 10^3 wei.
+    Kwei,
+    /// 10^6 wei.
+    Mwei,
+    /// 10^9 wei, the unit of gas prices.
+    Gwei,
+    /// 10^12 wei.
+    Szabo,
+    /// 10^15 wei.
+    Finney,
+    /// 10^18 wei.
+    Ether,
+}
+
+impl Unit {
+    /// Return the number of decimals of the unit relative to wei.
+    pub fn decimals(&self) -> u32 {
+        match self {
+            Unit::Wei => 0,
+            Unit::Kwei => 3,
+            Unit::Mwei => 6,
+            Unit::Gwei => 9,
+            Unit::Szabo => 12,
+            Unit::Finney => 15,
+            Unit::Ether => 18,
+        }
+    }
+
+    /// Parse an amount of this unit into wei.
+    pub fn parse(&self, value: &str) -> Result<u128> {
+        parse_units(value, self.decimals())
+    }
+
+    /// Format an amount of wei in this unit.
+    pub fn format(&self, wei: u128) -> String {
+        format_units(wei, self.decimals())
+    }
+}
+
+impl std::fmt::Display for Unit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            Unit::Wei => "wei",
+            Unit::Kwei => "kwei",
+            Unit::Mwei => "mwei",
+            Unit::Gwei => "gwei",
+            Unit::Szabo => "szabo",
+            Unit::Finney => "finney",
+            Unit::Ether => "ether",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl std::str::FromStr for Unit {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "wei" => Ok(Unit::Wei),
+            "kwei" => Ok(Unit::Kwei),
+            "mwei" => Ok(Unit::Mwei),
+            "gwei" => Ok(Unit::Gwei),
+            "szabo" => Ok(Unit::Szabo),
+            "finney" => Ok(Unit::Finney),
+            "ether" | "eth" => Ok(Unit::Ether),
+            _ => Err(UnitsError::UnknownUnit(s.to_string()).into()),
+        }
+    }
+}
+
+/// Parse a decimal string into an integer amount with the given number of
+/// decimals, e.g. `"1.5"` with 6 decimals is `1_500_000`.
+pub fn parse_units(value: &str, decimals: u32) -> Result<u128> {
+    let invalid = || UnitsError::InvalidNumber(value.to_string());
+
+    let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if whole.is_empty() && fraction.is_empty()
+        || !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid().into());
+    }
+
+    // trailing zeros do not change the amount
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > decimals as usize {
+        return Err(UnitsError::TooManyDecimals(decimals).into());
+    }
+
+    let digits = format!("{}{:0<width$}", whole, fraction, width = decimals as usize);
+    digits
+        .bytes()
+        .try_fold(0u128, |acc, b| {
+            acc.checked_mul(10)?.checked_add((b - b'0') as u128)
+        })
+        .ok_or_else(|| UnitsError::Overflow.into())
+}
+
+/// Format an integer amount with the given number of decimals. The result
+/// always has a fractional part and no trailing zeros beyond it, e.g.
+/// `1_500_000` with 6 decimals is `"1.5"` and `1_000_000` is `"1.0"`.
+pub fn format_units(value: u128, decimals: u32) -> String {
+    let digits = format!("{:0>width$}", value, width = decimals as usize + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals as usize);
+
+    let fraction = fraction.trim_end_matches('0');
+    let fraction = if fraction.is_empty() { "0" } else { fraction };
+    format!("{}.{}", whole, fraction)
+}
+
+/// An integer amount of the smallest unit of a currency with its number of
+/// decimals, displayed as a decimal string.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Amount {
+    value: u128,
+    decimals: u32,
+}
+
+impl Amount {
+    /// Create an amount of `value` smallest units with `decimals` decimals.
+    pub const fn new(value: u128, decimals: u32) -> Self {
+        Self { value, decimals }
+    }
+
+    /// Create an amount of ether from wei.
+    pub const fn wei(wei: u128) -> Self {
+        Self::new(wei, 18)
+    }
+
+    /// Parse a decimal string with `decimals` decimals.
+    pub fn parse(value: &str, decimals: u32) -> Result<Self> {
+        Ok(Self::new(parse_units(value, decimals)?, decimals))
+    }
+
+    /// Parse an amount of ether.
+    pub fn parse_ether(value: &str) -> Result<Self> {
+        Self::parse(value, 18)
+    }
+
+    /// Return the amount in the smallest unit.
+    pub fn value(&self) -> u128 {
+        self.value
+    }
+
+    /// Return the number of decimals.
+    pub fn decimals(&self) -> u32 {
+        self.decimals
+    }
+}
+
+impl From<Amount> for u128 {
+    fn from(amount: Amount) -> Self {
+        amount.value
+    }
+}
+
+impl std::fmt::Display for Amount {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", format_units(self.value, self.decimals))
+    }
+}
+
+/// Parse an amount of ether into wei.
+pub fn parse_ether(value: &str) -> Result<u128> {
+    Unit::Ether.parse(value)
+}
+
+/// Format an amount of wei in ether.
+pub fn format_ether(wei: u128) -> String {
+    Unit::Ether.format(wei)
+}
+
+/// Parse an amount of gwei into wei.
+pub fn parse_gwei(value: &str) -> Result<u128> {
+    Unit::Gwei.parse(value)
+}
+
+/// Format an amount of wei in gwei.
+pub fn format_gwei(wei: u128) -> String {
+    Unit::Gwei.format(wei)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_units() {
+        assert_eq!(parse_ether("1").unwrap(), 1_000_000_000_000_000_000);
+        assert_eq!(parse_ether("0.000000000000000001").unwrap(), 1);
+        assert_eq!(parse_ether(".5").unwrap(), 500_000_000_000_000_000);
+        assert_eq!(parse_ether("2.").unwrap(), 2_000_000_000_000_000_000);
+        assert_eq!(parse_gwei("1.50").unwrap(), 1_500_000_000);
+        assert_eq!(parse_units("1.230000", 2).unwrap(), 123);
+        assert_eq!(parse_units("42", 0).unwrap(), 42);
+
+        assert!(parse_ether("0.0000000000000000001").is_err());
+        assert!(parse_ether("").is_err());
+        assert!(parse_ether(".").is_err());
+        assert!(parse_ether("-1").is_err());
+        assert!(parse_ether("1e18").is_err());
+        assert!(parse_ether("1.2.3").is_err());
+        assert!(parse_ether("340282366920938463464").is_err());
+    }
+
+    #[test]
+    fn test_format_units() {
+        assert_eq!(format_ether(1_000_000_000_000_000_000), "1.0");
+        assert_eq!(format_ether(1), "0.000000000000000001");
+        assert_eq!(format_ether(0), "0.0");
+        assert_eq!(format_gwei(21_000_500_000_000), "21000.5");
+        assert_eq!(format_units(42, 0), "42.0");
+        assert_eq!(
+            format_units(u128::MAX, 18),
+            "340282366920938463463.374607431768211455"
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        for value in [0, 1, 999, 1_000_000_000, u128::MAX] {
+            for unit in [Unit::Wei, Unit::Gwei, Unit::Ether] {
+                assert_eq!(unit.parse(&unit.format(value)).unwrap(), value);
+            }
+        }
+        assert_eq!("GWEI".parse::<Unit>().unwrap(), Unit::Gwei);
+    }
+
+    #[test]
+    fn test_amount() {
+        let amount = Amount::parse_ether("1.5").unwrap();
+        assert_eq!(amount, Amount::wei(1_500_000_000_000_000_000));
+        assert_eq!(amount.to_string(), "1.5");
+
+        let usdc = Amount::parse("1.2345", 6).unwrap();
+        assert_eq!((usdc.value(), usdc.decimals()), (1_234_500, 6));
+        assert_eq!(u128::from(usdc), 1_234_500);
+        assert!(Amount::parse("1.2345678", 6).is_err());
+    }
+}
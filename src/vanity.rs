@@ -0,0 +1,467 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Vanity Addresses
+//!
+//! Search for keys whose address matches a [`Pattern`], a prefix, a suffix
+//! or, with the `regex` feature, a regular expression. The search runs on
+//! every core and either draws raw private keys, or mnemonics whose first
+//! addresses are checked so the result can be restored from a phrase.
+//!
+//! Each hex character of a pattern divides the odds by 16, see
+//! [`Pattern::difficulty`]; patterns of more than 6 or 7 characters take
+//! hours on a desktop.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::vanity::{Pattern, Search};
+//!
+//! let pattern = Pattern::prefix("00").unwrap();
+//! let found = Search::new(pattern).run(|progress| eprintln!("{:.0} keys/s", progress.rate())).unwrap();
+//! assert!(hex::encode(found.address.as_bytes()).starts_with("00"));
+//! ```
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use laron_crypto::PrivateKey;
+use rand::Rng;
+use zeroize::Zeroizing;
+
+use crate::{
+    address::Address,
+    bips::{
+        bip32::ExtendedKey,
+        bip39::{Mnemonic, MnemonicType},
+        wordlists::Language,
+        ChildNumber, DerivationPath,
+    },
+    error::Result,
+};
+
+/// Error returned when creating a pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VanityError {
+    /// The prefix or suffix is not hex, or longer than an address.
+    InvalidPattern(String),
+    /// The regular expression does not compile.
+    InvalidRegex(String),
+}
+
+impl std::fmt::Display for VanityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            VanityError::InvalidPattern(pattern) => {
+                write!(f, "Invalid vanity pattern: {}", pattern)
+            }
+            VanityError::InvalidRegex(e) => write!(f, "Invalid vanity regex: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for VanityError {}
+
+/// What an address must look like.
+///
+/// Prefixes and suffixes in lowercase match any case. With an uppercase
+/// letter, they are matched against the EIP-55 checksummed address, which
+/// takes about twice as many attempts per letter.
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    /// The address starts with the hex characters, after `0x`.
+    Prefix(String),
+    /// The address ends with the hex characters.
+    Suffix(String),
+    /// Both a prefix and a suffix.
+    PrefixSuffix(String, String),
+    /// The checksummed address without `0x` matches the expression.
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+impl Pattern {
+    /// Create a pattern matching a prefix, with or without `0x`.
+    pub fn prefix(prefix: &str) -> Result<Self> {
+        Ok(Pattern::Prefix(Self::hex(prefix)?))
+    }
+
+    /// Create a pattern matching a suffix.
+    pub fn suffix(suffix: &str) -> Result<Self> {
+        Ok(Pattern::Suffix(Self::hex(suffix)?))
+    }
+
+    /// Create a pattern matching both a prefix and a suffix.
+    pub fn prefix_suffix(prefix: &str, suffix: &str) -> Result<Self> {
+        let (prefix, suffix) = (Self::hex(prefix)?, Self::hex(suffix)?);
+        if prefix.len() + suffix.len() > 40 {
+            return Err(VanityError::InvalidPattern(format!("{}..{}", prefix, suffix)).into());
+        }
+        Ok(Pattern::PrefixSuffix(prefix, suffix))
+    }
+
+    /// Create a pattern matching a regular expression.
+    #[cfg(feature = "regex")]
+    pub fn regex(regex: &str) -> Result<Self> {
+        regex::Regex::new(regex)
+            .map(Pattern::Regex)
+            .map_err(|e| VanityError::InvalidRegex(e.to_string()).into())
+    }
+
+    fn hex(pattern: &str) -> Result<String> {
+        let hex = pattern.strip_prefix("0x").unwrap_or(pattern);
+        if hex.len() > 40 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(VanityError::InvalidPattern(pattern.to_string()).into());
+        }
+        Ok(hex.to_string())
+    }
+
+    /// Return whether the address matches.
+    pub fn matches(&self, address: &Address) -> bool {
+        // the checksum costs a hash, only compute it when the case matters
+        let hex = match self.is_case_sensitive() {
+            true => address.to_checksum().split_off(2),
+            false => hex::encode(address.as_bytes()),
+        };
+        let matches = |part: &str, found: &str| match is_case_sensitive(part) {
+            true => part == found,
+            false => part.eq_ignore_ascii_case(found),
+        };
+
+        match self {
+            Pattern::Prefix(prefix) => matches(prefix, &hex[..prefix.len()]),
+            Pattern::Suffix(suffix) => matches(suffix, &hex[40 - suffix.len()..]),
+            Pattern::PrefixSuffix(prefix, suffix) => {
+                matches(prefix, &hex[..prefix.len()]) && matches(suffix, &hex[40 - suffix.len()..])
+            }
+            #[cfg(feature = "regex")]
+            Pattern::Regex(regex) => regex.is_match(&hex),
+        }
+    }
+
+    fn is_case_sensitive(&self) -> bool {
+        match self {
+            Pattern::Prefix(part) | Pattern::Suffix(part) => is_case_sensitive(part),
+            Pattern::PrefixSuffix(prefix, suffix) => {
+                is_case_sensitive(prefix) || is_case_sensitive(suffix)
+            }
+            #[cfg(feature = "regex")]
+            Pattern::Regex(_) => true,
+        }
+    }
+
+    /// Return the expected number of attempts to find a match, `None` for a
+    /// regular expression.
+    pub fn difficulty(&self) -> Option<f64> {
+        let odds = |part: &str| {
+            let case_sensitive = is_case_sensitive(part);
+            part.bytes()
+                .map(|b| match case_sensitive && b.is_ascii_alphabetic() {
+                    true => 32.0,
+                    false => 16.0,
+                })
+                .product::<f64>()
+        };
+
+        match self {
+            Pattern::Prefix(part) | Pattern::Suffix(part) => Some(odds(part)),
+            Pattern::PrefixSuffix(prefix, suffix) => Some(odds(prefix) * odds(suffix)),
+            #[cfg(feature = "regex")]
+            Pattern::Regex(_) => None,
+        }
+    }
+}
+
+fn is_case_sensitive(part: &str) -> bool {
+    part.bytes().any(|b| b.is_ascii_uppercase())
+}
+
+/// The key that produced a matching address.
+pub enum VanityKey {
+    /// A random private key.
+    PrivateKey(PrivateKey),
+    /// A mnemonic, without passphrase, and the path of the address.
+    Mnemonic {
+        /// The mnemonic.
+        mnemonic: Mnemonic,
+        /// The derivation path of the address.
+        path: DerivationPath,
+    },
+}
+
+impl std::fmt::Debug for VanityKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            VanityKey::PrivateKey(_) => write!(f, "PrivateKey(<redacted>)"),
+            VanityKey::Mnemonic { path, .. } => f
+                .debug_struct("Mnemonic")
+                .field("mnemonic", &"<redacted>")
+                .field("path", path)
+                .finish(),
+        }
+    }
+}
+
+/// A matching address and its key.
+#[derive(Debug)]
+pub struct Found {
+    /// The matching address.
+    pub address: Address,
+    /// The key of the address.
+    pub key: VanityKey,
+    /// Progress of the search when the address was found.
+    pub progress: Progress,
+}
+
+/// Number of addresses checked so far.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    /// Addresses checked.
+    pub attempts: u64,
+    /// Time since the search started.
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// Return the number of addresses checked per second.
+    pub fn rate(&self) -> f64 {
+        self.attempts as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// A vanity address search.
+#[derive(Debug, Clone)]
+pub struct Search {
+    pattern: Pattern,
+    threads: usize,
+    mnemonic: Option<(MnemonicType, u32)>,
+    interval: Duration,
+}
+
+impl Search {
+    /// Create a search of random private keys on every core.
+    pub fn new(pattern: Pattern) -> Self {
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        Self {
+            pattern,
+            threads,
+            mnemonic: None,
+            interval: Duration::from_secs(1),
+        }
+    }
+
+    /// Set the number of threads.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Search random English mnemonics instead, checking the first
+    /// `addresses` addresses of `m/44'/60'/0'/0` of each. Every mnemonic
+    /// costs a PBKDF2 seed derivation, so checking more addresses per
+    /// mnemonic is faster.
+    pub fn mnemonic(mut self, ty: MnemonicType, addresses: u32) -> Self {
+        self.mnemonic = Some((ty, addresses.max(1)));
+        self
+    }
+
+    /// Set how often the progress callback is called, every second by
+    /// default.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Run the search until an address matches, calling `on_progress`
+    /// periodically from the calling thread.
+    pub fn run(&self, on_progress: impl Fn(Progress)) -> Result<Found> {
+        let start = Instant::now();
+        let attempts = AtomicU64::new(0);
+        let done = AtomicBool::new(false);
+        let result = Mutex::new(None);
+        let caller = std::thread::current();
+
+        std::thread::scope(|scope| {
+            for _ in 0..self.threads {
+                scope.spawn(|| {
+                    let found = match self.mnemonic {
+                        Some((ty, addresses)) => {
+                            self.search_mnemonics(ty, addresses, &attempts, &done)
+                        }
+                        None => self.search_keys(&attempts, &done),
+                    };
+                    if let Some(found) = found.transpose() {
+                        done.store(true, Ordering::Relaxed);
+                        let mut result = result.lock().unwrap_or_else(|e| e.into_inner());
+                        result.get_or_insert(found);
+                        caller.unpark();
+                    }
+                });
+            }
+
+            while !done.load(Ordering::Relaxed) {
+                std::thread::park_timeout(self.interval);
+                if !done.load(Ordering::Relaxed) {
+                    on_progress(Progress {
+                        attempts: attempts.load(Ordering::Relaxed),
+                        elapsed: start.elapsed(),
+                    });
+                }
+            }
+        });
+
+        let progress = Progress {
+            attempts: attempts.load(Ordering::Relaxed),
+            elapsed: start.elapsed(),
+        };
+        let (address, key) = result
+            .into_inner()
+            .unwrap_or_else(|e| e.into_inner())
+            .expect("a thread stops the search with a result")?;
+        Ok(Found {
+            address,
+            key,
+            progress,
+        })
+    }
+
+    fn search_keys(
+        &self,
+        attempts: &AtomicU64,
+        done: &AtomicBool,
+    ) -> Result<Option<(Address, VanityKey)>> {
+        let mut rng = rand::thread_rng();
+        while !done.load(Ordering::Relaxed) {
+            let bytes = Zeroizing::new(rng.gen::<[u8; 32]>());
+            // out of range keys are astronomically rare, draw again
+            let Ok(key) = PrivateKey::from_bytes(&*bytes) else {
+                continue;
+            };
+            let address = Address::from_public_key(&key.public_key())?;
+            attempts.fetch_add(1, Ordering::Relaxed);
+            if self.pattern.matches(&address) {
+                return Ok(Some((address, VanityKey::PrivateKey(key))));
+            }
+        }
+        Ok(None)
+    }
+
+    fn search_mnemonics(
+        &self,
+        ty: MnemonicType,
+        addresses: u32,
+        attempts: &AtomicU64,
+        done: &AtomicBool,
+    ) -> Result<Option<(Address, VanityKey)>> {
+        let account = DerivationPath::parse("m/44'/60'/0'/0")?;
+        while !done.load(Ordering::Relaxed) {
+            let mnemonic = Mnemonic::new(ty, Language::English);
+            let key = ExtendedKey::new_master(&mnemonic.to_seed(""))?.derive_path(&account)?;
+            for i in 0..addresses {
+                let child = ChildNumber::try_normal(i)?;
+                let address = Address::from_public_key(key.derive_child(child)?.public_key())?;
+                attempts.fetch_add(1, Ordering::Relaxed);
+                if self.pattern.matches(&address) {
+                    let path = DerivationPath::parse(&format!("{}/{}", account, i))?;
+                    return Ok(Some((address, VanityKey::Mnemonic { mnemonic, path })));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pattern() {
+        let address: Address = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+            .parse()
+            .unwrap();
+        assert!(Pattern::prefix("0x5aae").unwrap().matches(&address));
+        assert!(Pattern::prefix("5aAe").unwrap().matches(&address));
+        assert!(!Pattern::prefix("5AAe").unwrap().matches(&address));
+        assert!(Pattern::suffix("beaed").unwrap().matches(&address));
+        assert!(Pattern::prefix_suffix("5a", "Aed")
+            .unwrap()
+            .matches(&address));
+        assert!(!Pattern::prefix_suffix("5a", "AED")
+            .unwrap()
+            .matches(&address));
+
+        assert!(Pattern::prefix("xyz").is_err());
+        assert!(Pattern::suffix(&"0".repeat(41)).is_err());
+        assert!(Pattern::prefix_suffix(&"0".repeat(30), &"0".repeat(11)).is_err());
+
+        assert_eq!(Pattern::prefix("dead").unwrap().difficulty(), Some(65536.0));
+        // every letter of a checksummed pattern also has to match its case
+        assert_eq!(
+            Pattern::prefix("dEa0").unwrap().difficulty(),
+            Some(8.0 * 65536.0)
+        );
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_regex() {
+        let address: Address = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+            .parse()
+            .unwrap();
+        assert!(Pattern::regex("^5aAe.*Aed$").unwrap().matches(&address));
+        assert!(Pattern::regex("(").is_err());
+    }
+
+    #[test]
+    fn test_search() {
+        let found = Search::new(Pattern::prefix("0").unwrap())
+            .threads(2)
+            .run(|_| {})
+            .unwrap();
+        assert!(found.progress.attempts >= 1);
+        match found.key {
+            VanityKey::PrivateKey(key) => {
+                assert_eq!(
+                    Address::from_public_key(&key.public_key()).unwrap(),
+                    found.address
+                )
+            }
+            _ => unreachable!(),
+        }
+
+        let found = Search::new(Pattern::suffix("a").unwrap())
+            .threads(1)
+            .mnemonic(MnemonicType::Words12, 32)
+            .run(|_| {})
+            .unwrap();
+        assert!(found.address.to_checksum().to_lowercase().ends_with('a'));
+        match found.key {
+            VanityKey::Mnemonic { mnemonic, path } => {
+                let key = ExtendedKey::new_master(&mnemonic.to_seed(""))
+                    .unwrap()
+                    .derive_path(&path)
+                    .unwrap();
+                assert_eq!(
+                    Address::from_public_key(key.public_key()).unwrap(),
+                    found.address
+                );
+            }
+            _ => unreachable!(),
+        }
+    }
+}
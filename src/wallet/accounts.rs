@@ -0,0 +1,397 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::{json, Map, Value};
+
+use super::{Account, Wallet};
+use crate::{address::Address, error::Result};
+
+/// Version of the state file written by [`AccountManager::to_json`].
+const VERSION: u64 = 1;
+
+/// Error returned when reading the state of an [`AccountManager`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccountsError {
+    /// The JSON is not an account state, the malformed field is given.
+    InvalidFormat(&'static str),
+    /// The version of the state file is not supported.
+    UnsupportedVersion(u64),
+    /// No account with this index was created.
+    UnknownAccount(u32),
+    /// The address recorded for the account differs from the one of the
+    /// wallet, the state belongs to another wallet or passphrase.
+    WalletMismatch(u32),
+}
+
+impl std::fmt::Display for AccountsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AccountsError::InvalidFormat(field) => write!(f, "Invalid account state: {}", field),
+            AccountsError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported account state version: {}", version)
+            }
+            AccountsError::UnknownAccount(index) => write!(f, "Unknown account {}", index),
+            AccountsError::WalletMismatch(index) => {
+                write!(f, "Account {} does not belong to this wallet", index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AccountsError {}
+
+/// What an [`AccountManager`] records about an account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountMetadata {
+    /// Index of the account.
+    pub index: u32,
+    /// Name given by the user.
+    pub label: String,
+    /// Creation time, in seconds since the Unix epoch.
+    pub created_at: u64,
+    /// First receive address of the account.
+    pub address: Address,
+    /// Last receive index handed out, by chain id.
+    pub last_used: BTreeMap<u64, u32>,
+    /// Names of receive addresses, by index.
+    pub address_labels: BTreeMap<u32, String>,
+}
+
+impl AccountMetadata {
+    fn to_json(&self) -> Value {
+        let last_used = self
+            .last_used
+            .iter()
+            .map(|(chain_id, index)| (chain_id.to_string(), json!(index)))
+            .collect::<Map<_, _>>();
+        let address_labels = self
+            .address_labels
+            .iter()
+            .map(|(index, label)| (index.to_string(), json!(label)))
+            .collect::<Map<_, _>>();
+
+        json!({
+            "index": self.index,
+            "label": self.label,
+            "created_at": self.created_at,
+            "address": self.address.to_string(),
+            "last_used": last_used,
+            "address_labels": address_labels,
+        })
+    }
+
+    fn from_json(value: &Value) -> Result<Self> {
+        let index = value["index"]
+            .as_u64()
+            .and_then(|index| u32::try_from(index).ok())
+            .ok_or(AccountsError::InvalidFormat("index"))?;
+        let label = value["label"]
+            .as_str()
+            .ok_or(AccountsError::InvalidFormat("label"))?;
+        let created_at = value["created_at"]
+            .as_u64()
+            .ok_or(AccountsError::InvalidFormat("created_at"))?;
+        let address = value["address"]
+            .as_str()
+            .and_then(|address| address.parse().ok())
+            .ok_or(AccountsError::InvalidFormat("address"))?;
+
+        let last_used = parse_map(&value["last_used"], "last_used", |index| {
+            index.as_u64().and_then(|index| u32::try_from(index).ok())
+        })?;
+        let address_labels = parse_map(&value["address_labels"], "address_labels", |label| {
+            label.as_str().map(str::to_string)
+        })?;
+
+        Ok(Self {
+            index,
+            label: label.to_string(),
+            created_at,
+            address,
+            last_used,
+            address_labels,
+        })
+    }
+}
+
+/// Parse a JSON object whose keys are numbers.
+fn parse_map<K: std::str::FromStr + Ord, V>(
+    value: &Value,
+    field: &'static str,
+    parse: impl Fn(&Value) -> Option<V>,
+) -> Result<BTreeMap<K, V>> {
+    let map = value
+        .as_object()
+        .ok_or(AccountsError::InvalidFormat(field))?;
+    map.iter()
+        .map(|(key, value)| match (key.parse(), parse(value)) {
+            (Ok(key), Some(value)) => Ok((key, value)),
+            _ => Err(AccountsError::InvalidFormat(field).into()),
+        })
+        .collect()
+}
+
+/// Bookkeeping of the accounts of a [`Wallet`]: labels, creation times
+/// and the receive addresses handed out on each chain.
+///
+/// The state holds no secret, it is saved as JSON next to the wallet and
+/// checked against it when loaded.
+#[derive(Debug)]
+pub struct AccountManager {
+    wallet: Wallet,
+    accounts: BTreeMap<u32, AccountMetadata>,
+}
+
+impl AccountManager {
+    /// Manage the accounts of a wallet, none of which is created yet.
+    pub fn new(wallet: Wallet) -> Self {
+        Self {
+            wallet,
+            accounts: BTreeMap::new(),
+        }
+    }
+
+    /// Manage the accounts of a wallet as recorded in a state written by
+    /// [`AccountManager::to_json`].
+    pub fn from_json(wallet: Wallet, json: &str) -> Result<Self> {
+        Self::from_value(wallet, &serde_json::from_str(json)?)
+    }
+
+    pub(super) fn from_value(wallet: Wallet, root: &Value) -> Result<Self> {
+        let version = root["version"]
+            .as_u64()
+            .ok_or(AccountsError::InvalidFormat("version"))?;
+        if version != VERSION {
+            return Err(AccountsError::UnsupportedVersion(version).into());
+        }
+
+        let mut accounts = BTreeMap::new();
+        for value in root["accounts"]
+            .as_array()
+            .ok_or(AccountsError::InvalidFormat("accounts"))?
+        {
+            let metadata = AccountMetadata::from_json(value)?;
+            if wallet.account(metadata.index)?.address() != metadata.address {
+                return Err(AccountsError::WalletMismatch(metadata.index).into());
+            }
+            accounts.insert(metadata.index, metadata);
+        }
+
+        Ok(Self { wallet, accounts })
+    }
+
+    /// Serialize the state of the accounts.
+    pub fn to_json(&self) -> String {
+        self.to_value().to_string()
+    }
+
+    pub(super) fn to_value(&self) -> Value {
+        let accounts = self
+            .accounts
+            .values()
+            .map(AccountMetadata::to_json)
+            .collect::<Vec<_>>();
+        json!({ "version": VERSION, "accounts": accounts })
+    }
+
+    /// Read the state from a file.
+    pub fn load(wallet: Wallet, path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_json(wallet, &std::fs::read_to_string(path)?)
+    }
+
+    /// Write the state to a file. It is written to a temporary file first,
+    /// so a crash cannot leave a truncated state behind.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        std::fs::write(&temporary, self.to_json())?;
+        std::fs::rename(&temporary, path)?;
+        Ok(())
+    }
+
+    /// Return the managed wallet.
+    pub fn wallet(&self) -> &Wallet {
+        &self.wallet
+    }
+
+    /// Create the account following the last one.
+    pub fn create_account(&mut self, label: impl Into<String>) -> Result<Account> {
+        let index = self
+            .accounts
+            .keys()
+            .next_back()
+            .map_or(0, |index| index + 1);
+        let account = self.wallet.account(index)?;
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+
+        self.accounts.insert(
+            index,
+            AccountMetadata {
+                index,
+                label: label.into(),
+                created_at,
+                address: account.address(),
+                last_used: BTreeMap::new(),
+                address_labels: BTreeMap::new(),
+            },
+        );
+        Ok(account)
+    }
+
+    /// Return the created account at `index`.
+    pub fn account(&self, index: u32) -> Result<Account> {
+        self.metadata(index)?;
+        self.wallet.account(index)
+    }
+
+    /// Return what is recorded about the account at `index`.
+    pub fn metadata(&self, index: u32) -> Result<&AccountMetadata> {
+        Ok(self
+            .accounts
+            .get(&index)
+            .ok_or(AccountsError::UnknownAccount(index))?)
+    }
+
+    /// Return what is recorded about every account, by index.
+    pub fn accounts(&self) -> impl Iterator<Item = &AccountMetadata> {
+        self.accounts.values()
+    }
+
+    /// Rename an account.
+    pub fn set_label(&mut self, index: u32, label: impl Into<String>) -> Result<()> {
+        self.metadata_mut(index)?.label = label.into();
+        Ok(())
+    }
+
+    /// Name the receive address at `address_index` of an account.
+    pub fn set_address_label(
+        &mut self,
+        index: u32,
+        address_index: u32,
+        label: impl Into<String>,
+    ) -> Result<()> {
+        self.metadata_mut(index)?
+            .address_labels
+            .insert(address_index, label.into());
+        Ok(())
+    }
+
+    /// Hand out the receive address following the last one used on the
+    /// chain, returning its index and address.
+    pub fn next_address(&mut self, index: u32, chain_id: u64) -> Result<(u32, Address)> {
+        let account = self.account(index)?;
+        let next = self
+            .metadata(index)?
+            .last_used
+            .get(&chain_id)
+            .map_or(0, |last| last + 1);
+        let address = account.address_at(next)?;
+
+        self.mark_used(index, chain_id, next)?;
+        Ok((next, address))
+    }
+
+    /// Record that the receive address at `address_index` was used on the
+    /// chain, e.g. when it is found holding funds, so it is not handed out
+    /// again.
+    pub fn mark_used(&mut self, index: u32, chain_id: u64, address_index: u32) -> Result<()> {
+        let last = self
+            .metadata_mut(index)?
+            .last_used
+            .entry(chain_id)
+            .or_insert(address_index);
+        *last = (*last).max(address_index);
+        Ok(())
+    }
+
+    fn metadata_mut(&mut self, index: u32) -> Result<&mut AccountMetadata> {
+        Ok(self
+            .accounts
+            .get_mut(&index)
+            .ok_or(AccountsError::UnknownAccount(index))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn test_accounts() {
+        let mut manager = AccountManager::new(Wallet::from_phrase(PHRASE, "").unwrap());
+        let savings = manager.create_account("Savings").unwrap();
+        let trading = manager.create_account("Trading").unwrap();
+        assert_eq!((savings.index(), trading.index()), (0, 1));
+        assert!(manager.metadata(0).unwrap().created_at > 0);
+        assert!(manager.account(2).is_err());
+
+        manager.set_label(1, "Spending").unwrap();
+        manager.set_address_label(0, 3, "Donations").unwrap();
+        assert_eq!(manager.metadata(1).unwrap().label, "Spending");
+
+        assert_eq!(
+            manager.next_address(0, 1).unwrap(),
+            (0, savings.address_at(0).unwrap())
+        );
+        assert_eq!(manager.next_address(0, 1).unwrap().0, 1);
+        // chains are counted separately
+        assert_eq!(manager.next_address(0, 10).unwrap().0, 0);
+        manager.mark_used(0, 1, 5).unwrap();
+        manager.mark_used(0, 1, 2).unwrap();
+        assert_eq!(
+            manager.next_address(0, 1).unwrap(),
+            (6, savings.address_at(6).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_state() {
+        let mut manager = AccountManager::new(Wallet::from_phrase(PHRASE, "").unwrap());
+        manager.create_account("Savings").unwrap();
+        manager.set_address_label(0, 3, "Donations").unwrap();
+        manager.next_address(0, 1).unwrap();
+
+        let json = manager.to_json();
+        let loaded =
+            AccountManager::from_json(Wallet::from_phrase(PHRASE, "").unwrap(), &json).unwrap();
+        assert_eq!(
+            loaded.accounts().collect::<Vec<_>>(),
+            manager.accounts().collect::<Vec<_>>()
+        );
+
+        let path = std::env::temp_dir().join(format!("accounts-{}.json", std::process::id()));
+        manager.save(&path).unwrap();
+        let loaded = AccountManager::load(Wallet::from_phrase(PHRASE, "").unwrap(), &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.metadata(0).unwrap(), manager.metadata(0).unwrap());
+
+        // another passphrase is another wallet
+        let other = Wallet::from_phrase(PHRASE, "other").unwrap();
+        let error = AccountManager::from_json(other, &json).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Account 0 does not belong to this wallet"
+        );
+    }
+}
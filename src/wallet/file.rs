@@ -0,0 +1,424 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::path::Path;
+
+use chacha20poly1305::{aead::Aead, aead::Payload, KeyInit, XChaCha20Poly1305, XNonce};
+use laron_crypto::PrivateKey;
+use rand::Rng;
+use serde_json::{json, Value};
+use zeroize::{Zeroize, Zeroizing};
+
+use super::{AccountManager, Wallet};
+use crate::{
+    argon2::{self, Params},
+    bips::{bip32::ExtendedKey, bip39::Mnemonic, wordlists::LANGUAGES, ChildNumber},
+    error::{Result, WalletError},
+    types::B256,
+};
+
+/// Version of the format written by [`WalletFile::to_json`].
+const VERSION: u64 = 1;
+const KDF: &str = "argon2id";
+const CIPHER: &str = "xchacha20-poly1305";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// Error returned when reading or decrypting a [`WalletFile`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WalletFileError {
+    /// The JSON is not a wallet file, the malformed field is given.
+    InvalidFormat(&'static str),
+    /// The version of the file is not supported.
+    UnsupportedVersion(u64),
+    /// The key derivation function is not supported.
+    UnsupportedKdf(String),
+    /// The cipher is not supported.
+    UnsupportedCipher(String),
+    /// The password is wrong or the file was tampered with.
+    DecryptionFailed,
+}
+
+impl std::fmt::Display for WalletFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WalletFileError::InvalidFormat(field) => write!(f, "Invalid wallet file: {}", field),
+            WalletFileError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported wallet file version: {}", version)
+            }
+            WalletFileError::UnsupportedKdf(kdf) => {
+                write!(f, "Unsupported wallet file KDF: {}", kdf)
+            }
+            WalletFileError::UnsupportedCipher(cipher) => {
+                write!(f, "Unsupported wallet file cipher: {}", cipher)
+            }
+            WalletFileError::DecryptionFailed => {
+                write!(f, "Wrong password or corrupted wallet file")
+            }
+        }
+    }
+}
+
+impl std::error::Error for WalletFileError {}
+
+/// The whole state of a wallet, sealed with a password: its mnemonic, or
+/// master key when created from a seed, and the accounts kept by its
+/// [`AccountManager`].
+///
+/// The key is stretched with Argon2id and the state sealed with
+/// XChaCha20-Poly1305. The Argon2id parameters are stored in the file and
+/// authenticated with it, so they can be raised for new files while old
+/// ones still open.
+///
+/// ```json
+/// {
+///   "version": 1,
+///   "kdf": { "name": "argon2id", "memory": 65536, "iterations": 3, "lanes": 4, "salt": "..." },
+///   "cipher": "xchacha20-poly1305",
+///   "nonce": "...",
+///   "ciphertext": "..."
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalletFile {
+    params: Params,
+    salt: [u8; SALT_LEN],
+    nonce: [u8; NONCE_LEN],
+    ciphertext: Vec<u8>,
+}
+
+impl WalletFile {
+    /// Argon2id parameters of new files, the second recommendation of
+    /// RFC 9106: 64 MiB of memory, 3 passes and 4 lanes.
+    pub const DEFAULT_PARAMS: Params = Params {
+        memory: 1 << 16,
+        iterations: 3,
+        lanes: 4,
+    };
+
+    /// Seal the state of `manager` with `password`.
+    pub fn encrypt(manager: &AccountManager, password: &str, params: Params) -> Result<Self> {
+        if !params.is_valid() {
+            return Err(WalletFileError::InvalidFormat("kdf").into());
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut file = Self {
+            params,
+            salt: rng.gen(),
+            nonce: rng.gen(),
+            ciphertext: Vec::new(),
+        };
+
+        let plaintext = Zeroizing::new(wipe_after(state(manager), |state| state.to_string()));
+        file.ciphertext = file
+            .cipher(password)?
+            .encrypt(
+                XNonce::from_slice(&file.nonce),
+                Payload {
+                    msg: plaintext.as_bytes(),
+                    aad: file.header().as_bytes(),
+                },
+            )
+            .expect("the state is far below the XChaCha20-Poly1305 limit");
+        Ok(file)
+    }
+
+    /// Open the file with `password`, restoring the wallet and its accounts.
+    pub fn decrypt(&self, password: &str) -> Result<AccountManager> {
+        let plaintext = Zeroizing::new(
+            self.cipher(password)?
+                .decrypt(
+                    XNonce::from_slice(&self.nonce),
+                    Payload {
+                        msg: &self.ciphertext,
+                        aad: self.header().as_bytes(),
+                    },
+                )
+                .map_err(|_| WalletFileError::DecryptionFailed)?,
+        );
+
+        let state = serde_json::from_slice(&plaintext)?;
+        wipe_after(state, restore)
+    }
+
+    /// Serialize the file.
+    pub fn to_json(&self) -> String {
+        json!({
+            "version": VERSION,
+            "kdf": self.kdf(),
+            "cipher": CIPHER,
+            "nonce": hex::encode(self.nonce),
+            "ciphertext": hex::encode(&self.ciphertext),
+        })
+        .to_string()
+    }
+
+    /// Parse a file written by [`WalletFile::to_json`]. Argon2 costs over
+    /// [`argon2::MAX_MEMORY`] and [`argon2::MAX_ITERATIONS`] are refused.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let root: Value = serde_json::from_str(json)?;
+        let version = root["version"]
+            .as_u64()
+            .ok_or(WalletFileError::InvalidFormat("version"))?;
+        if version != VERSION {
+            return Err(WalletFileError::UnsupportedVersion(version).into());
+        }
+
+        let kdf = &root["kdf"];
+        let name = kdf["name"]
+            .as_str()
+            .ok_or(WalletFileError::InvalidFormat("kdf"))?;
+        if name != KDF {
+            return Err(WalletFileError::UnsupportedKdf(name.to_string()).into());
+        }
+        let cipher = root["cipher"]
+            .as_str()
+            .ok_or(WalletFileError::InvalidFormat("cipher"))?;
+        if cipher != CIPHER {
+            return Err(WalletFileError::UnsupportedCipher(cipher.to_string()).into());
+        }
+
+        let param = |field: &'static str| {
+            kdf[field]
+                .as_u64()
+                .and_then(|value| u32::try_from(value).ok())
+                .ok_or(WalletFileError::InvalidFormat(field))
+        };
+        let params = Params {
+            memory: param("memory")?,
+            iterations: param("iterations")?,
+            lanes: param("lanes")?,
+        };
+        if !params.is_valid() {
+            return Err(WalletFileError::InvalidFormat("kdf").into());
+        }
+
+        Ok(Self {
+            params,
+            salt: decode(&kdf["salt"], "salt")?,
+            nonce: decode(&root["nonce"], "nonce")?,
+            ciphertext: root["ciphertext"]
+                .as_str()
+                .and_then(|ciphertext| hex::decode(ciphertext).ok())
+                .ok_or(WalletFileError::InvalidFormat("ciphertext"))?,
+        })
+    }
+
+    /// Read a file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Write the file. It is written to a temporary file first, so a crash
+    /// cannot leave a truncated wallet behind.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        std::fs::write(&temporary, self.to_json())?;
+        std::fs::rename(&temporary, path)?;
+        Ok(())
+    }
+
+    /// Return the Argon2id parameters the file was sealed with.
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    fn kdf(&self) -> Value {
+        json!({
+            "name": KDF,
+            "memory": self.params.memory,
+            "iterations": self.params.iterations,
+            "lanes": self.params.lanes,
+            "salt": hex::encode(self.salt),
+        })
+    }
+
+    /// The plaintext fields, authenticated along with the state.
+    fn header(&self) -> String {
+        json!({ "version": VERSION, "kdf": self.kdf(), "cipher": CIPHER }).to_string()
+    }
+
+    fn cipher(&self, password: &str) -> Result<XChaCha20Poly1305> {
+        let mut key = Zeroizing::new([0u8; 32]);
+        argon2::argon2id(password.as_bytes(), &self.salt, &self.params, &mut *key)?;
+        Ok(XChaCha20Poly1305::new(&(*key).into()))
+    }
+}
+
+/// Decode a hex string of `N` bytes.
+fn decode<const N: usize>(value: &Value, field: &'static str) -> Result<[u8; N]> {
+    Ok(value
+        .as_str()
+        .and_then(|value| hex::decode(value).ok())
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(WalletFileError::InvalidFormat(field))?)
+}
+
+/// The secret state of the wallet, with its accounts.
+fn state(manager: &AccountManager) -> Value {
+    let wallet = manager.wallet();
+    let mnemonic = wallet.mnemonic().map(|mnemonic| {
+        json!({
+            "phrase": mnemonic.phrase(),
+            "language": format!("{:?}", mnemonic.language()),
+        })
+    });
+    let master = wallet.master();
+
+    json!({
+        "mnemonic": mnemonic,
+        "master": {
+            "key": hex::encode(Zeroizing::new(master.private_key().to_bytes())),
+            "chain_code": master.chain_code().to_string(),
+        },
+        "accounts": manager.to_value(),
+    })
+}
+
+fn restore(state: &Value) -> Result<AccountManager> {
+    let mnemonic = match &state["mnemonic"] {
+        Value::Null => None,
+        mnemonic => {
+            let phrase = mnemonic["phrase"]
+                .as_str()
+                .ok_or(WalletFileError::InvalidFormat("phrase"))?;
+            let language = mnemonic["language"]
+                .as_str()
+                .and_then(|name| {
+                    LANGUAGES
+                        .iter()
+                        .find(|language| format!("{:?}", language) == name)
+                })
+                .ok_or(WalletFileError::InvalidFormat("language"))?;
+            Some(Mnemonic::from_phrase(phrase, *language)?)
+        }
+    };
+
+    let key = Zeroizing::new(
+        state["master"]["key"]
+            .as_str()
+            .and_then(|key| hex::decode(key).ok())
+            .ok_or(WalletFileError::InvalidFormat("key"))?,
+    );
+    let key = PrivateKey::from_bytes(&key).map_err(WalletError::crypto)?;
+    let chain_code: B256 = state["master"]["chain_code"]
+        .as_str()
+        .and_then(|chain_code| chain_code.parse().ok())
+        .ok_or(WalletFileError::InvalidFormat("chain_code"))?;
+    let public_key = key.public_key();
+    let master = ExtendedKey::new(key, public_key, [0; 4], ChildNumber::from(0), 0, chain_code);
+
+    AccountManager::from_value(Wallet::with_master(mnemonic, master), &state["accounts"])
+}
+
+/// Call `f` on `value`, then zeroize the strings it holds.
+fn wipe_after<T>(value: Value, f: impl FnOnce(&Value) -> T) -> T {
+    let result = f(&value);
+    wipe(value);
+    result
+}
+
+fn wipe(value: Value) {
+    match value {
+        Value::String(mut string) => string.zeroize(),
+        Value::Array(values) => values.into_iter().for_each(wipe),
+        Value::Object(values) => values.into_iter().for_each(|(_, value)| wipe(value)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bips::bip39::Seed;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    const PARAMS: Params = Params {
+        memory: 64,
+        iterations: 1,
+        lanes: 1,
+    };
+
+    #[test]
+    fn test_wallet_file() {
+        let mut manager = AccountManager::new(Wallet::from_phrase(PHRASE, "TREZOR").unwrap());
+        manager.create_account("Savings").unwrap();
+        manager.next_address(0, 1).unwrap();
+
+        let file = WalletFile::encrypt(&manager, "password", PARAMS).unwrap();
+        let json = file.to_json();
+        assert!(!json.contains("abandon"));
+
+        let file = WalletFile::from_json(&json).unwrap();
+        assert_eq!(file.params(), &PARAMS);
+        let restored = file.decrypt("password").unwrap();
+        assert_eq!(restored.wallet().mnemonic().unwrap().phrase(), PHRASE);
+        assert_eq!(restored.metadata(0).unwrap(), manager.metadata(0).unwrap());
+        // the BIP39 passphrase is not needed to restore the accounts
+        assert_eq!(
+            restored.account(0).unwrap().address(),
+            manager.account(0).unwrap().address()
+        );
+
+        let error = file.decrypt("wrong").unwrap_err();
+        assert_eq!(error.to_string(), "Wrong password or corrupted wallet file");
+
+        let path = std::env::temp_dir().join(format!("wallet-{}.json", std::process::id()));
+        file.save(&path).unwrap();
+        let loaded = WalletFile::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, file);
+    }
+
+    #[test]
+    fn test_wallet_file_seed() {
+        let seed = Seed::from(vec![7; 64]);
+        let manager = AccountManager::new(Wallet::from_seed(&seed).unwrap());
+        let file = WalletFile::encrypt(&manager, "password", PARAMS).unwrap();
+
+        let restored = file.decrypt("password").unwrap();
+        assert!(restored.wallet().mnemonic().is_none());
+        assert_eq!(
+            restored.wallet().account(3).unwrap().address(),
+            manager.wallet().account(3).unwrap().address()
+        );
+    }
+
+    #[test]
+    fn test_wallet_file_tampered() {
+        let manager = AccountManager::new(Wallet::from_phrase(PHRASE, "").unwrap());
+        let json = WalletFile::encrypt(&manager, "password", PARAMS)
+            .unwrap()
+            .to_json();
+
+        // the parameters are authenticated
+        let weaker = json.replace("\"iterations\":1", "\"iterations\":2");
+        let file = WalletFile::from_json(&weaker).unwrap();
+        assert!(file.decrypt("password").is_err());
+
+        let error =
+            WalletFile::from_json(&json.replace("\"version\":1", "\"version\":2")).unwrap_err();
+        assert_eq!(error.to_string(), "Unsupported wallet file version: 2");
+        let error = WalletFile::from_json(&json.replace("argon2id", "scrypt")).unwrap_err();
+        assert_eq!(error.to_string(), "Unsupported wallet file KDF: scrypt");
+
+        // costs over the limits are refused before deriving the key
+        let costly = json.replace("\"memory\":64", "\"memory\":4294967295");
+        assert!(WalletFile::from_json(&costly).is_err());
+        let costly = json.replace("\"iterations\":1", "\"iterations\":4294967295");
+        assert!(WalletFile::from_json(&costly).is_err());
+    }
+}
@@ -0,0 +1,260 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Wallet
+//!
+//! A [`Wallet`] ties together a mnemonic, the master key of its seed and
+//! the BIP44 Ethereum accounts `m/44'/60'/account'`, derived the first time
+//! they are used. It signs with the first address of account 0, so the
+//! common case needs neither paths nor keys.
+//!
+//! An [`AccountManager`] keeps what a wallet application records about
+//! the accounts: their labels, creation times and the receive addresses
+//! handed out on each chain. A [`WalletFile`] seals both with a password.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::{signer::Signer, wallet::Wallet};
+//!
+//! let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+//! let wallet = Wallet::from_phrase(phrase, "").unwrap();
+//! let address = wallet.account(0).unwrap().address();
+//! let signature = wallet.sign_message("hello").unwrap();
+//!
+//! assert_eq!(address.to_string(), "0x9858EfFD232B4033E47d90003D41EC34EcaEda94");
+//! assert_eq!(signature.recover_personal("hello").unwrap(), address);
+//! ```
+
+mod accounts;
+mod file;
+
+pub use accounts::*;
+pub use file::*;
+
+use std::{collections::BTreeMap, sync::Mutex};
+
+use crate::{
+    address::Address,
+    bips::{
+        bip32::{xpub::ExtendedPublicKey, ExtendedKey},
+        bip39::{Mnemonic, Seed},
+        bip44::{self, Bip44Path},
+        ChildNumber, DerivationPath,
+    },
+    error::Result,
+    signature::Signature,
+    signer::Signer,
+};
+
+/// A BIP44 Ethereum account of a [`Wallet`].
+///
+/// It signs with its first receive address, `m/44'/60'/account'/0/0`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    index: u32,
+    key: ExtendedKey,
+    receive: ExtendedKey,
+    first: ExtendedKey,
+    address: Address,
+}
+
+impl Account {
+    fn derive(master: &ExtendedKey, index: u32) -> Result<Self> {
+        let path = DerivationPath::from_iter([
+            ChildNumber::hardened(bip44::PURPOSE),
+            ChildNumber::hardened(bip44::ETHEREUM),
+            ChildNumber::try_hardened(index)?,
+        ]);
+        let key = master.derive_path(&path)?;
+        let receive = key.derive_child(ChildNumber::normal(0))?;
+        let first = receive.derive_child(ChildNumber::normal(0))?;
+        let address = Address::from_public_key(first.public_key())?;
+
+        Ok(Self {
+            index,
+            key,
+            receive,
+            first,
+            address,
+        })
+    }
+
+    /// Return the index of the account.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Return the first receive address of the account.
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Return the path of the receive address at `index`.
+    pub fn path(&self, index: u32) -> Bip44Path {
+        Bip44Path::ethereum(self.index, index)
+    }
+
+    /// Return the key of the receive address at `index`.
+    pub fn key(&self, index: u32) -> Result<ExtendedKey> {
+        self.receive.derive_child(ChildNumber::try_normal(index)?)
+    }
+
+    /// Return the receive address at `index`.
+    pub fn address_at(&self, index: u32) -> Result<Address> {
+        Address::from_public_key(self.key(index)?.public_key())
+    }
+
+    /// Return the extended public key of the account, to generate its
+    /// addresses without the wallet, see [`ExtendedPublicKey::addresses`].
+    pub fn extended_public_key(&self) -> Result<ExtendedPublicKey> {
+        self.key.extended_public_key()
+    }
+}
+
+impl Signer for Account {
+    fn address(&self) -> Result<Address> {
+        Ok(self.address)
+    }
+
+    fn sign_hash(&self, hash: &[u8; 32]) -> Result<Signature> {
+        self.first.sign_hash(hash)
+    }
+}
+
+/// A hierarchical deterministic wallet, as [`Wallet`] is exported by the
+/// prelude.
+pub type HdWallet = Wallet;
+
+/// A single Ethereum account, signing with one address, as [`Account`] is
+/// exported by the prelude.
+pub type EthereumWallet = Account;
+
+/// A hierarchical deterministic wallet with its accounts.
+pub struct Wallet {
+    mnemonic: Option<Mnemonic>,
+    master: ExtendedKey,
+    accounts: Mutex<BTreeMap<u32, Account>>,
+}
+
+impl Wallet {
+    /// Create a wallet from a mnemonic and its passphrase.
+    pub fn new(mnemonic: Mnemonic, passphrase: &str) -> Result<Self> {
+        let master = ExtendedKey::new_master(&mnemonic.to_seed(passphrase))?;
+        Ok(Self::with_master(Some(mnemonic), master))
+    }
+
+    /// Create a wallet from an English phrase and its passphrase.
+    pub fn from_phrase(phrase: &str, passphrase: &str) -> Result<Self> {
+        Self::new(phrase.parse()?, passphrase)
+    }
+
+    /// Create a wallet from a seed, without its mnemonic.
+    pub fn from_seed(seed: &Seed) -> Result<Self> {
+        Ok(Self::with_master(None, ExtendedKey::new_master(seed)?))
+    }
+
+    fn with_master(mnemonic: Option<Mnemonic>, master: ExtendedKey) -> Self {
+        Self {
+            mnemonic,
+            master,
+            accounts: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Return the mnemonic, unless the wallet was created from a seed.
+    pub fn mnemonic(&self) -> Option<&Mnemonic> {
+        self.mnemonic.as_ref()
+    }
+
+    /// Return the master key.
+    pub fn master(&self) -> &ExtendedKey {
+        &self.master
+    }
+
+    /// Return the account at `index`, derived on first use.
+    pub fn account(&self, index: u32) -> Result<Account> {
+        let mut accounts = self.accounts.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(account) = accounts.get(&index) {
+            return Ok(account.clone());
+        }
+
+        let account = Account::derive(&self.master, index)?;
+        accounts.insert(index, account.clone());
+        Ok(account)
+    }
+}
+
+impl std::fmt::Debug for Wallet {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let accounts = self.accounts.lock().unwrap_or_else(|e| e.into_inner());
+        f.debug_struct("Wallet")
+            .field("mnemonic", &self.mnemonic)
+            .field("accounts", &accounts.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Signer for Wallet {
+    fn address(&self) -> Result<Address> {
+        Ok(self.account(0)?.address())
+    }
+
+    fn sign_hash(&self, hash: &[u8; 32]) -> Result<Signature> {
+        self.account(0)?.sign_hash(hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn test_accounts() {
+        let wallet = Wallet::from_phrase(PHRASE, "").unwrap();
+        let master = wallet.master().clone();
+
+        for index in [0, 1, 7] {
+            let account = wallet.account(index).unwrap();
+            assert_eq!(account.index(), index);
+            for i in [0, 3] {
+                let path = DerivationPath::from(account.path(i));
+                let key = master.derive_path(&path).unwrap();
+                assert_eq!(account.key(i).unwrap(), key);
+                assert_eq!(account.address_at(i).unwrap(), key.address().unwrap());
+            }
+            assert_eq!(account.address(), account.address_at(0).unwrap());
+        }
+        assert_eq!(wallet.account(1).unwrap(), wallet.account(1).unwrap());
+        assert!(wallet.account(1 << 31).is_err());
+        assert!(format!("{:?}", wallet).contains("accounts: [0, 1, 7]"));
+    }
+
+    #[test]
+    fn test_signer() {
+        let mnemonic: Mnemonic = PHRASE.parse().unwrap();
+        let seed = mnemonic.to_seed("");
+        let wallet = Wallet::new(mnemonic.clone(), "").unwrap();
+        assert_eq!(wallet.mnemonic(), Some(&mnemonic));
+
+        let signature = wallet.sign_message("hello").unwrap();
+        let address = Signer::address(&wallet).unwrap();
+        assert_eq!(address, wallet.account(0).unwrap().address());
+        assert_eq!(signature.recover_personal("hello").unwrap(), address);
+
+        let wallet = Wallet::from_seed(&seed).unwrap();
+        assert!(wallet.mnemonic().is_none());
+        assert_eq!(Signer::address(&wallet).unwrap(), address);
+    }
+}
@@ -0,0 +1,265 @@
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Watchtower
+//!
+//! Monitoring of the wallet's addresses for outgoing transactions it did not
+//! produce, a strong sign that a key has leaked.
+//!
+//! Every transaction the application sends is registered with
+//! [`Watchtower::expect`]. Transactions seen on chain are then either fed
+//! from a subscription with [`Watchtower::observe`], or detected by polling
+//! the nonces with [`Watchtower::poll`]. Anything unexpected raises an
+//! [`Alert`] to the registered listeners, which can forward it to a webhook,
+//! a pager or freeze the wallet.
+//!
+//! ## Example
+//! ```rust
+//! use wallet_rust::{address::Address, types::B256};
+//! use wallet_rust::watchtower::{Alert, Transfer, Watchtower};
+//!
+//! let address = Address::new([0x11; 20]);
+//! let mut watchtower = Watchtower::new();
+//! watchtower.watch(address, 0);
+//! watchtower.on_alert(|alert| eprintln!("{}", alert));
+//!
+//! watchtower.expect(address, B256::new([0xaa; 32]));
+//! let sent = Transfer { hash: B256::new([0xaa; 32]), from: address, to: None, value: 0, nonce: 0 };
+//! assert!(watchtower.observe(&sent).is_none());
+//!
+//! let stolen = Transfer { hash: B256::new([0xbb; 32]), from: address, to: None, value: 10, nonce: 1 };
+//! assert!(matches!(watchtower.observe(&stolen), Some(Alert::UnexpectedTransaction(_))));
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{address::Address, error::Result, provider::Provider, types::B256};
+
+/// An outgoing transaction seen on chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transfer {
+    /// Hash of the transaction.
+    pub hash: B256,
+    /// Sender of the transaction.
+    pub from: Address,
+    /// Recipient, `None` for a contract creation.
+    pub to: Option<Address>,
+    /// Value in wei.
+    pub value: u128,
+    /// Nonce of the transaction.
+    pub nonce: u64,
+}
+
+/// An alert raised by the [`Watchtower`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Alert {
+    /// A transaction from a watched address was not produced by the wallet.
+    UnexpectedTransaction(Transfer),
+    /// The nonce of a watched address moved past the transactions the
+    /// wallet produced.
+    NonceAdvanced {
+        /// The watched address.
+        address: Address,
+        /// The nonce the wallet expected.
+        expected: u64,
+        /// The nonce found on chain.
+        actual: u64,
+    },
+}
+
+impl std::fmt::Display for Alert {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Alert::UnexpectedTransaction(transfer) => write!(
+                f,
+                "Unexpected transaction {} from {}",
+                transfer.hash, transfer.from
+            ),
+            Alert::NonceAdvanced {
+                address,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Unexpected nonce for {}: expected {}, found {}",
+                address, expected, actual
+            ),
+        }
+    }
+}
+
+type Listener = Box<dyn Fn(&Alert) + Send + Sync>;
+
+/// Watches addresses for transactions the wallet did not produce.
+#[derive(Default)]
+pub struct Watchtower {
+    nonces: HashMap<Address, u64>,
+    expected: HashSet<B256>,
+    listeners: Vec<Listener>,
+}
+
+impl Watchtower {
+    /// Create a watchtower watching no address.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Watch an address whose next nonce is `nonce`.
+    pub fn watch(&mut self, address: Address, nonce: u64) {
+        self.nonces.insert(address, nonce);
+    }
+
+    /// Stop watching an address.
+    pub fn unwatch(&mut self, address: &Address) {
+        self.nonces.remove(address);
+    }
+
+    /// Return the watched addresses.
+    pub fn addresses(&self) -> impl Iterator<Item = &Address> {
+        self.nonces.keys()
+    }
+
+    /// Call `listener` on every alert.
+    pub fn on_alert(&mut self, listener: impl Fn(&Alert) + Send + Sync + 'static) {
+        self.listeners.push(Box::new(listener));
+    }
+
+    /// Register a transaction sent by the wallet from `from`.
+    pub fn expect(&mut self, from: Address, hash: B256) {
+        self.expected.insert(hash);
+        if let Some(nonce) = self.nonces.get_mut(&from) {
+            *nonce += 1;
+        }
+    }
+
+    /// Check a transaction seen on chain, returning the alert raised if it
+    /// was sent from a watched address without being expected.
+    pub fn observe(&mut self, transfer: &Transfer) -> Option<Alert> {
+        let nonce = self.nonces.get_mut(&transfer.from)?;
+        if self.expected.remove(&transfer.hash) {
+            return None;
+        }
+
+        // the nonce is consumed, polling must not raise the same alert
+        *nonce = (*nonce).max(transfer.nonce + 1);
+
+        let alert = Alert::UnexpectedTransaction(transfer.clone());
+        self.emit(&alert);
+        Some(alert)
+    }
+
+    /// Compare the on-chain nonce of every watched address with the
+    /// transactions the wallet produced, returning the alerts raised.
+    pub fn poll<P: Provider>(&mut self, provider: &P) -> Result<Vec<Alert>> {
+        let mut alerts = Vec::new();
+        for (address, nonce) in self.nonces.iter_mut() {
+            let actual = provider.transaction_count(address)?;
+            if actual > *nonce {
+                alerts.push(Alert::NonceAdvanced {
+                    address: *address,
+                    expected: *nonce,
+                    actual,
+                });
+                *nonce = actual;
+            }
+        }
+
+        for alert in &alerts {
+            self.emit(alert);
+        }
+        Ok(alerts)
+    }
+
+    fn emit(&self, alert: &Alert) {
+        for listener in &self.listeners {
+            listener(alert);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::mock::MockProvider;
+    use std::sync::{Arc, Mutex};
+
+    fn transfer(hash: u8, from: Address, nonce: u64) -> Transfer {
+        Transfer {
+            hash: B256::new([hash; 32]),
+            from,
+            to: Some(Address::new([0xff; 20])),
+            value: 1,
+            nonce,
+        }
+    }
+
+    #[test]
+    fn test_observe() {
+        let address = Address::new([1; 20]);
+        let mut watchtower = Watchtower::new();
+        watchtower.watch(address, 5);
+
+        let alerts = Arc::new(Mutex::new(Vec::new()));
+        let recorded = alerts.clone();
+        watchtower.on_alert(move |alert| recorded.lock().unwrap().push(alert.clone()));
+
+        watchtower.expect(address, B256::new([1; 32]));
+        assert_eq!(watchtower.observe(&transfer(1, address, 5)), None);
+        // unwatched senders are ignored
+        assert_eq!(
+            watchtower.observe(&transfer(2, Address::new([2; 20]), 0)),
+            None
+        );
+
+        let stolen = transfer(3, address, 6);
+        assert_eq!(
+            watchtower.observe(&stolen),
+            Some(Alert::UnexpectedTransaction(stolen.clone()))
+        );
+        assert_eq!(
+            *alerts.lock().unwrap(),
+            vec![Alert::UnexpectedTransaction(stolen)]
+        );
+    }
+
+    #[test]
+    fn test_poll() {
+        let address = Address::new([1; 20]);
+        let mut watchtower = Watchtower::new();
+        watchtower.watch(address, 0);
+        watchtower.expect(address, B256::new([1; 32]));
+
+        let mut provider = MockProvider {
+            nonces: Some(HashMap::from([(address, 1)])),
+            ..Default::default()
+        };
+        assert!(watchtower.poll(&provider).unwrap().is_empty());
+
+        provider.nonces.as_mut().unwrap().insert(address, 3);
+        assert_eq!(
+            watchtower.poll(&provider).unwrap(),
+            vec![Alert::NonceAdvanced {
+                address,
+                expected: 1,
+                actual: 3
+            }]
+        );
+        assert!(watchtower.poll(&provider).unwrap().is_empty());
+
+        // an alert seen through a subscription is not raised again by polling
+        watchtower.observe(&transfer(4, address, 3));
+        provider.nonces.as_mut().unwrap().insert(address, 4);
+        assert!(watchtower.poll(&provider).unwrap().is_empty());
+    }
+}